@@ -0,0 +1,62 @@
+//! Compares the two ways of wiring the user API: trait objects (`Arc<dyn ...>` service and
+//! repository) and fully generic, monomorphized types.
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::Request;
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::runtime::Runtime;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::domain::user::{model::CreateUser, repository::UserRepositoryPort};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+fn new_user() -> CreateUser {
+    CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap()
+}
+
+fn dispatch(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+
+    let repository: Arc<dyn UserRepositoryPort + Send + Sync> = Arc::new(InMemoryUserRepository::new());
+    let dyn_service: Arc<dyn UserServiceTrait + Send + Sync> = Arc::new(UserService::new(repository));
+    let generic_service = Arc::new(UserService::new(InMemoryUserRepository::new()));
+
+    let dyn_id = runtime.block_on(dyn_service.create_user(new_user())).unwrap().id();
+    let generic_id = runtime.block_on(generic_service.create_user(new_user())).unwrap().id();
+
+    let mut group = c.benchmark_group("get_user_service");
+    group.bench_function("dyn", |b| {
+        b.to_async(&runtime).iter(|| async { dyn_service.get_user(dyn_id).await.unwrap() })
+    });
+    group.bench_function("generic", |b| {
+        b.to_async(&runtime).iter(|| async { generic_service.get_user(generic_id).await.unwrap() })
+    });
+    group.finish();
+
+    let dyn_app = router(AppState::new(dyn_service.clone()));
+    let generic_app = router(AppState::new(generic_service.clone()));
+    let dyn_uri = format!("/api/v1/users/{}", dyn_id);
+    let generic_uri = format!("/api/v1/users/{}", generic_id);
+
+    let mut group = c.benchmark_group("get_user_router");
+    group.bench_function("dyn", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let request = Request::get(&dyn_uri).body(Body::empty()).unwrap();
+            dyn_app.clone().oneshot(request).await.unwrap()
+        })
+    });
+    group.bench_function("generic", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let request = Request::get(&generic_uri).body(Body::empty()).unwrap();
+            generic_app.clone().oneshot(request).await.unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, dispatch);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+//! Measures the overhead of the HTTP layer (routing, extractors, middleware) by comparing a
+//! request through the full router with calling the service directly.
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::Request;
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::runtime::Runtime;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::domain::user::model::CreateUser;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+fn http_stack(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let service = Arc::new(UserService::new(Arc::new(InMemoryUserRepository::new())));
+    let app = router(AppState::new(service.clone()));
+
+    let id = runtime
+        .block_on(service.create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap()))
+        .unwrap()
+        .id();
+    let uri = format!("/api/v1/users/{}", id);
+
+    let mut group = c.benchmark_group("get_user");
+    group.bench_function("service", |b| {
+        b.to_async(&runtime).iter(|| async { service.get_user(id).await.unwrap() })
+    });
+    group.bench_function("router", |b| {
+        b.to_async(&runtime).iter(|| async {
+            let request = Request::get(&uri).body(Body::empty()).unwrap();
+            app.clone().oneshot(request).await.unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, http_stack);
+criterion_main!(benches);
@@ -0,0 +1,61 @@
+//! Compares the repository adapters on the same operations.
+//!
+//! The PostgreSQL adapter is only benchmarked when `BENCH_DATABASE_URL` points at a
+//! database the migrations can be applied to.
+
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sqlx::postgres::PgPoolOptions;
+use tokio::runtime::Runtime;
+use uuid::Uuid;
+
+use rust_web_server_lib::domain::user::{model::{CreateUser, Email}, repository::UserRepositoryPort};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::infra::storage::adapter::postgres::{user_repository::UserRepository, MIGRATOR};
+
+const BENCH_DATABASE_URL_KEY: &str = "BENCH_DATABASE_URL";
+
+fn new_user(email: String) -> CreateUser {
+    CreateUser::new("Jane".to_string(), email, 30).unwrap()
+}
+
+fn bench_repository<R>(c: &mut Criterion, runtime: &Runtime, name: &str, repository: R)
+where
+    R: UserRepositoryPort + Send + Sync + 'static,
+{
+    let email = format!("{}@example.com", Uuid::new_v4());
+    let lookup = Email::parse(email.to_uppercase()).unwrap();
+    let id = runtime.block_on(repository.create_user(new_user(email.clone()), None)).unwrap().id();
+
+    // Reads run first so the lookups are not skewed by rows inserted by the create benchmark.
+    let mut group = c.benchmark_group(format!("repository/{}", name));
+    group.bench_function("get_user", |b| {
+        b.to_async(runtime).iter(|| async { repository.get_user(id).await.unwrap() })
+    });
+    group.bench_function("get_user_by_email", |b| {
+        b.to_async(runtime).iter(|| async { repository.get_user_by_email(lookup.clone()).await.unwrap() })
+    });
+    group.bench_function("create_user", |b| {
+        b.to_async(runtime).iter(|| async { repository.create_user(new_user(format!("{}@example.com", Uuid::new_v4())), None).await.unwrap() })
+    });
+    group.finish();
+}
+
+fn repositories(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+
+    bench_repository(c, &runtime, "in_memory", InMemoryUserRepository::new());
+
+    if let Ok(url) = std::env::var(BENCH_DATABASE_URL_KEY) {
+        let pool = runtime.block_on(async {
+            let pool = PgPoolOptions::new().max_connections(5).connect(&url).await.unwrap();
+            MIGRATOR.run(&pool).await.unwrap();
+            pool
+        });
+        bench_repository(c, &runtime, "postgres", UserRepository::new(Arc::new(pool)));
+    }
+}
+
+criterion_group!(benches, repositories);
+criterion_main!(benches);
@@ -0,0 +1,24 @@
+//! Measures the JSON (de)serialization paths of the HTTP DTOs.
+
+use axum::http::StatusCode;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use rust_web_server_lib::domain::user::model::{Email, User, UserId};
+use rust_web_server_lib::presentation::handlers::user_handlers::{ApiResponseBody, CreateUserRequestBody, UserResponseData};
+
+fn serialization(c: &mut Criterion) {
+    let user = User::new(UserId::parse("6f1c2b4e-8f7a-4c1d-9a3e-2b5d7c9e1f0a").unwrap(), "Jane".to_string(), Email::parse("jane@example.com").unwrap(), 30);
+    let request = br#"{"name":"Jane","email":"jane@example.com","age":30}"#;
+
+    let mut group = c.benchmark_group("serialization");
+    group.bench_function("deserialize_create_user_request", |b| {
+        b.iter(|| serde_json::from_slice::<CreateUserRequestBody>(black_box(request)).unwrap())
+    });
+    group.bench_function("serialize_user_response", |b| {
+        b.iter(|| serde_json::to_vec(&ApiResponseBody::new(StatusCode::OK, UserResponseData::from(black_box(&user)))).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, serialization);
+criterion_main!(benches);
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::ports::alert::{Alert, AlertPort};
+use crate::ports::throttle::ThrottlePort;
+
+/// Name of the alerts raised by [`MutationAnomalyDetector`].
+pub const USER_MUTATION_RATE_ALERT: &str = "user_mutation_rate";
+
+/// Past windows needed before the rate of a window is judged, as the mean and deviation of
+/// fewer are meaningless.
+pub const MIN_HISTORY_WINDOWS: usize = 5;
+
+/// Kind of user mutation whose rate is watched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserMutation {
+    Creation,
+    Deletion,
+}
+
+impl UserMutation {
+    fn index(self) -> usize {
+        match self {
+            UserMutation::Creation => 0,
+            UserMutation::Deletion => 1,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            UserMutation::Creation => "creations",
+            UserMutation::Deletion => "deletions",
+        }
+    }
+}
+
+/// Settings of the detection of anomalous user mutation rates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyDetectionPolicy {
+    /// Length of the windows mutations are counted in.
+    pub window: Duration,
+    /// Number of past windows the current one is compared to, at least [`MIN_HISTORY_WINDOWS`].
+    pub history: usize,
+    /// Number of standard deviations above the mean of the past windows from which the count of
+    /// the current window is anomalous (its z-score).
+    pub z_threshold: f64,
+    /// Mutations a window needs before it can be anomalous, so that a few mutations after a
+    /// quiet period do not raise alerts.
+    pub min_mutations: u64,
+    /// Tightening of the rate limits when an anomaly is detected, if any.
+    pub strict_rate_limit: Option<StrictRateLimit>,
+}
+
+/// Tightening of the rate limits applied while an anomaly is detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StrictRateLimit {
+    /// Divisor of every rate limit.
+    pub divisor: u32,
+    /// Time the limits stay tight after an anomaly.
+    pub duration: Duration,
+}
+
+/// Counts of the mutations of one kind, in the current window and the past ones.
+struct Rate {
+    window_start: Option<Instant>,
+    count: u64,
+    history: VecDeque<u64>,
+    /// Whether the current window already raised an alert.
+    alerted: bool,
+}
+
+impl Rate {
+    fn new() -> Self {
+        Self { window_start: None, count: 0, history: VecDeque::new(), alerted: false }
+    }
+
+    /// Closes the current window if `now` is past it, counting the windows without mutations
+    /// since as empty.
+    fn roll(&mut self, now: Instant, policy: &AnomalyDetectionPolicy) {
+        let Some(start) = self.window_start else {
+            self.window_start = Some(now);
+            return;
+        };
+        let elapsed = now.saturating_duration_since(start);
+        let windows = elapsed.as_nanos() / policy.window.as_nanos();
+        if windows == 0 {
+            return;
+        }
+
+        self.history.push_back(self.count);
+        let empty = usize::try_from(windows - 1).unwrap_or(usize::MAX).min(policy.history);
+        self.history.extend(std::iter::repeat_n(0, empty));
+        while self.history.len() > policy.history {
+            self.history.pop_front();
+        }
+        self.count = 0;
+        self.alerted = false;
+        self.window_start = Some(now - Duration::from_nanos((elapsed.as_nanos() % policy.window.as_nanos()) as u64));
+    }
+
+    /// Returns the z-score of the current window and the mean of the past ones, once enough
+    /// windows are past. The deviation is at least 1, so steady rates do not make every extra
+    /// mutation anomalous.
+    fn z_score(&self) -> Option<(f64, f64)> {
+        if self.history.len() < MIN_HISTORY_WINDOWS {
+            return None;
+        }
+        let n = self.history.len() as f64;
+        let mean = self.history.iter().sum::<u64>() as f64 / n;
+        let variance = self.history.iter().map(|&count| (count as f64 - mean).powi(2)).sum::<f64>() / n;
+        Some(((self.count as f64 - mean) / variance.sqrt().max(1.0), mean))
+    }
+}
+
+/// Detector of anomalous rates of user creations and deletions, e.g. a compromised admin
+/// token deleting users or a bot signing up in bulk.
+///
+/// Mutations are counted in fixed windows, and the count of the current window is compared to
+/// those of the past windows by its z-score. When it reaches the threshold, an alert is raised
+/// (once per window and kind of mutation) and the rate limits are tightened, if configured.
+///
+/// Counts are kept per replica, so each replica judges the mutations it handles.
+pub struct MutationAnomalyDetector {
+    policy: AnomalyDetectionPolicy,
+    alerts: Arc<dyn AlertPort + Send + Sync + 'static>,
+    /// The rate limits tightened on anomalies, when configured.
+    throttle: Option<Arc<dyn ThrottlePort + Send + Sync + 'static>>,
+    rates: Mutex<[Rate; 2]>,
+}
+
+impl MutationAnomalyDetector {
+    /// Creates a new `MutationAnomalyDetector` raising alerts to `alerts`. Fails when the window
+    /// is zero, the history is shorter than [`MIN_HISTORY_WINDOWS`], the threshold is not
+    /// positive or the rate limit divisor is zero.
+    pub fn new(policy: AnomalyDetectionPolicy, alerts: Arc<dyn AlertPort + Send + Sync + 'static>) -> eyre::Result<Self> {
+        if policy.window.is_zero() {
+            eyre::bail!("anomaly detection window must be positive");
+        }
+        if policy.history < MIN_HISTORY_WINDOWS {
+            eyre::bail!("anomaly detection history must be at least {} windows", MIN_HISTORY_WINDOWS);
+        }
+        if policy.z_threshold.is_nan() || policy.z_threshold <= 0.0 {
+            eyre::bail!("anomaly detection threshold must be positive");
+        }
+        if policy.strict_rate_limit.is_some_and(|strict| strict.divisor == 0) {
+            eyre::bail!("strict rate limit divisor must be positive");
+        }
+
+        Ok(Self { policy, alerts, throttle: None, rates: Mutex::new([Rate::new(), Rate::new()]) })
+    }
+
+    /// Tightens the rate limits of `throttle` on anomalies, as set by the strict rate limit of
+    /// the policy.
+    pub fn with_throttle(mut self, throttle: Arc<dyn ThrottlePort + Send + Sync + 'static>) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Records a mutation made now.
+    pub fn record(&self, mutation: UserMutation) {
+        self.record_at(mutation, Instant::now());
+    }
+
+    /// Records a mutation made at `now`, raising an alert if it makes the rate anomalous.
+    pub fn record_at(&self, mutation: UserMutation, now: Instant) {
+        let anomaly = {
+            let mut rates = self.rates.lock().unwrap_or_else(|e| e.into_inner());
+            let rate = &mut rates[mutation.index()];
+            rate.roll(now, &self.policy);
+            rate.count += 1;
+
+            match rate.z_score() {
+                Some((z, mean)) if !rate.alerted && rate.count >= self.policy.min_mutations && z >= self.policy.z_threshold => {
+                    rate.alerted = true;
+                    Some((rate.count, z, mean))
+                }
+                _ => None,
+            }
+        };
+
+        if let Some((count, z, mean)) = anomaly {
+            self.alerts.raise(Alert {
+                name: USER_MUTATION_RATE_ALERT,
+                message: format!(
+                    "{} user {} in the current {}s window, {:.1} standard deviations above the mean of {:.1}",
+                    count,
+                    mutation.as_str(),
+                    self.policy.window.as_secs(),
+                    z,
+                    mean
+                ),
+            });
+            if let (Some(throttle), Some(strict)) = (&self.throttle, self.policy.strict_rate_limit) {
+                throttle.tighten(strict.divisor, strict.duration);
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use domain::outcome::record_outcome;
+use domain::passkey::repository::PasskeyRepositoryPort;
+use domain::user::model::UserId;
+
+use crate::flows::group_service::DisabledGroupService;
+use crate::ports::auth::{AccessToken, AuthError, AuthenticatorPort, ExternalTokenPort, Principal, TokenPort};
+use crate::ports::group::GroupRolesPort;
+
+/// Service trait for authentication.
+#[async_trait]
+pub trait AuthServiceTrait {
+    /// Checks the credentials and issues an access token for the matching user and its roles,
+    /// limited to `scopes`.
+    async fn login(&self, email: String, password: String, scopes: Vec<String>) -> Result<AccessToken, AuthError>;
+
+    /// Validates an access token, returning the identity it authenticates.
+    async fn authenticate(&self, token: &str) -> Result<Principal, AuthError>;
+
+    /// Issues a time-boxed token letting the admin `actor_id` act on behalf of the user `subject_id`.
+    async fn impersonate(&self, actor_id: String, subject_id: String, ttl: Duration) -> Result<AccessToken, AuthError>;
+}
+
+/// Service implementation for authentication, combining a credentials check with a token issuer.
+///
+/// Both operations run in their own span carrying `outcome` and `error.class`; credentials
+/// and tokens are never recorded.
+pub struct AuthService {
+    authenticator: Arc<dyn AuthenticatorPort + Send + Sync + 'static>,
+    tokens: Arc<dyn TokenPort + Send + Sync + 'static>,
+    /// Passkeys of users, whose password logins are refused once they registered one.
+    passkeys: Option<Arc<dyn PasskeyRepositoryPort + Send + Sync + 'static>>,
+    /// Validation of the tokens of an external identity provider.
+    external_tokens: Option<Arc<dyn ExternalTokenPort + Send + Sync + 'static>>,
+    /// Resolution of the roles users get from their groups.
+    group_roles: Arc<dyn GroupRolesPort + Send + Sync + 'static>,
+}
+
+impl AuthService {
+    /// Creates a new `AuthService` instance, accepting password logins of every user.
+    pub fn new(authenticator: Arc<dyn AuthenticatorPort + Send + Sync + 'static>, tokens: Arc<dyn TokenPort + Send + Sync + 'static>) -> Self {
+        Self { authenticator, tokens, passkeys: None, external_tokens: None, group_roles: Arc::new(DisabledGroupService) }
+    }
+
+    /// Refuses password logins of users who registered a passkey in `passkeys`, so they can only
+    /// log in with it. Users without a passkey, or not stored locally, still log in with a password.
+    pub fn without_password_fallback(mut self, passkeys: Arc<dyn PasskeyRepositoryPort + Send + Sync + 'static>) -> Self {
+        self.passkeys = Some(passkeys);
+        self
+    }
+
+    /// Also accepts the tokens of the identity provider of `external_tokens`, recognized by their
+    /// issuer, and validated by it alone.
+    pub fn with_external_tokens(mut self, external_tokens: Arc<dyn ExternalTokenPort + Send + Sync + 'static>) -> Self {
+        self.external_tokens = Some(external_tokens);
+        self
+    }
+
+    /// Issues tokens carrying the roles in effect per `group_roles` rather than the roles of the
+    /// authenticator alone. Tokens of the external identity provider are left as issued.
+    pub fn with_group_roles(mut self, group_roles: Arc<dyn GroupRolesPort + Send + Sync + 'static>) -> Self {
+        self.group_roles = group_roles;
+        self
+    }
+
+    /// Fails with [`AuthError::PasskeyRequired`] if password logins are refused to the user.
+    async fn ensure_password_fallback(&self, user_id: &str) -> Result<(), AuthError> {
+        let (Some(passkeys), Ok(user_id)) = (&self.passkeys, UserId::parse(user_id)) else {
+            return Ok(());
+        };
+        match passkeys.list_passkeys(user_id).await {
+            Ok(registered) if registered.is_empty() => Ok(()),
+            Ok(_) => Err(AuthError::PasskeyRequired),
+            Err(_) => Err(AuthError::Unavailable),
+        }
+    }
+}
+
+#[async_trait]
+impl AuthServiceTrait for AuthService {
+    #[tracing::instrument(name = "auth_service.login", skip_all, fields(user.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn login(&self, email: String, password: String, scopes: Vec<String>) -> Result<AccessToken, AuthError> {
+        record_outcome(async {
+            let authentication = self.authenticator.authenticate(email, password).await?;
+            tracing::Span::current().record("user.id", &authentication.user_id);
+            self.ensure_password_fallback(&authentication.user_id).await?;
+            let roles = self
+                .group_roles
+                .effective_roles(&authentication.user_id, &authentication.roles)
+                .await
+                .map_err(|_| AuthError::Unavailable)?;
+            self.tokens.issue(&authentication.user_id, &roles, &scopes)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "auth_service.authenticate", skip_all, fields(user.id = tracing::field::Empty, actor.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn authenticate(&self, token: &str) -> Result<Principal, AuthError> {
+        let result = match &self.external_tokens {
+            Some(external_tokens) if external_tokens.issued_by_provider(token) => external_tokens.verify(token).await,
+            _ => self.tokens.verify(token),
+        };
+        record_outcome(result).inspect(|principal| {
+            let span = tracing::Span::current();
+            span.record("user.id", &principal.user_id);
+            if let Some(actor_id) = &principal.actor_id {
+                span.record("actor.id", actor_id);
+            }
+        })
+    }
+
+    /// Issued sessions are logged at `info`, so they are kept whatever the sampling of request logs.
+    #[tracing::instrument(name = "auth_service.impersonate", skip_all, fields(user.id = %subject_id, actor.id = %actor_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn impersonate(&self, actor_id: String, subject_id: String, ttl: Duration) -> Result<AccessToken, AuthError> {
+        record_outcome(self.tokens.issue_impersonation(&actor_id, &subject_id, ttl)).inspect(|_| {
+            tracing::info!(ttl_secs = ttl.as_secs(), "impersonation session issued");
+        })
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use domain::outcome::record_outcome;
+use domain::consent::{error::ConsentDomainError, model::{is_granted, Consent, ConsentType, RecordConsent}, repository::ConsentRepositoryPort};
+use domain::user::{error::UserDomainError, model::UserId, repository::UserRepositoryPort};
+
+use crate::ports::consent::ConsentPort;
+
+/// Service trait for the consent records of users.
+#[async_trait]
+pub trait ConsentServiceTrait: ConsentPort {
+    /// Records a grant or withdrawal of consent by an existing user.
+    async fn record_consent(&self, consent: RecordConsent) -> Result<Consent, ConsentDomainError>;
+
+    /// Lists the consent records of an existing user, in the order they were recorded.
+    async fn list_consents(&self, user_id: String) -> Result<Vec<Consent>, ConsentDomainError>;
+}
+
+/// Service implementation for consent records.
+///
+/// Consents can only be recorded and listed for existing users. The service is also the
+/// [`ConsentPort`] adapter through which other features check consent.
+pub struct ConsentService {
+    consent_repository: Arc<dyn ConsentRepositoryPort + Send + Sync + 'static>,
+    user_repository: Arc<dyn UserRepositoryPort + Send + Sync + 'static>,
+}
+
+impl ConsentService {
+    /// Creates a new `ConsentService` instance.
+    pub fn new(
+        consent_repository: Arc<dyn ConsentRepositoryPort + Send + Sync + 'static>,
+        user_repository: Arc<dyn UserRepositoryPort + Send + Sync + 'static>,
+    ) -> Self {
+        Self { consent_repository, user_repository }
+    }
+
+    /// Fails with [`ConsentDomainError::UserNotFound`] unless the user exists, or with `failure`
+    /// when the user cannot be looked up.
+    async fn ensure_user_exists(&self, user_id: &str, failure: ConsentDomainError) -> Result<(), ConsentDomainError> {
+        // Consents name users by the id given by the client, which cannot be a user unless it is a UUID
+        let Ok(user_id) = UserId::parse(user_id) else {
+            return Err(ConsentDomainError::UserNotFound);
+        };
+        match self.user_repository.get_user(user_id).await {
+            Ok(_) => Ok(()),
+            Err(UserDomainError::UserNotFound) => Err(ConsentDomainError::UserNotFound),
+            Err(_) => Err(failure),
+        }
+    }
+}
+
+#[async_trait]
+impl ConsentServiceTrait for ConsentService {
+    #[tracing::instrument(name = "consent_service.record_consent", skip_all, fields(user.id = %consent.user_id, consent.type = consent.consent_type.as_str(), outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn record_consent(&self, consent: RecordConsent) -> Result<Consent, ConsentDomainError> {
+        record_outcome(async {
+            self.ensure_user_exists(&consent.user_id, ConsentDomainError::ConsentRecordFailed).await?;
+            self.consent_repository.record_consent(consent).await
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "consent_service.list_consents", skip_all, fields(user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_consents(&self, user_id: String) -> Result<Vec<Consent>, ConsentDomainError> {
+        record_outcome(async {
+            self.ensure_user_exists(&user_id, ConsentDomainError::ConsentListFailed).await?;
+            self.consent_repository.list_consents(user_id).await
+        }
+        .await)
+    }
+}
+
+#[async_trait]
+impl ConsentPort for ConsentService {
+    #[tracing::instrument(name = "consent_service.has_consent", skip_all, fields(user.id = %user_id, consent.type = consent_type.as_str(), outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn has_consent(&self, user_id: &str, consent_type: ConsentType) -> Result<bool, ConsentDomainError> {
+        record_outcome(self.consent_repository.list_consents(user_id.to_string()).await)
+            .map(|consents| is_granted(&consents, consent_type))
+    }
+}
+
+/// Consent service used when consent tracking is not wired. Recording fails, users have no
+/// consent records and nothing is consented to.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledConsentService;
+
+#[async_trait]
+impl ConsentServiceTrait for DisabledConsentService {
+    async fn record_consent(&self, _consent: RecordConsent) -> Result<Consent, ConsentDomainError> {
+        tracing::error!("consent tracking is not configured");
+        Err(ConsentDomainError::ConsentRecordFailed)
+    }
+
+    async fn list_consents(&self, _user_id: String) -> Result<Vec<Consent>, ConsentDomainError> {
+        Ok(Vec::new())
+    }
+}
+
+#[async_trait]
+impl ConsentPort for DisabledConsentService {
+    async fn has_consent(&self, _user_id: &str, _consent_type: ConsentType) -> Result<bool, ConsentDomainError> {
+        Ok(false)
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use domain::outcome::record_outcome;
+
+use crate::ports::auth::{all_scopes, AccessToken, TokenPort};
+use crate::ports::device::{DeviceApproval, DeviceCodes, DeviceGrantError, DeviceGrantPort};
+
+/// Service trait for the device authorization grant (RFC 8628), letting devices without a
+/// browser, e.g. CLI tools, obtain an access token approved by a user on another device.
+#[async_trait]
+pub trait DeviceServiceTrait {
+    /// Issues the codes of a new authorization request of the client `client_id`.
+    async fn start(&self, client_id: String) -> Result<DeviceCodes, DeviceGrantError>;
+
+    /// Approves the request with the given user code on behalf of the user `user_id`, whose
+    /// `roles` are granted to the token of the device.
+    async fn approve(&self, user_code: String, user_id: String, roles: Vec<String>) -> Result<(), DeviceGrantError>;
+
+    /// Denies the request with the given user code.
+    async fn deny(&self, user_code: String) -> Result<(), DeviceGrantError>;
+
+    /// Exchanges an approved device code for an access token of the user who approved it.
+    async fn exchange(&self, device_code: String, client_id: String) -> Result<AccessToken, DeviceGrantError>;
+}
+
+/// Service implementation for the device authorization grant, combining the pending requests
+/// with the issuance of access tokens.
+///
+/// Every operation runs in its own span carrying `outcome` and `error.class`; codes and
+/// tokens are never recorded.
+pub struct DeviceService {
+    grants: Arc<dyn DeviceGrantPort + Send + Sync + 'static>,
+    tokens: Arc<dyn TokenPort + Send + Sync + 'static>,
+}
+
+impl DeviceService {
+    /// Creates a new `DeviceService` instance.
+    pub fn new(grants: Arc<dyn DeviceGrantPort + Send + Sync + 'static>, tokens: Arc<dyn TokenPort + Send + Sync + 'static>) -> Self {
+        Self { grants, tokens }
+    }
+}
+
+#[async_trait]
+impl DeviceServiceTrait for DeviceService {
+    #[tracing::instrument(name = "device_service.start", skip_all, fields(client.id = %client_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn start(&self, client_id: String) -> Result<DeviceCodes, DeviceGrantError> {
+        record_outcome(self.grants.issue(&client_id))
+    }
+
+    #[tracing::instrument(name = "device_service.approve", skip_all, fields(user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn approve(&self, user_code: String, user_id: String, roles: Vec<String>) -> Result<(), DeviceGrantError> {
+        record_outcome(self.grants.approve(&user_code, DeviceApproval { user_id, roles }))
+    }
+
+    #[tracing::instrument(name = "device_service.deny", skip_all, fields(outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn deny(&self, user_code: String) -> Result<(), DeviceGrantError> {
+        record_outcome(self.grants.deny(&user_code))
+    }
+
+    #[tracing::instrument(name = "device_service.exchange", skip_all, fields(client.id = %client_id, user.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn exchange(&self, device_code: String, client_id: String) -> Result<AccessToken, DeviceGrantError> {
+        record_outcome(self.grants.poll(&device_code, &client_id).and_then(|approval| {
+            tracing::Span::current().record("user.id", &approval.user_id);
+            self.tokens.issue(&approval.user_id, &approval.roles, &all_scopes()).map_err(|_| DeviceGrantError::Unavailable)
+        }))
+    }
+}
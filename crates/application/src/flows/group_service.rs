@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use domain::outcome::record_outcome;
+use domain::group::{error::GroupDomainError, model::{Group, GroupRoles, SaveGroup, UpdateMembers}, repository::GroupRepositoryPort};
+use domain::user::{error::UserDomainError, model::UserId, repository::UserRepositoryPort};
+
+use crate::ports::group::GroupRolesPort;
+
+/// Service trait for groups, their members and the roles they bundle.
+#[async_trait]
+pub trait GroupServiceTrait: GroupRolesPort {
+    /// Creates a group, or replaces the roles of the existing group of the same name.
+    async fn save_group(&self, group: SaveGroup) -> Result<Group, GroupDomainError>;
+
+    /// Retrieves a group by name.
+    async fn get_group(&self, name: String) -> Result<Group, GroupDomainError>;
+
+    /// Retrieves all groups, ordered by name.
+    async fn list_groups(&self) -> Result<Vec<Group>, GroupDomainError>;
+
+    /// Deletes a group and its memberships.
+    async fn delete_group(&self, name: String) -> Result<(), GroupDomainError>;
+
+    /// Retrieves the members of a group, ordered by id.
+    async fn list_members(&self, name: String) -> Result<Vec<UserId>, GroupDomainError>;
+
+    /// Adds and removes members of a group, all at once or not at all.
+    async fn update_members(&self, name: String, members: UpdateMembers) -> Result<(), GroupDomainError>;
+
+    /// Returns the groups of an existing user and the roles they grant and deny.
+    async fn group_roles(&self, user_id: UserId) -> Result<GroupRoles, GroupDomainError>;
+}
+
+/// Service implementation for groups.
+///
+/// The service is also the [`GroupRolesPort`] adapter through which token issuers resolve
+/// the roles of users, including the [`domain::user::model::Role`] stored with local users.
+pub struct GroupService {
+    group_repository: Arc<dyn GroupRepositoryPort + Send + Sync + 'static>,
+    user_repository: Arc<dyn UserRepositoryPort + Send + Sync + 'static>,
+}
+
+impl GroupService {
+    /// Creates a new `GroupService` instance.
+    pub fn new(
+        group_repository: Arc<dyn GroupRepositoryPort + Send + Sync + 'static>,
+        user_repository: Arc<dyn UserRepositoryPort + Send + Sync + 'static>,
+    ) -> Self {
+        Self { group_repository, user_repository }
+    }
+}
+
+#[async_trait]
+impl GroupServiceTrait for GroupService {
+    #[tracing::instrument(name = "group_service.save_group", skip_all, fields(group.name = %group.name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn save_group(&self, group: SaveGroup) -> Result<Group, GroupDomainError> {
+        record_outcome(self.group_repository.save_group(group).await)
+    }
+
+    #[tracing::instrument(name = "group_service.get_group", skip_all, fields(group.name = %name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_group(&self, name: String) -> Result<Group, GroupDomainError> {
+        record_outcome(self.group_repository.get_group(name).await)
+    }
+
+    #[tracing::instrument(name = "group_service.list_groups", skip_all, fields(outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_groups(&self) -> Result<Vec<Group>, GroupDomainError> {
+        record_outcome(self.group_repository.list_groups().await)
+    }
+
+    #[tracing::instrument(name = "group_service.delete_group", skip_all, fields(group.name = %name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn delete_group(&self, name: String) -> Result<(), GroupDomainError> {
+        record_outcome(self.group_repository.delete_group(name).await)
+    }
+
+    #[tracing::instrument(name = "group_service.list_members", skip_all, fields(group.name = %name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_members(&self, name: String) -> Result<Vec<UserId>, GroupDomainError> {
+        record_outcome(self.group_repository.list_members(name).await)
+    }
+
+    #[tracing::instrument(name = "group_service.update_members", skip_all, fields(group.name = %name, members.added = members.add.len(), members.removed = members.remove.len(), outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn update_members(&self, name: String, members: UpdateMembers) -> Result<(), GroupDomainError> {
+        record_outcome(self.group_repository.update_members(name, members).await)
+    }
+
+    #[tracing::instrument(name = "group_service.group_roles", skip_all, fields(user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn group_roles(&self, user_id: UserId) -> Result<GroupRoles, GroupDomainError> {
+        record_outcome(async {
+            match self.user_repository.get_user(user_id).await {
+                Ok(_) => {}
+                Err(UserDomainError::UserNotFound) => return Err(GroupDomainError::UserNotFound),
+                Err(_) => return Err(GroupDomainError::GroupListFailed),
+            }
+            let groups = self.group_repository.list_user_groups(user_id).await?;
+            Ok(GroupRoles::of(&groups))
+        }
+        .await)
+    }
+}
+
+#[async_trait]
+impl GroupRolesPort for GroupService {
+    #[tracing::instrument(name = "group_service.effective_roles", skip_all, fields(user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn effective_roles(&self, user_id: &str, direct_roles: &[String]) -> Result<Vec<String>, GroupDomainError> {
+        record_outcome(async {
+            // Users of an external directory may have ids that cannot name a local user
+            let Ok(user_id) = UserId::parse(user_id) else {
+                return Ok(direct_roles.to_vec());
+            };
+            // Users stored locally are granted their stored role first; others have none
+            let mut roles = Vec::with_capacity(direct_roles.len() + 1);
+            match self.user_repository.get_user(user_id).await {
+                Ok(user) => roles.push(user.role().as_str().to_string()),
+                Err(UserDomainError::UserNotFound) => {}
+                Err(_) => return Err(GroupDomainError::GroupListFailed),
+            }
+            roles.extend_from_slice(direct_roles);
+            let groups = self.group_repository.list_user_groups(user_id).await?;
+            Ok(GroupRoles::of(&groups).apply(&roles))
+        }
+        .await)
+    }
+}
+
+/// Group service used when groups are not wired. Saving fails, there are no groups, and users
+/// only have their direct roles.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledGroupService;
+
+#[async_trait]
+impl GroupServiceTrait for DisabledGroupService {
+    async fn save_group(&self, _group: SaveGroup) -> Result<Group, GroupDomainError> {
+        tracing::error!("groups are not configured");
+        Err(GroupDomainError::GroupSaveFailed)
+    }
+
+    async fn get_group(&self, _name: String) -> Result<Group, GroupDomainError> {
+        Err(GroupDomainError::GroupNotFound)
+    }
+
+    async fn list_groups(&self) -> Result<Vec<Group>, GroupDomainError> {
+        Ok(Vec::new())
+    }
+
+    async fn delete_group(&self, _name: String) -> Result<(), GroupDomainError> {
+        Err(GroupDomainError::GroupNotFound)
+    }
+
+    async fn list_members(&self, _name: String) -> Result<Vec<UserId>, GroupDomainError> {
+        Err(GroupDomainError::GroupNotFound)
+    }
+
+    async fn update_members(&self, _name: String, _members: UpdateMembers) -> Result<(), GroupDomainError> {
+        Err(GroupDomainError::GroupNotFound)
+    }
+
+    async fn group_roles(&self, _user_id: UserId) -> Result<GroupRoles, GroupDomainError> {
+        Ok(GroupRoles::default())
+    }
+}
+
+#[async_trait]
+impl GroupRolesPort for DisabledGroupService {
+    async fn effective_roles(&self, _user_id: &str, direct_roles: &[String]) -> Result<Vec<String>, GroupDomainError> {
+        Ok(direct_roles.to_vec())
+    }
+}
@@ -0,0 +1,10 @@
+pub mod anomaly_detector;
+pub mod auth_service;
+pub mod consent_service;
+pub mod device_service;
+pub mod group_service;
+pub mod passkey_service;
+pub mod password_policy;
+pub mod saml_service;
+pub mod slo_tracker;
+pub mod user_service;
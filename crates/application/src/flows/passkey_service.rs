@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+use domain::outcome::record_outcome;
+use domain::passkey::{error::PasskeyDomainError, model::{Passkey, RegisterPasskey}, repository::PasskeyRepositoryPort};
+use domain::user::{error::UserDomainError, model::{Email, UserId}, repository::UserRepositoryPort};
+
+use crate::flows::group_service::DisabledGroupService;
+use crate::ports::auth::{all_scopes, AccessToken, TokenPort};
+use crate::ports::group::GroupRolesPort;
+use crate::ports::webauthn::{PasskeyChallenge, PasskeyError, WebAuthnPort};
+
+/// Service trait for passkeys: their registration and management by users, and logins with them.
+#[async_trait]
+pub trait PasskeyServiceTrait {
+    /// Starts the registration of a new passkey of the user.
+    async fn start_registration(&self, user_id: UserId) -> Result<PasskeyChallenge, PasskeyError>;
+
+    /// Verifies the credential created for a registration ceremony, and stores it under `name`.
+    async fn finish_registration(&self, user_id: UserId, ceremony_id: String, name: String, response: Value) -> Result<Passkey, PasskeyError>;
+
+    /// Starts a login of the user with the given email, with one of its passkeys.
+    async fn start_login(&self, email: Email) -> Result<PasskeyChallenge, PasskeyError>;
+
+    /// Verifies the assertion of an authentication ceremony and issues an access token for its user.
+    async fn finish_login(&self, ceremony_id: String, response: Value) -> Result<AccessToken, PasskeyError>;
+
+    /// Lists the passkeys of the user, in the order they were registered.
+    async fn list_passkeys(&self, user_id: UserId) -> Result<Vec<Passkey>, PasskeyError>;
+
+    /// Deletes a passkey of the user.
+    async fn delete_passkey(&self, user_id: UserId, id: String) -> Result<(), PasskeyError>;
+}
+
+/// Service implementation for passkeys, combining the WebAuthn ceremonies with the storage of
+/// credentials and the issuance of access tokens.
+///
+/// Every operation runs in its own span carrying the user id, `outcome` and `error.class`;
+/// ceremony options, responses and credentials are never recorded.
+pub struct PasskeyService {
+    webauthn: Arc<dyn WebAuthnPort + Send + Sync + 'static>,
+    passkey_repository: Arc<dyn PasskeyRepositoryPort + Send + Sync + 'static>,
+    user_repository: Arc<dyn UserRepositoryPort + Send + Sync + 'static>,
+    tokens: Arc<dyn TokenPort + Send + Sync + 'static>,
+    group_roles: Arc<dyn GroupRolesPort + Send + Sync + 'static>,
+}
+
+impl PasskeyService {
+    /// Creates a new `PasskeyService` instance.
+    pub fn new(
+        webauthn: Arc<dyn WebAuthnPort + Send + Sync + 'static>,
+        passkey_repository: Arc<dyn PasskeyRepositoryPort + Send + Sync + 'static>,
+        user_repository: Arc<dyn UserRepositoryPort + Send + Sync + 'static>,
+        tokens: Arc<dyn TokenPort + Send + Sync + 'static>,
+    ) -> Self {
+        Self { webauthn, passkey_repository, user_repository, tokens, group_roles: Arc::new(DisabledGroupService) }
+    }
+
+    /// Issues tokens carrying the roles users get from their groups, instead of no roles.
+    pub fn with_group_roles(mut self, group_roles: Arc<dyn GroupRolesPort + Send + Sync + 'static>) -> Self {
+        self.group_roles = group_roles;
+        self
+    }
+}
+
+#[async_trait]
+impl PasskeyServiceTrait for PasskeyService {
+    #[tracing::instrument(name = "passkey_service.start_registration", skip_all, fields(user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn start_registration(&self, user_id: UserId) -> Result<PasskeyChallenge, PasskeyError> {
+        record_outcome(async {
+            let user = self.user_repository.get_user(user_id).await.map_err(|e| match e {
+                UserDomainError::UserNotFound => PasskeyError::UserNotFound,
+                _ => PasskeyError::Unavailable,
+            })?;
+            let registered = self.passkey_repository.list_passkeys(user_id).await?;
+            self.webauthn.start_registration(&user, &registered)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "passkey_service.finish_registration", skip_all, fields(user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn finish_registration(&self, user_id: UserId, ceremony_id: String, name: String, response: Value) -> Result<Passkey, PasskeyError> {
+        record_outcome(async {
+            let credential = self.webauthn.finish_registration(user_id, &ceremony_id, response)?;
+            let passkey = RegisterPasskey {
+                id: credential.id,
+                user_id,
+                name,
+                credential: credential.credential,
+            };
+            Ok(self.passkey_repository.add_passkey(passkey).await?)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "passkey_service.start_login", skip_all, fields(user.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn start_login(&self, email: Email) -> Result<PasskeyChallenge, PasskeyError> {
+        record_outcome(async {
+            let user = self.user_repository.get_user_by_email(email).await.map_err(|e| match e {
+                UserDomainError::UserNotFound => PasskeyError::NoPasskey,
+                _ => PasskeyError::Unavailable,
+            })?;
+            tracing::Span::current().record("user.id", tracing::field::display(user.id()));
+
+            let registered = self.passkey_repository.list_passkeys(user.id()).await?;
+            if registered.is_empty() {
+                return Err(PasskeyError::NoPasskey);
+            }
+            self.webauthn.start_authentication(user.id(), &registered)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "passkey_service.finish_login", skip_all, fields(user.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn finish_login(&self, ceremony_id: String, response: Value) -> Result<AccessToken, PasskeyError> {
+        record_outcome(async {
+            let assertion = self.webauthn.finish_authentication(&ceremony_id, response)?;
+            tracing::Span::current().record("user.id", tracing::field::display(assertion.user_id));
+
+            // A passkey deleted while the ceremony was running no longer logs the user in
+            self.passkey_repository
+                .record_passkey_use(assertion.user_id, assertion.id, assertion.credential)
+                .await
+                .map_err(|e| match e {
+                    PasskeyDomainError::PasskeyNotFound => PasskeyError::InvalidResponse,
+                    e => e.into(),
+                })?;
+            let user_id = assertion.user_id.to_string();
+            let roles = self.group_roles.effective_roles(&user_id, &[]).await.map_err(|_| PasskeyError::Unavailable)?;
+            self.tokens.issue(&user_id, &roles, &all_scopes()).map_err(|_| PasskeyError::Unavailable)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "passkey_service.list_passkeys", skip_all, fields(user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_passkeys(&self, user_id: UserId) -> Result<Vec<Passkey>, PasskeyError> {
+        record_outcome(self.passkey_repository.list_passkeys(user_id).await.map_err(PasskeyError::from))
+    }
+
+    #[tracing::instrument(name = "passkey_service.delete_passkey", skip_all, fields(user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn delete_passkey(&self, user_id: UserId, id: String) -> Result<(), PasskeyError> {
+        record_outcome(self.passkey_repository.delete_passkey(user_id, id).await.map_err(PasskeyError::from))
+    }
+}
@@ -0,0 +1,107 @@
+//! Rules the passwords of users must follow, checked when a password is set.
+//!
+//! A policy is a list of [`PasswordRule`]s, every one of which is checked, so a client learns
+//! all the rules a password violates at once. Rules of the length of passwords and of their
+//! presence in data breaches are defined here; others, such as the strength estimated by
+//! zxcvbn, are defined by adapters.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use domain::user::{error::UserDomainError, model::Password, validation::PasswordViolation};
+
+use crate::ports::breached_passwords::BreachedPasswordsPort;
+
+/// A rule of a [`PasswordPolicy`].
+#[async_trait]
+pub trait PasswordRule {
+    /// Returns how `password` violates the rule, if it does. `user_inputs` are the attributes
+    /// of its user (name and email), which a password should not be guessable from.
+    async fn check(&self, password: &Password, user_inputs: &[&str]) -> Option<PasswordViolation>;
+}
+
+/// The rules the passwords of users must follow, on top of the constraints of the domain.
+/// Without rules, every password the domain accepts is accepted.
+#[derive(Clone, Default)]
+pub struct PasswordPolicy {
+    rules: Vec<Arc<dyn PasswordRule + Send + Sync + 'static>>,
+}
+
+impl PasswordPolicy {
+    /// Creates a new `PasswordPolicy` without rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `rule` to the rules of the policy, checked after the rules already added.
+    pub fn with_rule(mut self, rule: Arc<dyn PasswordRule + Send + Sync + 'static>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Checks `password` against every rule, or fails with [`UserDomainError::WeakPassword`]
+    /// listing the violations, in the order of the rules.
+    pub async fn check(&self, password: &Password, user_inputs: &[&str]) -> Result<(), UserDomainError> {
+        let mut violations = Vec::new();
+        for rule in &self.rules {
+            violations.extend(rule.check(password, user_inputs).await);
+        }
+        if violations.is_empty() { Ok(()) } else { Err(UserDomainError::WeakPassword(violations)) }
+    }
+}
+
+/// Rule of the minimum length of passwords, in characters, raising the one of the domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinLengthRule {
+    min_length: usize,
+}
+
+impl MinLengthRule {
+    /// Creates a new `MinLengthRule` refusing the passwords shorter than `min_length` characters.
+    pub fn new(min_length: usize) -> Self {
+        Self { min_length }
+    }
+}
+
+#[async_trait]
+impl PasswordRule for MinLengthRule {
+    async fn check(&self, password: &Password, _user_inputs: &[&str]) -> Option<PasswordViolation> {
+        (password.as_str().chars().count() < self.min_length).then(|| PasswordViolation {
+            rule: "min_length",
+            message: format!("must be at least {} characters", self.min_length),
+        })
+    }
+}
+
+/// Rule refusing the passwords leaked by data breaches, which attackers try first.
+///
+/// Passwords are accepted when the breaches cannot be looked up, so an outage of the lookup
+/// does not prevent users from being created.
+pub struct BreachedPasswordRule {
+    breaches: Arc<dyn BreachedPasswordsPort + Send + Sync + 'static>,
+}
+
+impl BreachedPasswordRule {
+    /// Creates a new `BreachedPasswordRule` looking passwords up in `breaches`.
+    pub fn new(breaches: Arc<dyn BreachedPasswordsPort + Send + Sync + 'static>) -> Self {
+        Self { breaches }
+    }
+}
+
+#[async_trait]
+impl PasswordRule for BreachedPasswordRule {
+    async fn check(&self, password: &Password, _user_inputs: &[&str]) -> Option<PasswordViolation> {
+        match self.breaches.breach_count(password.as_str()).await {
+            Ok(0) => None,
+            Ok(count) => Some(PasswordViolation {
+                rule: "breached",
+                message: format!("appeared {} times in data breaches", count),
+            }),
+            Err(e) => {
+                tracing::warn!("failed to look up breached passwords, accepting the password: {:#}", e);
+                None
+            }
+        }
+    }
+}
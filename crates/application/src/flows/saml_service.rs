@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use domain::outcome::record_outcome;
+use domain::user::{error::UserDomainError, model::Email, repository::UserRepositoryPort};
+
+use crate::flows::group_service::DisabledGroupService;
+use crate::ports::auth::{all_scopes, AccessToken, AuthError, SamlServiceProviderPort, TokenPort};
+use crate::ports::group::GroupRolesPort;
+
+/// Service trait for single sign-on through a SAML identity provider.
+#[async_trait]
+pub trait SamlServiceTrait {
+    /// Returns the metadata document of the service provider.
+    fn metadata(&self) -> String;
+
+    /// Returns the URL of the identity provider the browser is redirected to for logging in.
+    fn login_url(&self) -> Result<String, AuthError>;
+
+    /// Validates a `SAMLResponse` of the identity provider and issues an access token for the
+    /// local user it asserts.
+    async fn login(&self, response: String) -> Result<AccessToken, AuthError>;
+}
+
+/// Service implementation for SAML single sign-on.
+///
+/// Asserted users are matched to local users by email; users unknown locally are rejected
+/// rather than created, as provisioning goes through SCIM.
+pub struct SamlService {
+    service_provider: Arc<dyn SamlServiceProviderPort + Send + Sync + 'static>,
+    user_repository: Arc<dyn UserRepositoryPort + Send + Sync + 'static>,
+    tokens: Arc<dyn TokenPort + Send + Sync + 'static>,
+    group_roles: Arc<dyn GroupRolesPort + Send + Sync + 'static>,
+}
+
+impl SamlService {
+    /// Creates a new `SamlService` instance.
+    pub fn new(
+        service_provider: Arc<dyn SamlServiceProviderPort + Send + Sync + 'static>,
+        user_repository: Arc<dyn UserRepositoryPort + Send + Sync + 'static>,
+        tokens: Arc<dyn TokenPort + Send + Sync + 'static>,
+    ) -> Self {
+        Self { service_provider, user_repository, tokens, group_roles: Arc::new(DisabledGroupService) }
+    }
+
+    /// Issues tokens carrying the roles in effect per `group_roles` rather than the roles
+    /// asserted by the identity provider alone.
+    pub fn with_group_roles(mut self, group_roles: Arc<dyn GroupRolesPort + Send + Sync + 'static>) -> Self {
+        self.group_roles = group_roles;
+        self
+    }
+}
+
+#[async_trait]
+impl SamlServiceTrait for SamlService {
+    fn metadata(&self) -> String {
+        self.service_provider.metadata()
+    }
+
+    fn login_url(&self) -> Result<String, AuthError> {
+        self.service_provider.login_url()
+    }
+
+    #[tracing::instrument(name = "saml_service.login", skip_all, fields(user.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn login(&self, response: String) -> Result<AccessToken, AuthError> {
+        record_outcome(async {
+            let identity = self.service_provider.consume(&response)?;
+            let Ok(email) = Email::parse(identity.email) else {
+                tracing::warn!("the identity provider asserted a malformed email address");
+                return Err(AuthError::InvalidCredentials);
+            };
+            let user = match self.user_repository.get_user_by_email(email).await {
+                Ok(user) => user,
+                Err(UserDomainError::UserNotFound) => {
+                    tracing::warn!("the identity provider asserted a user unknown locally");
+                    return Err(AuthError::InvalidCredentials);
+                }
+                Err(_) => return Err(AuthError::Unavailable),
+            };
+            tracing::Span::current().record("user.id", tracing::field::display(user.id()));
+            let user_id = user.id().to_string();
+            let roles = self.group_roles.effective_roles(&user_id, &identity.roles).await.map_err(|_| AuthError::Unavailable)?;
+            self.tokens.issue(&user_id, &roles, &all_scopes())
+        }
+        .await)
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::ports::alert::{Alert, AlertPort};
+
+/// Name of the alerts raised by [`SloTracker`].
+pub const SLO_BURN_RATE_ALERT: &str = "slo_burn_rate";
+
+/// A service level objective on the latency of requests, e.g. 99% of `GET` requests served
+/// in less than 100ms.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloObjective {
+    /// Stable name of the objective, e.g. `reads`, reported and alerted on.
+    pub name: String,
+    /// Method of the requests the objective covers, all requests when `None`.
+    pub method: Option<String>,
+    /// Latency a request must be served within to be good. Server errors are never good.
+    pub latency: Duration,
+    /// Share of the requests that must be good, in `0..1`, e.g. `0.99`.
+    pub target: f64,
+}
+
+impl SloObjective {
+    fn covers(&self, method: &str) -> bool {
+        self.method.as_deref().is_none_or(|covered| covered.eq_ignore_ascii_case(method))
+    }
+
+    /// Share of the requests that may be bad, e.g. 1% for a target of 99%.
+    fn error_budget(&self) -> f64 {
+        1.0 - self.target
+    }
+}
+
+/// Settings of the tracking of the objectives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloPolicy {
+    pub objectives: Vec<SloObjective>,
+    /// Rolling window the compliance and the error budget are computed over.
+    pub window: Duration,
+    /// Length of the buckets requests are counted in, the precision of the rolling windows.
+    pub bucket: Duration,
+    /// Shorter window the burn rate is computed over, at least one bucket.
+    pub burn_window: Duration,
+    /// Burn rate from which an alert is raised: how many times faster than allowed by the
+    /// target the error budget is consumed, e.g. 14.4 consumes 2% of a 30-day budget in an hour.
+    pub burn_rate_threshold: f64,
+    /// Requests the burn window needs before it can raise an alert, so that a few slow
+    /// requests after a quiet period do not.
+    pub min_requests: u64,
+}
+
+/// The state of an objective over the rolling window, as reported to operators.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloReport {
+    pub objective: SloObjective,
+    /// Requests covered by the objective in the window.
+    pub total: u64,
+    /// Requests of `total` served within the latency of the objective, without a server error.
+    pub good: u64,
+    /// Share of the requests of the window that were good, `None` without requests.
+    pub compliance: Option<f64>,
+    /// Share of the error budget of the window left, negative once the objective is missed.
+    pub error_budget_remaining: f64,
+    /// Rate the error budget is consumed at over the burn window, 1 consuming it exactly by the
+    /// end of the window.
+    pub burn_rate: f64,
+}
+
+/// Requests counted in one bucket.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    index: u64,
+    total: u64,
+    bad: u64,
+}
+
+/// Counts of the requests of one objective, in buckets of the rolling window.
+struct Counts {
+    buckets: VecDeque<Bucket>,
+    /// Bucket until which no further alert is raised, the burn window of the last alert.
+    quiet_until: Option<u64>,
+}
+
+impl Counts {
+    /// Sums the requests of the `buckets` last buckets up to `current`, as `(total, bad)`.
+    fn sum(&self, current: u64, buckets: u64) -> (u64, u64) {
+        self.buckets
+            .iter()
+            .filter(|bucket| bucket.index + buckets > current)
+            .fold((0, 0), |(total, bad), bucket| (total + bucket.total, bad + bucket.bad))
+    }
+}
+
+/// Tracker of the compliance of requests with latency objectives and of the consumption of
+/// their error budgets.
+///
+/// Requests are counted in buckets of a rolling window. When the error budget of an objective
+/// is consumed faster than the threshold over the burn window, an alert is raised, once per
+/// burn window.
+///
+/// Counts are kept per replica, in memory, so each replica reports the requests it serves
+/// since it started.
+pub struct SloTracker {
+    policy: SloPolicy,
+    alerts: Arc<dyn AlertPort + Send + Sync + 'static>,
+    /// Start of the first bucket.
+    epoch: Instant,
+    counts: Mutex<Vec<Counts>>,
+}
+
+impl SloTracker {
+    /// Creates a new `SloTracker` raising alerts to `alerts`. Fails when a target is not in
+    /// `0..1`, a name is duplicated, the bucket is zero, the burn window is shorter than the
+    /// bucket or longer than the window, or the threshold is not positive.
+    pub fn new(policy: SloPolicy, alerts: Arc<dyn AlertPort + Send + Sync + 'static>) -> eyre::Result<Self> {
+        for (i, objective) in policy.objectives.iter().enumerate() {
+            if !(objective.target > 0.0 && objective.target < 1.0) {
+                eyre::bail!("target of SLO {} must be between 0 and 1, exclusive", objective.name);
+            }
+            if policy.objectives[..i].iter().any(|other| other.name == objective.name) {
+                eyre::bail!("SLO {} is defined twice", objective.name);
+            }
+        }
+        if policy.bucket.is_zero() {
+            eyre::bail!("SLO bucket must be positive");
+        }
+        if policy.burn_window < policy.bucket || policy.burn_window > policy.window {
+            eyre::bail!("SLO burn window must be between the bucket and the window");
+        }
+        if policy.burn_rate_threshold.is_nan() || policy.burn_rate_threshold <= 0.0 {
+            eyre::bail!("SLO burn rate threshold must be positive");
+        }
+
+        let counts = policy.objectives.iter().map(|_| Counts { buckets: VecDeque::new(), quiet_until: None }).collect();
+        Ok(Self { policy, alerts, epoch: Instant::now(), counts: Mutex::new(counts) })
+    }
+
+    /// Records a request served now.
+    pub fn record(&self, method: &str, status: u16, latency: Duration) {
+        self.record_at(method, status, latency, Instant::now());
+    }
+
+    /// Records a request served at `now`, raising an alert if it makes the error budget of an
+    /// objective burn too fast.
+    pub fn record_at(&self, method: &str, status: u16, latency: Duration, now: Instant) {
+        let current = self.bucket_index(now);
+        let window = self.buckets(self.policy.window);
+        let burn_window = self.buckets(self.policy.burn_window);
+
+        let mut alerts = Vec::new();
+        {
+            let mut counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+            for (objective, counts) in self.policy.objectives.iter().zip(counts.iter_mut()) {
+                if !objective.covers(method) {
+                    continue;
+                }
+                let bad = status >= 500 || latency > objective.latency;
+                match counts.buckets.back_mut() {
+                    Some(bucket) if bucket.index == current => {
+                        bucket.total += 1;
+                        bucket.bad += u64::from(bad);
+                    }
+                    _ => counts.buckets.push_back(Bucket { index: current, total: 1, bad: u64::from(bad) }),
+                }
+                while counts.buckets.front().is_some_and(|bucket| bucket.index + window <= current) {
+                    counts.buckets.pop_front();
+                }
+
+                if !bad || counts.quiet_until.is_some_and(|quiet_until| current < quiet_until) {
+                    continue;
+                }
+                let (total, bad) = counts.sum(current, burn_window);
+                let burn_rate = burn_rate(objective, total, bad);
+                if total >= self.policy.min_requests && burn_rate >= self.policy.burn_rate_threshold {
+                    counts.quiet_until = Some(current + burn_window);
+                    alerts.push((objective, total, bad, burn_rate));
+                }
+            }
+        }
+
+        for (objective, total, bad, burn_rate) in alerts {
+            self.alerts.raise(Alert {
+                name: SLO_BURN_RATE_ALERT,
+                message: format!(
+                    "SLO {} burns its error budget {:.1} times too fast: {} of {} requests in the last {}s were slower than {}ms or failed, for a target of {}%",
+                    objective.name,
+                    burn_rate,
+                    bad,
+                    total,
+                    self.policy.burn_window.as_secs(),
+                    objective.latency.as_millis(),
+                    objective.target * 100.0
+                ),
+            });
+        }
+    }
+
+    /// Reports the state of every objective now.
+    pub fn report(&self) -> Vec<SloReport> {
+        self.report_at(Instant::now())
+    }
+
+    /// Reports the state of every objective at `now`, in the order of the policy.
+    pub fn report_at(&self, now: Instant) -> Vec<SloReport> {
+        let current = self.bucket_index(now);
+        let window = self.buckets(self.policy.window);
+        let burn_window = self.buckets(self.policy.burn_window);
+
+        let counts = self.counts.lock().unwrap_or_else(|e| e.into_inner());
+        self.policy
+            .objectives
+            .iter()
+            .zip(counts.iter())
+            .map(|(objective, counts)| {
+                let (total, bad) = counts.sum(current, window);
+                let (burn_total, burn_bad) = counts.sum(current, burn_window);
+                SloReport {
+                    objective: objective.clone(),
+                    total,
+                    good: total - bad,
+                    compliance: (total > 0).then(|| (total - bad) as f64 / total as f64),
+                    error_budget_remaining: 1.0 - burn_rate(objective, total, bad),
+                    burn_rate: burn_rate(objective, burn_total, burn_bad),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the rolling window of the policy.
+    pub fn window(&self) -> Duration {
+        self.policy.window
+    }
+
+    /// Returns the burn window of the policy.
+    pub fn burn_window(&self) -> Duration {
+        self.policy.burn_window
+    }
+
+    fn bucket_index(&self, now: Instant) -> u64 {
+        (now.saturating_duration_since(self.epoch).as_nanos() / self.policy.bucket.as_nanos()) as u64
+    }
+
+    /// Returns the number of buckets of `window`, at least one.
+    fn buckets(&self, window: Duration) -> u64 {
+        (window.as_nanos().div_ceil(self.policy.bucket.as_nanos()) as u64).max(1)
+    }
+}
+
+/// Returns the share of `total` that was bad, relative to the error budget of `objective`.
+fn burn_rate(objective: &SloObjective, total: u64, bad: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    bad as f64 / total as f64 / objective.error_budget()
+}
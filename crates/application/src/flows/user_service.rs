@@ -0,0 +1,712 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::flows::anomaly_detector::{MutationAnomalyDetector, UserMutation};
+use crate::flows::password_policy::PasswordPolicy;
+use crate::jobs::{welcome_email::{welcome_email, welcome_email_job}, JobQueuePort};
+use crate::ports::audit::{AuditAction, AuditEntry, AuditLogPort};
+use crate::ports::email::{DisabledEmailSender, EmailSenderPort};
+use crate::ports::events::{DisabledEventPublisher, EventPublisherPort};
+use crate::ports::password::{DisabledPasswordHasher, PasswordHasherPort};
+use crate::ports::purge::{surrogate_keys, PurgePort};
+use crate::ports::unit_of_work::{IsolationLevel, TransactionPort, UnitOfWorkPort};
+
+use domain::outcome::record_outcome;
+use domain::user::{error::{StorageError, UserDomainError}, event::UserEvent, model::{CreateUser, Email, ListUsers, PasswordHash, Role, UpdateUser, User, UserCredentials, UserFacets, UserFilter, UserId, UserPage}, repository::UserRepositoryPort};
+
+/// Service trait for user operations.
+///
+/// This trait defines the business logic interface for user management operations.
+/// Implementations should handle domain logic and coordinate with repositories for data access and other dependencies(rpc, metrics) via clearly defined interfaces(ports)
+#[async_trait]
+pub trait UserServiceTrait {
+    /// Creates a new user, storing the hash of the user's password if the user has one.
+    async fn create_user(&self, user: CreateUser) -> Result<User, UserDomainError>;
+
+    /// Creates users like [`UserServiceTrait::create_user`], inserting them in batches, and
+    /// returns the outcome of each user in order: a user failing to be created does not fail
+    /// the others.
+    async fn create_users_bulk(&self, users: Vec<CreateUser>) -> Vec<Result<User, UserDomainError>>;
+
+    /// Retrieves a user by ID.
+    async fn get_user(&self, id: UserId) -> Result<User, UserDomainError>;
+
+    /// Retrieves a user by email address, ignoring case and accents.
+    async fn get_user_by_email(&self, email: Email) -> Result<User, UserDomainError>;
+
+    /// Returns the user with the given email and password, or fails with
+    /// [`UserDomainError::InvalidCredentials`] without telling which of the two is wrong.
+    async fn verify_credentials(&self, email: Email, password: String) -> Result<User, UserDomainError>;
+
+    /// Lists a page of users in the requested order, with the total number of users.
+    async fn list_users(&self, query: ListUsers) -> Result<UserPage, UserDomainError>;
+
+    /// Searches a page of the users matching the filter, with the total number of matching users.
+    async fn search_users(&self, filter: UserFilter) -> Result<UserPage, UserDomainError>;
+
+    /// Counts the users by status, by email domain and by age bucket.
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError>;
+
+    /// Updates an existing user.
+    async fn update_user(&self, user: UpdateUser) -> Result<User, UserDomainError>;
+
+    /// Soft-deletes a user by ID, unless the user is under legal hold. The user can be restored.
+    async fn delete_user(&self, id: UserId) -> Result<(), UserDomainError>;
+
+    /// Restores a soft-deleted user by ID.
+    async fn restore_user(&self, id: UserId) -> Result<User, UserDomainError>;
+
+    /// Deletes a user by ID for good, whether soft-deleted or not, unless the user is under legal hold.
+    async fn hard_delete_user(&self, id: UserId) -> Result<(), UserDomainError>;
+
+    /// Places a user under legal hold, or lifts the hold.
+    async fn set_legal_hold(&self, id: UserId, legal_hold: bool) -> Result<User, UserDomainError>;
+
+    /// Sets the role of a user. Tokens already issued to the user keep their roles until they expire.
+    async fn set_role(&self, id: UserId, role: Role) -> Result<User, UserDomainError>;
+}
+
+/// Service implementation for user operations.
+///
+/// This service acts as an application layer between the presentation layer (handlers)
+/// and the domain/infrastructure layer (ports/adapters). It coordinates user-related
+/// business logic and delegates data access to the repository.
+///
+/// The service is generic over its repository. By default it holds an
+/// `Arc<dyn UserRepositoryPort>`, while `UserService<UserRepository>` (or any other adapter)
+/// dispatches every repository call statically.
+///
+/// Every operation runs in its own span (nested under the HTTP request span) carrying the
+/// user id, `outcome` and `error.class`; request payloads such as names and emails are not recorded.
+///
+/// Successful creations, updates and deletions publish a [`UserEvent`] once the repository
+/// returns. The event is not part of the repository transaction: a failure to publish it is
+/// logged, not returned, since the change itself is already made. With a unit of work, the
+/// change and its event are made in a single transaction instead, and fail together.
+///
+/// Once made, changes also purge the responses cached by shared caches their event alters,
+/// through every configured purge port.
+///
+/// Passwords are checked against the password policy, then hashed by the password hasher before
+/// users are created; only their hash is stored. Without a configured hasher, users cannot be
+/// created with a password.
+///
+/// With a job queue, the welcome e-mail of created users is enqueued once they are created.
+/// Without one, it is sent right away through the e-mail sender, when enabled. A failure to
+/// enqueue or send it is logged, not returned, like a failure to publish an event.
+///
+/// With an audit log, changes are recorded in it once made, with their actor and the fields
+/// they changed, read before updates and deletions. With a unit of work, they are recorded in
+/// the transaction of the change, through its audit log rather than the service's own, and a
+/// failure to record them fails the change. Without one, a failure is logged, not returned,
+/// like a failure to publish an event.
+pub struct UserService<R = Arc<dyn UserRepositoryPort + Send + Sync + 'static>> {
+    /// The user repository for data access operations.
+    user_repository: R,
+    /// The publisher of the events of the users, dropping them unless configured.
+    events: Arc<dyn EventPublisherPort + Send + Sync + 'static>,
+    /// Transactions in which changes are made along with their events, when configured.
+    unit_of_work: Option<Arc<dyn UnitOfWorkPort + Send + Sync + 'static>>,
+    /// The detector of anomalous creation and deletion rates, when configured.
+    anomalies: Option<Arc<MutationAnomalyDetector>>,
+    /// The ports purging the responses of shared caches on changes, e.g. of Varnish and a CDN.
+    purges: Vec<Arc<dyn PurgePort + Send + Sync + 'static>>,
+    /// The hasher of the passwords of the users, refusing passwords unless configured.
+    passwords: Arc<dyn PasswordHasherPort + Send + Sync + 'static>,
+    /// The rules the passwords of the users must follow, none unless configured.
+    password_policy: PasswordPolicy,
+    /// The queue of the background jobs following changes, when configured.
+    jobs: Option<Arc<dyn JobQueuePort + Send + Sync + 'static>>,
+    /// The sender of the e-mails not sent by background jobs, sending none unless configured.
+    email: Arc<dyn EmailSenderPort + Send + Sync + 'static>,
+    /// The audit log the changes are recorded in, when configured.
+    audit_log: Option<Arc<dyn AuditLogPort + Send + Sync + 'static>>,
+
+    // Note: Services can depend on multiple ports (repositories, external services, event publishers, etc.)
+    // to orchestrate use cases. They coordinate between domain logic and infrastructure adapters via ports.
+}
+
+impl<R> UserService<R> {
+    /// Creates a new `UserService` instance, publishing no events.
+    pub fn new(user_repository: R) -> Self {
+        Self {
+            user_repository,
+            events: Arc::new(DisabledEventPublisher),
+            unit_of_work: None,
+            anomalies: None,
+            purges: Vec::new(),
+            passwords: Arc::new(DisabledPasswordHasher),
+            password_policy: PasswordPolicy::new(),
+            jobs: None,
+            email: Arc::new(DisabledEmailSender),
+            audit_log: None,
+        }
+    }
+
+    /// Publishes the events of the users to `events`.
+    pub fn with_event_publisher(mut self, events: Arc<dyn EventPublisherPort + Send + Sync + 'static>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Makes creations, updates and deletions in transactions of `unit_of_work`, recording their
+    /// event and audit entry in the same transaction, through its repositories rather than the
+    /// service's own. Their events are then recorded through the transactions rather than the
+    /// event publisher, and so are their audit entries rather than through the audit log.
+    pub fn with_unit_of_work(mut self, unit_of_work: Arc<dyn UnitOfWorkPort + Send + Sync + 'static>) -> Self {
+        self.unit_of_work = Some(unit_of_work);
+        self
+    }
+
+    /// Records successful creations and deletions in `anomalies`, which alerts on anomalous rates.
+    pub fn with_anomaly_detector(mut self, anomalies: Arc<MutationAnomalyDetector>) -> Self {
+        self.anomalies = Some(anomalies);
+        self
+    }
+
+    /// Purges the responses altered by successful changes through `purge`, in addition to the
+    /// purge ports already configured.
+    pub fn with_purge(mut self, purge: Arc<dyn PurgePort + Send + Sync + 'static>) -> Self {
+        self.purges.push(purge);
+        self
+    }
+
+    /// Hashes the passwords of users with `passwords`.
+    pub fn with_password_hasher(mut self, passwords: Arc<dyn PasswordHasherPort + Send + Sync + 'static>) -> Self {
+        self.passwords = passwords;
+        self
+    }
+
+    /// Refuses the passwords of users violating the rules of `password_policy`.
+    pub fn with_password_policy(mut self, password_policy: PasswordPolicy) -> Self {
+        self.password_policy = password_policy;
+        self
+    }
+
+    /// Enqueues the jobs following changes, such as the welcome e-mails of created users, in `jobs`.
+    pub fn with_job_queue(mut self, jobs: Arc<dyn JobQueuePort + Send + Sync + 'static>) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Sends the welcome e-mails of created users through `email` when no job queue is configured.
+    pub fn with_email_sender(mut self, email: Arc<dyn EmailSenderPort + Send + Sync + 'static>) -> Self {
+        self.email = email;
+        self
+    }
+
+    /// Records the changes of users, with their actor, in `audit_log`.
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLogPort + Send + Sync + 'static>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Takes the password of `user`, if any, and hashes it once checked against the password
+    /// policy, failing the creation of the user if it violates the policy or cannot be hashed.
+    async fn hash_password(&self, user: &mut CreateUser) -> Result<Option<PasswordHash>, UserDomainError> {
+        let Some(password) = user.password.take() else {
+            return Ok(None);
+        };
+        self.password_policy.check(&password, &[&user.name, user.email.as_str()]).await?;
+        self.passwords.hash(&password).await.map(Some).map_err(|e| {
+            tracing::error!("failed to hash password: {:#}", e);
+            UserDomainError::UserCreationFailed(StorageError::boxed(e.into()))
+        })
+    }
+
+    /// Purges the responses the change of `event` alters through every purge port.
+    fn purge_changed(&self, event: &UserEvent) {
+        if self.purges.is_empty() {
+            return;
+        }
+        let keys = surrogate_keys(event);
+        for purge in &self.purges {
+            purge.purge(keys.clone());
+        }
+    }
+
+    /// Records `mutation` in the anomaly detector, if any.
+    fn record_mutation(&self, mutation: UserMutation) {
+        if let Some(anomalies) = &self.anomalies {
+            anomalies.record(mutation);
+        }
+    }
+
+    /// Enqueues the welcome e-mail of `user`, or else sends it if e-mail is enabled, logging a
+    /// failure to do so.
+    async fn send_welcome_email(&self, user: &User) {
+        if let Some(jobs) = &self.jobs {
+            if let Err(e) = jobs.enqueue(welcome_email_job(user)).await {
+                tracing::error!(user.id = %user.id(), "failed to enqueue welcome e-mail: {:#}", e);
+            }
+        } else if self.email.is_enabled()
+            && let Err(e) = self.email.send(welcome_email(user.name(), user.email().as_str())).await
+        {
+            tracing::error!(user.id = %user.id(), "failed to send welcome e-mail: {:#}", e);
+        }
+    }
+
+    /// Records `action` on the user `id`, changing it from `before` to `after`, in the audit log,
+    /// if any, logging a failure to do so.
+    async fn audit(&self, id: UserId, action: AuditAction, before: Option<&User>, after: Option<&User>) {
+        if let Some(audit_log) = &self.audit_log
+            && let Err(e) = audit_log.record(AuditEntry::new(id, action, before, after)).await
+        {
+            tracing::error!(user.id = %id, audit.action = action.as_str(), "failed to record audit entry: {:#}", e);
+        }
+    }
+
+    /// Returns the user `id` as it is before a change, for the audit log, `None` without audit
+    /// log or when the user cannot be read, e.g. once soft-deleted.
+    async fn audited_user(&self, id: UserId) -> Option<User>
+    where
+        R: UserRepositoryPort + Send + Sync,
+    {
+        match &self.audit_log {
+            Some(_) => self.user_repository.get_user(id).await.ok(),
+            None => None,
+        }
+    }
+
+    /// Publishes `event`, logging a failure to do so.
+    async fn publish(&self, event: UserEvent) {
+        let event_type = event.event_type();
+        let user_id = event.user_id();
+        if let Err(e) = self.events.publish(event).await {
+            tracing::error!(event.r#type = event_type, user.id = %user_id, "failed to publish user event: {:#}", e);
+        }
+    }
+}
+
+/// Rejects operations destroying the user's data while the user is under legal hold.
+///
+/// Every destructive operation (deletion, and erasure or archival when they are added)
+/// must call this first, with the repository it changes the user through, so the hold is
+/// enforced regardless of the adapter.
+async fn ensure_not_under_legal_hold<U>(users: &U, id: UserId) -> Result<(), UserDomainError>
+where
+    U: UserRepositoryPort + Send + Sync + ?Sized,
+{
+    let user = users.get_user(id).await?;
+    if user.legal_hold() {
+        Err(UserDomainError::UserUnderLegalHold)
+    } else {
+        Ok(())
+    }
+}
+
+/// Rejects hard deletions of users under legal hold. Soft-deleted users cannot be placed
+/// under legal hold, so only users that are not are looked at.
+async fn ensure_hard_deletable<U>(users: &U, id: UserId) -> Result<(), UserDomainError>
+where
+    U: UserRepositoryPort + Send + Sync + ?Sized,
+{
+    match ensure_not_under_legal_hold(users, id).await {
+        Err(UserDomainError::UserNotFound) => Ok(()),
+        result => result,
+    }
+}
+
+/// The error of a failed change of users, caused by the failure of the storage.
+type Failure = fn(StorageError) -> UserDomainError;
+
+/// Begins a transaction of `unit_of_work` with the given isolation level, failing with
+/// `failure` if it cannot be begun.
+async fn begin(unit_of_work: &(dyn UnitOfWorkPort + Send + Sync), isolation: IsolationLevel, failure: Failure) -> Result<Box<dyn TransactionPort + Send + Sync>, UserDomainError> {
+    unit_of_work.begin(isolation).await.map_err(|e| {
+        tracing::error!("failed to begin transaction: {:#}", e);
+        failure(StorageError::boxed(e.into()))
+    })
+}
+
+/// Commits `transaction`, failing with `failure` if it cannot be.
+async fn commit(transaction: Box<dyn TransactionPort + Send + Sync>, failure: Failure) -> Result<(), UserDomainError> {
+    transaction.commit().await.map_err(|e| {
+        tracing::error!("failed to commit transaction: {:#}", e);
+        failure(StorageError::boxed(e.into()))
+    })
+}
+
+/// Records `event` in `transaction` and commits it, failing with `failure` (the change being
+/// discarded) if either fails.
+async fn commit_with_event(transaction: Box<dyn TransactionPort + Send + Sync>, event: UserEvent, failure: Failure) -> Result<(), UserDomainError> {
+    let event_type = event.event_type();
+    let user_id = event.user_id();
+    if let Err(e) = transaction.events().publish(event).await {
+        tracing::error!(event.r#type = event_type, user.id = %user_id, "failed to record user event: {:#}", e);
+        return Err(failure(StorageError::boxed(e.into())));
+    }
+    commit(transaction, failure).await
+}
+
+/// Returns the user `id` as read in `transaction` before a change, when the change is
+/// `audited`, like [`UserService::audited_user`].
+async fn audited_user_in(transaction: &(dyn TransactionPort + Send + Sync), audited: bool, id: UserId) -> Option<User> {
+    if !audited {
+        return None;
+    }
+    transaction.users().get_user(id).await.ok()
+}
+
+/// Records `action` on the user `id`, changing it from `before` to `after`, in the audit log of
+/// `transaction` when the change is `audited`, failing with `failure` (the change being
+/// discarded) if it cannot be.
+async fn audit_in(transaction: &(dyn TransactionPort + Send + Sync), audited: bool, failure: Failure, id: UserId, action: AuditAction, before: Option<&User>, after: Option<&User>) -> Result<(), UserDomainError> {
+    if !audited {
+        return Ok(());
+    }
+    transaction.audit_log().record(AuditEntry::new(id, action, before, after)).await.map_err(|e| {
+        tracing::error!(user.id = %id, audit.action = action.as_str(), "failed to record audit entry: {:#}", e);
+        failure(StorageError::boxed(e.into()))
+    })
+}
+
+/// Creates a user and records its event, and its audit entry when `audited`, in a single
+/// transaction.
+async fn create_user_atomically(unit_of_work: &(dyn UnitOfWorkPort + Send + Sync), user: CreateUser, password_hash: Option<PasswordHash>, audited: bool) -> Result<User, UserDomainError> {
+    let failure: Failure = UserDomainError::UserCreationFailed;
+    let transaction = begin(unit_of_work, IsolationLevel::ReadCommitted, failure).await?;
+    let user = transaction.users().create_user(user, password_hash).await?;
+    audit_in(transaction.as_ref(), audited, failure, user.id(), AuditAction::Created, None, Some(&user)).await?;
+    commit_with_event(transaction, UserEvent::UserCreated(user.clone()), failure).await?;
+    Ok(user)
+}
+
+/// Creates users and records their events, and their audit entries when `audited`, in a single
+/// serializable transaction. Every user fails when the transaction does, none of them being
+/// created then.
+async fn create_users_atomically(unit_of_work: &(dyn UnitOfWorkPort + Send + Sync), users: Vec<(CreateUser, Option<PasswordHash>)>, audited: bool) -> Vec<Result<User, UserDomainError>> {
+    let count = users.len();
+    let failed = |e: StorageError| vec![Err(UserDomainError::UserCreationFailed(e)); count];
+    let transaction = match unit_of_work.begin(IsolationLevel::Serializable).await {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            tracing::error!("failed to begin transaction: {:#}", e);
+            return failed(StorageError::boxed(e.into()));
+        }
+    };
+
+    let created = transaction.users().create_users(users).await;
+    // A failed insertion aborts the transaction, discarding the users inserted before it
+    if let Some(Err(e)) = created.iter().find(|result| matches!(result, Err(e) if *e != UserDomainError::UserAlreadyExists)) {
+        return failed(StorageError::new(e.clone()));
+    }
+    for user in created.iter().flatten() {
+        if let Err(e) = transaction.events().publish(UserEvent::UserCreated(user.clone())).await {
+            tracing::error!(event.r#type = "user.created", user.id = %user.id(), "failed to record user event: {:#}", e);
+            return failed(StorageError::boxed(e.into()));
+        }
+        if let Err(e) = audit_in(transaction.as_ref(), audited, UserDomainError::UserCreationFailed, user.id(), AuditAction::Created, None, Some(user)).await {
+            return vec![Err(e); count];
+        }
+    }
+    if let Err(e) = transaction.commit().await {
+        tracing::error!("failed to commit transaction: {:#}", e);
+        return failed(StorageError::boxed(e.into()));
+    }
+    created
+}
+
+/// Updates a user and records its event, and its audit entry when `audited`, in a single
+/// transaction.
+async fn update_user_atomically(unit_of_work: &(dyn UnitOfWorkPort + Send + Sync), user: UpdateUser, audited: bool) -> Result<User, UserDomainError> {
+    let failure: Failure = UserDomainError::UserUpdateFailed;
+    let transaction = begin(unit_of_work, IsolationLevel::ReadCommitted, failure).await?;
+    let before = audited_user_in(transaction.as_ref(), audited, user.id).await;
+    let user = transaction.users().update_user(user).await?;
+    audit_in(transaction.as_ref(), audited, failure, user.id(), AuditAction::Updated, before.as_ref(), Some(&user)).await?;
+    commit_with_event(transaction, UserEvent::UserUpdated(user.clone()), failure).await?;
+    Ok(user)
+}
+
+/// Deletes a user not under legal hold and records its event, and its audit entry when
+/// `audited`, in a single transaction, which is serializable so the user cannot be placed
+/// under legal hold once checked.
+async fn delete_user_atomically(unit_of_work: &(dyn UnitOfWorkPort + Send + Sync), id: UserId, audited: bool) -> Result<(), UserDomainError> {
+    let failure: Failure = UserDomainError::UserDeletionFailed;
+    let transaction = begin(unit_of_work, IsolationLevel::Serializable, failure).await?;
+    ensure_not_under_legal_hold(transaction.users(), id).await?;
+    let before = audited_user_in(transaction.as_ref(), audited, id).await;
+    transaction.users().delete_user(id).await?;
+    audit_in(transaction.as_ref(), audited, failure, id, AuditAction::Deleted, before.as_ref(), None).await?;
+    commit_with_event(transaction, UserEvent::UserDeleted(id), failure).await
+}
+
+/// Restores a soft-deleted user and records its event, and its audit entry when `audited`, in a
+/// single transaction.
+async fn restore_user_atomically(unit_of_work: &(dyn UnitOfWorkPort + Send + Sync), id: UserId, audited: bool) -> Result<User, UserDomainError> {
+    let failure: Failure = UserDomainError::UserUpdateFailed;
+    let transaction = begin(unit_of_work, IsolationLevel::ReadCommitted, failure).await?;
+    let user = transaction.users().restore_user(id).await?;
+    audit_in(transaction.as_ref(), audited, failure, id, AuditAction::Restored, None, Some(&user)).await?;
+    commit_with_event(transaction, UserEvent::UserRestored(user.clone()), failure).await?;
+    Ok(user)
+}
+
+/// Hard-deletes a user not under legal hold and records its event, and its audit entry when
+/// `audited`, in a single serializable transaction, like [`delete_user_atomically`].
+async fn hard_delete_user_atomically(unit_of_work: &(dyn UnitOfWorkPort + Send + Sync), id: UserId, audited: bool) -> Result<(), UserDomainError> {
+    let failure: Failure = UserDomainError::UserDeletionFailed;
+    let transaction = begin(unit_of_work, IsolationLevel::Serializable, failure).await?;
+    ensure_hard_deletable(transaction.users(), id).await?;
+    let before = audited_user_in(transaction.as_ref(), audited, id).await;
+    transaction.users().hard_delete_user(id).await?;
+    audit_in(transaction.as_ref(), audited, failure, id, AuditAction::HardDeleted, before.as_ref(), None).await?;
+    commit_with_event(transaction, UserEvent::UserHardDeleted(id), failure).await
+}
+
+/// Places a user under legal hold, or lifts the hold, and records its audit entry when
+/// `audited` in a single transaction. Legal holds publish no event.
+async fn set_legal_hold_atomically(unit_of_work: &(dyn UnitOfWorkPort + Send + Sync), id: UserId, legal_hold: bool, audited: bool) -> Result<User, UserDomainError> {
+    let failure: Failure = UserDomainError::UserUpdateFailed;
+    let transaction = begin(unit_of_work, IsolationLevel::ReadCommitted, failure).await?;
+    let before = audited_user_in(transaction.as_ref(), audited, id).await;
+    let user = transaction.users().set_legal_hold(id, legal_hold).await?;
+    audit_in(transaction.as_ref(), audited, failure, id, AuditAction::Updated, before.as_ref(), Some(&user)).await?;
+    commit(transaction, failure).await?;
+    Ok(user)
+}
+
+/// Sets the role of a user and records its audit entry when `audited` in a single transaction.
+/// Changes of role publish no event.
+async fn set_role_atomically(unit_of_work: &(dyn UnitOfWorkPort + Send + Sync), id: UserId, role: Role, audited: bool) -> Result<User, UserDomainError> {
+    let failure: Failure = UserDomainError::UserUpdateFailed;
+    let transaction = begin(unit_of_work, IsolationLevel::ReadCommitted, failure).await?;
+    let before = audited_user_in(transaction.as_ref(), audited, id).await;
+    let user = transaction.users().set_role(id, role).await?;
+    audit_in(transaction.as_ref(), audited, failure, id, AuditAction::Updated, before.as_ref(), Some(&user)).await?;
+    commit(transaction, failure).await?;
+    Ok(user)
+}
+
+#[async_trait]
+impl<R> UserServiceTrait for UserService<R>
+where
+    R: UserRepositoryPort + Send + Sync,
+{
+    /// Creates a new user by delegating to the repository, once the user's password is checked
+    /// and hashed.
+    #[tracing::instrument(name = "user_service.create_user", skip_all, fields(user.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn create_user(&self, mut user: CreateUser) -> Result<User, UserDomainError> {
+        let record_id = |user: &User| {
+            tracing::Span::current().record("user.id", tracing::field::display(user.id()));
+        };
+        let password_hash = record_outcome(self.hash_password(&mut user).await)?;
+        let user = if let Some(unit_of_work) = &self.unit_of_work {
+            record_outcome(create_user_atomically(unit_of_work.as_ref(), user, password_hash, self.audit_log.is_some()).await).inspect(record_id)?
+        } else {
+            let user = record_outcome(self.user_repository.create_user(user, password_hash).await).inspect(record_id)?;
+            self.publish(UserEvent::UserCreated(user.clone())).await;
+            self.audit(user.id(), AuditAction::Created, None, Some(&user)).await;
+            user
+        };
+        self.purge_changed(&UserEvent::UserCreated(user.clone()));
+        self.record_mutation(UserMutation::Creation);
+        self.send_welcome_email(&user).await;
+        Ok(user)
+    }
+
+    /// Creates users by delegating to the repository in a single call, once their passwords are
+    /// checked and hashed. Users whose password violates the password policy or fails to be
+    /// hashed are left out of the call.
+    #[tracing::instrument(name = "user_service.create_users_bulk", skip_all, fields(users = users.len(), created = tracing::field::Empty))]
+    async fn create_users_bulk(&self, users: Vec<CreateUser>) -> Vec<Result<User, UserDomainError>> {
+        let mut hashed = Vec::with_capacity(users.len());
+        let mut hash_failures = Vec::with_capacity(users.len());
+        for mut user in users {
+            match self.hash_password(&mut user).await {
+                Ok(password_hash) => {
+                    hashed.push((user, password_hash));
+                    hash_failures.push(None);
+                }
+                Err(e) => hash_failures.push(Some(e)),
+            }
+        }
+
+        let created = if hashed.is_empty() {
+            Vec::new()
+        } else if let Some(unit_of_work) = &self.unit_of_work {
+            create_users_atomically(unit_of_work.as_ref(), hashed, self.audit_log.is_some()).await
+        } else {
+            let created = self.user_repository.create_users(hashed).await;
+            for user in created.iter().flatten() {
+                self.publish(UserEvent::UserCreated(user.clone())).await;
+                self.audit(user.id(), AuditAction::Created, None, Some(user)).await;
+            }
+            created
+        };
+
+        let mut created = created.into_iter();
+        let results: Vec<Result<User, UserDomainError>> = hash_failures
+            .into_iter()
+            .map(|failure| match failure {
+                Some(e) => Err(e),
+                None => created.next().unwrap_or_else(|| Err(UserDomainError::UserCreationFailed(StorageError::msg("no result for the user")))),
+            })
+            .collect();
+        for user in results.iter().flatten() {
+            self.purge_changed(&UserEvent::UserCreated(user.clone()));
+            self.record_mutation(UserMutation::Creation);
+            self.send_welcome_email(user).await;
+        }
+        tracing::Span::current().record("created", results.iter().flatten().count());
+        results
+    }
+    
+    /// Retrieves a user by ID by delegating to the repository.
+    #[tracing::instrument(name = "user_service.get_user", skip_all, fields(user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        record_outcome(self.user_repository.get_user(id).await)
+    }
+
+    /// Retrieves a user by email address by delegating to the repository.
+    #[tracing::instrument(name = "user_service.get_user_by_email", skip_all, fields(outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user_by_email(&self, email: Email) -> Result<User, UserDomainError> {
+        record_outcome(self.user_repository.get_user_by_email(email).await)
+    }
+
+    /// Verifies the password against the hash stored by the repository for the email. Unknown
+    /// emails, users without a password and hashes that cannot be read are all reported as
+    /// invalid credentials, after the same amount of work.
+    #[tracing::instrument(name = "user_service.verify_credentials", skip_all, fields(user.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn verify_credentials(&self, email: Email, password: String) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let (user, password_hash) = match self.user_repository.get_user_credentials(email).await {
+                Ok(UserCredentials { user, password_hash }) => (Some(user), password_hash),
+                Err(UserDomainError::UserNotFound) => (None, None),
+                Err(e) => return Err(e),
+            };
+            let verified = self.passwords.verify(&password, password_hash.as_ref()).await.unwrap_or_else(|e| {
+                tracing::error!("failed to verify password: {:#}", e);
+                false
+            });
+            match user {
+                Some(user) if verified => {
+                    tracing::Span::current().record("user.id", tracing::field::display(user.id()));
+                    Ok(user)
+                }
+                _ => Err(UserDomainError::InvalidCredentials),
+            }
+        }
+        .await)
+    }
+
+    /// Lists a page of users by delegating to the repository.
+    #[tracing::instrument(name = "user_service.list_users", skip_all, fields(page.limit = query.limit, page.offset = query.offset, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_users(&self, query: ListUsers) -> Result<UserPage, UserDomainError> {
+        record_outcome(self.user_repository.list_users(query).await)
+    }
+
+    /// Searches users by delegating to the repository.
+    #[tracing::instrument(name = "user_service.search_users", skip_all, fields(page.limit = filter.limit, page.offset = filter.offset, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn search_users(&self, filter: UserFilter) -> Result<UserPage, UserDomainError> {
+        record_outcome(self.user_repository.search_users(filter).await)
+    }
+
+    /// Counts the users by delegating to the repository.
+    #[tracing::instrument(name = "user_service.count_user_facets", skip_all, fields(outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError> {
+        record_outcome(self.user_repository.count_user_facets().await)
+    }
+    
+    /// Updates an existing user by delegating to the repository.
+    #[tracing::instrument(name = "user_service.update_user", skip_all, fields(user.id = %user.id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn update_user(&self, user: UpdateUser) -> Result<User, UserDomainError> {
+        let user = if let Some(unit_of_work) = &self.unit_of_work {
+            record_outcome(update_user_atomically(unit_of_work.as_ref(), user, self.audit_log.is_some()).await)?
+        } else {
+            let before = self.audited_user(user.id).await;
+            let user = record_outcome(self.user_repository.update_user(user).await)?;
+            self.publish(UserEvent::UserUpdated(user.clone())).await;
+            self.audit(user.id(), AuditAction::Updated, before.as_ref(), Some(&user)).await;
+            user
+        };
+        self.purge_changed(&UserEvent::UserUpdated(user.clone()));
+        Ok(user)
+    }
+    
+    /// Soft-deletes a user by ID by delegating to the repository, once the user is known not to be under legal hold.
+    #[tracing::instrument(name = "user_service.delete_user", skip_all, fields(user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        if let Some(unit_of_work) = &self.unit_of_work {
+            record_outcome(delete_user_atomically(unit_of_work.as_ref(), id, self.audit_log.is_some()).await)?;
+        } else {
+            let before = self.audited_user(id).await;
+            record_outcome(async {
+                ensure_not_under_legal_hold(&self.user_repository, id).await?;
+                self.user_repository.delete_user(id).await
+            }
+            .await)?;
+            self.publish(UserEvent::UserDeleted(id)).await;
+            self.audit(id, AuditAction::Deleted, before.as_ref(), None).await;
+        }
+        self.purge_changed(&UserEvent::UserDeleted(id));
+        self.record_mutation(UserMutation::Deletion);
+        Ok(())
+    }
+
+    /// Restores a soft-deleted user by ID by delegating to the repository.
+    #[tracing::instrument(name = "user_service.restore_user", skip_all, fields(user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn restore_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        let user = if let Some(unit_of_work) = &self.unit_of_work {
+            record_outcome(restore_user_atomically(unit_of_work.as_ref(), id, self.audit_log.is_some()).await)?
+        } else {
+            let user = record_outcome(self.user_repository.restore_user(id).await)?;
+            self.publish(UserEvent::UserRestored(user.clone())).await;
+            self.audit(id, AuditAction::Restored, None, Some(&user)).await;
+            user
+        };
+        self.purge_changed(&UserEvent::UserRestored(user.clone()));
+        Ok(user)
+    }
+
+    /// Hard-deletes a user by ID by delegating to the repository, once the user is known not to be under legal hold.
+    #[tracing::instrument(name = "user_service.hard_delete_user", skip_all, fields(user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn hard_delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        if let Some(unit_of_work) = &self.unit_of_work {
+            record_outcome(hard_delete_user_atomically(unit_of_work.as_ref(), id, self.audit_log.is_some()).await)?;
+        } else {
+            let before = self.audited_user(id).await;
+            record_outcome(async {
+                ensure_hard_deletable(&self.user_repository, id).await?;
+                self.user_repository.hard_delete_user(id).await
+            }
+            .await)?;
+            self.publish(UserEvent::UserHardDeleted(id)).await;
+            self.audit(id, AuditAction::HardDeleted, before.as_ref(), None).await;
+        }
+        self.purge_changed(&UserEvent::UserHardDeleted(id));
+        self.record_mutation(UserMutation::Deletion);
+        Ok(())
+    }
+
+    /// Places a user under legal hold, or lifts the hold, by delegating to the repository.
+    #[tracing::instrument(name = "user_service.set_legal_hold", skip_all, fields(user.id = %id, legal_hold = legal_hold, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn set_legal_hold(&self, id: UserId, legal_hold: bool) -> Result<User, UserDomainError> {
+        if let Some(unit_of_work) = &self.unit_of_work {
+            record_outcome(set_legal_hold_atomically(unit_of_work.as_ref(), id, legal_hold, self.audit_log.is_some()).await)
+        } else {
+            let before = self.audited_user(id).await;
+            let user = record_outcome(self.user_repository.set_legal_hold(id, legal_hold).await)?;
+            self.audit(id, AuditAction::Updated, before.as_ref(), Some(&user)).await;
+            Ok(user)
+        }
+    }
+
+    /// Sets the role of a user by delegating to the repository. Changes are logged at `info`, so
+    /// they are kept whatever the sampling of request logs.
+    #[tracing::instrument(name = "user_service.set_role", skip_all, fields(user.id = %id, role = %role, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn set_role(&self, id: UserId, role: Role) -> Result<User, UserDomainError> {
+        let user = if let Some(unit_of_work) = &self.unit_of_work {
+            record_outcome(set_role_atomically(unit_of_work.as_ref(), id, role, self.audit_log.is_some()).await)?
+        } else {
+            let before = self.audited_user(id).await;
+            let user = record_outcome(self.user_repository.set_role(id, role).await)?;
+            self.audit(id, AuditAction::Updated, before.as_ref(), Some(&user)).await;
+            user
+        };
+        tracing::info!("user role changed");
+        Ok(user)
+    }
+}
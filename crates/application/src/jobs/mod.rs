@@ -0,0 +1,94 @@
+//! Background jobs: work done after a request is answered, retried until it succeeds.
+//!
+//! Services enqueue jobs through the [`JobQueuePort`], and a worker of the infrastructure
+//! claims them and runs them with the [`JobHandler`] registered for their kind in [`JobHandlers`].
+
+pub mod welcome_email;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// A job to run, as enqueued by a service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewJob {
+    /// Kind of the job, naming the [`JobHandler`] running it, e.g. `user.welcome_email`.
+    pub kind: String,
+    /// Input of the handler.
+    pub payload: Value,
+}
+
+impl NewJob {
+    /// Creates a job of `kind` with `payload`.
+    pub fn new(kind: impl Into<String>, payload: Value) -> Self {
+        Self { kind: kind.into(), payload }
+    }
+}
+
+/// A job claimed by a worker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub id: i64,
+    pub kind: String,
+    pub payload: Value,
+    /// Number of times the job was claimed, this one included.
+    pub attempts: u32,
+}
+
+/// Port of the queue holding the jobs until they succeed.
+///
+/// Jobs are claimed for a lease: a job neither completed nor failed before its lease expires,
+/// e.g. because its worker crashed, is claimed again, so jobs run at least once and handlers
+/// must be idempotent.
+#[async_trait]
+pub trait JobQueuePort {
+    /// Enqueues `job`, to run as soon as a worker is available.
+    async fn enqueue(&self, job: NewJob) -> eyre::Result<()>;
+
+    /// Claims up to `limit` jobs due to run, oldest first, for `lease`.
+    async fn claim(&self, limit: usize, lease: Duration) -> eyre::Result<Vec<Job>>;
+
+    /// Removes the job `id`, which succeeded.
+    async fn complete(&self, id: i64) -> eyre::Result<()>;
+
+    /// Records the failure of the job `id` with `error`, running it again after `retry_in`,
+    /// or never when `None`: the job is then kept for inspection.
+    async fn fail(&self, id: i64, error: &str, retry_in: Option<Duration>) -> eyre::Result<()>;
+}
+
+/// Runs the jobs of a kind.
+#[async_trait]
+pub trait JobHandler {
+    /// Kind of the jobs run by the handler.
+    fn kind(&self) -> &'static str;
+
+    /// Runs the job with `payload`. Errors are retried by the worker.
+    async fn run(&self, payload: &Value) -> eyre::Result<()>;
+}
+
+/// The handlers of the jobs, by kind.
+#[derive(Clone, Default)]
+pub struct JobHandlers {
+    handlers: HashMap<&'static str, Arc<dyn JobHandler + Send + Sync>>,
+}
+
+impl JobHandlers {
+    /// Creates an empty set of handlers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `handler` for the jobs of its kind, replacing the handler of that kind if any.
+    pub fn with(mut self, handler: Arc<dyn JobHandler + Send + Sync>) -> Self {
+        self.handlers.insert(handler.kind(), handler);
+        self
+    }
+
+    /// Returns the handler of the jobs of `kind`, if any.
+    pub fn get(&self, kind: &str) -> Option<&Arc<dyn JobHandler + Send + Sync>> {
+        self.handlers.get(kind)
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use eyre::eyre;
+use serde_json::{json, Value};
+
+use domain::user::model::User;
+
+use crate::jobs::{JobHandler, NewJob};
+use crate::ports::email::{EmailMessage, EmailSenderPort};
+
+/// Kind of the jobs sending the welcome e-mail of a user.
+pub const WELCOME_EMAIL_JOB: &str = "user.welcome_email";
+
+/// Returns the job sending the welcome e-mail of `user`, enqueued once the user is created.
+pub fn welcome_email_job(user: &User) -> NewJob {
+    NewJob::new(WELCOME_EMAIL_JOB, json!({ "name": user.name(), "email": user.email().as_str() }))
+}
+
+/// Returns the welcome e-mail of the user named `name`, sent to `to`.
+pub fn welcome_email(name: &str, to: &str) -> EmailMessage {
+    EmailMessage {
+        to: to.to_string(),
+        subject: "Welcome".to_string(),
+        body: format!("Hello {},\n\nYour account was created.\n", name),
+    }
+}
+
+/// Sends the welcome e-mail of the users created.
+///
+/// Without a mail transport, the e-mail is logged instead of sent, so the job succeeds.
+pub struct WelcomeEmailJob {
+    email: Arc<dyn EmailSenderPort + Send + Sync + 'static>,
+}
+
+impl WelcomeEmailJob {
+    /// Creates a new `WelcomeEmailJob` sending e-mails through `email`.
+    pub fn new(email: Arc<dyn EmailSenderPort + Send + Sync + 'static>) -> Self {
+        Self { email }
+    }
+}
+
+#[async_trait]
+impl JobHandler for WelcomeEmailJob {
+    fn kind(&self) -> &'static str {
+        WELCOME_EMAIL_JOB
+    }
+
+    async fn run(&self, payload: &Value) -> eyre::Result<()> {
+        let to = payload["email"].as_str().ok_or_else(|| eyre!("welcome e-mail job without email"))?;
+        let name = payload["name"].as_str().unwrap_or_default();
+        let message = welcome_email(name, to);
+        if !self.email.is_enabled() {
+            tracing::info!(email.subject = %message.subject, "e-mail is disabled, welcome e-mail not sent");
+            return Ok(());
+        }
+        self.email.send(message).await
+    }
+}
@@ -0,0 +1,5 @@
+pub mod flows;
+pub mod jobs;
+pub mod error;
+pub mod dto;
+pub mod ports;
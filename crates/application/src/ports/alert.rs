@@ -0,0 +1,16 @@
+/// A condition requiring the attention of operators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    /// Stable name of the condition, e.g. `user_mutation_rate`, for routing and deduplication.
+    pub name: &'static str,
+    /// Description of the condition, without personal data.
+    pub message: String,
+}
+
+/// Port for raising alerts to operators (e.g. through logs, a pager or a chat channel).
+///
+/// Raising is fire-and-forget: adapters must not block the caller, as alerts are raised while
+/// requests are handled.
+pub trait AlertPort {
+    fn raise(&self, alert: Alert);
+}
@@ -0,0 +1,188 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use domain::user::model::{User, UserId};
+
+/// A change of a user recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Created,
+    Updated,
+    /// Soft-deleted, the user can still be restored.
+    Deleted,
+    Restored,
+    /// Deleted for good.
+    HardDeleted,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Created => "created",
+            AuditAction::Updated => "updated",
+            AuditAction::Deleted => "deleted",
+            AuditAction::Restored => "restored",
+            AuditAction::HardDeleted => "hard_deleted",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "created" => Some(AuditAction::Created),
+            "updated" => Some(AuditAction::Updated),
+            "deleted" => Some(AuditAction::Deleted),
+            "restored" => Some(AuditAction::Restored),
+            "hard_deleted" => Some(AuditAction::HardDeleted),
+            _ => None,
+        }
+    }
+}
+
+/// A field of a user changed by an action, with its values before and after the action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    /// Value before the action, `None` when the user did not exist or could not be read, e.g.
+    /// before its creation.
+    pub before: Option<Value>,
+    /// Value after the action, `None` when the user no longer exists, e.g. after its deletion.
+    pub after: Option<Value>,
+}
+
+/// Fields of a user recorded in the audit log. Passwords are left out.
+const AUDITED_FIELDS: [&str; 5] = ["name", "email", "age", "role", "legal_hold"];
+
+/// Returns the values of the [`AUDITED_FIELDS`] of `user`.
+fn audited_values(user: &User) -> [Value; 5] {
+    [json!(user.name()), json!(user.email().as_str()), json!(user.age()), json!(user.role().as_str()), json!(user.legal_hold())]
+}
+
+/// Returns the fields of the user that differ between `before` and `after`, in the order of the
+/// user's fields. Every field of a user missing on one side differs.
+pub fn diff(before: Option<&User>, after: Option<&User>) -> Vec<FieldChange> {
+    let before = before.map(audited_values);
+    let after = after.map(audited_values);
+    AUDITED_FIELDS
+        .iter()
+        .enumerate()
+        .filter_map(|(i, field)| {
+            let before = before.as_ref().map(|values| values[i].clone());
+            let after = after.as_ref().map(|values| values[i].clone());
+            (before != after).then(|| FieldChange { field: field.to_string(), before, after })
+        })
+        .collect()
+}
+
+/// An entry of the audit log: an action made on a user, by whom, when, and what it changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub user_id: UserId,
+    /// Id of the user who made the change: the admin when impersonating another user, or the
+    /// credential of the caller when it identifies no user, e.g. the admin token. `None` when
+    /// the change was not made by an authenticated caller, e.g. a signup.
+    pub actor: Option<String>,
+    /// Id of the user the actor impersonated when making the change, `None` when the actor
+    /// acted as themselves.
+    pub impersonated_user_id: Option<String>,
+    pub action: AuditAction,
+    pub changes: Vec<FieldChange>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl AuditEntry {
+    /// Creates the entry of `action` on the user `user_id`, made now by the actor of the current
+    /// task (see [`AUDIT_ACTOR`]), changing the user from `before` to `after`.
+    pub fn new(user_id: UserId, action: AuditAction, before: Option<&User>, after: Option<&User>) -> Self {
+        let actor = AUDIT_ACTOR.try_with(AuditActor::recorded).ok().flatten();
+        Self {
+            user_id,
+            actor: actor.as_ref().map(|actor| actor.id.clone()),
+            impersonated_user_id: actor.and_then(|actor| actor.impersonated_user_id),
+            action,
+            changes: diff(before, after),
+            recorded_at: Utc::now(),
+        }
+    }
+}
+
+/// Port of the audit log of the changes of users, kept after the users are deleted.
+#[async_trait]
+pub trait AuditLogPort {
+    /// Appends `entry` to the log.
+    async fn record(&self, entry: AuditEntry) -> eyre::Result<()>;
+
+    /// Returns the entries of the user `user_id`, oldest first.
+    async fn history(&self, user_id: UserId) -> eyre::Result<Vec<AuditEntry>>;
+}
+
+/// Audit log of the transactions of stores keeping none: entries are dropped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledAuditLog;
+
+#[async_trait]
+impl AuditLogPort for DisabledAuditLog {
+    async fn record(&self, _entry: AuditEntry) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    async fn history(&self, _user_id: UserId) -> eyre::Result<Vec<AuditEntry>> {
+        Ok(Vec::new())
+    }
+}
+
+/// The user making the changes of a task, known once the task is authenticated.
+#[derive(Debug, Clone, Default)]
+pub struct AuditActor(Arc<Mutex<Option<RecordedActor>>>);
+
+/// An actor recorded by [`AuditActor::record`].
+#[derive(Debug, Clone)]
+struct RecordedActor {
+    id: String,
+    impersonated_user_id: Option<String>,
+}
+
+impl AuditActor {
+    /// Records `actor_id` as the user making the changes, on behalf of `impersonated_user_id`
+    /// when impersonating them.
+    pub fn record(&self, actor_id: &str, impersonated_user_id: Option<&str>) {
+        let actor = RecordedActor { id: actor_id.to_string(), impersonated_user_id: impersonated_user_id.map(str::to_string) };
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(actor);
+    }
+
+    /// Returns the id of the user making the changes, `None` until recorded.
+    pub fn id(&self) -> Option<String> {
+        self.recorded().map(|actor| actor.id)
+    }
+
+    /// Returns the id of the user impersonated by the actor, `None` until recorded or when the
+    /// actor acts as themselves.
+    pub fn impersonated_user_id(&self) -> Option<String> {
+        self.recorded().and_then(|actor| actor.impersonated_user_id)
+    }
+
+    fn recorded(&self) -> Option<RecordedActor> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+tokio::task_local! {
+    /// The actor of the request currently being handled, set by the HTTP layer and the RPC
+    /// servers so that the changes made by the service are recorded with their actor.
+    pub static AUDIT_ACTOR: AuditActor;
+}
+
+/// Records `actor_id` as the actor of the current task, impersonating `impersonated_user_id`
+/// if any. Does nothing outside of an [`AUDIT_ACTOR`] scope.
+pub fn record_actor(actor_id: &str, impersonated_user_id: Option<&str>) {
+    let _ = AUDIT_ACTOR.try_with(|actor| actor.record(actor_id, impersonated_user_id));
+}
+
+/// Returns the actor of the current task, `None` outside of an [`AUDIT_ACTOR`] scope or until
+/// recorded.
+pub fn current_actor() -> Option<String> {
+    AUDIT_ACTOR.try_with(AuditActor::id).ok().flatten()
+}
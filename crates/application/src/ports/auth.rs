@@ -0,0 +1,207 @@
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+
+use domain::outcome::ErrorClass;
+
+/// Reasons an authentication attempt fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// The username and password do not match a user.
+    InvalidCredentials,
+    /// The access token is malformed, forged or expired.
+    InvalidToken,
+    /// The access token is signed by a key missing from the key set of its issuer, even after
+    /// fetching the key set again.
+    UnknownSigningKey,
+    /// The key set validating the access token could not be fetched from its issuer.
+    KeySetUnavailable,
+    /// The credentials are valid, but the user registered a passkey and must log in with it.
+    PasskeyRequired,
+    /// Authentication could not be performed, e.g. because it is not configured.
+    Unavailable,
+}
+
+impl ErrorClass for AuthError {
+    fn class(&self) -> &'static str {
+        match self {
+            AuthError::InvalidCredentials => "invalid_credentials",
+            AuthError::InvalidToken => "invalid_token",
+            AuthError::UnknownSigningKey => "unknown_signing_key",
+            AuthError::KeySetUnavailable => "key_set_unavailable",
+            AuthError::PasskeyRequired => "passkey_required",
+            AuthError::Unavailable => "unavailable",
+        }
+    }
+}
+
+/// Scope of the tokens allowed to update and delete users.
+pub const USERS_WRITE_SCOPE: &str = "users:write";
+
+/// Scope of the tokens allowed to record consents.
+pub const CONSENTS_WRITE_SCOPE: &str = "consents:write";
+
+/// Scope of the tokens allowed to register, list and delete passkeys.
+pub const PASSKEYS_SCOPE: &str = "passkeys";
+
+/// Scope of the tokens allowed to approve or deny devices.
+pub const DEVICES_SCOPE: &str = "devices";
+
+/// Scopes known to the server, all granted to the tokens of users unless fewer are requested.
+pub const ALL_SCOPES: &[&str] = &[USERS_WRITE_SCOPE, CONSENTS_WRITE_SCOPE, PASSKEYS_SCOPE, DEVICES_SCOPE];
+
+/// Returns every scope known to the server.
+pub fn all_scopes() -> Vec<String> {
+    ALL_SCOPES.iter().map(|scope| scope.to_string()).collect()
+}
+
+/// Parses a space-delimited list of scopes (RFC 6749, section 3.3), rejecting unknown scopes.
+pub fn parse_scopes(value: &str) -> Result<Vec<String>, String> {
+    let mut scopes: Vec<String> = Vec::new();
+    for scope in value.split_ascii_whitespace() {
+        if !ALL_SCOPES.contains(&scope) {
+            return Err(format!("unknown scope {}", scope));
+        }
+        if !scopes.iter().any(|parsed| parsed == scope) {
+            scopes.push(scope.to_string());
+        }
+    }
+    Ok(scopes)
+}
+
+/// A signed access token issued after a successful login.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessToken {
+    pub token: String,
+    /// Lifetime of the token from the time it was issued.
+    pub expires_in: Duration,
+}
+
+/// The identity an access token authenticates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    /// Id of the user the request acts as.
+    pub user_id: String,
+    /// Id of the admin acting on behalf of the user, for impersonation tokens.
+    pub actor_id: Option<String>,
+    /// Roles the user was granted when logging in.
+    pub roles: Vec<String>,
+    /// Scopes of the token, limiting the routes it grants access to.
+    pub scopes: Vec<String>,
+    /// Time the token was issued at.
+    pub issued_at: SystemTime,
+    /// Time the token expires at.
+    pub expires_at: SystemTime,
+}
+
+/// The user identified by a successful authentication.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Authentication {
+    /// Id of the authenticated user.
+    pub user_id: String,
+    /// Roles granted to the user by the authenticator (e.g. mapped from directory groups).
+    pub roles: Vec<String>,
+}
+
+/// Port issuing and validating access tokens.
+pub trait TokenPort {
+    /// Issues a token identifying the user with the given id and roles, limited to `scopes`.
+    fn issue(&self, user_id: &str, roles: &[String], scopes: &[String]) -> Result<AccessToken, AuthError>;
+
+    /// Issues a token letting the admin `actor_id` act on behalf of the user `subject_id`
+    /// for `ttl`, regardless of the lifetime of regular tokens. The token grants no roles,
+    /// and every scope.
+    fn issue_impersonation(&self, actor_id: &str, subject_id: &str, ttl: Duration) -> Result<AccessToken, AuthError>;
+
+    /// Validates a token, returning the identity it authenticates.
+    fn verify(&self, token: &str) -> Result<Principal, AuthError>;
+}
+
+/// Port validating the access tokens issued by an external identity provider, the server
+/// acting as a resource server.
+#[async_trait]
+pub trait ExternalTokenPort {
+    /// Returns whether `token` claims to be issued by the provider. The claim is not verified.
+    fn issued_by_provider(&self, token: &str) -> bool;
+
+    /// Validates a token of the provider against its keys, returning the identity it authenticates.
+    async fn verify(&self, token: &str) -> Result<Principal, AuthError>;
+}
+
+/// Public key validating access tokens, published as a JSON Web Key (RFC 7517): a P-256 key of
+/// the ES256 algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicSigningKey {
+    /// Id of the key, matching the `kid` header of the tokens it signed.
+    pub kid: String,
+    /// Base64url-encoded x coordinate of the public point.
+    pub x: String,
+    /// Base64url-encoded y coordinate of the public point.
+    pub y: String,
+}
+
+/// Port publishing the public keys validating access tokens, for other services to validate
+/// them without calling the server.
+pub trait KeySetPort {
+    /// Returns the keys of the tokens currently valid, and of the tokens about to be issued.
+    fn public_keys(&self) -> Vec<PublicSigningKey>;
+}
+
+/// Port checking user credentials, against the user store or an external directory.
+#[async_trait]
+pub trait AuthenticatorPort {
+    /// Returns the user with the given username (e.g. email) and password, with its roles.
+    async fn authenticate(&self, username: String, password: String) -> Result<Authentication, AuthError>;
+}
+
+/// The user asserted by an identity provider after single sign-on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamlIdentity {
+    /// Email of the user, matched against the email of local users.
+    pub email: String,
+    /// Roles granted to the user by the identity provider.
+    pub roles: Vec<String>,
+}
+
+/// Port of a SAML 2.0 service provider, delegating authentication to an identity provider.
+pub trait SamlServiceProviderPort {
+    /// Returns the metadata document describing the service provider to the identity provider.
+    fn metadata(&self) -> String;
+
+    /// Returns the URL of the identity provider starting a login (HTTP-Redirect binding).
+    fn login_url(&self) -> Result<String, AuthError>;
+
+    /// Validates a base64-encoded `SAMLResponse` posted by the identity provider, returning the
+    /// user it asserts.
+    fn consume(&self, response: &str) -> Result<SamlIdentity, AuthError>;
+}
+
+/// Token port used when no signing secret is configured. Tokens are neither issued nor
+/// accepted, so authenticated routes reject every request.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledTokens;
+
+impl TokenPort for DisabledTokens {
+    fn issue(&self, _user_id: &str, _roles: &[String], _scopes: &[String]) -> Result<AccessToken, AuthError> {
+        Err(AuthError::Unavailable)
+    }
+
+    fn issue_impersonation(&self, _actor_id: &str, _subject_id: &str, _ttl: Duration) -> Result<AccessToken, AuthError> {
+        Err(AuthError::Unavailable)
+    }
+
+    fn verify(&self, _token: &str) -> Result<Principal, AuthError> {
+        Err(AuthError::InvalidToken)
+    }
+}
+
+/// Authenticator used while users have no credentials to check. Every login is rejected.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledAuthenticator;
+
+#[async_trait]
+impl AuthenticatorPort for DisabledAuthenticator {
+    async fn authenticate(&self, _username: String, _password: String) -> Result<Authentication, AuthError> {
+        Err(AuthError::InvalidCredentials)
+    }
+}
@@ -0,0 +1,8 @@
+use async_trait::async_trait;
+
+/// Port for looking up passwords in the lists of passwords leaked by data breaches.
+#[async_trait]
+pub trait BreachedPasswordsPort {
+    /// Returns how many times `password` appears in the breaches, 0 if it never does.
+    async fn breach_count(&self, password: &str) -> eyre::Result<u64>;
+}
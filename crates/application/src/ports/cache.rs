@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::ports::capability::Capability;
+
+/// Port for a shared key-value cache.
+#[async_trait]
+pub trait CachePort: Capability {
+    /// Returns the cached value, or `None` on a miss.
+    async fn get(&self, key: &str) -> eyre::Result<Option<Vec<u8>>>;
+
+    /// Stores a value that expires after `ttl`.
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> eyre::Result<()>;
+
+    /// Removes a value, if present.
+    async fn delete(&self, key: &str) -> eyre::Result<()>;
+}
+
+/// Cache used when no cache is configured: every lookup misses and writes are discarded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledCache;
+
+impl Capability for DisabledCache {
+    fn name(&self) -> &'static str {
+        "cache"
+    }
+
+    fn is_enabled(&self) -> bool {
+        false
+    }
+}
+
+#[async_trait]
+impl CachePort for DisabledCache {
+    async fn get(&self, _key: &str) -> eyre::Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    async fn set(&self, _key: &str, _value: Vec<u8>, _ttl: Duration) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    async fn delete(&self, _key: &str) -> eyre::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::ports::{
+    cache::{CachePort, DisabledCache},
+    email::{DisabledEmailSender, EmailSenderPort},
+    error_reporter::{DisabledErrorReporter, ErrorReporterPort},
+    messaging::{DisabledMessagePublisher, MessagePublisherPort},
+};
+
+/// Availability of a dependency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    Up,
+    Down,
+    /// The dependency is not configured, the capability is unavailable by design.
+    Disabled,
+}
+
+/// An optional subsystem backing a port.
+///
+/// Ports of optional subsystems (cache, messaging, email, ...) extend this trait and come with
+/// an explicit `Disabled` adapter, so services can check `is_enabled` and degrade gracefully
+/// instead of failing when the subsystem is not configured.
+#[async_trait]
+pub trait Capability {
+    /// Name of the dependency, as shown by the admin dependencies endpoint.
+    fn name(&self) -> &'static str;
+
+    /// Returns `false` for `Disabled` adapters.
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    /// Checks the availability of the dependency.
+    ///
+    /// Adapters talking to a remote service should override this with an actual probe.
+    async fn check(&self) -> DependencyStatus {
+        if self.is_enabled() {
+            DependencyStatus::Up
+        } else {
+            DependencyStatus::Disabled
+        }
+    }
+}
+
+/// The optional subsystems available to services, each backed by a real or `Disabled` adapter.
+#[derive(Clone)]
+pub struct Capabilities {
+    pub cache: Arc<dyn CachePort + Send + Sync + 'static>,
+    pub messaging: Arc<dyn MessagePublisherPort + Send + Sync + 'static>,
+    pub email: Arc<dyn EmailSenderPort + Send + Sync + 'static>,
+    pub error_reporter: Arc<dyn ErrorReporterPort + Send + Sync + 'static>,
+}
+
+impl Capabilities {
+    /// Returns all capabilities, for reporting their status.
+    pub fn all(&self) -> Vec<&(dyn Capability + Send + Sync)> {
+        vec![self.cache.as_ref(), self.messaging.as_ref(), self.email.as_ref(), self.error_reporter.as_ref()]
+    }
+}
+
+impl Default for Capabilities {
+    /// All capabilities disabled.
+    fn default() -> Self {
+        Self {
+            cache: Arc::new(DisabledCache),
+            messaging: Arc::new(DisabledMessagePublisher),
+            email: Arc::new(DisabledEmailSender),
+            error_reporter: Arc::new(DisabledErrorReporter),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+/// Port for reading the current time, so expiry-dependent features (e.g. tokens, key
+/// retention) can be tested without waiting in real time.
+pub trait ClockPort {
+    /// Returns the current time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock reading the time of the system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl ClockPort for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock running with the time of the system, shifted forward or backward by an adjustable
+/// offset. Only meant for integration environments.
+#[derive(Debug, Default)]
+pub struct ShiftedClock {
+    offset: Mutex<TimeDelta>,
+}
+
+impl ShiftedClock {
+    /// Creates a new `ShiftedClock` without offset.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the offset of the clock from the time of the system.
+    pub fn offset(&self) -> TimeDelta {
+        *self.offset.lock().unwrap()
+    }
+
+    /// Shifts the clock by `delta`, backward when negative, and returns the new offset. The
+    /// offset saturates instead of overflowing.
+    pub fn shift(&self, delta: TimeDelta) -> TimeDelta {
+        let mut offset = self.offset.lock().unwrap();
+        *offset = offset.checked_add(&delta).unwrap_or(if delta < TimeDelta::zero() { TimeDelta::MIN } else { TimeDelta::MAX });
+        *offset
+    }
+
+    /// Puts the clock back to the time of the system.
+    pub fn reset(&self) {
+        *self.offset.lock().unwrap() = TimeDelta::zero();
+    }
+}
+
+impl ClockPort for ShiftedClock {
+    fn now(&self) -> DateTime<Utc> {
+        let now = Utc::now();
+        now.checked_add_signed(self.offset()).unwrap_or(now)
+    }
+}
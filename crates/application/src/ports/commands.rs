@@ -0,0 +1,21 @@
+use async_trait::async_trait;
+
+/// Port executing the commands consumed from a message broker, for backends changing users
+/// asynchronously instead of calling the APIs.
+///
+/// Commands and replies are opaque to the consumers, which only route them: every command
+/// gets a reply, reporting its result or why it failed, so handlers never fail themselves.
+#[async_trait]
+pub trait CommandHandlerPort {
+    /// Executes the command encoded in `payload`, and returns the reply to send back.
+    async fn handle(&self, payload: &[u8]) -> CommandReply;
+}
+
+/// The reply to a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandReply {
+    /// Id set by the sender of the command to match the reply with it, if any.
+    pub correlation_id: Option<String>,
+    /// The encoded reply.
+    pub payload: Vec<u8>,
+}
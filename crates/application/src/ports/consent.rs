@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+use domain::consent::{error::ConsentDomainError, model::ConsentType};
+
+/// Port checking whether users consent to a purpose.
+///
+/// Features that require consent (analytics, marketing e-mails, ...) depend on this port and
+/// skip users that have not granted it, instead of reading consent records themselves.
+/// `DisabledConsentService` answers `false` for every user when consent tracking is not wired.
+#[async_trait]
+pub trait ConsentPort {
+    /// Returns whether the user currently consents to `consent_type`.
+    ///
+    /// Consents never recorded are not granted.
+    async fn has_consent(&self, user_id: &str, consent_type: ConsentType) -> Result<bool, ConsentDomainError>;
+}
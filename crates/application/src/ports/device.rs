@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use domain::outcome::ErrorClass;
+
+/// Reasons a step of the device authorization grant (RFC 8628) fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceGrantError {
+    /// The user has not approved or denied the device yet.
+    AuthorizationPending,
+    /// The device polls faster than its interval, which is increased by 5 seconds.
+    SlowDown,
+    /// The user denied the device.
+    AccessDenied,
+    /// The device code expired before the user approved it.
+    ExpiredToken,
+    /// The device code is unknown, already exchanged, or was issued to another client.
+    InvalidGrant,
+    /// The user code is unknown, expired or already approved or denied.
+    InvalidUserCode,
+    /// The codes could not be issued, e.g. because too many are pending.
+    Unavailable,
+}
+
+impl ErrorClass for DeviceGrantError {
+    fn class(&self) -> &'static str {
+        match self {
+            DeviceGrantError::AuthorizationPending => "authorization_pending",
+            DeviceGrantError::SlowDown => "slow_down",
+            DeviceGrantError::AccessDenied => "access_denied",
+            DeviceGrantError::ExpiredToken => "expired_token",
+            DeviceGrantError::InvalidGrant => "invalid_grant",
+            DeviceGrantError::InvalidUserCode => "invalid_user_code",
+            DeviceGrantError::Unavailable => "unavailable",
+        }
+    }
+
+    /// A pending authorization is the expected answer to most polls, so it is not recorded as an error.
+    fn outcome(&self) -> &'static str {
+        match self {
+            DeviceGrantError::AuthorizationPending => "pending",
+            _ => "error",
+        }
+    }
+}
+
+/// The codes of a device authorization request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceCodes {
+    /// Secret code the device polls for its token with.
+    pub device_code: String,
+    /// Short code the user enters on the verification page, e.g. `BDWP-HQPK`.
+    pub user_code: String,
+    /// Time after which both codes expire.
+    pub expires_in: Duration,
+    /// Minimum time the device waits between polls.
+    pub interval: Duration,
+}
+
+/// The user who approved a device, and the roles granted to its token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceApproval {
+    pub user_id: String,
+    pub roles: Vec<String>,
+}
+
+/// Port keeping the pending device authorization requests, from their issue to the exchange of
+/// their device code.
+///
+/// Each device code is exchanged for a token at most once, and each user code is approved or
+/// denied at most once.
+pub trait DeviceGrantPort {
+    /// Issues the codes of a new request of the client `client_id`.
+    fn issue(&self, client_id: &str) -> Result<DeviceCodes, DeviceGrantError>;
+
+    /// Records the approval of the request with the given user code.
+    fn approve(&self, user_code: &str, approval: DeviceApproval) -> Result<(), DeviceGrantError>;
+
+    /// Records the denial of the request with the given user code.
+    fn deny(&self, user_code: &str) -> Result<(), DeviceGrantError>;
+
+    /// Returns the approval of the request with the given device code, issued to `client_id`,
+    /// enforcing its polling interval.
+    fn poll(&self, device_code: &str, client_id: &str) -> Result<DeviceApproval, DeviceGrantError>;
+}
@@ -0,0 +1,39 @@
+use async_trait::async_trait;
+
+use crate::ports::capability::Capability;
+
+/// An e-mail to be delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Port for delivering e-mails.
+#[async_trait]
+pub trait EmailSenderPort: Capability {
+    async fn send(&self, message: EmailMessage) -> eyre::Result<()>;
+}
+
+/// E-mail sender used when no mail transport is configured. Sending always fails;
+/// callers should check `is_enabled` first.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledEmailSender;
+
+impl Capability for DisabledEmailSender {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn is_enabled(&self) -> bool {
+        false
+    }
+}
+
+#[async_trait]
+impl EmailSenderPort for DisabledEmailSender {
+    async fn send(&self, _message: EmailMessage) -> eyre::Result<()> {
+        eyre::bail!("email is disabled")
+    }
+}
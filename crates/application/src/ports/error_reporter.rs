@@ -0,0 +1,61 @@
+use crate::ports::capability::Capability;
+
+/// Severity of a reported error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorLevel {
+    /// A request failed with a server error.
+    Error,
+    /// A thread panicked.
+    Fatal,
+}
+
+/// Context of the HTTP request an error occurred in.
+///
+/// Only the method and path are captured; query strings, headers and bodies are left out
+/// because they may contain credentials or personal data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    pub method: String,
+    pub path: String,
+}
+
+/// An error to be forwarded to an error tracking service.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorReport {
+    pub level: ErrorLevel,
+    pub message: String,
+    /// The request the error occurred in, if any.
+    pub request: Option<RequestContext>,
+}
+
+/// Port for forwarding unexpected errors (server errors, panics) to an error tracking service.
+///
+/// Reporting is fire-and-forget: adapters must not block the caller, as reports are also
+/// emitted from the panic hook.
+pub trait ErrorReporterPort: Capability {
+    fn report(&self, report: ErrorReport);
+}
+
+/// Error reporter used when no error tracking service is configured. Reports are discarded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledErrorReporter;
+
+impl Capability for DisabledErrorReporter {
+    fn name(&self) -> &'static str {
+        "error_reporting"
+    }
+
+    fn is_enabled(&self) -> bool {
+        false
+    }
+}
+
+impl ErrorReporterPort for DisabledErrorReporter {
+    fn report(&self, _report: ErrorReport) {}
+}
+
+tokio::task_local! {
+    /// The request currently being handled, set by the HTTP layer so that reports emitted
+    /// outside of it (e.g. from the panic hook) still carry request context.
+    pub static REQUEST_CONTEXT: RequestContext;
+}
@@ -0,0 +1,38 @@
+use async_trait::async_trait;
+
+use domain::user::event::UserEvent;
+
+use crate::ports::messaging::MessagePublisherPort;
+
+/// Port recording the domain events of the services, for downstream systems to react to.
+///
+/// Adapters are expected to persist events durably before returning: in an outbox dispatched
+/// to the message broker by [`OutboxPort::dispatch`], or in a broker acknowledging events
+/// once they are replicated.
+#[async_trait]
+pub trait EventPublisherPort {
+    /// Records `event`.
+    async fn publish(&self, event: UserEvent) -> eyre::Result<()>;
+}
+
+/// Event publisher used when neither an outbox nor a broker is configured: events are dropped.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledEventPublisher;
+
+#[async_trait]
+impl EventPublisherPort for DisabledEventPublisher {
+    async fn publish(&self, _event: UserEvent) -> eyre::Result<()> {
+        Ok(())
+    }
+}
+
+/// Port of the outbox holding recorded events until they are published to the message broker.
+#[async_trait]
+pub trait OutboxPort {
+    /// Publishes up to `limit` pending events to `publisher`, oldest first, removing them from
+    /// the outbox, and returns how many were published.
+    ///
+    /// Stops at the first event failing to publish, which is retried by the next call, so
+    /// events are published in order and at least once: consumers deduplicate them by id.
+    async fn dispatch(&self, publisher: &(dyn MessagePublisherPort + Send + Sync), limit: usize) -> eyre::Result<usize>;
+}
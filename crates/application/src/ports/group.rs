@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+
+use domain::group::error::GroupDomainError;
+
+/// Port resolving the roles in effect for a user, merging the role stored with them and the
+/// roles granted to them directly with those granted and denied by their groups.
+///
+/// Token issuers depend on this port so tokens carry the roles of the groups of the user.
+/// `DisabledGroupService` returns the direct roles unchanged when groups are not wired.
+#[async_trait]
+pub trait GroupRolesPort {
+    /// Returns the roles in effect for the user `user_id` granted `direct_roles`, see
+    /// [`domain::group::model::GroupRoles::apply`].
+    ///
+    /// Users that cannot be stored locally (ids which are not UUIDs) have no stored role and
+    /// belong to no group.
+    async fn effective_roles(&self, user_id: &str, direct_roles: &[String]) -> Result<Vec<String>, GroupDomainError>;
+}
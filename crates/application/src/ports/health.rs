@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::ports::capability::DependencyStatus;
+
+/// A dependency the server cannot serve requests without (e.g. the database).
+///
+/// Adapters of required dependencies implement this port so the readiness endpoint can probe
+/// them. Unlike [`Capability`](crate::ports::capability::Capability), failing checks make the
+/// whole server unready.
+#[async_trait]
+pub trait HealthCheckPort {
+    /// Name of the dependency, as shown by the readiness endpoint.
+    fn name(&self) -> &'static str;
+
+    /// Probes the dependency, returning `Up` or `Down`.
+    async fn check(&self) -> DependencyStatus;
+}
+
+/// The health checks probed by the readiness endpoint.
+#[derive(Clone, Default)]
+pub struct HealthChecks(Vec<Arc<dyn HealthCheckPort + Send + Sync + 'static>>);
+
+impl HealthChecks {
+    /// Creates the set of health checks.
+    pub fn new(checks: Vec<Arc<dyn HealthCheckPort + Send + Sync + 'static>>) -> Self {
+        Self(checks)
+    }
+
+    /// Runs every check, returning the status of each dependency by name.
+    pub async fn check_all(&self) -> Vec<(&'static str, DependencyStatus)> {
+        let mut statuses = Vec::with_capacity(self.0.len());
+        for check in &self.0 {
+            statuses.push((check.name(), check.check().await));
+        }
+        statuses
+    }
+}
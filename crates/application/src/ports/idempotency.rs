@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+/// A response stored to be replayed to the retries of its request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Headers of the response replayed along its body, e.g. `ETag` and `Location`, by name.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Outcome of [`IdempotencyPort::reserve`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reservation {
+    /// The key was free and is now held by the caller, which must complete or release it.
+    Reserved,
+    /// The key is held by a request still being handled.
+    InProgress { fingerprint: String },
+    /// A request with the key was handled, and its response stored.
+    Completed { fingerprint: String, response: StoredResponse },
+}
+
+/// Port of the idempotency keys sent by clients, so the retries of a request are answered
+/// with the response of the first attempt instead of being handled again.
+///
+/// Keys are stored with the fingerprint of their request, so a key reused for another request
+/// is detected. A key is held for a short lease while its request is handled, so a request
+/// that never completes (e.g. a replica crashing) does not hold it for long, then kept for a
+/// time to live once its response is stored. Expired keys can be reserved again.
+#[async_trait]
+pub trait IdempotencyPort {
+    /// Reserves `key` for the request of `fingerprint` for `lease`, unless a request holding it
+    /// has not expired yet, in which case that request is returned.
+    async fn reserve(&self, key: &str, fingerprint: &str, lease: Duration) -> eyre::Result<Reservation>;
+
+    /// Stores the `response` of the request holding `key`, replayed for `ttl`.
+    async fn complete(&self, key: &str, response: StoredResponse, ttl: Duration) -> eyre::Result<()>;
+
+    /// Releases `key` without a response, e.g. when its request failed, so it can be retried.
+    async fn release(&self, key: &str) -> eyre::Result<()>;
+}
@@ -0,0 +1,32 @@
+use async_trait::async_trait;
+
+use crate::ports::capability::Capability;
+
+/// Port for publishing messages to a message broker.
+#[async_trait]
+pub trait MessagePublisherPort: Capability {
+    /// Publishes `payload` to `topic`.
+    async fn publish(&self, topic: &str, payload: Vec<u8>) -> eyre::Result<()>;
+}
+
+/// Message publisher used when no broker is configured. Publishing always fails, as
+/// dropping messages silently would lose data; callers should check `is_enabled` first.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledMessagePublisher;
+
+impl Capability for DisabledMessagePublisher {
+    fn name(&self) -> &'static str {
+        "messaging"
+    }
+
+    fn is_enabled(&self) -> bool {
+        false
+    }
+}
+
+#[async_trait]
+impl MessagePublisherPort for DisabledMessagePublisher {
+    async fn publish(&self, topic: &str, _payload: Vec<u8>) -> eyre::Result<()> {
+        eyre::bail!("messaging is disabled, cannot publish to {}", topic)
+    }
+}
@@ -0,0 +1,25 @@
+pub mod alert;
+pub mod audit;
+pub mod auth;
+pub mod breached_passwords;
+pub mod cache;
+pub mod capability;
+pub mod clock;
+pub mod commands;
+pub mod consent;
+pub mod device;
+pub mod email;
+pub mod events;
+pub mod group;
+pub mod error_reporter;
+pub mod health;
+pub mod idempotency;
+pub mod messaging;
+pub mod password;
+pub mod purge;
+pub mod stats;
+pub mod tenant;
+pub mod throttle;
+pub mod traffic_archive;
+pub mod unit_of_work;
+pub mod webauthn;
@@ -0,0 +1,37 @@
+use async_trait::async_trait;
+
+use domain::user::model::{Password, PasswordHash};
+
+/// Port for hashing the passwords of users and verifying passwords against their hash.
+///
+/// Hashes are self-describing (algorithm, parameters and salt included), so `verify` accepts
+/// hashes made with other parameters than the current ones.
+#[async_trait]
+pub trait PasswordHasherPort {
+    /// Hashes `password` with a fresh salt.
+    async fn hash(&self, password: &Password) -> eyre::Result<PasswordHash>;
+
+    /// Returns whether `password` is the one `hash` was made from. Fails only when `hash`
+    /// cannot be read.
+    ///
+    /// Without a `hash` (there is no such user, or the user has no password) the answer is
+    /// `false`, but it takes as long as a verification, so the time to answer a login does not
+    /// reveal which emails belong to users.
+    async fn verify(&self, password: &str, hash: Option<&PasswordHash>) -> eyre::Result<bool>;
+}
+
+/// Password hasher used when none is configured. Hashing always fails, so users cannot be
+/// created with a password, and no password is ever verified.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledPasswordHasher;
+
+#[async_trait]
+impl PasswordHasherPort for DisabledPasswordHasher {
+    async fn hash(&self, _password: &Password) -> eyre::Result<PasswordHash> {
+        eyre::bail!("password hashing is disabled")
+    }
+
+    async fn verify(&self, _password: &str, _hash: Option<&PasswordHash>) -> eyre::Result<bool> {
+        Ok(false)
+    }
+}
@@ -0,0 +1,28 @@
+use domain::user::{event::UserEvent, model::UserId};
+
+/// Surrogate key of the responses listing users (lists and searches), which any change of a
+/// user may alter.
+pub const USERS_SURROGATE_KEY: &str = "users";
+
+/// Returns the surrogate key of the responses about the user `id`, e.g. `user-<id>`.
+pub fn user_surrogate_key(id: UserId) -> String {
+    format!("user-{}", id)
+}
+
+/// Returns the surrogate keys of the responses the change of `event` alters: the lists of users
+/// and, unless the user was just created, the responses about the user.
+pub fn surrogate_keys(event: &UserEvent) -> Vec<String> {
+    match event {
+        UserEvent::UserCreated(_) => vec![USERS_SURROGATE_KEY.to_string()],
+        _ => vec![user_surrogate_key(event.user_id()), USERS_SURROGATE_KEY.to_string()],
+    }
+}
+
+/// Port for invalidating the responses stored by shared caches (e.g. a CDN or Varnish), by the
+/// surrogate keys the responses were tagged with.
+///
+/// Purging is fire-and-forget: adapters must not block the caller, as purges are requested
+/// while requests are handled, once the change is made.
+pub trait PurgePort {
+    fn purge(&self, keys: Vec<String>);
+}
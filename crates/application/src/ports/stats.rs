@@ -0,0 +1,101 @@
+use async_trait::async_trait;
+use chrono::{Datelike, Days, NaiveDate};
+
+/// Numbers of users of a day (UTC), materialized by [`UserStatsPort::project`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyUserStats {
+    pub day: NaiveDate,
+    /// Users created during the day.
+    pub signups: u64,
+    /// Users deleted during the day, and not restored since.
+    pub deletions: u64,
+    /// Users existing, and not deleted, at the end of the day.
+    pub active_users: u64,
+}
+
+/// Port of the daily statistics of the users, materialized from the users rather than
+/// computed when read, so reports stay cheap whatever the number of users.
+#[async_trait]
+pub trait UserStatsPort {
+    /// Computes and stores the statistics of every day from the last day stored, or from the
+    /// first signup when none is, to `today`, included. The last day stored is computed again,
+    /// as it was stored before its end, while earlier days are kept as they are. Returns the
+    /// number of days stored.
+    async fn project(&self, today: NaiveDate) -> eyre::Result<u64>;
+
+    /// Returns the statistics stored for the days from `from` to `to`, included, by day.
+    async fn daily_stats(&self, from: NaiveDate, to: NaiveDate) -> eyre::Result<Vec<DailyUserStats>>;
+}
+
+/// Statistics used when they are not materialized: no days are stored.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisabledUserStats;
+
+#[async_trait]
+impl UserStatsPort for DisabledUserStats {
+    async fn project(&self, _today: NaiveDate) -> eyre::Result<u64> {
+        Ok(0)
+    }
+
+    async fn daily_stats(&self, _from: NaiveDate, _to: NaiveDate) -> eyre::Result<Vec<DailyUserStats>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Period the daily statistics are grouped by.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StatsPeriod {
+    #[default]
+    Day,
+    /// ISO week, starting on Monday.
+    Week,
+    Month,
+}
+
+impl StatsPeriod {
+    /// Returns the first day of the period containing `day`.
+    pub fn start(self, day: NaiveDate) -> NaiveDate {
+        match self {
+            StatsPeriod::Day => day,
+            StatsPeriod::Week => day - Days::new(day.weekday().num_days_from_monday().into()),
+            StatsPeriod::Month => day.with_day(1).unwrap_or(day),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StatsPeriod::Day => "day",
+            StatsPeriod::Week => "week",
+            StatsPeriod::Month => "month",
+        }
+    }
+}
+
+/// Numbers of users of a period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserStats {
+    /// First day of the period.
+    pub period: NaiveDate,
+    pub signups: u64,
+    pub deletions: u64,
+    /// Users active at the end of the last day of the period with statistics.
+    pub active_users: u64,
+}
+
+/// Groups daily statistics, ordered by day, by `period`: signups and deletions are summed, and
+/// the active users are those of the last day of each period.
+pub fn group_stats(days: &[DailyUserStats], period: StatsPeriod) -> Vec<UserStats> {
+    let mut grouped: Vec<UserStats> = Vec::new();
+    for day in days {
+        let start = period.start(day.day);
+        match grouped.last_mut() {
+            Some(stats) if stats.period == start => {
+                stats.signups += day.signups;
+                stats.deletions += day.deletions;
+                stats.active_users = day.active_users;
+            }
+            _ => grouped.push(UserStats { period: start, signups: day.signups, deletions: day.deletions, active_users: day.active_users }),
+        }
+    }
+    grouped
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::ports::email::EmailMessage;
+
+/// Settings a tenant overrides, the deployment-wide configuration applying to the others.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantSettings {
+    /// Requests each client of the tenant may send to each route per rate limit period,
+    /// replacing the limits of the routes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit_requests: Option<u32>,
+    /// Features enabled or disabled for the tenant, by name.
+    #[serde(default)]
+    pub feature_flags: BTreeMap<String, bool>,
+    /// Secret signing the webhook deliveries of the tenant, instead of the shared secrets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_secret: Option<String>,
+    /// Branding of the error responses and e-mails of the tenant.
+    #[serde(default, skip_serializing_if = "TenantBranding::is_empty")]
+    pub branding: TenantBranding,
+}
+
+impl TenantSettings {
+    /// Returns whether `flag` is enabled for the tenant, or `default` when it does not set it.
+    pub fn feature_enabled(&self, flag: &str, default: bool) -> bool {
+        self.feature_flags.get(flag).copied().unwrap_or(default)
+    }
+}
+
+impl std::fmt::Debug for TenantSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TenantSettings")
+            .field("rate_limit_requests", &self.rate_limit_requests)
+            .field("feature_flags", &self.feature_flags)
+            .field("webhook_secret", &self.webhook_secret.as_ref().map(|_| "<redacted>"))
+            .field("branding", &self.branding)
+            .finish()
+    }
+}
+
+/// Branding a tenant applies to the error responses and e-mails sent on its behalf, so they
+/// carry the name and support contacts of the tenant instead of those of the deployment.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TenantBranding {
+    /// Templates of the messages of the error responses, by status code. `{message}` is
+    /// replaced by the message of the deployment, and `{status}` by the status code.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub error_templates: BTreeMap<u16, String>,
+    /// Link to the support of the tenant (e.g. `https://help.acme.test` or
+    /// `mailto:support@acme.test`), added to the error responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub support_url: Option<String>,
+    /// Name prefixing the subject of the e-mails, e.g. `[Acme] Welcome`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_sender_name: Option<String>,
+    /// Text appended to the body of the e-mails, e.g. a signature or the address of the tenant.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email_footer: Option<String>,
+}
+
+impl TenantBranding {
+    /// Returns whether the tenant keeps the branding of the deployment.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Returns the message of an error response with `status`, from the template of the
+    /// tenant for `status`, or `message` when it has none.
+    pub fn error_message(&self, status: u16, message: &str) -> String {
+        match self.error_templates.get(&status) {
+            Some(template) => template.replace("{status}", &status.to_string()).replace("{message}", message),
+            None => message.to_string(),
+        }
+    }
+
+    /// Returns `message` with the sender name of the tenant prefixing its subject and its
+    /// footer appended to its body.
+    pub fn brand_email(&self, mut message: EmailMessage) -> EmailMessage {
+        if let Some(name) = &self.email_sender_name {
+            message.subject = format!("[{}] {}", name, message.subject);
+        }
+        if let Some(footer) = &self.email_footer {
+            message.body = format!("{}\n\n--\n{}", message.body.trim_end(), footer);
+        }
+        message
+    }
+}
+
+/// Port of the settings overridden by tenants, consulted by the middleware and the services
+/// handling the requests of a tenant.
+///
+/// Tenants without settings use the configuration of the deployment.
+#[async_trait]
+pub trait TenantConfigPort {
+    /// Returns the settings of `tenant`, or `None` when it overrides none.
+    async fn get(&self, tenant: &str) -> eyre::Result<Option<TenantSettings>>;
+
+    /// Replaces the settings of `tenant` with `settings`.
+    async fn put(&self, tenant: &str, settings: TenantSettings) -> eyre::Result<()>;
+
+    /// Removes the settings of `tenant`. Returns whether it had any.
+    async fn delete(&self, tenant: &str) -> eyre::Result<bool>;
+
+    /// Returns whether `flag` is enabled for `tenant`, or `default` when the tenant does not
+    /// set it. Failures are logged and fall back to `default`.
+    async fn feature_enabled(&self, tenant: &str, flag: &str, default: bool) -> bool {
+        match self.get(tenant).await {
+            Ok(settings) => settings.map_or(default, |settings| settings.feature_enabled(flag, default)),
+            Err(e) => {
+                tracing::warn!(tenant, flag, "failed to read tenant settings: {:#}", e);
+                default
+            }
+        }
+    }
+
+    /// Returns the secret signing the webhook deliveries of `tenant`, or `None` when the
+    /// deployment-wide secrets sign them.
+    async fn webhook_secret(&self, tenant: &str) -> eyre::Result<Option<String>> {
+        Ok(self.get(tenant).await?.and_then(|settings| settings.webhook_secret))
+    }
+
+    /// Returns `message` with the branding of `tenant` applied, for services sending e-mails
+    /// on behalf of a tenant. Failures are logged and leave `message` unbranded.
+    async fn brand_email(&self, tenant: &str, message: EmailMessage) -> EmailMessage {
+        match self.get(tenant).await {
+            Ok(Some(settings)) => settings.branding.brand_email(message),
+            Ok(None) => message,
+            Err(e) => {
+                tracing::warn!(tenant, "failed to read tenant settings: {:#}", e);
+                message
+            }
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use std::time::Duration;
+
+/// Port for temporarily tightening the limits of incoming requests, e.g. during an anomaly.
+pub trait ThrottlePort {
+    /// Divides the request limits by `divisor`, keeping at least one request per period, for
+    /// `duration`. Tightening again while the limits are tight keeps the strictest divisor and
+    /// the latest end.
+    fn tighten(&self, divisor: u32, duration: Duration);
+}
@@ -0,0 +1,38 @@
+use std::time::SystemTime;
+
+/// A request and its response, sanitized, as kept in the traffic archive.
+///
+/// Credentials and personal data are removed before exchanges reach the port: secret headers
+/// are redacted, and bodies are only kept when they are JSON, with secret and e-mail fields
+/// redacted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedExchange {
+    /// When the request was received.
+    pub received_at: SystemTime,
+    /// Id of the request, from its `x-request-id` header.
+    pub request_id: Option<String>,
+    pub method: String,
+    /// Template of the matched route, e.g. `/api/users/{id}`, if any route matched.
+    pub route: Option<String>,
+    pub path: String,
+    pub query: Option<String>,
+    /// Headers of the request as `(name, value)` pairs, in order and with repeated names.
+    pub request_headers: Vec<(String, String)>,
+    /// Body of the request, `None` when it was left out (not JSON or too large).
+    pub request_body: Option<Vec<u8>>,
+    pub status: u16,
+    /// Headers of the response as `(name, value)` pairs.
+    pub response_headers: Vec<(String, String)>,
+    /// Body of the response, `None` when it was left out.
+    pub response_body: Option<Vec<u8>>,
+    /// Time taken to produce the response, in milliseconds.
+    pub latency_ms: u64,
+}
+
+/// Port for archiving sampled traffic for offline analysis and replay-based load tests.
+///
+/// Archiving is fire-and-forget: adapters must not block the caller, and may drop exchanges
+/// rather than slow requests down.
+pub trait TrafficArchivePort {
+    fn archive(&self, exchange: ArchivedExchange);
+}
@@ -0,0 +1,50 @@
+use async_trait::async_trait;
+
+use domain::user::repository::UserRepositoryPort;
+
+use crate::ports::audit::AuditLogPort;
+use crate::ports::events::EventPublisherPort;
+
+/// Isolation of a transaction from the transactions running concurrently.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IsolationLevel {
+    /// Each statement sees the data committed before it began. Operations changing what they
+    /// did not read, or reading a single row, need no more.
+    #[default]
+    ReadCommitted,
+    /// The transaction behaves as if transactions ran one after the other. For operations
+    /// writing based on what they read, such as deletions checking a legal hold, or changing
+    /// many rows, such as bulk creations. The transaction may then fail to commit, or one of its
+    /// statements fail, when it conflicts with a concurrent one, and must be run again.
+    Serializable,
+}
+
+/// Port running several repository operations atomically, in a single transaction.
+///
+/// Services that must change several aggregates, or record an event or an audit entry along
+/// with a change, begin a transaction and go through the repositories it hands out instead of their own.
+#[async_trait]
+pub trait UnitOfWorkPort {
+    /// Begins a transaction with the given isolation level.
+    async fn begin(&self, isolation: IsolationLevel) -> eyre::Result<Box<dyn TransactionPort + Send + Sync>>;
+}
+
+/// A transaction begun by [`UnitOfWorkPort::begin`].
+///
+/// Changes made through its repositories are applied by [`TransactionPort::commit`], all at
+/// once, and discarded if the transaction is dropped without being committed, e.g. when an
+/// operation fails and the service returns early.
+#[async_trait]
+pub trait TransactionPort {
+    /// The users, read and changed within the transaction.
+    fn users(&self) -> &(dyn UserRepositoryPort + Send + Sync);
+
+    /// The events, recorded within the transaction and published once it is committed.
+    fn events(&self) -> &(dyn EventPublisherPort + Send + Sync);
+
+    /// The audit log, recorded within the transaction.
+    fn audit_log(&self) -> &(dyn AuditLogPort + Send + Sync);
+
+    /// Applies the changes made within the transaction.
+    async fn commit(self: Box<Self>) -> eyre::Result<()>;
+}
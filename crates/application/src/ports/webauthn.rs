@@ -0,0 +1,97 @@
+use serde_json::Value;
+
+use domain::outcome::ErrorClass;
+use domain::passkey::{error::PasskeyDomainError, model::Passkey};
+use domain::user::model::{User, UserId};
+
+/// Reasons a passkey ceremony or operation fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasskeyError {
+    /// The ceremony is unknown, expired, already completed or started by another user.
+    UnknownCeremony,
+    /// The response of the authenticator does not verify against the ceremony.
+    InvalidResponse,
+    /// The user is unknown, or has no passkey to log in with.
+    NoPasskey,
+    UserNotFound,
+    PasskeyNotFound,
+    /// The credential is already registered, to this user or another one.
+    PasskeyAlreadyExists,
+    /// The ceremony could not be performed, e.g. because storage failed.
+    Unavailable,
+}
+
+impl ErrorClass for PasskeyError {
+    fn class(&self) -> &'static str {
+        match self {
+            PasskeyError::UnknownCeremony => "unknown_ceremony",
+            PasskeyError::InvalidResponse | PasskeyError::NoPasskey => "invalid_credentials",
+            PasskeyError::UserNotFound | PasskeyError::PasskeyNotFound => "not_found",
+            PasskeyError::PasskeyAlreadyExists => "conflict",
+            PasskeyError::Unavailable => "unavailable",
+        }
+    }
+}
+
+impl From<PasskeyDomainError> for PasskeyError {
+    fn from(e: PasskeyDomainError) -> Self {
+        match e {
+            PasskeyDomainError::UserNotFound => PasskeyError::UserNotFound,
+            PasskeyDomainError::PasskeyNotFound => PasskeyError::PasskeyNotFound,
+            PasskeyDomainError::PasskeyAlreadyExists => PasskeyError::PasskeyAlreadyExists,
+            PasskeyDomainError::PasskeyRegistrationFailed
+            | PasskeyDomainError::PasskeyUpdateFailed
+            | PasskeyDomainError::PasskeyListFailed
+            | PasskeyDomainError::PasskeyDeletionFailed => PasskeyError::Unavailable,
+        }
+    }
+}
+
+/// The options of a started ceremony, and the id its response is sent back with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasskeyChallenge {
+    pub ceremony_id: String,
+    /// Options to pass to `navigator.credentials.create()` (registration) or
+    /// `navigator.credentials.get()` (login) in the browser.
+    pub options: Value,
+}
+
+/// A credential created by an authenticator, verified against its registration ceremony.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewCredential {
+    /// The credential id, base64url-encoded.
+    pub id: String,
+    /// The credential to store, see [`Passkey::credential`].
+    pub credential: String,
+}
+
+/// A login with a passkey, verified against its authentication ceremony.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasskeyAssertion {
+    /// The id of the user the ceremony was started for.
+    pub user_id: UserId,
+    /// The id of the passkey used, base64url-encoded.
+    pub id: String,
+    /// The credential updated by the login (e.g. its signature counter), to store.
+    pub credential: String,
+}
+
+/// Port of a WebAuthn relying party, running the registration and authentication ceremonies
+/// of passkeys.
+///
+/// Ceremonies are started and finished by separate requests: the adapter keeps the state of
+/// started ceremonies until they are finished or expire, and each can only be finished once.
+pub trait WebAuthnPort {
+    /// Starts the registration of a new passkey of `user`, excluding the ones already registered.
+    fn start_registration(&self, user: &User, registered: &[Passkey]) -> Result<PasskeyChallenge, PasskeyError>;
+
+    /// Verifies the credential created by the authenticator for a registration ceremony
+    /// started for the user `user_id`.
+    fn finish_registration(&self, user_id: UserId, ceremony_id: &str, response: Value) -> Result<NewCredential, PasskeyError>;
+
+    /// Starts a login of the user `user_id` with one of its `registered` passkeys.
+    fn start_authentication(&self, user_id: UserId, registered: &[Passkey]) -> Result<PasskeyChallenge, PasskeyError>;
+
+    /// Verifies the assertion of the authenticator for an authentication ceremony.
+    fn finish_authentication(&self, ceremony_id: &str, response: Value) -> Result<PasskeyAssertion, PasskeyError>;
+}
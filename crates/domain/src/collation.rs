@@ -0,0 +1,30 @@
+use std::cmp::Ordering;
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Folds a string into its case- and accent-insensitive comparison key.
+///
+/// The text is decomposed (NFD), combining marks are dropped and the remainder is
+/// lowercased, so "José" and "jose" produce the same key. This mirrors the
+/// `ignore_accent_case` ICU collation used by the PostgreSQL adapter and lets
+/// adapters without collation support (e.g. in-memory) behave the same way.
+pub fn fold(value: &str) -> String {
+    value
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Returns `true` if both values are equal ignoring case and accents.
+pub fn eq(left: &str, right: &str) -> bool {
+    fold(left) == fold(right)
+}
+
+/// Compares two values ignoring case and accents.
+///
+/// Values that only differ by case or accents are ordered by their original form
+/// so that sorting stays deterministic.
+pub fn cmp(left: &str, right: &str) -> Ordering {
+    fold(left).cmp(&fold(right)).then_with(|| left.cmp(right))
+}
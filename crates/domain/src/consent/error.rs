@@ -0,0 +1,26 @@
+use port_decorators::Retryable;
+
+use crate::outcome::ErrorClass;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsentDomainError {
+    UserNotFound,
+    ConsentRecordFailed,
+    ConsentListFailed,
+}
+
+impl ErrorClass for ConsentDomainError {
+    fn class(&self) -> &'static str {
+        match self {
+            ConsentDomainError::UserNotFound => "not_found",
+            ConsentDomainError::ConsentRecordFailed | ConsentDomainError::ConsentListFailed => "internal",
+        }
+    }
+}
+
+impl Retryable for ConsentDomainError {
+    /// Internal failures may be transient, while a missing user will fail the same way again.
+    fn is_retryable(&self) -> bool {
+        self.class() == "internal"
+    }
+}
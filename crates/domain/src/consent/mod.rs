@@ -1,3 +1,3 @@
 pub mod model;
 pub mod repository;
-pub mod error;
\ No newline at end of file
+pub mod error;
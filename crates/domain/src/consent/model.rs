@@ -0,0 +1,103 @@
+use std::time::SystemTime;
+
+/// Purpose a user consents to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConsentType {
+    /// Collection of usage analytics.
+    Analytics,
+    /// Delivery of marketing e-mails.
+    MarketingEmail,
+}
+
+impl ConsentType {
+    /// Returns the stable identifier of the consent type, as stored.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsentType::Analytics => "analytics",
+            ConsentType::MarketingEmail => "marketing_email",
+        }
+    }
+
+    /// Parses a stored identifier, see [`ConsentType::as_str`].
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "analytics" => Some(ConsentType::Analytics),
+            "marketing_email" => Some(ConsentType::MarketingEmail),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a consent was granted or withdrawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsentAction {
+    Granted,
+    Withdrawn,
+}
+
+impl ConsentAction {
+    /// Returns the stable identifier of the action, as stored.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConsentAction::Granted => "granted",
+            ConsentAction::Withdrawn => "withdrawn",
+        }
+    }
+
+    /// Parses a stored identifier, see [`ConsentAction::as_str`].
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "granted" => Some(ConsentAction::Granted),
+            "withdrawn" => Some(ConsentAction::Withdrawn),
+            _ => None,
+        }
+    }
+}
+
+/// A recorded grant or withdrawal of consent.
+///
+/// Records are never updated: the consent currently in effect for a type is the action of
+/// the most recent record of that type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Consent {
+    /// The id of the user the consent belongs to.
+    pub user_id: String,
+    /// The purpose consented to.
+    pub consent_type: ConsentType,
+    /// Whether the consent was granted or withdrawn.
+    pub action: ConsentAction,
+    /// Version of the terms or policy the user was shown.
+    pub version: String,
+    /// Where the consent was collected (e.g. `signup_form`, `settings`).
+    pub source: String,
+    /// When the consent was recorded.
+    pub recorded_at: SystemTime,
+}
+
+/// Data transfer object for recording a grant or withdrawal of consent.
+///
+/// The time of the record is set by the repository.
+pub struct RecordConsent {
+    /// The id of the user the consent belongs to.
+    pub user_id: String,
+    /// The purpose consented to.
+    pub consent_type: ConsentType,
+    /// Whether the consent is granted or withdrawn.
+    pub action: ConsentAction,
+    /// Version of the terms or policy the user was shown.
+    pub version: String,
+    /// Where the consent was collected.
+    pub source: String,
+}
+
+/// Returns whether `consents` leave `consent_type` granted, i.e. whether its most recent
+/// record is a grant. Consents never recorded are not granted.
+///
+/// `consents` must be in the order they were recorded.
+pub fn is_granted(consents: &[Consent], consent_type: ConsentType) -> bool {
+    consents
+        .iter()
+        .rev()
+        .find(|consent| consent.consent_type == consent_type)
+        .is_some_and(|consent| consent.action == ConsentAction::Granted)
+}
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use port_decorators::instrumented_port;
+
+use crate::consent::{error::ConsentDomainError, model::{Consent, RecordConsent}};
+
+/// Repository port (interface) for the consent records of users.
+///
+/// Records are append-only, so the history of every consent is kept as evidence.
+#[instrumented_port]
+#[async_trait]
+pub trait ConsentRepositoryPort {
+    /// Appends a consent record.
+    async fn record_consent(&self, consent: RecordConsent) -> Result<Consent, ConsentDomainError>;
+
+    /// Retrieves all consent records of a user, in the order they were recorded.
+    #[port(retry)]
+    async fn list_consents(&self, user_id: String) -> Result<Vec<Consent>, ConsentDomainError>;
+}
+
+/// Shared repositories are repositories too, see the equivalent implementation for users.
+#[async_trait]
+impl<T> ConsentRepositoryPort for Arc<T>
+where
+    T: ConsentRepositoryPort + Send + Sync + ?Sized,
+{
+    async fn record_consent(&self, consent: RecordConsent) -> Result<Consent, ConsentDomainError> {
+        (**self).record_consent(consent).await
+    }
+
+    async fn list_consents(&self, user_id: String) -> Result<Vec<Consent>, ConsentDomainError> {
+        (**self).list_consents(user_id).await
+    }
+}
@@ -0,0 +1,36 @@
+use port_decorators::Retryable;
+
+use crate::outcome::ErrorClass;
+
+use crate::user::model::UserId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupDomainError {
+    GroupNotFound,
+    UserNotFound,
+    /// Users to add to a group do not exist, none were added.
+    UnknownMembers(Vec<UserId>),
+    GroupSaveFailed,
+    GroupListFailed,
+    GroupDeletionFailed,
+    MembershipUpdateFailed,
+}
+
+impl ErrorClass for GroupDomainError {
+    fn class(&self) -> &'static str {
+        match self {
+            GroupDomainError::GroupNotFound | GroupDomainError::UserNotFound | GroupDomainError::UnknownMembers(_) => "not_found",
+            GroupDomainError::GroupSaveFailed
+            | GroupDomainError::GroupListFailed
+            | GroupDomainError::GroupDeletionFailed
+            | GroupDomainError::MembershipUpdateFailed => "internal",
+        }
+    }
+}
+
+impl Retryable for GroupDomainError {
+    /// Internal failures may be transient, while missing groups or users will fail the same way again.
+    fn is_retryable(&self) -> bool {
+        self.class() == "internal"
+    }
+}
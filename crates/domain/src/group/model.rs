@@ -0,0 +1,164 @@
+use std::collections::BTreeSet;
+
+use crate::user::model::UserId;
+use crate::user::validation::ValidationErrors;
+
+/// Maximum length of group names, in characters, matching the `groups.name` column.
+pub const MAX_GROUP_NAME_LENGTH: usize = 64;
+
+/// Maximum length of roles, in characters.
+pub const MAX_ROLE_LENGTH: usize = 64;
+
+/// Maximum number of users added to or removed from a group at once.
+pub const MAX_MEMBERS_PER_UPDATE: usize = 1000;
+
+/// A named bundle of roles, granted to or denied to every member of the group.
+///
+/// Members get the roles in effect per [`GroupRoles::apply`]: the roles granted to them
+/// directly (e.g. by their directory) and by their groups, except those denied by any group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    /// The unique name of the group, e.g. `support-agents`.
+    pub name: String,
+    /// Roles granted to the members, sorted.
+    pub granted_roles: Vec<String>,
+    /// Roles denied to the members, even when granted directly or by another group, sorted.
+    pub denied_roles: Vec<String>,
+}
+
+/// Data transfer object for creating a group, or replacing its roles.
+///
+/// Build it with [`SaveGroup::new`], which checks the name and roles and sorts the roles.
+pub struct SaveGroup {
+    pub name: String,
+    pub granted_roles: Vec<String>,
+    pub denied_roles: Vec<String>,
+}
+
+impl SaveGroup {
+    /// Creates a new `SaveGroup`, or returns every constraint the given fields violate. A role
+    /// cannot be both granted and denied by the same group.
+    pub fn new(name: String, granted_roles: Vec<String>, denied_roles: Vec<String>) -> Result<Self, ValidationErrors> {
+        let granted_roles: BTreeSet<String> = granted_roles.into_iter().collect();
+        let denied_roles: BTreeSet<String> = denied_roles.into_iter().collect();
+
+        let mut errors = ValidationErrors::new();
+        errors.check("name", validate_group_name(&name));
+        errors.check("granted_roles", validate_roles(&granted_roles));
+        errors.check("denied_roles", validate_roles(&denied_roles));
+        let both: Vec<&str> = granted_roles.intersection(&denied_roles).map(String::as_str).collect();
+        if !both.is_empty() {
+            errors.check("denied_roles", Err(format!("must not include granted roles ({})", both.join(", "))));
+        }
+
+        errors.into_result(Self {
+            name,
+            granted_roles: granted_roles.into_iter().collect(),
+            denied_roles: denied_roles.into_iter().collect(),
+        })
+    }
+}
+
+/// Users added to and removed from a group in a single, atomic update.
+pub struct UpdateMembers {
+    pub add: Vec<UserId>,
+    pub remove: Vec<UserId>,
+}
+
+impl UpdateMembers {
+    /// Creates a new `UpdateMembers` from user ids, or returns every constraint they violate.
+    /// A user cannot be both added and removed.
+    pub fn new(add: Vec<String>, remove: Vec<String>) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if add.len() + remove.len() > MAX_MEMBERS_PER_UPDATE {
+            errors.check("add", Err(format!("must list at most {} users along with remove", MAX_MEMBERS_PER_UPDATE)));
+        }
+        let add = parse_user_ids(&mut errors, "add", add);
+        let remove = parse_user_ids(&mut errors, "remove", remove);
+        if remove.iter().any(|id| add.contains(id)) {
+            errors.check("remove", Err("must not include users to add".to_string()));
+        }
+        errors.into_result(Self { add, remove })
+    }
+}
+
+fn parse_user_ids(errors: &mut ValidationErrors, field: &'static str, ids: Vec<String>) -> Vec<UserId> {
+    let mut parsed = BTreeSet::new();
+    for id in &ids {
+        match UserId::parse(id) {
+            Ok(id) => {
+                parsed.insert(id);
+            }
+            Err(_) => {
+                errors.check(field, Err("must only contain UUIDs".to_string()));
+                break;
+            }
+        }
+    }
+    parsed.into_iter().collect()
+}
+
+/// The roles granted and denied to a user by all of their groups.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GroupRoles {
+    /// Names of the groups of the user, sorted.
+    pub groups: Vec<String>,
+    /// Roles granted by a group and denied by none, sorted.
+    pub granted: Vec<String>,
+    /// Roles denied by a group, sorted.
+    pub denied: Vec<String>,
+}
+
+impl GroupRoles {
+    /// Merges the roles of `groups`. Denials take precedence over grants, whichever group they
+    /// come from.
+    pub fn of(groups: &[Group]) -> Self {
+        let denied: BTreeSet<&String> = groups.iter().flat_map(|group| &group.denied_roles).collect();
+        let granted: BTreeSet<&String> = groups
+            .iter()
+            .flat_map(|group| &group.granted_roles)
+            .filter(|role| !denied.contains(role))
+            .collect();
+        let groups: BTreeSet<&String> = groups.iter().map(|group| &group.name).collect();
+
+        Self {
+            groups: groups.into_iter().cloned().collect(),
+            granted: granted.into_iter().cloned().collect(),
+            denied: denied.into_iter().cloned().collect(),
+        }
+    }
+
+    /// Returns the roles in effect for a user granted `direct_roles` (e.g. by their directory):
+    /// the direct roles followed by the roles granted by groups, without duplicates, except the
+    /// roles denied by groups, which are removed even when granted directly.
+    pub fn apply(&self, direct_roles: &[String]) -> Vec<String> {
+        let mut roles: Vec<String> = Vec::with_capacity(direct_roles.len() + self.granted.len());
+        for role in direct_roles.iter().chain(&self.granted) {
+            if !roles.contains(role) && self.denied.binary_search(role).is_err() {
+                roles.push(role.clone());
+            }
+        }
+        roles
+    }
+}
+
+/// Group names are 1 to 64 lowercase ASCII letters, digits, `-`, `_` or `.`.
+pub fn validate_group_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_GROUP_NAME_LENGTH {
+        return Err(format!("must be 1 to {} characters", MAX_GROUP_NAME_LENGTH));
+    }
+    if !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.')) {
+        return Err("must only contain lowercase letters, digits, '-', '_' and '.'".to_string());
+    }
+    Ok(())
+}
+
+/// Roles are 1 to 64 characters, without whitespace.
+fn validate_roles(roles: &BTreeSet<String>) -> Result<(), String> {
+    let valid = |role: &String| !role.is_empty() && role.chars().count() <= MAX_ROLE_LENGTH && !role.contains(char::is_whitespace);
+    if roles.iter().all(valid) {
+        Ok(())
+    } else {
+        Err(format!("must be 1 to {} characters without whitespace", MAX_ROLE_LENGTH))
+    }
+}
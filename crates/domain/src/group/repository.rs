@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use port_decorators::instrumented_port;
+
+use crate::group::{error::GroupDomainError, model::{Group, SaveGroup, UpdateMembers}};
+use crate::user::model::UserId;
+
+/// Repository port (interface) for groups and their members.
+///
+/// Memberships are removed along with their group or user.
+#[instrumented_port]
+#[async_trait]
+pub trait GroupRepositoryPort {
+    /// Creates a group, or replaces the roles of the existing group of the same name.
+    async fn save_group(&self, group: SaveGroup) -> Result<Group, GroupDomainError>;
+
+    /// Retrieves a group by name.
+    #[port(retry)]
+    async fn get_group(&self, name: String) -> Result<Group, GroupDomainError>;
+
+    /// Retrieves all groups, ordered by name.
+    #[port(retry)]
+    async fn list_groups(&self) -> Result<Vec<Group>, GroupDomainError>;
+
+    /// Deletes a group and its memberships.
+    async fn delete_group(&self, name: String) -> Result<(), GroupDomainError>;
+
+    /// Retrieves the members of a group, ordered by id.
+    #[port(retry)]
+    async fn list_members(&self, name: String) -> Result<Vec<UserId>, GroupDomainError>;
+
+    /// Adds and removes members of a group, all at once or not at all. Adding members twice
+    /// and removing non-members are no-ops. Fails with [`GroupDomainError::UnknownMembers`]
+    /// when users to add do not exist, if the adapter knows the users.
+    async fn update_members(&self, name: String, members: UpdateMembers) -> Result<(), GroupDomainError>;
+
+    /// Retrieves the groups a user is a member of, ordered by name.
+    #[port(retry)]
+    async fn list_user_groups(&self, user_id: UserId) -> Result<Vec<Group>, GroupDomainError>;
+}
+
+/// Shared repositories are repositories too, see the equivalent implementation for users.
+#[async_trait]
+impl<T> GroupRepositoryPort for Arc<T>
+where
+    T: GroupRepositoryPort + Send + Sync + ?Sized,
+{
+    async fn save_group(&self, group: SaveGroup) -> Result<Group, GroupDomainError> {
+        (**self).save_group(group).await
+    }
+
+    async fn get_group(&self, name: String) -> Result<Group, GroupDomainError> {
+        (**self).get_group(name).await
+    }
+
+    async fn list_groups(&self) -> Result<Vec<Group>, GroupDomainError> {
+        (**self).list_groups().await
+    }
+
+    async fn delete_group(&self, name: String) -> Result<(), GroupDomainError> {
+        (**self).delete_group(name).await
+    }
+
+    async fn list_members(&self, name: String) -> Result<Vec<UserId>, GroupDomainError> {
+        (**self).list_members(name).await
+    }
+
+    async fn update_members(&self, name: String, members: UpdateMembers) -> Result<(), GroupDomainError> {
+        (**self).update_members(name, members).await
+    }
+
+    async fn list_user_groups(&self, user_id: UserId) -> Result<Vec<Group>, GroupDomainError> {
+        (**self).list_user_groups(user_id).await
+    }
+}
@@ -0,0 +1,7 @@
+pub mod collation;
+pub mod consent;
+pub mod group;
+pub mod outcome;
+pub mod passkey;
+pub mod secret;
+pub mod user;
\ No newline at end of file
@@ -0,0 +1,34 @@
+//! Outcome of operations, recorded in the fields of their spans.
+
+/// Errors with a stable, low-cardinality class, used in logs and traces.
+pub trait ErrorClass {
+    /// Returns the class of the error, e.g. `not_found` or `internal`.
+    fn class(&self) -> &'static str;
+
+    /// Returns the `outcome` recorded for the error, `error` unless it is an expected answer
+    /// (e.g. a pending authorization), whose class is then not recorded.
+    fn outcome(&self) -> &'static str {
+        "error"
+    }
+}
+
+/// Records the `outcome` and `error.class` fields of the current span from an operation result.
+///
+/// Spans of operations declare both fields as empty and call this on return, so traces can
+/// be queried by outcome without logging the (potentially sensitive) payload.
+pub fn record_outcome<T, E: ErrorClass>(result: Result<T, E>) -> Result<T, E> {
+    let span = tracing::Span::current();
+    match &result {
+        Ok(_) => {
+            span.record("outcome", "success");
+        }
+        Err(e) => {
+            let outcome = e.outcome();
+            span.record("outcome", outcome);
+            if outcome == "error" {
+                span.record("error.class", e.class());
+            }
+        }
+    }
+    result
+}
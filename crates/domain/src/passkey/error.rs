@@ -0,0 +1,35 @@
+use port_decorators::Retryable;
+
+use crate::outcome::ErrorClass;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasskeyDomainError {
+    UserNotFound,
+    PasskeyNotFound,
+    /// The credential is already registered, to this user or another one.
+    PasskeyAlreadyExists,
+    PasskeyRegistrationFailed,
+    PasskeyUpdateFailed,
+    PasskeyListFailed,
+    PasskeyDeletionFailed,
+}
+
+impl ErrorClass for PasskeyDomainError {
+    fn class(&self) -> &'static str {
+        match self {
+            PasskeyDomainError::UserNotFound | PasskeyDomainError::PasskeyNotFound => "not_found",
+            PasskeyDomainError::PasskeyAlreadyExists => "conflict",
+            PasskeyDomainError::PasskeyRegistrationFailed
+            | PasskeyDomainError::PasskeyUpdateFailed
+            | PasskeyDomainError::PasskeyListFailed
+            | PasskeyDomainError::PasskeyDeletionFailed => "internal",
+        }
+    }
+}
+
+impl Retryable for PasskeyDomainError {
+    /// Internal failures may be transient, while missing or conflicting passkeys will fail the same way again.
+    fn is_retryable(&self) -> bool {
+        self.class() == "internal"
+    }
+}
@@ -0,0 +1,35 @@
+use std::time::SystemTime;
+
+use crate::user::model::UserId;
+
+/// A WebAuthn credential (passkey) a user registered to log in with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Passkey {
+    /// The credential id chosen by the authenticator, base64url-encoded.
+    pub id: String,
+    /// The id of the user the passkey belongs to.
+    pub user_id: UserId,
+    /// Label given by the user to tell their passkeys apart (e.g. `Work laptop`).
+    pub name: String,
+    /// The public key and state of the credential, serialized by the WebAuthn adapter and
+    /// opaque to the domain.
+    pub credential: String,
+    /// When the passkey was registered.
+    pub created_at: SystemTime,
+    /// When the passkey was last used to log in, if ever.
+    pub last_used_at: Option<SystemTime>,
+}
+
+/// Data transfer object for storing a newly registered passkey.
+///
+/// The registration time is set by the repository.
+pub struct RegisterPasskey {
+    /// The credential id chosen by the authenticator, base64url-encoded.
+    pub id: String,
+    /// The id of the user the passkey belongs to.
+    pub user_id: UserId,
+    /// Label given by the user.
+    pub name: String,
+    /// The credential, as serialized by the WebAuthn adapter.
+    pub credential: String,
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use port_decorators::instrumented_port;
+
+use crate::passkey::{error::PasskeyDomainError, model::{Passkey, RegisterPasskey}};
+use crate::user::model::UserId;
+
+/// Repository port (interface) for the passkeys of users.
+///
+/// Credential ids are unique across users, so a credential cannot be registered to two accounts.
+#[instrumented_port]
+#[async_trait]
+pub trait PasskeyRepositoryPort {
+    /// Stores a newly registered passkey.
+    async fn add_passkey(&self, passkey: RegisterPasskey) -> Result<Passkey, PasskeyDomainError>;
+
+    /// Retrieves the passkeys of a user, in the order they were registered.
+    #[port(retry)]
+    async fn list_passkeys(&self, user_id: UserId) -> Result<Vec<Passkey>, PasskeyDomainError>;
+
+    /// Records a login with a passkey, storing the credential state updated by the login.
+    async fn record_passkey_use(&self, user_id: UserId, id: String, credential: String) -> Result<(), PasskeyDomainError>;
+
+    /// Deletes a passkey of a user.
+    async fn delete_passkey(&self, user_id: UserId, id: String) -> Result<(), PasskeyDomainError>;
+}
+
+/// Shared repositories are repositories too, see the equivalent implementation for users.
+#[async_trait]
+impl<T> PasskeyRepositoryPort for Arc<T>
+where
+    T: PasskeyRepositoryPort + Send + Sync + ?Sized,
+{
+    async fn add_passkey(&self, passkey: RegisterPasskey) -> Result<Passkey, PasskeyDomainError> {
+        (**self).add_passkey(passkey).await
+    }
+
+    async fn list_passkeys(&self, user_id: UserId) -> Result<Vec<Passkey>, PasskeyDomainError> {
+        (**self).list_passkeys(user_id).await
+    }
+
+    async fn record_passkey_use(&self, user_id: UserId, id: String, credential: String) -> Result<(), PasskeyDomainError> {
+        (**self).record_passkey_use(user_id, id, credential).await
+    }
+
+    async fn delete_passkey(&self, user_id: UserId, id: String) -> Result<(), PasskeyDomainError> {
+        (**self).delete_passkey(user_id, id).await
+    }
+}
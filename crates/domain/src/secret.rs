@@ -0,0 +1,50 @@
+//! Values that must never be printed: passwords, tokens, keys, and URLs carrying credentials.
+//!
+//! [`Secret`] redacts its value in `Debug` output and implements neither `Display` nor
+//! `Serialize`, nor `tracing::Value`, so a secret cannot end up in a log line, a span field, an
+//! error message or a response by accident: formatting it with `{}`, recording it in a span or
+//! serializing it does not compile. Reading it takes an explicit
+//! [`expose_secret`](Secret::expose_secret), which reviews can look for.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Placeholder printed instead of the value of a [`Secret`].
+pub const REDACTED: &str = "[redacted]";
+
+/// A value redacted from every output, read through [`Secret::expose_secret`] only.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// Wraps `value`.
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Returns the value, to hand it to the code using it (e.g. a connection or a signature).
+    /// It must not be logged, traced or returned.
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: FromStr> FromStr for Secret<T> {
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
@@ -0,0 +1,185 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+
+use port_decorators::Retryable;
+
+use crate::outcome::ErrorClass;
+use crate::user::validation::{PasswordViolation, ValidationErrors};
+
+/// The party a [`UserDomainError`] is attributed to, so callers (e.g. HTTP handlers) report
+/// client mistakes and server faults with distinct statuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserErrorCategory {
+    /// The request is invalid and fails the same way until the client changes it.
+    Validation,
+    /// The user does not exist.
+    NotFound,
+    /// The request conflicts with the current state of the user.
+    Conflict,
+    /// The storage failed, independently of the request.
+    Infrastructure,
+}
+
+/// The failure of the storage behind a [`UserDomainError`], kept as its source so the cause
+/// (e.g. the database error) is reported with it.
+///
+/// Storage errors compare equal whatever their cause: errors are compared by kind, e.g. in
+/// tests, as the causes are not comparable.
+#[derive(Debug, Clone)]
+pub struct StorageError(Arc<dyn Error + Send + Sync + 'static>);
+
+impl StorageError {
+    /// Creates a new `StorageError` caused by `e`.
+    pub fn new(e: impl Error + Send + Sync + 'static) -> Self {
+        Self(Arc::new(e))
+    }
+
+    /// Creates a new `StorageError` described by `message`, for storages failing without an
+    /// error of their own.
+    pub fn msg(message: impl Into<String>) -> Self {
+        Self(Arc::new(Message(message.into())))
+    }
+
+    /// Creates a new `StorageError` caused by the boxed `e`, e.g. an `eyre::Report` of a port.
+    pub fn boxed(e: Box<dyn Error + Send + Sync + 'static>) -> Self {
+        Self(Arc::from(e))
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Error for StorageError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+impl PartialEq for StorageError {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for StorageError {}
+
+/// The cause of a [`StorageError::msg`].
+#[derive(Debug)]
+struct Message(String);
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Error for Message {}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum UserDomainError {
+    /// The user violates the constraints of [`crate::user::validation`].
+    #[error("invalid user: {0}")]
+    InvalidUser(ValidationErrors),
+    /// The password of the user violates the rules of the password policy.
+    #[error("password violates the password policy")]
+    WeakPassword(Vec<PasswordViolation>),
+    /// The storage rejected the user as violating one of its constraints (e.g. a check or the
+    /// length of a column) not covered by the validation of the domain.
+    #[error("user violates a constraint of the storage")]
+    UserConstraintViolation(#[source] StorageError),
+    #[error("user not found")]
+    UserNotFound,
+    #[error("user already exists")]
+    UserAlreadyExists,
+    #[error("failed to create user")]
+    UserCreationFailed(#[source] StorageError),
+    /// The storage failed to read users, as opposed to finding none.
+    #[error("failed to read user")]
+    UserReadFailed(#[source] StorageError),
+    #[error("failed to update user")]
+    UserUpdateFailed(#[source] StorageError),
+    #[error("failed to delete user")]
+    UserDeletionFailed(#[source] StorageError),
+    #[error("failed to list users")]
+    UserListFailed(#[source] StorageError),
+    /// The storage did not complete the operation in time, e.g. no connection was available.
+    #[error("storage timed out")]
+    StorageTimeout(#[source] StorageError),
+    /// The user is under legal hold and cannot be deleted until the hold is lifted.
+    #[error("user is under legal hold")]
+    UserUnderLegalHold,
+    /// No user has the given email and password.
+    #[error("invalid credentials")]
+    InvalidCredentials,
+    /// The user was changed since the version the update expected.
+    #[error("user was changed since it was read")]
+    UserVersionMismatch,
+    /// The change was aborted by the storage as conflicting with a concurrent one (e.g. a
+    /// serialization failure or a deadlock), and may succeed when made again.
+    #[error("user was changed concurrently")]
+    UserChangeConflict(#[source] StorageError),
+}
+
+impl UserDomainError {
+    /// Returns the party the error is attributed to.
+    pub fn category(&self) -> UserErrorCategory {
+        match self {
+            UserDomainError::InvalidUser(_)
+            | UserDomainError::WeakPassword(_)
+            | UserDomainError::UserConstraintViolation(_)
+            | UserDomainError::InvalidCredentials => UserErrorCategory::Validation,
+            UserDomainError::UserNotFound => UserErrorCategory::NotFound,
+            UserDomainError::UserAlreadyExists
+            | UserDomainError::UserUnderLegalHold
+            | UserDomainError::UserVersionMismatch
+            | UserDomainError::UserChangeConflict(_) => UserErrorCategory::Conflict,
+            UserDomainError::UserCreationFailed(_)
+            | UserDomainError::UserReadFailed(_)
+            | UserDomainError::UserUpdateFailed(_)
+            | UserDomainError::UserDeletionFailed(_)
+            | UserDomainError::UserListFailed(_)
+            | UserDomainError::StorageTimeout(_) => UserErrorCategory::Infrastructure,
+        }
+    }
+}
+
+impl ErrorClass for UserDomainError {
+    fn class(&self) -> &'static str {
+        match self {
+            UserDomainError::InvalidUser(_) => "validation",
+            UserDomainError::WeakPassword(_) => "weak_password",
+            UserDomainError::UserConstraintViolation(_) => "constraint_violation",
+            UserDomainError::UserNotFound => "not_found",
+            UserDomainError::UserAlreadyExists => "conflict",
+            UserDomainError::UserUnderLegalHold => "legal_hold",
+            UserDomainError::InvalidCredentials => "invalid_credentials",
+            UserDomainError::UserVersionMismatch => "version_mismatch",
+            UserDomainError::UserChangeConflict(_) => "concurrent_change",
+            UserDomainError::StorageTimeout(_) => "timeout",
+            UserDomainError::UserCreationFailed(_)
+            | UserDomainError::UserReadFailed(_)
+            | UserDomainError::UserUpdateFailed(_)
+            | UserDomainError::UserDeletionFailed(_)
+            | UserDomainError::UserListFailed(_) => "internal",
+        }
+    }
+}
+
+impl From<ValidationErrors> for UserDomainError {
+    fn from(e: ValidationErrors) -> Self {
+        UserDomainError::InvalidUser(e)
+    }
+}
+
+impl Retryable for UserDomainError {
+    /// Infrastructure failures may be transient (e.g. a lost database connection), like
+    /// changes aborted by concurrent ones, while invalid, missing or conflicting users will
+    /// fail the same way again.
+    fn is_retryable(&self) -> bool {
+        self.category() == UserErrorCategory::Infrastructure || matches!(self, UserDomainError::UserChangeConflict(_))
+    }
+}
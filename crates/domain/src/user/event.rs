@@ -0,0 +1,38 @@
+use crate::user::model::{User, UserId};
+
+/// Changes to users, published to downstream systems once made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserEvent {
+    /// A user was created.
+    UserCreated(User),
+    /// A user was updated; carries the user as updated.
+    UserUpdated(User),
+    /// A user was deleted; the user can still be restored.
+    UserDeleted(UserId),
+    /// A deleted user was restored; carries the user as restored.
+    UserRestored(User),
+    /// A user was deleted for good, along with the user's data.
+    UserHardDeleted(UserId),
+}
+
+impl UserEvent {
+    /// Returns the stable type of the event, e.g. `user.created`, used as the topic it is
+    /// published to.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            UserEvent::UserCreated(_) => "user.created",
+            UserEvent::UserUpdated(_) => "user.updated",
+            UserEvent::UserDeleted(_) => "user.deleted",
+            UserEvent::UserRestored(_) => "user.restored",
+            UserEvent::UserHardDeleted(_) => "user.hard_deleted",
+        }
+    }
+
+    /// Returns the id of the user the event is about.
+    pub fn user_id(&self) -> UserId {
+        match self {
+            UserEvent::UserCreated(user) | UserEvent::UserUpdated(user) | UserEvent::UserRestored(user) => user.id(),
+            UserEvent::UserDeleted(id) | UserEvent::UserHardDeleted(id) => *id,
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod model;
+pub mod repository;
+pub mod error;
+pub mod event;
+pub mod validation;
@@ -0,0 +1,686 @@
+use std::fmt;
+use std::str::FromStr;
+
+use uuid::Uuid;
+
+use crate::collation;
+use crate::secret::Secret;
+use crate::user::validation::{validate_age, validate_email, validate_name, validate_password, ValidationErrors};
+
+/// Unique identifier of a user, a UUID.
+///
+/// Identifiers are stored and exposed in their hyphenated form, see [`UserId::parse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(try_from = "String", into = "String"))]
+pub struct UserId(Uuid);
+
+impl UserId {
+    /// Generates a new random identifier.
+    pub fn generate() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    /// Parses an identifier, or returns the error of the `id` field if it is not a UUID.
+    pub fn parse(value: &str) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        match Uuid::parse_str(value) {
+            Ok(uuid) => Ok(Self(uuid)),
+            Err(_) => {
+                errors.check("id", Err("must be a UUID".to_string()));
+                Err(errors)
+            }
+        }
+    }
+
+    /// Returns the identifier as a UUID.
+    pub fn as_uuid(&self) -> &Uuid {
+        &self.0
+    }
+}
+
+impl From<Uuid> for UserId {
+    fn from(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+}
+
+impl FromStr for UserId {
+    type Err = ValidationErrors;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+    }
+}
+
+impl TryFrom<String> for UserId {
+    type Error = ValidationErrors;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value)
+    }
+}
+
+impl From<UserId> for String {
+    fn from(id: UserId) -> Self {
+        id.to_string()
+    }
+}
+
+impl fmt::Display for UserId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.hyphenated().fmt(f)
+    }
+}
+
+/// Email address of a user, checked by [`validate_email`].
+///
+/// The address is kept as given; lookups by email ignore case and accents, see
+/// [`crate::user::repository::UserRepositoryPort::get_user_by_email`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(try_from = "String", into = "String"))]
+pub struct Email(String);
+
+impl Email {
+    /// Parses an email address, or returns the error of the `email` field if it is malformed.
+    pub fn parse(value: impl Into<String>) -> Result<Self, ValidationErrors> {
+        let value = value.into();
+        let mut errors = ValidationErrors::new();
+        errors.check("email", validate_email(&value));
+        errors.into_result(Self(value))
+    }
+
+    /// Returns the address as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the domain of the address, the part after its first `@`, as written.
+    pub fn domain(&self) -> &str {
+        self.0.split_once('@').map_or("", |(_, domain)| domain)
+    }
+}
+
+impl FromStr for Email {
+    type Err = ValidationErrors;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse(value)
+    }
+}
+
+impl TryFrom<String> for Email {
+    type Error = ValidationErrors;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(value)
+    }
+}
+
+impl From<Email> for String {
+    fn from(email: Email) -> Self {
+        email.0
+    }
+}
+
+impl AsRef<str> for Email {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Password a user logs in with, checked by [`validate_password`]. It is only kept until it
+/// is hashed, and is never printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Password(Secret<String>);
+
+impl Password {
+    /// Parses a password, or returns the error of the `password` field if it is too short or too long.
+    pub fn parse(value: impl Into<String>) -> Result<Self, ValidationErrors> {
+        let value = value.into();
+        let mut errors = ValidationErrors::new();
+        errors.check("password", validate_password(&value));
+        errors.into_result(Self(Secret::new(value)))
+    }
+
+    /// Returns the password as a string slice.
+    pub fn as_str(&self) -> &str {
+        self.0.expose_secret()
+    }
+}
+
+/// Hash of the password of a user, in the PHC string format (e.g. `$argon2id$v=19$...`), as
+/// stored by the repository. It is never printed either.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordHash(Secret<String>);
+
+impl PasswordHash {
+    /// Wraps a hash produced by a password hasher, or read back from storage.
+    pub fn new(hash: impl Into<String>) -> Self {
+        Self(Secret::new(hash.into()))
+    }
+
+    /// Returns the hash as a string slice.
+    pub fn as_str(&self) -> &str {
+        self.0.expose_secret()
+    }
+}
+
+/// Both value objects are bound and decoded as text, matching the `VARCHAR` columns of
+/// `users`; decoding checks the stored value like [`UserId::parse`] and [`Email::parse`].
+#[cfg(feature = "sqlx")]
+mod sqlx_impls {
+    use sqlx::encode::IsNull;
+    use sqlx::error::BoxDynError;
+    use sqlx::{Database, Decode, Encode, Type};
+
+    use super::{Email, UserId};
+
+    impl<DB: Database> Type<DB> for UserId
+    where
+        String: Type<DB>,
+    {
+        fn type_info() -> DB::TypeInfo {
+            <String as Type<DB>>::type_info()
+        }
+
+        fn compatible(ty: &DB::TypeInfo) -> bool {
+            <String as Type<DB>>::compatible(ty)
+        }
+    }
+
+    impl<'q, DB: Database> Encode<'q, DB> for UserId
+    where
+        String: Encode<'q, DB>,
+    {
+        fn encode_by_ref(&self, buf: &mut <DB as Database>::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+            self.to_string().encode_by_ref(buf)
+        }
+    }
+
+    impl<'r, DB: Database> Decode<'r, DB> for UserId
+    where
+        String: Decode<'r, DB>,
+    {
+        fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+            Ok(UserId::try_from(String::decode(value)?)?)
+        }
+    }
+
+    impl<DB: Database> Type<DB> for Email
+    where
+        String: Type<DB>,
+    {
+        fn type_info() -> DB::TypeInfo {
+            <String as Type<DB>>::type_info()
+        }
+
+        fn compatible(ty: &DB::TypeInfo) -> bool {
+            <String as Type<DB>>::compatible(ty)
+        }
+    }
+
+    impl<'q, DB: Database> Encode<'q, DB> for Email
+    where
+        String: Encode<'q, DB>,
+    {
+        fn encode_by_ref(&self, buf: &mut <DB as Database>::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+            self.0.encode_by_ref(buf)
+        }
+    }
+
+    impl<'r, DB: Database> Decode<'r, DB> for Email
+    where
+        String: Decode<'r, DB>,
+    {
+        fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+            Ok(Email::try_from(String::decode(value)?)?)
+        }
+    }
+}
+
+/// Role of a user, stored with the user and granted to them on login, along with the roles
+/// of their directory and groups. Routes declare the roles they require.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "snake_case"))]
+pub enum Role {
+    /// Administers the service and its users.
+    Admin,
+    /// A regular user, the role of new users.
+    #[default]
+    User,
+}
+
+impl Role {
+    /// Every role.
+    pub const ALL: [Role; 2] = [Role::Admin, Role::User];
+
+    /// Returns the name of the role, as carried by tokens and stored by adapters.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::User => "user",
+        }
+    }
+
+    /// Parses the name of a role, as returned by [`Role::as_str`].
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|role| role.as_str() == value)
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Domain model representing a User entity.
+///
+/// This is the core domain entity that encapsulates user business logic and data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    id: UserId,
+    name: String,
+    email: Email,
+    age: u8,
+    legal_hold: bool,
+    role: Role,
+    version: u64,
+}
+
+impl User {
+    /// Version of a user never changed since its creation.
+    pub const INITIAL_VERSION: u64 = 1;
+
+    /// Creates a new `User` instance with the [`Role::User`] role, not under legal hold, at its
+    /// initial version.
+    pub fn new(id: UserId, name: String, email: Email, age: u8) -> Self {
+        Self { id, name, email, age, legal_hold: false, role: Role::User, version: Self::INITIAL_VERSION }
+    }
+
+    /// Sets whether the user is under legal hold.
+    pub fn with_legal_hold(mut self, legal_hold: bool) -> Self {
+        self.legal_hold = legal_hold;
+        self
+    }
+
+    /// Sets the role of the user.
+    pub fn with_role(mut self, role: Role) -> Self {
+        self.role = role;
+        self
+    }
+
+    /// Sets the version of the user.
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Returns the user's unique identifier.
+    pub fn id(&self) -> UserId {
+        self.id
+    }
+
+    /// Returns the user's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the user's email address.
+    pub fn email(&self) -> &Email {
+        &self.email
+    }
+
+    /// Returns the user's age.
+    pub fn age(&self) -> u8 {
+        self.age
+    }
+
+    /// Returns whether the user is under legal hold. Users under legal hold cannot be deleted.
+    pub fn legal_hold(&self) -> bool {
+        self.legal_hold
+    }
+
+    /// Returns the role of the user.
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Returns the version of the user, incremented by every change, so concurrent changes
+    /// can be detected.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+/// Data transfer object for creating a new user.
+///
+/// This struct represents the data required to create a user in the system. Build it with
+/// [`CreateUser::new`], which enforces the constraints of [`crate::user::validation`].
+pub struct CreateUser {
+    /// The user's full name.
+    pub name: String,
+    /// The user's email address.
+    pub email: Email,
+    /// The user's age.
+    pub age: u8,
+    /// The password the user logs in with, if any. Users without one (e.g. provisioned
+    /// through SCIM) log in by other means.
+    pub password: Option<Password>,
+}
+
+impl CreateUser {
+    /// Creates a new `CreateUser` without a password, or returns every constraint the fields violate.
+    pub fn new(name: String, email: String, age: u8) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.check("name", validate_name(&name));
+        errors.check("email", validate_email(&email));
+        errors.check("age", validate_age(age));
+        errors.into_result(Self { name, email: Email(email), age, password: None })
+    }
+
+    /// Sets the password the user logs in with, or returns the constraint it violates.
+    pub fn with_password(self, password: String) -> Result<Self, ValidationErrors> {
+        Ok(Self { password: Some(Password::parse(password)?), ..self })
+    }
+}
+
+/// A user with the hash of the password the user logs in with, if the user has one.
+///
+/// Returned by [`crate::user::repository::UserRepositoryPort::get_user_credentials`] only, so
+/// the hash stays out of the users returned by other reads, and of their events and caches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserCredentials {
+    pub user: User,
+    pub password_hash: Option<PasswordHash>,
+}
+
+/// Data transfer object for updating an existing user.
+///
+/// This struct represents partial update data for a user. All fields are optional,
+/// and the ones given are checked by [`UpdateUser::new`] like those of [`CreateUser::new`].
+pub struct UpdateUser {
+    /// The unique identifier of the user to update.
+    pub id: UserId,
+    /// Optional new name for the user. If `None`, the existing name is preserved.
+    pub name: Option<String>,
+    /// Optional new email for the user. If `None`, the existing email is preserved.
+    pub email: Option<Email>,
+    /// Optional new age for the user. If `None`, the existing age is preserved.
+    pub age: Option<u8>,
+    /// Version the user must still be at for the update to apply, e.g. the version it was read
+    /// at. If `None`, the user is updated whatever its version.
+    pub expected_version: Option<u64>,
+}
+
+impl UpdateUser {
+    /// Creates a new `UpdateUser`, or returns every constraint the given fields violate.
+    pub fn new(id: UserId, name: Option<String>, email: Option<String>, age: Option<u8>) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if let Some(name) = &name {
+            errors.check("name", validate_name(name));
+        }
+        if let Some(email) = &email {
+            errors.check("email", validate_email(email));
+        }
+        if let Some(age) = age {
+            errors.check("age", validate_age(age));
+        }
+        errors.into_result(Self { id, name, email: email.map(Email), age, expected_version: None })
+    }
+
+    /// Makes the update apply only while the user is at `version`.
+    pub fn with_expected_version(self, version: u64) -> Self {
+        Self { expected_version: Some(version), ..self }
+    }
+}
+/// Field a list of users is ordered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UserSortField {
+    /// Case- and accent-insensitive order of names.
+    #[default]
+    Name,
+    /// Case- and accent-insensitive order of emails.
+    Email,
+    /// Numeric order of ages.
+    Age,
+}
+
+/// Direction of a sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Value of the field a list of users is ordered by, for a given user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserSortKey {
+    Name(String),
+    Email(String),
+    Age(u8),
+}
+
+/// Position of a user in a list of users: the value of the field the list is ordered by and
+/// the id of the user, which orders the users with an equal value.
+///
+/// Unlike an offset, the page after a position starts with the same user whatever users were
+/// created or deleted before the position in the meantime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserPosition {
+    pub key: UserSortKey,
+    pub id: UserId,
+}
+
+impl UserPosition {
+    /// Returns the position of `user` in a list ordered by `sort_by`.
+    pub fn of(user: &User, sort_by: UserSortField) -> Self {
+        let key = match sort_by {
+            UserSortField::Name => UserSortKey::Name(user.name().to_string()),
+            UserSortField::Email => UserSortKey::Email(user.email().as_str().to_string()),
+            UserSortField::Age => UserSortKey::Age(user.age()),
+        };
+        Self { key, id: user.id() }
+    }
+}
+
+/// Query for a page of users.
+///
+/// Users with an equal sort key are ordered by id, so pages are stable between requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListUsers {
+    /// Maximum number of users returned.
+    pub limit: u32,
+    /// Number of users skipped before the first returned one.
+    pub offset: u64,
+    /// Field the users are ordered by.
+    pub sort_by: UserSortField,
+    /// Direction the users are ordered in.
+    pub direction: SortDirection,
+    /// Position the page starts after, in the order of the query, `None` to start from the
+    /// first user. The `offset` is counted from the position.
+    pub after: Option<UserPosition>,
+}
+
+/// A page of users together with the total number of users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserPage {
+    /// The users of the requested page, in order.
+    pub users: Vec<User>,
+    /// Total number of users, regardless of the page.
+    pub total: u64,
+}
+
+/// Search for a page of the users matching every given criterion.
+///
+/// Matching users are ordered by name, then by id, and the `total` of the returned
+/// [`UserPage`] counts the matching users only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserFilter {
+    /// Text the name contains, ignoring case and accents.
+    pub name: Option<String>,
+    /// Text the email contains, ignoring case and accents.
+    pub email: Option<String>,
+    /// Lowest age, inclusive.
+    pub min_age: Option<u8>,
+    /// Highest age, inclusive.
+    pub max_age: Option<u8>,
+    /// Maximum number of users returned.
+    pub limit: u32,
+    /// Number of matching users skipped before the first returned one.
+    pub offset: u64,
+    /// Position, in the order of the names, the page starts after, `None` to start from the
+    /// first matching user. The `offset` is counted from the position.
+    pub after: Option<UserPosition>,
+}
+
+impl UserFilter {
+    /// Returns `true` if `user` matches every criterion of the filter.
+    pub fn matches(&self, user: &User) -> bool {
+        let contains = |value: &str, text: &Option<String>| text.as_ref().is_none_or(|text| collation::fold(value).contains(&collation::fold(text)));
+
+        contains(user.name(), &self.name)
+            && contains(user.email().as_str(), &self.email)
+            && self.min_age.is_none_or(|min_age| user.age() >= min_age)
+            && self.max_age.is_none_or(|max_age| user.age() <= max_age)
+    }
+}
+
+/// Status of a user, as counted by [`UserFacets`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UserStatus {
+    /// The user is not under legal hold.
+    Active,
+    /// The user is under legal hold, see [`User::legal_hold`].
+    LegalHold,
+}
+
+impl UserStatus {
+    /// Every status, in the order facets list them.
+    pub const ALL: [UserStatus; 2] = [UserStatus::Active, UserStatus::LegalHold];
+
+    /// Returns the status of `user`.
+    pub fn of(user: &User) -> Self {
+        if user.legal_hold() {
+            UserStatus::LegalHold
+        } else {
+            UserStatus::Active
+        }
+    }
+
+    /// Returns the name of the status, as exposed by the API and stored by adapters.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserStatus::Active => "active",
+            UserStatus::LegalHold => "legal_hold",
+        }
+    }
+}
+
+/// Range of ages users are counted in by [`UserFacets`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AgeBucket {
+    Under18,
+    From18To24,
+    From25To34,
+    From35To44,
+    From45To54,
+    From55To64,
+    From65,
+}
+
+impl AgeBucket {
+    /// Every bucket, from the youngest to the oldest ages.
+    pub const ALL: [AgeBucket; 7] = [
+        AgeBucket::Under18,
+        AgeBucket::From18To24,
+        AgeBucket::From25To34,
+        AgeBucket::From35To44,
+        AgeBucket::From45To54,
+        AgeBucket::From55To64,
+        AgeBucket::From65,
+    ];
+
+    /// Returns the bucket `age` falls in.
+    pub fn of(age: u8) -> Self {
+        match age {
+            0..=17 => AgeBucket::Under18,
+            18..=24 => AgeBucket::From18To24,
+            25..=34 => AgeBucket::From25To34,
+            35..=44 => AgeBucket::From35To44,
+            45..=54 => AgeBucket::From45To54,
+            55..=64 => AgeBucket::From55To64,
+            _ => AgeBucket::From65,
+        }
+    }
+
+    /// Returns the name of the bucket, as exposed by the API and stored by adapters.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AgeBucket::Under18 => "under_18",
+            AgeBucket::From18To24 => "18_24",
+            AgeBucket::From25To34 => "25_34",
+            AgeBucket::From35To44 => "35_44",
+            AgeBucket::From45To54 => "45_54",
+            AgeBucket::From55To64 => "55_64",
+            AgeBucket::From65 => "65_plus",
+        }
+    }
+}
+
+/// Maximum number of email domains counted by [`UserFacets`].
+pub const MAX_EMAIL_DOMAIN_FACETS: usize = 10;
+
+/// Number of users having a given facet value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FacetCount<V> {
+    pub value: V,
+    pub count: u64,
+}
+
+/// Numbers of users by status, by email domain and by age bucket.
+///
+/// Every status and age bucket is listed, with a count of zero when no user has it. Email
+/// domains (lowercased) are limited to the [`MAX_EMAIL_DOMAIN_FACETS`] most common ones,
+/// ordered by decreasing count then by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserFacets {
+    pub by_status: Vec<FacetCount<UserStatus>>,
+    pub by_email_domain: Vec<FacetCount<String>>,
+    pub by_age_bucket: Vec<FacetCount<AgeBucket>>,
+}
+
+impl UserFacets {
+    /// Creates `UserFacets` from the counts of the values users have, in any order, listing the
+    /// missing statuses and age buckets and keeping the most common email domains.
+    pub fn from_counts(
+        by_status: impl IntoIterator<Item = (UserStatus, u64)>,
+        by_email_domain: impl IntoIterator<Item = (String, u64)>,
+        by_age_bucket: impl IntoIterator<Item = (AgeBucket, u64)>,
+    ) -> Self {
+        let by_status: Vec<_> = by_status.into_iter().collect();
+        let by_age_bucket: Vec<_> = by_age_bucket.into_iter().collect();
+
+        let mut by_email_domain: Vec<_> = by_email_domain.into_iter().map(|(value, count)| FacetCount { value, count }).collect();
+        by_email_domain.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        by_email_domain.truncate(MAX_EMAIL_DOMAIN_FACETS);
+
+        Self {
+            by_status: UserStatus::ALL.into_iter().map(|value| FacetCount { value, count: count_of(&by_status, &value) }).collect(),
+            by_email_domain,
+            by_age_bucket: AgeBucket::ALL.into_iter().map(|value| FacetCount { value, count: count_of(&by_age_bucket, &value) }).collect(),
+        }
+    }
+}
+
+/// Sums the counts of `value` in `counts`.
+fn count_of<V: PartialEq>(counts: &[(V, u64)], value: &V) -> u64 {
+    counts.iter().filter(|(v, _)| v == value).map(|(_, count)| count).sum()
+}
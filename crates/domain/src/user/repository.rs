@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use port_decorators::instrumented_port;
+
+use crate::user::{error::UserDomainError, model::{CreateUser, Email, ListUsers, PasswordHash, Role, UpdateUser, User, UserCredentials, UserFacets, UserFilter, UserId, UserPage}};
+
+/// Repository port (interface) for user data access operations.
+///
+/// This trait defines the contract for persisting and retrieving user data without
+/// specifying implementation details. It follows the Repository Pattern and
+/// Hexagonal Architecture principles, acting as a port that can be implemented
+/// by various adapters (e.g., PostgreSQL, MongoDB, in-memory storage).
+///
+/// Any implementation can be wrapped in the generated `InstrumentedUserRepository` to record
+/// call timings and retry reads that fail with an internal error.
+#[instrumented_port]
+#[async_trait]
+pub trait UserRepositoryPort {
+    /// Creates a new user in the repository, with the hash of the user's password if the user
+    /// has one. The plain password of `user` is never stored.
+    async fn create_user(&self, user: CreateUser, password_hash: Option<PasswordHash>) -> Result<User, UserDomainError>;
+
+    /// Creates users like [`UserRepositoryPort::create_user`], returning the outcome of each
+    /// user in order: a user failing to be created does not fail the others.
+    ///
+    /// Creates them one at a time, unless the repository inserts them in batches.
+    async fn create_users(&self, users: Vec<(CreateUser, Option<PasswordHash>)>) -> Vec<Result<User, UserDomainError>> {
+        let mut created = Vec::with_capacity(users.len());
+        for (user, password_hash) in users {
+            created.push(self.create_user(user, password_hash).await);
+        }
+        created
+    }
+
+    /// Retrieves a user by their unique identifier.
+    #[port(retry)]
+    async fn get_user(&self, id: UserId) -> Result<User, UserDomainError>;
+
+    /// Retrieves a user by email address, ignoring case and accents.
+    #[port(retry)]
+    async fn get_user_by_email(&self, email: Email) -> Result<User, UserDomainError>;
+
+    /// Retrieves a user by email address like [`UserRepositoryPort::get_user_by_email`], with the
+    /// hash of the user's password.
+    #[port(retry)]
+    async fn get_user_credentials(&self, email: Email) -> Result<UserCredentials, UserDomainError>;
+
+    /// Retrieves a page of users in the requested order, with the total number of users.
+    #[port(retry)]
+    async fn list_users(&self, query: ListUsers) -> Result<UserPage, UserDomainError>;
+
+    /// Retrieves a page of the users matching the filter, with the total number of matching users.
+    #[port(retry)]
+    async fn search_users(&self, filter: UserFilter) -> Result<UserPage, UserDomainError>;
+
+    /// Counts the users by status, by email domain and by age bucket.
+    #[port(retry)]
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError>;
+
+    /// Updates an existing user in the repository.
+    async fn update_user(&self, user: UpdateUser) -> Result<User, UserDomainError>;
+
+    /// Soft-deletes a user: the user is kept, but left out of every read and change until restored.
+    async fn delete_user(&self, id: UserId) -> Result<(), UserDomainError>;
+
+    /// Restores a soft-deleted user, failing with `UserNotFound` unless the user is soft-deleted.
+    async fn restore_user(&self, id: UserId) -> Result<User, UserDomainError>;
+
+    /// Deletes a user from the repository for good, whether soft-deleted or not.
+    async fn hard_delete_user(&self, id: UserId) -> Result<(), UserDomainError>;
+
+    /// Places the user under legal hold, or lifts the hold.
+    async fn set_legal_hold(&self, id: UserId, legal_hold: bool) -> Result<User, UserDomainError>;
+
+    /// Sets the role of the user.
+    async fn set_role(&self, id: UserId, role: Role) -> Result<User, UserDomainError>;
+}
+
+/// Shared repositories are repositories too, so services can be generic over the port and still
+/// receive an `Arc<dyn UserRepositoryPort + Send + Sync>` when dynamic dispatch is preferred.
+#[async_trait]
+impl<T> UserRepositoryPort for Arc<T>
+where
+    T: UserRepositoryPort + Send + Sync + ?Sized,
+{
+    async fn create_user(&self, user: CreateUser, password_hash: Option<PasswordHash>) -> Result<User, UserDomainError> {
+        (**self).create_user(user, password_hash).await
+    }
+
+    async fn create_users(&self, users: Vec<(CreateUser, Option<PasswordHash>)>) -> Vec<Result<User, UserDomainError>> {
+        (**self).create_users(users).await
+    }
+
+    async fn get_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        (**self).get_user(id).await
+    }
+
+    async fn get_user_by_email(&self, email: Email) -> Result<User, UserDomainError> {
+        (**self).get_user_by_email(email).await
+    }
+
+    async fn get_user_credentials(&self, email: Email) -> Result<UserCredentials, UserDomainError> {
+        (**self).get_user_credentials(email).await
+    }
+
+    async fn list_users(&self, query: ListUsers) -> Result<UserPage, UserDomainError> {
+        (**self).list_users(query).await
+    }
+
+    async fn search_users(&self, filter: UserFilter) -> Result<UserPage, UserDomainError> {
+        (**self).search_users(filter).await
+    }
+
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError> {
+        (**self).count_user_facets().await
+    }
+
+    async fn update_user(&self, user: UpdateUser) -> Result<User, UserDomainError> {
+        (**self).update_user(user).await
+    }
+
+    async fn delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        (**self).delete_user(id).await
+    }
+
+    async fn restore_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        (**self).restore_user(id).await
+    }
+
+    async fn hard_delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        (**self).hard_delete_user(id).await
+    }
+
+    async fn set_legal_hold(&self, id: UserId, legal_hold: bool) -> Result<User, UserDomainError> {
+        (**self).set_legal_hold(id, legal_hold).await
+    }
+
+    async fn set_role(&self, id: UserId, role: Role) -> Result<User, UserDomainError> {
+        (**self).set_role(id, role).await
+    }
+}
@@ -0,0 +1,131 @@
+//! Constraints on user attributes, enforced by the domain constructors and reused to
+//! validate requests before they reach the domain.
+
+use std::fmt;
+
+/// Maximum length of names, in characters, matching the `users.name` column.
+pub const MAX_NAME_LENGTH: usize = 255;
+
+/// Maximum length of email addresses, in characters, matching the `users.email` column.
+pub const MAX_EMAIL_LENGTH: usize = 255;
+
+/// Smallest accepted age.
+pub const MIN_AGE: u8 = 1;
+
+/// Largest accepted age.
+pub const MAX_AGE: u8 = 150;
+
+/// Minimum length of passwords, in characters.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Maximum length of passwords, in characters, bounding the work of hashing them.
+pub const MAX_PASSWORD_LENGTH: usize = 128;
+
+/// A constraint violated by the value of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+/// Every constraint violated by a set of fields.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    /// Creates an empty set of errors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the error of `result`, if any, against `field`.
+    pub fn check(&mut self, field: &'static str, result: Result<(), String>) {
+        if let Err(message) = result {
+            self.0.push(FieldError { field, message });
+        }
+    }
+
+    /// Returns the recorded errors, in the order they were found.
+    pub fn errors(&self) -> &[FieldError] {
+        &self.0
+    }
+
+    /// Returns `value` if no error was recorded, or the errors otherwise.
+    pub fn into_result<T>(self, value: T) -> Result<T, Self> {
+        if self.0.is_empty() { Ok(value) } else { Err(self) }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self.0.iter().map(|error| format!("{} {}", error.field, error.message)).collect();
+        write!(f, "{}", errors.join(", "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// A rule of the password policy violated by a password.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordViolation {
+    /// Stable identifier of the rule, e.g. `min_length`, for clients to react to.
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// Names must contain a non-whitespace character and fit the `users.name` column.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("must not be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("must be at most {} characters", MAX_NAME_LENGTH));
+    }
+    Ok(())
+}
+
+/// Emails must have the shape `local@domain.tld`, without whitespace, and fit the `users.email` column.
+///
+/// This only rejects addresses that are obviously malformed; whether an address exists can
+/// only be known by sending it an e-mail.
+pub fn validate_email(email: &str) -> Result<(), String> {
+    if email.chars().count() > MAX_EMAIL_LENGTH {
+        return Err(format!("must be at most {} characters", MAX_EMAIL_LENGTH));
+    }
+
+    let valid = match email.split_once('@') {
+        Some((local, domain)) => {
+            let labels: Vec<&str> = domain.split('.').collect();
+            !local.is_empty()
+                && !local.chars().any(|c| c.is_whitespace() || c == '@')
+                && labels.len() >= 2
+                && labels.iter().all(|label| {
+                    !label.is_empty()
+                        && !label.starts_with('-')
+                        && !label.ends_with('-')
+                        && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+                })
+        }
+        None => false,
+    };
+    if valid { Ok(()) } else { Err("must be a valid email address".to_string()) }
+}
+
+/// Ages must be between [`MIN_AGE`] and [`MAX_AGE`].
+pub fn validate_age(age: u8) -> Result<(), String> {
+    if (MIN_AGE..=MAX_AGE).contains(&age) {
+        Ok(())
+    } else {
+        Err(format!("must be between {} and {}", MIN_AGE, MAX_AGE))
+    }
+}
+
+/// Passwords must be between [`MIN_PASSWORD_LENGTH`] and [`MAX_PASSWORD_LENGTH`] characters.
+pub fn validate_password(password: &str) -> Result<(), String> {
+    let length = password.chars().count();
+    if (MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&length) {
+        Ok(())
+    } else {
+        Err(format!("must be between {} and {} characters", MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH))
+    }
+}
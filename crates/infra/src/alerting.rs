@@ -0,0 +1,12 @@
+use application::ports::alert::{Alert, AlertPort};
+
+/// Alert adapter writing alerts to the logs at the `warn` level, with their name in the
+/// `alert.name` field for log-based alerting rules.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogAlerts;
+
+impl AlertPort for LogAlerts {
+    fn raise(&self, alert: Alert) {
+        tracing::warn!(alert.name = alert.name, "alert: {}", alert.message);
+    }
+}
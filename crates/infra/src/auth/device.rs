@@ -0,0 +1,210 @@
+//! Pending requests of the device authorization grant (RFC 8628), kept in memory.
+//!
+//! Requests only live for a few minutes, so they are not persisted: the device and the
+//! verification page must reach the same replica, and requests pending at a restart are lost,
+//! the device starting over once its code expires.
+//!
+//! Codes expire with the time of a [`ClockPort`], so a test clock expires them too; the
+//! polling interval follows the monotonic time of the system.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use rand::distributions::{Alphanumeric, DistString};
+use rand::seq::SliceRandom;
+
+use application::ports::clock::{ClockPort, SystemClock};
+use application::ports::device::{DeviceApproval, DeviceCodes, DeviceGrantError, DeviceGrantPort};
+
+use crate::auth::DeviceGrantConfig;
+
+/// Characters of user codes: consonants only, so codes are unambiguous and spell no words
+/// (RFC 8628, section 6.1).
+const USER_CODE_CHARACTERS: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+
+/// Length of user codes, without their separator.
+const USER_CODE_LENGTH: usize = 8;
+
+/// Length of device codes.
+const DEVICE_CODE_LENGTH: usize = 40;
+
+/// Increase of the polling interval of a device polling too fast (RFC 8628, section 3.5).
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+/// Maximum number of requests pending at once; issuing more fails until some complete or
+/// expire, bounding the memory used by abandoned requests.
+const MAX_PENDING_REQUESTS: usize = 10_000;
+
+enum Decision {
+    Pending,
+    Approved(DeviceApproval),
+    Denied,
+}
+
+struct PendingRequest {
+    client_id: String,
+    user_code: String,
+    expires_at: DateTime<Utc>,
+    interval: Duration,
+    last_polled_at: Option<Instant>,
+    decision: Decision,
+}
+
+#[derive(Default)]
+struct Requests {
+    by_device_code: HashMap<String, PendingRequest>,
+    /// Device codes by normalized user code.
+    device_codes: HashMap<String, String>,
+}
+
+impl Requests {
+    fn remove(&mut self, device_code: &str) {
+        if let Some(request) = self.by_device_code.remove(device_code) {
+            self.device_codes.remove(&request.user_code);
+        }
+    }
+
+    fn purge_expired(&mut self, now: DateTime<Utc>) {
+        self.by_device_code.retain(|_, request| request.expires_at > now);
+        let by_device_code = &self.by_device_code;
+        self.device_codes.retain(|_, device_code| by_device_code.contains_key(device_code));
+    }
+
+    /// Records the decision on the pending request with the given user code, unless expired at `now`.
+    fn decide(&mut self, user_code: &str, decision: Decision, now: DateTime<Utc>) -> Result<(), DeviceGrantError> {
+        let request = self
+            .device_codes
+            .get(&normalize_user_code(user_code))
+            .and_then(|device_code| self.by_device_code.get_mut(device_code))
+            .filter(|request| request.expires_at > now && matches!(request.decision, Decision::Pending))
+            .ok_or(DeviceGrantError::InvalidUserCode)?;
+        request.decision = decision;
+        Ok(())
+    }
+}
+
+/// Device authorization requests kept in the memory of the server.
+pub struct InMemoryDeviceGrants {
+    lifetime: Duration,
+    interval: Duration,
+    requests: Mutex<Requests>,
+    clock: Arc<dyn ClockPort + Send + Sync>,
+}
+
+impl InMemoryDeviceGrants {
+    /// Creates a new `InMemoryDeviceGrants` instance.
+    pub fn new(config: &DeviceGrantConfig) -> Self {
+        Self {
+            lifetime: Duration::from_secs(config.code_lifetime_secs),
+            interval: Duration::from_secs(config.poll_interval_secs),
+            requests: Mutex::new(Requests::default()),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Expires the codes with the time of `clock` instead of the time of the system.
+    pub fn with_clock(mut self, clock: Arc<dyn ClockPort + Send + Sync>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+impl DeviceGrantPort for InMemoryDeviceGrants {
+    fn issue(&self, client_id: &str) -> Result<DeviceCodes, DeviceGrantError> {
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        let now = self.clock.now();
+        requests.purge_expired(now);
+        if requests.by_device_code.len() >= MAX_PENDING_REQUESTS {
+            tracing::warn!("too many pending device authorization requests");
+            return Err(DeviceGrantError::Unavailable);
+        }
+
+        let mut rng = rand::thread_rng();
+        let user_code = loop {
+            let code: String = (0..USER_CODE_LENGTH)
+                .map(|_| *USER_CODE_CHARACTERS.choose(&mut rng).unwrap() as char)
+                .collect();
+            if !requests.device_codes.contains_key(&code) {
+                break code;
+            }
+        };
+        let device_code = Alphanumeric.sample_string(&mut rng, DEVICE_CODE_LENGTH);
+
+        requests.device_codes.insert(user_code.clone(), device_code.clone());
+        requests.by_device_code.insert(
+            device_code.clone(),
+            PendingRequest {
+                client_id: client_id.to_string(),
+                user_code: user_code.clone(),
+                expires_at: TimeDelta::from_std(self.lifetime).ok().and_then(|lifetime| now.checked_add_signed(lifetime)).unwrap_or(DateTime::<Utc>::MAX_UTC),
+                interval: self.interval,
+                last_polled_at: None,
+                decision: Decision::Pending,
+            },
+        );
+
+        Ok(DeviceCodes {
+            device_code,
+            user_code: format!("{}-{}", &user_code[..USER_CODE_LENGTH / 2], &user_code[USER_CODE_LENGTH / 2..]),
+            expires_in: self.lifetime,
+            interval: self.interval,
+        })
+    }
+
+    fn approve(&self, user_code: &str, approval: DeviceApproval) -> Result<(), DeviceGrantError> {
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        requests.decide(user_code, Decision::Approved(approval), self.clock.now())
+    }
+
+    fn deny(&self, user_code: &str) -> Result<(), DeviceGrantError> {
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        requests.decide(user_code, Decision::Denied, self.clock.now())
+    }
+
+    fn poll(&self, device_code: &str, client_id: &str) -> Result<DeviceApproval, DeviceGrantError> {
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let request = requests
+            .by_device_code
+            .get_mut(device_code)
+            .filter(|request| request.client_id == client_id)
+            .ok_or(DeviceGrantError::InvalidGrant)?;
+
+        if request.expires_at <= self.clock.now() {
+            requests.remove(device_code);
+            return Err(DeviceGrantError::ExpiredToken);
+        }
+        let polled_too_fast = request
+            .last_polled_at
+            .is_some_and(|last_polled_at| now.duration_since(last_polled_at) < request.interval);
+        request.last_polled_at = Some(now);
+        if polled_too_fast {
+            request.interval += SLOW_DOWN_INCREMENT;
+            return Err(DeviceGrantError::SlowDown);
+        }
+
+        match &request.decision {
+            Decision::Pending => Err(DeviceGrantError::AuthorizationPending),
+            Decision::Approved(approval) => {
+                let approval = approval.clone();
+                requests.remove(device_code);
+                Ok(approval)
+            }
+            Decision::Denied => {
+                requests.remove(device_code);
+                Err(DeviceGrantError::AccessDenied)
+            }
+        }
+    }
+}
+
+/// Normalizes a user code as typed by a user: case-insensitive, ignoring separators and spaces.
+fn normalize_user_code(user_code: &str) -> String {
+    user_code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
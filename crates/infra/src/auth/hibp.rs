@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eyre::Context;
+use reqwest::Client;
+use ring::digest::{digest, SHA1_FOR_LEGACY_USE_ONLY};
+
+use application::ports::breached_passwords::BreachedPasswordsPort;
+
+use crate::auth::BreachCheckConfig;
+
+/// Length of the prefix of the SHA-1 hashes of passwords sent to the API.
+const PREFIX_LENGTH: usize = 5;
+
+/// Breached passwords looked up in the Pwned Passwords range API of Have I Been Pwned.
+///
+/// Passwords are looked up with k-anonymity: only the first 5 characters of the SHA-1 hash of a
+/// password are sent, and the API returns the suffixes of every breached hash starting with them,
+/// among which the suffix of the password is searched locally. Responses are padded with fake
+/// suffixes, so their size does not reveal the prefix either.
+#[derive(Clone)]
+pub struct HibpBreachedPasswords {
+    client: Client,
+    api_url: String,
+}
+
+impl HibpBreachedPasswords {
+    /// Creates the client of the API of `config`.
+    pub fn new(config: &BreachCheckConfig) -> eyre::Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .context("failed to create the HTTP client of Have I Been Pwned")?;
+        Ok(Self { client, api_url: config.api_url.trim_end_matches('/').to_string() })
+    }
+}
+
+#[async_trait]
+impl BreachedPasswordsPort for HibpBreachedPasswords {
+    async fn breach_count(&self, password: &str) -> eyre::Result<u64> {
+        let hash: String = digest(&SHA1_FOR_LEGACY_USE_ONLY, password.as_bytes()).as_ref().iter().map(|byte| format!("{:02X}", byte)).collect();
+        let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+
+        let range = self
+            .client
+            .get(format!("{}/range/{}", self.api_url, prefix))
+            .header("Add-Padding", "true")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("failed to query Have I Been Pwned")?
+            .text()
+            .await
+            .context("failed to read the response of Have I Been Pwned")?;
+        Ok(count_in_range(&range, suffix))
+    }
+}
+
+/// Returns the count of `suffix` in `range`, a response of the range API made of `SUFFIX:COUNT`
+/// lines, or 0 if it is not listed. Padding suffixes are listed with a count of 0.
+pub fn count_in_range(range: &str, suffix: &str) -> u64 {
+    range
+        .lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(listed, _)| listed.eq_ignore_ascii_case(suffix))
+        .and_then(|(_, count)| count.trim().parse().ok())
+        .unwrap_or(0)
+}
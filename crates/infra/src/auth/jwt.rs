@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+use application::ports::auth::{all_scopes, AccessToken, AuthError, Principal, TokenPort};
+use application::ports::clock::{ClockPort, SystemClock};
+
+use crate::auth::signing_keys::SigningKeys;
+use crate::auth::JwtConfig;
+
+/// Claims of the issued tokens.
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    /// Id of the authenticated user.
+    sub: String,
+    /// Issue time, in seconds since the Unix epoch.
+    iat: u64,
+    /// Expiry time, in seconds since the Unix epoch.
+    exp: u64,
+    /// The admin acting on behalf of `sub`, only present in impersonation tokens (RFC 8693).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    act: Option<ActorClaim>,
+    /// Roles of the user, omitted when it has none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    roles: Vec<String>,
+    /// Space-delimited scopes of the token (RFC 8693, section 4.2), omitted when it has none.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    scope: String,
+}
+
+/// The `act` claim of impersonation tokens.
+#[derive(Debug, Serialize, Deserialize)]
+struct ActorClaim {
+    /// Id of the acting admin.
+    sub: String,
+}
+
+/// The keys tokens are signed with.
+enum Keys {
+    /// HS256 with the configured secret.
+    Secret { encoding: EncodingKey, decoding: DecodingKey },
+    /// ES256 with the rotating keys, the `kid` header of tokens identifying their key.
+    Rotating(Arc<SigningKeys>),
+}
+
+/// JWT implementation of the token port, signing with the configured secret (HS256) or with
+/// rotating keys (ES256).
+pub struct JwtTokens {
+    keys: Keys,
+    validation: Validation,
+    expiry: Duration,
+    clock: Arc<dyn ClockPort + Send + Sync>,
+}
+
+impl JwtTokens {
+    /// Creates a new `JwtTokens` instance signing with the configured secret.
+    pub fn new(config: &JwtConfig) -> Self {
+        let keys = Keys::Secret {
+            encoding: EncodingKey::from_secret(config.secret.expose_secret().as_bytes()),
+            decoding: DecodingKey::from_secret(config.secret.expose_secret().as_bytes()),
+        };
+        Self::with_keys(keys, Algorithm::HS256, config)
+    }
+
+    /// Creates a new `JwtTokens` instance signing with the current key of `keys`, and accepting
+    /// the tokens of all their keys.
+    pub fn with_signing_keys(config: &JwtConfig, keys: Arc<SigningKeys>) -> Self {
+        Self::with_keys(Keys::Rotating(keys), Algorithm::ES256, config)
+    }
+
+    fn with_keys(keys: Keys, algorithm: Algorithm, config: &JwtConfig) -> Self {
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = 0;
+        // Expiry is checked against the clock instead of the time of the system
+        validation.validate_exp = false;
+
+        Self {
+            keys,
+            validation,
+            expiry: Duration::from_secs(config.expiry_secs),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Issues and expires the tokens with the time of `clock` instead of the time of the system.
+    pub fn with_clock(mut self, clock: Arc<dyn ClockPort + Send + Sync>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the current time of the clock, in seconds since the Unix epoch.
+    fn now(&self) -> Result<u64, AuthError> {
+        u64::try_from(self.clock.now().timestamp()).map_err(|_| AuthError::Unavailable)
+    }
+}
+
+impl JwtTokens {
+    /// Signs a token for `subject`, its `roles` and `scopes` valid for `expiry`, on behalf of `actor` if any.
+    fn sign(&self, subject: &str, roles: &[String], scopes: &[String], actor: Option<&str>, expiry: Duration) -> Result<AccessToken, AuthError> {
+        let now = self.now()?;
+        let claims = Claims {
+            sub: subject.to_string(),
+            iat: now,
+            exp: now.saturating_add(expiry.as_secs()),
+            act: actor.map(|actor| ActorClaim { sub: actor.to_string() }),
+            roles: roles.to_vec(),
+            scope: scopes.join(" "),
+        };
+
+        let result = match &self.keys {
+            Keys::Secret { encoding, .. } => jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, encoding),
+            Keys::Rotating(keys) => {
+                let (kid, encoding) = keys.signing_key().ok_or_else(|| {
+                    tracing::error!("No signing key is loaded");
+                    AuthError::Unavailable
+                })?;
+                let header = Header { kid: Some(kid), ..Header::new(Algorithm::ES256) };
+                jsonwebtoken::encode(&header, &claims, &encoding)
+            }
+        };
+        let token = result.map_err(|e| {
+            tracing::error!("Failed to sign access token: {}", e);
+            AuthError::Unavailable
+        })?;
+
+        Ok(AccessToken { token, expires_in: expiry })
+    }
+}
+
+impl TokenPort for JwtTokens {
+    fn issue(&self, user_id: &str, roles: &[String], scopes: &[String]) -> Result<AccessToken, AuthError> {
+        self.sign(user_id, roles, scopes, None, self.expiry)
+    }
+
+    fn issue_impersonation(&self, actor_id: &str, subject_id: &str, ttl: Duration) -> Result<AccessToken, AuthError> {
+        self.sign(subject_id, &[], &all_scopes(), Some(actor_id), ttl)
+    }
+
+    fn verify(&self, token: &str) -> Result<Principal, AuthError> {
+        let rotating;
+        let decoding = match &self.keys {
+            Keys::Secret { decoding, .. } => decoding,
+            Keys::Rotating(keys) => {
+                rotating = jsonwebtoken::decode_header(token)
+                    .ok()
+                    .and_then(|header| header.kid)
+                    .and_then(|kid| keys.decoding_key(&kid))
+                    .ok_or(AuthError::InvalidToken)?;
+                &rotating
+            }
+        };
+
+        let claims = jsonwebtoken::decode::<Claims>(token, decoding, &self.validation).map_err(|_| AuthError::InvalidToken)?.claims;
+        if claims.exp < self.now()? {
+            return Err(AuthError::InvalidToken);
+        }
+
+        Ok(Principal {
+            user_id: claims.sub,
+            actor_id: claims.act.map(|actor| actor.sub),
+            roles: claims.roles,
+            scopes: claims.scope.split_ascii_whitespace().map(str::to_string).collect(),
+            issued_at: UNIX_EPOCH + Duration::from_secs(claims.iat),
+            expires_at: UNIX_EPOCH + Duration::from_secs(claims.exp),
+        })
+    }
+}
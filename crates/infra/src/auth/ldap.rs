@@ -0,0 +1,146 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ldap3::{ldap_escape, Ldap, LdapConnAsync, LdapConnSettings, LdapError, Scope, SearchEntry};
+use tokio::sync::Semaphore;
+
+use application::ports::auth::{AuthError, Authentication, AuthenticatorPort};
+
+use crate::auth::LdapConfig;
+
+/// LDAP result code of a bind with a wrong DN or password (RFC 4511).
+const INVALID_CREDENTIALS: u32 = 49;
+
+/// Authenticator checking credentials against an LDAP server or Active Directory.
+///
+/// The entry of the user is searched as the service account (or anonymously), then its DN is
+/// bound with the password. Roles are mapped from the groups listed in the entry.
+///
+/// Connections are pooled: at most `pool_size` are open at once, and idle ones are kept bound
+/// as the service account between logins.
+pub struct LdapAuthenticator {
+    config: LdapConfig,
+    timeout: Duration,
+    idle: Mutex<Vec<Ldap>>,
+    permits: Semaphore,
+}
+
+impl LdapAuthenticator {
+    /// Creates a new `LdapAuthenticator` instance. Connections are opened on first use.
+    pub fn new(config: LdapConfig) -> Self {
+        Self {
+            timeout: Duration::from_secs(config.timeout_secs),
+            permits: Semaphore::new(config.pool_size.max(1)),
+            idle: Mutex::new(Vec::new()),
+            config,
+        }
+    }
+
+    /// Opens a connection bound as the service account.
+    async fn connect(&self) -> Result<Ldap, LdapError> {
+        let settings = LdapConnSettings::new().set_conn_timeout(self.timeout);
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &self.config.url).await?;
+        ldap3::drive!(conn);
+
+        if let (Some(dn), Some(password)) = (&self.config.bind_dn, &self.config.bind_password) {
+            ldap.with_timeout(self.timeout).simple_bind(dn, password.expose_secret()).await?.success()?;
+        }
+        Ok(ldap)
+    }
+
+    /// Binds a connection back to the service account (or anonymous) after a user bind.
+    async fn reset(&self, ldap: &mut Ldap) -> Result<(), LdapError> {
+        let (dn, password) = match (&self.config.bind_dn, &self.config.bind_password) {
+            (Some(dn), Some(password)) => (dn.as_str(), password.expose_secret().as_str()),
+            _ => ("", ""),
+        };
+        ldap.with_timeout(self.timeout).simple_bind(dn, password).await?.success()?;
+        Ok(())
+    }
+
+    /// Takes an idle connection that is still open, or opens a new one.
+    async fn checkout(&self) -> Result<Ldap, LdapError> {
+        loop {
+            let idle = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+            match idle {
+                Some(mut ldap) => {
+                    if !ldap.is_closed() {
+                        return Ok(ldap);
+                    }
+                }
+                None => return self.connect().await,
+            }
+        }
+    }
+
+    /// Searches the entry of the user, then binds its DN with the password.
+    async fn bind_user(&self, ldap: &mut Ldap, username: &str, password: &str) -> Result<Authentication, AuthError> {
+        let filter = self.config.user_filter.replace("{username}", &ldap_escape(username));
+        let attributes = [self.config.user_id_attribute.as_str(), self.config.group_attribute.as_str()];
+
+        let (entries, _) = ldap
+            .with_timeout(self.timeout)
+            .search(&self.config.user_base_dn, Scope::Subtree, &filter, attributes)
+            .await
+            .and_then(|result| result.success())
+            .map_err(unavailable)?;
+
+        let entry = match <[_; 1]>::try_from(entries) {
+            Ok([entry]) => SearchEntry::construct(entry),
+            Err(entries) if entries.is_empty() => return Err(AuthError::InvalidCredentials),
+            Err(entries) => {
+                tracing::warn!("{} LDAP entries match the user filter, refusing to pick one", entries.len());
+                return Err(AuthError::InvalidCredentials);
+            }
+        };
+
+        match ldap.with_timeout(self.timeout).simple_bind(&entry.dn, password).await.and_then(|result| result.success()) {
+            Ok(_) => {}
+            Err(LdapError::LdapResult { result }) if result.rc == INVALID_CREDENTIALS => return Err(AuthError::InvalidCredentials),
+            Err(e) => return Err(unavailable(e)),
+        }
+
+        let user_id = entry
+            .attrs
+            .get(&self.config.user_id_attribute)
+            .and_then(|values| values.first())
+            .cloned()
+            .ok_or_else(|| {
+                tracing::error!("LDAP entry has no {} attribute", self.config.user_id_attribute);
+                AuthError::Unavailable
+            })?;
+        let groups = entry.attrs.get(&self.config.group_attribute).cloned().unwrap_or_default();
+
+        Ok(Authentication {
+            user_id,
+            roles: self.config.roles_for_groups(&groups),
+        })
+    }
+}
+
+#[async_trait]
+impl AuthenticatorPort for LdapAuthenticator {
+    async fn authenticate(&self, username: String, password: String) -> Result<Authentication, AuthError> {
+        // A bind with an empty password is an unauthenticated bind, which servers accept (RFC 4513)
+        if password.is_empty() {
+            return Err(AuthError::InvalidCredentials);
+        }
+
+        let _permit = self.permits.acquire().await.map_err(|_| AuthError::Unavailable)?;
+        let mut ldap = self.checkout().await.map_err(unavailable)?;
+
+        let result = self.bind_user(&mut ldap, &username, &password).await;
+
+        // Connections that cannot be bound back to the service account are dropped
+        if self.reset(&mut ldap).await.is_ok() {
+            self.idle.lock().unwrap_or_else(|e| e.into_inner()).push(ldap);
+        }
+        result
+    }
+}
+
+fn unavailable(e: LdapError) -> AuthError {
+    tracing::error!("LDAP authentication failed: {}", e);
+    AuthError::Unavailable
+}
@@ -0,0 +1,192 @@
+pub mod device;
+pub mod jwt;
+#[cfg(feature = "hibp")]
+pub mod hibp;
+#[cfg(feature = "ldap")]
+pub mod ldap;
+#[cfg(feature = "oidc")]
+pub mod oidc;
+pub mod password;
+#[cfg(feature = "saml")]
+pub mod saml;
+pub mod signing_keys;
+#[cfg(feature = "webauthn")]
+pub mod webauthn;
+#[cfg(feature = "zxcvbn")]
+pub mod zxcvbn;
+
+use domain::secret::Secret;
+
+/// Settings of the JWT access tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JwtConfig {
+    /// Secret signing and validating the tokens (HS256).
+    pub secret: Secret<String>,
+    /// Lifetime of issued tokens, in seconds.
+    pub expiry_secs: u64,
+}
+
+/// Settings of the rotation of the keys signing access tokens, replacing the JWT secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SigningKeysConfig {
+    /// Time a key signs tokens before it is replaced, in seconds.
+    pub rotation_interval_secs: u64,
+    /// Time a new key is published before it signs tokens, in seconds, so the services caching
+    /// the key set know it before receiving its tokens.
+    pub publication_delay_secs: u64,
+    /// Interval between reloads of the keys, and checks whether they are due for rotation, in seconds.
+    pub refresh_interval_secs: u64,
+}
+
+/// Settings of the device authorization grant, letting CLI tools log in through a browser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceGrantConfig {
+    /// Public URL of the verification page, e.g. `https://app.example.com/api/auth/device`.
+    pub verification_uri: String,
+    /// Lifetime of the device and user codes, in seconds.
+    pub code_lifetime_secs: u64,
+    /// Minimum time devices wait between polls, in seconds.
+    pub poll_interval_secs: u64,
+}
+
+/// Settings of the LDAP/Active Directory authenticator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LdapConfig {
+    /// URL of the directory server, e.g. `ldaps://ldap.example.com:636`.
+    pub url: String,
+    /// DN of the service account searching for users, anonymous when unset.
+    pub bind_dn: Option<String>,
+    pub bind_password: Option<Secret<String>>,
+    /// DN under which users are searched, e.g. `ou=people,dc=example,dc=com`.
+    pub user_base_dn: String,
+    /// Filter matching the entry of a user, `{username}` being replaced with the escaped username.
+    pub user_filter: String,
+    /// Attribute of the user entry used as user id (e.g. `uid`, or `sAMAccountName` on Active Directory).
+    pub user_id_attribute: String,
+    /// Attribute of the user entry listing the DNs of its groups.
+    pub group_attribute: String,
+    /// Roles granted to the members of each group, as `(group DN, role)` pairs.
+    pub group_roles: Vec<(String, String)>,
+    /// Maximum number of connections kept to the server.
+    pub pool_size: usize,
+    /// Timeout of connecting and of each LDAP operation, in seconds.
+    pub timeout_secs: u64,
+}
+
+impl LdapConfig {
+    /// Returns the roles granted to the members of `groups`, in the order of `group_roles`.
+    ///
+    /// DNs are compared case-insensitively and regardless of the spaces around their separators.
+    pub fn roles_for_groups(&self, groups: &[String]) -> Vec<String> {
+        let groups: Vec<String> = groups.iter().map(|group| normalize_dn(group)).collect();
+
+        let mut roles: Vec<String> = Vec::new();
+        for (group, role) in &self.group_roles {
+            if groups.contains(&normalize_dn(group)) && !roles.contains(role) {
+                roles.push(role.clone());
+            }
+        }
+        roles
+    }
+}
+
+/// Settings of the SAML 2.0 service provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamlConfig {
+    /// Entity id of the service provider, e.g. `https://app.example.com/saml`.
+    pub sp_entity_id: String,
+    /// Public URL of the assertion consumer service, e.g. `https://app.example.com/api/auth/saml/acs`.
+    pub acs_url: String,
+    /// Entity id of the identity provider, expected as the issuer of its responses.
+    pub idp_entity_id: String,
+    /// Single sign-on URL of the identity provider (HTTP-Redirect binding).
+    pub idp_sso_url: String,
+    /// Base64 DER certificates of the identity provider; responses signed with any of them are
+    /// accepted, so certificates can be rotated.
+    pub idp_certificates: Vec<String>,
+    /// Attribute holding the email of the user, the `NameID` of the subject being used when unset.
+    pub email_attribute: Option<String>,
+    /// Attribute listing the roles of the user, no roles being granted when unset.
+    pub roles_attribute: Option<String>,
+    /// Tolerated difference between the clocks of the identity provider and the server, in seconds.
+    pub clock_skew_secs: u64,
+    /// Page the browser is redirected to after logging in, with the access token in the URL
+    /// fragment. The token is returned as JSON when unset.
+    pub login_redirect_url: Option<String>,
+}
+
+/// Settings of the validation of the access tokens of an external OpenID Connect provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcConfig {
+    /// Issuer of the tokens, e.g. `https://idp.example.com/realms/main`, expected in their `iss` claim.
+    pub issuer: String,
+    /// Audience expected in the `aud` claim of the tokens, not checked when unset.
+    pub audience: Option<String>,
+    /// URL of the key set of the provider, discovered from its OpenID configuration when unset.
+    pub jwks_url: Option<String>,
+    /// Claim listing the roles of the user, no roles being granted when missing from a token.
+    pub roles_claim: String,
+    /// Interval between fetches of the key set, in seconds.
+    pub refresh_interval_secs: u64,
+    /// Minimum time between fetches of the key set triggered by tokens of unknown keys, in seconds.
+    pub refetch_cooldown_secs: u64,
+    /// Tolerated difference between the clocks of the provider and the server, in seconds.
+    pub clock_skew_secs: u64,
+}
+
+/// Settings of the WebAuthn relying party, registering passkeys and logging users in with them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WebAuthnConfig {
+    /// Id of the relying party: the effective domain of the origin, or one of its registrable
+    /// suffixes, e.g. `example.com`. Passkeys are bound to it and cannot be used if it changes.
+    pub rp_id: String,
+    /// Origin of the web application running the ceremonies, e.g. `https://app.example.com`.
+    pub rp_origin: String,
+    /// Name of the relying party shown by authenticators.
+    pub rp_name: String,
+    /// Time within which a started ceremony must be finished, in seconds.
+    pub ceremony_timeout_secs: u64,
+    pub password_fallback: PasswordFallback,
+}
+
+/// Whether users who registered a passkey can still log in with their password.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PasswordFallback {
+    /// Password logins remain accepted, passkeys being an alternative.
+    #[default]
+    Allowed,
+    /// Password logins are refused once a user registered a passkey.
+    Disabled,
+}
+
+/// Settings of the password policy, the rules the passwords of users must follow when set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordPolicyConfig {
+    /// Minimum length of passwords, in characters.
+    pub min_length: usize,
+    /// Minimum strength of passwords estimated by zxcvbn, from 0 (too guessable) to 4 (very
+    /// unguessable), unchecked when unset.
+    pub min_strength: Option<u8>,
+    /// Lookup of passwords in the breaches known to Have I Been Pwned, when enabled.
+    pub breach_check: Option<BreachCheckConfig>,
+}
+
+/// Settings of the lookup of passwords in the Pwned Passwords range API of Have I Been Pwned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreachCheckConfig {
+    /// Base URL of the API, e.g. `https://api.pwnedpasswords.com`.
+    pub api_url: String,
+    /// Time within which the API must answer, in milliseconds, after which passwords are accepted.
+    pub timeout_ms: u64,
+}
+
+fn normalize_dn(dn: &str) -> String {
+    dn.split(',')
+        .map(|rdn| {
+            let (attribute, value) = rdn.split_once('=').unwrap_or((rdn, ""));
+            format!("{}={}", attribute.trim(), value.trim())
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+        .to_lowercase()
+}
@@ -0,0 +1,308 @@
+//! Validation of the access tokens of an external OpenID Connect provider, the server acting as
+//! a resource server.
+//!
+//! The key set of the provider is cached and refreshed in the background. A token signed by a
+//! key missing from the cache triggers a fetch of the key set, at most once per cooldown, so
+//! keys rotated by the provider are picked up without letting forged `kid`s flood it. Tokens are
+//! rejected while the key set cannot be fetched.
+
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use eyre::Context;
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk, PublicKeyUse};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tokio::sync::{Mutex, OnceCell};
+use tokio::time;
+
+use application::ports::auth::{AuthError, ExternalTokenPort, Principal};
+
+use crate::auth::OidcConfig;
+
+const OIDC_CLIENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Time within which the provider must answer.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Algorithms of the RSA keys. Symmetric algorithms are never accepted, as the key set is public.
+const RSA_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+];
+
+const EC_ALGORITHMS: &[Algorithm] = &[Algorithm::ES256, Algorithm::ES384];
+
+const OKP_ALGORITHMS: &[Algorithm] = &[Algorithm::EdDSA];
+
+/// The OpenID provider metadata needed to find the key set.
+#[derive(Deserialize)]
+struct ProviderMetadata {
+    jwks_uri: String,
+}
+
+/// A key set, its keys parsed one by one so keys of unsupported types are skipped.
+#[derive(Deserialize)]
+struct KeySetData {
+    keys: Vec<Value>,
+}
+
+/// A key of the provider, with the algorithms it validates.
+struct ProviderKey {
+    kid: Option<String>,
+    key: DecodingKey,
+    algorithms: Vec<Algorithm>,
+}
+
+/// Validator of the access tokens of an external OpenID Connect provider, against its cached key set.
+pub struct OidcTokens {
+    client: Client,
+    config: OidcConfig,
+    jwks_url: OnceCell<String>,
+    /// The keys fetched last, `None` until the key set was fetched once.
+    keys: RwLock<Option<Vec<Arc<ProviderKey>>>>,
+    /// Time of the last fetch, held while fetching so concurrent misses wait for a single fetch.
+    last_fetch: Mutex<Option<Instant>>,
+}
+
+impl OidcTokens {
+    /// Creates the validator of the tokens of the provider of `config`, fetches its key set, and
+    /// spawns the task refreshing it every `refresh_interval_secs` while the validator is alive.
+    ///
+    /// An unreachable provider does not fail: its tokens are rejected until its key set is fetched.
+    pub async fn spawn(config: OidcConfig) -> eyre::Result<Arc<Self>> {
+        eyre::ensure!(config.refresh_interval_secs > 0, "the refresh interval of the key set must be positive");
+        let client = Client::builder()
+            .user_agent(OIDC_CLIENT)
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .context("failed to create OIDC HTTP client")?;
+        let refresh_interval = Duration::from_secs(config.refresh_interval_secs);
+
+        let tokens = Arc::new(Self {
+            client,
+            config,
+            jwks_url: OnceCell::new(),
+            keys: RwLock::new(None),
+            last_fetch: Mutex::new(None),
+        });
+        if let Err(e) = tokens.fetch_keys(Duration::ZERO).await {
+            tracing::warn!("failed to fetch the key set of {}, its tokens are rejected until it is: {:#}", tokens.config.issuer, e);
+        }
+
+        let validator = Arc::downgrade(&tokens);
+        tokio::spawn(async move {
+            let mut interval = time::interval_at(time::Instant::now() + refresh_interval, refresh_interval);
+            loop {
+                interval.tick().await;
+                // The validator was dropped
+                let Some(tokens) = validator.upgrade() else { break };
+                if let Err(e) = tokens.fetch_keys(Duration::ZERO).await {
+                    tracing::warn!("failed to refresh the key set of {}: {:#}", tokens.config.issuer, e);
+                }
+            }
+        });
+
+        Ok(tokens)
+    }
+
+    /// Fetches the key set, unless it was fetched less than `min_age` ago.
+    async fn fetch_keys(&self, min_age: Duration) -> eyre::Result<()> {
+        let mut last_fetch = self.last_fetch.lock().await;
+        if last_fetch.is_some_and(|at| at.elapsed() < min_age) {
+            return Ok(());
+        }
+        // Failed fetches count too, so an unavailable provider is not retried on every request
+        *last_fetch = Some(Instant::now());
+
+        let jwks_url = self.jwks_url.get_or_try_init(|| self.discover_jwks_url()).await?;
+        let key_set: KeySetData = self
+            .client
+            .get(jwks_url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("failed to fetch key set from {}", jwks_url))?
+            .json()
+            .await
+            .with_context(|| format!("failed to parse key set from {}", jwks_url))?;
+
+        let keys: Vec<Arc<ProviderKey>> = key_set.keys.into_iter().filter_map(parse_key).map(Arc::new).collect();
+        tracing::debug!(oidc.issuer = %self.config.issuer, "fetched {} signing keys", keys.len());
+        *self.keys.write().unwrap_or_else(|e| e.into_inner()) = Some(keys);
+        Ok(())
+    }
+
+    /// Returns the configured URL of the key set, or the one advertised by the OpenID
+    /// configuration of the provider.
+    async fn discover_jwks_url(&self) -> eyre::Result<String> {
+        if let Some(jwks_url) = &self.config.jwks_url {
+            return Ok(jwks_url.clone());
+        }
+        let url = format!("{}/.well-known/openid-configuration", self.config.issuer.trim_end_matches('/'));
+        let metadata: ProviderMetadata = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("failed to fetch OpenID configuration from {}", url))?
+            .json()
+            .await
+            .with_context(|| format!("failed to parse OpenID configuration from {}", url))?;
+        Ok(metadata.jwks_uri)
+    }
+
+    /// Returns the key `kid`, or the only key when the token names none. Fails while the key
+    /// set was never fetched.
+    fn find_key(&self, kid: Option<&str>) -> Result<Option<Arc<ProviderKey>>, AuthError> {
+        let keys = self.keys.read().unwrap_or_else(|e| e.into_inner());
+        let keys = keys.as_ref().ok_or(AuthError::KeySetUnavailable)?;
+        let key = match (kid, keys.as_slice()) {
+            (Some(kid), _) => keys.iter().find(|key| key.kid.as_deref() == Some(kid)),
+            (None, [key]) => Some(key),
+            (None, _) => None,
+        };
+        Ok(key.cloned())
+    }
+
+    /// Builds the identity authenticated by validated `claims`.
+    fn principal(&self, claims: &Map<String, Value>) -> Result<Principal, AuthError> {
+        let user_id = claims.get("sub").and_then(Value::as_str).ok_or(AuthError::InvalidToken)?;
+        let expires_at = claims.get("exp").and_then(Value::as_u64).ok_or(AuthError::InvalidToken)?;
+        let issued_at = claims.get("iat").and_then(Value::as_u64);
+
+        // Providers put the scopes in `scope` (RFC 9068) or `scp`, space-delimited or as an array
+        let scopes = claims.get("scope").or_else(|| claims.get("scp")).map(strings).unwrap_or_default();
+        // The roles claim may be nested, e.g. `realm_access.roles`
+        let roles = nested(claims, &self.config.roles_claim).map(strings).unwrap_or_default();
+
+        Ok(Principal {
+            user_id: user_id.to_string(),
+            actor_id: None,
+            roles,
+            scopes,
+            issued_at: issued_at.map_or_else(SystemTime::now, |iat| UNIX_EPOCH + Duration::from_secs(iat)),
+            expires_at: UNIX_EPOCH + Duration::from_secs(expires_at),
+        })
+    }
+}
+
+#[async_trait]
+impl ExternalTokenPort for OidcTokens {
+    fn issued_by_provider(&self, token: &str) -> bool {
+        let issuer = token
+            .split('.')
+            .nth(1)
+            .and_then(|payload| URL_SAFE_NO_PAD.decode(payload).ok())
+            .and_then(|payload| serde_json::from_slice::<Map<String, Value>>(&payload).ok())
+            .and_then(|claims| claims.get("iss").and_then(Value::as_str).map(str::to_string));
+        issuer.as_deref() == Some(self.config.issuer.as_str())
+    }
+
+    #[tracing::instrument(name = "oidc.verify", skip_all, fields(oidc.issuer = %self.config.issuer))]
+    async fn verify(&self, token: &str) -> Result<Principal, AuthError> {
+        let header = decode_header(token).map_err(|_| AuthError::InvalidToken)?;
+        let kid = header.kid.as_deref();
+
+        let key = match self.find_key(kid) {
+            Ok(Some(found)) => found,
+            _ => {
+                // The provider may have rotated its keys since the last fetch
+                let cooldown = Duration::from_secs(self.config.refetch_cooldown_secs);
+                if let Err(e) = self.fetch_keys(cooldown).await {
+                    tracing::warn!("failed to fetch the key set of {}: {:#}", self.config.issuer, e);
+                }
+                match self.find_key(kid)? {
+                    Some(found) => found,
+                    None => {
+                        tracing::info!(token.kid = kid.unwrap_or_default(), "rejected token signed by a key missing from the key set");
+                        return Err(AuthError::UnknownSigningKey);
+                    }
+                }
+            }
+        };
+        if !key.algorithms.contains(&header.alg) {
+            tracing::info!(token.alg = ?header.alg, "rejected token signed with an algorithm its key does not allow");
+            return Err(AuthError::InvalidToken);
+        }
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+        validation.leeway = self.config.clock_skew_secs;
+        match &self.config.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        let claims = decode::<Map<String, Value>>(token, &key.key, &validation)
+            .map_err(|e| {
+                tracing::info!("rejected token: {}", e);
+                AuthError::InvalidToken
+            })?
+            .claims;
+
+        self.principal(&claims)
+    }
+}
+
+/// Parses a key of the set, skipping keys not meant for signatures or of unsupported types.
+fn parse_key(value: Value) -> Option<ProviderKey> {
+    let jwk: Jwk = match serde_json::from_value(value) {
+        Ok(jwk) => jwk,
+        Err(e) => {
+            tracing::debug!("skipping unsupported key of the key set: {}", e);
+            return None;
+        }
+    };
+    if matches!(jwk.common.public_key_use, Some(ref key_use) if *key_use != PublicKeyUse::Signature) {
+        return None;
+    }
+
+    let supported = match &jwk.algorithm {
+        AlgorithmParameters::RSA(_) => RSA_ALGORITHMS,
+        AlgorithmParameters::EllipticCurve(_) => EC_ALGORITHMS,
+        AlgorithmParameters::OctetKeyPair(_) => OKP_ALGORITHMS,
+        AlgorithmParameters::OctetKey(_) => return None,
+    };
+    let algorithms = match jwk.common.key_algorithm {
+        Some(algorithm) => {
+            let algorithm = algorithm.to_string().parse::<Algorithm>().ok().filter(|algorithm| supported.contains(algorithm))?;
+            vec![algorithm]
+        }
+        None => supported.to_vec(),
+    };
+
+    Some(ProviderKey {
+        kid: jwk.common.key_id.clone(),
+        key: DecodingKey::from_jwk(&jwk).ok()?,
+        algorithms,
+    })
+}
+
+/// Returns the value at the dotted `path` of `claims`.
+fn nested<'a>(claims: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let (object, name) = match path.rsplit_once('.') {
+        Some((parents, name)) => (nested(claims, parents)?.as_object()?, name),
+        None => (claims, path),
+    };
+    object.get(name)
+}
+
+/// Returns the strings of an array, or of a space-delimited string.
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(value) => value.split_ascii_whitespace().map(str::to_string).collect(),
+        Value::Array(values) => values.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,82 @@
+//! Password hashing with Argon2id, the algorithm recommended by OWASP for storing passwords.
+
+use std::sync::{Arc, OnceLock};
+
+use argon2::password_hash::{PasswordHash as PhcString, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use async_trait::async_trait;
+use eyre::{eyre, WrapErr};
+
+use application::ports::password::PasswordHasherPort;
+use domain::user::model::{Password, PasswordHash};
+
+/// Length of the random salt of every hash, in bytes.
+const SALT_LENGTH: usize = 16;
+
+/// Hashes passwords with Argon2id into PHC strings (`$argon2id$v=19$m=...`).
+///
+/// Hashing is deliberately slow, so it runs on the blocking thread pool rather than on the
+/// async workers. Hashes made with other parameters are still verified, with their own.
+#[derive(Clone)]
+pub struct Argon2PasswordHasher {
+    argon2: Argon2<'static>,
+    /// Hash of a random password, verified against when there is no hash to verify, so that
+    /// takes as long as a verification.
+    dummy_hash: Arc<OnceLock<String>>,
+}
+
+impl Argon2PasswordHasher {
+    /// Creates a hasher using `params` (memory in KiB, iterations and parallelism).
+    pub fn new(params: Params) -> Self {
+        Self { argon2: Argon2::new(Algorithm::Argon2id, Version::V0x13, params), dummy_hash: Arc::default() }
+    }
+
+    fn hash_blocking(argon2: &Argon2<'static>, password: &[u8]) -> eyre::Result<String> {
+        let salt = SaltString::encode_b64(&rand::random::<[u8; SALT_LENGTH]>()).map_err(|e| eyre!("invalid salt: {}", e))?;
+        let hash = argon2.hash_password(password, &salt).map_err(|e| eyre!("failed to hash password: {}", e))?;
+        Ok(hash.to_string())
+    }
+}
+
+impl Default for Argon2PasswordHasher {
+    /// Creates a hasher using the default parameters of the `argon2` crate (19 MiB of memory,
+    /// 2 iterations, 1 lane), the minimum recommended by OWASP.
+    fn default() -> Self {
+        Self::new(Params::default())
+    }
+}
+
+#[async_trait]
+impl PasswordHasherPort for Argon2PasswordHasher {
+    async fn hash(&self, password: &Password) -> eyre::Result<PasswordHash> {
+        let argon2 = self.argon2.clone();
+        let password = password.clone();
+        let hash = tokio::task::spawn_blocking(move || Self::hash_blocking(&argon2, password.as_str().as_bytes()))
+            .await
+            .wrap_err("password hashing panicked")??;
+        Ok(PasswordHash::new(hash))
+    }
+
+    async fn verify(&self, password: &str, hash: Option<&PasswordHash>) -> eyre::Result<bool> {
+        let (argon2, dummy_hash) = (self.argon2.clone(), self.dummy_hash.clone());
+        let password = password.to_string();
+        let hash = hash.map(|hash| hash.as_str().to_string());
+        tokio::task::spawn_blocking(move || {
+            let (hash, known) = match &hash {
+                Some(hash) => (hash.as_str(), true),
+                None => {
+                    if dummy_hash.get().is_none() {
+                        let _ = dummy_hash.set(Self::hash_blocking(&argon2, &rand::random::<[u8; SALT_LENGTH]>())?);
+                    }
+                    (dummy_hash.get().map(String::as_str).unwrap_or_default(), false)
+                }
+            };
+            let hash = PhcString::new(hash).map_err(|e| eyre!("invalid password hash: {}", e))?;
+            // The hash names its own algorithm and parameters, which take precedence over ours
+            let verified = argon2.verify_password(password.as_bytes(), &hash).is_ok();
+            Ok(known && verified)
+        })
+        .await
+        .wrap_err("password verification panicked")?
+    }
+}
@@ -0,0 +1,382 @@
+//! SAML 2.0 service provider: SP-initiated single sign-on with the HTTP-Redirect binding for
+//! authentication requests and the HTTP-POST binding for responses.
+//!
+//! Responses are accepted when the response or its assertion is signed by a configured
+//! certificate of the identity provider, and only the signed part is trusted. Authentication
+//! requests are not stored: their id carries its issue time and a MAC, so any replica can check
+//! that a response answers a request it issued. Consumed assertions are remembered until they
+//! expire, rejecting replays on the same replica.
+
+mod signature;
+mod xml;
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use hmac::{Hmac, Mac};
+use quick_xml::escape::escape;
+use rsa::pkcs1v15::VerifyingKey;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::RsaPublicKey;
+use sha2::Sha256;
+use x509_cert::der::{Decode, Encode};
+use x509_cert::Certificate;
+
+use application::ports::auth::{AuthError, SamlIdentity, SamlServiceProviderPort};
+
+use crate::auth::SamlConfig;
+
+use self::signature::{decode_base64, is_signed};
+use self::xml::{parse, Element};
+
+const PROTOCOL_NAMESPACE: &str = "urn:oasis:names:tc:SAML:2.0:protocol";
+const ASSERTION_NAMESPACE: &str = "urn:oasis:names:tc:SAML:2.0:assertion";
+const SUCCESS_STATUS: &str = "urn:oasis:names:tc:SAML:2.0:status:Success";
+const BEARER_METHOD: &str = "urn:oasis:names:tc:SAML:2.0:cm:bearer";
+const POST_BINDING: &str = "urn:oasis:names:tc:SAML:2.0:bindings:HTTP-POST";
+const EMAIL_NAME_ID_FORMAT: &str = "urn:oasis:names:tc:SAML:1.1:nameid-format:emailAddress";
+
+/// Time within which a response must answer an authentication request.
+const REQUEST_LIFETIME: Duration = Duration::from_secs(600);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Reasons a SAML response is rejected, or the service provider cannot be created.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SamlError {
+    #[error("malformed XML: {0}")]
+    Xml(String),
+    #[error("invalid signature: {0}")]
+    Signature(String),
+    #[error("invalid response: {0}")]
+    Response(String),
+    #[error("invalid identity provider certificate: {0}")]
+    Certificate(String),
+}
+
+/// SAML 2.0 service provider authenticating users with an identity provider.
+pub struct SamlServiceProvider {
+    config: SamlConfig,
+    keys: Vec<VerifyingKey<Sha256>>,
+    request_key: Vec<u8>,
+    /// Ids of the consumed assertions, with the time after which they are rejected anyway.
+    consumed: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl SamlServiceProvider {
+    /// Creates a new `SamlServiceProvider` instance, failing if a certificate of the identity
+    /// provider is invalid. The ids of authentication requests are authenticated with
+    /// `request_key`, which must be shared by all replicas.
+    pub fn new(config: SamlConfig, request_key: &[u8]) -> Result<Self, SamlError> {
+        if config.idp_certificates.is_empty() {
+            return Err(SamlError::Certificate("no certificate is configured".to_string()));
+        }
+        let keys = config
+            .idp_certificates
+            .iter()
+            .map(|certificate| verifying_key(certificate))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            config,
+            keys,
+            request_key: request_key.to_vec(),
+            consumed: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the login URL as of `now`, see [`SamlServiceProviderPort::login_url`].
+    pub fn login_url_at(&self, now: DateTime<Utc>) -> String {
+        let request = format!(
+            concat!(
+                r#"<samlp:AuthnRequest xmlns:samlp="{}" xmlns:saml="{}" ID="{}" Version="2.0" IssueInstant="{}" "#,
+                r#"Destination="{}" AssertionConsumerServiceURL="{}" ProtocolBinding="{}">"#,
+                r#"<saml:Issuer>{}</saml:Issuer><samlp:NameIDPolicy AllowCreate="false"/></samlp:AuthnRequest>"#,
+            ),
+            PROTOCOL_NAMESPACE,
+            ASSERTION_NAMESPACE,
+            self.request_id(now),
+            now.format("%Y-%m-%dT%H:%M:%SZ"),
+            escape(self.config.idp_sso_url.as_str()),
+            escape(self.config.acs_url.as_str()),
+            POST_BINDING,
+            escape(self.config.sp_entity_id.as_str()),
+        );
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(request.as_bytes()).expect("writing to a Vec cannot fail");
+        let deflated = encoder.finish().expect("writing to a Vec cannot fail");
+
+        let separator = if self.config.idp_sso_url.contains('?') { '&' } else { '?' };
+        format!("{}{}SAMLRequest={}", self.config.idp_sso_url, separator, url_encode(&STANDARD.encode(deflated)))
+    }
+
+    /// Validates a response as of `now`, see [`SamlServiceProviderPort::consume`].
+    pub fn consume_at(&self, response: &str, now: DateTime<Utc>) -> Result<SamlIdentity, SamlError> {
+        let document = String::from_utf8(decode_base64(response)?).map_err(|e| SamlError::Xml(e.to_string()))?;
+        let response = parse(&document)?;
+        if !response.is(PROTOCOL_NAMESPACE, "Response") {
+            return Err(SamlError::Response(format!("expected a Response element, got {}", response.local_name)));
+        }
+
+        // Signatures reference elements by id, so ids must be unique for a reference to be unambiguous
+        let mut ids = HashSet::new();
+        for element in response.descendants() {
+            if let Some(id) = element.attribute("ID").filter(|id| !ids.insert(*id)) {
+                return Err(SamlError::Response(format!("duplicate ID {}", id)));
+            }
+        }
+
+        // Only the signed response, or else the signed assertion, is looked into from here on
+        let response_signed = is_signed(&response, &self.keys)?;
+        let assertion = match response.only_child(ASSERTION_NAMESPACE, "Assertion")? {
+            Some(assertion) => assertion,
+            None if response.only_child(ASSERTION_NAMESPACE, "EncryptedAssertion")?.is_some() => {
+                return Err(SamlError::Response("encrypted assertions are not supported".to_string()));
+            }
+            None => return Err(SamlError::Response(status(&response)?.unwrap_or_else(|| "no assertion".to_string()))),
+        };
+        if !is_signed(assertion, &self.keys)? && !response_signed {
+            return Err(SamlError::Signature("neither the response nor the assertion is signed".to_string()));
+        }
+
+        if let Some(status) = status(&response)? {
+            return Err(SamlError::Response(status));
+        }
+        if let Some(destination) = response.attribute("Destination").filter(|destination| *destination != self.config.acs_url) {
+            return Err(SamlError::Response(format!("the response is destined to {}", destination)));
+        }
+        if let Some(issuer) = response.only_child(ASSERTION_NAMESPACE, "Issuer")? {
+            self.check_issuer(issuer)?;
+        }
+        self.check_issuer(required(assertion, ASSERTION_NAMESPACE, "Issuer")?)?;
+
+        let skew = chrono::Duration::seconds(self.config.clock_skew_secs as i64);
+        let subject = required(assertion, ASSERTION_NAMESPACE, "Subject")?;
+        let confirmation = subject
+            .children_named(ASSERTION_NAMESPACE, "SubjectConfirmation")
+            .find(|confirmation| confirmation.attribute("Method") == Some(BEARER_METHOD))
+            .ok_or_else(|| SamlError::Response("no bearer subject confirmation".to_string()))?;
+        let confirmation_data = required(confirmation, ASSERTION_NAMESPACE, "SubjectConfirmationData")?;
+        if confirmation_data.attribute("Recipient") != Some(self.config.acs_url.as_str()) {
+            return Err(SamlError::Response("the subject confirmation is not for this service provider".to_string()));
+        }
+        let expires_at = timestamp(confirmation_data, "NotOnOrAfter")?
+            .ok_or_else(|| SamlError::Response("the subject confirmation has no expiry".to_string()))?;
+        check_validity(confirmation_data, now, skew)?;
+
+        let request_id = confirmation_data
+            .attribute("InResponseTo")
+            .ok_or_else(|| SamlError::Response("unsolicited responses are not accepted".to_string()))?;
+        if response.attribute("InResponseTo").is_some_and(|id| id != request_id) {
+            return Err(SamlError::Response("the response and its assertion answer different requests".to_string()));
+        }
+        self.check_request_id(request_id, now, skew)?;
+
+        let conditions = required(assertion, ASSERTION_NAMESPACE, "Conditions")?;
+        check_validity(conditions, now, skew)?;
+        let mut restrictions = conditions.children_named(ASSERTION_NAMESPACE, "AudienceRestriction").peekable();
+        if restrictions.peek().is_none() {
+            return Err(SamlError::Response("the assertion has no audience restriction".to_string()));
+        }
+        for restriction in restrictions {
+            if !restriction
+                .children_named(ASSERTION_NAMESPACE, "Audience")
+                .any(|audience| audience.text().trim() == self.config.sp_entity_id)
+            {
+                return Err(SamlError::Response("the assertion is not intended for this service provider".to_string()));
+            }
+        }
+
+        let attributes = attributes(assertion);
+        let email = match &self.config.email_attribute {
+            Some(name) => attributes.get(name.as_str()).and_then(|values| values.first()).cloned(),
+            None => subject.only_child(ASSERTION_NAMESPACE, "NameID")?.map(|name_id| name_id.text()),
+        }
+        .map(|email| email.trim().to_string())
+        .filter(|email| !email.is_empty())
+        .ok_or_else(|| SamlError::Response("the assertion has no email".to_string()))?;
+        let roles = match &self.config.roles_attribute {
+            Some(name) => attributes.get(name.as_str()).cloned().unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        let assertion_id = assertion
+            .attribute("ID")
+            .ok_or_else(|| SamlError::Response("the assertion has no ID".to_string()))?;
+        self.consume_once(assertion_id, expires_at + skew, now)?;
+
+        Ok(SamlIdentity { email, roles })
+    }
+
+    fn check_issuer(&self, issuer: &Element) -> Result<(), SamlError> {
+        if issuer.text().trim() != self.config.idp_entity_id {
+            return Err(SamlError::Response(format!("unexpected issuer {}", issuer.text().trim())));
+        }
+        Ok(())
+    }
+
+    /// Returns a request id carrying its issue time, authenticated with the request key.
+    fn request_id(&self, now: DateTime<Utc>) -> String {
+        let issued_at = now.timestamp();
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        let mac = to_hex(&self.request_mac(issued_at, &nonce).finalize().into_bytes());
+        format!("id-{}-{}-{}", issued_at, nonce, mac)
+    }
+
+    fn check_request_id(&self, request_id: &str, now: DateTime<Utc>, skew: chrono::Duration) -> Result<(), SamlError> {
+        let unknown = || SamlError::Response("the response does not answer a request of this service provider".to_string());
+
+        let mut parts = request_id.strip_prefix("id-").ok_or_else(unknown)?.splitn(3, '-');
+        let (Some(issued_at), Some(nonce), Some(mac)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(unknown());
+        };
+        let issued_at: i64 = issued_at.parse().map_err(|_| unknown())?;
+        let mac = from_hex(mac).ok_or_else(unknown)?;
+        self.request_mac(issued_at, nonce).verify_slice(&mac).map_err(|_| unknown())?;
+
+        let issued_at = DateTime::from_timestamp(issued_at, 0).ok_or_else(unknown)?;
+        if now > issued_at + chrono::Duration::from_std(REQUEST_LIFETIME).expect("the request lifetime is valid") + skew {
+            return Err(SamlError::Response("the authentication request has expired".to_string()));
+        }
+        Ok(())
+    }
+
+    fn request_mac(&self, issued_at: i64, nonce: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.request_key).expect("HMAC accepts keys of any length");
+        mac.update(format!("saml-request.{}.{}", issued_at, nonce).as_bytes());
+        mac
+    }
+
+    /// Records the assertion as consumed until `expires_at`, failing if it already was.
+    fn consume_once(&self, assertion_id: &str, expires_at: DateTime<Utc>, now: DateTime<Utc>) -> Result<(), SamlError> {
+        let mut consumed = self.consumed.lock().unwrap_or_else(|e| e.into_inner());
+        consumed.retain(|_, expiry| *expiry > now);
+        if consumed.insert(assertion_id.to_string(), expires_at).is_some() {
+            return Err(SamlError::Response("the assertion was already consumed".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl SamlServiceProviderPort for SamlServiceProvider {
+    fn metadata(&self) -> String {
+        format!(
+            concat!(
+                r#"<md:EntityDescriptor xmlns:md="urn:oasis:names:tc:SAML:2.0:metadata" entityID="{}">"#,
+                r#"<md:SPSSODescriptor AuthnRequestsSigned="false" WantAssertionsSigned="true" protocolSupportEnumeration="{}">"#,
+                r#"<md:NameIDFormat>{}</md:NameIDFormat>"#,
+                r#"<md:AssertionConsumerService Binding="{}" Location="{}" index="0" isDefault="true"/>"#,
+                r#"</md:SPSSODescriptor></md:EntityDescriptor>"#,
+            ),
+            escape(self.config.sp_entity_id.as_str()),
+            PROTOCOL_NAMESPACE,
+            EMAIL_NAME_ID_FORMAT,
+            POST_BINDING,
+            escape(self.config.acs_url.as_str()),
+        )
+    }
+
+    fn login_url(&self) -> Result<String, AuthError> {
+        Ok(self.login_url_at(Utc::now()))
+    }
+
+    fn consume(&self, response: &str) -> Result<SamlIdentity, AuthError> {
+        self.consume_at(response, Utc::now()).map_err(|e| {
+            tracing::warn!("SAML response rejected: {}", e);
+            AuthError::InvalidCredentials
+        })
+    }
+}
+
+fn verifying_key(certificate: &str) -> Result<VerifyingKey<Sha256>, SamlError> {
+    let der = STANDARD.decode(certificate).map_err(|e| SamlError::Certificate(e.to_string()))?;
+    let certificate = Certificate::from_der(&der).map_err(|e| SamlError::Certificate(e.to_string()))?;
+    let public_key = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|e| SamlError::Certificate(e.to_string()))?;
+    let public_key = RsaPublicKey::from_public_key_der(&public_key).map_err(|e| SamlError::Certificate(e.to_string()))?;
+    Ok(VerifyingKey::new(public_key))
+}
+
+fn required<'a>(element: &'a Element, namespace: &str, local_name: &str) -> Result<&'a Element, SamlError> {
+    element
+        .only_child(namespace, local_name)?
+        .ok_or_else(|| SamlError::Response(format!("{} element is missing", local_name)))
+}
+
+/// Returns a description of the status of an unsuccessful response.
+fn status(response: &Element) -> Result<Option<String>, SamlError> {
+    let status = required(response, PROTOCOL_NAMESPACE, "Status")?;
+    let code = required(status, PROTOCOL_NAMESPACE, "StatusCode")?;
+    match code.attribute("Value") {
+        Some(SUCCESS_STATUS) => Ok(None),
+        value => Ok(Some(format!("the identity provider answered with status {}", value.unwrap_or("(none)")))),
+    }
+}
+
+/// Checks the `NotBefore` and `NotOnOrAfter` attributes of `element`, with a tolerance of `skew`.
+fn check_validity(element: &Element, now: DateTime<Utc>, skew: chrono::Duration) -> Result<(), SamlError> {
+    if timestamp(element, "NotBefore")?.is_some_and(|not_before| now + skew < not_before) {
+        return Err(SamlError::Response(format!("the {} is not valid yet", element.local_name)));
+    }
+    if timestamp(element, "NotOnOrAfter")?.is_some_and(|not_on_or_after| now - skew >= not_on_or_after) {
+        return Err(SamlError::Response(format!("the {} has expired", element.local_name)));
+    }
+    Ok(())
+}
+
+fn timestamp(element: &Element, attribute: &str) -> Result<Option<DateTime<Utc>>, SamlError> {
+    element
+        .attribute(attribute)
+        .map(|value| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|timestamp| timestamp.with_timezone(&Utc))
+                .map_err(|_| SamlError::Response(format!("invalid {} timestamp {}", attribute, value)))
+        })
+        .transpose()
+}
+
+/// Returns the values of the attributes of the assertion, by attribute name.
+fn attributes(assertion: &Element) -> HashMap<&str, Vec<String>> {
+    let mut attributes: HashMap<&str, Vec<String>> = HashMap::new();
+    for statement in assertion.children_named(ASSERTION_NAMESPACE, "AttributeStatement") {
+        for attribute in statement.children_named(ASSERTION_NAMESPACE, "Attribute") {
+            if let Some(name) = attribute.attribute("Name") {
+                attributes
+                    .entry(name)
+                    .or_default()
+                    .extend(attribute.children_named(ASSERTION_NAMESPACE, "AttributeValue").map(|value| value.text()));
+            }
+        }
+    }
+    attributes
+}
+
+/// Percent-encodes the characters of base64 that are reserved in query strings.
+fn url_encode(value: &str) -> String {
+    value.replace('+', "%2B").replace('/', "%2F").replace('=', "%3D")
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,115 @@
+//! Verification of enveloped XML signatures (XML-DSig), as produced by SAML identity providers.
+//!
+//! Only the algorithms identity providers use by default are accepted: exclusive
+//! canonicalization, SHA-256 digests and RSA-SHA256 signatures. The keys are always the
+//! configured ones; keys embedded in the signature (`KeyInfo`) are ignored.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::signature::Verifier;
+use sha2::{Digest, Sha256};
+
+use super::xml::{canonicalize, Element};
+use super::SamlError;
+
+const DSIG_NAMESPACE: &str = "http://www.w3.org/2000/09/xmldsig#";
+const EXCLUSIVE_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+const ENVELOPED_SIGNATURE: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+
+/// Returns whether `element` is signed by one of `keys`, the signature being an enveloped
+/// `Signature` child covering the whole element.
+///
+/// Unsigned elements return `false`; signatures that are present but invalid are an error.
+pub fn is_signed(element: &Element, keys: &[VerifyingKey<Sha256>]) -> Result<bool, SamlError> {
+    let Some(signature) = element.only_child(DSIG_NAMESPACE, "Signature")? else {
+        return Ok(false);
+    };
+    let signed_info = required_child(signature, "SignedInfo")?;
+
+    let canonicalization = required_child(signed_info, "CanonicalizationMethod")?;
+    if canonicalization.attribute("Algorithm") != Some(EXCLUSIVE_C14N) {
+        return Err(unsupported("canonicalization", canonicalization));
+    }
+    let signature_method = required_child(signed_info, "SignatureMethod")?;
+    if signature_method.attribute("Algorithm") != Some(RSA_SHA256) {
+        return Err(unsupported("signature", signature_method));
+    }
+
+    // A single reference to the signed element itself, so the signature covers nothing else
+    let mut references = signed_info.children_named(DSIG_NAMESPACE, "Reference");
+    let reference = match (references.next(), references.next()) {
+        (Some(reference), None) => reference,
+        _ => return Err(SamlError::Signature("the signature must have exactly one reference".to_string())),
+    };
+    let id = element
+        .attribute("ID")
+        .ok_or_else(|| SamlError::Signature(format!("signed {} element has no ID", element.local_name)))?;
+    if reference.attribute("URI") != Some(format!("#{}", id).as_str()) {
+        return Err(SamlError::Signature(format!("the signature does not reference the {} element", element.local_name)));
+    }
+
+    let mut inclusive_prefixes = Vec::new();
+    let mut canonicalized = false;
+    if let Some(transforms) = reference.only_child(DSIG_NAMESPACE, "Transforms")? {
+        for transform in transforms.children_named(DSIG_NAMESPACE, "Transform") {
+            match transform.attribute("Algorithm") {
+                Some(ENVELOPED_SIGNATURE) => {}
+                Some(EXCLUSIVE_C14N) => {
+                    inclusive_prefixes = inclusive_namespace_prefixes(transform)?;
+                    canonicalized = true;
+                }
+                _ => return Err(unsupported("transform", transform)),
+            }
+        }
+    }
+    if !canonicalized {
+        return Err(SamlError::Signature("the reference is not canonicalized with exclusive canonicalization".to_string()));
+    }
+
+    let digest_method = required_child(reference, "DigestMethod")?;
+    if digest_method.attribute("Algorithm") != Some(SHA256) {
+        return Err(unsupported("digest", digest_method));
+    }
+    let digest_value = decode_base64(&required_child(reference, "DigestValue")?.text())?;
+    let digest = Sha256::digest(canonicalize(element, Some(signature), &inclusive_prefixes).as_bytes());
+    if digest.as_slice() != digest_value.as_slice() {
+        return Err(SamlError::Signature(format!("the digest of the {} element does not match", element.local_name)));
+    }
+
+    let signature_value = decode_base64(&required_child(signature, "SignatureValue")?.text())?;
+    let signature_value = Signature::try_from(signature_value.as_slice()).map_err(|e| SamlError::Signature(e.to_string()))?;
+    let signed_info = canonicalize(signed_info, None, &inclusive_namespace_prefixes(canonicalization)?);
+    if keys.iter().any(|key| key.verify(signed_info.as_bytes(), &signature_value).is_ok()) {
+        Ok(true)
+    } else {
+        Err(SamlError::Signature("the signature does not match any identity provider certificate".to_string()))
+    }
+}
+
+fn required_child<'a>(element: &'a Element, local_name: &str) -> Result<&'a Element, SamlError> {
+    element
+        .only_child(DSIG_NAMESPACE, local_name)?
+        .ok_or_else(|| SamlError::Signature(format!("{} element is missing", local_name)))
+}
+
+/// Returns the `PrefixList` of the `InclusiveNamespaces` of an exclusive canonicalization.
+fn inclusive_namespace_prefixes(method: &Element) -> Result<Vec<String>, SamlError> {
+    Ok(method
+        .only_child(EXCLUSIVE_C14N, "InclusiveNamespaces")?
+        .and_then(|inclusive_namespaces| inclusive_namespaces.attribute("PrefixList"))
+        .map(|prefixes| prefixes.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default())
+}
+
+fn unsupported(kind: &str, method: &Element) -> SamlError {
+    SamlError::Signature(format!("unsupported {} algorithm {}", kind, method.attribute("Algorithm").unwrap_or("(none)")))
+}
+
+/// Decodes base64 content, which XML documents may wrap over several lines.
+pub fn decode_base64(content: &str) -> Result<Vec<u8>, SamlError> {
+    let content: String = content.chars().filter(|character| !character.is_ascii_whitespace()).collect();
+    STANDARD.decode(content).map_err(|e| SamlError::Xml(format!("invalid base64 content: {}", e)))
+}
@@ -0,0 +1,356 @@
+//! A minimal XML tree and its Exclusive XML Canonicalization (without comments), enough to
+//! verify the signatures of SAML messages.
+//!
+//! Documents type definitions, processing instructions and entities other than the predefined
+//! ones are rejected: SAML messages never contain them, and they are the usual vectors of
+//! attacks against XML parsers.
+
+use std::collections::BTreeMap;
+
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion};
+
+use super::SamlError;
+
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Elements nested deeper are rejected, SAML messages being much shallower.
+const MAX_DEPTH: usize = 32;
+
+/// An element of a parsed document.
+#[derive(Debug, Clone)]
+pub struct Element {
+    pub prefix: Option<String>,
+    pub local_name: String,
+    pub namespace: Option<String>,
+    /// Attributes other than namespace declarations, in document order.
+    pub attributes: Vec<Attribute>,
+    /// Namespaces in scope, by prefix (`""` for the default namespace, `""` when undeclared).
+    pub scope: BTreeMap<String, String>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Attribute {
+    pub prefix: Option<String>,
+    pub local_name: String,
+    pub namespace: Option<String>,
+    pub value: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    /// Returns whether the element has the given namespace and local name.
+    pub fn is(&self, namespace: &str, local_name: &str) -> bool {
+        self.namespace.as_deref() == Some(namespace) && self.local_name == local_name
+    }
+
+    /// Returns the value of the attribute without namespace named `local_name`.
+    pub fn attribute(&self, local_name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.namespace.is_none() && attribute.local_name == local_name)
+            .map(|attribute| attribute.value.as_str())
+    }
+
+    /// Returns the child elements, in document order.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// Returns the child elements with the given namespace and local name.
+    pub fn children_named<'a>(&'a self, namespace: &'a str, local_name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.elements().filter(move |element| element.is(namespace, local_name))
+    }
+
+    /// Returns the only child element with the given namespace and local name.
+    pub fn only_child(&self, namespace: &str, local_name: &str) -> Result<Option<&Element>, SamlError> {
+        let mut child = None;
+        for element in self.elements().filter(|element| element.is(namespace, local_name)) {
+            if child.replace(element).is_some() {
+                return Err(SamlError::Response(format!("more than one {} element in {}", local_name, self.local_name)));
+            }
+        }
+        Ok(child)
+    }
+
+    /// Returns the concatenated text content of the element and its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.collect_text(&mut text);
+        text
+    }
+
+    fn collect_text(&self, text: &mut String) {
+        for node in &self.children {
+            match node {
+                Node::Element(element) => element.collect_text(text),
+                Node::Text(content) => text.push_str(content),
+            }
+        }
+    }
+
+    /// Returns the element and its descendants, depth first.
+    pub fn descendants(&self) -> Vec<&Element> {
+        let mut elements = vec![self];
+        let mut index = 0;
+        while index < elements.len() {
+            let element = elements[index];
+            elements.extend(element.elements());
+            index += 1;
+        }
+        elements
+    }
+}
+
+/// Parses a document, returning its root element.
+pub fn parse(document: &str) -> Result<Element, SamlError> {
+    let mut reader = Reader::from_str(document);
+    reader.config_mut().expand_empty_elements = true;
+
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root = None;
+    loop {
+        let event = reader.read_event().map_err(|e| SamlError::Xml(e.to_string()))?;
+        match event {
+            Event::Start(start) => {
+                if root.is_some() {
+                    return Err(SamlError::Xml("content after the root element".to_string()));
+                }
+                if stack.len() == MAX_DEPTH {
+                    return Err(SamlError::Xml("elements are nested too deeply".to_string()));
+                }
+                let parent_scope = stack.last().map(|parent| parent.scope.clone()).unwrap_or_default();
+                stack.push(start_element(&start, parent_scope)?);
+            }
+            Event::End(_) => {
+                let element = stack.pop().ok_or_else(|| SamlError::Xml("unexpected end tag".to_string()))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(Node::Element(element)),
+                    None => root = Some(element),
+                }
+            }
+            Event::Text(text) => push_text(&mut stack, &text.xml10_content())?,
+            Event::CData(cdata) => push_text(&mut stack, &cdata.xml10_content())?,
+            Event::GeneralRef(reference) => {
+                let character = match reference.resolve_char_ref().map_err(|e| SamlError::Xml(e.to_string()))? {
+                    Some(character) => character.to_string(),
+                    None => {
+                        let name = reference.xml10_content();
+                        resolve_predefined_entity(&name)
+                            .ok_or_else(|| SamlError::Xml(format!("undefined entity {}", name)))?
+                            .to_string()
+                    }
+                };
+                push_text(&mut stack, &character)?;
+            }
+            Event::Comment(_) | Event::Decl(_) => {}
+            Event::DocType(_) => return Err(SamlError::Xml("document type definitions are not allowed".to_string())),
+            Event::PI(_) => return Err(SamlError::Xml("processing instructions are not allowed".to_string())),
+            Event::Empty(_) => unreachable!("empty elements are expanded"),
+            Event::Eof => break,
+        }
+    }
+
+    match (root, stack.is_empty()) {
+        (Some(root), true) => Ok(root),
+        _ => Err(SamlError::Xml("unexpected end of document".to_string())),
+    }
+}
+
+fn start_element(start: &BytesStart<'_>, mut scope: BTreeMap<String, String>) -> Result<Element, SamlError> {
+    let mut attributes = Vec::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(|e| SamlError::Xml(e.to_string()))?;
+        let name: &str = attribute.key.as_ref();
+        let value = attribute
+            .normalized_value(XmlVersion::Implicit1_0)
+            .map_err(|e| SamlError::Xml(e.to_string()))?
+            .into_owned();
+
+        if name == "xmlns" {
+            scope.insert(String::new(), value);
+        } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+            if value.is_empty() {
+                return Err(SamlError::Xml(format!("namespace prefix {} is undeclared", prefix)));
+            }
+            scope.insert(prefix.to_string(), value);
+        } else {
+            let (prefix, local_name) = split_name(name);
+            attributes.push(Attribute {
+                prefix: prefix.map(str::to_string),
+                local_name: local_name.to_string(),
+                namespace: None,
+                value,
+            });
+        }
+    }
+
+    for attribute in &mut attributes {
+        attribute.namespace = match attribute.prefix.as_deref() {
+            None => None,
+            Some(prefix) => Some(resolve(&scope, prefix)?),
+        };
+    }
+
+    let name = start.name();
+    let (prefix, local_name) = split_name(name.as_ref());
+    let namespace = match prefix {
+        Some(prefix) => Some(resolve(&scope, prefix)?),
+        None => scope.get("").filter(|namespace| !namespace.is_empty()).cloned(),
+    };
+
+    Ok(Element {
+        prefix: prefix.map(str::to_string),
+        local_name: local_name.to_string(),
+        namespace,
+        attributes,
+        scope,
+        children: Vec::new(),
+    })
+}
+
+fn push_text(stack: &mut [Element], text: &str) -> Result<(), SamlError> {
+    match stack.last_mut() {
+        Some(element) => {
+            match element.children.last_mut() {
+                Some(Node::Text(content)) => content.push_str(text),
+                _ => element.children.push(Node::Text(text.to_string())),
+            }
+            Ok(())
+        }
+        None if text.trim().is_empty() => Ok(()),
+        None => Err(SamlError::Xml("text outside of the root element".to_string())),
+    }
+}
+
+fn split_name(name: &str) -> (Option<&str>, &str) {
+    match name.split_once(':') {
+        Some((prefix, local_name)) => (Some(prefix), local_name),
+        None => (None, name),
+    }
+}
+
+fn resolve(scope: &BTreeMap<String, String>, prefix: &str) -> Result<String, SamlError> {
+    if prefix == "xml" {
+        return Ok(XML_NAMESPACE.to_string());
+    }
+    scope
+        .get(prefix)
+        .cloned()
+        .ok_or_else(|| SamlError::Xml(format!("namespace prefix {} is undeclared", prefix)))
+}
+
+/// Canonicalizes `element` with Exclusive XML Canonicalization 1.0, omitting comments and the
+/// `excluded` descendant (the enveloped signature).
+///
+/// `inclusive_prefixes` is the `PrefixList` of the transform: namespaces with these prefixes
+/// are rendered as in inclusive canonicalization.
+pub fn canonicalize(element: &Element, excluded: Option<&Element>, inclusive_prefixes: &[String]) -> String {
+    let mut output = String::new();
+    render(element, excluded, inclusive_prefixes, &BTreeMap::new(), &mut output);
+    output
+}
+
+fn render(element: &Element, excluded: Option<&Element>, inclusive_prefixes: &[String], rendered: &BTreeMap<String, String>, output: &mut String) {
+    // Namespaces visibly utilized by the element and its attributes, plus the inclusive ones
+    let mut prefixes: Vec<&str> = vec![element.prefix.as_deref().unwrap_or("")];
+    prefixes.extend(element.attributes.iter().filter_map(|attribute| attribute.prefix.as_deref()));
+    prefixes.extend(
+        inclusive_prefixes
+            .iter()
+            .map(|prefix| if prefix == "#default" { "" } else { prefix.as_str() })
+            .filter(|prefix| element.scope.contains_key(*prefix)),
+    );
+
+    let mut declarations: BTreeMap<&str, &str> = BTreeMap::new();
+    for prefix in prefixes {
+        if prefix == "xml" {
+            continue;
+        }
+        let namespace = element.scope.get(prefix).map(String::as_str).unwrap_or("");
+        let already_rendered = rendered.get(prefix).map(String::as_str).unwrap_or("");
+        if namespace != already_rendered {
+            declarations.insert(prefix, namespace);
+        }
+    }
+
+    let mut rendered = rendered.clone();
+    output.push('<');
+    output.push_str(&qualified_name(element.prefix.as_deref(), &element.local_name));
+    for (prefix, namespace) in &declarations {
+        rendered.insert(prefix.to_string(), namespace.to_string());
+        if prefix.is_empty() {
+            output.push_str(" xmlns=\"");
+        } else {
+            output.push_str(&format!(" xmlns:{}=\"", prefix));
+        }
+        escape_attribute(namespace, output);
+        output.push('"');
+    }
+
+    let mut attributes: Vec<&Attribute> = element.attributes.iter().collect();
+    attributes.sort_by(|a, b| (a.namespace.as_deref().unwrap_or(""), &a.local_name).cmp(&(b.namespace.as_deref().unwrap_or(""), &b.local_name)));
+    for attribute in attributes {
+        output.push(' ');
+        output.push_str(&qualified_name(attribute.prefix.as_deref(), &attribute.local_name));
+        output.push_str("=\"");
+        escape_attribute(&attribute.value, output);
+        output.push('"');
+    }
+    output.push('>');
+
+    for node in &element.children {
+        match node {
+            Node::Element(child) if excluded.is_some_and(|excluded| std::ptr::eq(child, excluded)) => {}
+            Node::Element(child) => render(child, excluded, inclusive_prefixes, &rendered, output),
+            Node::Text(text) => escape_text(text, output),
+        }
+    }
+
+    output.push_str("</");
+    output.push_str(&qualified_name(element.prefix.as_deref(), &element.local_name));
+    output.push('>');
+}
+
+fn qualified_name(prefix: Option<&str>, local_name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}:{}", prefix, local_name),
+        None => local_name.to_string(),
+    }
+}
+
+fn escape_text(text: &str, output: &mut String) {
+    for character in text.chars() {
+        match character {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '\r' => output.push_str("&#xD;"),
+            _ => output.push(character),
+        }
+    }
+}
+
+fn escape_attribute(value: &str, output: &mut String) {
+    for character in value.chars() {
+        match character {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '"' => output.push_str("&quot;"),
+            '\t' => output.push_str("&#x9;"),
+            '\n' => output.push_str("&#xA;"),
+            '\r' => output.push_str("&#xD;"),
+            _ => output.push(character),
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Rotating ES256 keys signing access tokens.
+//!
+//! Keys are stored, encrypted with the JWT secret, so every replica signs and validates with
+//! the same keys. A new key is generated every rotation interval, published for the
+//! publication delay before it signs tokens, so the services caching the key set learn it
+//! before receiving its tokens, and the key it replaces keeps validating tokens until all the
+//! tokens it signed expired.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, TimeDelta, Utc};
+use eyre::Context;
+use hmac::{Hmac, Mac};
+use jsonwebtoken::{DecodingKey, EncodingKey};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use sha2::{Digest, Sha256};
+use tokio::{sync::watch, task::JoinHandle, time};
+
+use application::ports::auth::{KeySetPort, PublicSigningKey};
+use application::ports::clock::{ClockPort, SystemClock};
+
+use crate::auth::SigningKeysConfig;
+
+/// Label deriving the key encrypting the stored keys from the JWT secret, so the secret is not
+/// used as is for two purposes.
+const ENCRYPTION_KEY_LABEL: &[u8] = b"jwt-signing-keys";
+
+/// Length of the nonce prefixing the encrypted keys.
+const NONCE_LENGTH: usize = 12;
+
+/// A signing key as stored, its private key encrypted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredSigningKey {
+    /// Id of the key, its JWK thumbprint (RFC 7638).
+    pub kid: String,
+    pub created_at: DateTime<Utc>,
+    /// The PKCS#8 document of the key pair, encrypted with AES-256-GCM and prefixed with the nonce.
+    pub encrypted_key: Vec<u8>,
+}
+
+/// Storage of the signing keys, shared by the replicas.
+#[async_trait]
+pub trait SigningKeyStore {
+    /// Returns the stored keys, oldest first.
+    async fn list(&self) -> eyre::Result<Vec<StoredSigningKey>>;
+
+    /// Stores `key`, unless a key was created after `created_after`, e.g. by another replica
+    /// rotating the keys concurrently. Returns whether `key` was stored.
+    async fn add(&self, key: StoredSigningKey, created_after: DateTime<Utc>) -> eyre::Result<bool>;
+
+    /// Removes the keys with the ids `kids`.
+    async fn remove(&self, kids: &[String]) -> eyre::Result<()>;
+}
+
+/// A decrypted key.
+struct LoadedKey {
+    kid: String,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    public: PublicSigningKey,
+}
+
+/// The keys loaded at the last refresh.
+#[derive(Default)]
+struct KeyRing {
+    keys: Vec<Arc<LoadedKey>>,
+    signing: Option<Arc<LoadedKey>>,
+}
+
+/// The rotating keys signing and validating access tokens, as loaded from the store at the last refresh.
+pub struct SigningKeys {
+    store: Arc<dyn SigningKeyStore + Send + Sync>,
+    cipher: Aes256Gcm,
+    rotation_interval: TimeDelta,
+    publication_delay: TimeDelta,
+    retention: TimeDelta,
+    refresh_interval: Duration,
+    ring: RwLock<KeyRing>,
+    clock: Arc<dyn ClockPort + Send + Sync>,
+}
+
+impl SigningKeys {
+    /// Creates the keys stored in `store`, encrypted with `secret`. Retiring keys validate
+    /// tokens for `max_token_lifetime` after they stopped signing.
+    ///
+    /// No key is loaded until the first [`SigningKeys::refresh`]. Fails unless keys are refreshed
+    /// more often than they are published before signing, so every replica loads a new key
+    /// before receiving its tokens, and published for less than the rotation interval.
+    pub fn new(store: Arc<dyn SigningKeyStore + Send + Sync>, secret: &str, config: &SigningKeysConfig, max_token_lifetime: Duration) -> eyre::Result<Self> {
+        eyre::ensure!(
+            config.refresh_interval_secs > 0 && config.refresh_interval_secs < config.publication_delay_secs,
+            "the refresh interval of signing keys must be positive and shorter than their publication delay"
+        );
+        eyre::ensure!(
+            config.publication_delay_secs < config.rotation_interval_secs,
+            "the publication delay of signing keys must be shorter than their rotation interval"
+        );
+
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret.as_bytes()).context("invalid JWT secret")?;
+        mac.update(ENCRYPTION_KEY_LABEL);
+        let encryption_key = mac.finalize().into_bytes();
+        let delta = |secs: u64| TimeDelta::from_std(Duration::from_secs(secs)).context("signing key lifetime is out of range");
+
+        Ok(Self {
+            store,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&encryption_key)),
+            rotation_interval: delta(config.rotation_interval_secs)?,
+            publication_delay: delta(config.publication_delay_secs)?,
+            retention: TimeDelta::from_std(max_token_lifetime).context("token lifetime is out of range")?,
+            refresh_interval: Duration::from_secs(config.refresh_interval_secs),
+            ring: RwLock::new(KeyRing::default()),
+            clock: Arc::new(SystemClock),
+        })
+    }
+
+    /// Rotates and retires the keys with the time of `clock` instead of the time of the system.
+    pub fn with_clock(mut self, clock: Arc<dyn ClockPort + Send + Sync>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Rotates the keys when due, removes the retired ones, and loads the remaining ones.
+    pub async fn refresh(&self) -> eyre::Result<()> {
+        self.refresh_at(self.clock.now()).await
+    }
+
+    /// Refreshes the keys as of `now`, see [`SigningKeys::refresh`].
+    pub async fn refresh_at(&self, now: DateTime<Utc>) -> eyre::Result<()> {
+        let mut stored = self.store.list().await.context("failed to list signing keys")?;
+
+        if stored.last().is_none_or(|newest| newest.created_at + self.rotation_interval <= now) {
+            let key = self.generate(now)?;
+            let kid = key.kid.clone();
+            if self.store.add(key, now - self.rotation_interval).await.context("failed to store signing key")? {
+                tracing::info!(key.id = %kid, "generated signing key");
+            }
+            stored = self.store.list().await.context("failed to list signing keys")?;
+        }
+
+        // The oldest key signs as soon as it is created, there being no key to replace
+        let signing_start = |index: usize, key: &StoredSigningKey| match index {
+            0 => key.created_at,
+            _ => key.created_at + self.publication_delay,
+        };
+        let signing = stored.iter().enumerate().rposition(|(index, key)| signing_start(index, key) <= now).unwrap_or(0);
+        let retired: Vec<String> = stored[..signing]
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| signing_start(index + 1, &stored[index + 1]) + self.retention <= now)
+            .map(|(_, key)| key.kid.clone())
+            .collect();
+        if !retired.is_empty() {
+            self.store.remove(&retired).await.context("failed to remove retired signing keys")?;
+            tracing::info!("removed retired signing keys {}", retired.join(", "));
+        }
+
+        let keys = stored
+            .iter()
+            .filter(|key| !retired.contains(&key.kid))
+            .map(|key| self.decrypt(key).map(Arc::new))
+            .collect::<eyre::Result<Vec<_>>>()?;
+        let signing = keys.iter().find(|key| key.kid == stored[signing].kid).cloned();
+
+        *self.ring.write().unwrap_or_else(|e| e.into_inner()) = KeyRing { keys, signing };
+        Ok(())
+    }
+
+    /// Returns the id and the private key of the key signing tokens.
+    pub(crate) fn signing_key(&self) -> Option<(String, EncodingKey)> {
+        let ring = self.ring.read().unwrap_or_else(|e| e.into_inner());
+        ring.signing.as_ref().map(|key| (key.kid.clone(), key.encoding.clone()))
+    }
+
+    /// Returns the public key of the key `kid`, if it still validates tokens.
+    pub(crate) fn decoding_key(&self, kid: &str) -> Option<DecodingKey> {
+        let ring = self.ring.read().unwrap_or_else(|e| e.into_inner());
+        ring.keys.iter().find(|key| key.kid == kid).map(|key| key.decoding.clone())
+    }
+
+    /// Generates a key pair created at `now`, encrypted for storage.
+    fn generate(&self, now: DateTime<Utc>) -> eyre::Result<StoredSigningKey> {
+        let rng = SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).map_err(|_| eyre::eyre!("failed to generate signing key"))?;
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng).map_err(|e| eyre::eyre!("invalid signing key: {}", e))?;
+        let (x, y) = public_point(&pair);
+        let kid = thumbprint(&x, &y);
+
+        let nonce: [u8; NONCE_LENGTH] = rand::random();
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: pkcs8.as_ref(), aad: kid.as_bytes() })
+            .map_err(|_| eyre::eyre!("failed to encrypt signing key"))?;
+
+        Ok(StoredSigningKey {
+            kid,
+            created_at: now,
+            encrypted_key: [nonce.as_slice(), &ciphertext].concat(),
+        })
+    }
+
+    fn decrypt(&self, key: &StoredSigningKey) -> eyre::Result<LoadedKey> {
+        if key.encrypted_key.len() < NONCE_LENGTH {
+            eyre::bail!("signing key {} is truncated", key.kid);
+        }
+        let (nonce, ciphertext) = key.encrypted_key.split_at(NONCE_LENGTH);
+        let pkcs8 = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: key.kid.as_bytes() })
+            .map_err(|_| eyre::eyre!("failed to decrypt signing key {}, was JWT_SECRET changed?", key.kid))?;
+
+        let pair = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &pkcs8, &SystemRandom::new())
+            .map_err(|e| eyre::eyre!("invalid signing key {}: {}", key.kid, e))?;
+        let (x, y) = public_point(&pair);
+        let public = PublicSigningKey { kid: key.kid.clone(), x, y };
+        let decoding = DecodingKey::from_ec_components(&public.x, &public.y).with_context(|| format!("invalid signing key {}", key.kid))?;
+
+        Ok(LoadedKey {
+            kid: key.kid.clone(),
+            encoding: EncodingKey::from_ec_der(&pkcs8),
+            decoding,
+            public,
+        })
+    }
+}
+
+impl KeySetPort for SigningKeys {
+    fn public_keys(&self) -> Vec<PublicSigningKey> {
+        let ring = self.ring.read().unwrap_or_else(|e| e.into_inner());
+        ring.keys.iter().map(|key| key.public.clone()).collect()
+    }
+}
+
+/// Returns the base64url-encoded coordinates of the public point of `pair`.
+fn public_point(pair: &EcdsaKeyPair) -> (String, String) {
+    // Uncompressed point: 0x04, then the x and y coordinates
+    let point = pair.public_key().as_ref();
+    (URL_SAFE_NO_PAD.encode(&point[1..33]), URL_SAFE_NO_PAD.encode(&point[33..65]))
+}
+
+/// Returns the JWK thumbprint (RFC 7638) of the P-256 public key with the coordinates `x` and `y`.
+fn thumbprint(x: &str, y: &str) -> String {
+    let canonical = format!(r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#, x, y);
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
+
+/// Background task refreshing [`SigningKeys`], rotating them when due.
+///
+/// Failures are logged and retried at the next refresh, the keys loaded last remaining in use.
+pub struct KeyRotation {
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl KeyRotation {
+    /// Starts refreshing `keys` every `refresh_interval_secs`.
+    pub fn spawn(keys: Arc<SigningKeys>) -> Self {
+        let (shutdown, mut stopped) = watch::channel(false);
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    // The task was dropped without being shut down
+                    changed = stopped.changed() => if changed.is_err() || *stopped.borrow() { break },
+                    _ = time::sleep(keys.refresh_interval) => {}
+                }
+                if let Err(e) = keys.refresh().await {
+                    tracing::warn!("failed to refresh signing keys: {:#}", e);
+                }
+            }
+        });
+
+        Self { shutdown, task }
+    }
+
+    /// Stops refreshing the keys.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            tracing::error!("signing key rotation task failed: {}", e);
+        }
+    }
+}
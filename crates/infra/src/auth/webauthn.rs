@@ -0,0 +1,198 @@
+//! WebAuthn relying party registering passkeys and logging users in with them.
+//!
+//! Passkeys are bound to the configured relying party id and origin. The state of started
+//! ceremonies is kept in memory until they are finished or time out, so both requests of a
+//! ceremony must reach the same replica. Credentials are stored as the JSON serialization of
+//! [`webauthn_rs::prelude::Passkey`].
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::Value;
+use uuid::Uuid;
+use webauthn_rs::prelude::{
+    Passkey as Credential, PasskeyAuthentication, PasskeyRegistration, PublicKeyCredential, RegisterPublicKeyCredential, Url,
+};
+use webauthn_rs::{Webauthn, WebauthnBuilder};
+
+use application::ports::webauthn::{NewCredential, PasskeyAssertion, PasskeyChallenge, PasskeyError, WebAuthnPort};
+use domain::passkey::model::Passkey;
+use domain::user::model::{User, UserId};
+
+use crate::auth::WebAuthnConfig;
+
+/// Maximum number of ceremonies pending at once; starting more fails until some finish or
+/// time out, bounding the memory used by abandoned ceremonies.
+const MAX_PENDING_CEREMONIES: usize = 10_000;
+
+/// Reasons the relying party cannot be created from its settings.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WebAuthnError {
+    #[error("invalid origin: {0}")]
+    Origin(String),
+    #[error("invalid relying party: {0}")]
+    RelyingParty(String),
+}
+
+/// State of a started ceremony, kept until its response is received.
+enum Ceremony {
+    Registration {
+        user_id: UserId,
+        state: PasskeyRegistration,
+    },
+    Authentication {
+        user_id: UserId,
+        state: PasskeyAuthentication,
+        /// The passkeys the ceremony was started with, one of which gets updated by the login.
+        registered: Vec<Credential>,
+    },
+}
+
+/// WebAuthn relying party running the passkey ceremonies with `webauthn-rs`.
+pub struct WebAuthnRelyingParty {
+    webauthn: Webauthn,
+    timeout: Duration,
+    /// Started ceremonies by id, with the time they were started.
+    ceremonies: Mutex<HashMap<String, (Instant, Ceremony)>>,
+}
+
+impl WebAuthnRelyingParty {
+    /// Creates a new `WebAuthnRelyingParty` instance, failing if the origin is not a URL or the
+    /// relying party id is not a suffix of its host.
+    pub fn new(config: &WebAuthnConfig) -> Result<Self, WebAuthnError> {
+        let origin = Url::parse(&config.rp_origin).map_err(|e| WebAuthnError::Origin(e.to_string()))?;
+        let timeout = Duration::from_secs(config.ceremony_timeout_secs);
+        let webauthn = WebauthnBuilder::new(&config.rp_id, &origin)
+            .and_then(|builder| builder.rp_name(&config.rp_name).timeout(timeout).build())
+            .map_err(|e| WebAuthnError::RelyingParty(e.to_string()))?;
+
+        Ok(Self {
+            webauthn,
+            timeout,
+            ceremonies: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Stores a started ceremony, returning its id.
+    fn begin(&self, ceremony: Ceremony) -> Result<String, PasskeyError> {
+        let mut ceremonies = self.ceremonies.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        ceremonies.retain(|_, (started_at, _)| now.duration_since(*started_at) < self.timeout);
+        if ceremonies.len() >= MAX_PENDING_CEREMONIES {
+            tracing::warn!("too many pending WebAuthn ceremonies");
+            return Err(PasskeyError::Unavailable);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        ceremonies.insert(id.clone(), (now, ceremony));
+        Ok(id)
+    }
+
+    /// Removes a started ceremony, so it cannot be finished twice, unless it timed out.
+    fn take(&self, ceremony_id: &str) -> Result<Ceremony, PasskeyError> {
+        let mut ceremonies = self.ceremonies.lock().unwrap_or_else(|e| e.into_inner());
+        match ceremonies.remove(ceremony_id) {
+            Some((started_at, ceremony)) if started_at.elapsed() < self.timeout => Ok(ceremony),
+            _ => Err(PasskeyError::UnknownCeremony),
+        }
+    }
+}
+
+impl WebAuthnPort for WebAuthnRelyingParty {
+    fn start_registration(&self, user: &User, registered: &[Passkey]) -> Result<PasskeyChallenge, PasskeyError> {
+        let exclude = registered
+            .iter()
+            .map(|passkey| decode_credential(&passkey.credential).map(|credential| credential.cred_id().clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (options, state) = self
+            .webauthn
+            .start_passkey_registration(*user.id().as_uuid(), user.email().as_str(), user.name(), Some(exclude))
+            .map_err(|e| {
+                tracing::warn!(error = %e, "failed to start a passkey registration");
+                PasskeyError::Unavailable
+            })?;
+
+        let ceremony_id = self.begin(Ceremony::Registration { user_id: user.id(), state })?;
+        Ok(PasskeyChallenge {
+            ceremony_id,
+            options: serde_json::to_value(options).map_err(|_| PasskeyError::Unavailable)?,
+        })
+    }
+
+    fn finish_registration(&self, user_id: UserId, ceremony_id: &str, response: Value) -> Result<NewCredential, PasskeyError> {
+        let Ceremony::Registration { user_id: started_by, state } = self.take(ceremony_id)? else {
+            return Err(PasskeyError::UnknownCeremony);
+        };
+        if started_by != user_id {
+            return Err(PasskeyError::UnknownCeremony);
+        }
+
+        let response: RegisterPublicKeyCredential = serde_json::from_value(response).map_err(|_| PasskeyError::InvalidResponse)?;
+        let credential = self.webauthn.finish_passkey_registration(&response, &state).map_err(|e| {
+            tracing::debug!(error = %e, "rejected a passkey registration");
+            PasskeyError::InvalidResponse
+        })?;
+
+        Ok(NewCredential {
+            id: URL_SAFE_NO_PAD.encode(credential.cred_id()),
+            credential: encode_credential(&credential)?,
+        })
+    }
+
+    fn start_authentication(&self, user_id: UserId, registered: &[Passkey]) -> Result<PasskeyChallenge, PasskeyError> {
+        let registered = registered
+            .iter()
+            .map(|passkey| decode_credential(&passkey.credential))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (options, state) = self.webauthn.start_passkey_authentication(&registered).map_err(|e| {
+            tracing::warn!(error = %e, "failed to start a passkey authentication");
+            PasskeyError::Unavailable
+        })?;
+
+        let ceremony_id = self.begin(Ceremony::Authentication { user_id, state, registered })?;
+        Ok(PasskeyChallenge {
+            ceremony_id,
+            options: serde_json::to_value(options).map_err(|_| PasskeyError::Unavailable)?,
+        })
+    }
+
+    fn finish_authentication(&self, ceremony_id: &str, response: Value) -> Result<PasskeyAssertion, PasskeyError> {
+        let Ceremony::Authentication { user_id, state, registered } = self.take(ceremony_id)? else {
+            return Err(PasskeyError::UnknownCeremony);
+        };
+
+        let response: PublicKeyCredential = serde_json::from_value(response).map_err(|_| PasskeyError::InvalidResponse)?;
+        let result = self.webauthn.finish_passkey_authentication(&response, &state).map_err(|e| {
+            tracing::debug!(error = %e, "rejected a passkey authentication");
+            PasskeyError::InvalidResponse
+        })?;
+
+        let mut credential = registered
+            .into_iter()
+            .find(|credential| credential.cred_id() == result.cred_id())
+            .ok_or(PasskeyError::InvalidResponse)?;
+        credential.update_credential(&result);
+
+        Ok(PasskeyAssertion {
+            user_id,
+            id: URL_SAFE_NO_PAD.encode(credential.cred_id()),
+            credential: encode_credential(&credential)?,
+        })
+    }
+}
+
+fn encode_credential(credential: &Credential) -> Result<String, PasskeyError> {
+    serde_json::to_string(credential).map_err(|_| PasskeyError::Unavailable)
+}
+
+fn decode_credential(credential: &str) -> Result<Credential, PasskeyError> {
+    serde_json::from_str(credential).map_err(|e| {
+        tracing::error!(error = %e, "failed to decode a stored passkey");
+        PasskeyError::Unavailable
+    })
+}
@@ -0,0 +1,36 @@
+use async_trait::async_trait;
+
+use application::flows::password_policy::PasswordRule;
+use domain::user::{model::Password, validation::PasswordViolation};
+
+/// Rule refusing the passwords easy to guess, by the strength zxcvbn estimates from the patterns
+/// they are made of (words, names, dates, keyboard sequences, ...) and the attributes of their
+/// user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZxcvbnStrengthRule {
+    min_score: u8,
+}
+
+impl ZxcvbnStrengthRule {
+    /// Creates a new `ZxcvbnStrengthRule` refusing the passwords scoring less than `min_score`,
+    /// from 0 (too guessable) to 4 (very unguessable).
+    pub fn new(min_score: u8) -> Self {
+        Self { min_score }
+    }
+}
+
+#[async_trait]
+impl PasswordRule for ZxcvbnStrengthRule {
+    async fn check(&self, password: &Password, user_inputs: &[&str]) -> Option<PasswordViolation> {
+        let entropy = zxcvbn::zxcvbn(password.as_str(), user_inputs);
+        let score = u8::from(entropy.score());
+        if score >= self.min_score {
+            return None;
+        }
+        let mut message = format!("is too easy to guess (strength {} of 4, {} required)", score, self.min_score);
+        if let Some(warning) = entropy.feedback().and_then(|feedback| feedback.warning()) {
+            message = format!("{}: {}", message, warning);
+        }
+        Some(PasswordViolation { rule: "min_strength", message })
+    }
+}
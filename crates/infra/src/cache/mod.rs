@@ -0,0 +1,13 @@
+#[cfg(feature = "redis")]
+pub mod redis;
+
+use domain::secret::Secret;
+
+/// Settings of the Redis cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// Connection URL, e.g. `redis://:password@cache:6379/0` (`rediss://` for TLS).
+    pub url: Secret<String>,
+    /// Time cached values are served before being read again from the database, in seconds.
+    pub ttl_secs: u64,
+}
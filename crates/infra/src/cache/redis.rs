@@ -0,0 +1,87 @@
+use std::future::Future;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eyre::Context;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use redis::{AsyncCommands, RedisResult};
+use tokio::time;
+
+use application::ports::{cache::CachePort, capability::{Capability, DependencyStatus}};
+
+use crate::cache::CacheConfig;
+
+/// Maximum duration of a command, reconnection included. A slow cache is treated as
+/// unavailable, so callers fall back to the database instead of stalling requests.
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Cache backed by Redis.
+///
+/// The connection is established on first use and re-established in the background after
+/// failures, so the server starts and keeps serving while Redis is unavailable.
+#[derive(Clone)]
+pub struct RedisCache {
+    connection: ConnectionManager,
+}
+
+impl RedisCache {
+    /// Creates a new `RedisCache` connecting to `config.url`. Fails when the URL is invalid.
+    pub fn new(config: &CacheConfig) -> eyre::Result<Self> {
+        let client = redis::Client::open(config.url.expose_secret().as_str()).context("invalid cache URL")?;
+        let connection = client
+            .get_connection_manager_lazy(
+                ConnectionManagerConfig::new()
+                    .set_number_of_retries(1)
+                    .set_connection_timeout(Some(TIMEOUT))
+                    .set_response_timeout(Some(TIMEOUT)),
+            )
+            .context("failed to create Redis connection manager")?;
+        Ok(Self { connection })
+    }
+}
+
+#[async_trait]
+impl Capability for RedisCache {
+    fn name(&self) -> &'static str {
+        "cache"
+    }
+
+    async fn check(&self) -> DependencyStatus {
+        match bounded(redis::cmd("PING").query_async::<()>(&mut self.connection.clone())).await {
+            Ok(()) => DependencyStatus::Up,
+            Err(e) => {
+                tracing::warn!("cache health check failed: {}", e);
+                DependencyStatus::Down
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CachePort for RedisCache {
+    async fn get(&self, key: &str) -> eyre::Result<Option<Vec<u8>>> {
+        bounded(self.connection.clone().get(key)).await
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> eyre::Result<()> {
+        // Redis rejects a zero expiry, and a value expiring at once is not worth storing
+        let millis = ttl.as_millis().min(u128::from(u64::MAX)) as u64;
+        if millis > 0 {
+            bounded(self.connection.clone().pset_ex::<_, _, ()>(key, value, millis)).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> eyre::Result<()> {
+        bounded(self.connection.clone().del::<_, usize>(key)).await?;
+        Ok(())
+    }
+}
+
+/// Runs a Redis command, failing when it does not complete within [`TIMEOUT`].
+async fn bounded<T>(command: impl Future<Output = RedisResult<T>>) -> eyre::Result<T> {
+    match time::timeout(TIMEOUT, command).await {
+        Ok(result) => Ok(result?),
+        Err(_) => eyre::bail!("Redis command timed out after {:?}", TIMEOUT),
+    }
+}
@@ -0,0 +1,1501 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use eyre::Context;
+use figment::providers::{Format, Toml, Yaml};
+use figment::Figment;
+
+use domain::secret::Secret;
+use domain::user::validation::{MAX_PASSWORD_LENGTH, MIN_PASSWORD_LENGTH};
+
+use crate::{auth::{BreachCheckConfig, DeviceGrantConfig, JwtConfig, LdapConfig, OidcConfig, PasswordFallback, PasswordPolicyConfig, SamlConfig, SigningKeysConfig, WebAuthnConfig}, cache::CacheConfig, discovery::DiscoveryConfig, email::{SmtpConfig, SmtpTls}, error_reporting::{SentryConfig, DEFAULT_RELEASE}, jobs::JobsConfig, kubernetes::{KubernetesConfig, LeaderElectionConfig, PodMetadata}, messaging::{CommandConsumerConfig, EventFormat, KafkaConfig, MqttConfig}, outbox::OutboxConfig, stats::StatsConfig, purge::{CloudflarePurgeConfig, FastlyPurgeConfig, PurgeConfig}, telemetry::{sampling::SamplingConfig, LogFormat, OtlpConfig}, storage::PoolConfig, traffic_archive::TrafficArchiveConfig};
+
+const CONFIG_FILE_KEY: &str = "CONFIG_FILE";
+
+const DATABASE_URL_KEY: &str = "DATABASE_URL";
+
+const DATABASE_MAX_CONNECTIONS_KEY: &str = "DATABASE_MAX_CONNECTIONS";
+
+const DATABASE_MIN_CONNECTIONS_KEY: &str = "DATABASE_MIN_CONNECTIONS";
+
+const DATABASE_ACQUIRE_TIMEOUT_SECS_KEY: &str = "DATABASE_ACQUIRE_TIMEOUT_SECS";
+
+const DATABASE_IDLE_TIMEOUT_SECS_KEY: &str = "DATABASE_IDLE_TIMEOUT_SECS";
+
+const DATABASE_MAX_LIFETIME_SECS_KEY: &str = "DATABASE_MAX_LIFETIME_SECS";
+
+const DATABASE_CONNECT_ATTEMPTS_KEY: &str = "DATABASE_CONNECT_ATTEMPTS";
+
+const SERVER_PORT_KEY: &str = "SERVER_PORT";
+
+const LOG_FORMAT_KEY: &str = "LOG_FORMAT";
+
+const OTEL_EXPORTER_OTLP_ENDPOINT_KEY: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+const OTEL_SERVICE_NAME_KEY: &str = "OTEL_SERVICE_NAME";
+
+const OTEL_TRACES_SAMPLER_ARG_KEY: &str = "OTEL_TRACES_SAMPLER_ARG";
+
+const DATABASE_SRV_RECORD_KEY: &str = "DATABASE_SRV_RECORD";
+
+const DISCOVERY_REFRESH_INTERVAL_SECS_KEY: &str = "DISCOVERY_REFRESH_INTERVAL_SECS";
+
+const DISCOVERY_FAILURE_THRESHOLD_KEY: &str = "DISCOVERY_FAILURE_THRESHOLD";
+
+const POD_NAME_KEY: &str = "POD_NAME";
+
+const POD_NAMESPACE_KEY: &str = "POD_NAMESPACE";
+
+const NODE_NAME_KEY: &str = "NODE_NAME";
+
+const POD_IP_KEY: &str = "POD_IP";
+
+const LEADER_ELECTION_LEASE_NAME_KEY: &str = "LEADER_ELECTION_LEASE_NAME";
+
+const LEADER_ELECTION_LEASE_DURATION_SECS_KEY: &str = "LEADER_ELECTION_LEASE_DURATION_SECS";
+
+const TERMINATION_DELAY_SECS_KEY: &str = "TERMINATION_DELAY_SECS";
+
+const SHUTDOWN_TIMEOUT_SECS_KEY: &str = "SHUTDOWN_TIMEOUT_SECS";
+
+const MAX_BODY_BYTES_KEY: &str = "MAX_BODY_BYTES";
+
+const REQUEST_TIMEOUT_SECS_KEY: &str = "REQUEST_TIMEOUT_SECS";
+
+const MAX_CONCURRENT_REQUESTS_KEY: &str = "MAX_CONCURRENT_REQUESTS";
+
+const COMPRESSION_ENCODINGS_KEY: &str = "COMPRESSION_ENCODINGS";
+
+const THRIFT_PORT_KEY: &str = "THRIFT_PORT";
+
+const GRAPHQL_ENABLED_KEY: &str = "GRAPHQL_ENABLED";
+
+const SAMPLING_SUCCESS_RATE_KEY: &str = "SAMPLING_SUCCESS_RATE";
+
+const SAMPLING_ERROR_RATE_KEY: &str = "SAMPLING_ERROR_RATE";
+
+const SAMPLING_ROUTE_OVERRIDES_KEY: &str = "SAMPLING_ROUTE_OVERRIDES";
+
+const ADMIN_TOKEN_KEY: &str = "ADMIN_TOKEN";
+
+const MANAGEMENT_TOKEN_KEY: &str = "MANAGEMENT_TOKEN";
+
+const MANAGEMENT_BASE_PATH_KEY: &str = "MANAGEMENT_BASE_PATH";
+
+const MANAGEMENT_PORT_KEY: &str = "MANAGEMENT_PORT";
+
+const DEFAULT_MANAGEMENT_BASE_PATH: &str = "/actuator";
+
+const SCIM_TOKEN_KEY: &str = "SCIM_TOKEN";
+
+const INTROSPECTION_TOKEN_KEY: &str = "INTROSPECTION_TOKEN";
+
+const PAGINATION_CURSOR_SECRET_KEY: &str = "PAGINATION_CURSOR_SECRET";
+
+const RUN_MIGRATIONS_KEY: &str = "RUN_MIGRATIONS";
+
+const OUTBOX_ENABLED_KEY: &str = "OUTBOX_ENABLED";
+
+const OUTBOX_POLL_INTERVAL_MS_KEY: &str = "OUTBOX_POLL_INTERVAL_MS";
+
+const OUTBOX_BATCH_SIZE_KEY: &str = "OUTBOX_BATCH_SIZE";
+
+const OUTBOX_FORMAT_KEY: &str = "OUTBOX_FORMAT";
+
+const KAFKA_BROKERS_KEY: &str = "KAFKA_BROKERS";
+
+const KAFKA_TOPIC_KEY: &str = "KAFKA_TOPIC";
+
+const KAFKA_FORMAT_KEY: &str = "KAFKA_FORMAT";
+
+const KAFKA_DELIVERY_TIMEOUT_MS_KEY: &str = "KAFKA_DELIVERY_TIMEOUT_MS";
+
+const MQTT_BROKER_KEY: &str = "MQTT_BROKER";
+
+const MQTT_CLIENT_ID_KEY: &str = "MQTT_CLIENT_ID";
+
+const MQTT_USERNAME_KEY: &str = "MQTT_USERNAME";
+
+const MQTT_PASSWORD_KEY: &str = "MQTT_PASSWORD";
+
+const MQTT_TOPIC_KEY: &str = "MQTT_TOPIC";
+
+const MQTT_QOS_KEY: &str = "MQTT_QOS";
+
+const MQTT_RETAIN_KEY: &str = "MQTT_RETAIN";
+
+const MQTT_FORMAT_KEY: &str = "MQTT_FORMAT";
+
+const MQTT_DELIVERY_TIMEOUT_MS_KEY: &str = "MQTT_DELIVERY_TIMEOUT_MS";
+
+const COMMANDS_KAFKA_BROKERS_KEY: &str = "COMMANDS_KAFKA_BROKERS";
+
+const COMMANDS_TOPIC_KEY: &str = "COMMANDS_TOPIC";
+
+const COMMANDS_REPLY_TOPIC_KEY: &str = "COMMANDS_REPLY_TOPIC";
+
+const COMMANDS_GROUP_ID_KEY: &str = "COMMANDS_GROUP_ID";
+
+const STATS_ENABLED_KEY: &str = "STATS_ENABLED";
+
+const STATS_INTERVAL_SECS_KEY: &str = "STATS_INTERVAL_SECS";
+
+const JOBS_ENABLED_KEY: &str = "JOBS_ENABLED";
+
+const JOBS_POLL_INTERVAL_MS_KEY: &str = "JOBS_POLL_INTERVAL_MS";
+
+const JOBS_BATCH_SIZE_KEY: &str = "JOBS_BATCH_SIZE";
+
+const JOBS_MAX_ATTEMPTS_KEY: &str = "JOBS_MAX_ATTEMPTS";
+
+const JOBS_LEASE_SECS_KEY: &str = "JOBS_LEASE_SECS";
+
+const JWE_KEYS_KEY: &str = "JWE_KEYS";
+
+const CORS_ALLOWED_ORIGINS_KEY: &str = "CORS_ALLOWED_ORIGINS";
+
+const CORS_ALLOWED_METHODS_KEY: &str = "CORS_ALLOWED_METHODS";
+
+const CORS_ALLOWED_HEADERS_KEY: &str = "CORS_ALLOWED_HEADERS";
+
+const CORS_ALLOW_CREDENTIALS_KEY: &str = "CORS_ALLOW_CREDENTIALS";
+
+const IDEMPOTENCY_ENABLED_KEY: &str = "IDEMPOTENCY_ENABLED";
+
+const AUDIT_LOG_ENABLED_KEY: &str = "AUDIT_LOG_ENABLED";
+
+const IDEMPOTENCY_TTL_SECS_KEY: &str = "IDEMPOTENCY_TTL_SECS";
+
+const IDEMPOTENCY_LEASE_SECS_KEY: &str = "IDEMPOTENCY_LEASE_SECS";
+
+const TENANT_SETTINGS_ENABLED_KEY: &str = "TENANT_SETTINGS_ENABLED";
+
+const TENANT_SETTINGS_CACHE_TTL_SECS_KEY: &str = "TENANT_SETTINGS_CACHE_TTL_SECS";
+
+const RATE_LIMIT_REQUESTS_KEY: &str = "RATE_LIMIT_REQUESTS";
+
+const RATE_LIMIT_PERIOD_SECS_KEY: &str = "RATE_LIMIT_PERIOD_SECS";
+
+const RATE_LIMIT_ROUTE_OVERRIDES_KEY: &str = "RATE_LIMIT_ROUTE_OVERRIDES";
+
+const RATE_LIMIT_CLASS_OVERRIDES_KEY: &str = "RATE_LIMIT_CLASS_OVERRIDES";
+
+const RATE_LIMIT_TRUST_FORWARDED_FOR_KEY: &str = "RATE_LIMIT_TRUST_FORWARDED_FOR";
+
+const ANOMALY_DETECTION_ENABLED_KEY: &str = "ANOMALY_DETECTION_ENABLED";
+
+const ANOMALY_WINDOW_SECS_KEY: &str = "ANOMALY_WINDOW_SECS";
+
+const ANOMALY_HISTORY_WINDOWS_KEY: &str = "ANOMALY_HISTORY_WINDOWS";
+
+const ANOMALY_Z_THRESHOLD_KEY: &str = "ANOMALY_Z_THRESHOLD";
+
+const ANOMALY_MIN_MUTATIONS_KEY: &str = "ANOMALY_MIN_MUTATIONS";
+
+const ANOMALY_RATE_LIMIT_DIVISOR_KEY: &str = "ANOMALY_RATE_LIMIT_DIVISOR";
+
+const ANOMALY_RATE_LIMIT_SECS_KEY: &str = "ANOMALY_RATE_LIMIT_SECS";
+
+const SLO_OBJECTIVES_KEY: &str = "SLO_OBJECTIVES";
+
+const SLO_WINDOW_SECS_KEY: &str = "SLO_WINDOW_SECS";
+
+const SLO_BUCKET_SECS_KEY: &str = "SLO_BUCKET_SECS";
+
+const SLO_BURN_WINDOW_SECS_KEY: &str = "SLO_BURN_WINDOW_SECS";
+
+const SLO_BURN_RATE_THRESHOLD_KEY: &str = "SLO_BURN_RATE_THRESHOLD";
+
+const SLO_MIN_REQUESTS_KEY: &str = "SLO_MIN_REQUESTS";
+
+const REQUEST_TAP_ENABLED_KEY: &str = "REQUEST_TAP_ENABLED";
+
+const REQUEST_TAP_BUFFER_KEY: &str = "REQUEST_TAP_BUFFER";
+
+const IN_FLIGHT_REQUESTS_ENABLED_KEY: &str = "IN_FLIGHT_REQUESTS_ENABLED";
+
+const TEST_CLOCK_ENABLED_KEY: &str = "TEST_CLOCK_ENABLED";
+
+const APP_ENVIRONMENT_KEY: &str = "APP_ENVIRONMENT";
+
+const TRAFFIC_ARCHIVE_URL_KEY: &str = "TRAFFIC_ARCHIVE_URL";
+
+const TRAFFIC_ARCHIVE_SAMPLE_RATE_KEY: &str = "TRAFFIC_ARCHIVE_SAMPLE_RATE";
+
+const TRAFFIC_ARCHIVE_MAX_BODY_BYTES_KEY: &str = "TRAFFIC_ARCHIVE_MAX_BODY_BYTES";
+
+const TRAFFIC_ARCHIVE_BATCH_SIZE_KEY: &str = "TRAFFIC_ARCHIVE_BATCH_SIZE";
+
+const TRAFFIC_ARCHIVE_FLUSH_INTERVAL_SECS_KEY: &str = "TRAFFIC_ARCHIVE_FLUSH_INTERVAL_SECS";
+
+const PURGE_URL_KEY: &str = "PURGE_URL";
+
+const PURGE_METHOD_KEY: &str = "PURGE_METHOD";
+
+const PURGE_KEYS_HEADER_KEY: &str = "PURGE_KEYS_HEADER";
+
+const PURGE_TOKEN_KEY: &str = "PURGE_TOKEN";
+
+const CLOUDFLARE_ZONE_ID_KEY: &str = "CLOUDFLARE_ZONE_ID";
+
+const CLOUDFLARE_API_TOKEN_KEY: &str = "CLOUDFLARE_API_TOKEN";
+
+const CLOUDFLARE_API_URL_KEY: &str = "CLOUDFLARE_API_URL";
+
+const FASTLY_SERVICE_ID_KEY: &str = "FASTLY_SERVICE_ID";
+
+const FASTLY_API_TOKEN_KEY: &str = "FASTLY_API_TOKEN";
+
+const FASTLY_API_URL_KEY: &str = "FASTLY_API_URL";
+
+const FASTLY_SOFT_PURGE_KEY: &str = "FASTLY_SOFT_PURGE";
+
+const CACHE_URL_KEY: &str = "CACHE_URL";
+
+const CACHE_TTL_SECS_KEY: &str = "CACHE_TTL_SECS";
+
+const JWT_SECRET_KEY: &str = "JWT_SECRET";
+
+const JWT_EXPIRY_SECS_KEY: &str = "JWT_EXPIRY_SECS";
+
+const JWT_KEY_ROTATION_INTERVAL_SECS_KEY: &str = "JWT_KEY_ROTATION_INTERVAL_SECS";
+
+const JWT_KEY_PUBLICATION_DELAY_SECS_KEY: &str = "JWT_KEY_PUBLICATION_DELAY_SECS";
+
+const JWT_KEY_REFRESH_INTERVAL_SECS_KEY: &str = "JWT_KEY_REFRESH_INTERVAL_SECS";
+
+const DEVICE_VERIFICATION_URI_KEY: &str = "DEVICE_VERIFICATION_URI";
+
+const DEVICE_CODE_LIFETIME_SECS_KEY: &str = "DEVICE_CODE_LIFETIME_SECS";
+
+const DEVICE_POLL_INTERVAL_SECS_KEY: &str = "DEVICE_POLL_INTERVAL_SECS";
+
+const OIDC_ISSUER_KEY: &str = "OIDC_ISSUER";
+
+const OIDC_AUDIENCE_KEY: &str = "OIDC_AUDIENCE";
+
+const OIDC_JWKS_URL_KEY: &str = "OIDC_JWKS_URL";
+
+const OIDC_ROLES_CLAIM_KEY: &str = "OIDC_ROLES_CLAIM";
+
+const OIDC_JWKS_REFRESH_INTERVAL_SECS_KEY: &str = "OIDC_JWKS_REFRESH_INTERVAL_SECS";
+
+const OIDC_JWKS_REFETCH_COOLDOWN_SECS_KEY: &str = "OIDC_JWKS_REFETCH_COOLDOWN_SECS";
+
+const OIDC_CLOCK_SKEW_SECS_KEY: &str = "OIDC_CLOCK_SKEW_SECS";
+
+const LDAP_URL_KEY: &str = "LDAP_URL";
+
+const LDAP_BIND_DN_KEY: &str = "LDAP_BIND_DN";
+
+const LDAP_BIND_PASSWORD_KEY: &str = "LDAP_BIND_PASSWORD";
+
+const LDAP_USER_BASE_DN_KEY: &str = "LDAP_USER_BASE_DN";
+
+const LDAP_USER_FILTER_KEY: &str = "LDAP_USER_FILTER";
+
+const LDAP_USER_ID_ATTRIBUTE_KEY: &str = "LDAP_USER_ID_ATTRIBUTE";
+
+const LDAP_GROUP_ATTRIBUTE_KEY: &str = "LDAP_GROUP_ATTRIBUTE";
+
+const LDAP_GROUP_ROLES_KEY: &str = "LDAP_GROUP_ROLES";
+
+const LDAP_POOL_SIZE_KEY: &str = "LDAP_POOL_SIZE";
+
+const LDAP_TIMEOUT_SECS_KEY: &str = "LDAP_TIMEOUT_SECS";
+
+const SAML_IDP_SSO_URL_KEY: &str = "SAML_IDP_SSO_URL";
+
+const SAML_IDP_ENTITY_ID_KEY: &str = "SAML_IDP_ENTITY_ID";
+
+const SAML_IDP_CERTIFICATES_KEY: &str = "SAML_IDP_CERTIFICATES";
+
+const SAML_SP_ENTITY_ID_KEY: &str = "SAML_SP_ENTITY_ID";
+
+const SAML_ACS_URL_KEY: &str = "SAML_ACS_URL";
+
+const SAML_EMAIL_ATTRIBUTE_KEY: &str = "SAML_EMAIL_ATTRIBUTE";
+
+const SAML_ROLES_ATTRIBUTE_KEY: &str = "SAML_ROLES_ATTRIBUTE";
+
+const SAML_CLOCK_SKEW_SECS_KEY: &str = "SAML_CLOCK_SKEW_SECS";
+
+const SAML_LOGIN_REDIRECT_URL_KEY: &str = "SAML_LOGIN_REDIRECT_URL";
+
+const WEBAUTHN_RP_ID_KEY: &str = "WEBAUTHN_RP_ID";
+
+const WEBAUTHN_RP_ORIGIN_KEY: &str = "WEBAUTHN_RP_ORIGIN";
+
+const WEBAUTHN_RP_NAME_KEY: &str = "WEBAUTHN_RP_NAME";
+
+const WEBAUTHN_CEREMONY_TIMEOUT_SECS_KEY: &str = "WEBAUTHN_CEREMONY_TIMEOUT_SECS";
+
+const WEBAUTHN_PASSWORD_FALLBACK_KEY: &str = "WEBAUTHN_PASSWORD_FALLBACK";
+
+const PASSWORD_MIN_LENGTH_KEY: &str = "PASSWORD_MIN_LENGTH";
+
+const PASSWORD_MIN_STRENGTH_KEY: &str = "PASSWORD_MIN_STRENGTH";
+
+const PASSWORD_BREACH_CHECK_KEY: &str = "PASSWORD_BREACH_CHECK";
+
+const PASSWORD_BREACH_API_URL_KEY: &str = "PASSWORD_BREACH_API_URL";
+
+const PASSWORD_BREACH_TIMEOUT_MS_KEY: &str = "PASSWORD_BREACH_TIMEOUT_MS";
+
+const SENTRY_DSN_KEY: &str = "SENTRY_DSN";
+
+const SENTRY_ENVIRONMENT_KEY: &str = "SENTRY_ENVIRONMENT";
+
+const SENTRY_RELEASE_KEY: &str = "SENTRY_RELEASE";
+
+const SMTP_HOST_KEY: &str = "SMTP_HOST";
+
+const SMTP_PORT_KEY: &str = "SMTP_PORT";
+
+const SMTP_USERNAME_KEY: &str = "SMTP_USERNAME";
+
+const SMTP_PASSWORD_KEY: &str = "SMTP_PASSWORD";
+
+const SMTP_FROM_KEY: &str = "SMTP_FROM";
+
+const SMTP_TLS_KEY: &str = "SMTP_TLS";
+
+const SMTP_TIMEOUT_MS_KEY: &str = "SMTP_TIMEOUT_MS";
+
+const DEFAULT_CONFIG_FILES: [&str; 3] = ["config.toml", "config.yaml", "config.yml"];
+
+const DEFAULT_DATABASE_MAX_CONNECTIONS: u32 = 5;
+
+const DEFAULT_DATABASE_MIN_CONNECTIONS: u32 = 0;
+
+const DEFAULT_DATABASE_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+
+const DEFAULT_DATABASE_IDLE_TIMEOUT_SECS: u64 = 600;
+
+const DEFAULT_DATABASE_MAX_LIFETIME_SECS: u64 = 1800;
+
+const DEFAULT_DATABASE_CONNECT_ATTEMPTS: u32 = 5;
+
+const DEFAULT_DISCOVERY_REFRESH_INTERVAL_SECS: u64 = 30;
+
+const DEFAULT_DISCOVERY_FAILURE_THRESHOLD: u32 = 3;
+
+const DEFAULT_LEADER_ELECTION_LEASE_DURATION_SECS: u32 = 15;
+
+const DEFAULT_TERMINATION_DELAY_SECS: u64 = 5;
+
+const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+const DEFAULT_MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
+
+const DEFAULT_COMPRESSION_ENCODINGS: &str = "gzip,br,zstd,deflate";
+
+const DEFAULT_SAMPLING_SUCCESS_RATE: f64 = 0.01;
+
+const DEFAULT_SAMPLING_ERROR_RATE: f64 = 1.0;
+
+const DEFAULT_PASSWORD_BREACH_API_URL: &str = "https://api.pwnedpasswords.com";
+
+const DEFAULT_PASSWORD_BREACH_TIMEOUT_MS: u64 = 2000;
+
+/// Highest strength of passwords estimated by zxcvbn.
+const MAX_PASSWORD_STRENGTH: u8 = 4;
+
+const DEFAULT_SENTRY_ENVIRONMENT: &str = "production";
+
+/// Name of the production environment, in which the test clock is refused.
+const PRODUCTION_ENVIRONMENT: &str = "production";
+
+const DEFAULT_SMTP_TIMEOUT_MS: u64 = 10_000;
+
+const DEFAULT_OTEL_SERVICE_NAME: &str = "rust-web-server";
+
+const DEFAULT_OTEL_TRACES_SAMPLER_ARG: f64 = 1.0;
+
+const DEFAULT_OUTBOX_POLL_INTERVAL_MS: u64 = 1000;
+
+const DEFAULT_OUTBOX_BATCH_SIZE: usize = 100;
+
+const DEFAULT_KAFKA_TOPIC: &str = "user-events";
+
+const DEFAULT_KAFKA_DELIVERY_TIMEOUT_MS: u64 = 30_000;
+
+const DEFAULT_MQTT_TOPIC: &str = "users/{user_id}/events";
+
+const DEFAULT_MQTT_QOS: u8 = 1;
+
+const DEFAULT_MQTT_DELIVERY_TIMEOUT_MS: u64 = 30_000;
+
+const DEFAULT_COMMANDS_TOPIC: &str = "user-commands";
+
+const DEFAULT_COMMANDS_REPLY_TOPIC: &str = "user-command-replies";
+
+const DEFAULT_COMMANDS_GROUP_ID: &str = "rust-web-server";
+
+const DEFAULT_STATS_INTERVAL_SECS: u64 = 3600;
+
+const DEFAULT_JOBS_POLL_INTERVAL_MS: u64 = 1000;
+
+const DEFAULT_JOBS_BATCH_SIZE: usize = 10;
+
+const DEFAULT_JOBS_MAX_ATTEMPTS: u32 = 5;
+
+const DEFAULT_JOBS_LEASE_SECS: u64 = 300;
+
+const DEFAULT_JWT_EXPIRY_SECS: u64 = 3600;
+
+const DEFAULT_JWT_KEY_PUBLICATION_DELAY_SECS: u64 = 3600;
+
+const DEFAULT_JWT_KEY_REFRESH_INTERVAL_SECS: u64 = 60;
+
+const DEFAULT_CORS_ALLOWED_METHODS: &str = "GET,POST,PUT,PATCH,DELETE";
+
+const DEFAULT_CORS_ALLOWED_HEADERS: &str = "authorization,content-type,if-match";
+
+const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 86400;
+
+const DEFAULT_IDEMPOTENCY_LEASE_SECS: u64 = 60;
+
+const DEFAULT_TENANT_SETTINGS_CACHE_TTL_SECS: u64 = 30;
+
+const DEFAULT_RATE_LIMIT_PERIOD_SECS: u64 = 60;
+
+const DEFAULT_ANOMALY_WINDOW_SECS: u64 = 60;
+
+const DEFAULT_ANOMALY_HISTORY_WINDOWS: usize = 60;
+
+const DEFAULT_ANOMALY_Z_THRESHOLD: f64 = 4.0;
+
+const DEFAULT_ANOMALY_MIN_MUTATIONS: u64 = 20;
+
+const DEFAULT_ANOMALY_RATE_LIMIT_SECS: u64 = 900;
+
+const DEFAULT_SLO_WINDOW_SECS: u64 = 86400;
+
+const DEFAULT_SLO_BUCKET_SECS: u64 = 60;
+
+const DEFAULT_SLO_BURN_WINDOW_SECS: u64 = 300;
+
+const DEFAULT_SLO_BURN_RATE_THRESHOLD: f64 = 14.4;
+
+const DEFAULT_SLO_MIN_REQUESTS: u64 = 100;
+
+const DEFAULT_REQUEST_TAP_BUFFER: usize = 1024;
+
+const DEFAULT_TRAFFIC_ARCHIVE_SAMPLE_RATE: f64 = 0.01;
+
+const DEFAULT_TRAFFIC_ARCHIVE_MAX_BODY_BYTES: usize = 64 * 1024;
+
+const DEFAULT_TRAFFIC_ARCHIVE_BATCH_SIZE: usize = 1000;
+
+const DEFAULT_TRAFFIC_ARCHIVE_FLUSH_INTERVAL_SECS: u64 = 300;
+
+const DEFAULT_PURGE_METHOD: &str = "POST";
+
+const DEFAULT_PURGE_KEYS_HEADER: &str = "Surrogate-Key";
+
+const DEFAULT_CLOUDFLARE_API_URL: &str = "https://api.cloudflare.com/client/v4";
+
+const DEFAULT_FASTLY_API_URL: &str = "https://api.fastly.com";
+
+const DEFAULT_FASTLY_SOFT_PURGE: bool = false;
+
+const DEFAULT_CACHE_TTL_SECS: u64 = 300;
+
+const DEFAULT_DEVICE_CODE_LIFETIME_SECS: u64 = 600;
+
+const DEFAULT_DEVICE_POLL_INTERVAL_SECS: u64 = 5;
+
+const DEFAULT_OIDC_ROLES_CLAIM: &str = "roles";
+
+const DEFAULT_OIDC_JWKS_REFRESH_INTERVAL_SECS: u64 = 300;
+
+const DEFAULT_OIDC_JWKS_REFETCH_COOLDOWN_SECS: u64 = 30;
+
+const DEFAULT_OIDC_CLOCK_SKEW_SECS: u64 = 60;
+
+const DEFAULT_LDAP_USER_FILTER: &str = "(mail={username})";
+
+const DEFAULT_LDAP_USER_ID_ATTRIBUTE: &str = "uid";
+
+const DEFAULT_LDAP_GROUP_ATTRIBUTE: &str = "memberOf";
+
+const DEFAULT_LDAP_POOL_SIZE: usize = 4;
+
+const DEFAULT_LDAP_TIMEOUT_SECS: u64 = 5;
+
+const DEFAULT_SAML_CLOCK_SKEW_SECS: u64 = 60;
+
+const DEFAULT_WEBAUTHN_CEREMONY_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub server_port: u16,
+    /// URL of the database, which may carry the credentials of the connections.
+    pub database_url: Secret<String>,
+    /// Limits and timeouts of the pool of database connections.
+    pub database_pool: PoolConfig,
+    /// Whether pending migrations are applied at startup (`RUN_MIGRATIONS`, default false).
+    pub run_migrations: bool,
+    /// Format of the logs (`LOG_FORMAT`, `pretty` (default) or `json`).
+    pub log_format: LogFormat,
+    /// Export of spans over OTLP, enabled when `OTEL_EXPORTER_OTLP_ENDPOINT` is set.
+    pub otlp: Option<OtlpConfig>,
+    /// Publishing of the events of the users through the `event_outbox` table, enabled when
+    /// `OUTBOX_ENABLED` is true.
+    pub outbox: Option<OutboxConfig>,
+    /// Publishing of the events of the users straight to Kafka, enabled when `KAFKA_BROKERS`
+    /// is set. Exclusive with the outbox.
+    pub kafka: Option<KafkaConfig>,
+    /// Publishing of the events of the users to an MQTT broker, enabled when `MQTT_BROKER` is
+    /// set. Exclusive with the outbox and Kafka.
+    pub mqtt: Option<MqttConfig>,
+    /// Execution of the commands changing users consumed from Kafka, enabled when
+    /// `COMMANDS_KAFKA_BROKERS` is set.
+    pub commands: Option<CommandConsumerConfig>,
+    /// Materialization of the daily statistics of the users, enabled when `STATS_ENABLED` is
+    /// true.
+    pub stats: Option<StatsConfig>,
+    /// Background jobs stored in the `jobs` table and run by a worker on every replica,
+    /// enabled when `JOBS_ENABLED` is true.
+    pub jobs: Option<JobsConfig>,
+    /// Maximum time in-flight requests are given to complete after SIGTERM/SIGINT, in seconds.
+    pub shutdown_timeout_secs: u64,
+    /// Maximum size of request bodies, in bytes (`MAX_BODY_BYTES`, default 2 MiB). Larger
+    /// requests are answered with 413 Payload Too Large.
+    pub max_body_bytes: usize,
+    /// Maximum time a request is given to be answered, in seconds (`REQUEST_TIMEOUT_SECS`,
+    /// default 30). Later requests are answered with 408 Request Timeout. `None` when set to
+    /// 0, requests then never time out.
+    pub request_timeout_secs: Option<u64>,
+    /// Maximum number of requests handled at once (`MAX_CONCURRENT_REQUESTS`). Further requests
+    /// are answered with 503 Service Unavailable. Unlimited when unset.
+    pub max_concurrent_requests: Option<usize>,
+    /// Encodings responses may be compressed with, among `gzip`, `br`, `zstd` and `deflate`
+    /// (`COMPRESSION_ENCODINGS`, default all of them), as accepted by the client. Compression
+    /// is disabled with `none`.
+    pub compression_encodings: Vec<String>,
+    /// Port of the internal Thrift RPC server of the user service, started when `THRIFT_PORT`
+    /// is set. Calls are limited to `max_body_bytes` like HTTP requests.
+    pub thrift_port: Option<u16>,
+    /// Whether the GraphQL API of the users is served at `/api/graphql` (`GRAPHQL_ENABLED`,
+    /// default false).
+    pub graphql: bool,
+    /// Optional SRV-based discovery of the database endpoint. When set, the host and port of
+    /// `database_url` are replaced with the resolved endpoint and refreshed at runtime.
+    pub database_discovery: Option<DiscoveryConfig>,
+    /// Kubernetes integration, enabled when the pod name and namespace are exposed
+    /// through the downward API.
+    pub kubernetes: Option<KubernetesConfig>,
+    /// Sampling of request logs. `SAMPLING_ROUTE_OVERRIDES` uses the `prefix=rate,prefix=rate` format.
+    pub sampling: SamplingConfig,
+    /// Bearer token protecting the admin routes, which are disabled when unset.
+    pub admin_token: Option<Secret<String>>,
+    /// Operational endpoints (health, info, metrics, loggers, routes, dependencies) under a
+    /// base path, enabled when `MANAGEMENT_TOKEN` is set.
+    pub management: Option<ManagementConfig>,
+    /// Bearer token of the identity provider using the SCIM routes, which are disabled when unset.
+    pub scim_token: Option<Secret<String>>,
+    /// Bearer token of the services introspecting access tokens, whose route is disabled when unset.
+    pub introspection_token: Option<Secret<String>>,
+    /// Secret signing the cursors of the lists of users, shared by the replicas. A random one is
+    /// used when unset, whose cursors are refused by the other replicas and after a restart.
+    pub pagination_cursor_secret: Option<Secret<String>>,
+    /// Keys accepted for JWE-encrypted request bodies, as `(key id, base64url key)` pairs.
+    /// `JWE_KEYS` uses the `kid=key,kid=key` format; request encryption is disabled when empty.
+    pub jwe_keys: Vec<(String, Secret<String>)>,
+    /// Cross-origin requests of browser frontends, accepted when `CORS_ALLOWED_ORIGINS` is set.
+    pub cors: Option<CorsConfig>,
+    /// Replay of the responses of the user routes to the retries sent with the same
+    /// `Idempotency-Key`, enabled when `IDEMPOTENCY_ENABLED` is true.
+    pub idempotency: Option<IdempotencyConfig>,
+    /// Whether the changes of the users are recorded in the `audit_log` table, and their history
+    /// served at `/api/users/{id}/audit` (`AUDIT_LOG_ENABLED`, default false).
+    pub audit_log: bool,
+    /// Settings overridden by tenants, stored in the database and enabled when
+    /// `TENANT_SETTINGS_ENABLED` is true.
+    pub tenant_settings: Option<TenantSettingsConfig>,
+    /// Rate limiting of the API per client and route, enabled when `RATE_LIMIT_REQUESTS` is set.
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Alerting on anomalous rates of user creations and deletions, enabled when
+    /// `ANOMALY_DETECTION_ENABLED` is true.
+    pub anomaly_detection: Option<AnomalyDetectionConfig>,
+    /// Tracking of the latency objectives of the `/api` routes, enabled when `SLO_OBJECTIVES` is set.
+    pub slo: Option<SloConfig>,
+    /// Live feed of the API requests for the admin routes, enabled when `REQUEST_TAP_ENABLED`
+    /// is true.
+    pub request_tap: Option<RequestTapConfig>,
+    /// Whether the API requests being handled are tracked, to be listed and cancelled through
+    /// the admin routes (`IN_FLIGHT_REQUESTS_ENABLED`, default false).
+    pub in_flight_requests: bool,
+    /// Whether the clock of the tokens, signing keys and device codes can be shifted through the
+    /// admin routes, for integration environments only (`TEST_CLOCK_ENABLED`, default false).
+    /// Refused in the production environment.
+    pub test_clock: bool,
+    /// Name of the environment the server runs in (`APP_ENVIRONMENT`, e.g. `staging`), also the
+    /// default of `SENTRY_ENVIRONMENT`.
+    pub environment: Option<String>,
+    /// Archiving of a sample of the API traffic, enabled when `TRAFFIC_ARCHIVE_URL` is set.
+    pub traffic_archive: Option<TrafficArchiveConfig>,
+    /// Purging of the responses cached by shared caches on changes, enabled when `PURGE_URL` is set.
+    pub purge: Option<PurgeConfig>,
+    /// Purging of the responses cached by Cloudflare on changes, enabled when
+    /// `CLOUDFLARE_ZONE_ID` is set.
+    pub cloudflare_purge: Option<CloudflarePurgeConfig>,
+    /// Purging of the responses cached by Fastly on changes, enabled when `FASTLY_SERVICE_ID`
+    /// is set.
+    pub fastly_purge: Option<FastlyPurgeConfig>,
+    /// Caching of users in Redis, enabled when `CACHE_URL` is set.
+    pub cache: Option<CacheConfig>,
+    /// Signing of access tokens, enabled when `JWT_SECRET` is set. Authenticated routes reject
+    /// every request when unset.
+    pub jwt: Option<JwtConfig>,
+    /// Signing of access tokens with rotating keys instead of the JWT secret, enabled when
+    /// `JWT_KEY_ROTATION_INTERVAL_SECS` is set. The keys are stored encrypted with the secret.
+    pub jwt_signing_keys: Option<SigningKeysConfig>,
+    /// Device authorization grant for CLI tools, enabled when `DEVICE_VERIFICATION_URI` is set.
+    pub device_grant: Option<DeviceGrantConfig>,
+    /// Validation of the access tokens of an external OpenID Connect provider, enabled when
+    /// `OIDC_ISSUER` is set. The key set is discovered from the issuer unless `OIDC_JWKS_URL` is set.
+    pub oidc: Option<OidcConfig>,
+    /// Authentication against an LDAP server or Active Directory, enabled when `LDAP_URL` is set.
+    /// `LDAP_GROUP_ROLES` uses the `group DN=role;group DN=role` format.
+    pub ldap: Option<LdapConfig>,
+    /// Single sign-on through a SAML identity provider, enabled when `SAML_IDP_SSO_URL` is set.
+    /// `SAML_IDP_CERTIFICATES` lists base64 DER (or PEM) certificates separated by commas.
+    pub saml: Option<SamlConfig>,
+    /// Passkey registration and login, enabled when `WEBAUTHN_RP_ID` is set. The name of the
+    /// relying party defaults to its id; `WEBAUTHN_PASSWORD_FALLBACK` is `allowed` (default) or
+    /// `disabled`, refusing password logins of users who registered a passkey.
+    pub webauthn: Option<WebAuthnConfig>,
+    /// Rules the passwords of users must follow: at least `PASSWORD_MIN_LENGTH` characters
+    /// (8 by default, up to 128), a zxcvbn strength of at least `PASSWORD_MIN_STRENGTH` (0 to 4)
+    /// when set, and absence from the breaches known to Have I Been Pwned when
+    /// `PASSWORD_BREACH_CHECK` is `true`.
+    pub password_policy: PasswordPolicyConfig,
+    /// Reporting of server errors and panics to Sentry, enabled when `SENTRY_DSN` is set.
+    pub sentry: Option<SentryConfig>,
+    /// Delivery of e-mails, such as welcome e-mails, through an SMTP server, enabled when
+    /// `SMTP_HOST` is set. `SMTP_FROM` is then required; `SMTP_TLS` is `starttls` (default),
+    /// `tls` or `none`, and `SMTP_USERNAME` and `SMTP_PASSWORD` are set together.
+    pub smtp: Option<SmtpConfig>,
+}
+
+/// Settings of the cross-origin requests of browser frontends. Lists are separated by commas in
+/// the environment, and `*` allows any value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    /// Origins allowed to call the API, e.g. `https://app.example.com`.
+    pub allowed_origins: Vec<String>,
+    /// Methods of the cross-origin requests (`CORS_ALLOWED_METHODS`, default
+    /// `GET,POST,PUT,PATCH,DELETE`).
+    pub allowed_methods: Vec<String>,
+    /// Headers the cross-origin requests may set (`CORS_ALLOWED_HEADERS`, default
+    /// `authorization,content-type,if-match`).
+    pub allowed_headers: Vec<String>,
+    /// Whether cross-origin requests may carry credentials (`CORS_ALLOW_CREDENTIALS`, default false).
+    pub allow_credentials: bool,
+}
+
+/// Settings of the idempotency keys of the requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotencyConfig {
+    /// Time the responses are replayed for, in seconds (`IDEMPOTENCY_TTL_SECS`, default 86400).
+    pub ttl_secs: u64,
+    /// Time a key is held for while its request is handled, in seconds
+    /// (`IDEMPOTENCY_LEASE_SECS`, default 60).
+    pub lease_secs: u64,
+}
+
+/// Settings of the overrides of the tenants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TenantSettingsConfig {
+    /// Time each replica caches the settings of a tenant for, in seconds
+    /// (`TENANT_SETTINGS_CACHE_TTL_SECS`, default 30).
+    pub cache_ttl_secs: u64,
+}
+
+/// Settings of the rate limiting of the API, applied to each client on each route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// Requests a client may send to a route in each period.
+    pub requests: u32,
+    /// Period of the limit, in seconds (`RATE_LIMIT_PERIOD_SECS`, default 60).
+    pub period_secs: u64,
+    /// Per-route overrides of `requests`, as `(path prefix, requests)` pairs.
+    /// `RATE_LIMIT_ROUTE_OVERRIDES` uses the `prefix=requests,prefix=requests` format.
+    pub route_overrides: Vec<(String, u32)>,
+    /// Overrides of `requests` for the routes of a rate limit class (`standard`, `credentials`
+    /// or `bulk`), as `(class, requests)` pairs. `RATE_LIMIT_CLASS_OVERRIDES` uses the
+    /// `class=requests,class=requests` format.
+    pub class_overrides: Vec<(String, u32)>,
+    /// Whether clients are identified by the `X-Forwarded-For` header set by a reverse proxy
+    /// (`RATE_LIMIT_TRUST_FORWARDED_FOR`, default false).
+    pub trust_forwarded_for: bool,
+}
+
+/// Settings of the detection of anomalous rates of user creations and deletions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyDetectionConfig {
+    /// Length of the windows mutations are counted in, in seconds (`ANOMALY_WINDOW_SECS`, default 60).
+    pub window_secs: u64,
+    /// Number of past windows the current one is compared to (`ANOMALY_HISTORY_WINDOWS`, default 60).
+    pub history_windows: usize,
+    /// Z-score from which the count of a window is anomalous (`ANOMALY_Z_THRESHOLD`, default 4).
+    pub z_threshold: f64,
+    /// Mutations a window needs before it can be anomalous (`ANOMALY_MIN_MUTATIONS`, default 20).
+    pub min_mutations: u64,
+    /// Divisor of the rate limits applied on anomalies (`ANOMALY_RATE_LIMIT_DIVISOR`). Anomalies
+    /// only raise alerts when unset, or when rate limiting is disabled.
+    pub rate_limit_divisor: Option<u32>,
+    /// Time the rate limits stay tight after an anomaly, in seconds (`ANOMALY_RATE_LIMIT_SECS`, default 900).
+    pub rate_limit_secs: u64,
+}
+
+/// Settings of the tracking of latency objectives and of their error budgets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloConfig {
+    /// The objectives. `SLO_OBJECTIVES` uses the `name:method:latency_ms:target_percent` format,
+    /// separated by commas, with `*` for any method, e.g. `reads:GET:100:99,all:*:500:99.9`.
+    pub objectives: Vec<SloObjectiveConfig>,
+    /// Rolling window of the compliance and the error budgets, in seconds (`SLO_WINDOW_SECS`, default 86400).
+    pub window_secs: u64,
+    /// Length of the buckets requests are counted in, in seconds (`SLO_BUCKET_SECS`, default 60).
+    pub bucket_secs: u64,
+    /// Window of the burn rates, in seconds (`SLO_BURN_WINDOW_SECS`, default 300).
+    pub burn_window_secs: u64,
+    /// Burn rate from which an alert is raised (`SLO_BURN_RATE_THRESHOLD`, default 14.4).
+    pub burn_rate_threshold: f64,
+    /// Requests the burn window needs before it can raise an alert (`SLO_MIN_REQUESTS`, default 100).
+    pub min_requests: u64,
+}
+
+/// A latency objective of `SLO_OBJECTIVES`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloObjectiveConfig {
+    pub name: String,
+    /// Method of the requests covered, all requests when `None`.
+    pub method: Option<String>,
+    pub latency_ms: u64,
+    /// Share of the requests served within the latency, in percent, e.g. 99.
+    pub target_percent: f64,
+}
+
+/// Settings of the operational endpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManagementConfig {
+    /// Path the endpoints are nested under (`MANAGEMENT_BASE_PATH`, default `/actuator`).
+    pub base_path: String,
+    /// Bearer token required by the endpoints other than health (`MANAGEMENT_TOKEN`).
+    pub token: Secret<String>,
+    /// Port of a separate listener serving only the endpoints (`MANAGEMENT_PORT`). They are
+    /// served by the API port when unset.
+    pub port: Option<u16>,
+}
+
+/// Settings of the live feed of the API requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestTapConfig {
+    /// Summaries buffered for each subscriber before the oldest are skipped
+    /// (`REQUEST_TAP_BUFFER`, default 1024).
+    pub buffer: usize,
+}
+
+impl Config {
+    /// Loads the configuration from `source`, failing with every missing and invalid value.
+    pub fn load(source: &ConfigSource) -> eyre::Result<Config> {
+        let mut loader = Loader::new(source);
+
+        let server_port = loader.required(SERVER_PORT_KEY);
+        let database_url = loader.required(DATABASE_URL_KEY);
+
+        let database_pool = PoolConfig {
+            max_connections: loader.or(DATABASE_MAX_CONNECTIONS_KEY, DEFAULT_DATABASE_MAX_CONNECTIONS),
+            min_connections: loader.or(DATABASE_MIN_CONNECTIONS_KEY, DEFAULT_DATABASE_MIN_CONNECTIONS),
+            acquire_timeout_secs: loader.or(DATABASE_ACQUIRE_TIMEOUT_SECS_KEY, DEFAULT_DATABASE_ACQUIRE_TIMEOUT_SECS),
+            // 0 disables the timeouts
+            idle_timeout_secs: Some(loader.or(DATABASE_IDLE_TIMEOUT_SECS_KEY, DEFAULT_DATABASE_IDLE_TIMEOUT_SECS)).filter(|secs| *secs > 0),
+            max_lifetime_secs: Some(loader.or(DATABASE_MAX_LIFETIME_SECS_KEY, DEFAULT_DATABASE_MAX_LIFETIME_SECS)).filter(|secs| *secs > 0),
+            connect_attempts: loader.or(DATABASE_CONNECT_ATTEMPTS_KEY, DEFAULT_DATABASE_CONNECT_ATTEMPTS),
+        };
+        if database_pool.max_connections == 0 {
+            loader.invalid(DATABASE_MAX_CONNECTIONS_KEY, "expected at least 1 connection");
+        } else if database_pool.min_connections > database_pool.max_connections {
+            loader.invalid(DATABASE_MIN_CONNECTIONS_KEY, "exceeds DATABASE_MAX_CONNECTIONS");
+        }
+
+        let database_discovery = loader.optional(DATABASE_SRV_RECORD_KEY).map(|record| DiscoveryConfig {
+            record,
+            refresh_interval_secs: loader.or(DISCOVERY_REFRESH_INTERVAL_SECS_KEY, DEFAULT_DISCOVERY_REFRESH_INTERVAL_SECS),
+            failure_threshold: loader.or(DISCOVERY_FAILURE_THRESHOLD_KEY, DEFAULT_DISCOVERY_FAILURE_THRESHOLD),
+        });
+
+        let kubernetes = match (loader.optional(POD_NAME_KEY), loader.optional(POD_NAMESPACE_KEY)) {
+            (Some(name), Some(namespace)) => Some(KubernetesConfig {
+                pod: PodMetadata {
+                    name,
+                    namespace,
+                    node_name: loader.optional(NODE_NAME_KEY),
+                    pod_ip: loader.optional(POD_IP_KEY),
+                },
+                leader_election: loader.optional(LEADER_ELECTION_LEASE_NAME_KEY).map(|lease_name| LeaderElectionConfig {
+                    lease_name,
+                    lease_duration_secs: loader.or(LEADER_ELECTION_LEASE_DURATION_SECS_KEY, DEFAULT_LEADER_ELECTION_LEASE_DURATION_SECS),
+                }),
+                termination_delay_secs: loader.or(TERMINATION_DELAY_SECS_KEY, DEFAULT_TERMINATION_DELAY_SECS),
+            }),
+            _ => None,
+        };
+
+        let sampling = SamplingConfig {
+            success_rate: loader.or(SAMPLING_SUCCESS_RATE_KEY, DEFAULT_SAMPLING_SUCCESS_RATE),
+            error_rate: loader.or(SAMPLING_ERROR_RATE_KEY, DEFAULT_SAMPLING_ERROR_RATE),
+            route_overrides: loader.parse_with(SAMPLING_ROUTE_OVERRIDES_KEY, parse_route_overrides).unwrap_or_default(),
+        };
+
+        let otlp = loader.optional(OTEL_EXPORTER_OTLP_ENDPOINT_KEY).map(|endpoint| OtlpConfig {
+            endpoint,
+            service_name: loader.optional(OTEL_SERVICE_NAME_KEY).unwrap_or_else(|| DEFAULT_OTEL_SERVICE_NAME.to_string()),
+            sample_rate: loader.or(OTEL_TRACES_SAMPLER_ARG_KEY, DEFAULT_OTEL_TRACES_SAMPLER_ARG),
+        });
+
+        let environment = loader.optional(APP_ENVIRONMENT_KEY);
+        let sentry = loader.optional(SENTRY_DSN_KEY).map(|dsn| SentryConfig {
+            dsn: Secret::new(dsn),
+            environment: loader
+                .optional(SENTRY_ENVIRONMENT_KEY)
+                .or_else(|| environment.clone())
+                .unwrap_or_else(|| DEFAULT_SENTRY_ENVIRONMENT.to_string()),
+            release: loader.optional(SENTRY_RELEASE_KEY).unwrap_or_else(|| DEFAULT_RELEASE.to_string()),
+        });
+
+        // Anyone holding the admin token could make expired tokens valid again
+        let test_clock = loader.or(TEST_CLOCK_ENABLED_KEY, false);
+        let production = environment
+            .iter()
+            .chain(sentry.as_ref().map(|sentry| &sentry.environment))
+            .any(|environment| environment.eq_ignore_ascii_case(PRODUCTION_ENVIRONMENT));
+        if test_clock && production {
+            loader.invalid(TEST_CLOCK_ENABLED_KEY, "not allowed in the production environment (APP_ENVIRONMENT, or SENTRY_ENVIRONMENT when SENTRY_DSN is set)");
+        }
+
+        let smtp = loader.optional(SMTP_HOST_KEY).map(|host| {
+            let tls = loader.parse_with(SMTP_TLS_KEY, parse_smtp_tls).unwrap_or_default();
+            SmtpConfig {
+                host,
+                port: loader.or(SMTP_PORT_KEY, tls.default_port()),
+                username: loader.optional(SMTP_USERNAME_KEY),
+                password: loader.optional(SMTP_PASSWORD_KEY).map(Secret::new),
+                from: loader.required(SMTP_FROM_KEY),
+                tls,
+                timeout_ms: loader.or(SMTP_TIMEOUT_MS_KEY, DEFAULT_SMTP_TIMEOUT_MS),
+            }
+        });
+        if let Some(smtp) = &smtp {
+            match (&smtp.username, &smtp.password) {
+                (Some(_), None) => loader.invalid(SMTP_USERNAME_KEY, "SMTP_PASSWORD is missing"),
+                (None, Some(_)) => loader.invalid(SMTP_PASSWORD_KEY, "SMTP_USERNAME is missing"),
+                _ => {}
+            }
+        }
+
+        let outbox = if loader.or(OUTBOX_ENABLED_KEY, false) {
+            Some(OutboxConfig {
+                poll_interval_ms: loader.or(OUTBOX_POLL_INTERVAL_MS_KEY, DEFAULT_OUTBOX_POLL_INTERVAL_MS),
+                batch_size: loader.or(OUTBOX_BATCH_SIZE_KEY, DEFAULT_OUTBOX_BATCH_SIZE),
+                format: loader.parse_with(OUTBOX_FORMAT_KEY, parse_outbox_format).unwrap_or_default(),
+            })
+        } else {
+            None
+        };
+
+        let kafka = loader.optional(KAFKA_BROKERS_KEY).map(|brokers| KafkaConfig {
+            brokers,
+            topic: loader.optional(KAFKA_TOPIC_KEY).unwrap_or_else(|| DEFAULT_KAFKA_TOPIC.to_string()),
+            format: loader.parse_with(KAFKA_FORMAT_KEY, parse_event_format).unwrap_or_default(),
+            delivery_timeout_ms: loader.or(KAFKA_DELIVERY_TIMEOUT_MS_KEY, DEFAULT_KAFKA_DELIVERY_TIMEOUT_MS),
+        });
+
+        let mqtt = loader.optional(MQTT_BROKER_KEY).map(|broker| MqttConfig {
+            broker,
+            client_id: loader.optional(MQTT_CLIENT_ID_KEY),
+            username: loader.optional(MQTT_USERNAME_KEY),
+            password: loader.optional(MQTT_PASSWORD_KEY).map(Secret::new),
+            topic: loader.optional(MQTT_TOPIC_KEY).unwrap_or_else(|| DEFAULT_MQTT_TOPIC.to_string()),
+            qos: loader.parse_with(MQTT_QOS_KEY, parse_mqtt_qos).unwrap_or(DEFAULT_MQTT_QOS),
+            retain: loader.or(MQTT_RETAIN_KEY, false),
+            format: loader.parse_with(MQTT_FORMAT_KEY, parse_outbox_format).unwrap_or_default(),
+            delivery_timeout_ms: loader.or(MQTT_DELIVERY_TIMEOUT_MS_KEY, DEFAULT_MQTT_DELIVERY_TIMEOUT_MS),
+        });
+
+        let commands = loader.optional(COMMANDS_KAFKA_BROKERS_KEY).map(|brokers| CommandConsumerConfig {
+            brokers,
+            topic: loader.optional(COMMANDS_TOPIC_KEY).unwrap_or_else(|| DEFAULT_COMMANDS_TOPIC.to_string()),
+            reply_topic: loader.optional(COMMANDS_REPLY_TOPIC_KEY).unwrap_or_else(|| DEFAULT_COMMANDS_REPLY_TOPIC.to_string()),
+            group_id: loader.optional(COMMANDS_GROUP_ID_KEY).unwrap_or_else(|| DEFAULT_COMMANDS_GROUP_ID.to_string()),
+        });
+
+        let stats = loader.or(STATS_ENABLED_KEY, false).then(|| StatsConfig {
+            interval_secs: loader.or(STATS_INTERVAL_SECS_KEY, DEFAULT_STATS_INTERVAL_SECS),
+        });
+
+        let jobs = loader.or(JOBS_ENABLED_KEY, false).then(|| JobsConfig {
+            poll_interval_ms: loader.or(JOBS_POLL_INTERVAL_MS_KEY, DEFAULT_JOBS_POLL_INTERVAL_MS),
+            batch_size: loader.or(JOBS_BATCH_SIZE_KEY, DEFAULT_JOBS_BATCH_SIZE),
+            max_attempts: loader.or(JOBS_MAX_ATTEMPTS_KEY, DEFAULT_JOBS_MAX_ATTEMPTS),
+            lease_secs: loader.or(JOBS_LEASE_SECS_KEY, DEFAULT_JOBS_LEASE_SECS),
+        });
+
+        let jwt_signing_keys = loader.parse(JWT_KEY_ROTATION_INTERVAL_SECS_KEY).map(|rotation_interval_secs| SigningKeysConfig {
+            rotation_interval_secs,
+            publication_delay_secs: loader.or(JWT_KEY_PUBLICATION_DELAY_SECS_KEY, DEFAULT_JWT_KEY_PUBLICATION_DELAY_SECS),
+            refresh_interval_secs: loader.or(JWT_KEY_REFRESH_INTERVAL_SECS_KEY, DEFAULT_JWT_KEY_REFRESH_INTERVAL_SECS),
+        });
+
+        let cors = loader.optional(CORS_ALLOWED_ORIGINS_KEY).map(|origins| CorsConfig {
+            allowed_origins: parse_list(&origins),
+            allowed_methods: parse_list(&loader.optional(CORS_ALLOWED_METHODS_KEY).unwrap_or_else(|| DEFAULT_CORS_ALLOWED_METHODS.to_string())),
+            allowed_headers: parse_list(&loader.optional(CORS_ALLOWED_HEADERS_KEY).unwrap_or_else(|| DEFAULT_CORS_ALLOWED_HEADERS.to_string())),
+            allow_credentials: loader.or(CORS_ALLOW_CREDENTIALS_KEY, false),
+        });
+
+        let anomaly_detection = if loader.or(ANOMALY_DETECTION_ENABLED_KEY, false) {
+            Some(AnomalyDetectionConfig {
+                window_secs: loader.or(ANOMALY_WINDOW_SECS_KEY, DEFAULT_ANOMALY_WINDOW_SECS),
+                history_windows: loader.or(ANOMALY_HISTORY_WINDOWS_KEY, DEFAULT_ANOMALY_HISTORY_WINDOWS),
+                z_threshold: loader.or(ANOMALY_Z_THRESHOLD_KEY, DEFAULT_ANOMALY_Z_THRESHOLD),
+                min_mutations: loader.or(ANOMALY_MIN_MUTATIONS_KEY, DEFAULT_ANOMALY_MIN_MUTATIONS),
+                rate_limit_divisor: loader.parse(ANOMALY_RATE_LIMIT_DIVISOR_KEY),
+                rate_limit_secs: loader.or(ANOMALY_RATE_LIMIT_SECS_KEY, DEFAULT_ANOMALY_RATE_LIMIT_SECS),
+            })
+        } else {
+            None
+        };
+
+        let slo = loader.parse_with(SLO_OBJECTIVES_KEY, parse_slo_objectives).map(|objectives| SloConfig {
+            objectives,
+            window_secs: loader.or(SLO_WINDOW_SECS_KEY, DEFAULT_SLO_WINDOW_SECS),
+            bucket_secs: loader.or(SLO_BUCKET_SECS_KEY, DEFAULT_SLO_BUCKET_SECS),
+            burn_window_secs: loader.or(SLO_BURN_WINDOW_SECS_KEY, DEFAULT_SLO_BURN_WINDOW_SECS),
+            burn_rate_threshold: loader.or(SLO_BURN_RATE_THRESHOLD_KEY, DEFAULT_SLO_BURN_RATE_THRESHOLD),
+            min_requests: loader.or(SLO_MIN_REQUESTS_KEY, DEFAULT_SLO_MIN_REQUESTS),
+        });
+
+        let idempotency = loader.or(IDEMPOTENCY_ENABLED_KEY, false).then(|| IdempotencyConfig {
+            ttl_secs: loader.or(IDEMPOTENCY_TTL_SECS_KEY, DEFAULT_IDEMPOTENCY_TTL_SECS),
+            lease_secs: loader.or(IDEMPOTENCY_LEASE_SECS_KEY, DEFAULT_IDEMPOTENCY_LEASE_SECS),
+        });
+
+        let tenant_settings = loader.or(TENANT_SETTINGS_ENABLED_KEY, false).then(|| TenantSettingsConfig {
+            cache_ttl_secs: loader.or(TENANT_SETTINGS_CACHE_TTL_SECS_KEY, DEFAULT_TENANT_SETTINGS_CACHE_TTL_SECS),
+        });
+
+        let rate_limit = loader.parse(RATE_LIMIT_REQUESTS_KEY).map(|requests| RateLimitConfig {
+            requests,
+            period_secs: loader.or(RATE_LIMIT_PERIOD_SECS_KEY, DEFAULT_RATE_LIMIT_PERIOD_SECS),
+            route_overrides: loader.parse_with(RATE_LIMIT_ROUTE_OVERRIDES_KEY, parse_route_overrides).unwrap_or_default(),
+            class_overrides: loader.parse_with(RATE_LIMIT_CLASS_OVERRIDES_KEY, parse_route_overrides).unwrap_or_default(),
+            trust_forwarded_for: loader.or(RATE_LIMIT_TRUST_FORWARDED_FOR_KEY, false),
+        });
+
+        let management = loader.optional(MANAGEMENT_TOKEN_KEY).map(|token| ManagementConfig {
+            base_path: loader
+                .parse_with(MANAGEMENT_BASE_PATH_KEY, |path| {
+                    if !path.starts_with('/') || path.len() < 2 || path.ends_with('/') {
+                        eyre::bail!("expected a path such as `/actuator`, starting with `/` and without a trailing `/`");
+                    }
+                    Ok(path.to_string())
+                })
+                .unwrap_or_else(|| DEFAULT_MANAGEMENT_BASE_PATH.to_string()),
+            token: Secret::new(token),
+            port: loader.parse(MANAGEMENT_PORT_KEY),
+        });
+        if management.as_ref().is_some_and(|management| management.port == Some(server_port)) {
+            loader.invalid(MANAGEMENT_PORT_KEY, "is the port of SERVER_PORT");
+        }
+
+        let request_tap = loader.or(REQUEST_TAP_ENABLED_KEY, false).then(|| RequestTapConfig {
+            buffer: loader.or(REQUEST_TAP_BUFFER_KEY, DEFAULT_REQUEST_TAP_BUFFER),
+        });
+
+        let traffic_archive = loader.optional(TRAFFIC_ARCHIVE_URL_KEY).map(|url| TrafficArchiveConfig {
+            url,
+            sample_rate: loader.or(TRAFFIC_ARCHIVE_SAMPLE_RATE_KEY, DEFAULT_TRAFFIC_ARCHIVE_SAMPLE_RATE),
+            max_body_bytes: loader.or(TRAFFIC_ARCHIVE_MAX_BODY_BYTES_KEY, DEFAULT_TRAFFIC_ARCHIVE_MAX_BODY_BYTES),
+            batch_size: loader.or(TRAFFIC_ARCHIVE_BATCH_SIZE_KEY, DEFAULT_TRAFFIC_ARCHIVE_BATCH_SIZE),
+            flush_interval_secs: loader.or(TRAFFIC_ARCHIVE_FLUSH_INTERVAL_SECS_KEY, DEFAULT_TRAFFIC_ARCHIVE_FLUSH_INTERVAL_SECS),
+        });
+
+        let purge = loader.optional(PURGE_URL_KEY).map(|url| PurgeConfig {
+            url,
+            method: loader.optional(PURGE_METHOD_KEY).unwrap_or_else(|| DEFAULT_PURGE_METHOD.to_string()),
+            keys_header: loader.optional(PURGE_KEYS_HEADER_KEY).unwrap_or_else(|| DEFAULT_PURGE_KEYS_HEADER.to_string()),
+            token: loader.optional(PURGE_TOKEN_KEY).map(Secret::new),
+        });
+
+        let cloudflare_purge = loader.optional(CLOUDFLARE_ZONE_ID_KEY).map(|zone_id| CloudflarePurgeConfig {
+            api_url: loader.optional(CLOUDFLARE_API_URL_KEY).unwrap_or_else(|| DEFAULT_CLOUDFLARE_API_URL.to_string()),
+            zone_id,
+            api_token: loader.required(CLOUDFLARE_API_TOKEN_KEY),
+        });
+
+        let fastly_purge = loader.optional(FASTLY_SERVICE_ID_KEY).map(|service_id| FastlyPurgeConfig {
+            api_url: loader.optional(FASTLY_API_URL_KEY).unwrap_or_else(|| DEFAULT_FASTLY_API_URL.to_string()),
+            service_id,
+            api_token: loader.required(FASTLY_API_TOKEN_KEY),
+            soft: loader.or(FASTLY_SOFT_PURGE_KEY, DEFAULT_FASTLY_SOFT_PURGE),
+        });
+
+        let jwt = loader.optional(JWT_SECRET_KEY).map(|secret| JwtConfig {
+            secret: Secret::new(secret),
+            expiry_secs: loader.or(JWT_EXPIRY_SECS_KEY, DEFAULT_JWT_EXPIRY_SECS),
+        });
+
+        let device_grant = loader.optional(DEVICE_VERIFICATION_URI_KEY).map(|verification_uri| DeviceGrantConfig {
+            verification_uri,
+            code_lifetime_secs: loader.or(DEVICE_CODE_LIFETIME_SECS_KEY, DEFAULT_DEVICE_CODE_LIFETIME_SECS),
+            poll_interval_secs: loader.or(DEVICE_POLL_INTERVAL_SECS_KEY, DEFAULT_DEVICE_POLL_INTERVAL_SECS),
+        });
+
+        let cache = loader.optional(CACHE_URL_KEY).map(|url| CacheConfig {
+            url: Secret::new(url),
+            ttl_secs: loader.or(CACHE_TTL_SECS_KEY, DEFAULT_CACHE_TTL_SECS),
+        });
+
+        let oidc = loader.optional(OIDC_ISSUER_KEY).map(|issuer| OidcConfig {
+            issuer,
+            audience: loader.optional(OIDC_AUDIENCE_KEY),
+            jwks_url: loader.optional(OIDC_JWKS_URL_KEY),
+            roles_claim: loader.optional(OIDC_ROLES_CLAIM_KEY).unwrap_or_else(|| DEFAULT_OIDC_ROLES_CLAIM.to_string()),
+            refresh_interval_secs: loader.or(OIDC_JWKS_REFRESH_INTERVAL_SECS_KEY, DEFAULT_OIDC_JWKS_REFRESH_INTERVAL_SECS),
+            refetch_cooldown_secs: loader.or(OIDC_JWKS_REFETCH_COOLDOWN_SECS_KEY, DEFAULT_OIDC_JWKS_REFETCH_COOLDOWN_SECS),
+            clock_skew_secs: loader.or(OIDC_CLOCK_SKEW_SECS_KEY, DEFAULT_OIDC_CLOCK_SKEW_SECS),
+        });
+
+        let ldap = loader.optional(LDAP_URL_KEY).map(|url| LdapConfig {
+            url,
+            bind_dn: loader.optional(LDAP_BIND_DN_KEY),
+            bind_password: loader.optional(LDAP_BIND_PASSWORD_KEY).map(Secret::new),
+            user_base_dn: loader.required(LDAP_USER_BASE_DN_KEY),
+            user_filter: loader.optional(LDAP_USER_FILTER_KEY).unwrap_or_else(|| DEFAULT_LDAP_USER_FILTER.to_string()),
+            user_id_attribute: loader.optional(LDAP_USER_ID_ATTRIBUTE_KEY).unwrap_or_else(|| DEFAULT_LDAP_USER_ID_ATTRIBUTE.to_string()),
+            group_attribute: loader.optional(LDAP_GROUP_ATTRIBUTE_KEY).unwrap_or_else(|| DEFAULT_LDAP_GROUP_ATTRIBUTE.to_string()),
+            group_roles: loader.parse_with(LDAP_GROUP_ROLES_KEY, parse_group_roles).unwrap_or_default(),
+            pool_size: loader.or(LDAP_POOL_SIZE_KEY, DEFAULT_LDAP_POOL_SIZE),
+            timeout_secs: loader.or(LDAP_TIMEOUT_SECS_KEY, DEFAULT_LDAP_TIMEOUT_SECS),
+        });
+
+        let saml = loader.optional(SAML_IDP_SSO_URL_KEY).map(|idp_sso_url| SamlConfig {
+            sp_entity_id: loader.required(SAML_SP_ENTITY_ID_KEY),
+            acs_url: loader.required(SAML_ACS_URL_KEY),
+            idp_entity_id: loader.required(SAML_IDP_ENTITY_ID_KEY),
+            idp_sso_url,
+            idp_certificates: parse_certificates(&loader.required::<String>(SAML_IDP_CERTIFICATES_KEY)),
+            email_attribute: loader.optional(SAML_EMAIL_ATTRIBUTE_KEY),
+            roles_attribute: loader.optional(SAML_ROLES_ATTRIBUTE_KEY),
+            clock_skew_secs: loader.or(SAML_CLOCK_SKEW_SECS_KEY, DEFAULT_SAML_CLOCK_SKEW_SECS),
+            login_redirect_url: loader.optional(SAML_LOGIN_REDIRECT_URL_KEY),
+        });
+
+        let webauthn = loader.optional(WEBAUTHN_RP_ID_KEY).map(|rp_id| WebAuthnConfig {
+            rp_origin: loader.required(WEBAUTHN_RP_ORIGIN_KEY),
+            rp_name: loader.optional(WEBAUTHN_RP_NAME_KEY).unwrap_or_else(|| rp_id.clone()),
+            rp_id,
+            ceremony_timeout_secs: loader.or(WEBAUTHN_CEREMONY_TIMEOUT_SECS_KEY, DEFAULT_WEBAUTHN_CEREMONY_TIMEOUT_SECS),
+            password_fallback: loader.parse_with(WEBAUTHN_PASSWORD_FALLBACK_KEY, parse_password_fallback).unwrap_or_default(),
+        });
+
+        let password_policy = PasswordPolicyConfig {
+            min_length: loader.or(PASSWORD_MIN_LENGTH_KEY, MIN_PASSWORD_LENGTH),
+            min_strength: loader.parse(PASSWORD_MIN_STRENGTH_KEY),
+            breach_check: loader.or(PASSWORD_BREACH_CHECK_KEY, false).then(|| BreachCheckConfig {
+                api_url: loader.optional(PASSWORD_BREACH_API_URL_KEY).unwrap_or_else(|| DEFAULT_PASSWORD_BREACH_API_URL.to_string()),
+                timeout_ms: loader.or(PASSWORD_BREACH_TIMEOUT_MS_KEY, DEFAULT_PASSWORD_BREACH_TIMEOUT_MS),
+            }),
+        };
+        if !(MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&password_policy.min_length) {
+            loader.invalid(PASSWORD_MIN_LENGTH_KEY, format!("expected {} to {} characters", MIN_PASSWORD_LENGTH, MAX_PASSWORD_LENGTH));
+        }
+        if password_policy.min_strength.is_some_and(|strength| strength > MAX_PASSWORD_STRENGTH) {
+            loader.invalid(PASSWORD_MIN_STRENGTH_KEY, format!("expected 0 to {}", MAX_PASSWORD_STRENGTH));
+        }
+
+        let compression_encodings = match loader.optional(COMPRESSION_ENCODINGS_KEY).as_deref() {
+            Some("none") => Vec::new(),
+            Some(encodings) => parse_list(encodings),
+            None => parse_list(DEFAULT_COMPRESSION_ENCODINGS),
+        };
+
+        let config = Config {
+            server_port,
+            database_url,
+            database_pool,
+            run_migrations: loader.or(RUN_MIGRATIONS_KEY, false),
+            log_format: loader.parse_with(LOG_FORMAT_KEY, parse_log_format).unwrap_or_default(),
+            otlp,
+            outbox,
+            kafka,
+            mqtt,
+            commands,
+            stats,
+            jobs,
+            shutdown_timeout_secs: loader.or(SHUTDOWN_TIMEOUT_SECS_KEY, DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+            max_body_bytes: loader.or(MAX_BODY_BYTES_KEY, DEFAULT_MAX_BODY_BYTES),
+            request_timeout_secs: Some(loader.or(REQUEST_TIMEOUT_SECS_KEY, DEFAULT_REQUEST_TIMEOUT_SECS)).filter(|secs| *secs > 0),
+            max_concurrent_requests: loader.parse(MAX_CONCURRENT_REQUESTS_KEY),
+            compression_encodings,
+            thrift_port: loader.parse(THRIFT_PORT_KEY),
+            graphql: loader.or(GRAPHQL_ENABLED_KEY, false),
+            database_discovery,
+            kubernetes,
+            sampling,
+            admin_token: loader.optional(ADMIN_TOKEN_KEY).map(Secret::new),
+            management,
+            scim_token: loader.optional(SCIM_TOKEN_KEY).map(Secret::new),
+            introspection_token: loader.optional(INTROSPECTION_TOKEN_KEY).map(Secret::new),
+            pagination_cursor_secret: loader.optional(PAGINATION_CURSOR_SECRET_KEY).map(Secret::new),
+            jwe_keys: loader.parse_with(JWE_KEYS_KEY, parse_jwe_keys).unwrap_or_default(),
+            cors,
+            idempotency,
+            audit_log: loader.or(AUDIT_LOG_ENABLED_KEY, false),
+            tenant_settings,
+            rate_limit,
+            anomaly_detection,
+            slo,
+            request_tap,
+            in_flight_requests: loader.or(IN_FLIGHT_REQUESTS_ENABLED_KEY, false),
+            test_clock,
+            environment,
+            traffic_archive,
+            purge,
+            cloudflare_purge,
+            fastly_purge,
+            cache,
+            jwt,
+            jwt_signing_keys,
+            device_grant,
+            oidc,
+            ldap,
+            saml,
+            webauthn,
+            password_policy,
+            sentry,
+            smtp,
+        };
+        loader.finish(config)
+    }
+}
+
+/// The values of the configuration: environment variables, over the values of the config file.
+///
+/// The config file is `CONFIG_FILE`, or else the first of `config.toml`, `config.yaml` and
+/// `config.yml` found in the working directory. Its keys are the names of the environment
+/// variables in any case, and tables prefix the keys they contain, e.g. `brokers` in a `kafka`
+/// table is `KAFKA_BROKERS`. Arrays are joined with commas, like the lists of the environment.
+#[derive(Clone, Default)]
+pub struct ConfigSource {
+    values: HashMap<String, String>,
+}
+
+impl ConfigSource {
+    /// Reads the environment of the process, and the config file.
+    pub fn from_env() -> eyre::Result<Self> {
+        Self::new(env::vars_os().filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?))))
+    }
+
+    /// Reads the given environment variables, and the config file.
+    pub fn new(vars: impl IntoIterator<Item = (String, String)>) -> eyre::Result<Self> {
+        // Empty variables are unset, rather than overriding the config file
+        let vars: HashMap<String, String> = vars.into_iter().filter(|(_, value)| !value.is_empty()).collect();
+        let path = match vars.get(CONFIG_FILE_KEY) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => DEFAULT_CONFIG_FILES.iter().map(PathBuf::from).find(|path| path.is_file()),
+        };
+
+        let mut values = match path {
+            Some(path) => read_config_file(&path).with_context(|| format!("failed to read config file {}", path.display()))?,
+            None => HashMap::new(),
+        };
+        values.extend(vars);
+        Ok(Self { values })
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str).filter(|value| !value.is_empty())
+    }
+}
+
+/// Loads `DATABASE_URL` alone, for tools that connect to the database without serving HTTP
+/// (e.g. the `migrate` binary).
+pub fn load_database_url(source: &ConfigSource) -> eyre::Result<Secret<String>> {
+    let mut loader = Loader::new(source);
+    let database_url = loader.required(DATABASE_URL_KEY);
+    loader.finish(database_url)
+}
+
+/// Loads `OUTBOX_ENABLED` alone, for tools changing users without serving HTTP (e.g. the
+/// `rustweb-cli` binary), whose events are recorded in the outbox like those of the server.
+pub fn load_outbox_enabled(source: &ConfigSource) -> eyre::Result<bool> {
+    let mut loader = Loader::new(source);
+    let outbox_enabled = loader.or(OUTBOX_ENABLED_KEY, false);
+    loader.finish(outbox_enabled)
+}
+
+/// Loads `LOG_FORMAT` alone, for tools that log without loading the whole configuration.
+pub fn load_log_format(source: &ConfigSource) -> eyre::Result<LogFormat> {
+    let mut loader = Loader::new(source);
+    let log_format = loader.parse_with(LOG_FORMAT_KEY, parse_log_format).unwrap_or_default();
+    loader.finish(log_format)
+}
+
+/// Loads values from a [`ConfigSource`], collecting the missing and invalid ones so that they
+/// are reported together rather than one per restart.
+struct Loader<'a> {
+    source: &'a ConfigSource,
+    errors: Vec<String>,
+}
+
+impl<'a> Loader<'a> {
+    fn new(source: &'a ConfigSource) -> Self {
+        Self { source, errors: Vec::new() }
+    }
+
+    fn optional(&self, key: &str) -> Option<String> {
+        self.source.get(key).map(str::to_string)
+    }
+
+    /// Returns the value of `key`, or a placeholder when missing or invalid, as the loaded
+    /// configuration is only returned without errors.
+    fn required<T>(&mut self, key: &str) -> T
+    where
+        T: FromStr + Default,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        if self.source.get(key).is_none() {
+            self.errors.push(format!("{} is missing", key));
+        }
+        self.parse(key).unwrap_or_default()
+    }
+
+    fn or<T>(&mut self, key: &str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.parse(key).unwrap_or(default)
+    }
+
+    fn parse<T>(&mut self, key: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.parse_with(key, |value| Ok(value.parse()?))
+    }
+
+    /// Parses the value of `key` when set, recording an error when invalid.
+    fn parse_with<T>(&mut self, key: &str, parse: impl FnOnce(&str) -> eyre::Result<T>) -> Option<T> {
+        let value = self.source.get(key)?;
+        match parse(value) {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.invalid(key, error);
+                None
+            }
+        }
+    }
+
+    fn invalid(&mut self, key: &str, reason: impl std::fmt::Display) {
+        self.errors.push(format!("{} is invalid: {:#}", key, reason));
+    }
+
+    /// Returns `value`, or an error listing every missing and invalid value.
+    fn finish<T>(self, value: T) -> eyre::Result<T> {
+        if self.errors.is_empty() {
+            return Ok(value);
+        }
+        let errors: String = self.errors.iter().map(|error| format!("\n  - {}", error)).collect();
+        Err(eyre::eyre!("invalid configuration:{}", errors))
+    }
+}
+
+/// Reads the values of a TOML or YAML config file.
+fn read_config_file(path: &Path) -> eyre::Result<HashMap<String, String>> {
+    let figment = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => Figment::from(Toml::file_exact(path)),
+        Some("yaml" | "yml") => Figment::from(Yaml::file_exact(path)),
+        _ => eyre::bail!("expected a .toml, .yaml or .yml file"),
+    };
+    let table: serde_json::Map<String, serde_json::Value> = figment.extract()?;
+
+    let mut values = HashMap::new();
+    flatten_config_table("", table, &mut values);
+    Ok(values)
+}
+
+/// Flattens the tables of a config file into the names of the environment variables, e.g.
+/// `brokers` in a `kafka` table into `KAFKA_BROKERS`.
+fn flatten_config_table(prefix: &str, table: serde_json::Map<String, serde_json::Value>, values: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let key = format!("{}{}", prefix, key.to_uppercase().replace('-', "_"));
+        match value {
+            serde_json::Value::Object(table) => flatten_config_table(&format!("{}_", key), table, values),
+            serde_json::Value::Array(items) => {
+                values.insert(key, items.iter().map(config_value).collect::<Vec<_>>().join(","));
+            }
+            serde_json::Value::Null => {}
+            value => {
+                values.insert(key, config_value(&value));
+            }
+        }
+    }
+}
+
+fn config_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Parses `prefix=value` entries separated by commas, e.g. sampling rates or rate limits.
+fn parse_route_overrides<T>(value: &str) -> eyre::Result<Vec<(String, T)>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (prefix, value) = entry
+                .split_once('=')
+                .ok_or_else(|| eyre::eyre!("expected prefix=value, got {}", entry))?;
+            let value = value.trim().parse().with_context(|| format!("invalid value in {}", entry))?;
+            Ok((prefix.trim().to_string(), value))
+        })
+        .collect()
+}
+
+fn parse_password_fallback(value: &str) -> eyre::Result<PasswordFallback> {
+    match value.trim().to_lowercase().as_str() {
+        "allowed" => Ok(PasswordFallback::Allowed),
+        "disabled" => Ok(PasswordFallback::Disabled),
+        _ => Err(eyre::eyre!("expected allowed or disabled, got {}", value)),
+    }
+}
+
+fn parse_event_format(value: &str) -> eyre::Result<EventFormat> {
+    match value.trim().to_lowercase().as_str() {
+        "json" => Ok(EventFormat::Json),
+        "avro" => Ok(EventFormat::Avro),
+        "protobuf" => Ok(EventFormat::Protobuf),
+        _ => Err(eyre::eyre!("expected json, avro or protobuf, got {}", value)),
+    }
+}
+
+fn parse_outbox_format(value: &str) -> eyre::Result<EventFormat> {
+    match value.trim().to_lowercase().as_str() {
+        "json" => Ok(EventFormat::Json),
+        "protobuf" => Ok(EventFormat::Protobuf),
+        _ => Err(eyre::eyre!("expected json or protobuf, got {}", value)),
+    }
+}
+
+fn parse_smtp_tls(value: &str) -> eyre::Result<SmtpTls> {
+    match value.trim().to_lowercase().as_str() {
+        "starttls" => Ok(SmtpTls::StartTls),
+        "tls" => Ok(SmtpTls::Tls),
+        "none" => Ok(SmtpTls::None),
+        _ => Err(eyre::eyre!("expected starttls, tls or none, got {}", value)),
+    }
+}
+
+fn parse_mqtt_qos(value: &str) -> eyre::Result<u8> {
+    match value.trim() {
+        "0" => Ok(0),
+        "1" => Ok(1),
+        "2" => Ok(2),
+        _ => Err(eyre::eyre!("expected 0, 1 or 2, got {}", value)),
+    }
+}
+
+fn parse_log_format(value: &str) -> eyre::Result<LogFormat> {
+    match value.trim().to_lowercase().as_str() {
+        "pretty" => Ok(LogFormat::Pretty),
+        "json" => Ok(LogFormat::Json),
+        _ => Err(eyre::eyre!("expected pretty or json, got {}", value)),
+    }
+}
+
+/// Parses `group DN=role` entries separated by `;`, as DNs contain commas. The role follows the
+/// last `=`, as DNs contain `=` too.
+fn parse_group_roles(value: &str) -> eyre::Result<Vec<(String, String)>> {
+    value
+        .split(';')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (group, role) = entry
+                .rsplit_once('=')
+                .filter(|(group, role)| group.contains('=') && !role.trim().is_empty())
+                .ok_or_else(|| eyre::eyre!("expected group DN=role entries, got {}", entry))?;
+            Ok((group.trim().to_string(), role.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Parses `name:method:latency_ms:target_percent` objectives separated by commas.
+fn parse_slo_objectives(value: &str) -> eyre::Result<Vec<SloObjectiveConfig>> {
+    let objectives = value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let [name, method, latency_ms, target_percent] = entry.split(':').map(str::trim).collect::<Vec<_>>()[..] else {
+                eyre::bail!("expected name:method:latency_ms:target_percent, got {}", entry);
+            };
+            Ok(SloObjectiveConfig {
+                name: name.to_string(),
+                method: (method != "*").then(|| method.to_uppercase()),
+                latency_ms: latency_ms.parse().with_context(|| format!("invalid latency in {}", entry))?,
+                target_percent: target_percent.parse().with_context(|| format!("invalid target in {}", entry))?,
+            })
+        })
+        .collect::<eyre::Result<Vec<_>>>()?;
+    if objectives.is_empty() {
+        eyre::bail!("expected at least one objective");
+    }
+    Ok(objectives)
+}
+
+/// Splits comma-separated values, dropping empty ones.
+fn parse_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect()
+}
+
+/// Splits base64 DER certificates separated by commas, also accepting PEM certificates.
+fn parse_certificates(value: &str) -> Vec<String> {
+    value
+        .replace("-----BEGIN CERTIFICATE-----", ",")
+        .replace("-----END CERTIFICATE-----", ",")
+        .split(',')
+        .map(|certificate| certificate.split_whitespace().collect::<String>())
+        .filter(|certificate| !certificate.is_empty())
+        .collect()
+}
+
+fn parse_jwe_keys(value: &str) -> eyre::Result<Vec<(String, Secret<String>)>> {
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (kid, key) = entry
+                .split_once('=')
+                // The entry is not echoed back, as it may contain a key.
+                .ok_or_else(|| eyre::eyre!("expected kid=key entries"))?;
+            Ok((kid.trim().to_string(), Secret::new(key.trim().to_string())))
+        })
+        .collect()
+}
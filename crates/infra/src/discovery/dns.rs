@@ -0,0 +1,82 @@
+use async_trait::async_trait;
+use eyre::Context;
+use hickory_resolver::TokioAsyncResolver;
+use rand::Rng;
+
+use crate::discovery::{Endpoint, ServiceDiscoveryPort};
+
+/// DNS SRV implementation of the service discovery port.
+///
+/// Records are ordered by priority (lowest first), and records of the same priority in a
+/// random order weighted by their weight, following the selection rules of RFC 2782.
+pub struct DnsSrvDiscovery {
+    resolver: TokioAsyncResolver,
+}
+
+impl DnsSrvDiscovery {
+    /// Creates a new `DnsSrvDiscovery` using the system resolver configuration.
+    pub fn from_system_conf() -> eyre::Result<Self> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf()
+            .context("failed to load system DNS resolver configuration")?;
+        Ok(Self { resolver })
+    }
+}
+
+#[async_trait]
+impl ServiceDiscoveryPort for DnsSrvDiscovery {
+    async fn resolve(&self, service: &str) -> eyre::Result<Vec<Endpoint>> {
+        let lookup = self
+            .resolver
+            .srv_lookup(service)
+            .await
+            .with_context(|| format!("failed to resolve SRV record {}", service))?;
+
+        let records = lookup.iter().map(|srv| SrvRecord {
+            priority: srv.priority(),
+            weight: srv.weight(),
+            target: srv.target().to_utf8(),
+            port: srv.port(),
+        });
+        Ok(preferred_endpoints(records, &mut rand::thread_rng()))
+    }
+}
+
+/// A record of an SRV lookup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrvRecord {
+    pub priority: u16,
+    pub weight: u16,
+    /// Fully qualified name of the target, with or without its trailing dot.
+    pub target: String,
+    pub port: u16,
+}
+
+/// Returns the endpoints of `records`, ordered by priority (lowest first), and records of the
+/// same priority in the weighted random order of RFC 2782: each next record is drawn with a
+/// probability proportional to its weight, records of weight 0 having a small chance to be
+/// drawn first.
+pub fn preferred_endpoints(records: impl IntoIterator<Item = SrvRecord>, rng: &mut impl Rng) -> Vec<Endpoint> {
+    let mut records: Vec<_> = records.into_iter().collect();
+    // Records of weight 0 first within a priority, so they are drawn only by a draw of 0
+    records.sort_by_key(|srv| (srv.priority, srv.weight != 0));
+
+    let mut endpoints = Vec::with_capacity(records.len());
+    for group in records.chunk_by(|a, b| a.priority == b.priority) {
+        let mut group = group.to_vec();
+        while !group.is_empty() {
+            let total: u32 = group.iter().map(|srv| u32::from(srv.weight)).sum();
+            let draw = rng.gen_range(0..=total);
+            let mut running = 0;
+            let drawn = group
+                .iter()
+                .position(|srv| {
+                    running += u32::from(srv.weight);
+                    running >= draw
+                })
+                .unwrap_or(0);
+            let srv = group.remove(drawn);
+            endpoints.push(Endpoint { host: srv.target.trim_end_matches('.').to_string(), port: srv.port });
+        }
+    }
+    endpoints
+}
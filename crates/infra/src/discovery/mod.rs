@@ -0,0 +1,89 @@
+#[cfg(feature = "discovery")]
+pub mod dns;
+
+use std::{fmt, time::Duration};
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+/// A network endpoint of a dependency (database, cache, message broker, etc.).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+/// Port for resolving the endpoints of a dependency at runtime.
+///
+/// Adapters (e.g. DNS SRV, Consul, static lists) return the currently available endpoints
+/// ordered by preference, so callers can follow failovers without a restart.
+#[async_trait]
+pub trait ServiceDiscoveryPort {
+    /// Resolves the endpoints of the given service, most preferred first.
+    async fn resolve(&self, service: &str) -> eyre::Result<Vec<Endpoint>>;
+}
+
+/// Settings controlling how often discovered endpoints are refreshed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveryConfig {
+    /// The discovery record to resolve (e.g. `_postgres._tcp.db.example.internal`).
+    pub record: String,
+    /// Interval between periodic re-resolutions, in seconds.
+    pub refresh_interval_secs: u64,
+    /// Number of consecutive failed health probes that trigger an immediate re-resolution.
+    pub failure_threshold: u32,
+}
+
+/// Decides when discovered endpoints are re-resolved, from the results of the health probes of
+/// the dependency: every `refresh_interval_secs`, and immediately after `failure_threshold`
+/// consecutive failed probes.
+#[derive(Debug, Clone)]
+pub struct RefreshSchedule {
+    refresh_interval: Duration,
+    failure_threshold: u32,
+    last_resolved: Instant,
+    consecutive_failures: u32,
+}
+
+impl RefreshSchedule {
+    /// Creates a schedule for endpoints resolved at `resolved_at`.
+    pub fn new(config: &DiscoveryConfig, resolved_at: Instant) -> Self {
+        Self {
+            refresh_interval: Duration::from_secs(config.refresh_interval_secs),
+            failure_threshold: config.failure_threshold,
+            last_resolved: resolved_at,
+            consecutive_failures: 0,
+        }
+    }
+
+    /// Records the result of a health probe made at `now`, returning whether the endpoints are
+    /// due for re-resolution.
+    pub fn probed(&mut self, healthy: bool, now: Instant) -> bool {
+        if healthy {
+            self.consecutive_failures = 0;
+        } else {
+            self.consecutive_failures += 1;
+        }
+        self.consecutive_failures >= self.failure_threshold || now.duration_since(self.last_resolved) >= self.refresh_interval
+    }
+
+    /// Records an attempt to re-resolve the endpoints made at `now`. A successful one forgets
+    /// the failed probes, a failed one is retried after the next failed probe.
+    pub fn resolved(&mut self, succeeded: bool, now: Instant) {
+        self.last_resolved = now;
+        if succeeded {
+            self.consecutive_failures = 0;
+        }
+    }
+
+    /// Number of consecutive failed health probes.
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+}
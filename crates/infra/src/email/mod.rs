@@ -0,0 +1,67 @@
+#[cfg(feature = "smtp")]
+pub mod smtp;
+
+use async_trait::async_trait;
+
+use application::ports::{
+    capability::Capability,
+    email::{EmailMessage, EmailSenderPort},
+};
+use domain::secret::Secret;
+
+/// How the connection to the SMTP server is secured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SmtpTls {
+    /// Plain connection upgraded with `STARTTLS`, failing if the server does not support it.
+    #[default]
+    StartTls,
+    /// TLS from the start of the connection (SMTPS).
+    Tls,
+    /// Unencrypted connection, for local mail catchers only.
+    None,
+}
+
+impl SmtpTls {
+    /// Returns the port SMTP servers listen on for this kind of connection.
+    pub fn default_port(self) -> u16 {
+        match self {
+            SmtpTls::StartTls | SmtpTls::None => 587,
+            SmtpTls::Tls => 465,
+        }
+    }
+}
+
+/// Settings of the delivery of e-mails through an SMTP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmtpConfig {
+    /// Host name of the SMTP server.
+    pub host: String,
+    /// Port of the SMTP server, [`SmtpTls::default_port`] by default.
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<Secret<String>>,
+    /// Address the e-mails are sent from, e.g. `Example <no-reply@example.com>`.
+    pub from: String,
+    pub tls: SmtpTls,
+    /// Time within which an e-mail must be accepted by the server, in milliseconds.
+    pub timeout_ms: u64,
+}
+
+/// E-mail sender accepting and discarding every e-mail, for tests and environments where the
+/// flows sending e-mails must run without delivering them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopEmailSender;
+
+impl Capability for NoopEmailSender {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+}
+
+#[async_trait]
+impl EmailSenderPort for NoopEmailSender {
+    async fn send(&self, message: EmailMessage) -> eyre::Result<()> {
+        tracing::debug!(email.subject = %message.subject, "discarding e-mail");
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eyre::Context;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use application::ports::{
+    capability::{Capability, DependencyStatus},
+    email::{EmailMessage, EmailSenderPort},
+};
+
+use crate::email::{SmtpConfig, SmtpTls};
+
+/// E-mail sender delivering e-mails through an SMTP server, over pooled connections.
+#[derive(Clone)]
+pub struct SmtpEmailSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpEmailSender {
+    /// Creates the transport to the server of `config`. Connections are opened on first use.
+    pub fn new(config: &SmtpConfig) -> eyre::Result<Self> {
+        let from = config.from.parse::<Mailbox>().with_context(|| format!("invalid sender address '{}'", config.from))?;
+        let builder = match config.tls {
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host).context("failed to create SMTP transport")?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host).context("failed to create SMTP transport")?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+        };
+        let builder = builder.port(config.port).timeout(Some(Duration::from_millis(config.timeout_ms)));
+        let builder = match (&config.username, &config.password) {
+            (Some(username), Some(password)) => builder.credentials(Credentials::new(username.clone(), password.expose_secret().clone())),
+            _ => builder,
+        };
+
+        Ok(Self { transport: builder.build(), from })
+    }
+}
+
+#[async_trait]
+impl Capability for SmtpEmailSender {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    async fn check(&self) -> DependencyStatus {
+        match self.transport.test_connection().await {
+            Ok(true) => DependencyStatus::Up,
+            _ => DependencyStatus::Down,
+        }
+    }
+}
+
+#[async_trait]
+impl EmailSenderPort for SmtpEmailSender {
+    async fn send(&self, message: EmailMessage) -> eyre::Result<()> {
+        let to = message.to.parse::<Mailbox>().with_context(|| format!("invalid recipient address '{}'", message.to))?;
+        let email = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(message.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body)
+            .context("failed to build e-mail")?;
+
+        self.transport.send(email).await.context("failed to send e-mail over SMTP")?;
+        Ok(())
+    }
+}
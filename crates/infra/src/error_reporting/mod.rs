@@ -0,0 +1,108 @@
+#[cfg(feature = "sentry")]
+pub mod sentry;
+
+use std::sync::Arc;
+
+use application::ports::error_reporter::{ErrorLevel, ErrorReport, ErrorReporterPort, REQUEST_CONTEXT};
+use domain::secret::Secret;
+
+/// Release identifier attached to error reports when `SENTRY_RELEASE` is not set.
+pub const DEFAULT_RELEASE: &str = concat!(env!("CARGO_PKG_NAME"), "@", env!("CARGO_PKG_VERSION"));
+
+/// Settings of the Sentry error reporter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentryConfig {
+    /// The project DSN, e.g. `https://<public key>@o0.ingest.sentry.io/<project id>`.
+    pub dsn: Secret<String>,
+    /// Environment the server runs in (e.g. `production`, `staging`).
+    pub environment: String,
+    /// Release identifier the reports are tagged with.
+    pub release: String,
+}
+
+/// Placeholder replacing e-mail addresses in error reports.
+const EMAIL_PLACEHOLDER: &str = "[email]";
+
+/// Placeholder replacing bearer tokens and credential headers in error reports.
+const TOKEN_PLACEHOLDER: &str = "[redacted]";
+
+/// Headers carrying credentials, whose values are redacted up to the end of their line.
+const CREDENTIAL_HEADERS: [&str; 4] = ["authorization:", "proxy-authorization:", "cookie:", "set-cookie:"];
+
+/// Installs a panic hook forwarding panics to `reporter`, before running the previously
+/// installed hook (which prints the panic message by default).
+///
+/// Panics raised while handling a request are reported with that request's context.
+pub fn install_panic_hook(reporter: Arc<dyn ErrorReporterPort + Send + Sync>) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload_as_str().unwrap_or("Box<dyn Any>");
+        let message = match info.location() {
+            Some(location) => format!("panicked at {}: {}", location, payload),
+            None => format!("panicked: {}", payload),
+        };
+
+        reporter.report(ErrorReport {
+            level: ErrorLevel::Fatal,
+            message,
+            request: REQUEST_CONTEXT.try_with(Clone::clone).ok(),
+        });
+        previous(info);
+    }));
+}
+
+/// Removes personal data and credentials from text sent to an error tracking service.
+///
+/// E-mail addresses, bearer tokens and the values of the `Authorization` and `Cookie` headers
+/// are replaced with placeholders, everything else is kept as is.
+pub fn scrub_pii(text: &str) -> String {
+    let text: String = text.split_inclusive('\n').map(scrub_credential_header).collect();
+    let mut scrubbed = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+
+    while let Some(start) = rest.find(|c: char| is_email_char(c)) {
+        scrubbed.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = rest.find(|c: char| !is_email_char(c)).unwrap_or(rest.len());
+        let word = &rest[..end];
+        if is_email(word) {
+            scrubbed.push_str(EMAIL_PLACEHOLDER);
+        } else {
+            scrubbed.push_str(word);
+        }
+        rest = &rest[end..];
+
+        if word == "Bearer" && rest.starts_with(' ') {
+            let token_end = rest[1..].find(char::is_whitespace).map_or(rest.len(), |i| i + 1);
+            scrubbed.push(' ');
+            scrubbed.push_str(TOKEN_PLACEHOLDER);
+            rest = &rest[token_end..];
+        }
+    }
+
+    scrubbed.push_str(rest);
+    scrubbed
+}
+
+/// Redacts the value of the credential header of `line`, if any, keeping its line ending.
+fn scrub_credential_header(line: &str) -> String {
+    // ASCII lowercasing keeps the byte offsets
+    let lower = line.to_ascii_lowercase();
+    let Some(value_start) = CREDENTIAL_HEADERS.iter().filter_map(|name| lower.find(name).map(|i| i + name.len())).min() else {
+        return line.to_string();
+    };
+    let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+    format!("{} {}{}", &line[..value_start], TOKEN_PLACEHOLDER, ending)
+}
+
+fn is_email_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '.' | '_' | '%' | '+' | '-' | '@')
+}
+
+fn is_email(word: &str) -> bool {
+    match word.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.'),
+        None => false,
+    }
+}
@@ -0,0 +1,142 @@
+use eyre::{Context, OptionExt};
+use reqwest::{Client, Url};
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+
+use application::ports::{capability::Capability, error_reporter::{ErrorLevel, ErrorReport, ErrorReporterPort}};
+
+use crate::error_reporting::{scrub_pii, SentryConfig};
+
+/// Maximum number of reports waiting to be sent. Further reports are dropped, so a burst
+/// of errors cannot exhaust memory or stall request handling.
+const QUEUE_CAPACITY: usize = 256;
+
+const SENTRY_CLIENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Error reporter sending events to Sentry through its envelope endpoint.
+///
+/// Reports are queued and sent by a background task. Messages and request paths are
+/// scrubbed of personal data before leaving the process.
+#[derive(Debug, Clone)]
+pub struct SentryErrorReporter {
+    sender: mpsc::Sender<ErrorReport>,
+}
+
+impl SentryErrorReporter {
+    /// Parses the DSN from `config` and spawns the task sending reports to Sentry.
+    pub fn spawn(config: SentryConfig) -> eyre::Result<Self> {
+        let dsn = Dsn::parse(config.dsn.expose_secret())?;
+        let client = Client::builder().build().context("failed to create Sentry HTTP client")?;
+        let (sender, mut receiver) = mpsc::channel(QUEUE_CAPACITY);
+
+        tokio::spawn(async move {
+            while let Some(report) = receiver.recv().await {
+                if let Err(e) = send(&client, &dsn, &config, &report).await {
+                    tracing::warn!("failed to send error report to Sentry: {:#}", e);
+                }
+            }
+        });
+
+        Ok(Self { sender })
+    }
+}
+
+impl Capability for SentryErrorReporter {
+    fn name(&self) -> &'static str {
+        "error_reporting"
+    }
+}
+
+impl ErrorReporterPort for SentryErrorReporter {
+    fn report(&self, report: ErrorReport) {
+        if self.sender.try_send(report).is_err() {
+            tracing::warn!("error report queue is full, dropping report");
+        }
+    }
+}
+
+/// The parts of a Sentry DSN needed to send events.
+#[derive(Debug, Clone)]
+struct Dsn {
+    raw: String,
+    public_key: String,
+    envelope_url: Url,
+}
+
+impl Dsn {
+    fn parse(dsn: &str) -> eyre::Result<Self> {
+        let url = Url::parse(dsn).context("failed to parse Sentry DSN")?;
+        let public_key = url.username();
+        if public_key.is_empty() {
+            eyre::bail!("Sentry DSN is missing the public key");
+        }
+
+        let path = url.path().trim_end_matches('/');
+        let (prefix, project_id) = path.rsplit_once('/').ok_or_eyre("Sentry DSN is missing the project id")?;
+        if project_id.is_empty() {
+            eyre::bail!("Sentry DSN is missing the project id");
+        }
+
+        let mut envelope_url = url.clone();
+        envelope_url.set_username("").ok();
+        envelope_url.set_password(None).ok();
+        envelope_url.set_path(&format!("{}/api/{}/envelope/", prefix, project_id));
+
+        Ok(Self {
+            raw: dsn.to_string(),
+            public_key: public_key.to_string(),
+            envelope_url,
+        })
+    }
+}
+
+/// Returns the Sentry event of `report`, tagged with the release and environment of `config`.
+///
+/// Every text of the report is scrubbed with [`scrub_pii`], and only the method and path of
+/// its request are kept, so no personal data or credentials leave the process.
+pub fn before_send(report: &ErrorReport, config: &SentryConfig) -> Value {
+    let mut event = json!({
+        "event_id": uuid::Uuid::new_v4().simple().to_string(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "platform": "native",
+        "level": match report.level {
+            ErrorLevel::Error => "error",
+            ErrorLevel::Fatal => "fatal",
+        },
+        "logger": env!("CARGO_PKG_NAME"),
+        "release": config.release,
+        "environment": config.environment,
+        "message": { "formatted": scrub_pii(&report.message) },
+    });
+    if let Some(request) = &report.request {
+        event["request"] = json!({
+            "method": request.method,
+            "url": scrub_pii(&request.path),
+        });
+    }
+    event
+}
+
+async fn send(client: &Client, dsn: &Dsn, config: &SentryConfig, report: &ErrorReport) -> eyre::Result<()> {
+    let event = before_send(report, config);
+    let (event_id, timestamp) = (event["event_id"].clone(), event["timestamp"].clone());
+
+    let event = serde_json::to_string(&event)?;
+    let header = json!({ "event_id": event_id, "dsn": dsn.raw, "sent_at": timestamp });
+    let item_header = json!({ "type": "event", "length": event.len() });
+    let envelope = format!("{}\n{}\n{}\n", header, item_header, event);
+
+    client
+        .post(dsn.envelope_url.clone())
+        .header("Content-Type", "application/x-sentry-envelope")
+        .header(
+            "X-Sentry-Auth",
+            format!("Sentry sentry_version=7, sentry_client={}, sentry_key={}", SENTRY_CLIENT, dsn.public_key),
+        )
+        .body(envelope)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -0,0 +1,118 @@
+//! Execution of the background jobs.
+//!
+//! Jobs are enqueued by the services through the [`JobQueuePort`] adapters of the storage,
+//! then run by a [`JobWorker`] running in the background on every replica.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::{sync::watch, task::JoinHandle, time};
+
+use application::jobs::{Job, JobHandlers, JobQueuePort};
+
+/// Longest delay before a failed job is run again.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// Settings of the job worker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobsConfig {
+    /// Interval between polls of the queue while it is drained, in milliseconds.
+    pub poll_interval_ms: u64,
+    /// Maximum number of jobs claimed per poll.
+    pub batch_size: usize,
+    /// Number of runs after which a failing job is given up on.
+    pub max_attempts: u32,
+    /// Time the jobs of a batch are leased to the worker for, in seconds, after which those
+    /// neither completed nor failed are claimed again.
+    pub lease_secs: u64,
+}
+
+/// Background task running the jobs of a queue with their handlers.
+///
+/// The queue is polled every `poll_interval_ms`, and again immediately while full batches
+/// are claimed, so a backlog is drained without waiting. Jobs of a batch run one after the
+/// other. A failing job is run again after a delay doubling with every attempt, up to an
+/// hour, until it has run `max_attempts` times; jobs without a handler fail for good.
+pub struct JobWorker {
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl JobWorker {
+    /// Starts running the jobs of `queue` with `handlers`.
+    pub fn spawn(queue: Arc<dyn JobQueuePort + Send + Sync>, handlers: JobHandlers, config: JobsConfig) -> Self {
+        let (shutdown, mut stopped) = watch::channel(false);
+        let task = tokio::spawn(async move {
+            let poll_interval = Duration::from_millis(config.poll_interval_ms);
+            let lease = Duration::from_secs(config.lease_secs);
+            while !*stopped.borrow() {
+                let jobs = match queue.claim(config.batch_size, lease).await {
+                    Ok(jobs) => jobs,
+                    Err(e) => {
+                        tracing::warn!("failed to claim jobs: {:#}", e);
+                        Vec::new()
+                    }
+                };
+                let claimed = jobs.len();
+                for job in jobs {
+                    run_job(queue.as_ref(), &handlers, config.max_attempts, job).await;
+                }
+                if claimed > 0 && claimed == config.batch_size {
+                    continue;
+                }
+                tokio::select! {
+                    // The worker was dropped without being shut down
+                    changed = stopped.changed() => if changed.is_err() { break },
+                    _ = time::sleep(poll_interval) => {}
+                }
+            }
+        });
+
+        Self { shutdown, task }
+    }
+
+    /// Stops the worker, letting the batch being run complete.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            tracing::error!("job worker task failed: {}", e);
+        }
+    }
+}
+
+/// Runs `job` with its handler, then completes it or records its failure in `queue`.
+#[tracing::instrument(name = "jobs.run", skip_all, fields(job.id = job.id, job.kind = %job.kind, job.attempts = job.attempts))]
+async fn run_job(queue: &(dyn JobQueuePort + Send + Sync), handlers: &JobHandlers, max_attempts: u32, job: Job) {
+    let Some(handler) = handlers.get(&job.kind) else {
+        let error = format!("no handler for jobs of kind {}", job.kind);
+        tracing::error!("{}", error);
+        if let Err(e) = queue.fail(job.id, &error, None).await {
+            tracing::warn!("failed to record job failure: {:#}", e);
+        }
+        return;
+    };
+
+    let recorded = match handler.run(&job.payload).await {
+        Ok(()) => queue.complete(job.id).await,
+        Err(e) if job.attempts >= max_attempts => {
+            tracing::error!("job failed for good: {:#}", e);
+            queue.fail(job.id, &format!("{:#}", e), None).await
+        }
+        Err(e) => {
+            let retry_in = retry_delay(job.attempts);
+            tracing::warn!(retry_in_secs = retry_in.as_secs(), "job failed: {:#}", e);
+            queue.fail(job.id, &format!("{:#}", e), Some(retry_in)).await
+        }
+    };
+    // The job runs again once its lease expires
+    if let Err(e) = recorded {
+        tracing::warn!("failed to record job outcome: {:#}", e);
+    }
+}
+
+/// Returns the delay before a job failed after `attempts` runs is run again: a second,
+/// doubling with every attempt, up to an hour.
+fn retry_delay(attempts: u32) -> Duration {
+    let delay = Duration::from_secs(1).saturating_mul(2u32.saturating_pow(attempts.saturating_sub(1)));
+    delay.min(MAX_RETRY_DELAY)
+}
@@ -0,0 +1,83 @@
+use std::env;
+
+use eyre::Context;
+use reqwest::{Certificate, Method, StatusCode};
+use serde::{de::DeserializeOwned, Serialize};
+
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+const SERVICE_HOST_KEY: &str = "KUBERNETES_SERVICE_HOST";
+
+const SERVICE_PORT_KEY: &str = "KUBERNETES_SERVICE_PORT";
+
+/// Outcome of a Kubernetes API call that can legitimately be rejected.
+#[derive(Debug)]
+pub enum ApiResponse<T> {
+    Ok(T),
+    NotFound,
+    Conflict,
+}
+
+/// Minimal in-cluster Kubernetes API client.
+///
+/// Authenticates with the pod's service account. The token is re-read on every request
+/// because projected service account tokens are rotated by the kubelet.
+#[derive(Clone)]
+pub struct KubeClient {
+    http: reqwest::Client,
+    base_url: String,
+}
+
+impl KubeClient {
+    /// Creates a client using the in-cluster service account and API server address.
+    pub fn in_cluster() -> eyre::Result<Self> {
+        let host = env::var(SERVICE_HOST_KEY).with_context(|| format!("failed to load environment variable {}", SERVICE_HOST_KEY))?;
+        let port = env::var(SERVICE_PORT_KEY).with_context(|| format!("failed to load environment variable {}", SERVICE_PORT_KEY))?;
+
+        let ca = std::fs::read(format!("{}/ca.crt", SERVICE_ACCOUNT_DIR)).context("failed to read service account CA certificate")?;
+        let http = reqwest::Client::builder()
+            .add_root_certificate(Certificate::from_pem(&ca).context("invalid service account CA certificate")?)
+            .build()
+            .context("failed to build Kubernetes API client")?;
+
+        Ok(Self {
+            http,
+            base_url: format!("https://{}:{}", host, port),
+        })
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str) -> eyre::Result<ApiResponse<T>> {
+        self.send::<(), T>(Method::GET, path, None).await
+    }
+
+    pub async fn create<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> eyre::Result<ApiResponse<T>> {
+        self.send(Method::POST, path, Some(body)).await
+    }
+
+    pub async fn replace<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> eyre::Result<ApiResponse<T>> {
+        self.send(Method::PUT, path, Some(body)).await
+    }
+
+    async fn send<B: Serialize, T: DeserializeOwned>(&self, method: Method, path: &str, body: Option<&B>) -> eyre::Result<ApiResponse<T>> {
+        let token = std::fs::read_to_string(format!("{}/token", SERVICE_ACCOUNT_DIR)).context("failed to read service account token")?;
+
+        let mut request = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path))
+            .bearer_auth(token.trim());
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = request.send().await.with_context(|| format!("request to {} failed", path))?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(ApiResponse::NotFound),
+            StatusCode::CONFLICT => Ok(ApiResponse::Conflict),
+            status if status.is_success() => Ok(ApiResponse::Ok(response.json().await.context("invalid Kubernetes API response")?)),
+            status => {
+                let message = response.text().await.unwrap_or_default();
+                Err(eyre::eyre!("Kubernetes API returned {} for {}: {}", status, path, message))
+            }
+        }
+    }
+}
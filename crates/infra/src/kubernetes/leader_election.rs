@@ -0,0 +1,256 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::watch, task::JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use crate::kubernetes::{LeaderElectionConfig, PodMetadata, client::{ApiResponse, KubeClient}};
+
+/// Lease-based leader election among the replicas of a deployment.
+///
+/// Each replica tries to acquire or renew a `coordination.k8s.io/v1` Lease every third of
+/// the lease duration. Updates use the lease `resourceVersion`, so concurrent writers are
+/// rejected by the API server instead of overwriting each other. On shutdown the lease is
+/// released so another replica can take over without waiting for it to expire.
+///
+/// An attempt not completed within the retry period counts as failed, so a replica whose
+/// renewals fail or hang gives up leadership before its lease expires and another replica
+/// can take over.
+pub struct LeaderElection {
+    is_leader: watch::Receiver<bool>,
+    cancellation: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl LeaderElection {
+    /// Starts participating in leader election using the pod name as identity.
+    pub fn spawn<C: LeaseClient>(client: C, pod: &PodMetadata, config: LeaderElectionConfig) -> Self {
+        let (sender, is_leader) = watch::channel(false);
+        let cancellation = CancellationToken::new();
+
+        let elector = Elector {
+            client,
+            path: format!("/apis/coordination.k8s.io/v1/namespaces/{}/leases/{}", pod.namespace, config.lease_name),
+            collection_path: format!("/apis/coordination.k8s.io/v1/namespaces/{}/leases", pod.namespace),
+            identity: pod.name.clone(),
+            config,
+        };
+        let task = tokio::spawn(elector.run(sender, cancellation.clone()));
+
+        Self { is_leader, cancellation, task }
+    }
+
+    /// Returns `true` if this replica currently holds the lease.
+    pub fn is_leader(&self) -> bool {
+        *self.is_leader.borrow()
+    }
+
+    /// Returns a receiver notified whenever leadership is gained or lost.
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.is_leader.clone()
+    }
+
+    /// Stops participating in leader election, releasing the lease if held.
+    pub async fn shutdown(self) {
+        self.cancellation.cancel();
+        if let Err(e) = self.task.await {
+            tracing::error!("leader election task failed: {}", e);
+        }
+    }
+}
+
+/// Access to the Lease objects of the API server.
+#[async_trait]
+pub trait LeaseClient: Send + Sync + 'static {
+    /// Reads the lease at `path`.
+    async fn get_lease(&self, path: &str) -> eyre::Result<ApiResponse<Lease>>;
+    /// Creates `lease` in the collection at `collection_path`.
+    async fn create_lease(&self, collection_path: &str, lease: &Lease) -> eyre::Result<ApiResponse<Lease>>;
+    /// Replaces the lease at `path`, rejected with a conflict unless `lease` carries its
+    /// current `resourceVersion`.
+    async fn replace_lease(&self, path: &str, lease: &Lease) -> eyre::Result<ApiResponse<Lease>>;
+}
+
+#[async_trait]
+impl LeaseClient for KubeClient {
+    async fn get_lease(&self, path: &str) -> eyre::Result<ApiResponse<Lease>> {
+        self.get(path).await
+    }
+
+    async fn create_lease(&self, collection_path: &str, lease: &Lease) -> eyre::Result<ApiResponse<Lease>> {
+        self.create(collection_path, lease).await
+    }
+
+    async fn replace_lease(&self, path: &str, lease: &Lease) -> eyre::Result<ApiResponse<Lease>> {
+        self.replace(path, lease).await
+    }
+}
+
+struct Elector<C> {
+    client: C,
+    path: String,
+    collection_path: String,
+    identity: String,
+    config: LeaderElectionConfig,
+}
+
+impl<C: LeaseClient> Elector<C> {
+    async fn run(self, is_leader: watch::Sender<bool>, cancellation: CancellationToken) {
+        let retry_period = Duration::from_secs(u64::from(self.config.lease_duration_secs).div_ceil(3).max(1));
+
+        loop {
+            // Given up after the retry period, before a lease renewed by the last attempt expires
+            let leader = match tokio::time::timeout(retry_period, self.try_acquire_or_renew()).await {
+                Ok(Ok(leader)) => leader,
+                Ok(Err(e)) => {
+                    tracing::warn!("failed to acquire or renew lease {}: {:#}", self.config.lease_name, e);
+                    false
+                }
+                Err(_) => {
+                    tracing::warn!("timed out acquiring or renewing lease {}", self.config.lease_name);
+                    false
+                }
+            };
+
+            is_leader.send_if_modified(|current| {
+                let changed = *current != leader;
+                if changed {
+                    tracing::info!(identity = %self.identity, lease = %self.config.lease_name, leader, "leadership changed");
+                }
+                *current = leader;
+                changed
+            });
+
+            tokio::select! {
+                _ = cancellation.cancelled() => break,
+                _ = tokio::time::sleep(retry_period) => {}
+            }
+        }
+
+        if *is_leader.borrow() {
+            if let Err(e) = self.release().await {
+                tracing::warn!("failed to release lease {}: {:#}", self.config.lease_name, e);
+            }
+            is_leader.send_replace(false);
+        }
+    }
+
+    async fn try_acquire_or_renew(&self) -> eyre::Result<bool> {
+        let now = Utc::now();
+
+        let mut lease = match self.client.get_lease(&self.path).await? {
+            ApiResponse::Ok(lease) => lease,
+            ApiResponse::NotFound => {
+                let lease = Lease::new(&self.config.lease_name, LeaseSpec {
+                    holder_identity: Some(self.identity.clone()),
+                    lease_duration_seconds: Some(self.config.lease_duration_secs),
+                    acquire_time: Some(MicroTime(now)),
+                    renew_time: Some(MicroTime(now)),
+                    lease_transitions: Some(0),
+                });
+                return Ok(matches!(self.client.create_lease(&self.collection_path, &lease).await?, ApiResponse::Ok(_)));
+            }
+            ApiResponse::Conflict => return Ok(false),
+        };
+
+        let held_by_us = lease.spec.holder_identity.as_deref() == Some(self.identity.as_str());
+        if !held_by_us && !lease.spec.is_expired(now) {
+            return Ok(false);
+        }
+
+        if !held_by_us {
+            lease.spec.holder_identity = Some(self.identity.clone());
+            lease.spec.acquire_time = Some(MicroTime(now));
+            lease.spec.lease_transitions = Some(lease.spec.lease_transitions.unwrap_or(0) + 1);
+        }
+        lease.spec.lease_duration_seconds = Some(self.config.lease_duration_secs);
+        lease.spec.renew_time = Some(MicroTime(now));
+
+        Ok(matches!(self.client.replace_lease(&self.path, &lease).await?, ApiResponse::Ok(_)))
+    }
+
+    async fn release(&self) -> eyre::Result<()> {
+        if let ApiResponse::Ok(mut lease) = self.client.get_lease(&self.path).await? {
+            if lease.spec.holder_identity.as_deref() != Some(self.identity.as_str()) {
+                return Ok(());
+            }
+            lease.spec.holder_identity = None;
+            lease.spec.lease_duration_seconds = Some(1);
+            lease.spec.renew_time = Some(MicroTime(Utc::now()));
+            self.client.replace_lease(&self.path, &lease).await?;
+        }
+        Ok(())
+    }
+}
+
+/// A `coordination.k8s.io/v1` Lease object.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Lease {
+    pub api_version: String,
+    pub kind: String,
+    pub metadata: ObjectMeta,
+    pub spec: LeaseSpec,
+}
+
+impl Lease {
+    pub fn new(name: &str, spec: LeaseSpec) -> Self {
+        Self {
+            api_version: "coordination.k8s.io/v1".to_string(),
+            kind: "Lease".to_string(),
+            metadata: ObjectMeta {
+                name: name.to_string(),
+                resource_version: None,
+            },
+            spec,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectMeta {
+    pub name: String,
+    /// Version of the object, which updates must carry to be accepted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeaseSpec {
+    /// Identity of the replica holding the lease, `None` when released.
+    pub holder_identity: Option<String>,
+    pub lease_duration_seconds: Option<u32>,
+    pub acquire_time: Option<MicroTime>,
+    pub renew_time: Option<MicroTime>,
+    /// Number of times the lease changed hands.
+    pub lease_transitions: Option<u32>,
+}
+
+impl LeaseSpec {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        match (self.renew_time.as_ref(), self.lease_duration_seconds) {
+            (Some(renew_time), Some(duration)) => renew_time.0 + chrono::Duration::seconds(i64::from(duration)) < now,
+            _ => true,
+        }
+    }
+}
+
+/// Timestamp in the Kubernetes `MicroTime` format (RFC 3339 with exactly six fractional digits).
+#[derive(Debug, Clone)]
+pub struct MicroTime(pub DateTime<Utc>);
+
+impl Serialize for MicroTime {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_rfc3339_opts(chrono::SecondsFormat::Micros, true))
+    }
+}
+
+impl<'de> Deserialize<'de> for MicroTime {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DateTime::<Utc>::deserialize(deserializer).map(MicroTime)
+    }
+}
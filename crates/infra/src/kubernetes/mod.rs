@@ -0,0 +1,53 @@
+#[cfg(feature = "kubernetes")]
+pub mod client;
+#[cfg(feature = "kubernetes")]
+pub mod leader_election;
+pub mod termination;
+
+/// Pod metadata exposed to the container through the Kubernetes downward API.
+///
+/// The values are read from environment variables that the pod spec maps from
+/// `metadata.name`, `metadata.namespace`, `spec.nodeName` and `status.podIP`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PodMetadata {
+    pub name: String,
+    pub namespace: String,
+    pub node_name: Option<String>,
+    pub pod_ip: Option<String>,
+}
+
+impl PodMetadata {
+    /// Returns a span carrying the pod metadata, used as the parent of request spans so
+    /// every log line can be attributed to the pod that produced it.
+    pub fn span(&self) -> tracing::Span {
+        tracing::info_span!(
+            "pod",
+            name = %self.name,
+            namespace = %self.namespace,
+            node = self.node_name.as_deref(),
+            ip = self.pod_ip.as_deref(),
+        )
+    }
+}
+
+/// Settings for lease-based leader election.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeaderElectionConfig {
+    /// Name of the `coordination.k8s.io/v1` Lease object shared by all replicas.
+    pub lease_name: String,
+    /// How long a lease is valid without being renewed, in seconds.
+    pub lease_duration_secs: u32,
+}
+
+/// Kubernetes integration settings, present when the server runs inside a cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KubernetesConfig {
+    pub pod: PodMetadata,
+    /// Optional leader election among replicas of the deployment.
+    pub leader_election: Option<LeaderElectionConfig>,
+    /// Delay between receiving SIGTERM and stopping to accept connections, in seconds.
+    ///
+    /// Endpoint removal propagates asynchronously through the cluster, so the server keeps
+    /// serving for this long to avoid dropping requests routed to it during a rollout.
+    pub termination_delay_secs: u64,
+}
@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+/// Waits for a termination request (`signal`) and the configured grace delay.
+///
+/// Resolves `delay` after `signal` does. Kubernetes removes a terminating pod from service
+/// endpoints concurrently with signalling it, so the server keeps accepting connections
+/// during the delay before it starts draining.
+pub async fn wait_for_termination<F>(signal: F, delay: Duration)
+where
+    F: Future<Output = ()>,
+{
+    signal.await;
+
+    tracing::info!("termination requested, keeping the server up for {:?} before draining", delay);
+    tokio::time::sleep(delay).await;
+}
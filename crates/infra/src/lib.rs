@@ -0,0 +1,17 @@
+pub mod alerting;
+pub mod auth;
+pub mod cache;
+pub mod discovery;
+pub mod email;
+pub mod error_reporting;
+pub mod jobs;
+pub mod kubernetes;
+pub mod messaging;
+pub mod outbox;
+pub mod purge;
+pub mod stats;
+pub mod storage;
+pub mod telemetry;
+pub mod traffic_archive;
+pub mod webhooks;
+pub mod config;
\ No newline at end of file
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::Context;
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::{BorrowedMessage, Header, Headers, Message, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::ClientConfig;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+use application::ports::commands::{CommandHandlerPort, CommandReply};
+
+use crate::messaging::CommandConsumerConfig;
+
+const KAFKA_CLIENT_ID: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Header of a command naming the topic its reply is produced to.
+const REPLY_TO_HEADER: &str = "reply_to";
+
+/// Header of a reply carrying the `correlation_id` of its command.
+const CORRELATION_ID_HEADER: &str = "correlation_id";
+
+/// Time within which a reply must be acknowledged by the brokers, retries included.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Delay before retrying after the brokers failed to deliver a command or a reply.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Background task consuming the commands changing users from a Kafka topic, executing them
+/// with a [`CommandHandlerPort`], and producing their replies to the reply topic, keyed by
+/// their `correlation_id`.
+///
+/// Commands are executed one at a time per replica, the replicas sharing the partitions of
+/// the topic in a consumer group. The offset of a command is committed once its reply is
+/// acknowledged, so commands are executed at least once: a command whose reply fails to be
+/// delivered is retried on the next start, and may then fail because it was applied already.
+pub struct KafkaCommandConsumer {
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl KafkaCommandConsumer {
+    /// Subscribes to the command topic of `config`, and starts executing its commands with
+    /// `handler`. Brokers are connected to lazily, so they do not need to be reachable yet.
+    pub fn spawn(config: &CommandConsumerConfig, handler: Arc<dyn CommandHandlerPort + Send + Sync>) -> eyre::Result<Self> {
+        let consumer: StreamConsumer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("client.id", KAFKA_CLIENT_ID)
+            .set("group.id", &config.group_id)
+            .set("enable.auto.commit", "false")
+            .set("auto.offset.reset", "earliest")
+            .create()
+            .context("failed to create Kafka consumer")?;
+        consumer.subscribe(&[&config.topic]).with_context(|| format!("failed to subscribe to Kafka topic {}", config.topic))?;
+
+        let producer: FutureProducer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("client.id", KAFKA_CLIENT_ID)
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .set("message.timeout.ms", REPLY_TIMEOUT.as_millis().to_string())
+            .create()
+            .context("failed to create Kafka producer")?;
+
+        let (shutdown, stopped) = watch::channel(false);
+        let task = tokio::spawn(consume(consumer, producer, handler, config.reply_topic.clone(), stopped).in_current_span());
+        Ok(Self { shutdown, task })
+    }
+
+    /// Stops consuming commands, letting the command being executed complete and its reply
+    /// be produced.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            tracing::error!("Kafka command consumer task failed: {}", e);
+        }
+    }
+}
+
+async fn consume(
+    consumer: StreamConsumer,
+    producer: FutureProducer,
+    handler: Arc<dyn CommandHandlerPort + Send + Sync>,
+    reply_topic: String,
+    mut stopped: watch::Receiver<bool>,
+) {
+    loop {
+        let message = tokio::select! {
+            // The consumer was dropped without being shut down
+            changed = stopped.changed() => if changed.is_err() || *stopped.borrow() { break } else { continue },
+            received = consumer.recv() => match received {
+                Ok(message) => message,
+                Err(e) => {
+                    tracing::warn!("failed to consume Kafka command: {}", e);
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            },
+        };
+
+        // Tombstones carry no command
+        if let Some(payload) = message.payload() {
+            let reply = handler.handle(payload).await;
+            let topic = header(&message, REPLY_TO_HEADER).unwrap_or(&reply_topic);
+            while let Err(e) = send_reply(&producer, topic, &reply).await {
+                tracing::error!(messaging.destination = topic, "failed to deliver command reply to Kafka: {:#}", e);
+                // The command is consumed again by the next replica of the group
+                if *stopped.borrow() {
+                    return;
+                }
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+        if let Err(e) = consumer.commit_message(&message, CommitMode::Async) {
+            tracing::warn!("failed to commit Kafka command offset: {}", e);
+        }
+    }
+}
+
+async fn send_reply(producer: &FutureProducer, topic: &str, reply: &CommandReply) -> eyre::Result<()> {
+    let mut headers = OwnedHeaders::new().insert(Header { key: "content_type", value: Some("application/json") });
+    if let Some(correlation_id) = &reply.correlation_id {
+        headers = headers.insert(Header { key: CORRELATION_ID_HEADER, value: Some(correlation_id) });
+    }
+    let mut record = FutureRecord::<str, [u8]>::to(topic).payload(&reply.payload).headers(headers);
+    record.key = reply.correlation_id.as_deref();
+
+    match producer.send(record, REPLY_TIMEOUT).await {
+        Ok(_) => Ok(()),
+        Err((e, _)) => Err(eyre::Report::new(e).wrap_err(format!("failed to deliver reply to Kafka topic {}", topic))),
+    }
+}
+
+/// Returns the UTF-8 value of the header `key` of `message`.
+fn header<'a>(message: &'a BorrowedMessage<'_>, key: &str) -> Option<&'a str> {
+    let headers = message.headers()?;
+    let value = headers.iter().find(|header| header.key == key)?.value?;
+    std::str::from_utf8(value).ok()
+}
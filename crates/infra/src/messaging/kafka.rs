@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use apache_avro::{types::Value as AvroValue, GenericSingleObjectWriter, Schema};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use eyre::Context;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::ClientConfig;
+use uuid::Uuid;
+
+use application::ports::events::EventPublisherPort;
+use domain::user::event::UserEvent;
+
+use crate::messaging::{protobuf, EventFormat, KafkaConfig, USER_EVENT_AVRO_SCHEMA};
+use crate::outbox::{event_data, event_message};
+
+const KAFKA_CLIENT_ID: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Backoff between the retries of a failed produce request, in milliseconds.
+const RETRY_BACKOFF_MS: u64 = 250;
+
+/// Event publisher sending the events of the users straight to a Kafka topic, keyed by the
+/// id of the user so the events of a user are consumed in order.
+///
+/// Events are acknowledged by all in-sync replicas before `publish` returns. Failed produce
+/// requests are retried by the idempotent producer, without duplicating or reordering events,
+/// until `delivery_timeout_ms` elapses; the event is then logged and reported as failed.
+pub struct KafkaEventPublisher {
+    producer: FutureProducer,
+    topic: String,
+    format: EventFormat,
+    delivery_timeout: Duration,
+    schema: Schema,
+}
+
+impl KafkaEventPublisher {
+    /// Creates the producer connecting to the brokers of `config`. Brokers are connected to
+    /// lazily, so they do not need to be reachable yet.
+    pub fn new(config: &KafkaConfig) -> eyre::Result<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", &config.brokers)
+            .set("client.id", KAFKA_CLIENT_ID)
+            .set("acks", "all")
+            .set("enable.idempotence", "true")
+            .set("retry.backoff.ms", RETRY_BACKOFF_MS.to_string())
+            .set("message.timeout.ms", config.delivery_timeout_ms.to_string())
+            .create()
+            .context("failed to create Kafka producer")?;
+
+        Ok(Self {
+            producer,
+            topic: config.topic.clone(),
+            format: config.format,
+            delivery_timeout: Duration::from_millis(config.delivery_timeout_ms),
+            schema: user_event_schema()?,
+        })
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for KafkaEventPublisher {
+    #[tracing::instrument(name = "kafka.publish", skip_all, fields(messaging.system = "kafka", messaging.destination = %self.topic, event.type = event.event_type(), user.id = %event.user_id()))]
+    async fn publish(&self, event: UserEvent) -> eyre::Result<()> {
+        let id = Uuid::new_v4().to_string();
+        let payload = encode_event(&event, self.format, &self.schema, &id, Utc::now())?;
+        let key = event.user_id().to_string();
+        let headers = OwnedHeaders::new()
+            .insert(Header { key: "event_type", value: Some(event.event_type()) })
+            .insert(Header { key: "content_type", value: Some(content_type(self.format)) });
+        let record = FutureRecord::to(&self.topic).key(&key).payload(&payload).headers(headers);
+
+        match self.producer.send(record, self.delivery_timeout).await {
+            Ok((partition, offset)) => {
+                tracing::debug!(messaging.kafka.partition = partition, messaging.kafka.offset = offset, "published user event {}", id);
+                Ok(())
+            }
+            Err((e, _)) => {
+                tracing::error!(event.id = %id, "failed to deliver user event to Kafka: {}", e);
+                Err(eyre::Report::new(e).wrap_err(format!("failed to deliver event {} to Kafka topic {}", id, self.topic)))
+            }
+        }
+    }
+}
+
+/// Parses [`USER_EVENT_AVRO_SCHEMA`].
+pub fn user_event_schema() -> eyre::Result<Schema> {
+    Schema::parse_str(USER_EVENT_AVRO_SCHEMA).context("failed to parse the Avro schema of user events")
+}
+
+/// Returns the message published for `event`, in `format`: the JSON envelope of the outbox,
+/// a record of `schema` in Avro single-object encoding, prefixed with the fingerprint of the
+/// schema, or a Protobuf `UserEvent` message.
+pub fn encode_event(event: &UserEvent, format: EventFormat, schema: &Schema, id: &str, occurred_at: DateTime<Utc>) -> eyre::Result<Vec<u8>> {
+    match format {
+        EventFormat::Json => event_message(id, event.event_type(), occurred_at, event_data(event)),
+        EventFormat::Avro => {
+            let (name, email, age) = match event {
+                UserEvent::UserCreated(user) | UserEvent::UserUpdated(user) | UserEvent::UserRestored(user) => (
+                    Some(AvroValue::String(user.name().to_string())),
+                    Some(AvroValue::String(user.email().as_str().to_string())),
+                    Some(AvroValue::Int(user.age().into())),
+                ),
+                UserEvent::UserDeleted(_) | UserEvent::UserHardDeleted(_) => (None, None, None),
+            };
+            let record = AvroValue::Record(vec![
+                ("id".to_string(), AvroValue::String(id.to_string())),
+                ("type".to_string(), AvroValue::String(event.event_type().to_string())),
+                ("occurred_at".to_string(), AvroValue::TimestampMillis(occurred_at.timestamp_millis())),
+                ("user_id".to_string(), AvroValue::String(event.user_id().to_string())),
+                ("name".to_string(), nullable(name)),
+                ("email".to_string(), nullable(email)),
+                ("age".to_string(), nullable(age)),
+            ]);
+
+            let mut writer = GenericSingleObjectWriter::new_with_capacity(schema, 256).context("failed to create Avro writer")?;
+            let mut payload = Vec::new();
+            writer.write_value(record, &mut payload).context("failed to encode event as Avro")?;
+            Ok(payload)
+        }
+        EventFormat::Protobuf => Ok(protobuf::encode_event(event, id, occurred_at)),
+    }
+}
+
+/// Returns the value of a `["null", T]` union.
+fn nullable(value: Option<AvroValue>) -> AvroValue {
+    match value {
+        Some(value) => AvroValue::Union(1, Box::new(value)),
+        None => AvroValue::Union(0, Box::new(AvroValue::Null)),
+    }
+}
+
+fn content_type(format: EventFormat) -> &'static str {
+    match format {
+        EventFormat::Json => "application/json",
+        EventFormat::Avro => "application/vnd.apache.avro+binary",
+        EventFormat::Protobuf => "application/x-protobuf",
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(feature = "kafka")]
+pub mod commands;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod protobuf;
+
+use domain::secret::Secret;
+
+/// Settings of the Kafka publisher of the events of the users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KafkaConfig {
+    /// Comma-separated `host:port` list of the bootstrap brokers.
+    pub brokers: String,
+    /// Topic the events are published to.
+    pub topic: String,
+    /// Encoding of the published events.
+    pub format: EventFormat,
+    /// Time within which an event must be acknowledged by the brokers, retries included,
+    /// in milliseconds.
+    pub delivery_timeout_ms: u64,
+}
+
+/// Settings of the Kafka consumer of the commands changing users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandConsumerConfig {
+    /// Comma-separated `host:port` list of the bootstrap brokers.
+    pub brokers: String,
+    /// Topic the commands are consumed from.
+    pub topic: String,
+    /// Topic the replies are produced to, unless a command names another in its `reply_to`
+    /// header.
+    pub reply_topic: String,
+    /// Consumer group sharing the partitions of `topic` between the replicas.
+    pub group_id: String,
+}
+
+/// Settings of the MQTT publisher of the events of the users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttConfig {
+    /// `host:port` of the broker, the port defaulting to 1883.
+    pub broker: String,
+    /// Id of the client, which must be unique per replica. A random id is used when `None`.
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<Secret<String>>,
+    /// Topic the events are published to, where `{user_id}` and `{event_type}` are replaced
+    /// by the id of the user and the type of the event.
+    pub topic: String,
+    /// Quality of service of the events: 0 (at most once), 1 (at least once) or 2 (exactly once).
+    pub qos: u8,
+    /// Whether the broker retains the last event of each topic for future subscribers.
+    pub retain: bool,
+    /// Encoding of the published events, JSON or Protobuf.
+    pub format: EventFormat,
+    /// Time within which an event must be acknowledged by the broker, in milliseconds.
+    pub delivery_timeout_ms: u64,
+}
+
+/// Encoding of the events published to the message broker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventFormat {
+    /// The JSON envelope published from the outbox.
+    #[default]
+    Json,
+    /// Avro single-object encoding, with the schema [`USER_EVENT_AVRO_SCHEMA`].
+    Avro,
+    /// A `UserEvent` message of [`USER_EVENT_PROTO_SCHEMA`].
+    Protobuf,
+}
+
+/// Avro schema of the events of the users. Attributes of the user are null in `user.deleted`
+/// and `user.hard_deleted` events.
+pub const USER_EVENT_AVRO_SCHEMA: &str = r#"{
+    "type": "record",
+    "name": "UserEvent",
+    "namespace": "rustweb.events",
+    "fields": [
+        { "name": "id", "type": "string" },
+        { "name": "type", "type": "string" },
+        { "name": "occurred_at", "type": { "type": "long", "logicalType": "timestamp-millis" } },
+        { "name": "user_id", "type": "string" },
+        { "name": "name", "type": ["null", "string"], "default": null },
+        { "name": "email", "type": ["null", "string"], "default": null },
+        { "name": "age", "type": ["null", "int"], "default": null }
+    ]
+}"#;
+
+/// Protocol Buffers schema of the events of the users, mirrored by
+/// [`protobuf::UserEventMessage`]. `user` is unset in `user.deleted` and `user.hard_deleted`
+/// events.
+pub const USER_EVENT_PROTO_SCHEMA: &str = r#"syntax = "proto3";
+
+package rustweb.events;
+
+message UserEvent {
+  string id = 1;
+  string type = 2;
+  // Milliseconds since the Unix epoch.
+  int64 occurred_at = 3;
+  string user_id = 4;
+  User user = 5;
+}
+
+message User {
+  string name = 1;
+  string email = 2;
+  uint32 age = 3;
+}
+"#;
@@ -0,0 +1,190 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use eyre::Context;
+use rumqttc::{AsyncClient, ConnectionError, Event, EventLoop, MqttOptions, Outgoing, Packet, QoS};
+use tokio::sync::oneshot;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use application::ports::events::EventPublisherPort;
+use domain::user::event::UserEvent;
+
+use crate::messaging::{protobuf, EventFormat, MqttConfig};
+use crate::outbox::{event_data, event_message};
+
+const DEFAULT_MQTT_PORT: u16 = 1883;
+
+/// Interval of the pings keeping the connection to the broker alive.
+const KEEP_ALIVE: Duration = Duration::from_secs(30);
+
+/// Publishes waiting for the event loop before `publish` waits too.
+const REQUEST_CAPACITY: usize = 100;
+
+/// Delay before reconnecting to the broker once the connection failed.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Event publisher sending the events of the users to an MQTT broker, on a topic derived from
+/// the id of the user and the type of the event, for devices and gateways following the
+/// presence and lifecycle of users.
+///
+/// `publish` returns once the event is acknowledged by the broker with QoS 1 and 2, or written
+/// to the connection with QoS 0. The connection is kept by a background task, reconnecting
+/// after failures: events published meanwhile are sent once reconnected, unless
+/// `delivery_timeout_ms` elapses first; the event is then logged and reported as failed.
+pub struct MqttEventPublisher {
+    client: AsyncClient,
+    topic: String,
+    qos: QoS,
+    retain: bool,
+    format: EventFormat,
+    delivery_timeout: Duration,
+    deliveries: Arc<Mutex<Deliveries>>,
+    /// Serializes the publishes, so that they reach the event loop in the order of `deliveries`.
+    publishing: tokio::sync::Mutex<()>,
+}
+
+impl MqttEventPublisher {
+    /// Creates the client publishing to the broker of `config`, and spawns its event loop.
+    /// The broker is connected to in the background, so it does not need to be reachable yet.
+    pub fn new(config: &MqttConfig) -> eyre::Result<Self> {
+        if config.topic.contains(['+', '#']) {
+            eyre::bail!("MQTT topic {} contains wildcards", config.topic);
+        }
+        if config.format == EventFormat::Avro {
+            eyre::bail!("MQTT events are encoded as JSON or Protobuf");
+        }
+        let qos = rumqttc::qos(config.qos).map_err(|_| eyre::eyre!("invalid MQTT QoS {}", config.qos))?;
+        let (host, port) = match config.broker.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().with_context(|| format!("invalid port in MQTT broker {}", config.broker))?),
+            None => (config.broker.as_str(), DEFAULT_MQTT_PORT),
+        };
+
+        // Replicas sharing a client id would disconnect each other
+        let client_id = config.client_id.clone().unwrap_or_else(|| format!("rustweb-{}", &Uuid::new_v4().simple().to_string()[..12]));
+        let mut options = MqttOptions::new(client_id, host, port);
+        options.set_keep_alive(KEEP_ALIVE);
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.as_ref().map(|password| password.expose_secret().clone()).unwrap_or_default());
+        }
+
+        let (client, event_loop) = AsyncClient::new(options, REQUEST_CAPACITY);
+        let deliveries = Arc::new(Mutex::new(Deliveries::default()));
+        tokio::spawn(run_event_loop(event_loop, deliveries.clone()).in_current_span());
+
+        Ok(Self {
+            client,
+            topic: config.topic.clone(),
+            qos,
+            retain: config.retain,
+            format: config.format,
+            delivery_timeout: Duration::from_millis(config.delivery_timeout_ms),
+            deliveries,
+            publishing: tokio::sync::Mutex::new(()),
+        })
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for MqttEventPublisher {
+    #[tracing::instrument(name = "mqtt.publish", skip_all, fields(messaging.system = "mqtt", messaging.destination, event.type = event.event_type(), user.id = %event.user_id()))]
+    async fn publish(&self, event: UserEvent) -> eyre::Result<()> {
+        let id = Uuid::new_v4().to_string();
+        let payload = encode_event(&event, self.format, &id, Utc::now())?;
+        let topic = event_topic(&self.topic, &event);
+        tracing::Span::current().record("messaging.destination", topic.as_str());
+
+        let (delivered, delivery) = oneshot::channel();
+        {
+            let _publishing = self.publishing.lock().await;
+            self.deliveries.lock().unwrap_or_else(|e| e.into_inner()).queued.push_back(delivered);
+            if let Err(e) = self.client.publish(topic.as_str(), self.qos, self.retain, payload).await {
+                self.deliveries.lock().unwrap_or_else(|e| e.into_inner()).queued.pop_back();
+                return Err(eyre::Report::new(e).wrap_err(format!("failed to publish event {} to MQTT topic {}", id, topic)));
+            }
+        }
+
+        match tokio::time::timeout(self.delivery_timeout, delivery).await {
+            Ok(Ok(())) => {
+                tracing::debug!("published user event {}", id);
+                Ok(())
+            }
+            _ => {
+                tracing::error!(event.id = %id, "failed to deliver user event to MQTT broker within {:?}", self.delivery_timeout);
+                Err(eyre::eyre!("failed to deliver event {} to MQTT topic {}", id, topic))
+            }
+        }
+    }
+}
+
+/// Returns `template` with `{user_id}` and `{event_type}` replaced by those of `event`.
+pub fn event_topic(template: &str, event: &UserEvent) -> String {
+    template.replace("{user_id}", &event.user_id().to_string()).replace("{event_type}", event.event_type())
+}
+
+/// Returns the message published for `event`: the JSON envelope of the outbox, or a Protobuf
+/// `UserEvent` message.
+fn encode_event(event: &UserEvent, format: EventFormat, id: &str, occurred_at: DateTime<Utc>) -> eyre::Result<Vec<u8>> {
+    match format {
+        EventFormat::Json => event_message(id, event.event_type(), occurred_at, event_data(event)),
+        EventFormat::Protobuf => Ok(protobuf::encode_event(event, id, occurred_at)),
+        EventFormat::Avro => eyre::bail!("MQTT events are encoded as JSON or Protobuf"),
+    }
+}
+
+/// Publishes awaiting their delivery, matched with the packets written and received by the
+/// event loop.
+#[derive(Default)]
+struct Deliveries {
+    /// Publishes not written to the connection yet, in the order they reached the event loop.
+    queued: VecDeque<oneshot::Sender<()>>,
+    /// Publishes written to the connection and awaiting their acknowledgement, by packet id.
+    in_flight: HashMap<u16, oneshot::Sender<()>>,
+}
+
+impl Deliveries {
+    fn written(&mut self, pkid: u16) {
+        // Publishes are written again after reconnecting, until acknowledged
+        if pkid != 0 && self.in_flight.contains_key(&pkid) {
+            return;
+        }
+        let Some(delivered) = self.queued.pop_front() else {
+            return;
+        };
+        // QoS 0 publishes have no packet id, and are never acknowledged
+        if pkid == 0 {
+            let _ = delivered.send(());
+        } else {
+            self.in_flight.insert(pkid, delivered);
+        }
+    }
+
+    fn acknowledged(&mut self, pkid: u16) {
+        if let Some(delivered) = self.in_flight.remove(&pkid) {
+            let _ = delivered.send(());
+        }
+    }
+}
+
+/// Polls the connection to the broker until the publisher is dropped, reconnecting after
+/// failures.
+async fn run_event_loop(mut event_loop: EventLoop, deliveries: Arc<Mutex<Deliveries>>) {
+    loop {
+        match event_loop.poll().await {
+            Ok(Event::Outgoing(Outgoing::Publish(pkid))) => deliveries.lock().unwrap_or_else(|e| e.into_inner()).written(pkid),
+            // QoS 1 publishes are acknowledged by PUBACK, and QoS 2 ones by PUBCOMP
+            Ok(Event::Incoming(Packet::PubAck(ack))) => deliveries.lock().unwrap_or_else(|e| e.into_inner()).acknowledged(ack.pkid),
+            Ok(Event::Incoming(Packet::PubComp(comp))) => deliveries.lock().unwrap_or_else(|e| e.into_inner()).acknowledged(comp.pkid),
+            Ok(Event::Incoming(Packet::ConnAck(_))) => tracing::info!("connected to MQTT broker"),
+            Ok(_) => {}
+            Err(ConnectionError::RequestsDone) => break,
+            Err(e) => {
+                tracing::warn!("MQTT connection failed, reconnecting: {}", e);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
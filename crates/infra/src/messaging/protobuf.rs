@@ -0,0 +1,93 @@
+//! Protocol Buffers encoding of the events of the users, with the schema
+//! [`USER_EVENT_PROTO_SCHEMA`](crate::messaging::USER_EVENT_PROTO_SCHEMA).
+//!
+//! The messages are declared with the `prost` derives rather than generated from the schema,
+//! so building does not need `protoc`; they must be kept in sync with the schema.
+
+use chrono::{DateTime, Utc};
+use eyre::{eyre, Context};
+use prost::Message;
+
+use domain::user::event::UserEvent;
+use domain::user::model::{Email, User, UserId};
+
+/// The `UserEvent` message.
+#[derive(Clone, PartialEq, Message)]
+pub struct UserEventMessage {
+    /// Id consumers deduplicate events with.
+    #[prost(string, tag = "1")]
+    pub id: String,
+    /// Type of the event, e.g. `user.created`.
+    #[prost(string, tag = "2")]
+    pub r#type: String,
+    /// Time the event was recorded, in milliseconds since the Unix epoch.
+    #[prost(int64, tag = "3")]
+    pub occurred_at: i64,
+    #[prost(string, tag = "4")]
+    pub user_id: String,
+    /// The user as changed, unset when the user was deleted.
+    #[prost(message, optional, tag = "5")]
+    pub user: Option<UserMessage>,
+}
+
+/// The `User` message.
+#[derive(Clone, PartialEq, Message)]
+pub struct UserMessage {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(string, tag = "2")]
+    pub email: String,
+    #[prost(uint32, tag = "3")]
+    pub age: u32,
+}
+
+impl UserEventMessage {
+    /// Returns the message of the event `id`, recorded at `occurred_at`.
+    pub fn new(event: &UserEvent, id: &str, occurred_at: DateTime<Utc>) -> Self {
+        let user = match event {
+            UserEvent::UserCreated(user) | UserEvent::UserUpdated(user) | UserEvent::UserRestored(user) => {
+                Some(UserMessage { name: user.name().to_string(), email: user.email().as_str().to_string(), age: user.age().into() })
+            }
+            UserEvent::UserDeleted(_) | UserEvent::UserHardDeleted(_) => None,
+        };
+        Self {
+            id: id.to_string(),
+            r#type: event.event_type().to_string(),
+            occurred_at: occurred_at.timestamp_millis(),
+            user_id: event.user_id().to_string(),
+            user,
+        }
+    }
+}
+
+impl TryFrom<UserEventMessage> for UserEvent {
+    type Error = eyre::Report;
+
+    fn try_from(message: UserEventMessage) -> eyre::Result<Self> {
+        let id = UserId::parse(&message.user_id).map_err(|e| eyre!("invalid user id {}: {}", message.user_id, e))?;
+        let user = || -> eyre::Result<User> {
+            let user = message.user.clone().ok_or_else(|| eyre!("{} event without user", message.r#type))?;
+            let email = Email::parse(user.email).map_err(|e| eyre!("invalid email: {}", e))?;
+            let age = u8::try_from(user.age).map_err(|_| eyre!("invalid age {}", user.age))?;
+            Ok(User::new(id, user.name, email, age))
+        };
+        match message.r#type.as_str() {
+            "user.created" => Ok(UserEvent::UserCreated(user()?)),
+            "user.updated" => Ok(UserEvent::UserUpdated(user()?)),
+            "user.deleted" => Ok(UserEvent::UserDeleted(id)),
+            "user.restored" => Ok(UserEvent::UserRestored(user()?)),
+            "user.hard_deleted" => Ok(UserEvent::UserHardDeleted(id)),
+            other => Err(eyre!("unknown event type {}", other)),
+        }
+    }
+}
+
+/// Returns the `UserEvent` message of the event `id`, encoded.
+pub fn encode_event(event: &UserEvent, id: &str, occurred_at: DateTime<Utc>) -> Vec<u8> {
+    UserEventMessage::new(event, id, occurred_at).encode_to_vec()
+}
+
+/// Decodes a `UserEvent` message.
+pub fn decode_event(payload: &[u8]) -> eyre::Result<UserEventMessage> {
+    UserEventMessage::decode(payload).context("failed to decode event as Protobuf")
+}
@@ -0,0 +1,146 @@
+//! Dispatch of the outbox to the message broker.
+//!
+//! Events are recorded in the outbox by the [`EventPublisherPort`](application::ports::events::EventPublisherPort)
+//! adapters of the storage, then published by an [`OutboxDispatcher`] running in the
+//! background, so a broker outage delays events instead of losing them.
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use eyre::{eyre, Context};
+use serde_json::{json, Value};
+use tokio::{sync::watch, task::JoinHandle, time};
+
+use application::ports::events::OutboxPort;
+use application::ports::messaging::MessagePublisherPort;
+use domain::user::event::UserEvent;
+use domain::user::model::{Email, User, UserId};
+
+use crate::messaging::{protobuf, EventFormat};
+
+/// Settings of the outbox dispatcher.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxConfig {
+    /// Interval between polls of the outbox while it is drained, in milliseconds.
+    pub poll_interval_ms: u64,
+    /// Maximum number of events published per poll.
+    pub batch_size: usize,
+    /// Encoding of the published events, [`EventFormat::Json`] or [`EventFormat::Protobuf`].
+    pub format: EventFormat,
+}
+
+/// Background task publishing the events of an outbox to the message broker.
+///
+/// The outbox is polled every `poll_interval_ms`, and again immediately while full batches
+/// are published, so a backlog is drained without waiting. Failures are logged and retried
+/// at the next poll.
+pub struct OutboxDispatcher {
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl OutboxDispatcher {
+    /// Starts publishing the events of `outbox` to `publisher`.
+    pub fn spawn(
+        outbox: Arc<dyn OutboxPort + Send + Sync>,
+        publisher: Arc<dyn MessagePublisherPort + Send + Sync>,
+        config: OutboxConfig,
+    ) -> Self {
+        let (shutdown, mut stopped) = watch::channel(false);
+        let task = tokio::spawn(async move {
+            let poll_interval = Duration::from_millis(config.poll_interval_ms);
+            while !*stopped.borrow() {
+                let dispatched = match outbox.dispatch(publisher.as_ref(), config.batch_size).await {
+                    Ok(dispatched) => dispatched,
+                    Err(e) => {
+                        tracing::warn!("failed to dispatch outbox events: {:#}", e);
+                        0
+                    }
+                };
+                if dispatched > 0 {
+                    tracing::debug!("dispatched {} outbox events", dispatched);
+                }
+                if dispatched > 0 && dispatched == config.batch_size {
+                    continue;
+                }
+                tokio::select! {
+                    // The dispatcher was dropped without being shut down
+                    changed = stopped.changed() => if changed.is_err() { break },
+                    _ = time::sleep(poll_interval) => {}
+                }
+            }
+        });
+
+        Self { shutdown, task }
+    }
+
+    /// Stops the dispatcher, letting the batch being published complete.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            tracing::error!("outbox dispatcher task failed: {}", e);
+        }
+    }
+}
+
+/// Returns the data of `event`, as recorded in the outbox.
+pub fn event_data(event: &UserEvent) -> Value {
+    match event {
+        UserEvent::UserCreated(user) | UserEvent::UserUpdated(user) | UserEvent::UserRestored(user) => json!({
+            "id": user.id().to_string(),
+            "name": user.name(),
+            "email": user.email().as_str(),
+            "age": user.age(),
+        }),
+        UserEvent::UserDeleted(id) | UserEvent::UserHardDeleted(id) => json!({ "id": id.to_string() }),
+    }
+}
+
+/// Returns the event of `event_type` recorded in the outbox with `data`, the inverse of
+/// [`event_data`].
+pub fn event_from_data(event_type: &str, data: &Value) -> eyre::Result<UserEvent> {
+    let id = data["id"].as_str().ok_or_else(|| eyre!("event data without id"))?;
+    let id = UserId::parse(id).map_err(|e| eyre!("invalid user id {}: {}", id, e))?;
+    let user = || -> eyre::Result<User> {
+        let name = data["name"].as_str().ok_or_else(|| eyre!("event data without name"))?;
+        let email = data["email"].as_str().ok_or_else(|| eyre!("event data without email"))?;
+        let email = Email::parse(email).map_err(|e| eyre!("invalid email: {}", e))?;
+        let age = data["age"].as_u64().and_then(|age| u8::try_from(age).ok()).ok_or_else(|| eyre!("event data without valid age"))?;
+        Ok(User::new(id, name.to_string(), email, age))
+    };
+    match event_type {
+        "user.created" => Ok(UserEvent::UserCreated(user()?)),
+        "user.updated" => Ok(UserEvent::UserUpdated(user()?)),
+        "user.deleted" => Ok(UserEvent::UserDeleted(id)),
+        "user.restored" => Ok(UserEvent::UserRestored(user()?)),
+        "user.hard_deleted" => Ok(UserEvent::UserHardDeleted(id)),
+        other => Err(eyre!("unknown event type {}", other)),
+    }
+}
+
+/// Returns the message published for the event `id` of `event_type`, recorded in the outbox
+/// with `data`, in `format`: the JSON envelope of [`event_message`], or a Protobuf
+/// `UserEvent` message.
+pub fn outbox_message(format: EventFormat, id: impl fmt::Display, event_type: &str, occurred_at: DateTime<Utc>, data: Value) -> eyre::Result<Vec<u8>> {
+    match format {
+        EventFormat::Json => event_message(id, event_type, occurred_at, data),
+        EventFormat::Protobuf => {
+            let event = event_from_data(event_type, &data).with_context(|| format!("invalid data of outbox event {}", id))?;
+            Ok(protobuf::encode_event(&event, &id.to_string(), occurred_at))
+        }
+        EventFormat::Avro => Err(eyre!("Avro events are only published to Kafka")),
+    }
+}
+
+/// Returns the message published for the event `id`: a JSON envelope carrying the id consumers
+/// deduplicate events with, the event type, the time the event was recorded and its data.
+pub fn event_message(id: impl fmt::Display, event_type: &str, occurred_at: DateTime<Utc>, data: Value) -> eyre::Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&json!({
+        "id": id.to_string(),
+        "type": event_type,
+        "occurred_at": occurred_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "data": data,
+    }))?)
+}
@@ -0,0 +1,57 @@
+use reqwest::{Client, RequestBuilder};
+use serde_json::json;
+
+use application::ports::purge::PurgePort;
+use domain::secret::Secret;
+
+use crate::purge::queue::{PurgeApi, PurgeQueue};
+use crate::purge::CloudflarePurgeConfig;
+
+/// Maximum number of cache tags purged by a request of the Cloudflare API.
+const MAX_TAGS: usize = 30;
+
+/// Purge adapter purging the responses of a Cloudflare zone by cache tag, through the
+/// `purge_cache` endpoint of the Cloudflare API.
+///
+/// Cloudflare tags cached responses with the tags of their `Cache-Tag` header, which the
+/// presentation layer sets along with `Surrogate-Key`. Purges are batched and sent by a
+/// background task, see [`PurgeQueue`].
+#[derive(Debug, Clone)]
+pub struct CloudflarePurger {
+    queue: PurgeQueue,
+}
+
+impl CloudflarePurger {
+    /// Spawns the task sending the purges of the zone of `config`.
+    pub fn spawn(config: CloudflarePurgeConfig) -> eyre::Result<Self> {
+        let url = format!("{}/zones/{}/purge_cache", config.api_url.trim_end_matches('/'), config.zone_id);
+
+        Ok(Self { queue: PurgeQueue::spawn(CloudflarePurgeApi { url, api_token: config.api_token })? })
+    }
+}
+
+impl PurgePort for CloudflarePurger {
+    fn purge(&self, keys: Vec<String>) {
+        self.queue.push(keys);
+    }
+}
+
+/// The `purge_cache` endpoint of a Cloudflare zone.
+struct CloudflarePurgeApi {
+    url: String,
+    api_token: Secret<String>,
+}
+
+impl PurgeApi for CloudflarePurgeApi {
+    fn provider(&self) -> &'static str {
+        "cloudflare"
+    }
+
+    fn max_keys(&self) -> usize {
+        MAX_TAGS
+    }
+
+    fn request(&self, client: &Client, keys: &[String]) -> RequestBuilder {
+        client.post(&self.url).bearer_auth(self.api_token.expose_secret()).json(&json!({ "tags": keys }))
+    }
+}
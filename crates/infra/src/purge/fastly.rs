@@ -0,0 +1,61 @@
+use reqwest::{Client, RequestBuilder};
+
+use application::ports::purge::PurgePort;
+use domain::secret::Secret;
+
+use crate::purge::queue::{PurgeApi, PurgeQueue};
+use crate::purge::FastlyPurgeConfig;
+
+/// Maximum number of surrogate keys purged by a request of the Fastly API.
+const MAX_KEYS: usize = 256;
+
+/// Purge adapter purging the responses of a Fastly service by surrogate key, through the bulk
+/// purge endpoint of the Fastly API.
+///
+/// Fastly tags cached responses with the keys of their `Surrogate-Key` header. Purges are
+/// batched and sent by a background task, see [`PurgeQueue`].
+#[derive(Debug, Clone)]
+pub struct FastlyPurger {
+    queue: PurgeQueue,
+}
+
+impl FastlyPurger {
+    /// Spawns the task sending the purges of the service of `config`.
+    pub fn spawn(config: FastlyPurgeConfig) -> eyre::Result<Self> {
+        let url = format!("{}/service/{}/purge", config.api_url.trim_end_matches('/'), config.service_id);
+
+        Ok(Self { queue: PurgeQueue::spawn(FastlyPurgeApi { url, api_token: config.api_token, soft: config.soft })? })
+    }
+}
+
+impl PurgePort for FastlyPurger {
+    fn purge(&self, keys: Vec<String>) {
+        self.queue.push(keys);
+    }
+}
+
+/// The bulk purge endpoint of a Fastly service.
+struct FastlyPurgeApi {
+    url: String,
+    api_token: Secret<String>,
+    soft: bool,
+}
+
+impl PurgeApi for FastlyPurgeApi {
+    fn provider(&self) -> &'static str {
+        "fastly"
+    }
+
+    fn max_keys(&self) -> usize {
+        MAX_KEYS
+    }
+
+    fn request(&self, client: &Client, keys: &[String]) -> RequestBuilder {
+        let request = client.post(&self.url).header("Fastly-Key", self.api_token.expose_secret()).header("Surrogate-Key", keys.join(" "));
+        if self.soft {
+            request.header("Fastly-Soft-Purge", "1")
+        } else {
+            request
+        }
+    }
+}
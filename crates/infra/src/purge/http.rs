@@ -0,0 +1,63 @@
+use eyre::Context;
+use reqwest::header::{HeaderName, AUTHORIZATION};
+use reqwest::{Client, Method, RequestBuilder};
+
+use application::ports::purge::PurgePort;
+use domain::secret::Secret;
+
+use crate::purge::queue::{PurgeApi, PurgeQueue};
+use crate::purge::PurgeConfig;
+
+/// Maximum number of surrogate keys listed in the header of a purge request.
+const MAX_KEYS: usize = 256;
+
+/// Purge adapter sending requests to an HTTP endpoint (e.g. Varnish), with the surrogate keys
+/// to purge in a header.
+///
+/// Purges are batched and sent by a background task, see [`PurgeQueue`].
+#[derive(Debug, Clone)]
+pub struct HttpPurger {
+    queue: PurgeQueue,
+}
+
+impl HttpPurger {
+    /// Checks the method and header of `config` and spawns the task sending the purges.
+    pub fn spawn(config: PurgeConfig) -> eyre::Result<Self> {
+        let method = Method::from_bytes(config.method.as_bytes()).with_context(|| format!("invalid purge method {}", config.method))?;
+        let keys_header = HeaderName::from_bytes(config.keys_header.as_bytes()).with_context(|| format!("invalid purge keys header {}", config.keys_header))?;
+
+        Ok(Self { queue: PurgeQueue::spawn(HttpPurgeApi { url: config.url, method, keys_header, token: config.token })? })
+    }
+}
+
+impl PurgePort for HttpPurger {
+    fn purge(&self, keys: Vec<String>) {
+        self.queue.push(keys);
+    }
+}
+
+/// Endpoint purging the keys listed in a header of its requests.
+struct HttpPurgeApi {
+    url: String,
+    method: Method,
+    keys_header: HeaderName,
+    token: Option<Secret<String>>,
+}
+
+impl PurgeApi for HttpPurgeApi {
+    fn provider(&self) -> &'static str {
+        "http"
+    }
+
+    fn max_keys(&self) -> usize {
+        MAX_KEYS
+    }
+
+    fn request(&self, client: &Client, keys: &[String]) -> RequestBuilder {
+        let request = client.request(self.method.clone(), &self.url).header(self.keys_header.clone(), keys.join(" "));
+        match &self.token {
+            Some(token) => request.header(AUTHORIZATION, format!("Bearer {}", token.expose_secret())),
+            None => request,
+        }
+    }
+}
@@ -0,0 +1,49 @@
+#[cfg(feature = "purge")]
+pub mod cloudflare;
+#[cfg(feature = "purge")]
+pub mod fastly;
+#[cfg(feature = "purge")]
+pub mod http;
+#[cfg(feature = "purge")]
+pub mod queue;
+
+use domain::secret::Secret;
+
+/// Settings of the purging of shared caches (e.g. Varnish or a CDN) over HTTP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PurgeConfig {
+    /// Endpoint purge requests are sent to, e.g. a Varnish server or a purge API of a CDN.
+    pub url: String,
+    /// Method of the purge requests, e.g. `PURGE` for Varnish or `POST` for most CDNs.
+    pub method: String,
+    /// Header of the purge requests listing the surrogate keys to purge, separated by spaces,
+    /// e.g. `Surrogate-Key`, or `xkey-purge` for the Varnish `xkey` module.
+    pub keys_header: String,
+    /// Bearer token authenticating the purge requests, if the endpoint requires one.
+    pub token: Option<Secret<String>>,
+}
+
+/// Settings of the purging of the responses cached by Cloudflare, by cache tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloudflarePurgeConfig {
+    /// Base URL of the Cloudflare API, e.g. `https://api.cloudflare.com/client/v4`.
+    pub api_url: String,
+    /// Id of the zone whose cache is purged.
+    pub zone_id: String,
+    /// API token with the `Cache Purge` permission on the zone.
+    pub api_token: Secret<String>,
+}
+
+/// Settings of the purging of the responses cached by Fastly, by surrogate key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastlyPurgeConfig {
+    /// Base URL of the Fastly API, e.g. `https://api.fastly.com`.
+    pub api_url: String,
+    /// Id of the service whose cache is purged.
+    pub service_id: String,
+    /// API token with the `purge_select` scope on the service.
+    pub api_token: Secret<String>,
+    /// Whether responses are marked as stale rather than removed, so Fastly may still serve
+    /// them while revalidating.
+    pub soft: bool,
+}
@@ -0,0 +1,136 @@
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+use eyre::Context;
+use port_decorators::RetryPolicy;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use tokio::sync::mpsc;
+use tracing::Instrument;
+
+/// Maximum number of purges waiting to be sent. Further purges are dropped, so an unreachable
+/// cache cannot exhaust memory or stall request handling.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// How long purges are collected into a batch once the first one is queued.
+const BATCH_WINDOW: Duration = Duration::from_millis(100);
+
+/// Timeout of a single purge request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Retries of purge requests failing with a transient error: four attempts, retried after
+/// 500ms, 1s and 2s.
+const RETRY_POLICY: RetryPolicy = RetryPolicy {
+    max_attempts: 4,
+    initial_backoff: Duration::from_millis(500),
+};
+
+/// Purge API of a shared cache, e.g. a CDN, purging a batch of surrogate keys per request.
+pub trait PurgeApi: Send + Sync + 'static {
+    /// Name of the API recorded in the spans of its requests, e.g. `cloudflare`.
+    fn provider(&self) -> &'static str;
+
+    /// Maximum number of keys purged by a single request.
+    fn max_keys(&self) -> usize;
+
+    /// Builds the request purging `keys`.
+    fn request(&self, client: &Client, keys: &[String]) -> RequestBuilder;
+}
+
+/// Queue of purges sent to a [`PurgeApi`] by a background task.
+///
+/// The keys of the purges queued within [`BATCH_WINDOW`] of each other are deduplicated and sent
+/// together, in as few requests as the API allows. Requests failing with a transient error
+/// (a network error, `429 Too Many Requests` or a `5xx` status) are retried with exponential
+/// backoff; other failures are logged, leaving the cached responses to expire.
+///
+/// Every request runs in a `purge.request` span recording the `purge.provider`, the number of
+/// `purge.keys`, the `attempts`, the `duration_ms` of the request with its retries, the
+/// `latency_ms` since the oldest purge of the batch was queued, and the `outcome`.
+#[derive(Debug, Clone)]
+pub struct PurgeQueue {
+    sender: mpsc::Sender<(Instant, Vec<String>)>,
+}
+
+impl PurgeQueue {
+    /// Spawns the task sending the purges queued to `api`.
+    pub fn spawn(api: impl PurgeApi) -> eyre::Result<Self> {
+        let client = Client::builder().timeout(REQUEST_TIMEOUT).build().context("failed to create purge HTTP client")?;
+        let (sender, mut receiver) = mpsc::channel::<(Instant, Vec<String>)>(QUEUE_CAPACITY);
+
+        tokio::spawn(async move {
+            while let Some((queued_at, keys)) = receiver.recv().await {
+                let mut batch: BTreeSet<String> = keys.into_iter().collect();
+                let deadline = tokio::time::Instant::now() + BATCH_WINDOW;
+                while let Ok(Some((_, keys))) = tokio::time::timeout_at(deadline, receiver.recv()).await {
+                    batch.extend(keys);
+                }
+
+                let batch: Vec<String> = batch.into_iter().collect();
+                for keys in batch.chunks(api.max_keys().max(1)) {
+                    send(&api, &client, keys, queued_at).await;
+                }
+            }
+        });
+
+        Ok(Self { sender })
+    }
+
+    /// Queues the purge of `keys`, dropping it when the queue is full.
+    pub fn push(&self, keys: Vec<String>) {
+        if self.sender.try_send((Instant::now(), keys)).is_err() {
+            tracing::warn!("purge queue is full, dropping purge");
+        }
+    }
+}
+
+/// Sends the request purging `keys`, retrying it while it fails with a transient error.
+async fn send(api: &impl PurgeApi, client: &Client, keys: &[String], queued_at: Instant) {
+    let span = tracing::info_span!(
+        "purge.request",
+        purge.provider = api.provider(),
+        purge.keys = keys.len(),
+        attempts = tracing::field::Empty,
+        duration_ms = tracing::field::Empty,
+        latency_ms = tracing::field::Empty,
+        outcome = tracing::field::Empty,
+    );
+
+    async {
+        let start = Instant::now();
+        let mut attempts = 1;
+        let result = loop {
+            match api.request(client, keys).send().await.and_then(|response| response.error_for_status()) {
+                Err(e) if attempts < RETRY_POLICY.max_attempts && is_transient(&e) => {
+                    tokio::time::sleep(RETRY_POLICY.backoff(attempts)).await;
+                    attempts += 1;
+                }
+                result => break result,
+            }
+        };
+
+        let span = tracing::Span::current();
+        span.record("attempts", attempts);
+        span.record("duration_ms", start.elapsed().as_millis() as u64);
+        span.record("latency_ms", queued_at.elapsed().as_millis() as u64);
+        match result {
+            Ok(_) => {
+                span.record("outcome", "success");
+                tracing::debug!("purged surrogate keys {}", keys.join(" "));
+            }
+            Err(e) => {
+                span.record("outcome", "error");
+                tracing::warn!("failed to purge surrogate keys {}: {}", keys.join(" "), e);
+            }
+        }
+    }
+    .instrument(span)
+    .await
+}
+
+/// Returns whether the failed request may succeed when retried.
+fn is_transient(error: &reqwest::Error) -> bool {
+    match error.status() {
+        Some(status) => status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+        None => error.is_timeout() || error.is_connect() || error.is_request(),
+    }
+}
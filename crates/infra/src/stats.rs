@@ -0,0 +1,61 @@
+//! Materialization of the daily statistics of the users.
+//!
+//! The [`StatsProjector`] computes the statistics of the current day in the background, so
+//! reading them is a lookup of the days stored by the
+//! [`UserStatsPort`](application::ports::stats::UserStatsPort) adapter.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::{sync::watch, task::JoinHandle, time};
+
+use application::ports::stats::UserStatsPort;
+
+/// Settings of the stats projector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsConfig {
+    /// Interval between projections of the statistics of the current day, in seconds.
+    pub interval_secs: u64,
+}
+
+/// Background task projecting the daily statistics of the users up to the current day (UTC).
+///
+/// The statistics are projected once started, filling the days missed while the server was
+/// stopped, then every `interval_secs`, so the current day lags by at most the interval.
+/// Failures are logged and retried at the next projection.
+pub struct StatsProjector {
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl StatsProjector {
+    /// Starts projecting the statistics of `stats`.
+    pub fn spawn(stats: Arc<dyn UserStatsPort + Send + Sync>, config: StatsConfig) -> Self {
+        let (shutdown, mut stopped) = watch::channel(false);
+        let task = tokio::spawn(async move {
+            let interval = Duration::from_secs(config.interval_secs);
+            while !*stopped.borrow() {
+                match stats.project(Utc::now().date_naive()).await {
+                    Ok(days) => tracing::debug!("projected the statistics of {} days", days),
+                    Err(e) => tracing::warn!("failed to project user statistics: {:#}", e),
+                }
+                tokio::select! {
+                    // The projector was dropped without being shut down
+                    changed = stopped.changed() => if changed.is_err() { break },
+                    _ = time::sleep(interval) => {}
+                }
+            }
+        });
+
+        Self { shutdown, task }
+    }
+
+    /// Stops the projector, letting the projection in progress complete.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            tracing::error!("stats projector task failed: {}", e);
+        }
+    }
+}
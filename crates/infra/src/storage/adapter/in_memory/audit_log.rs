@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use application::ports::audit::{AuditEntry, AuditLogPort};
+use domain::user::model::UserId;
+
+/// In-memory implementation of the audit log, for demos, local development and tests.
+///
+/// Entries are lost on restart, and only hold the changes made through this replica.
+#[derive(Default)]
+pub struct InMemoryAuditLog {
+    /// Entries of every user, oldest first.
+    entries: Mutex<Vec<AuditEntry>>,
+}
+
+impl InMemoryAuditLog {
+    /// Creates a new, empty `InMemoryAuditLog` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AuditLogPort for InMemoryAuditLog {
+    async fn record(&self, entry: AuditEntry) -> eyre::Result<()> {
+        self.entries.lock().await.push(entry);
+        Ok(())
+    }
+
+    async fn history(&self, user_id: UserId) -> eyre::Result<Vec<AuditEntry>> {
+        Ok(self.entries.lock().await.iter().filter(|entry| entry.user_id == user_id).cloned().collect())
+    }
+}
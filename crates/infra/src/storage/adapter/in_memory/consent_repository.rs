@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+
+use domain::outcome::record_outcome;
+use domain::consent::{error::ConsentDomainError, model::{Consent, RecordConsent}, repository::ConsentRepositoryPort};
+
+/// In-memory implementation of the consent repository, for demos, local development and tests.
+#[derive(Default)]
+pub struct InMemoryConsentRepository {
+    /// Consent records keyed by user id, in the order they were recorded.
+    consents: RwLock<HashMap<String, Vec<Consent>>>,
+}
+
+impl InMemoryConsentRepository {
+    /// Creates a new, empty `InMemoryConsentRepository` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ConsentRepositoryPort for InMemoryConsentRepository {
+    #[tracing::instrument(name = "consent_repository.record_consent", skip_all, fields(db.system = "in_memory", user.id = %consent.user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn record_consent(&self, consent: RecordConsent) -> Result<Consent, ConsentDomainError> {
+        record_outcome(async {
+            let recorded = Consent {
+                user_id: consent.user_id,
+                consent_type: consent.consent_type,
+                action: consent.action,
+                version: consent.version,
+                source: consent.source,
+                recorded_at: SystemTime::now(),
+            };
+
+            self.consents
+                .write()
+                .map_err(|_| ConsentDomainError::ConsentRecordFailed)?
+                .entry(recorded.user_id.clone())
+                .or_default()
+                .push(recorded.clone());
+
+            Ok(recorded)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "consent_repository.list_consents", skip_all, fields(db.system = "in_memory", user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_consents(&self, user_id: String) -> Result<Vec<Consent>, ConsentDomainError> {
+        record_outcome(async {
+            Ok(self
+                .consents
+                .read()
+                .map_err(|_| ConsentDomainError::ConsentListFailed)?
+                .get(&user_id)
+                .cloned()
+                .unwrap_or_default())
+        }
+        .await)
+    }
+}
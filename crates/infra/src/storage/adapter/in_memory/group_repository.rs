@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+
+use domain::outcome::record_outcome;
+use domain::group::{error::GroupDomainError, model::{Group, SaveGroup, UpdateMembers}, repository::GroupRepositoryPort};
+use domain::user::model::UserId;
+
+#[derive(Default)]
+struct Groups {
+    by_name: BTreeMap<String, Group>,
+    members: BTreeMap<String, BTreeSet<UserId>>,
+}
+
+/// In-memory implementation of the group repository, for demos, local development and tests.
+///
+/// The repository does not know the users, so any user can be added to a group, and users
+/// stay members after being deleted.
+#[derive(Default)]
+pub struct InMemoryGroupRepository {
+    groups: RwLock<Groups>,
+}
+
+impl InMemoryGroupRepository {
+    /// Creates a new, empty `InMemoryGroupRepository` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl GroupRepositoryPort for InMemoryGroupRepository {
+    #[tracing::instrument(name = "group_repository.save_group", skip_all, fields(db.system = "in_memory", group.name = %group.name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn save_group(&self, group: SaveGroup) -> Result<Group, GroupDomainError> {
+        record_outcome(async {
+            let saved = Group {
+                name: group.name,
+                granted_roles: group.granted_roles,
+                denied_roles: group.denied_roles,
+            };
+            self.groups
+                .write()
+                .map_err(|_| GroupDomainError::GroupSaveFailed)?
+                .by_name
+                .insert(saved.name.clone(), saved.clone());
+            Ok(saved)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.get_group", skip_all, fields(db.system = "in_memory", group.name = %name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_group(&self, name: String) -> Result<Group, GroupDomainError> {
+        record_outcome(async {
+            self.groups
+                .read()
+                .map_err(|_| GroupDomainError::GroupListFailed)?
+                .by_name
+                .get(&name)
+                .cloned()
+                .ok_or(GroupDomainError::GroupNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.list_groups", skip_all, fields(db.system = "in_memory", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_groups(&self) -> Result<Vec<Group>, GroupDomainError> {
+        record_outcome(async {
+            Ok(self.groups.read().map_err(|_| GroupDomainError::GroupListFailed)?.by_name.values().cloned().collect())
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.delete_group", skip_all, fields(db.system = "in_memory", group.name = %name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn delete_group(&self, name: String) -> Result<(), GroupDomainError> {
+        record_outcome(async {
+            let mut groups = self.groups.write().map_err(|_| GroupDomainError::GroupDeletionFailed)?;
+            groups.by_name.remove(&name).ok_or(GroupDomainError::GroupNotFound)?;
+            groups.members.remove(&name);
+            Ok(())
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.list_members", skip_all, fields(db.system = "in_memory", group.name = %name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_members(&self, name: String) -> Result<Vec<UserId>, GroupDomainError> {
+        record_outcome(async {
+            let groups = self.groups.read().map_err(|_| GroupDomainError::GroupListFailed)?;
+            if !groups.by_name.contains_key(&name) {
+                return Err(GroupDomainError::GroupNotFound);
+            }
+            Ok(groups.members.get(&name).map(|members| members.iter().copied().collect()).unwrap_or_default())
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.update_members", skip_all, fields(db.system = "in_memory", group.name = %name, members.added = members.add.len(), members.removed = members.remove.len(), outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn update_members(&self, name: String, members: UpdateMembers) -> Result<(), GroupDomainError> {
+        record_outcome(async {
+            let mut groups = self.groups.write().map_err(|_| GroupDomainError::MembershipUpdateFailed)?;
+            if !groups.by_name.contains_key(&name) {
+                return Err(GroupDomainError::GroupNotFound);
+            }
+            let group_members = groups.members.entry(name).or_default();
+            group_members.extend(members.add);
+            for id in &members.remove {
+                group_members.remove(id);
+            }
+            Ok(())
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.list_user_groups", skip_all, fields(db.system = "in_memory", user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_user_groups(&self, user_id: UserId) -> Result<Vec<Group>, GroupDomainError> {
+        record_outcome(async {
+            let groups = self.groups.read().map_err(|_| GroupDomainError::GroupListFailed)?;
+            Ok(groups
+                .members
+                .iter()
+                .filter(|(_, members)| members.contains(&user_id))
+                .filter_map(|(name, _)| groups.by_name.get(name).cloned())
+                .collect())
+        }
+        .await)
+    }
+}
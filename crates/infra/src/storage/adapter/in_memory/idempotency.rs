@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use application::ports::idempotency::{IdempotencyPort, Reservation, StoredResponse};
+
+/// A reserved key.
+struct Entry {
+    fingerprint: String,
+    /// The response of the request, `None` while it is handled.
+    response: Option<StoredResponse>,
+    expires_at: Instant,
+}
+
+/// In-memory storage of the idempotency keys, for demos, local development and tests.
+///
+/// Keys are lost on restart and are not shared between replicas, so a retry reaching another
+/// replica is handled again.
+#[derive(Default)]
+pub struct InMemoryIdempotencyStore {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl InMemoryIdempotencyStore {
+    /// Creates a new, empty `InMemoryIdempotencyStore` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl IdempotencyPort for InMemoryIdempotencyStore {
+    async fn reserve(&self, key: &str, fingerprint: &str, lease: Duration) -> eyre::Result<Reservation> {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().await;
+        entries.retain(|_, entry| entry.expires_at > now);
+
+        if let Some(entry) = entries.get(key) {
+            let fingerprint = entry.fingerprint.clone();
+            return Ok(match &entry.response {
+                Some(response) => Reservation::Completed { fingerprint, response: response.clone() },
+                None => Reservation::InProgress { fingerprint },
+            });
+        }
+        entries.insert(key.to_string(), Entry { fingerprint: fingerprint.to_string(), response: None, expires_at: now + lease });
+        Ok(Reservation::Reserved)
+    }
+
+    async fn complete(&self, key: &str, response: StoredResponse, ttl: Duration) -> eyre::Result<()> {
+        if let Some(entry) = self.entries.lock().await.get_mut(key) {
+            entry.response = Some(response);
+            entry.expires_at = Instant::now() + ttl;
+        }
+        Ok(())
+    }
+
+    async fn release(&self, key: &str) -> eyre::Result<()> {
+        let mut entries = self.entries.lock().await;
+        // Completed keys are kept until they expire
+        if entries.get(key).is_some_and(|entry| entry.response.is_none()) {
+            entries.remove(key);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+use application::jobs::{Job, JobQueuePort, NewJob};
+
+struct QueuedJob {
+    id: i64,
+    kind: String,
+    payload: Value,
+    attempts: u32,
+    run_at: Instant,
+    last_error: Option<String>,
+    failed: bool,
+}
+
+#[derive(Default)]
+struct Jobs {
+    queued: Vec<QueuedJob>,
+    last_id: i64,
+}
+
+/// In-memory implementation of the job queue, for demos, local development and tests.
+///
+/// Pending jobs are lost on restart, and only run by the worker of this replica.
+#[derive(Default)]
+pub struct InMemoryJobQueue {
+    /// Jobs not completed yet, oldest first.
+    jobs: Mutex<Jobs>,
+}
+
+impl InMemoryJobQueue {
+    /// Creates a new, empty `InMemoryJobQueue` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of jobs not completed yet, including those failed for good.
+    pub async fn pending(&self) -> usize {
+        self.jobs.lock().await.queued.len()
+    }
+
+    /// Returns the last error of each job failed for good, oldest first.
+    pub async fn failed(&self) -> Vec<String> {
+        let jobs = self.jobs.lock().await;
+        jobs.queued.iter().filter(|job| job.failed).map(|job| job.last_error.clone().unwrap_or_default()).collect()
+    }
+}
+
+#[async_trait]
+impl JobQueuePort for InMemoryJobQueue {
+    async fn enqueue(&self, job: NewJob) -> eyre::Result<()> {
+        let mut jobs = self.jobs.lock().await;
+        jobs.last_id += 1;
+        let id = jobs.last_id;
+        jobs.queued.push(QueuedJob { id, kind: job.kind, payload: job.payload, attempts: 0, run_at: Instant::now(), last_error: None, failed: false });
+        Ok(())
+    }
+
+    async fn claim(&self, limit: usize, lease: Duration) -> eyre::Result<Vec<Job>> {
+        let now = Instant::now();
+        let mut jobs = self.jobs.lock().await;
+        let claimed = jobs
+            .queued
+            .iter_mut()
+            .filter(|job| !job.failed && job.run_at <= now)
+            .take(limit)
+            .map(|job| {
+                job.attempts += 1;
+                job.run_at = now + lease;
+                Job { id: job.id, kind: job.kind.clone(), payload: job.payload.clone(), attempts: job.attempts }
+            })
+            .collect();
+        Ok(claimed)
+    }
+
+    async fn complete(&self, id: i64) -> eyre::Result<()> {
+        self.jobs.lock().await.queued.retain(|job| job.id != id);
+        Ok(())
+    }
+
+    async fn fail(&self, id: i64, error: &str, retry_in: Option<Duration>) -> eyre::Result<()> {
+        let mut jobs = self.jobs.lock().await;
+        if let Some(job) = jobs.queued.iter_mut().find(|job| job.id == id) {
+            job.last_error = Some(error.to_string());
+            match retry_in {
+                Some(retry_in) => job.run_at = Instant::now() + retry_in,
+                None => job.failed = true,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+pub mod audit_log;
+pub mod consent_repository;
+pub mod group_repository;
+pub mod idempotency;
+pub mod job_queue;
+pub mod outbox;
+pub mod passkey_repository;
+pub mod signing_keys;
+pub mod tenant_config;
+pub mod user_repository;
+
+use crate::storage::{StorageRepositories, adapter::in_memory::{consent_repository::InMemoryConsentRepository, group_repository::InMemoryGroupRepository, passkey_repository::InMemoryPasskeyRepository, user_repository::InMemoryUserRepository}, create_repositories};
+
+pub fn create_in_memory_repositories() -> eyre::Result<StorageRepositories<InMemoryUserRepository, InMemoryConsentRepository, InMemoryPasskeyRepository, InMemoryGroupRepository>> {
+    create_repositories((), |_| Ok(InMemoryUserRepository::new()), |_| Ok(InMemoryConsentRepository::new()), |_| Ok(InMemoryPasskeyRepository::new()), |_| Ok(InMemoryGroupRepository::new()))
+}
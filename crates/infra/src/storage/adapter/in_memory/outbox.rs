@@ -0,0 +1,87 @@
+use std::collections::VecDeque;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::Mutex;
+
+use application::ports::events::{EventPublisherPort, OutboxPort};
+use application::ports::messaging::MessagePublisherPort;
+use domain::user::event::UserEvent;
+
+use crate::messaging::EventFormat;
+use crate::outbox::{event_data, outbox_message};
+
+struct PendingEvent {
+    id: i64,
+    event: UserEvent,
+    recorded_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct Events {
+    pending: VecDeque<PendingEvent>,
+    last_id: i64,
+}
+
+/// In-memory implementation of the outbox, for demos, local development and tests.
+///
+/// Pending events are lost on restart.
+#[derive(Default)]
+pub struct InMemoryOutbox {
+    /// Events not published yet, oldest first. The lock is held while dispatching, so
+    /// dispatches do not interleave.
+    events: Mutex<Events>,
+    /// Encoding of the dispatched events.
+    format: EventFormat,
+}
+
+impl InMemoryOutbox {
+    /// Creates a new, empty `InMemoryOutbox` instance, dispatching events as JSON.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dispatches events in `format`, [`EventFormat::Json`] or [`EventFormat::Protobuf`].
+    pub fn with_format(mut self, format: EventFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Returns the number of events not published yet.
+    pub async fn pending(&self) -> usize {
+        self.events.lock().await.pending.len()
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for InMemoryOutbox {
+    async fn publish(&self, event: UserEvent) -> eyre::Result<()> {
+        let mut events = self.events.lock().await;
+        events.last_id += 1;
+        let id = events.last_id;
+        events.pending.push_back(PendingEvent { id, event, recorded_at: Utc::now() });
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl OutboxPort for InMemoryOutbox {
+    async fn dispatch(&self, publisher: &(dyn MessagePublisherPort + Send + Sync), limit: usize) -> eyre::Result<usize> {
+        let mut events = self.events.lock().await;
+        let mut published = 0;
+        while published < limit {
+            let Some(pending) = events.pending.front() else {
+                break;
+            };
+            let event_type = pending.event.event_type();
+            let message = outbox_message(self.format, pending.id, event_type, pending.recorded_at, event_data(&pending.event))?;
+            publisher
+                .publish(event_type, message)
+                .await
+                .map_err(|e| e.wrap_err(format!("failed to publish outbox event {}", pending.id)))?;
+            events.pending.pop_front();
+            published += 1;
+        }
+        Ok(published)
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+
+use domain::outcome::record_outcome;
+use domain::passkey::{error::PasskeyDomainError, model::{Passkey, RegisterPasskey}, repository::PasskeyRepositoryPort};
+use domain::user::model::UserId;
+
+/// In-memory implementation of the passkey repository, for demos, local development and tests.
+///
+/// Users are not checked to exist, as the in-memory repositories are independent of each other.
+#[derive(Default)]
+pub struct InMemoryPasskeyRepository {
+    /// Passkeys keyed by user id, in the order they were registered.
+    passkeys: RwLock<HashMap<UserId, Vec<Passkey>>>,
+}
+
+impl InMemoryPasskeyRepository {
+    /// Creates a new, empty `InMemoryPasskeyRepository` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl PasskeyRepositoryPort for InMemoryPasskeyRepository {
+    #[tracing::instrument(name = "passkey_repository.add_passkey", skip_all, fields(db.system = "in_memory", user.id = %passkey.user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn add_passkey(&self, passkey: RegisterPasskey) -> Result<Passkey, PasskeyDomainError> {
+        record_outcome(async {
+            let mut passkeys = self.passkeys.write().map_err(|_| PasskeyDomainError::PasskeyRegistrationFailed)?;
+            if passkeys.values().flatten().any(|registered| registered.id == passkey.id) {
+                return Err(PasskeyDomainError::PasskeyAlreadyExists);
+            }
+
+            let registered = Passkey {
+                id: passkey.id,
+                user_id: passkey.user_id,
+                name: passkey.name,
+                credential: passkey.credential,
+                created_at: SystemTime::now(),
+                last_used_at: None,
+            };
+            passkeys.entry(registered.user_id).or_default().push(registered.clone());
+
+            Ok(registered)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "passkey_repository.list_passkeys", skip_all, fields(db.system = "in_memory", user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_passkeys(&self, user_id: UserId) -> Result<Vec<Passkey>, PasskeyDomainError> {
+        record_outcome(async {
+            Ok(self
+                .passkeys
+                .read()
+                .map_err(|_| PasskeyDomainError::PasskeyListFailed)?
+                .get(&user_id)
+                .cloned()
+                .unwrap_or_default())
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "passkey_repository.record_passkey_use", skip_all, fields(db.system = "in_memory", user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn record_passkey_use(&self, user_id: UserId, id: String, credential: String) -> Result<(), PasskeyDomainError> {
+        record_outcome(async {
+            let mut passkeys = self.passkeys.write().map_err(|_| PasskeyDomainError::PasskeyUpdateFailed)?;
+            let passkey = passkeys
+                .get_mut(&user_id)
+                .and_then(|passkeys| passkeys.iter_mut().find(|passkey| passkey.id == id))
+                .ok_or(PasskeyDomainError::PasskeyNotFound)?;
+
+            passkey.credential = credential;
+            passkey.last_used_at = Some(SystemTime::now());
+
+            Ok(())
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "passkey_repository.delete_passkey", skip_all, fields(db.system = "in_memory", user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn delete_passkey(&self, user_id: UserId, id: String) -> Result<(), PasskeyDomainError> {
+        record_outcome(async {
+            let mut passkeys = self.passkeys.write().map_err(|_| PasskeyDomainError::PasskeyDeletionFailed)?;
+            let passkeys = passkeys.get_mut(&user_id).ok_or(PasskeyDomainError::PasskeyNotFound)?;
+            let index = passkeys
+                .iter()
+                .position(|passkey| passkey.id == id)
+                .ok_or(PasskeyDomainError::PasskeyNotFound)?;
+
+            passkeys.remove(index);
+
+            Ok(())
+        }
+        .await)
+    }
+}
@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::Mutex;
+
+use crate::auth::signing_keys::{SigningKeyStore, StoredSigningKey};
+
+/// In-memory storage of the signing keys, for demos, local development and tests.
+///
+/// Keys are lost on restart, invalidating the tokens they signed, and are not shared between
+/// replicas.
+#[derive(Default)]
+pub struct InMemorySigningKeyStore {
+    /// The stored keys, oldest first.
+    keys: Mutex<Vec<StoredSigningKey>>,
+}
+
+impl InMemorySigningKeyStore {
+    /// Creates a new, empty `InMemorySigningKeyStore` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SigningKeyStore for InMemorySigningKeyStore {
+    async fn list(&self) -> eyre::Result<Vec<StoredSigningKey>> {
+        Ok(self.keys.lock().await.clone())
+    }
+
+    async fn add(&self, key: StoredSigningKey, created_after: DateTime<Utc>) -> eyre::Result<bool> {
+        let mut keys = self.keys.lock().await;
+        if keys.iter().any(|stored| stored.created_at > created_after) {
+            return Ok(false);
+        }
+        keys.push(key);
+        Ok(true)
+    }
+
+    async fn remove(&self, kids: &[String]) -> eyre::Result<()> {
+        self.keys.lock().await.retain(|key| !kids.contains(&key.kid));
+        Ok(())
+    }
+}
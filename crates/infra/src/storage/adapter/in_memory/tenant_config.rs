@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+
+use application::ports::tenant::{TenantConfigPort, TenantSettings};
+
+/// In-memory storage of the tenant settings, for demos, local development and tests.
+///
+/// Settings are lost on restart and are not shared between replicas.
+#[derive(Default)]
+pub struct InMemoryTenantConfig {
+    settings: RwLock<HashMap<String, TenantSettings>>,
+}
+
+impl InMemoryTenantConfig {
+    /// Creates a new `InMemoryTenantConfig` instance, where no tenant overrides any setting.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl TenantConfigPort for InMemoryTenantConfig {
+    async fn get(&self, tenant: &str) -> eyre::Result<Option<TenantSettings>> {
+        Ok(self.settings.read().await.get(tenant).cloned())
+    }
+
+    async fn put(&self, tenant: &str, settings: TenantSettings) -> eyre::Result<()> {
+        self.settings.write().await.insert(tenant.to_string(), settings);
+        Ok(())
+    }
+
+    async fn delete(&self, tenant: &str) -> eyre::Result<bool> {
+        Ok(self.settings.write().await.remove(tenant).is_some())
+    }
+}
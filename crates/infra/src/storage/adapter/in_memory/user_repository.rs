@@ -0,0 +1,288 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{PoisonError, RwLock};
+
+use async_trait::async_trait;
+
+use domain::{collation, outcome::record_outcome, user::{error::{StorageError, UserDomainError}, model::{AgeBucket, CreateUser, Email, ListUsers, PasswordHash, Role, SortDirection, UpdateUser, User, UserCredentials, UserFacets, UserFilter, UserId, UserPage, UserPosition, UserSortField, UserSortKey, UserStatus}, repository::UserRepositoryPort}};
+
+/// In-memory implementation of the user repository.
+///
+/// This repository keeps users in a process-local map. It is intended for demos, local
+/// development and tests, and mirrors the behavior of the PostgreSQL adapter, including
+/// case- and accent-insensitive matching of names and emails.
+#[derive(Default)]
+pub struct InMemoryUserRepository {
+    /// Users keyed by their unique identifier.
+    users: RwLock<HashMap<UserId, User>>,
+    /// Soft-deleted users keyed by their unique identifier, locked after `users` when both are.
+    deleted: RwLock<HashMap<UserId, User>>,
+    /// Password hashes keyed by the identifier of their user, locked after `users` and `deleted`.
+    password_hashes: RwLock<HashMap<UserId, PasswordHash>>,
+}
+
+impl InMemoryUserRepository {
+    /// Creates a new, empty `InMemoryUserRepository` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// The cause of the failures of the repository: a lock poisoned by a panic while it was held.
+fn poisoned<T>(e: PoisonError<T>) -> StorageError {
+    StorageError::msg(e.to_string())
+}
+
+#[async_trait]
+impl UserRepositoryPort for InMemoryUserRepository {
+    #[tracing::instrument(name = "user_repository.create_user", skip_all, fields(db.system = "in_memory", user.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn create_user(&self, user: CreateUser, password_hash: Option<PasswordHash>) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let id = UserId::generate();
+            tracing::Span::current().record("user.id", tracing::field::display(id));
+            let created = User::new(id, user.name, user.email, user.age);
+
+            let mut users = self.users.write().map_err(|e| UserDomainError::UserCreationFailed(poisoned(e)))?;
+            if let Some(password_hash) = password_hash {
+                self.password_hashes.write().map_err(|e| UserDomainError::UserCreationFailed(poisoned(e)))?.insert(id, password_hash);
+            }
+            users.insert(id, created.clone());
+
+            Ok(created)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.get_user", skip_all, fields(db.system = "in_memory", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            self.users
+                .read()
+                .map_err(|e| UserDomainError::UserReadFailed(poisoned(e)))?
+                .get(&id)
+                .cloned()
+                .ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.get_user_by_email", skip_all, fields(db.system = "in_memory", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user_by_email(&self, email: Email) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let users = self.users.read().map_err(|e| UserDomainError::UserReadFailed(poisoned(e)))?;
+
+            let email = collation::fold(email.as_str());
+
+            users
+                .values()
+                .filter(|user| collation::fold(user.email().as_str()) == email)
+                .min_by(|a, b| collation::cmp(a.email().as_str(), b.email().as_str()))
+                .cloned()
+                .ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.get_user_credentials", skip_all, fields(db.system = "in_memory", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user_credentials(&self, email: Email) -> Result<UserCredentials, UserDomainError> {
+        record_outcome(async {
+            let user = self.get_user_by_email(email).await?;
+            let password_hash = self.password_hashes.read().map_err(|e| UserDomainError::UserReadFailed(poisoned(e)))?.get(&user.id()).cloned();
+
+            Ok(UserCredentials { user, password_hash })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.list_users", skip_all, fields(db.system = "in_memory", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_users(&self, query: ListUsers) -> Result<UserPage, UserDomainError> {
+        record_outcome(async {
+            let users = self.users.read().map_err(|e| UserDomainError::UserListFailed(poisoned(e)))?;
+
+            let mut sorted: Vec<&User> = users
+                .values()
+                .filter(|user| query.after.as_ref().is_none_or(|after| follows(user, after, query.direction)))
+                .collect();
+            sorted.sort_by(|a, b| {
+                let ordering = match query.sort_by {
+                    UserSortField::Name => collation::cmp(a.name(), b.name()),
+                    UserSortField::Email => collation::cmp(a.email().as_str(), b.email().as_str()),
+                    UserSortField::Age => a.age().cmp(&b.age()),
+                };
+                let ordering = match query.direction {
+                    SortDirection::Asc => ordering,
+                    SortDirection::Desc => ordering.reverse(),
+                };
+                ordering.then_with(|| a.id().cmp(&b.id()))
+            });
+
+            let page = sorted
+                .into_iter()
+                .skip(usize::try_from(query.offset).unwrap_or(usize::MAX))
+                .take(query.limit as usize)
+                .cloned()
+                .collect();
+
+            Ok(UserPage { users: page, total: users.len() as u64 })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.search_users", skip_all, fields(db.system = "in_memory", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn search_users(&self, filter: UserFilter) -> Result<UserPage, UserDomainError> {
+        record_outcome(async {
+            let users = self.users.read().map_err(|e| UserDomainError::UserListFailed(poisoned(e)))?;
+
+            let total = users.values().filter(|user| filter.matches(user)).count() as u64;
+            let mut matching: Vec<&User> = users
+                .values()
+                .filter(|user| filter.matches(user) && filter.after.as_ref().is_none_or(|after| follows(user, after, SortDirection::Asc)))
+                .collect();
+            matching.sort_by(|a, b| collation::cmp(a.name(), b.name()).then_with(|| a.id().cmp(&b.id())));
+
+            let page = matching
+                .into_iter()
+                .skip(usize::try_from(filter.offset).unwrap_or(usize::MAX))
+                .take(filter.limit as usize)
+                .cloned()
+                .collect();
+
+            Ok(UserPage { users: page, total })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.count_user_facets", skip_all, fields(db.system = "in_memory", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError> {
+        record_outcome(async {
+            let users = self.users.read().map_err(|e| UserDomainError::UserListFailed(poisoned(e)))?;
+
+            Ok(UserFacets::from_counts(
+                count_by(users.values(), UserStatus::of),
+                count_by(users.values(), |user| user.email().domain().to_lowercase()),
+                count_by(users.values(), |user| AgeBucket::of(user.age())),
+            ))
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.update_user", skip_all, fields(db.system = "in_memory", user.id = %user.id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn update_user(&self, user: UpdateUser) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let mut users = self.users.write().map_err(|e| UserDomainError::UserUpdateFailed(poisoned(e)))?;
+            let existing = users.get(&user.id).ok_or(UserDomainError::UserNotFound)?;
+            if user.expected_version.is_some_and(|version| version != existing.version()) {
+                return Err(UserDomainError::UserVersionMismatch);
+            }
+
+            let name = user.name.unwrap_or_else(|| existing.name().to_string());
+            let email = user.email.unwrap_or_else(|| existing.email().clone());
+            let age = user.age.unwrap_or(existing.age());
+
+            let updated = User::new(user.id, name, email, age).with_legal_hold(existing.legal_hold()).with_role(existing.role()).with_version(existing.version() + 1);
+            users.insert(user.id, updated.clone());
+
+            Ok(updated)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.delete_user", skip_all, fields(db.system = "in_memory", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        record_outcome(async {
+            let mut users = self.users.write().map_err(|e| UserDomainError::UserDeletionFailed(poisoned(e)))?;
+            let mut deleted = self.deleted.write().map_err(|e| UserDomainError::UserDeletionFailed(poisoned(e)))?;
+
+            let user = users.remove(&id).ok_or(UserDomainError::UserNotFound)?;
+            let version = user.version() + 1;
+            deleted.insert(id, user.with_version(version));
+
+            Ok(())
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.restore_user", skip_all, fields(db.system = "in_memory", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn restore_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let mut users = self.users.write().map_err(|e| UserDomainError::UserUpdateFailed(poisoned(e)))?;
+            let mut deleted = self.deleted.write().map_err(|e| UserDomainError::UserUpdateFailed(poisoned(e)))?;
+
+            let user = deleted.remove(&id).ok_or(UserDomainError::UserNotFound)?;
+            let user = user.clone().with_version(user.version() + 1);
+            users.insert(id, user.clone());
+
+            Ok(user)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.hard_delete_user", skip_all, fields(db.system = "in_memory", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn hard_delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        record_outcome(async {
+            let mut users = self.users.write().map_err(|e| UserDomainError::UserDeletionFailed(poisoned(e)))?;
+            let mut deleted = self.deleted.write().map_err(|e| UserDomainError::UserDeletionFailed(poisoned(e)))?;
+            let mut password_hashes = self.password_hashes.write().map_err(|e| UserDomainError::UserDeletionFailed(poisoned(e)))?;
+
+            users
+                .remove(&id)
+                .or_else(|| deleted.remove(&id))
+                .map(|_| {
+                    password_hashes.remove(&id);
+                })
+                .ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.set_legal_hold", skip_all, fields(db.system = "in_memory", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn set_legal_hold(&self, id: UserId, legal_hold: bool) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let mut users = self.users.write().map_err(|e| UserDomainError::UserUpdateFailed(poisoned(e)))?;
+            let user = users.get_mut(&id).ok_or(UserDomainError::UserNotFound)?;
+
+            *user = user.clone().with_legal_hold(legal_hold).with_version(user.version() + 1);
+
+            Ok(user.clone())
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.set_role", skip_all, fields(db.system = "in_memory", user.id = %id, role = %role, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn set_role(&self, id: UserId, role: Role) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let mut users = self.users.write().map_err(|e| UserDomainError::UserUpdateFailed(poisoned(e)))?;
+            let user = users.get_mut(&id).ok_or(UserDomainError::UserNotFound)?;
+
+            *user = user.clone().with_role(role).with_version(user.version() + 1);
+
+            Ok(user.clone())
+        }
+        .await)
+    }
+}
+
+/// Returns `true` if `user` is ordered after the position `after` in a list ordered by the
+/// field of its key in `direction`, users with an equal key being ordered by id.
+fn follows(user: &User, after: &UserPosition, direction: SortDirection) -> bool {
+    let ordering = match &after.key {
+        UserSortKey::Name(name) => collation::cmp(user.name(), name),
+        UserSortKey::Email(email) => collation::cmp(user.email().as_str(), email),
+        UserSortKey::Age(age) => user.age().cmp(age),
+    };
+    let ordering = match direction {
+        SortDirection::Asc => ordering,
+        SortDirection::Desc => ordering.reverse(),
+    };
+    ordering.then_with(|| user.id().cmp(&after.id)) == Ordering::Greater
+}
+
+/// Counts `users` by the value `key` returns for each of them.
+fn count_by<'a, K: Eq + Hash>(users: impl Iterator<Item = &'a User>, key: impl Fn(&User) -> K) -> HashMap<K, u64> {
+    let mut counts = HashMap::new();
+    for user in users {
+        *counts.entry(key(user)).or_insert(0) += 1;
+    }
+    counts
+}
@@ -0,0 +1,11 @@
+pub mod in_memory;
+pub mod postgres;
+pub mod sql_error;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+/// Returns whether `database_url` is the URL of a SQLite database (`sqlite://users.db`,
+/// `sqlite::memory:`), stored by the `sqlite` adapter rather than the PostgreSQL one.
+pub fn is_sqlite_url(database_url: &str) -> bool {
+    database_url.starts_with("sqlite:")
+}
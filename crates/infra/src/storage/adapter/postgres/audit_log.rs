@@ -0,0 +1,168 @@
+use async_trait::async_trait;
+use chrono::SubsecRound;
+use eyre::{Context, OptionExt};
+use futures_util::TryStreamExt;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+use application::ports::audit::{AuditAction, AuditEntry, AuditLogPort, FieldChange};
+use domain::user::model::UserId;
+
+use crate::storage::adapter::postgres::{unit_of_work::{Connection, SharedTransaction}, Db};
+
+/// PostgreSQL storage of the audit log, backed by the `audit_log` table, with the changes of
+/// each entry as JSON.
+///
+/// Entries are hash-chained: each row stores the hash of the previous row, in the order of
+/// their ids, and its own hash, over the previous hash and the contents of the entry. Changing,
+/// removing or inserting a row breaks the chain, which [`PostgresAuditLog::verify_chain`]
+/// detects. The hash of the last entry is kept in the `audit_log_chain_head` row, locked by
+/// each append until its transaction ends, so entries are chained one at a time, in the order
+/// of their ids, and an append in a serializable transaction whose snapshot predates the last
+/// one fails with a serialization failure instead of forking the chain.
+pub struct PostgresAuditLog {
+    /// The connection pool, or the transaction of a unit of work.
+    connection: Connection,
+}
+
+/// Result of [`PostgresAuditLog::verify_chain`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainVerification {
+    /// Every chained entry matches its hash and follows the previous one.
+    Intact {
+        /// Number of chained entries.
+        entries: u64,
+        /// Number of entries recorded before the chain was introduced, which are not verified.
+        unchained: u64,
+        /// Hash of the last entry, `None` when no entry is chained. Entries removed from the
+        /// end of the log go unnoticed unless this hash is kept out of the database.
+        last_hash: Option<String>,
+    },
+    /// The entry `id` does not match its hash, or does not follow the entry before it: it or a
+    /// previous entry was changed, removed or inserted.
+    Broken { id: i64 },
+}
+
+impl PostgresAuditLog {
+    /// Creates a new `PostgresAuditLog` instance.
+    pub fn new(db: Db) -> Self {
+        Self { connection: Connection::Pool(db) }
+    }
+
+    /// Creates a `PostgresAuditLog` recording its entries in `transaction`.
+    pub(crate) fn in_transaction(transaction: SharedTransaction) -> Self {
+        Self { connection: Connection::Transaction(transaction) }
+    }
+
+    /// Walks the whole log in the order of the ids, checking the hash of every chained entry
+    /// and that it follows the previous one. Stops at the first broken entry. The entries are
+    /// streamed, so the log is not loaded at once.
+    #[tracing::instrument(name = "audit_log.verify_chain", skip_all, fields(db.system = "postgresql"))]
+    pub async fn verify_chain(&self) -> eyre::Result<ChainVerification> {
+        let mut connection = self.connection.acquire().await.context("failed to read audit log")?;
+        let mut rows = sqlx::query("SELECT id, user_id, actor, impersonated_user_id, action, changes, recorded_at, prev_hash, hash FROM audit_log ORDER BY id")
+            .fetch(&mut *connection);
+
+        let (mut entries, mut unchained, mut last_hash) = (0, 0, None::<String>);
+        while let Some(row) = rows.try_next().await.context("failed to read audit log")? {
+            let id: i64 = row.try_get("id")?;
+            let prev_hash: Option<String> = row.try_get("prev_hash")?;
+            let Some(hash) = row.try_get::<Option<String>, _>("hash")? else {
+                // Entries recorded before the chain was introduced precede every chained one
+                if entries > 0 {
+                    return Ok(ChainVerification::Broken { id });
+                }
+                unchained += 1;
+                continue;
+            };
+            let entry = audit_entry(&row, row.try_get("user_id")?)?;
+            if prev_hash != last_hash || entry_hash(prev_hash.as_deref(), &entry) != hash {
+                return Ok(ChainVerification::Broken { id });
+            }
+            entries += 1;
+            last_hash = Some(hash);
+        }
+        Ok(ChainVerification::Intact { entries, unchained, last_hash })
+    }
+}
+
+/// Returns the hash of `entry`, following the entry of hash `prev_hash`: the hex-encoded SHA-256
+/// of the canonical JSON of both, whose keys are sorted.
+fn entry_hash(prev_hash: Option<&str>, entry: &AuditEntry) -> String {
+    let canonical = json!({
+        "prev_hash": prev_hash,
+        "user_id": entry.user_id.to_string(),
+        "actor": entry.actor,
+        "impersonated_user_id": entry.impersonated_user_id,
+        "action": entry.action.as_str(),
+        "changes": entry.changes,
+        // Stored with a precision of a microsecond
+        "recorded_at": entry.recorded_at.timestamp_micros(),
+    });
+    Sha256::digest(canonical.to_string().as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Reads the entry of `row`, of the user `user_id`.
+fn audit_entry(row: &PgRow, user_id: UserId) -> eyre::Result<AuditEntry> {
+    let action: String = row.try_get("action")?;
+    let changes: Value = row.try_get("changes")?;
+    Ok(AuditEntry {
+        user_id,
+        actor: row.try_get("actor")?,
+        impersonated_user_id: row.try_get("impersonated_user_id")?,
+        action: AuditAction::parse(&action).ok_or_eyre(format!("unknown audit action {}", action))?,
+        changes: serde_json::from_value::<Vec<FieldChange>>(changes).context("invalid audit changes")?,
+        recorded_at: row.try_get("recorded_at")?,
+    })
+}
+
+#[async_trait]
+impl AuditLogPort for PostgresAuditLog {
+    #[tracing::instrument(name = "audit_log.record", skip_all, fields(db.system = "postgresql", user.id = %entry.user_id, audit.action = entry.action.as_str()))]
+    async fn record(&self, mut entry: AuditEntry) -> eyre::Result<()> {
+        entry.recorded_at = entry.recorded_at.trunc_subsecs(6);
+        let mut connection = self.connection.acquire().await.context("failed to record audit entry")?;
+        // In a unit of work, a savepoint, the lock being held until the unit of work ends
+        let mut tx = sqlx::Connection::begin(&mut *connection).await.context("failed to begin audit log transaction")?;
+
+        // Reads the head last committed, even when the snapshot of the transaction is older
+        let prev_hash: Option<String> = sqlx::query_scalar("SELECT hash FROM audit_log_chain_head FOR UPDATE")
+            .fetch_one(&mut *tx)
+            .await
+            .context("failed to lock the head of the audit log")?;
+        let hash = entry_hash(prev_hash.as_deref(), &entry);
+
+        sqlx::query("INSERT INTO audit_log (user_id, actor, impersonated_user_id, action, changes, recorded_at, prev_hash, hash) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)")
+            .bind(entry.user_id)
+            .bind(&entry.actor)
+            .bind(&entry.impersonated_user_id)
+            .bind(entry.action.as_str())
+            .bind(serde_json::to_value(&entry.changes)?)
+            .bind(entry.recorded_at)
+            .bind(&prev_hash)
+            .bind(&hash)
+            .execute(&mut *tx)
+            .await
+            .context("failed to record audit entry")?;
+        sqlx::query("UPDATE audit_log_chain_head SET hash = $1")
+            .bind(&hash)
+            .execute(&mut *tx)
+            .await
+            .context("failed to move the head of the audit log")?;
+        tx.commit().await.context("failed to commit audit log transaction")
+    }
+
+    #[tracing::instrument(name = "audit_log.history", skip_all, fields(db.system = "postgresql", user.id = %user_id))]
+    async fn history(&self, user_id: UserId) -> eyre::Result<Vec<AuditEntry>> {
+        let mut connection = self.connection.acquire().await.context("failed to read audit log")?;
+        let rows = sqlx::query("SELECT actor, impersonated_user_id, action, changes, recorded_at FROM audit_log WHERE user_id = $1 ORDER BY id")
+            .bind(user_id)
+            .fetch_all(&mut *connection)
+            .await
+            .context("failed to read audit log")?;
+
+        rows.iter().map(|row| audit_entry(row, user_id)).collect()
+    }
+}
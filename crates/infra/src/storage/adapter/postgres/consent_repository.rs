@@ -0,0 +1,100 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, Row};
+
+use domain::outcome::record_outcome;
+use domain::consent::{error::ConsentDomainError, model::{Consent, ConsentAction, ConsentType, RecordConsent}, repository::ConsentRepositoryPort};
+
+use crate::storage::adapter::postgres::Db;
+use crate::storage::adapter::sql_error::is_foreign_key_violation;
+
+/// PostgreSQL implementation of the consent repository, backed by the `user_consents` table.
+pub struct ConsentRepository {
+    /// The PostgreSQL database connection pool.
+    db: Db,
+}
+
+impl ConsentRepository {
+    /// Creates a new `ConsentRepository` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ConsentRepositoryPort for ConsentRepository {
+    #[tracing::instrument(name = "consent_repository.record_consent", skip_all, fields(db.system = "postgresql", user.id = %consent.user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn record_consent(&self, consent: RecordConsent) -> Result<Consent, ConsentDomainError> {
+        record_outcome(async {
+            let row = sqlx::query(
+                r#"
+                INSERT INTO user_consents (user_id, consent_type, action, version, source)
+                VALUES ($1, $2, $3, $4, $5)
+                RETURNING user_id, consent_type, action, version, source, recorded_at
+                "#,
+            )
+            .bind(&consent.user_id)
+            .bind(consent.consent_type.as_str())
+            .bind(consent.action.as_str())
+            .bind(&consent.version)
+            .bind(&consent.source)
+            .fetch_one(&*self.db)
+            .await
+            .map_err(|e| {
+                if is_foreign_key_violation(&e) {
+                    ConsentDomainError::UserNotFound
+                } else {
+                    tracing::error!("Failed to record consent: {}", e);
+                    ConsentDomainError::ConsentRecordFailed
+                }
+            })?;
+
+            consent_from_row(row).ok_or(ConsentDomainError::ConsentRecordFailed)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "consent_repository.list_consents", skip_all, fields(db.system = "postgresql", user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_consents(&self, user_id: String) -> Result<Vec<Consent>, ConsentDomainError> {
+        record_outcome(async {
+            let rows = sqlx::query(
+                r#"
+                SELECT user_id, consent_type, action, version, source, recorded_at
+                FROM user_consents
+                WHERE user_id = $1
+                ORDER BY recorded_at, id
+                "#,
+            )
+            .bind(&user_id)
+            .fetch_all(&*self.db)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to list consents: {}", e);
+                ConsentDomainError::ConsentListFailed
+            })?;
+
+            rows.into_iter()
+                .map(|row| consent_from_row(row).ok_or(ConsentDomainError::ConsentListFailed))
+                .collect()
+        }
+        .await)
+    }
+}
+
+/// Maps a `user_consents` table row to the domain `Consent` model, or `None` if the row
+/// holds a consent type or action unknown to this version.
+fn consent_from_row(row: PgRow) -> Option<Consent> {
+    let consent_type: String = row.get("consent_type");
+    let action: String = row.get("action");
+    let recorded_at: DateTime<Utc> = row.get("recorded_at");
+
+    let consent = Consent {
+        user_id: row.get("user_id"),
+        consent_type: ConsentType::parse(&consent_type)?,
+        action: ConsentAction::parse(&action)?,
+        version: row.get("version"),
+        source: row.get("source"),
+        recorded_at: recorded_at.into(),
+    };
+    Some(consent)
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeSet;
+
+use async_trait::async_trait;
+use sqlx::{postgres::PgRow, Row};
+
+use domain::outcome::record_outcome;
+use domain::group::{error::GroupDomainError, model::{Group, SaveGroup, UpdateMembers}, repository::GroupRepositoryPort};
+use domain::user::model::UserId;
+
+use crate::storage::adapter::postgres::{retry::retry_transient, Db};
+
+/// PostgreSQL implementation of the group repository, backed by the `groups` and
+/// `group_members` tables.
+pub struct GroupRepository {
+    /// The PostgreSQL database connection pool.
+    db: Db,
+}
+
+impl GroupRepository {
+    /// Creates a new `GroupRepository` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+
+    /// Adds and removes the members of group `name` in a transaction. Fails with the database
+    /// error, so transient ones can be retried, or returns the rejection of the update.
+    async fn apply_members(&self, name: &str, members: &UpdateMembers) -> Result<Result<(), GroupDomainError>, sqlx::Error> {
+        let add: Vec<String> = members.add.iter().map(UserId::to_string).collect();
+        let remove: Vec<String> = members.remove.iter().map(UserId::to_string).collect();
+
+        // Changes are rolled back when the transaction is dropped without being committed
+        let mut tx = self.db.begin().await?;
+
+        // Concurrent updates of the group are serialized, and it cannot be deleted meanwhile
+        let group = sqlx::query("SELECT name FROM groups WHERE name = $1 FOR UPDATE").bind(name).fetch_optional(&mut *tx).await?;
+        if group.is_none() {
+            return Ok(Err(GroupDomainError::GroupNotFound));
+        }
+
+        if !add.is_empty() {
+            // The users to add cannot be deleted, soft or not, until the transaction completes
+            let existing: BTreeSet<String> = sqlx::query_scalar("SELECT id FROM users WHERE id = ANY($1) AND deleted_at IS NULL FOR SHARE")
+                .bind(&add)
+                .fetch_all(&mut *tx)
+                .await?
+                .into_iter()
+                .collect();
+            let unknown: Vec<UserId> = members.add.iter().filter(|id| !existing.contains(&id.to_string())).copied().collect();
+            if !unknown.is_empty() {
+                return Ok(Err(GroupDomainError::UnknownMembers(unknown)));
+            }
+
+            sqlx::query(
+                r#"
+                INSERT INTO group_members (group_name, user_id)
+                SELECT $1, user_id FROM UNNEST($2::TEXT[]) AS user_id
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(name)
+            .bind(&add)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if !remove.is_empty() {
+            sqlx::query("DELETE FROM group_members WHERE group_name = $1 AND user_id = ANY($2)")
+                .bind(name)
+                .bind(&remove)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await.map(Ok)
+    }
+}
+
+#[async_trait]
+impl GroupRepositoryPort for GroupRepository {
+    #[tracing::instrument(name = "group_repository.save_group", skip_all, fields(db.system = "postgresql", group.name = %group.name, db.retries = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn save_group(&self, group: SaveGroup) -> Result<Group, GroupDomainError> {
+        record_outcome(async {
+            retry_transient(|| {
+                sqlx::query(
+                    r#"
+                    INSERT INTO groups (name, granted_roles, denied_roles)
+                    VALUES ($1, $2, $3)
+                    ON CONFLICT (name) DO UPDATE
+                    SET granted_roles = EXCLUDED.granted_roles, denied_roles = EXCLUDED.denied_roles, updated_at = CURRENT_TIMESTAMP
+                    RETURNING name, granted_roles, denied_roles
+                    "#,
+                )
+                .bind(&group.name)
+                .bind(&group.granted_roles)
+                .bind(&group.denied_roles)
+                .fetch_one(&*self.db)
+            })
+            .await
+            .and_then(group_from_row)
+            .map_err(|e| {
+                tracing::error!("Failed to save group: {}", e);
+                GroupDomainError::GroupSaveFailed
+            })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.get_group", skip_all, fields(db.system = "postgresql", group.name = %name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_group(&self, name: String) -> Result<Group, GroupDomainError> {
+        record_outcome(async {
+            sqlx::query("SELECT name, granted_roles, denied_roles FROM groups WHERE name = $1")
+                .bind(&name)
+                .fetch_optional(&*self.db)
+                .await
+                .and_then(|row| row.map(group_from_row).transpose())
+                .map_err(|e| {
+                    tracing::error!("Failed to get group: {}", e);
+                    GroupDomainError::GroupListFailed
+                })?
+                .ok_or(GroupDomainError::GroupNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.list_groups", skip_all, fields(db.system = "postgresql", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_groups(&self) -> Result<Vec<Group>, GroupDomainError> {
+        record_outcome(async {
+            sqlx::query("SELECT name, granted_roles, denied_roles FROM groups ORDER BY name")
+                .fetch_all(&*self.db)
+                .await
+                .and_then(|rows| rows.into_iter().map(group_from_row).collect())
+                .map_err(|e| {
+                    tracing::error!("Failed to list groups: {}", e);
+                    GroupDomainError::GroupListFailed
+                })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.delete_group", skip_all, fields(db.system = "postgresql", group.name = %name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn delete_group(&self, name: String) -> Result<(), GroupDomainError> {
+        record_outcome(async {
+            let result = sqlx::query("DELETE FROM groups WHERE name = $1")
+                .bind(&name)
+                .execute(&*self.db)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to delete group: {}", e);
+                    GroupDomainError::GroupDeletionFailed
+                })?;
+
+            if result.rows_affected() == 0 {
+                return Err(GroupDomainError::GroupNotFound);
+            }
+            Ok(())
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.list_members", skip_all, fields(db.system = "postgresql", group.name = %name, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_members(&self, name: String) -> Result<Vec<UserId>, GroupDomainError> {
+        record_outcome(async {
+            // The group is always returned, so a group without members is told from a missing one
+            let rows = sqlx::query(
+                r#"
+                SELECT m.user_id
+                FROM groups g
+                LEFT JOIN group_members m ON m.group_name = g.name
+                WHERE g.name = $1
+                ORDER BY m.user_id
+                "#,
+            )
+            .bind(&name)
+            .fetch_all(&*self.db)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to list group members: {}", e);
+                GroupDomainError::GroupListFailed
+            })?;
+
+            if rows.is_empty() {
+                return Err(GroupDomainError::GroupNotFound);
+            }
+            rows.into_iter()
+                .map(|row| row.try_get::<Option<UserId>, _>("user_id"))
+                .filter_map(Result::transpose)
+                .collect::<Result<_, _>>()
+                .map_err(|e| {
+                    tracing::error!("Failed to decode group members: {}", e);
+                    GroupDomainError::GroupListFailed
+                })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.update_members", skip_all, fields(db.system = "postgresql", group.name = %name, members.added = members.add.len(), members.removed = members.remove.len(), db.retries = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn update_members(&self, name: String, members: UpdateMembers) -> Result<(), GroupDomainError> {
+        record_outcome(async {
+            // Concurrent updates lock the same rows, so the transaction may be aborted to break
+            // a deadlock, and is then run again as a whole
+            retry_transient(|| self.apply_members(&name, &members))
+                .await
+                .map_err(|e| {
+                    tracing::error!("Failed to update group members: {}", e);
+                    GroupDomainError::MembershipUpdateFailed
+                })?
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "group_repository.list_user_groups", skip_all, fields(db.system = "postgresql", user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_user_groups(&self, user_id: UserId) -> Result<Vec<Group>, GroupDomainError> {
+        record_outcome(async {
+            sqlx::query(
+                r#"
+                SELECT g.name, g.granted_roles, g.denied_roles
+                FROM groups g
+                JOIN group_members m ON m.group_name = g.name
+                WHERE m.user_id = $1
+                ORDER BY g.name
+                "#,
+            )
+            .bind(user_id)
+            .fetch_all(&*self.db)
+            .await
+            .and_then(|rows| rows.into_iter().map(group_from_row).collect())
+            .map_err(|e| {
+                tracing::error!("Failed to list groups of user: {}", e);
+                GroupDomainError::GroupListFailed
+            })
+        }
+        .await)
+    }
+}
+
+/// Maps a `groups` table row to the domain `Group` model.
+fn group_from_row(row: PgRow) -> Result<Group, sqlx::Error> {
+    Ok(Group {
+        name: row.try_get("name")?,
+        granted_roles: row.try_get("granted_roles")?,
+        denied_roles: row.try_get("denied_roles")?,
+    })
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eyre::Context;
+use sqlx::{types::Json, Row};
+
+use application::ports::idempotency::{IdempotencyPort, Reservation, StoredResponse};
+
+use crate::storage::adapter::postgres::{retry::retry_transient, Db};
+
+/// PostgreSQL storage of the idempotency keys, backed by the `idempotency_keys` table, so the
+/// retries of a request are replayed whichever replica they reach.
+pub struct PostgresIdempotencyStore {
+    /// The PostgreSQL database connection pool.
+    db: Db,
+}
+
+impl PostgresIdempotencyStore {
+    /// Creates a new `PostgresIdempotencyStore` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl IdempotencyPort for PostgresIdempotencyStore {
+    #[tracing::instrument(name = "idempotency.reserve", skip_all, fields(db.system = "postgresql", db.retries = tracing::field::Empty))]
+    async fn reserve(&self, key: &str, fingerprint: &str, lease: Duration) -> eyre::Result<Reservation> {
+        loop {
+            // An expired key is taken over, as if it was free
+            let reserved = retry_transient(|| {
+                sqlx::query(
+                    r#"
+                    INSERT INTO idempotency_keys (key, fingerprint, expires_at)
+                    VALUES ($1, $2, CURRENT_TIMESTAMP + $3 * INTERVAL '1 millisecond')
+                    ON CONFLICT (key) DO UPDATE
+                        SET fingerprint = EXCLUDED.fingerprint, status = NULL, content_type = NULL, headers = NULL, body = NULL,
+                            created_at = CURRENT_TIMESTAMP, expires_at = EXCLUDED.expires_at
+                        WHERE idempotency_keys.expires_at <= CURRENT_TIMESTAMP
+                    "#,
+                )
+                .bind(key)
+                .bind(fingerprint)
+                .bind(lease.as_millis() as i64)
+                .execute(&*self.db)
+            })
+            .await
+            .context("failed to reserve idempotency key")?
+            .rows_affected()
+                > 0;
+            if reserved {
+                return Ok(Reservation::Reserved);
+            }
+
+            let row = sqlx::query("SELECT fingerprint, status, content_type, headers, body FROM idempotency_keys WHERE key = $1")
+                .bind(key)
+                .fetch_optional(&*self.db)
+                .await
+                .context("failed to read idempotency key")?;
+            // Released since the reservation was attempted, attempted again
+            let Some(row) = row else { continue };
+
+            let fingerprint = row.try_get("fingerprint")?;
+            let status: Option<i16> = row.try_get("status")?;
+            return Ok(match status {
+                Some(status) => Reservation::Completed {
+                    fingerprint,
+                    response: StoredResponse {
+                        status: status as u16,
+                        content_type: row.try_get("content_type")?,
+                        headers: row.try_get::<Option<Json<Vec<(String, String)>>>, _>("headers")?.map_or_else(Vec::new, |headers| headers.0),
+                        body: row.try_get::<Option<Vec<u8>>, _>("body")?.unwrap_or_default(),
+                    },
+                },
+                None => Reservation::InProgress { fingerprint },
+            });
+        }
+    }
+
+    #[tracing::instrument(name = "idempotency.complete", skip_all, fields(db.system = "postgresql"))]
+    async fn complete(&self, key: &str, response: StoredResponse, ttl: Duration) -> eyre::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET status = $2, content_type = $3, headers = $4, body = $5, expires_at = CURRENT_TIMESTAMP + $6 * INTERVAL '1 millisecond'
+            WHERE key = $1
+            "#,
+        )
+        .bind(key)
+        .bind(response.status as i16)
+        .bind(&response.content_type)
+        .bind(Json(&response.headers))
+        .bind(&response.body)
+        .bind(ttl.as_millis() as i64)
+        .execute(&*self.db)
+        .await
+        .context("failed to store idempotent response")?;
+
+        // Expired keys are only taken over when reused, the others are removed along the way
+        sqlx::query("DELETE FROM idempotency_keys WHERE expires_at <= CURRENT_TIMESTAMP")
+            .execute(&*self.db)
+            .await
+            .context("failed to remove expired idempotency keys")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "idempotency.release", skip_all, fields(db.system = "postgresql"))]
+    async fn release(&self, key: &str) -> eyre::Result<()> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE key = $1 AND status IS NULL")
+            .bind(key)
+            .execute(&*self.db)
+            .await
+            .context("failed to release idempotency key")?;
+        Ok(())
+    }
+}
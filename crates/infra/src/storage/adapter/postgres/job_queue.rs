@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eyre::Context;
+use serde_json::Value;
+use sqlx::Row;
+
+use application::jobs::{Job, JobQueuePort, NewJob};
+
+use crate::storage::adapter::postgres::Db;
+
+/// PostgreSQL implementation of the job queue, backed by the `jobs` table.
+///
+/// Jobs are claimed with `FOR UPDATE SKIP LOCKED`, so the workers of every replica claim
+/// distinct jobs without waiting for each other. A claimed job is leased by pushing back the
+/// time it is due, so it is claimed again once its lease expires unless completed or failed.
+pub struct PostgresJobQueue {
+    /// The PostgreSQL database connection pool.
+    db: Db,
+}
+
+impl PostgresJobQueue {
+    /// Creates a new `PostgresJobQueue` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl JobQueuePort for PostgresJobQueue {
+    #[tracing::instrument(name = "jobs.enqueue", skip_all, fields(db.system = "postgresql", job.kind = %job.kind))]
+    async fn enqueue(&self, job: NewJob) -> eyre::Result<()> {
+        sqlx::query("INSERT INTO jobs (kind, payload) VALUES ($1, $2)")
+            .bind(&job.kind)
+            .bind(&job.payload)
+            .execute(&*self.db)
+            .await
+            .context("failed to enqueue job")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "jobs.claim", skip_all, fields(db.system = "postgresql"))]
+    async fn claim(&self, limit: usize, lease: Duration) -> eyre::Result<Vec<Job>> {
+        let rows = sqlx::query(
+            r#"
+            UPDATE jobs
+            SET attempts = attempts + 1, run_at = CURRENT_TIMESTAMP + $2 * INTERVAL '1 millisecond'
+            WHERE id IN (
+                SELECT id
+                FROM jobs
+                WHERE run_at <= CURRENT_TIMESTAMP AND failed_at IS NULL
+                ORDER BY run_at, id
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, kind, payload, attempts
+            "#,
+        )
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .bind(lease.as_millis() as i64)
+        .fetch_all(&*self.db)
+        .await
+        .context("failed to claim jobs")?;
+
+        let mut jobs = rows
+            .into_iter()
+            .map(|row| {
+                let attempts: i32 = row.try_get("attempts")?;
+                let payload: Value = row.try_get("payload")?;
+                Ok(Job { id: row.try_get("id")?, kind: row.try_get("kind")?, payload, attempts: attempts as u32 })
+            })
+            .collect::<eyre::Result<Vec<Job>>>()?;
+        // RETURNING does not keep the order of the subquery
+        jobs.sort_by_key(|job| job.id);
+        Ok(jobs)
+    }
+
+    #[tracing::instrument(name = "jobs.complete", skip_all, fields(db.system = "postgresql", job.id = id))]
+    async fn complete(&self, id: i64) -> eyre::Result<()> {
+        sqlx::query("DELETE FROM jobs WHERE id = $1")
+            .bind(id)
+            .execute(&*self.db)
+            .await
+            .context("failed to complete job")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "jobs.fail", skip_all, fields(db.system = "postgresql", job.id = id))]
+    async fn fail(&self, id: i64, error: &str, retry_in: Option<Duration>) -> eyre::Result<()> {
+        let query = match retry_in {
+            Some(retry_in) => sqlx::query("UPDATE jobs SET last_error = $2, run_at = CURRENT_TIMESTAMP + $3 * INTERVAL '1 millisecond' WHERE id = $1")
+                .bind(id)
+                .bind(error)
+                .bind(retry_in.as_millis() as i64),
+            None => sqlx::query("UPDATE jobs SET last_error = $2, failed_at = CURRENT_TIMESTAMP WHERE id = $1").bind(id).bind(error),
+        };
+        query.execute(&*self.db).await.context("failed to record job failure")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+pub mod audit_log;
+pub mod consent_repository;
+pub mod group_repository;
+pub mod health_check;
+pub mod idempotency;
+pub mod job_queue;
+pub mod outbox;
+pub mod passkey_repository;
+pub mod retry;
+pub mod signing_keys;
+pub mod tenant_config;
+pub mod stats;
+pub mod unit_of_work;
+pub mod user_repository;
+#[cfg(feature = "testing")]
+pub mod test_db;
+
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use eyre::{Context, OptionExt};
+use sqlx::{migrate::Migrator, postgres::{PgConnectOptions, PgPoolOptions}, Connection, PgConnection, Pool, Postgres};
+use tokio::{task::JoinHandle, time::{self, Instant}};
+
+use crate::{config::Config, discovery::{DiscoveryConfig, Endpoint, RefreshSchedule, ServiceDiscoveryPort}, storage::{StorageRepositories, connect_with_retry, adapter::postgres::{consent_repository::ConsentRepository, group_repository::GroupRepository, passkey_repository::PasskeyRepository, user_repository::UserRepository}, create_repositories}};
+
+pub type Db = Arc<Pool<Postgres>>;
+
+/// Migrations from the `migrations/` directory, embedded into the binary at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("../../migrations");
+
+/// Interval between health probes of the database used by the discovery refresh task.
+const DISCOVERY_PROBE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Connects to the database at `config.database_url`, with the pool settings of `config`,
+/// retrying while the database cannot be reached.
+pub async fn db_connect(config: &Config) -> eyre::Result<Db> {
+    let options = PgConnectOptions::from_str(config.database_url.expose_secret()).context("failed to parse database url")?;
+    let pool_options = config.database_pool.apply(PgPoolOptions::new());
+
+    let pool = connect_with_retry(config.database_pool.connect_retry(), || pool_options.clone().connect_with(options.clone()))
+        .await
+        .context("failed to connect to database")?;
+    Ok(Arc::new(pool))
+}
+
+/// Connects to the database endpoint resolved through the given discovery port.
+///
+/// Credentials, database name and other options are taken from `config.database_url`,
+/// only the host and port are replaced with those of the discovered endpoints, tried in order
+/// of preference until one accepts connections.
+pub async fn db_connect_discovered(
+    config: &Config,
+    discovery: &(dyn ServiceDiscoveryPort + Send + Sync),
+    discovery_config: &DiscoveryConfig,
+) -> eyre::Result<Db> {
+    let endpoints = resolve_endpoints(discovery, &discovery_config.record).await?;
+    let candidates = endpoints
+        .into_iter()
+        .map(|endpoint| Ok((discovered_connect_options(config, &endpoint)?, endpoint)))
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    let pool_options = config.database_pool.apply(PgPoolOptions::new());
+    let (pool, endpoint) = connect_with_retry(config.database_pool.connect_retry(), || connect_first(&pool_options, &candidates))
+        .await
+        .with_context(|| format!("failed to connect to database at any endpoint of {}", discovery_config.record))?;
+    tracing::info!("connected to database at discovered endpoint {}", endpoint);
+
+    Ok(Arc::new(pool))
+}
+
+/// Spawns a background task that keeps the pool pointed at the current database endpoint.
+///
+/// The endpoint is re-resolved every `refresh_interval_secs`, and immediately after
+/// `failure_threshold` consecutive failed health probes, so the server follows failovers
+/// without a restart. Existing connections are not interrupted, new connections use the
+/// updated endpoint.
+pub fn spawn_discovery_refresh(
+    db: Db,
+    config: Config,
+    discovery: Arc<dyn ServiceDiscoveryPort + Send + Sync>,
+    discovery_config: DiscoveryConfig,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut schedule = RefreshSchedule::new(&discovery_config, Instant::now());
+
+        loop {
+            time::sleep(DISCOVERY_PROBE_INTERVAL).await;
+
+            let healthy = time::timeout(DISCOVERY_PROBE_INTERVAL, sqlx::query("SELECT 1").execute(&*db))
+                .await
+                .is_ok_and(|result| result.is_ok());
+
+            let due = schedule.probed(healthy, Instant::now());
+            if !healthy {
+                tracing::warn!("database health probe failed ({} consecutive)", schedule.consecutive_failures());
+            }
+            if !due {
+                continue;
+            }
+
+            let result = refresh_endpoint(&db, &config, discovery.as_ref(), &discovery_config).await;
+            schedule.resolved(result.is_ok(), Instant::now());
+            if let Err(e) = result {
+                tracing::error!("failed to refresh database endpoint: {:#}", e);
+            }
+        }
+    })
+}
+
+/// Applies the embedded migrations not applied to the database yet.
+///
+/// Migrations are applied in order, each in its own transaction, and the database is locked
+/// meanwhile, so replicas starting together do not apply the same migration twice.
+pub async fn run_migrations(db: &Pool<Postgres>) -> eyre::Result<()> {
+    MIGRATOR.run(db).await.context("failed to apply database migrations")?;
+
+    match MIGRATOR.iter().map(|migration| migration.version).max() {
+        Some(version) => tracing::info!("database schema is at version {}", version),
+        None => tracing::info!("no database migrations to apply"),
+    }
+    Ok(())
+}
+
+pub fn create_postgres_repositories(db: Db) -> eyre::Result<StorageRepositories<UserRepository, ConsentRepository, PasskeyRepository, GroupRepository>> {
+    create_repositories(db, |db| Ok(UserRepository::new(db)), |db| Ok(ConsentRepository::new(db)), |db| Ok(PasskeyRepository::new(db)), |db| Ok(GroupRepository::new(db)))
+}
+
+async fn refresh_endpoint(
+    db: &Db,
+    config: &Config,
+    discovery: &(dyn ServiceDiscoveryPort + Send + Sync),
+    discovery_config: &DiscoveryConfig,
+) -> eyre::Result<()> {
+    let endpoints = resolve_endpoints(discovery, &discovery_config.record).await?;
+    let (options, endpoint) = reachable_endpoint(config, endpoints).await?;
+    let current = db.connect_options();
+
+    if current.get_host() != endpoint.host || current.get_port() != endpoint.port {
+        tracing::info!("database endpoint changed to {}", endpoint);
+        db.set_connect_options(options);
+    }
+
+    Ok(())
+}
+
+/// Resolves the endpoints of `record`, most preferred first, failing if there are none.
+async fn resolve_endpoints(discovery: &(dyn ServiceDiscoveryPort + Send + Sync), record: &str) -> eyre::Result<Vec<Endpoint>> {
+    let endpoints = discovery.resolve(record).await?;
+    if endpoints.is_empty() {
+        eyre::bail!("no endpoints found for {}", record);
+    }
+    Ok(endpoints)
+}
+
+/// Connects a pool to the first of `candidates` accepting connections, returning the error of
+/// the last one when none does.
+async fn connect_first(pool_options: &PgPoolOptions, candidates: &[(PgConnectOptions, Endpoint)]) -> Result<(Pool<Postgres>, Endpoint), sqlx::Error> {
+    let mut last_error = sqlx::Error::Configuration("no database endpoints to connect to".into());
+    for (options, endpoint) in candidates {
+        match pool_options.clone().connect_with(options.clone()).await {
+            Ok(pool) => return Ok((pool, endpoint.clone())),
+            Err(e) => {
+                tracing::warn!("failed to connect to database at {}: {}", endpoint, e);
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+/// Returns the first of `endpoints` accepting connections, with its connect options, or the
+/// most preferred one when none does, so the pool keeps following the discovery record.
+async fn reachable_endpoint(config: &Config, endpoints: Vec<Endpoint>) -> eyre::Result<(PgConnectOptions, Endpoint)> {
+    let mut candidates = Vec::with_capacity(endpoints.len());
+    for endpoint in endpoints {
+        let options = discovered_connect_options(config, &endpoint)?;
+        match time::timeout(DISCOVERY_PROBE_INTERVAL, PgConnection::connect_with(&options)).await {
+            Ok(Ok(connection)) => {
+                connection.close().await.ok();
+                return Ok((options, endpoint));
+            }
+            Ok(Err(e)) => tracing::warn!("database endpoint {} refused connections: {}", endpoint, e),
+            Err(_) => tracing::warn!("database endpoint {} timed out", endpoint),
+        }
+        candidates.push((options, endpoint));
+    }
+    tracing::warn!("no database endpoint accepted connections, keeping the most preferred one");
+    candidates.into_iter().next().ok_or_eyre("no database endpoints to probe")
+}
+
+fn discovered_connect_options(config: &Config, endpoint: &Endpoint) -> eyre::Result<PgConnectOptions> {
+    let options = PgConnectOptions::from_str(config.database_url.expose_secret()).context("failed to parse database url")?;
+    Ok(options.host(&endpoint.host).port(endpoint.port))
+}
@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use eyre::Context;
+use serde_json::Value;
+use sqlx::{Executor, Postgres, Row};
+
+use application::ports::events::{EventPublisherPort, OutboxPort};
+use application::ports::messaging::MessagePublisherPort;
+use domain::user::event::UserEvent;
+
+use crate::messaging::EventFormat;
+use crate::outbox::{event_data, outbox_message};
+use crate::storage::adapter::postgres::Db;
+
+/// Key of the advisory lock held while dispatching the outbox, so a single replica dispatches
+/// at a time and events are published in order.
+const DISPATCH_LOCK_KEY: i64 = 0x6f7574626f78;
+
+/// PostgreSQL implementation of the outbox, backed by the `event_outbox` table.
+pub struct PostgresOutbox {
+    /// The PostgreSQL database connection pool.
+    db: Db,
+    /// Encoding of the dispatched events; they are recorded as JSON either way.
+    format: EventFormat,
+}
+
+impl PostgresOutbox {
+    /// Creates a new `PostgresOutbox` instance, dispatching events as JSON.
+    pub fn new(db: Db) -> Self {
+        Self { db, format: EventFormat::Json }
+    }
+
+    /// Dispatches events in `format`, [`EventFormat::Json`] or [`EventFormat::Protobuf`].
+    pub fn with_format(mut self, format: EventFormat) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for PostgresOutbox {
+    #[tracing::instrument(name = "outbox.publish", skip_all, fields(db.system = "postgresql", event.type = event.event_type(), user.id = %event.user_id()))]
+    async fn publish(&self, event: UserEvent) -> eyre::Result<()> {
+        record_event(&*self.db, &event).await
+    }
+}
+
+/// Records `event` in the outbox through `executor`, the pool or a transaction.
+pub(crate) async fn record_event<'c, E>(executor: E, event: &UserEvent) -> eyre::Result<()>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO event_outbox (event_type, aggregate_id, payload)
+        VALUES ($1, $2, $3)
+        "#,
+    )
+    .bind(event.event_type())
+    .bind(event.user_id())
+    .bind(event_data(event))
+    .execute(executor)
+    .await
+    .context("failed to record event in the outbox")?;
+    Ok(())
+}
+
+#[async_trait]
+impl OutboxPort for PostgresOutbox {
+    #[tracing::instrument(name = "outbox.dispatch", skip_all, fields(db.system = "postgresql"))]
+    async fn dispatch(&self, publisher: &(dyn MessagePublisherPort + Send + Sync), limit: usize) -> eyre::Result<usize> {
+        let mut tx = self.db.begin().await.context("failed to begin outbox transaction")?;
+
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_xact_lock($1)")
+            .bind(DISPATCH_LOCK_KEY)
+            .fetch_one(&mut *tx)
+            .await
+            .context("failed to lock the outbox")?;
+        if !locked {
+            // Another replica is dispatching
+            return Ok(0);
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, event_type, payload, created_at
+            FROM event_outbox
+            ORDER BY id
+            LIMIT $1
+            "#,
+        )
+        .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+        .fetch_all(&mut *tx)
+        .await
+        .context("failed to read the outbox")?;
+
+        let mut published = Vec::with_capacity(rows.len());
+        let mut failure = None;
+        for row in rows {
+            let id: i64 = row.try_get("id")?;
+            let event_type: String = row.try_get("event_type")?;
+            let payload: Value = row.try_get("payload")?;
+            let created_at: DateTime<Utc> = row.try_get("created_at")?;
+
+            let result = match outbox_message(self.format, id, &event_type, created_at, payload) {
+                Ok(message) => publisher.publish(&event_type, message).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                failure = Some(e.wrap_err(format!("failed to publish outbox event {}", id)));
+                break;
+            }
+            published.push(id);
+        }
+
+        if !published.is_empty() {
+            sqlx::query("DELETE FROM event_outbox WHERE id = ANY($1)")
+                .bind(&published)
+                .execute(&mut *tx)
+                .await
+                .context("failed to remove published events from the outbox")?;
+        }
+        tx.commit().await.context("failed to commit outbox transaction")?;
+
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(published.len()),
+        }
+    }
+}
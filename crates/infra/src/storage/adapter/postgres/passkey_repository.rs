@@ -0,0 +1,152 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, Row};
+
+use domain::outcome::record_outcome;
+use domain::passkey::{error::PasskeyDomainError, model::{Passkey, RegisterPasskey}, repository::PasskeyRepositoryPort};
+use domain::user::model::UserId;
+
+use crate::storage::adapter::postgres::Db;
+use crate::storage::adapter::sql_error::{is_foreign_key_violation, is_unique_violation};
+
+/// PostgreSQL implementation of the passkey repository, backed by the `user_passkeys` table.
+pub struct PasskeyRepository {
+    /// The PostgreSQL database connection pool.
+    db: Db,
+}
+
+impl PasskeyRepository {
+    /// Creates a new `PasskeyRepository` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl PasskeyRepositoryPort for PasskeyRepository {
+    #[tracing::instrument(name = "passkey_repository.add_passkey", skip_all, fields(db.system = "postgresql", user.id = %passkey.user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn add_passkey(&self, passkey: RegisterPasskey) -> Result<Passkey, PasskeyDomainError> {
+        record_outcome(async {
+            sqlx::query(
+                r#"
+                INSERT INTO user_passkeys (id, user_id, name, credential)
+                VALUES ($1, $2, $3, $4)
+                RETURNING id, user_id, name, credential, created_at, last_used_at
+                "#,
+            )
+            .bind(&passkey.id)
+            .bind(passkey.user_id)
+            .bind(&passkey.name)
+            .bind(&passkey.credential)
+            .fetch_one(&*self.db)
+            .await
+            .and_then(passkey_from_row)
+            .map_err(|e| {
+                if is_foreign_key_violation(&e) {
+                    PasskeyDomainError::UserNotFound
+                } else if is_unique_violation(&e) {
+                    PasskeyDomainError::PasskeyAlreadyExists
+                } else {
+                    tracing::error!("Failed to add passkey: {}", e);
+                    PasskeyDomainError::PasskeyRegistrationFailed
+                }
+            })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "passkey_repository.list_passkeys", skip_all, fields(db.system = "postgresql", user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_passkeys(&self, user_id: UserId) -> Result<Vec<Passkey>, PasskeyDomainError> {
+        record_outcome(async {
+            sqlx::query(
+                r#"
+                SELECT id, user_id, name, credential, created_at, last_used_at
+                FROM user_passkeys
+                WHERE user_id = $1
+                ORDER BY created_at, id
+                "#,
+            )
+            .bind(user_id)
+            .fetch_all(&*self.db)
+            .await
+            .and_then(|rows| rows.into_iter().map(passkey_from_row).collect())
+            .map_err(|e| {
+                tracing::error!("Failed to list passkeys: {}", e);
+                PasskeyDomainError::PasskeyListFailed
+            })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "passkey_repository.record_passkey_use", skip_all, fields(db.system = "postgresql", user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn record_passkey_use(&self, user_id: UserId, id: String, credential: String) -> Result<(), PasskeyDomainError> {
+        record_outcome(async {
+            let rows_affected = sqlx::query(
+                r#"
+                UPDATE user_passkeys
+                SET credential = $1, last_used_at = CURRENT_TIMESTAMP
+                WHERE user_id = $2 AND id = $3
+                "#,
+            )
+            .bind(&credential)
+            .bind(user_id)
+            .bind(&id)
+            .execute(&*self.db)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to record passkey use: {}", e);
+                PasskeyDomainError::PasskeyUpdateFailed
+            })?
+            .rows_affected();
+
+            if rows_affected == 0 {
+                Err(PasskeyDomainError::PasskeyNotFound)
+            } else {
+                Ok(())
+            }
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "passkey_repository.delete_passkey", skip_all, fields(db.system = "postgresql", user.id = %user_id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn delete_passkey(&self, user_id: UserId, id: String) -> Result<(), PasskeyDomainError> {
+        record_outcome(async {
+            let rows_affected = sqlx::query(
+                r#"
+                DELETE FROM user_passkeys
+                WHERE user_id = $1 AND id = $2
+                "#,
+            )
+            .bind(user_id)
+            .bind(&id)
+            .execute(&*self.db)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to delete passkey: {}", e);
+                PasskeyDomainError::PasskeyDeletionFailed
+            })?
+            .rows_affected();
+
+            if rows_affected == 0 {
+                Err(PasskeyDomainError::PasskeyNotFound)
+            } else {
+                Ok(())
+            }
+        }
+        .await)
+    }
+}
+
+/// Maps a `user_passkeys` table row to the domain `Passkey` model.
+fn passkey_from_row(row: PgRow) -> Result<Passkey, sqlx::Error> {
+    let created_at: DateTime<Utc> = row.try_get("created_at")?;
+    let last_used_at: Option<DateTime<Utc>> = row.try_get("last_used_at")?;
+    Ok(Passkey {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        name: row.try_get("name")?,
+        credential: row.try_get("credential")?,
+        created_at: created_at.into(),
+        last_used_at: last_used_at.map(Into::into),
+    })
+}
@@ -0,0 +1,66 @@
+//! Retries of statements failing with transient PostgreSQL errors.
+//!
+//! Concurrent upserts and row locks may be aborted by PostgreSQL with a serialization failure
+//! or a deadlock, which succeed once run again. Statements run on a pooled connection are
+//! retried here, after a random delay so the transactions that collided do not collide again.
+//! Statements of a unit of work are not: the failure aborts the whole transaction.
+
+use std::future::Future;
+use std::time::Duration;
+
+/// SQLSTATE of a transaction aborted because it could not be serialized with concurrent ones.
+pub const SERIALIZATION_FAILURE: &str = "40001";
+
+/// SQLSTATE of a transaction aborted to break a deadlock.
+pub const DEADLOCK_DETECTED: &str = "40P01";
+
+/// Maximum number of attempts of a statement failing with a transient error, including the first.
+pub const MAX_ATTEMPTS: u32 = 4;
+
+/// Bound of the delay before the first retry, doubled for every following one.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(20);
+
+/// Bound of the delay before any retry.
+const MAX_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Returns whether `e` is a serialization failure or a deadlock, so running the statement
+/// again may succeed.
+pub fn is_transient(e: &sqlx::Error) -> bool {
+    e.as_database_error()
+        .and_then(|e| e.code())
+        .is_some_and(|code| code == SERIALIZATION_FAILURE || code == DEADLOCK_DETECTED)
+}
+
+/// Returns the bound of the delay before the retry following the given (1-based) failed
+/// attempt: exponential, capped at `MAX_BACKOFF`.
+pub fn backoff_bound(attempt: u32) -> Duration {
+    INITIAL_BACKOFF.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1))).min(MAX_BACKOFF)
+}
+
+/// Runs `operation` until it succeeds, fails with an error other than a transient one, or
+/// makes `MAX_ATTEMPTS` attempts, sleeping a random duration up to [`backoff_bound`] between
+/// attempts.
+///
+/// The number of retries is recorded in the `db.retries` field of the current span, which
+/// operations using this declare as empty.
+pub async fn retry_transient<T, F, Fut>(mut operation: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Err(e) if is_transient(&e) && attempt < MAX_ATTEMPTS => {
+                let backoff = backoff_bound(attempt).mul_f64(rand::random::<f64>());
+                tracing::warn!("transient database failure (attempt {} of {}), retrying in {:?}: {}", attempt, MAX_ATTEMPTS, backoff, e);
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            result => {
+                tracing::Span::current().record("db.retries", attempt - 1);
+                return result;
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use eyre::Context;
+use sqlx::Row;
+
+use crate::auth::signing_keys::{SigningKeyStore, StoredSigningKey};
+use crate::storage::adapter::postgres::Db;
+
+/// Key of the advisory lock held while adding a key, so replicas rotating the keys
+/// concurrently add a single one.
+const ROTATION_LOCK_KEY: i64 = 0x6a776b73;
+
+/// PostgreSQL storage of the signing keys, backed by the `jwt_signing_keys` table.
+pub struct PostgresSigningKeyStore {
+    /// The PostgreSQL database connection pool.
+    db: Db,
+}
+
+impl PostgresSigningKeyStore {
+    /// Creates a new `PostgresSigningKeyStore` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl SigningKeyStore for PostgresSigningKeyStore {
+    #[tracing::instrument(name = "signing_keys.list", skip_all, fields(db.system = "postgresql"))]
+    async fn list(&self) -> eyre::Result<Vec<StoredSigningKey>> {
+        let rows = sqlx::query("SELECT kid, created_at, encrypted_key FROM jwt_signing_keys ORDER BY created_at, kid")
+            .fetch_all(&*self.db)
+            .await
+            .context("failed to read signing keys")?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(StoredSigningKey {
+                    kid: row.try_get("kid")?,
+                    created_at: row.try_get("created_at")?,
+                    encrypted_key: row.try_get("encrypted_key")?,
+                })
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(name = "signing_keys.add", skip_all, fields(db.system = "postgresql", key.id = %key.kid))]
+    async fn add(&self, key: StoredSigningKey, created_after: DateTime<Utc>) -> eyre::Result<bool> {
+        let mut tx = self.db.begin().await.context("failed to begin signing key transaction")?;
+
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(ROTATION_LOCK_KEY)
+            .execute(&mut *tx)
+            .await
+            .context("failed to lock the signing keys")?;
+
+        let rotated: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM jwt_signing_keys WHERE created_at > $1)")
+            .bind(created_after)
+            .fetch_one(&mut *tx)
+            .await
+            .context("failed to read signing keys")?;
+        if rotated {
+            return Ok(false);
+        }
+
+        sqlx::query("INSERT INTO jwt_signing_keys (kid, created_at, encrypted_key) VALUES ($1, $2, $3)")
+            .bind(&key.kid)
+            .bind(key.created_at)
+            .bind(&key.encrypted_key)
+            .execute(&mut *tx)
+            .await
+            .context("failed to insert signing key")?;
+        tx.commit().await.context("failed to commit signing key transaction")?;
+        Ok(true)
+    }
+
+    #[tracing::instrument(name = "signing_keys.remove", skip_all, fields(db.system = "postgresql"))]
+    async fn remove(&self, kids: &[String]) -> eyre::Result<()> {
+        sqlx::query("DELETE FROM jwt_signing_keys WHERE kid = ANY($1)")
+            .bind(kids)
+            .execute(&*self.db)
+            .await
+            .context("failed to remove signing keys")?;
+        Ok(())
+    }
+}
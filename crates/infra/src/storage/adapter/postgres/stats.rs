@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use eyre::Context;
+use sqlx::Row;
+
+use application::ports::stats::{DailyUserStats, UserStatsPort};
+
+use crate::storage::adapter::postgres::{retry::retry_transient, Db};
+
+/// PostgreSQL storage of the daily statistics of the users, backed by the `user_daily_stats`
+/// table and computed from the `users` table.
+pub struct PostgresUserStats {
+    /// The PostgreSQL database connection pool.
+    db: Db,
+}
+
+impl PostgresUserStats {
+    /// Creates a new `PostgresUserStats` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl UserStatsPort for PostgresUserStats {
+    #[tracing::instrument(name = "stats.project", skip_all, fields(db.system = "postgresql", db.retries = tracing::field::Empty))]
+    async fn project(&self, today: NaiveDate) -> eyre::Result<u64> {
+        // Days are bounded in UTC, whatever the time zone of the session
+        let result = retry_transient(|| {
+            sqlx::query(
+                r#"
+                WITH days AS (
+                    SELECT (d AT TIME ZONE 'UTC') AS day_start, ((d + INTERVAL '1 day') AT TIME ZONE 'UTC') AS day_end, d::date AS day
+                    FROM generate_series(
+                        COALESCE(
+                            (SELECT MAX(day) FROM user_daily_stats),
+                            (SELECT MIN(created_at AT TIME ZONE 'UTC')::date FROM users),
+                            $1::date
+                        )::timestamp,
+                        $1::date::timestamp,
+                        INTERVAL '1 day'
+                    ) AS d
+                )
+                INSERT INTO user_daily_stats (day, signups, deletions, active_users, computed_at)
+                SELECT
+                    day,
+                    (SELECT COUNT(*) FROM users WHERE created_at >= day_start AND created_at < day_end),
+                    (SELECT COUNT(*) FROM users WHERE deleted_at >= day_start AND deleted_at < day_end),
+                    (SELECT COUNT(*) FROM users WHERE created_at < day_end AND (deleted_at IS NULL OR deleted_at >= day_end)),
+                    CURRENT_TIMESTAMP
+                FROM days
+                ON CONFLICT (day) DO UPDATE SET
+                    signups = EXCLUDED.signups,
+                    deletions = EXCLUDED.deletions,
+                    active_users = EXCLUDED.active_users,
+                    computed_at = EXCLUDED.computed_at
+                "#,
+            )
+            .bind(today)
+            .execute(&*self.db)
+        })
+        .await
+        .context("failed to project the daily statistics of users")?;
+
+        Ok(result.rows_affected())
+    }
+
+    #[tracing::instrument(name = "stats.daily_stats", skip_all, fields(db.system = "postgresql"))]
+    async fn daily_stats(&self, from: NaiveDate, to: NaiveDate) -> eyre::Result<Vec<DailyUserStats>> {
+        let rows = sqlx::query("SELECT day, signups, deletions, active_users FROM user_daily_stats WHERE day BETWEEN $1 AND $2 ORDER BY day")
+            .bind(from)
+            .bind(to)
+            .fetch_all(&*self.db)
+            .await
+            .context("failed to read the daily statistics of users")?;
+
+        rows.into_iter()
+            .map(|row| {
+                let count = |column: &str| -> eyre::Result<u64> { Ok(row.try_get::<i64, _>(column)?.try_into()?) };
+                Ok(DailyUserStats {
+                    day: row.try_get("day")?,
+                    signups: count("signups")?,
+                    deletions: count("deletions")?,
+                    active_users: count("active_users")?,
+                })
+            })
+            .collect()
+    }
+}
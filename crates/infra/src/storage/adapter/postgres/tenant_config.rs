@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use eyre::Context;
+use sqlx::types::Json;
+use sqlx::Row;
+
+use application::ports::tenant::{TenantBranding, TenantConfigPort, TenantSettings};
+
+use crate::storage::adapter::postgres::{retry::retry_transient, Db};
+
+/// PostgreSQL storage of the tenant settings, backed by the `tenant_settings` table.
+pub struct PostgresTenantConfig {
+    /// The PostgreSQL database connection pool.
+    db: Db,
+}
+
+impl PostgresTenantConfig {
+    /// Creates a new `PostgresTenantConfig` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl TenantConfigPort for PostgresTenantConfig {
+    #[tracing::instrument(name = "tenant_config.get", skip_all, fields(db.system = "postgresql"))]
+    async fn get(&self, tenant: &str) -> eyre::Result<Option<TenantSettings>> {
+        let row = sqlx::query("SELECT rate_limit_requests, feature_flags, webhook_secret, branding FROM tenant_settings WHERE tenant_id = $1")
+            .bind(tenant)
+            .fetch_optional(&*self.db)
+            .await
+            .context("failed to read tenant settings")?;
+        let Some(row) = row else { return Ok(None) };
+
+        let rate_limit_requests: Option<i32> = row.try_get("rate_limit_requests")?;
+        let Json(feature_flags): Json<BTreeMap<String, bool>> = row.try_get("feature_flags")?;
+        let Json(branding): Json<TenantBranding> = row.try_get("branding")?;
+        Ok(Some(TenantSettings {
+            rate_limit_requests: rate_limit_requests.map(|requests| requests as u32),
+            feature_flags,
+            webhook_secret: row.try_get("webhook_secret")?,
+            branding,
+        }))
+    }
+
+    #[tracing::instrument(name = "tenant_config.put", skip_all, fields(db.system = "postgresql", db.retries = tracing::field::Empty))]
+    async fn put(&self, tenant: &str, settings: TenantSettings) -> eyre::Result<()> {
+        let rate_limit_requests = settings
+            .rate_limit_requests
+            .map(i32::try_from)
+            .transpose()
+            .context("tenant rate limit is out of range")?;
+        retry_transient(|| {
+            sqlx::query(
+                r#"
+                INSERT INTO tenant_settings (tenant_id, rate_limit_requests, feature_flags, webhook_secret, branding)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (tenant_id) DO UPDATE
+                    SET rate_limit_requests = EXCLUDED.rate_limit_requests, feature_flags = EXCLUDED.feature_flags,
+                        webhook_secret = EXCLUDED.webhook_secret, branding = EXCLUDED.branding, updated_at = CURRENT_TIMESTAMP
+                "#,
+            )
+            .bind(tenant)
+            .bind(rate_limit_requests)
+            .bind(Json(&settings.feature_flags))
+            .bind(&settings.webhook_secret)
+            .bind(Json(&settings.branding))
+            .execute(&*self.db)
+        })
+        .await
+        .context("failed to save tenant settings")?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "tenant_config.delete", skip_all, fields(db.system = "postgresql"))]
+    async fn delete(&self, tenant: &str) -> eyre::Result<bool> {
+        let deleted = sqlx::query("DELETE FROM tenant_settings WHERE tenant_id = $1")
+            .bind(tenant)
+            .execute(&*self.db)
+            .await
+            .context("failed to delete tenant settings")?
+            .rows_affected();
+        Ok(deleted > 0)
+    }
+}
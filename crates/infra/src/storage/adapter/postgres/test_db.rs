@@ -0,0 +1,139 @@
+use std::{env, str::FromStr, sync::Arc};
+
+use eyre::Context;
+use sqlx::{Connection, Executor, PgConnection, postgres::{PgConnectOptions, PgPoolOptions}};
+use testcontainers_modules::{postgres::Postgres, testcontainers::{ContainerAsync, ImageExt, runners::AsyncRunner}};
+use uuid::Uuid;
+
+use crate::storage::adapter::postgres::{Db, MIGRATOR};
+
+const TEST_DATABASE_URL_KEY: &str = "TEST_DATABASE_URL";
+
+const DATABASE_URL_KEY: &str = "DATABASE_URL";
+
+/// Tag of the `postgres` image the tests run on without a database server.
+const POSTGRES_IMAGE_TAG: &str = "16-alpine";
+
+/// Port PostgreSQL listens on in its container.
+const POSTGRES_PORT: u16 = 5432;
+
+/// A throwaway PostgreSQL database for a single test.
+///
+/// Creates a uniquely named database on the server referenced by `TEST_DATABASE_URL`
+/// (falling back to `DATABASE_URL`), applies the embedded migrations and drops the
+/// database again when dropped, so integration tests can run in parallel without
+/// sharing state.
+///
+/// When neither variable is set, the server is a PostgreSQL container started with
+/// testcontainers for the test, and removed with the database.
+pub struct TestDb {
+    db: Db,
+    name: String,
+    server_url: String,
+    admin_options: PgConnectOptions,
+    container: Option<ContainerAsync<Postgres>>,
+}
+
+impl TestDb {
+    /// Provisions a new migrated database.
+    pub async fn new() -> eyre::Result<Self> {
+        let (url, container) = match env::var(TEST_DATABASE_URL_KEY).or_else(|_| env::var(DATABASE_URL_KEY)) {
+            Ok(url) => (url, None),
+            Err(_) => {
+                let (url, container) = start_container().await?;
+                (url, Some(container))
+            }
+        };
+        let admin_options = PgConnectOptions::from_str(&url).context("failed to parse test database url")?;
+
+        let name = format!("test_{}", Uuid::new_v4().simple());
+        let mut admin = PgConnection::connect_with(&admin_options)
+            .await
+            .context("failed to connect to test database server")?;
+        admin
+            .execute(format!(r#"CREATE DATABASE "{}" TEMPLATE template0 ENCODING 'UTF8'"#, name).as_str())
+            .await
+            .with_context(|| format!("failed to create test database {}", name))?;
+        admin.close().await.ok();
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect_with(admin_options.clone().database(&name))
+            .await
+            .with_context(|| format!("failed to connect to test database {}", name))?;
+        MIGRATOR
+            .run(&pool)
+            .await
+            .with_context(|| format!("failed to migrate test database {}", name))?;
+
+        Ok(Self {
+            db: Arc::new(pool),
+            name,
+            server_url: url,
+            admin_options,
+            container,
+        })
+    }
+
+    /// Returns the connection pool of the test database.
+    pub fn db(&self) -> Db {
+        self.db.clone()
+    }
+
+    /// Returns the name of the test database.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the URL of the server the test database was created on.
+    pub fn server_url(&self) -> &str {
+        &self.server_url
+    }
+}
+
+/// Starts a PostgreSQL container, returning the URL of its server.
+async fn start_container() -> eyre::Result<(String, ContainerAsync<Postgres>)> {
+    let container = Postgres::default()
+        .with_tag(POSTGRES_IMAGE_TAG)
+        .start()
+        .await
+        .context("failed to start PostgreSQL container, is Docker running? (or set TEST_DATABASE_URL)")?;
+    let host = container.get_host().await.context("failed to get host of PostgreSQL container")?;
+    let port = container
+        .get_host_port_ipv4(POSTGRES_PORT)
+        .await
+        .context("failed to get port of PostgreSQL container")?;
+
+    Ok((format!("postgres://postgres:postgres@{}:{}/postgres", host, port), container))
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let name = self.name.clone();
+        let admin_options = self.admin_options.clone();
+        let container = self.container.take();
+
+        // `Drop` can't await and may run inside a runtime, so the cleanup runs on its own thread.
+        let cleanup = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+            runtime.block_on(async {
+                // The database goes with the container
+                if let Some(container) = container {
+                    return container.rm().await.context("failed to remove PostgreSQL container");
+                }
+                let mut admin = PgConnection::connect_with(&admin_options).await?;
+                admin
+                    .execute(format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#, name).as_str())
+                    .await?;
+                admin.close().await?;
+                eyre::Ok(())
+            })
+        });
+
+        match cleanup.join() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!("failed to drop test database {}: {:#}", self.name, e),
+            Err(_) => tracing::warn!("failed to drop test database {}: cleanup panicked", self.name),
+        }
+    }
+}
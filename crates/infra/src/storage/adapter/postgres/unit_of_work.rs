@@ -0,0 +1,157 @@
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use eyre::Context;
+use sqlx::{pool::PoolConnection, PgConnection, Postgres, Transaction};
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use application::ports::audit::AuditLogPort;
+use application::ports::events::EventPublisherPort;
+use application::ports::unit_of_work::{IsolationLevel, TransactionPort, UnitOfWorkPort};
+use domain::user::{event::UserEvent, repository::UserRepositoryPort};
+
+use crate::storage::adapter::postgres::{audit_log::PostgresAuditLog, outbox::record_event, retry::retry_transient, user_repository::UserRepository, Db};
+
+/// Transaction shared by the repositories of a unit of work, each query locking it in turn.
+pub(crate) type SharedTransaction = Arc<Mutex<Transaction<'static, Postgres>>>;
+
+/// Where repositories run their queries.
+pub(crate) enum Connection {
+    /// A connection of the pool, acquired for each operation.
+    Pool(Db),
+    /// The transaction of a unit of work.
+    Transaction(SharedTransaction),
+}
+
+impl Connection {
+    /// Acquires a connection of the pool, or waits for the transaction to be free.
+    pub(crate) async fn acquire(&self) -> Result<ConnectionGuard, sqlx::Error> {
+        match self {
+            Connection::Pool(db) => Ok(ConnectionGuard::Pool(db.acquire().await?)),
+            Connection::Transaction(transaction) => Ok(ConnectionGuard::Transaction(transaction.clone().lock_owned().await)),
+        }
+    }
+
+    /// Runs `operation`, retried while it fails with a transient error on the pool. In a
+    /// transaction the failure aborts the transaction, so it is returned at once.
+    pub(crate) async fn retry_transient<T, F, Fut>(&self, mut operation: F) -> Result<T, sqlx::Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        match self {
+            Connection::Pool(_) => retry_transient(operation).await,
+            Connection::Transaction(_) => operation().await,
+        }
+    }
+}
+
+/// A connection acquired by [`Connection::acquire`], released when dropped.
+pub(crate) enum ConnectionGuard {
+    Pool(PoolConnection<Postgres>),
+    Transaction(OwnedMutexGuard<Transaction<'static, Postgres>>),
+}
+
+impl Deref for ConnectionGuard {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        match self {
+            ConnectionGuard::Pool(connection) => connection,
+            ConnectionGuard::Transaction(transaction) => transaction,
+        }
+    }
+}
+
+impl DerefMut for ConnectionGuard {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        match self {
+            ConnectionGuard::Pool(connection) => connection,
+            ConnectionGuard::Transaction(transaction) => transaction,
+        }
+    }
+}
+
+/// PostgreSQL implementation of the unit of work, running the operations of each transaction
+/// on a single connection of the pool.
+pub struct PostgresUnitOfWork {
+    /// The PostgreSQL database connection pool.
+    db: Db,
+}
+
+impl PostgresUnitOfWork {
+    /// Creates a new `PostgresUnitOfWork` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl UnitOfWorkPort for PostgresUnitOfWork {
+    async fn begin(&self, isolation: IsolationLevel) -> eyre::Result<Box<dyn TransactionPort + Send + Sync>> {
+        let mut transaction = self.db.begin().await.context("failed to begin transaction")?;
+        // Set explicitly rather than relying on the default of the database
+        sqlx::query(set_isolation_statement(isolation)).execute(&mut *transaction).await.context("failed to set transaction isolation level")?;
+        let transaction = Arc::new(Mutex::new(transaction));
+        Ok(Box::new(PostgresTransaction {
+            users: UserRepository::in_transaction(transaction.clone()),
+            events: TransactionOutbox(transaction.clone()),
+            audit_log: PostgresAuditLog::in_transaction(transaction.clone()),
+            transaction,
+        }))
+    }
+}
+
+/// Returns the statement setting the isolation level of the current transaction, to run
+/// before any other statement of it.
+fn set_isolation_statement(isolation: IsolationLevel) -> &'static str {
+    match isolation {
+        IsolationLevel::ReadCommitted => "SET TRANSACTION ISOLATION LEVEL READ COMMITTED",
+        IsolationLevel::Serializable => "SET TRANSACTION ISOLATION LEVEL SERIALIZABLE",
+    }
+}
+
+/// A transaction of [`PostgresUnitOfWork`], rolled back when dropped without being committed.
+struct PostgresTransaction {
+    users: UserRepository,
+    events: TransactionOutbox,
+    audit_log: PostgresAuditLog,
+    transaction: SharedTransaction,
+}
+
+#[async_trait]
+impl TransactionPort for PostgresTransaction {
+    fn users(&self) -> &(dyn UserRepositoryPort + Send + Sync) {
+        &self.users
+    }
+
+    fn events(&self) -> &(dyn EventPublisherPort + Send + Sync) {
+        &self.events
+    }
+
+    fn audit_log(&self) -> &(dyn AuditLogPort + Send + Sync) {
+        &self.audit_log
+    }
+
+    async fn commit(self: Box<Self>) -> eyre::Result<()> {
+        let PostgresTransaction { users, events, audit_log, transaction } = *self;
+        // The repositories hold the only other references to the transaction
+        drop((users, events, audit_log));
+        let transaction = Arc::try_unwrap(transaction).map_err(|_| eyre::eyre!("transaction is still in use"))?.into_inner();
+        transaction.commit().await.context("failed to commit transaction")
+    }
+}
+
+/// The outbox, recording events in a transaction.
+struct TransactionOutbox(SharedTransaction);
+
+#[async_trait]
+impl EventPublisherPort for TransactionOutbox {
+    #[tracing::instrument(name = "outbox.publish", skip_all, fields(db.system = "postgresql", event.type = event.event_type(), user.id = %event.user_id()))]
+    async fn publish(&self, event: UserEvent) -> eyre::Result<()> {
+        let mut transaction = self.0.lock().await;
+        record_event(&mut **transaction, &event).await
+    }
+}
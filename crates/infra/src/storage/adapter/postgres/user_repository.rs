@@ -0,0 +1,601 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use sqlx::{postgres::PgRow, Postgres, QueryBuilder, Row};
+
+use domain::{collation, outcome::record_outcome, user::{error::UserDomainError, model::{AgeBucket, CreateUser, Email, ListUsers, PasswordHash, Role, SortDirection, UpdateUser, User, UserCredentials, UserFacets, UserFilter, UserId, UserPage, UserPosition, UserSortField, UserSortKey, UserStatus, MAX_EMAIL_DOMAIN_FACETS}, repository::UserRepositoryPort}};
+
+use crate::storage::adapter::sql_error::user_error;
+use crate::storage::adapter::postgres::{unit_of_work::{Connection, SharedTransaction}, Db};
+
+/// Users inserted per statement by [`UserRepository::create_users`], 5 parameters each, well
+/// under the 65535 parameters of a statement.
+const CREATE_USERS_BATCH_SIZE: usize = 500;
+
+/// PostgreSQL implementation of the user repository.
+///
+/// This repository provides data access operations for users using SQLx and PostgreSQL.
+/// It implements the [`UserRepositoryTrait`] and handles all CRUD operations for the `users` table.
+pub struct UserRepository {
+    /// The connection pool, or the transaction of a unit of work.
+    connection: Connection,
+}
+
+impl UserRepository {
+    /// Creates a new `UserRepository` instance.
+    pub fn new(db: Db) -> Self {
+        Self { connection: Connection::Pool(db) }
+    }
+
+    /// Creates a `UserRepository` running its queries in `transaction`.
+    pub(crate) fn in_transaction(transaction: SharedTransaction) -> Self {
+        Self { connection: Connection::Transaction(transaction) }
+    }
+
+    /// Inserts `batch` in a single statement. Users conflicting with a stored one are skipped
+    /// and fail with `UserAlreadyExists`, while a failing statement fails every user of `batch`.
+    async fn insert_users(&self, batch: Vec<(UserId, CreateUser, Option<PasswordHash>)>) -> Vec<Result<User, UserDomainError>> {
+        let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserCreationFailed);
+        // Concurrent inserts of the same ids may deadlock, the statement is then run again
+        let rows = &batch;
+        let inserted: Result<HashSet<UserId>, UserDomainError> = self
+            .connection
+            .retry_transient(|| async move {
+                let mut connection = self.connection.acquire().await?;
+
+                let mut query = QueryBuilder::<Postgres>::new("INSERT INTO users (id, name, email, age, password_hash) ");
+                query.push_values(rows, |mut row, (id, user, password_hash)| {
+                    row.push_bind(*id)
+                        .push_bind(&user.name)
+                        .push_bind(&user.email)
+                        .push_bind(user.age as i16)
+                        .push_bind(password_hash.as_ref().map(PasswordHash::as_str));
+                });
+                query.push(" ON CONFLICT DO NOTHING RETURNING id");
+
+                let ids = query.build_query_scalar::<UserId>().fetch_all(&mut *connection).await?;
+                Ok(ids.into_iter().collect())
+            })
+            .await
+            .map_err(failed);
+
+        batch
+            .into_iter()
+            .map(|(id, user, _)| match &inserted {
+                Ok(ids) if ids.contains(&id) => Ok(User::new(id, user.name, user.email, user.age)),
+                Ok(_) => Err(UserDomainError::UserAlreadyExists),
+                Err(e) => Err(e.clone()),
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl UserRepositoryPort for UserRepository {
+    #[tracing::instrument(name = "user_repository.create_user", skip_all, fields(db.system = "postgresql", user.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn create_user(&self, user: CreateUser, password_hash: Option<PasswordHash>) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let id = UserId::generate();
+            tracing::Span::current().record("user.id", tracing::field::display(id));
+
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserCreationFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            // Better to use sqlx::query! macro for compile-time verification of the schema and query. Used functions because of absence of installed locally db.
+            sqlx::query(
+                r#"
+                INSERT INTO users (id, name, email, age, password_hash)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(id)
+            .bind(&user.name)
+            .bind(&user.email)
+            .bind(user.age as i16)
+            .bind(password_hash.as_ref().map(PasswordHash::as_str))
+            .execute(&mut *connection)
+            .await
+            .map_err(failed)?;
+
+            Ok(User::new(id, user.name, user.email, user.age))
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.create_users", skip_all, fields(db.system = "postgresql", users = users.len(), db.retries = tracing::field::Empty))]
+    async fn create_users(&self, users: Vec<(CreateUser, Option<PasswordHash>)>) -> Vec<Result<User, UserDomainError>> {
+        let mut created = Vec::with_capacity(users.len());
+        let mut users = users.into_iter().peekable();
+        while users.peek().is_some() {
+            let batch = users.by_ref().take(CREATE_USERS_BATCH_SIZE).map(|(user, password_hash)| (UserId::generate(), user, password_hash)).collect();
+            created.extend(self.insert_users(batch).await);
+        }
+        created
+    }
+
+    #[tracing::instrument(name = "user_repository.get_user", skip_all, fields(db.system = "postgresql", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserReadFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            // Better to use sqlx::query! macro for compile-time verification of the schema and query. Used functions because of absence of installed locally db.
+            let row = sqlx::query(
+                r#"
+                SELECT id, name, email, age, legal_hold, role, version
+                FROM users
+                WHERE id = $1 AND deleted_at IS NULL
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&mut *connection)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(failed)?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.get_user_by_email", skip_all, fields(db.system = "postgresql", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user_by_email(&self, email: Email) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserReadFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            // The `email` column uses the `ignore_accent_case` collation, so the comparison below
+            // is case- and accent-insensitive without normalizing the input.
+            let row = sqlx::query(
+                r#"
+                SELECT id, name, email, age, legal_hold, role, version
+                FROM users
+                WHERE email = $1 AND deleted_at IS NULL
+                "#,
+            )
+            .bind(&email)
+            .fetch_optional(&mut *connection)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(failed)?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.get_user_credentials", skip_all, fields(db.system = "postgresql", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user_credentials(&self, email: Email) -> Result<UserCredentials, UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserReadFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            let row = sqlx::query(
+                r#"
+                SELECT id, name, email, age, legal_hold, role, version, password_hash
+                FROM users
+                WHERE email = $1 AND deleted_at IS NULL
+                "#,
+            )
+            .bind(&email)
+            .fetch_optional(&mut *connection)
+            .await
+            .and_then(|row| row.map(credentials_from_row).transpose())
+            .map_err(failed)?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.list_users", skip_all, fields(db.system = "postgresql", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_users(&self, query: ListUsers) -> Result<UserPage, UserDomainError> {
+        record_outcome(async {
+            // Identifiers cannot be bound as parameters, the ORDER BY clause is built from a
+            // fixed set of columns instead. `name` and `email` sort with the `ignore_accent_case` collation.
+            let column = match query.sort_by {
+                UserSortField::Name => "name",
+                UserSortField::Email => "email",
+                UserSortField::Age => "age",
+            };
+            let direction = match query.direction {
+                SortDirection::Asc => "ASC",
+                SortDirection::Desc => "DESC",
+            };
+
+            let mut connection = self.connection.acquire().await.map_err(|e| user_error(e, UserDomainError::UserListFailed))?;
+
+            let mut rows = QueryBuilder::new("SELECT id, name, email, age, legal_hold, role, version FROM users WHERE deleted_at IS NULL");
+            if let Some(after) = &query.after {
+                push_after(&mut rows, after, query.direction);
+            }
+            rows.push(format!(" ORDER BY {column} {direction}, id LIMIT "))
+                .push_bind(i64::from(query.limit))
+                .push(" OFFSET ")
+                .push_bind(i64::try_from(query.offset).unwrap_or(i64::MAX));
+            let rows = rows
+                .build()
+                .fetch_all(&mut *connection)
+                .await
+                .and_then(|rows| rows.into_iter().map(user_from_row).collect::<Result<Vec<_>, _>>())
+                .map_err(|e| user_error(e, UserDomainError::UserListFailed))?;
+
+            let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL")
+                .fetch_one(&mut *connection)
+                .await
+                .map_err(|e| user_error(e, UserDomainError::UserListFailed))?;
+
+            Ok(UserPage {
+                users: rows,
+                total: total as u64,
+            })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.search_users", skip_all, fields(db.system = "postgresql", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn search_users(&self, filter: UserFilter) -> Result<UserPage, UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserListFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            let mut query = QueryBuilder::new("SELECT id, name, email, age, legal_hold, role, version FROM users");
+            push_filter(&mut query, &filter);
+            if let Some(after) = &filter.after {
+                push_after(&mut query, after, SortDirection::Asc);
+            }
+            query
+                .push(" ORDER BY name, id LIMIT ")
+                .push_bind(i64::from(filter.limit))
+                .push(" OFFSET ")
+                .push_bind(i64::try_from(filter.offset).unwrap_or(i64::MAX));
+            let rows = query
+                .build()
+                .fetch_all(&mut *connection)
+                .await
+                .and_then(|rows| rows.into_iter().map(user_from_row).collect::<Result<Vec<_>, _>>())
+                .map_err(failed)?;
+
+            let mut count = QueryBuilder::new("SELECT COUNT(*) FROM users");
+            push_filter(&mut count, &filter);
+            let total: i64 = count.build_query_scalar().fetch_one(&mut *connection).await.map_err(failed)?;
+
+            Ok(UserPage {
+                users: rows,
+                total: total as u64,
+            })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.count_user_facets", skip_all, fields(db.system = "postgresql", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserListFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            // Every facet is counted by one grouping set of a single scan of the table. Age
+            // buckets are identified by their lowest age, and domains are split off the emails
+            // with the `C` collation, as substring searches do not support the nondeterministic
+            // `ignore_accent_case` one. Only the most common domains are returned.
+            let rows = sqlx::query(
+                r#"
+                SELECT facet, legal_hold, email_domain, min_age, users
+                FROM (
+                    SELECT
+                        CASE GROUPING(legal_hold, email_domain, min_age)
+                            WHEN 3 THEN 'status'
+                            WHEN 5 THEN 'email_domain'
+                            ELSE 'age_bucket'
+                        END AS facet,
+                        legal_hold,
+                        email_domain,
+                        min_age,
+                        COUNT(*) AS users,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY GROUPING(legal_hold, email_domain, min_age)
+                            ORDER BY COUNT(*) DESC, email_domain
+                        ) AS rank
+                    FROM (
+                        SELECT
+                            legal_hold,
+                            lower(split_part(email COLLATE "C", '@', 2)) AS email_domain,
+                            CASE
+                                WHEN age < 18 THEN 0
+                                WHEN age < 25 THEN 18
+                                WHEN age < 35 THEN 25
+                                WHEN age < 45 THEN 35
+                                WHEN age < 55 THEN 45
+                                WHEN age < 65 THEN 55
+                                ELSE 65
+                            END AS min_age
+                        FROM users
+                        WHERE deleted_at IS NULL
+                    ) AS users
+                    GROUP BY GROUPING SETS ((legal_hold), (email_domain), (min_age))
+                ) AS facets
+                WHERE facet <> 'email_domain' OR rank <= $1
+                "#,
+            )
+            .bind(MAX_EMAIL_DOMAIN_FACETS as i64)
+            .fetch_all(&mut *connection)
+            .await
+            .map_err(failed)?;
+
+            let (mut by_status, mut by_email_domain, mut by_age_bucket) = (Vec::new(), Vec::new(), Vec::new());
+            for row in rows {
+                let facet: String = row.try_get("facet").map_err(failed)?;
+                let users: i64 = row.try_get("users").map_err(failed)?;
+                match facet.as_str() {
+                    "status" => {
+                        let legal_hold: bool = row.try_get("legal_hold").map_err(failed)?;
+                        let status = if legal_hold { UserStatus::LegalHold } else { UserStatus::Active };
+                        by_status.push((status, users as u64));
+                    }
+                    "email_domain" => by_email_domain.push((row.try_get("email_domain").map_err(failed)?, users as u64)),
+                    _ => {
+                        let min_age: i32 = row.try_get("min_age").map_err(failed)?;
+                        by_age_bucket.push((AgeBucket::of(min_age as u8), users as u64));
+                    }
+                }
+            }
+
+            Ok(UserFacets::from_counts(by_status, by_email_domain, by_age_bucket))
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.update_user", skip_all, fields(db.system = "postgresql", user.id = %user.id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn update_user(&self, user: UpdateUser) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            // First, get the existing user to merge with updates
+            let existing = self.get_user(user.id).await?;
+            if user.expected_version.is_some_and(|version| version != existing.version()) {
+                return Err(UserDomainError::UserVersionMismatch);
+            }
+
+            let name = user.name.unwrap_or_else(|| existing.name().to_string());
+            let email = user.email.unwrap_or_else(|| existing.email().clone());
+            let age = user.age.unwrap_or(existing.age());
+
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserUpdateFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            // A compare-and-swap on the expected version, so a user changed since it was read
+            // by the caller is not overwritten
+            let row = sqlx::query(
+                r#"
+                UPDATE users
+                SET name = $1, email = $2, age = $3, version = version + 1, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $4 AND deleted_at IS NULL AND ($5::BIGINT IS NULL OR version = $5)
+                RETURNING id, name, email, age, legal_hold, role, version
+                "#,
+            )
+            .bind(&name)
+            .bind(&email)
+            .bind(age as i16)
+            .bind(user.id)
+            .bind(user.expected_version.map(|version| version as i64))
+            .fetch_optional(&mut *connection)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(failed)?;
+
+            match row {
+                Some(updated) => Ok(updated),
+                // Deleted, or changed by a concurrent update, since it was read above
+                None => {
+                    self.get_user(user.id).await?;
+                    Err(UserDomainError::UserVersionMismatch)
+                }
+            }
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.delete_user", skip_all, fields(db.system = "postgresql", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserDeletionFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            // Better to use sqlx::query! macro for compile-time verification of the schema and query. Used functions because of absence of installed locally db.
+            let rows_affected = sqlx::query(
+                r#"
+                UPDATE users
+                SET deleted_at = CURRENT_TIMESTAMP, version = version + 1
+                WHERE id = $1 AND deleted_at IS NULL
+                "#,
+            )
+            .bind(id)
+            .execute(&mut *connection)
+            .await
+            .map_err(failed)?
+            .rows_affected();
+
+            if rows_affected == 0 {
+                Err(UserDomainError::UserNotFound)
+            } else {
+                Ok(())
+            }
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.set_legal_hold", skip_all, fields(db.system = "postgresql", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn set_legal_hold(&self, id: UserId, legal_hold: bool) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserUpdateFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            let row = sqlx::query(
+                r#"
+                UPDATE users
+                SET legal_hold = $1, version = version + 1, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $2 AND deleted_at IS NULL
+                RETURNING id, name, email, age, legal_hold, role, version
+                "#,
+            )
+            .bind(legal_hold)
+            .bind(id)
+            .fetch_optional(&mut *connection)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(failed)?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.set_role", skip_all, fields(db.system = "postgresql", user.id = %id, role = %role, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn set_role(&self, id: UserId, role: Role) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserUpdateFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            let row = sqlx::query(
+                r#"
+                UPDATE users
+                SET role = $1, version = version + 1, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $2 AND deleted_at IS NULL
+                RETURNING id, name, email, age, legal_hold, role, version
+                "#,
+            )
+            .bind(role.as_str())
+            .bind(id)
+            .fetch_optional(&mut *connection)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(failed)?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.restore_user", skip_all, fields(db.system = "postgresql", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn restore_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserUpdateFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            let row = sqlx::query(
+                r#"
+                UPDATE users
+                SET deleted_at = NULL, version = version + 1, updated_at = CURRENT_TIMESTAMP
+                WHERE id = $1 AND deleted_at IS NOT NULL
+                RETURNING id, name, email, age, legal_hold, role, version
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&mut *connection)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(failed)?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.hard_delete_user", skip_all, fields(db.system = "postgresql", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn hard_delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserDeletionFailed);
+            let mut connection = self.connection.acquire().await.map_err(failed)?;
+
+            let rows_affected = sqlx::query(
+                r#"
+                DELETE FROM users
+                WHERE id = $1
+                "#,
+            )
+            .bind(id)
+            .execute(&mut *connection)
+            .await
+            .map_err(failed)?
+            .rows_affected();
+
+            if rows_affected == 0 {
+                Err(UserDomainError::UserNotFound)
+            } else {
+                Ok(())
+            }
+        }
+        .await)
+    }
+}
+
+/// Maps a `users` table row to the domain `User` model.
+///
+/// Fails when a stored id, email or role is rejected by the domain, rather than letting it through.
+fn user_from_row(row: PgRow) -> Result<User, sqlx::Error> {
+    let id: UserId = row.try_get("id")?;
+    let name: String = row.try_get("name")?;
+    let email: Email = row.try_get("email")?;
+    let age: i16 = row.try_get("age")?;
+    let legal_hold: bool = row.try_get("legal_hold")?;
+    let role: String = row.try_get("role")?;
+    let role = Role::parse(&role).ok_or_else(|| sqlx::Error::ColumnDecode { index: "role".to_string(), source: format!("unknown role {:?}", role).into() })?;
+    let version: i64 = row.try_get("version")?;
+    Ok(User::new(id, name, email, age as u8).with_legal_hold(legal_hold).with_role(role).with_version(version as u64))
+}
+
+/// Maps a `users` table row to the user it holds and the hash of their password.
+fn credentials_from_row(row: PgRow) -> Result<UserCredentials, sqlx::Error> {
+    let password_hash: Option<String> = row.try_get("password_hash")?;
+    Ok(UserCredentials { user: user_from_row(row)?, password_hash: password_hash.map(PasswordHash::new) })
+}
+
+/// Appends the WHERE clause of `filter` to `query`, binding every value.
+///
+/// Substring searches do not support the nondeterministic `ignore_accent_case` collation, so
+/// the columns are folded like [`collation::fold`] instead: accents are stripped from the
+/// decomposed text with the `C` collation, and the rest is lowercased with the ICU root one.
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &UserFilter) {
+    let folded = |column: &str| format!(r#"lower(regexp_replace(normalize({column} COLLATE "C", NFD), '[\u0300-\u036f]', '', 'g') COLLATE "und-x-icu")"#);
+
+    query.push(" WHERE deleted_at IS NULL");
+    if let Some(name) = &filter.name {
+        query.push(format!(" AND strpos({}, ", folded("name"))).push_bind(collation::fold(name)).push(") > 0");
+    }
+    if let Some(email) = &filter.email {
+        query.push(format!(" AND strpos({}, ", folded("email"))).push_bind(collation::fold(email)).push(") > 0");
+    }
+    if let Some(min_age) = filter.min_age {
+        query.push(" AND age >= ").push_bind(i16::from(min_age));
+    }
+    if let Some(max_age) = filter.max_age {
+        query.push(" AND age <= ").push_bind(i16::from(max_age));
+    }
+}
+
+/// Appends the condition of the users ordered after `after` in `direction` to the `WHERE`
+/// clause of `query`, users with an equal key being ordered by id. `name` and `email` compare
+/// with the `ignore_accent_case` collation, like they sort.
+fn push_after(query: &mut QueryBuilder<'_, Postgres>, after: &UserPosition, direction: SortDirection) {
+    let comparison = match direction {
+        SortDirection::Asc => ">",
+        SortDirection::Desc => "<",
+    };
+    let column = match after.key {
+        UserSortKey::Name(_) => "name",
+        UserSortKey::Email(_) => "email",
+        UserSortKey::Age(_) => "age",
+    };
+    let push_key = |query: &mut QueryBuilder<'_, Postgres>| match &after.key {
+        UserSortKey::Name(text) | UserSortKey::Email(text) => {
+            query.push_bind(text.clone());
+        }
+        UserSortKey::Age(age) => {
+            query.push_bind(i16::from(*age));
+        }
+    };
+
+    query.push(format!(" AND ({column} {comparison} "));
+    push_key(query);
+    query.push(format!(" OR ({column} = "));
+    push_key(query);
+    query.push(" AND id > ").push_bind(after.id).push("))");
+}
@@ -0,0 +1,70 @@
+//! Classification of the errors of the SQL databases into the errors of the user operations.
+//!
+//! Errors are told apart by their SQLSTATE on PostgreSQL, and by their kind on the other
+//! databases, rather than by their message, which changes with the locale and version of the
+//! database.
+
+use sqlx::error::ErrorKind;
+
+use domain::user::error::{StorageError, UserDomainError};
+
+use crate::storage::adapter::postgres::retry::{DEADLOCK_DETECTED, SERIALIZATION_FAILURE};
+
+/// SQLSTATE of an insert or update duplicating a unique key, e.g. the email of a user.
+pub const UNIQUE_VIOLATION: &str = "23505";
+
+/// SQLSTATE of a row referencing a missing row of another table, e.g. a user deleted meanwhile.
+pub const FOREIGN_KEY_VIOLATION: &str = "23503";
+
+/// SQLSTATE of a `NULL` stored in a `NOT NULL` column.
+const NOT_NULL_VIOLATION: &str = "23502";
+
+/// SQLSTATE of a row failing a `CHECK` constraint.
+const CHECK_VIOLATION: &str = "23514";
+
+/// SQLSTATE of a value longer than its column.
+const STRING_DATA_RIGHT_TRUNCATION: &str = "22001";
+
+/// SQLSTATE of a statement cancelled by the `statement_timeout`.
+const QUERY_CANCELED: &str = "57014";
+
+/// Code of a SQLite database still locked by another connection once the busy timeout elapsed.
+const SQLITE_BUSY: &str = "5";
+
+/// Returns the error of a user operation failing with `e`: a duplicate user, a constraint
+/// violation, a concurrent change or a timeout, or else `failed`, caused by `e`.
+pub fn user_error(e: sqlx::Error, failed: fn(StorageError) -> UserDomainError) -> UserDomainError {
+    if matches!(e, sqlx::Error::PoolTimedOut) {
+        return UserDomainError::StorageTimeout(StorageError::new(e));
+    }
+    let Some(database_error) = e.as_database_error() else {
+        return failed(StorageError::new(e));
+    };
+    let kind = database_error.kind();
+    let code = database_error.code().map(|code| code.into_owned());
+
+    match (kind, code.as_deref()) {
+        (ErrorKind::UniqueViolation, _) | (_, Some(UNIQUE_VIOLATION)) => UserDomainError::UserAlreadyExists,
+        (ErrorKind::NotNullViolation | ErrorKind::CheckViolation, _) | (_, Some(NOT_NULL_VIOLATION | CHECK_VIOLATION | STRING_DATA_RIGHT_TRUNCATION)) => {
+            UserDomainError::UserConstraintViolation(StorageError::new(e))
+        }
+        (_, Some(SERIALIZATION_FAILURE | DEADLOCK_DETECTED)) => UserDomainError::UserChangeConflict(StorageError::new(e)),
+        (_, Some(QUERY_CANCELED | SQLITE_BUSY)) => UserDomainError::StorageTimeout(StorageError::new(e)),
+        _ => failed(StorageError::new(e)),
+    }
+}
+
+/// Returns whether `e` is an insert or update duplicating a unique key.
+pub fn is_unique_violation(e: &sqlx::Error) -> bool {
+    is_violation(e, ErrorKind::UniqueViolation, UNIQUE_VIOLATION)
+}
+
+/// Returns whether `e` is an insert or update referencing a missing row of another table.
+pub fn is_foreign_key_violation(e: &sqlx::Error) -> bool {
+    is_violation(e, ErrorKind::ForeignKeyViolation, FOREIGN_KEY_VIOLATION)
+}
+
+fn is_violation(e: &sqlx::Error, violation: ErrorKind, sqlstate: &str) -> bool {
+    e.as_database_error()
+        .is_some_and(|database_error| database_error.kind() == violation || database_error.code().as_deref() == Some(sqlstate))
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time;
+
+use application::ports::{capability::DependencyStatus, health::HealthCheckPort};
+
+use crate::storage::adapter::sqlite::Db;
+
+/// Maximum duration of a database probe, kept below typical readiness probe timeouts.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Health check of the SQLite pool, running `SELECT 1` on a pooled connection.
+pub struct SqliteHealthCheck {
+    db: Db,
+}
+
+impl SqliteHealthCheck {
+    /// Creates a new `SqliteHealthCheck` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl HealthCheckPort for SqliteHealthCheck {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    async fn check(&self) -> DependencyStatus {
+        match time::timeout(PROBE_TIMEOUT, sqlx::query("SELECT 1").execute(&*self.db)).await {
+            Ok(Ok(_)) => DependencyStatus::Up,
+            Ok(Err(e)) => {
+                tracing::warn!("database health check failed: {}", e);
+                DependencyStatus::Down
+            }
+            Err(_) => {
+                tracing::warn!("database health check timed out after {:?}", PROBE_TIMEOUT);
+                DependencyStatus::Down
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+pub mod health_check;
+pub mod user_repository;
+
+use std::{str::FromStr, sync::Arc};
+
+use eyre::Context;
+use sqlx::{migrate::Migrator, sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions}, Pool, Sqlite};
+
+use crate::storage::{PoolConfig, StorageRepositories, adapter::{in_memory::{consent_repository::InMemoryConsentRepository, group_repository::InMemoryGroupRepository, passkey_repository::InMemoryPasskeyRepository}, sqlite::user_repository::SqliteUserRepository}, create_repositories};
+
+pub type Db = Arc<Pool<Sqlite>>;
+
+/// Migrations from the `migrations/sqlite/` directory, embedded into the binary at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("../../migrations/sqlite");
+
+/// Connects to the SQLite database at `database_url`, creating it if it does not exist.
+///
+/// File databases are opened in WAL mode, so reads are not blocked by writes. An in-memory
+/// database (`sqlite::memory:`) lives as long as the connection holding it, so the pool keeps
+/// a single connection open for good, whatever the settings of `pool`.
+pub async fn db_connect(database_url: &str, pool: &PoolConfig) -> eyre::Result<Db> {
+    let options = SqliteConnectOptions::from_str(database_url)
+        .context("failed to parse database url")?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal);
+    let in_memory = database_url.contains(":memory:") || database_url.contains("mode=memory");
+
+    let pool_options = match in_memory {
+        true => SqlitePoolOptions::new().max_connections(1).min_connections(1).idle_timeout(None).max_lifetime(None),
+        false => pool.apply(SqlitePoolOptions::new()),
+    };
+    let pool = pool_options.connect_with(options).await.context("failed to connect to database")?;
+    Ok(Arc::new(pool))
+}
+
+/// Applies the embedded SQLite migrations not applied to the database yet.
+pub async fn run_migrations(db: &Pool<Sqlite>) -> eyre::Result<()> {
+    MIGRATOR.run(db).await.context("failed to apply database migrations")?;
+
+    match MIGRATOR.iter().map(|migration| migration.version).max() {
+        Some(version) => tracing::info!("database schema is at version {}", version),
+        None => tracing::info!("no database migrations to apply"),
+    }
+    Ok(())
+}
+
+/// Creates the repositories of a SQLite deployment. Only users are stored in the database;
+/// consents, passkeys and groups are kept in memory, and lost on restart.
+pub fn create_sqlite_repositories(db: Db) -> eyre::Result<StorageRepositories<SqliteUserRepository, InMemoryConsentRepository, InMemoryPasskeyRepository, InMemoryGroupRepository>> {
+    create_repositories(db, |db| Ok(SqliteUserRepository::new(db)), |_| Ok(InMemoryConsentRepository::new()), |_| Ok(InMemoryPasskeyRepository::new()), |_| Ok(InMemoryGroupRepository::new()))
+}
@@ -0,0 +1,455 @@
+use async_trait::async_trait;
+use sqlx::{sqlite::SqliteRow, QueryBuilder, Row, Sqlite};
+
+use domain::{collation, outcome::record_outcome, user::{error::UserDomainError, model::{AgeBucket, CreateUser, Email, ListUsers, PasswordHash, Role, SortDirection, UpdateUser, User, UserCredentials, UserFacets, UserFilter, UserId, UserPage, UserPosition, UserSortField, UserSortKey, UserStatus, MAX_EMAIL_DOMAIN_FACETS}, repository::UserRepositoryPort}};
+
+use crate::storage::adapter::sql_error::user_error;
+use crate::storage::adapter::sqlite::Db;
+
+/// SQLite implementation of the user repository, for self-contained deployments such as demos
+/// and CI.
+///
+/// It mirrors the behavior of the PostgreSQL adapter. As SQLite has no accent-insensitive
+/// collation, names and emails are also stored folded like [`collation::fold`], in the
+/// `name_key` and `email_key` columns, which lookups, searches and sorts use instead.
+pub struct SqliteUserRepository {
+    /// The SQLite database connection pool.
+    db: Db,
+}
+
+impl SqliteUserRepository {
+    /// Creates a new `SqliteUserRepository` instance.
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl UserRepositoryPort for SqliteUserRepository {
+    #[tracing::instrument(name = "user_repository.create_user", skip_all, fields(db.system = "sqlite", user.id = tracing::field::Empty, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn create_user(&self, user: CreateUser, password_hash: Option<PasswordHash>) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let id = UserId::generate();
+            tracing::Span::current().record("user.id", tracing::field::display(id));
+
+            sqlx::query(
+                r#"
+                INSERT INTO users (id, name, name_key, email, email_key, age, password_hash)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+            )
+            .bind(id)
+            .bind(&user.name)
+            .bind(collation::fold(&user.name))
+            .bind(&user.email)
+            .bind(collation::fold(user.email.as_str()))
+            .bind(i64::from(user.age))
+            .bind(password_hash.as_ref().map(PasswordHash::as_str))
+            .execute(&*self.db)
+            .await
+            .map_err(|e| user_error(e, UserDomainError::UserCreationFailed))?;
+
+            Ok(User::new(id, user.name, user.email, user.age))
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.get_user", skip_all, fields(db.system = "sqlite", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let row = sqlx::query(
+                r#"
+                SELECT id, name, email, age, legal_hold, role, version
+                FROM users
+                WHERE id = $1 AND deleted_at IS NULL
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&*self.db)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(|e| user_error(e, UserDomainError::UserReadFailed))?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.get_user_by_email", skip_all, fields(db.system = "sqlite", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user_by_email(&self, email: Email) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let row = sqlx::query(
+                r#"
+                SELECT id, name, email, age, legal_hold, role, version
+                FROM users
+                WHERE email_key = $1 AND deleted_at IS NULL
+                "#,
+            )
+            .bind(collation::fold(email.as_str()))
+            .fetch_optional(&*self.db)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(|e| user_error(e, UserDomainError::UserReadFailed))?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.get_user_credentials", skip_all, fields(db.system = "sqlite", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn get_user_credentials(&self, email: Email) -> Result<UserCredentials, UserDomainError> {
+        record_outcome(async {
+            let row = sqlx::query(
+                r#"
+                SELECT id, name, email, age, legal_hold, role, version, password_hash
+                FROM users
+                WHERE email_key = $1 AND deleted_at IS NULL
+                "#,
+            )
+            .bind(collation::fold(email.as_str()))
+            .fetch_optional(&*self.db)
+            .await
+            .and_then(|row| row.map(credentials_from_row).transpose())
+            .map_err(|e| user_error(e, UserDomainError::UserReadFailed))?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.list_users", skip_all, fields(db.system = "sqlite", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn list_users(&self, query: ListUsers) -> Result<UserPage, UserDomainError> {
+        record_outcome(async {
+            // Identifiers cannot be bound as parameters, the ORDER BY clause is built from a
+            // fixed set of columns instead
+            let column = match query.sort_by {
+                UserSortField::Name => "name_key",
+                UserSortField::Email => "email_key",
+                UserSortField::Age => "age",
+            };
+            let direction = match query.direction {
+                SortDirection::Asc => "ASC",
+                SortDirection::Desc => "DESC",
+            };
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserListFailed);
+
+            let mut users = QueryBuilder::new("SELECT id, name, email, age, legal_hold, role, version FROM users WHERE deleted_at IS NULL");
+            if let Some(after) = &query.after {
+                push_after(&mut users, after, query.direction);
+            }
+            users
+                .push(format!(" ORDER BY {column} {direction}, id LIMIT "))
+                .push_bind(i64::from(query.limit))
+                .push(" OFFSET ")
+                .push_bind(i64::try_from(query.offset).unwrap_or(i64::MAX));
+            let users = users
+                .build()
+                .fetch_all(&*self.db)
+                .await
+                .and_then(|rows| rows.into_iter().map(user_from_row).collect::<Result<Vec<_>, _>>())
+                .map_err(failed)?;
+
+            let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL").fetch_one(&*self.db).await.map_err(failed)?;
+
+            Ok(UserPage { users, total: total as u64 })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.search_users", skip_all, fields(db.system = "sqlite", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn search_users(&self, filter: UserFilter) -> Result<UserPage, UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserListFailed);
+
+            let mut query = QueryBuilder::new("SELECT id, name, email, age, legal_hold, role, version FROM users");
+            push_filter(&mut query, &filter);
+            if let Some(after) = &filter.after {
+                push_after(&mut query, after, SortDirection::Asc);
+            }
+            query
+                .push(" ORDER BY name_key, id LIMIT ")
+                .push_bind(i64::from(filter.limit))
+                .push(" OFFSET ")
+                .push_bind(i64::try_from(filter.offset).unwrap_or(i64::MAX));
+            let users = query
+                .build()
+                .fetch_all(&*self.db)
+                .await
+                .and_then(|rows| rows.into_iter().map(user_from_row).collect::<Result<Vec<_>, _>>())
+                .map_err(failed)?;
+
+            let mut count = QueryBuilder::new("SELECT COUNT(*) FROM users");
+            push_filter(&mut count, &filter);
+            let total: i64 = count.build_query_scalar().fetch_one(&*self.db).await.map_err(failed)?;
+
+            Ok(UserPage { users, total: total as u64 })
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.count_user_facets", skip_all, fields(db.system = "sqlite", outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError> {
+        record_outcome(async {
+            let failed = |e: sqlx::Error| user_error(e, UserDomainError::UserListFailed);
+
+            // SQLite has no grouping sets, so every facet is counted by its own query. Users are
+            // counted by age, summed into age buckets by `UserFacets::from_counts`.
+            let by_status: Vec<(bool, i64)> = sqlx::query_as("SELECT legal_hold, COUNT(*) FROM users WHERE deleted_at IS NULL GROUP BY legal_hold")
+                .fetch_all(&*self.db)
+                .await
+                .map_err(failed)?;
+            let by_email_domain: Vec<(String, i64)> = sqlx::query_as(
+                r#"
+                SELECT lower(substr(email, instr(email, '@') + 1)) AS email_domain, COUNT(*) AS users
+                FROM users
+                WHERE deleted_at IS NULL
+                GROUP BY email_domain
+                ORDER BY users DESC, email_domain
+                LIMIT $1
+                "#,
+            )
+            .bind(MAX_EMAIL_DOMAIN_FACETS as i64)
+            .fetch_all(&*self.db)
+            .await
+            .map_err(failed)?;
+            let by_age: Vec<(i64, i64)> = sqlx::query_as("SELECT age, COUNT(*) FROM users WHERE deleted_at IS NULL GROUP BY age")
+                .fetch_all(&*self.db)
+                .await
+                .map_err(failed)?;
+
+            Ok(UserFacets::from_counts(
+                by_status.into_iter().map(|(legal_hold, users)| (if legal_hold { UserStatus::LegalHold } else { UserStatus::Active }, users as u64)),
+                by_email_domain.into_iter().map(|(domain, users)| (domain, users as u64)),
+                by_age.into_iter().map(|(age, users)| (AgeBucket::of(age as u8), users as u64)),
+            ))
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.update_user", skip_all, fields(db.system = "sqlite", user.id = %user.id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn update_user(&self, user: UpdateUser) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            // First, get the existing user to merge with updates
+            let existing = self.get_user(user.id).await?;
+            if user.expected_version.is_some_and(|version| version != existing.version()) {
+                return Err(UserDomainError::UserVersionMismatch);
+            }
+
+            let name = user.name.unwrap_or_else(|| existing.name().to_string());
+            let email = user.email.unwrap_or_else(|| existing.email().clone());
+            let age = user.age.unwrap_or(existing.age());
+
+            // A compare-and-swap on the expected version, so a user changed since it was read
+            // by the caller is not overwritten
+            let row = sqlx::query(
+                r#"
+                UPDATE users
+                SET name = $1, name_key = $2, email = $3, email_key = $4, age = $5, version = version + 1, updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now')
+                WHERE id = $6 AND deleted_at IS NULL AND ($7 IS NULL OR version = $7)
+                RETURNING id, name, email, age, legal_hold, role, version
+                "#,
+            )
+            .bind(&name)
+            .bind(collation::fold(&name))
+            .bind(&email)
+            .bind(collation::fold(email.as_str()))
+            .bind(i64::from(age))
+            .bind(user.id)
+            .bind(user.expected_version.map(|version| version as i64))
+            .fetch_optional(&*self.db)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(|e| user_error(e, UserDomainError::UserUpdateFailed))?;
+
+            match row {
+                Some(updated) => Ok(updated),
+                // Deleted, or changed by a concurrent update, since it was read above
+                None => {
+                    self.get_user(user.id).await?;
+                    Err(UserDomainError::UserVersionMismatch)
+                }
+            }
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.delete_user", skip_all, fields(db.system = "sqlite", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        record_outcome(async {
+            let rows_affected = sqlx::query(
+                r#"
+                UPDATE users
+                SET deleted_at = strftime('%Y-%m-%d %H:%M:%f', 'now'), version = version + 1
+                WHERE id = $1 AND deleted_at IS NULL
+                "#,
+            )
+            .bind(id)
+            .execute(&*self.db)
+            .await
+            .map_err(|e| user_error(e, UserDomainError::UserDeletionFailed))?
+            .rows_affected();
+
+            if rows_affected == 0 {
+                Err(UserDomainError::UserNotFound)
+            } else {
+                Ok(())
+            }
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.restore_user", skip_all, fields(db.system = "sqlite", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn restore_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let row = sqlx::query(
+                r#"
+                UPDATE users
+                SET deleted_at = NULL, version = version + 1, updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now')
+                WHERE id = $1 AND deleted_at IS NOT NULL
+                RETURNING id, name, email, age, legal_hold, role, version
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&*self.db)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(|e| user_error(e, UserDomainError::UserUpdateFailed))?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.hard_delete_user", skip_all, fields(db.system = "sqlite", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn hard_delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        record_outcome(async {
+            let rows_affected = sqlx::query("DELETE FROM users WHERE id = $1")
+                .bind(id)
+                .execute(&*self.db)
+                .await
+                .map_err(|e| user_error(e, UserDomainError::UserDeletionFailed))?
+                .rows_affected();
+
+            if rows_affected == 0 {
+                Err(UserDomainError::UserNotFound)
+            } else {
+                Ok(())
+            }
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.set_legal_hold", skip_all, fields(db.system = "sqlite", user.id = %id, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn set_legal_hold(&self, id: UserId, legal_hold: bool) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let row = sqlx::query(
+                r#"
+                UPDATE users
+                SET legal_hold = $1, version = version + 1, updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now')
+                WHERE id = $2 AND deleted_at IS NULL
+                RETURNING id, name, email, age, legal_hold, role, version
+                "#,
+            )
+            .bind(legal_hold)
+            .bind(id)
+            .fetch_optional(&*self.db)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(|e| user_error(e, UserDomainError::UserUpdateFailed))?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+
+    #[tracing::instrument(name = "user_repository.set_role", skip_all, fields(db.system = "sqlite", user.id = %id, role = %role, outcome = tracing::field::Empty, error.class = tracing::field::Empty))]
+    async fn set_role(&self, id: UserId, role: Role) -> Result<User, UserDomainError> {
+        record_outcome(async {
+            let row = sqlx::query(
+                r#"
+                UPDATE users
+                SET role = $1, version = version + 1, updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now')
+                WHERE id = $2 AND deleted_at IS NULL
+                RETURNING id, name, email, age, legal_hold, role, version
+                "#,
+            )
+            .bind(role.as_str())
+            .bind(id)
+            .fetch_optional(&*self.db)
+            .await
+            .and_then(|row| row.map(user_from_row).transpose())
+            .map_err(|e| user_error(e, UserDomainError::UserUpdateFailed))?;
+
+            row.ok_or(UserDomainError::UserNotFound)
+        }
+        .await)
+    }
+}
+
+/// Maps a `users` table row to the domain `User` model.
+///
+/// Fails when a stored id, email or role is rejected by the domain, rather than letting it through.
+fn user_from_row(row: SqliteRow) -> Result<User, sqlx::Error> {
+    let id: UserId = row.try_get("id")?;
+    let name: String = row.try_get("name")?;
+    let email: Email = row.try_get("email")?;
+    let age: i64 = row.try_get("age")?;
+    let legal_hold: bool = row.try_get("legal_hold")?;
+    let role: String = row.try_get("role")?;
+    let role = Role::parse(&role).ok_or_else(|| sqlx::Error::ColumnDecode { index: "role".to_string(), source: format!("unknown role {:?}", role).into() })?;
+    let version: i64 = row.try_get("version")?;
+    Ok(User::new(id, name, email, age as u8).with_legal_hold(legal_hold).with_role(role).with_version(version as u64))
+}
+
+/// Maps a `users` table row to the user it holds and the hash of their password.
+fn credentials_from_row(row: SqliteRow) -> Result<UserCredentials, sqlx::Error> {
+    let password_hash: Option<String> = row.try_get("password_hash")?;
+    Ok(UserCredentials { user: user_from_row(row)?, password_hash: password_hash.map(PasswordHash::new) })
+}
+
+/// Appends the WHERE clause of `filter` to `query`, binding every value. Names and emails are
+/// matched through their folded `name_key` and `email_key` columns.
+fn push_filter(query: &mut QueryBuilder<'_, Sqlite>, filter: &UserFilter) {
+    query.push(" WHERE deleted_at IS NULL");
+    if let Some(name) = &filter.name {
+        query.push(" AND instr(name_key, ").push_bind(collation::fold(name)).push(") > 0");
+    }
+    if let Some(email) = &filter.email {
+        query.push(" AND instr(email_key, ").push_bind(collation::fold(email)).push(") > 0");
+    }
+    if let Some(min_age) = filter.min_age {
+        query.push(" AND age >= ").push_bind(i64::from(min_age));
+    }
+    if let Some(max_age) = filter.max_age {
+        query.push(" AND age <= ").push_bind(i64::from(max_age));
+    }
+}
+
+/// Appends the condition of the users ordered after `after` in `direction` to the `WHERE`
+/// clause of `query`, users with an equal key being ordered by id. Names and emails compare
+/// folded, like they sort.
+fn push_after(query: &mut QueryBuilder<'_, Sqlite>, after: &UserPosition, direction: SortDirection) {
+    let comparison = match direction {
+        SortDirection::Asc => ">",
+        SortDirection::Desc => "<",
+    };
+    let column = match after.key {
+        UserSortKey::Name(_) => "name_key",
+        UserSortKey::Email(_) => "email_key",
+        UserSortKey::Age(_) => "age",
+    };
+    let push_key = |query: &mut QueryBuilder<'_, Sqlite>| match &after.key {
+        UserSortKey::Name(text) | UserSortKey::Email(text) => {
+            query.push_bind(collation::fold(text));
+        }
+        UserSortKey::Age(age) => {
+            query.push_bind(i64::from(*age));
+        }
+    };
+
+    query.push(format!(" AND ({column} {comparison} "));
+    push_key(query);
+    query.push(format!(" OR ({column} = "));
+    push_key(query);
+    query.push(" AND id > ").push_bind(after.id).push("))");
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use application::ports::tenant::{TenantConfigPort, TenantSettings};
+
+/// Number of cached tenants above which expired entries are dropped.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Settings of a tenant, or their absence, read at `read_at`.
+struct Entry {
+    settings: Option<TenantSettings>,
+    read_at: Instant,
+}
+
+/// Tenant settings served from memory, as they are consulted on every request of a tenant.
+///
+/// Settings, and the absence of settings, are cached by each replica for `ttl`. Changes made
+/// through this replica are seen right away, those made through the others once the entry
+/// expires, so `ttl` bounds the staleness of the settings.
+pub struct CachedTenantConfig<P> {
+    inner: P,
+    ttl: Duration,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl<P: TenantConfigPort> CachedTenantConfig<P> {
+    /// Creates a new `CachedTenantConfig` caching the settings of `inner` for `ttl`.
+    pub fn new(inner: P, ttl: Duration) -> Self {
+        Self { inner, ttl, entries: Mutex::new(HashMap::new()) }
+    }
+
+    fn cached(&self, tenant: &str, now: Instant) -> Option<Option<TenantSettings>> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(tenant)
+            .filter(|entry| now.saturating_duration_since(entry.read_at) < self.ttl)
+            .map(|entry| entry.settings.clone())
+    }
+
+    fn store(&self, tenant: &str, settings: Option<TenantSettings>, read_at: Instant) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= PRUNE_THRESHOLD {
+            entries.retain(|_, entry| read_at.saturating_duration_since(entry.read_at) < self.ttl);
+        }
+        entries.insert(tenant.to_string(), Entry { settings, read_at });
+    }
+
+    fn evict(&self, tenant: &str) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).remove(tenant);
+    }
+}
+
+#[async_trait]
+impl<P: TenantConfigPort + Send + Sync> TenantConfigPort for CachedTenantConfig<P> {
+    async fn get(&self, tenant: &str) -> eyre::Result<Option<TenantSettings>> {
+        let now = Instant::now();
+        if let Some(settings) = self.cached(tenant, now) {
+            return Ok(settings);
+        }
+        let settings = self.inner.get(tenant).await?;
+        self.store(tenant, settings.clone(), now);
+        Ok(settings)
+    }
+
+    async fn put(&self, tenant: &str, settings: TenantSettings) -> eyre::Result<()> {
+        // Evicted even on failures, as the settings may have been saved nonetheless
+        let result = self.inner.put(tenant, settings).await;
+        self.evict(tenant);
+        result
+    }
+
+    async fn delete(&self, tenant: &str) -> eyre::Result<bool> {
+        let result = self.inner.delete(tenant).await;
+        self.evict(tenant);
+        result
+    }
+}
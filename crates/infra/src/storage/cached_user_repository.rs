@@ -0,0 +1,346 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use application::ports::audit::AuditLogPort;
+use application::ports::cache::CachePort;
+use application::ports::events::EventPublisherPort;
+use application::ports::unit_of_work::{IsolationLevel, TransactionPort, UnitOfWorkPort};
+use domain::user::{
+    error::UserDomainError,
+    model::{CreateUser, Email, ListUsers, PasswordHash, Role, UpdateUser, User, UserCredentials, UserFacets, UserFilter, UserId, UserPage},
+    repository::UserRepositoryPort,
+};
+
+/// Prefix of the cache keys of users, followed by their id.
+const KEY_PREFIX: &str = "user:";
+
+/// Cached representation of a [`User`].
+#[derive(Serialize, Deserialize)]
+struct CachedUser {
+    id: UserId,
+    name: String,
+    email: Email,
+    age: u8,
+    legal_hold: bool,
+    /// Missing from users cached before roles were stored, who are regular users.
+    #[serde(default)]
+    role: Role,
+    /// Required, so users cached before versions were stored are discarded rather than served
+    /// at a version they may not be at.
+    version: u64,
+}
+
+impl From<&User> for CachedUser {
+    fn from(user: &User) -> Self {
+        Self {
+            id: user.id(),
+            name: user.name().to_string(),
+            email: user.email().clone(),
+            age: user.age(),
+            legal_hold: user.legal_hold(),
+            role: user.role(),
+            version: user.version(),
+        }
+    }
+}
+
+impl From<CachedUser> for User {
+    fn from(cached: CachedUser) -> Self {
+        User::new(cached.id, cached.name, cached.email, cached.age).with_legal_hold(cached.legal_hold).with_role(cached.role).with_version(cached.version)
+    }
+}
+
+/// User repository serving [`UserRepositoryPort::get_user`] from a cache, to take the load of
+/// read-heavy deployments off the database.
+///
+/// Users are cached for `ttl` on a miss and evicted after every update, deletion or change of
+/// legal hold. The cache is best effort: its failures are logged and the wrapped repository is
+/// used instead. An eviction failing while the cache is unavailable leaves a stale entry, served
+/// until it expires, so `ttl` bounds the staleness of reads. Other reads are never cached.
+pub struct CachedUserRepository<R> {
+    inner: R,
+    cache: Arc<dyn CachePort + Send + Sync>,
+    ttl: Duration,
+}
+
+impl<R: UserRepositoryPort> CachedUserRepository<R> {
+    /// Creates a new `CachedUserRepository` caching users of `inner` in `cache` for `ttl`.
+    /// With a disabled cache, every call goes straight to `inner`.
+    pub fn new(inner: R, cache: Arc<dyn CachePort + Send + Sync>, ttl: Duration) -> Self {
+        Self { inner, cache, ttl }
+    }
+
+    async fn cached(&self, key: &str) -> Option<User> {
+        match self.cache.get(key).await {
+            Ok(Some(bytes)) => match serde_json::from_slice::<CachedUser>(&bytes) {
+                Ok(cached) => Some(cached.into()),
+                Err(e) => {
+                    tracing::warn!(cache.key = key, "discarding undecodable cached user: {}", e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!(cache.key = key, "failed to read user from cache: {:#}", e);
+                None
+            }
+        }
+    }
+
+    async fn store(&self, key: &str, user: &User) {
+        let bytes = match serde_json::to_vec(&CachedUser::from(user)) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!(cache.key = key, "failed to encode user for cache: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = self.cache.set(key, bytes, self.ttl).await {
+            tracing::warn!(cache.key = key, "failed to write user to cache: {:#}", e);
+        }
+    }
+
+    async fn evict(&self, id: UserId) {
+        evict(self.cache.as_ref(), id).await;
+    }
+}
+
+fn cache_key(id: UserId) -> String {
+    format!("{}{}", KEY_PREFIX, id)
+}
+
+async fn evict(cache: &(dyn CachePort + Send + Sync), id: UserId) {
+    if !cache.is_enabled() {
+        return;
+    }
+    let key = cache_key(id);
+    if let Err(e) = cache.delete(&key).await {
+        tracing::warn!(cache.key = %key, "failed to evict user from cache: {:#}", e);
+    }
+}
+
+#[async_trait]
+impl<R> UserRepositoryPort for CachedUserRepository<R>
+where
+    R: UserRepositoryPort + Send + Sync,
+{
+    async fn create_user(&self, user: CreateUser, password_hash: Option<PasswordHash>) -> Result<User, UserDomainError> {
+        self.inner.create_user(user, password_hash).await
+    }
+
+    async fn create_users(&self, users: Vec<(CreateUser, Option<PasswordHash>)>) -> Vec<Result<User, UserDomainError>> {
+        self.inner.create_users(users).await
+    }
+
+    async fn get_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        if !self.cache.is_enabled() {
+            return self.inner.get_user(id).await;
+        }
+
+        let key = cache_key(id);
+        if let Some(user) = self.cached(&key).await {
+            return Ok(user);
+        }
+        let user = self.inner.get_user(id).await?;
+        self.store(&key, &user).await;
+        Ok(user)
+    }
+
+    async fn get_user_by_email(&self, email: Email) -> Result<User, UserDomainError> {
+        self.inner.get_user_by_email(email).await
+    }
+
+    async fn get_user_credentials(&self, email: Email) -> Result<UserCredentials, UserDomainError> {
+        self.inner.get_user_credentials(email).await
+    }
+
+    async fn list_users(&self, query: ListUsers) -> Result<UserPage, UserDomainError> {
+        self.inner.list_users(query).await
+    }
+
+    async fn search_users(&self, filter: UserFilter) -> Result<UserPage, UserDomainError> {
+        self.inner.search_users(filter).await
+    }
+
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError> {
+        self.inner.count_user_facets().await
+    }
+
+    // Writes evict the user even when they fail, as a failure (e.g. a timeout) does not prove
+    // that the write was not applied.
+
+    async fn update_user(&self, user: UpdateUser) -> Result<User, UserDomainError> {
+        let id = user.id;
+        let result = self.inner.update_user(user).await;
+        self.evict(id).await;
+        result
+    }
+
+    async fn delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        let result = self.inner.delete_user(id).await;
+        self.evict(id).await;
+        result
+    }
+
+    async fn restore_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        let result = self.inner.restore_user(id).await;
+        self.evict(id).await;
+        result
+    }
+
+    async fn hard_delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        let result = self.inner.hard_delete_user(id).await;
+        self.evict(id).await;
+        result
+    }
+
+    async fn set_legal_hold(&self, id: UserId, legal_hold: bool) -> Result<User, UserDomainError> {
+        let result = self.inner.set_legal_hold(id, legal_hold).await;
+        self.evict(id).await;
+        result
+    }
+
+    async fn set_role(&self, id: UserId, role: Role) -> Result<User, UserDomainError> {
+        let result = self.inner.set_role(id, role).await;
+        self.evict(id).await;
+        result
+    }
+}
+
+/// Unit of work evicting the users changed in a transaction from the cache of a
+/// [`CachedUserRepository`] once the transaction is committed.
+///
+/// Users are read from the transaction, never from the cache: cached users may be outdated
+/// within it, and users it read must not be cached before it is committed.
+pub struct CachedUnitOfWork<U> {
+    inner: U,
+    cache: Arc<dyn CachePort + Send + Sync>,
+}
+
+impl<U: UnitOfWorkPort> CachedUnitOfWork<U> {
+    /// Creates a new `CachedUnitOfWork` evicting the users changed in transactions of `inner`
+    /// from `cache`.
+    pub fn new(inner: U, cache: Arc<dyn CachePort + Send + Sync>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl<U> UnitOfWorkPort for CachedUnitOfWork<U>
+where
+    U: UnitOfWorkPort + Send + Sync,
+{
+    async fn begin(&self, isolation: IsolationLevel) -> eyre::Result<Box<dyn TransactionPort + Send + Sync>> {
+        let inner = self.inner.begin(isolation).await?;
+        if !self.cache.is_enabled() {
+            return Ok(inner);
+        }
+        Ok(Box::new(CachedTransaction { inner, cache: self.cache.clone(), changed: Mutex::new(Vec::new()) }))
+    }
+}
+
+/// A transaction recording the users changed in it, to evict them once it is committed.
+struct CachedTransaction {
+    inner: Box<dyn TransactionPort + Send + Sync>,
+    cache: Arc<dyn CachePort + Send + Sync>,
+    changed: Mutex<Vec<UserId>>,
+}
+
+impl CachedTransaction {
+    fn changed(&self, id: UserId) {
+        self.changed.lock().unwrap_or_else(|e| e.into_inner()).push(id);
+    }
+}
+
+#[async_trait]
+impl TransactionPort for CachedTransaction {
+    fn users(&self) -> &(dyn UserRepositoryPort + Send + Sync) {
+        self
+    }
+
+    fn events(&self) -> &(dyn EventPublisherPort + Send + Sync) {
+        self.inner.events()
+    }
+
+    fn audit_log(&self) -> &(dyn AuditLogPort + Send + Sync) {
+        self.inner.audit_log()
+    }
+
+    async fn commit(self: Box<Self>) -> eyre::Result<()> {
+        let CachedTransaction { inner, cache, changed } = *self;
+        // Evicted even when the commit fails, as a failure does not prove it was not applied
+        let result = inner.commit().await;
+        for id in changed.into_inner().unwrap_or_else(|e| e.into_inner()) {
+            evict(cache.as_ref(), id).await;
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl UserRepositoryPort for CachedTransaction {
+    async fn create_user(&self, user: CreateUser, password_hash: Option<PasswordHash>) -> Result<User, UserDomainError> {
+        self.inner.users().create_user(user, password_hash).await
+    }
+
+    async fn create_users(&self, users: Vec<(CreateUser, Option<PasswordHash>)>) -> Vec<Result<User, UserDomainError>> {
+        self.inner.users().create_users(users).await
+    }
+
+    async fn get_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        self.inner.users().get_user(id).await
+    }
+
+    async fn get_user_by_email(&self, email: Email) -> Result<User, UserDomainError> {
+        self.inner.users().get_user_by_email(email).await
+    }
+
+    async fn get_user_credentials(&self, email: Email) -> Result<UserCredentials, UserDomainError> {
+        self.inner.users().get_user_credentials(email).await
+    }
+
+    async fn list_users(&self, query: ListUsers) -> Result<UserPage, UserDomainError> {
+        self.inner.users().list_users(query).await
+    }
+
+    async fn search_users(&self, filter: UserFilter) -> Result<UserPage, UserDomainError> {
+        self.inner.users().search_users(filter).await
+    }
+
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError> {
+        self.inner.users().count_user_facets().await
+    }
+
+    async fn update_user(&self, user: UpdateUser) -> Result<User, UserDomainError> {
+        self.changed(user.id);
+        self.inner.users().update_user(user).await
+    }
+
+    async fn delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        self.changed(id);
+        self.inner.users().delete_user(id).await
+    }
+
+    async fn restore_user(&self, id: UserId) -> Result<User, UserDomainError> {
+        self.changed(id);
+        self.inner.users().restore_user(id).await
+    }
+
+    async fn hard_delete_user(&self, id: UserId) -> Result<(), UserDomainError> {
+        self.changed(id);
+        self.inner.users().hard_delete_user(id).await
+    }
+
+    async fn set_legal_hold(&self, id: UserId, legal_hold: bool) -> Result<User, UserDomainError> {
+        self.changed(id);
+        self.inner.users().set_legal_hold(id, legal_hold).await
+    }
+
+    async fn set_role(&self, id: UserId, role: Role) -> Result<User, UserDomainError> {
+        self.changed(id);
+        self.inner.users().set_role(id, role).await
+    }
+}
@@ -0,0 +1,124 @@
+pub mod adapter;
+pub mod cached_tenant_config;
+pub mod cached_user_repository;
+
+use std::future::Future;
+use std::time::Duration;
+
+use port_decorators::RetryPolicy;
+use sqlx::pool::PoolOptions;
+use sqlx::Database;
+
+use domain::consent::repository::ConsentRepositoryPort;
+use domain::group::repository::GroupRepositoryPort;
+use domain::passkey::repository::PasskeyRepositoryPort;
+use domain::user::repository::UserRepositoryPort;
+
+/// Container for all storage repository implementations (adapters).
+///
+/// This struct groups all repository adapters together, making it easy to pass
+/// them as a unit to services or other components. It uses generics to allow
+/// for different repository implementations (e.g., PostgreSQL, MongoDB, in-memory)
+/// while maintaining type safety.
+pub struct StorageRepositories<UR: UserRepositoryPort, CR: ConsentRepositoryPort, PR: PasskeyRepositoryPort, GR: GroupRepositoryPort> where UR: Send + Sync + 'static, CR: Send + Sync + 'static, PR: Send + Sync + 'static, GR: Send + Sync + 'static {
+    /// The user repository adapter implementation.
+    pub user_repository: UR,
+    /// The consent repository adapter implementation.
+    pub consent_repository: CR,
+    /// The passkey repository adapter implementation.
+    pub passkey_repository: PR,
+    /// The group repository adapter implementation.
+    pub group_repository: GR,
+}
+
+/// Factory function for creating repository instances.
+///
+/// This function initializes repository adapters using one creator function per repository.
+/// It centralizes repositories creation and makes dependency injection explicit at
+/// application startup. The generic design allows for different database types and
+/// repository implementations.
+pub fn create_repositories<DB: Clone, UR, URC, CR, CRC, PR, PRC, GR, GRC>(
+    db: DB,
+    user_repository_creator: URC,
+    consent_repository_creator: CRC,
+    passkey_repository_creator: PRC,
+    group_repository_creator: GRC,
+) -> eyre::Result<StorageRepositories<UR, CR, PR, GR>>
+where
+    UR: UserRepositoryPort + Send + Sync + 'static,
+    URC: FnOnce(DB) -> eyre::Result<UR>,
+    CR: ConsentRepositoryPort + Send + Sync + 'static,
+    CRC: FnOnce(DB) -> eyre::Result<CR>,
+    PR: PasskeyRepositoryPort + Send + Sync + 'static,
+    PRC: FnOnce(DB) -> eyre::Result<PR>,
+    GR: GroupRepositoryPort + Send + Sync + 'static,
+    GRC: FnOnce(DB) -> eyre::Result<GR>,
+{
+    let user_repository = user_repository_creator(db.clone())?;
+    let consent_repository = consent_repository_creator(db.clone())?;
+    let passkey_repository = passkey_repository_creator(db.clone())?;
+    let group_repository = group_repository_creator(db)?;
+    Ok(StorageRepositories { user_repository, consent_repository, passkey_repository, group_repository })
+}
+/// Delay before the first retry of a failed connection to the database at startup.
+const CONNECT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Settings of the pool of database connections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolConfig {
+    /// Maximum number of open connections (`DATABASE_MAX_CONNECTIONS`, default 5).
+    pub max_connections: u32,
+    /// Connections kept open even when idle (`DATABASE_MIN_CONNECTIONS`, default 0).
+    pub min_connections: u32,
+    /// Maximum time spent waiting for a connection, in seconds, whether the pool is exhausted
+    /// or the database is not accepting connections yet (`DATABASE_ACQUIRE_TIMEOUT_SECS`, default 30).
+    pub acquire_timeout_secs: u64,
+    /// Time after which idle connections above `min_connections` are closed, in seconds
+    /// (`DATABASE_IDLE_TIMEOUT_SECS`, default 600). Idle connections are kept when `None` (0).
+    pub idle_timeout_secs: Option<u64>,
+    /// Time after which connections are closed and replaced, in seconds
+    /// (`DATABASE_MAX_LIFETIME_SECS`, default 1800). Connections are kept when `None` (0).
+    pub max_lifetime_secs: Option<u64>,
+    /// Attempts to connect at startup, retried after 1 second, then twice as long each time
+    /// (`DATABASE_CONNECT_ATTEMPTS`, default 5).
+    pub connect_attempts: u32,
+}
+
+impl PoolConfig {
+    /// Returns `options` with the limits and timeouts of the pool.
+    pub fn apply<DB: Database>(&self, options: PoolOptions<DB>) -> PoolOptions<DB> {
+        options
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_secs))
+            .idle_timeout(self.idle_timeout_secs.map(Duration::from_secs))
+            .max_lifetime(self.max_lifetime_secs.map(Duration::from_secs))
+    }
+
+    /// Returns the policy of the retries of the connection at startup.
+    pub fn connect_retry(&self) -> RetryPolicy {
+        RetryPolicy { max_attempts: self.connect_attempts.max(1), initial_backoff: CONNECT_INITIAL_BACKOFF }
+    }
+}
+
+/// Connects to the database with `connect`, retrying as per `retry` while the database cannot
+/// be reached, e.g. while it starts alongside the server. Other errors, such as invalid
+/// credentials, are returned at once.
+pub async fn connect_with_retry<T, F, Fut>(retry: RetryPolicy, mut connect: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match connect().await {
+            Err(e @ (sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut)) if attempt < retry.max_attempts => {
+                let backoff = retry.backoff(attempt);
+                tracing::warn!("failed to connect to database (attempt {} of {}), retrying in {:?}: {}", attempt, retry.max_attempts, backoff, e);
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
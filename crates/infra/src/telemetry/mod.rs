@@ -0,0 +1,86 @@
+#[cfg(feature = "otel")]
+pub mod otlp;
+pub mod sampling;
+
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::telemetry::sampling::{sampled_events_filter, SamplingDecisionLayer};
+
+/// Default log filter used when `RUST_LOG` is not set.
+const DEFAULT_LOG_FILTER: &str = "info";
+
+/// Format of the logs written to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines, prefixed with the enclosing spans and their fields.
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log collectors. The fields of the event are at the top
+    /// level, the current span is under `span` and every enclosing span under `spans`.
+    Json,
+}
+
+/// Settings of the export of spans to an OpenTelemetry collector over OTLP/HTTP.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpConfig {
+    /// Base URL of the collector, e.g. `http://otel-collector:4318`; spans are sent to its
+    /// `/v1/traces` path.
+    pub endpoint: String,
+    /// Name of the service the spans are reported under.
+    pub service_name: String,
+    /// Fraction of the traces started by the server that are exported. Traces continued from
+    /// a `traceparent` header follow the sampling decision of the caller.
+    pub sample_rate: f64,
+}
+
+/// Handle to the tracing set up by [`init_tracing`].
+pub struct Tracing {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Tracing {
+    /// Exports the spans not exported yet, if spans are exported.
+    pub fn shutdown(self) {
+        #[cfg(feature = "otel")]
+        if let Some(Err(e)) = self.provider.map(|provider| provider.shutdown()) {
+            tracing::error!("Failed to export the remaining spans: {}", e);
+        }
+    }
+}
+
+/// Initializes the global tracing subscriber, writing logs in `format` and exporting spans
+/// over OTLP when `otlp` is set.
+///
+/// Log levels follow `RUST_LOG` (defaulting to `info`), for logs and exported spans alike.
+/// Debug and trace events emitted inside a span whose `sampled_field` is recorded as `false`
+/// are dropped, so verbose logs are only kept for sampled requests. Fails when `otlp` is set
+/// but the crate was built without the `otel` feature.
+pub fn init_tracing(sampled_field: &'static str, format: LogFormat, otlp: Option<&OtlpConfig>) -> eyre::Result<Tracing> {
+    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    let fmt_layer = match format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer().boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().flatten_event(true).with_current_span(true).with_span_list(true).boxed(),
+    };
+
+    let subscriber = tracing_subscriber::registry()
+        .with(env_filter)
+        .with(SamplingDecisionLayer::new(sampled_field))
+        .with(fmt_layer.with_filter(sampled_events_filter()));
+
+    #[cfg(feature = "otel")]
+    {
+        let provider = otlp.map(otlp::tracer_provider).transpose()?;
+        subscriber.with(provider.as_ref().map(otlp::layer)).init();
+        Ok(Tracing { provider })
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        if otlp.is_some() {
+            eyre::bail!("OTEL_EXPORTER_OTLP_ENDPOINT is set, but the server was built without the `otel` feature");
+        }
+        subscriber.init();
+        Ok(Tracing {})
+    }
+}
@@ -0,0 +1,49 @@
+//! Export of spans to an OpenTelemetry collector (e.g. Jaeger, Tempo) over OTLP/HTTP.
+
+use eyre::Context;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::telemetry::OtlpConfig;
+
+/// Name of the tracer the spans are reported by.
+const TRACER_NAME: &str = "rust-web-server";
+
+/// Creates a provider exporting the sampled traces to the collector of `config` in batches,
+/// and makes W3C trace context (`traceparent` and `tracestate` headers) the propagation format.
+///
+/// Fails when the sample rate is not within `0.0..=1.0` or the exporter cannot be created.
+pub fn tracer_provider(config: &OtlpConfig) -> eyre::Result<SdkTracerProvider> {
+    if !(0.0..=1.0).contains(&config.sample_rate) {
+        eyre::bail!("trace sample rate must be between 0 and 1, got {}", config.sample_rate);
+    }
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", config.endpoint.trim_end_matches('/')))
+        .build()
+        .context("failed to create OTLP span exporter")?;
+
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_rate))))
+        .with_resource(Resource::builder().with_service_name(config.service_name.clone()).build())
+        .build())
+}
+
+/// Layer turning the spans of `tracing` into OpenTelemetry spans exported by `provider`.
+///
+/// Span fields become attributes, and the `otel.kind` and `otel.status_code` fields set the
+/// kind and status of the span.
+pub fn layer<S>(provider: &SdkTracerProvider) -> OpenTelemetryLayer<S, SdkTracer>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer(TRACER_NAME))
+}
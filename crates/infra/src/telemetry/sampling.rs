@@ -0,0 +1,87 @@
+use tracing::field::{Field, Visit};
+use tracing::{span, Level, Metadata, Subscriber};
+use tracing_subscriber::filter::{dynamic_filter_fn, DynFilterFn};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Log sampling settings loaded from the environment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplingConfig {
+    /// Fraction of successful requests that are logged, including their debug logs.
+    pub success_rate: f64,
+    /// Fraction of failed requests that are logged.
+    pub error_rate: f64,
+    /// Per-route overrides of `success_rate` as `(path prefix, rate)` pairs.
+    pub route_overrides: Vec<(String, f64)>,
+}
+
+/// Sampling decision stored in the extensions of the span it was recorded on.
+#[derive(Debug, Clone, Copy)]
+struct SamplingDecision(bool);
+
+/// Layer that captures sampling decisions recorded on spans.
+///
+/// The decision is recorded on a span field (e.g. `sampled`) by the HTTP middleware and kept
+/// in the span extensions, where [`sampled_events_filter`] looks it up.
+pub struct SamplingDecisionLayer {
+    field: &'static str,
+}
+
+impl SamplingDecisionLayer {
+    /// Creates a new layer capturing decisions recorded on the given field.
+    pub fn new(field: &'static str) -> Self {
+        Self { field }
+    }
+}
+
+impl<S> Layer<S> for SamplingDecisionLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, ctx: Context<'_, S>) {
+        let mut visitor = DecisionVisitor { field: self.field, decision: None };
+        values.record(&mut visitor);
+
+        if let (Some(decision), Some(span)) = (visitor.decision, ctx.span(id)) {
+            span.extensions_mut().replace(SamplingDecision(decision));
+        }
+    }
+}
+
+struct DecisionVisitor {
+    field: &'static str,
+    decision: Option<bool>,
+}
+
+impl Visit for DecisionVisitor {
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        if field.name() == self.field {
+            self.decision = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+/// Per-layer filter dropping debug/trace events of requests that were not sampled.
+///
+/// Spans and events at `info` or above always pass, as do events outside any span with a
+/// sampling decision.
+pub fn sampled_events_filter<S>() -> DynFilterFn<S, impl Fn(&Metadata<'_>, &Context<'_, S>) -> bool>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    dynamic_filter_fn(|metadata, ctx| {
+        if !metadata.is_event() || *metadata.level() <= Level::INFO {
+            return true;
+        }
+
+        ctx.lookup_current()
+            .and_then(|span| {
+                span.scope()
+                    .find_map(|span| span.extensions().get::<SamplingDecision>().map(|decision| decision.0))
+            })
+            .unwrap_or(true)
+    })
+}
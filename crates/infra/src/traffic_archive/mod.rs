@@ -0,0 +1,24 @@
+//! Archive of sampled traffic, for offline analysis and replay-based load tests.
+//!
+//! Exchanges sampled and sanitized by the HTTP layer are written in batches as Parquet files
+//! to object storage, partitioned by day (`<prefix>/date=YYYY-MM-DD/`), so they can be queried
+//! in place by engines such as DuckDB, Athena or Spark.
+
+#[cfg(feature = "archive")]
+pub mod parquet;
+
+/// Settings of the traffic archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrafficArchiveConfig {
+    /// Location of the archive: `s3://<bucket>/<prefix>`, with credentials and region from the
+    /// standard `AWS_*` variables, or `file:///<directory>`.
+    pub url: String,
+    /// Fraction of the requests archived with their responses.
+    pub sample_rate: f64,
+    /// Largest body archived, in bytes.
+    pub max_body_bytes: usize,
+    /// Exchanges written per file.
+    pub batch_size: usize,
+    /// Interval after which a partial batch is written anyway, in seconds.
+    pub flush_interval_secs: u64,
+}
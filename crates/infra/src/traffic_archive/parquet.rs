@@ -0,0 +1,220 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow_array::builder::{BinaryBuilder, StringBuilder, TimestampMillisecondBuilder, UInt16Builder, UInt64Builder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, Utc};
+use eyre::Context;
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use parquet::arrow::async_writer::{AsyncArrowWriter, ParquetObjectWriter};
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time;
+
+use application::ports::traffic_archive::{ArchivedExchange, TrafficArchivePort};
+
+use crate::traffic_archive::TrafficArchiveConfig;
+
+/// Maximum number of exchanges waiting to be written, beyond a full batch. Further exchanges
+/// are dropped, so a slow object store cannot exhaust memory or stall request handling.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Traffic archive queuing exchanges for a [`TrafficArchiveWriter`].
+#[derive(Debug, Clone)]
+pub struct ParquetTrafficArchive {
+    sender: mpsc::Sender<ArchivedExchange>,
+}
+
+impl TrafficArchivePort for ParquetTrafficArchive {
+    fn archive(&self, exchange: ArchivedExchange) {
+        if self.sender.try_send(exchange).is_err() {
+            tracing::warn!("traffic archive queue is full, dropping exchange");
+        }
+    }
+}
+
+/// Background task writing archived exchanges to object storage as Parquet files.
+///
+/// A file is written per `batch_size` exchanges, or every `flush_interval_secs` for partial
+/// batches. Batches failing to be written are logged and dropped.
+pub struct TrafficArchiveWriter {
+    archive: ParquetTrafficArchive,
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl TrafficArchiveWriter {
+    /// Opens the object store at the URL of `config` and starts writing to it.
+    pub fn spawn(config: &TrafficArchiveConfig) -> eyre::Result<Self> {
+        let (store, prefix) = open_store(&config.url)?;
+        Ok(Self::with_store(store, prefix, config))
+    }
+
+    /// Starts writing to `store`, under `prefix`.
+    pub fn with_store(store: Arc<dyn ObjectStore>, prefix: Path, config: &TrafficArchiveConfig) -> Self {
+        let batch_size = config.batch_size.max(1);
+        let flush_interval = Duration::from_secs(config.flush_interval_secs.max(1));
+        let (sender, mut receiver) = mpsc::channel(QUEUE_CAPACITY + batch_size);
+        let (shutdown, mut stopped) = watch::channel(false);
+
+        let task = tokio::spawn(async move {
+            let mut batch = Vec::with_capacity(batch_size);
+            let mut interval = time::interval_at(time::Instant::now() + flush_interval, flush_interval);
+            loop {
+                tokio::select! {
+                    exchange = receiver.recv() => match exchange {
+                        Some(exchange) => {
+                            batch.push(exchange);
+                            if batch.len() >= batch_size {
+                                flush(&store, &prefix, &mut batch).await;
+                            }
+                        }
+                        None => break,
+                    },
+                    _ = interval.tick() => flush(&store, &prefix, &mut batch).await,
+                    // Stopped, or the writer was dropped without being shut down
+                    _ = stopped.changed() => break,
+                }
+            }
+
+            while let Ok(exchange) = receiver.try_recv() {
+                batch.push(exchange);
+                if batch.len() >= batch_size {
+                    flush(&store, &prefix, &mut batch).await;
+                }
+            }
+            flush(&store, &prefix, &mut batch).await;
+        });
+
+        Self { archive: ParquetTrafficArchive { sender }, shutdown, task }
+    }
+
+    /// Returns the archive queuing exchanges for this writer.
+    pub fn archive(&self) -> Arc<dyn TrafficArchivePort + Send + Sync> {
+        Arc::new(self.archive.clone())
+    }
+
+    /// Stops the writer, writing the exchanges queued so far.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            tracing::error!("traffic archive writer task failed: {}", e);
+        }
+    }
+}
+
+/// Opens the object store at `url`, returning it with the prefix of the archive.
+fn open_store(url: &str) -> eyre::Result<(Arc<dyn ObjectStore>, Path)> {
+    if let Some(location) = url.strip_prefix("s3://") {
+        let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+        let store = AmazonS3Builder::from_env()
+            .with_bucket_name(bucket)
+            .build()
+            .context("failed to create S3 traffic archive")?;
+        Ok((Arc::new(store), Path::from(prefix)))
+    } else if let Some(directory) = url.strip_prefix("file://") {
+        std::fs::create_dir_all(directory).with_context(|| format!("failed to create traffic archive directory {}", directory))?;
+        let store = LocalFileSystem::new_with_prefix(directory).context("failed to open traffic archive directory")?;
+        Ok((Arc::new(store), Path::default()))
+    } else {
+        eyre::bail!("traffic archive URL must start with s3:// or file://, got {}", url)
+    }
+}
+
+async fn flush(store: &Arc<dyn ObjectStore>, prefix: &Path, batch: &mut Vec<ArchivedExchange>) {
+    if batch.is_empty() {
+        return;
+    }
+    let now = Utc::now();
+    let path = prefix
+        .child(format!("date={}", now.format("%Y-%m-%d")))
+        .child(format!("{}-{}.parquet", now.format("%H%M%S"), uuid::Uuid::new_v4().simple()));
+
+    match write(store, &path, batch).await {
+        Ok(()) => tracing::debug!("archived {} exchanges to {}", batch.len(), path),
+        Err(e) => tracing::warn!("failed to write {} archived exchanges to {}: {:#}", batch.len(), path, e),
+    }
+    batch.clear();
+}
+
+async fn write(store: &Arc<dyn ObjectStore>, path: &Path, batch: &[ArchivedExchange]) -> eyre::Result<()> {
+    let batch = record_batch(batch)?;
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = AsyncArrowWriter::try_new(ParquetObjectWriter::new(store.clone(), path.clone()), batch.schema(), Some(properties))?;
+    writer.write(&batch).await?;
+    writer.close().await?;
+    Ok(())
+}
+
+/// Schema of the archive, one row per exchange.
+///
+/// Headers are JSON arrays of `[name, value]` pairs, keeping their order and repeated names.
+/// Bodies are null when they were left out.
+pub fn schema() -> Schema {
+    Schema::new(vec![
+        Field::new("received_at", DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())), false),
+        Field::new("request_id", DataType::Utf8, true),
+        Field::new("method", DataType::Utf8, false),
+        Field::new("route", DataType::Utf8, true),
+        Field::new("path", DataType::Utf8, false),
+        Field::new("query", DataType::Utf8, true),
+        Field::new("request_headers", DataType::Utf8, false),
+        Field::new("request_body", DataType::Binary, true),
+        Field::new("status", DataType::UInt16, false),
+        Field::new("response_headers", DataType::Utf8, false),
+        Field::new("response_body", DataType::Binary, true),
+        Field::new("latency_ms", DataType::UInt64, false),
+    ])
+}
+
+fn record_batch(exchanges: &[ArchivedExchange]) -> eyre::Result<RecordBatch> {
+    let mut received_at = TimestampMillisecondBuilder::new().with_timezone("UTC");
+    let mut request_id = StringBuilder::new();
+    let mut method = StringBuilder::new();
+    let mut route = StringBuilder::new();
+    let mut path = StringBuilder::new();
+    let mut query = StringBuilder::new();
+    let mut request_headers = StringBuilder::new();
+    let mut request_body = BinaryBuilder::new();
+    let mut status = UInt16Builder::new();
+    let mut response_headers = StringBuilder::new();
+    let mut response_body = BinaryBuilder::new();
+    let mut latency_ms = UInt64Builder::new();
+
+    for exchange in exchanges {
+        received_at.append_value(DateTime::<Utc>::from(exchange.received_at).timestamp_millis());
+        request_id.append_option(exchange.request_id.as_deref());
+        method.append_value(&exchange.method);
+        route.append_option(exchange.route.as_deref());
+        path.append_value(&exchange.path);
+        query.append_option(exchange.query.as_deref());
+        request_headers.append_value(serde_json::to_string(&exchange.request_headers)?);
+        request_body.append_option(exchange.request_body.as_deref());
+        status.append_value(exchange.status);
+        response_headers.append_value(serde_json::to_string(&exchange.response_headers)?);
+        response_body.append_option(exchange.response_body.as_deref());
+        latency_ms.append_value(exchange.latency_ms);
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(received_at.finish()),
+        Arc::new(request_id.finish()),
+        Arc::new(method.finish()),
+        Arc::new(route.finish()),
+        Arc::new(path.finish()),
+        Arc::new(query.finish()),
+        Arc::new(request_headers.finish()),
+        Arc::new(request_body.finish()),
+        Arc::new(status.finish()),
+        Arc::new(response_headers.finish()),
+        Arc::new(response_body.finish()),
+        Arc::new(latency_ms.finish()),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema()), columns)?)
+}
@@ -0,0 +1,144 @@
+//! Signatures of outbound webhook deliveries.
+//!
+//! Every delivery carries a [`SIGNATURE_HEADER`] of the form `t=<unix seconds>,v1=<hex>`,
+//! where the signature is the HMAC-SHA256 of `<t>.<body>` with a shared secret. While a
+//! secret is being rotated, the sender signs with every active secret and emits one `v1`
+//! entry per secret, so receivers still holding the old secret keep accepting deliveries.
+//!
+//! Integrators embedding this crate can verify deliveries with [`WebhookVerifier`], which
+//! runs the exact code the sender signs with.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Header carrying the signature of a webhook delivery.
+pub const SIGNATURE_HEADER: &str = "webhook-signature";
+
+/// Version tag of the HMAC-SHA256 signature scheme.
+const SCHEME: &str = "v1";
+
+/// Maximum age (and clock skew) of a delivery accepted by default, limiting replays.
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(300);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Reasons a delivery is rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SignatureError {
+    #[error("malformed webhook signature header")]
+    Malformed,
+    #[error("webhook timestamp is outside of the tolerance")]
+    Expired,
+    #[error("no webhook signature matches")]
+    Mismatch,
+}
+
+/// Signs `payload` with every secret in `secrets`, returning the value of the [`SIGNATURE_HEADER`].
+///
+/// `timestamp` is the time of the delivery; a fresh one must be used for every attempt,
+/// as receivers reject timestamps older than their tolerance.
+pub fn sign<K: AsRef<[u8]>>(secrets: &[K], timestamp: SystemTime, payload: &[u8]) -> String {
+    let timestamp = unix_seconds(timestamp);
+
+    let mut header = format!("t={}", timestamp);
+    for secret in secrets {
+        header.push_str(&format!(",{}={}", SCHEME, to_hex(&mac(secret.as_ref(), timestamp, payload).finalize().into_bytes())));
+    }
+    header
+}
+
+/// Verifies the signatures of webhook deliveries.
+///
+/// Holds every secret currently accepted, so a secret can be rotated without downtime: add
+/// the new secret, wait for the sender to sign with it, then remove the old one.
+#[derive(Clone)]
+pub struct WebhookVerifier {
+    secrets: Vec<Vec<u8>>,
+    tolerance: Duration,
+}
+
+impl WebhookVerifier {
+    /// Creates a verifier accepting signatures made with any of `secrets`.
+    pub fn new<K: AsRef<[u8]>>(secrets: impl IntoIterator<Item = K>) -> Self {
+        Self {
+            secrets: secrets.into_iter().map(|secret| secret.as_ref().to_vec()).collect(),
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Sets the maximum age of accepted deliveries.
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Verifies `header` (the value of the [`SIGNATURE_HEADER`]) against the raw request body.
+    pub fn verify(&self, header: &str, payload: &[u8]) -> Result<(), SignatureError> {
+        self.verify_at(header, payload, SystemTime::now())
+    }
+
+    /// Verifies a delivery as of `now`, see [`WebhookVerifier::verify`].
+    pub fn verify_at(&self, header: &str, payload: &[u8], now: SystemTime) -> Result<(), SignatureError> {
+        let mut timestamp = None;
+        let mut signatures = Vec::new();
+        for entry in header.split(',') {
+            match entry.trim().split_once('=') {
+                Some(("t", value)) => timestamp = Some(value.parse::<u64>().map_err(|_| SignatureError::Malformed)?),
+                Some((SCHEME, value)) => signatures.push(from_hex(value).ok_or(SignatureError::Malformed)?),
+                // Entries of other schemes are skipped, so new schemes can be rolled out gradually.
+                Some(_) => {}
+                None => return Err(SignatureError::Malformed),
+            }
+        }
+        let timestamp = timestamp.ok_or(SignatureError::Malformed)?;
+
+        if unix_seconds(now).abs_diff(timestamp) > self.tolerance.as_secs() {
+            return Err(SignatureError::Expired);
+        }
+
+        let matches = self.secrets.iter().any(|secret| {
+            signatures
+                .iter()
+                .any(|signature| mac(secret, timestamp, payload).verify_slice(signature).is_ok())
+        });
+        if matches { Ok(()) } else { Err(SignatureError::Mismatch) }
+    }
+}
+
+impl std::fmt::Debug for WebhookVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookVerifier")
+            .field("secrets", &format_args!("[{} redacted]", self.secrets.len()))
+            .field("tolerance", &self.tolerance)
+            .finish()
+    }
+}
+
+/// Returns the MAC of `<timestamp>.<payload>`, not finalized so it can be compared in constant time.
+fn mac(secret: &[u8], timestamp: u64, payload: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+    mac
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
@@ -0,0 +1,227 @@
+//! Procedural macros of the `port-decorators` crate, see its documentation.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, spanned::Spanned, FnArg, Ident, ItemTrait, ReturnType, TraitItem, TraitItemFn, Type};
+
+/// Generates an `Instrumented<Name>` decorator implementing the annotated port trait.
+///
+/// Must be placed above `#[async_trait]`. Only `async fn` methods taking `&self` without
+/// generic parameters are supported. Supertraits are not delegated, the wrapper has to
+/// implement them by hand.
+#[proc_macro_attribute]
+pub fn instrumented_port(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        return syn::Error::new(Span::call_site(), "instrumented_port takes no arguments")
+            .to_compile_error()
+            .into();
+    }
+
+    let mut item = parse_macro_input!(input as ItemTrait);
+    match expand(&mut item) {
+        Ok(decorator) => quote!(#item #decorator).into(),
+        Err(e) => {
+            let error = e.to_compile_error();
+            quote!(#item #error).into()
+        }
+    }
+}
+
+fn expand(item: &mut ItemTrait) -> syn::Result<proc_macro2::TokenStream> {
+    let trait_name = &item.ident;
+    let base_name = trait_name.to_string();
+    let base_name = base_name.strip_suffix("Port").unwrap_or(&base_name);
+    let wrapper = format_ident!("Instrumented{}", base_name);
+    let span_prefix = format!("port.{}", to_snake_case(base_name));
+    let vis = &item.vis;
+
+    let mut methods = Vec::new();
+    for trait_item in &mut item.items {
+        if let TraitItem::Fn(method) = trait_item {
+            let retry = take_port_attribute(method)?;
+            methods.push(expand_method(method, retry, &span_prefix)?);
+        }
+    }
+
+    let doc = format!("Decorator of [`{}`] recording a span for every call and retrying `#[port(retry)]` methods.", trait_name);
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #wrapper<T> {
+            inner: T,
+            retry: ::port_decorators::RetryPolicy,
+        }
+
+        impl<T> #wrapper<T> {
+            /// Wraps `inner`, without retries.
+            pub fn new(inner: T) -> Self {
+                Self { inner, retry: ::port_decorators::RetryPolicy::NONE }
+            }
+
+            /// Sets the policy used to retry `#[port(retry)]` methods.
+            pub fn with_retry(mut self, retry: ::port_decorators::RetryPolicy) -> Self {
+                self.retry = retry;
+                self
+            }
+
+            /// Returns the wrapped implementation.
+            pub fn into_inner(self) -> T {
+                self.inner
+            }
+        }
+
+        #[::port_decorators::__private::async_trait]
+        impl<T> #trait_name for #wrapper<T>
+        where
+            T: #trait_name + Send + Sync,
+        {
+            #(#methods)*
+        }
+    })
+}
+
+/// Removes the `#[port(...)]` helper attributes of a method, returning whether it is retried.
+fn take_port_attribute(method: &mut TraitItemFn) -> syn::Result<bool> {
+    let mut retry = false;
+    let mut error = None;
+
+    method.attrs.retain(|attr| {
+        if !attr.path().is_ident("port") {
+            return true;
+        }
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("retry") {
+                retry = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported port option, expected `retry`"))
+            }
+        });
+        if let Err(e) = result {
+            error = Some(e);
+        }
+        false
+    });
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(retry),
+    }
+}
+
+fn expand_method(method: &TraitItemFn, retry: bool, span_prefix: &str) -> syn::Result<proc_macro2::TokenStream> {
+    let signature = &method.sig;
+    let name = &signature.ident;
+
+    if signature.asyncness.is_none() {
+        return Err(syn::Error::new(signature.span(), "instrumented_port only supports async methods"));
+    }
+    if !signature.generics.params.is_empty() {
+        return Err(syn::Error::new(signature.generics.span(), "instrumented_port does not support generic methods"));
+    }
+
+    let mut receiver = false;
+    let mut arg_names: Vec<Ident> = Vec::new();
+    let mut arg_types: Vec<&Type> = Vec::new();
+    for input in &signature.inputs {
+        match input {
+            FnArg::Receiver(r) if r.reference.is_some() && r.mutability.is_none() => receiver = true,
+            FnArg::Receiver(r) => return Err(syn::Error::new(r.span(), "instrumented_port methods must take `&self`")),
+            FnArg::Typed(arg) => {
+                arg_names.push(format_ident!("arg{}", arg_names.len()));
+                arg_types.push(&arg.ty);
+            }
+        }
+    }
+    if !receiver {
+        return Err(syn::Error::new(signature.span(), "instrumented_port methods must take `&self`"));
+    }
+
+    let output = &signature.output;
+    let returns_result = match output {
+        ReturnType::Type(_, ty) => is_result(ty),
+        ReturnType::Default => false,
+    };
+    if retry && !returns_result {
+        return Err(syn::Error::new(signature.span(), "only methods returning a Result can be retried"));
+    }
+
+    let span_name = format!("{}.{}", span_prefix, name);
+    let tracing = quote!(::port_decorators::__private::tracing);
+
+    let call = if retry {
+        quote! {
+            let mut attempts = 0u32;
+            let result = loop {
+                attempts += 1;
+                let result = self.inner.#name(#(::core::clone::Clone::clone(&#arg_names)),*).await;
+                match &result {
+                    Err(e) if attempts < self.retry.max_attempts && ::port_decorators::Retryable::is_retryable(e) => {
+                        #tracing::warn!(attempt = attempts, "retrying failed port call");
+                        ::port_decorators::__private::sleep(self.retry.backoff(attempts)).await;
+                    }
+                    _ => break result,
+                }
+            };
+        }
+    } else {
+        quote! {
+            let attempts = 1u32;
+            let result = self.inner.#name(#(#arg_names),*).await;
+        }
+    };
+
+    let record_outcome = if returns_result {
+        quote!(span.record("outcome", if result.is_ok() { "success" } else { "error" });)
+    } else {
+        quote!(span.record("outcome", "success");)
+    };
+
+    Ok(quote! {
+        async fn #name(&self, #(#arg_names: #arg_types),*) #output {
+            use ::port_decorators::__private::Instrument as _;
+
+            let span = #tracing::info_span!(
+                #span_name,
+                attempts = #tracing::field::Empty,
+                duration_ms = #tracing::field::Empty,
+                outcome = #tracing::field::Empty,
+            );
+
+            async {
+                let start = ::std::time::Instant::now();
+                #call
+                let span = #tracing::Span::current();
+                span.record("attempts", attempts);
+                span.record("duration_ms", start.elapsed().as_millis() as u64);
+                #record_outcome
+                result
+            }
+            .instrument(span)
+            .await
+        }
+    })
+}
+
+fn is_result(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.path.segments.last().is_some_and(|segment| segment.ident == "Result"),
+        _ => false,
+    }
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
@@ -0,0 +1,73 @@
+//! Decorators adding observability and retries to port implementations.
+//!
+//! Annotating a port trait with [`instrumented_port`] generates an `Instrumented<Name>`
+//! wrapper (the `Port` suffix is dropped, e.g. `InstrumentedUserRepository`) that implements
+//! the trait for any inner implementation. Every call runs in a span named
+//! `port.<name>.<method>` recording `attempts`, `duration_ms` and `outcome`, so adapters
+//! added later get the same telemetry without hand-written wrappers.
+//!
+//! Methods marked `#[port(retry)]` are retried according to the wrapper's [`RetryPolicy`]
+//! while they fail with an error whose [`Retryable::is_retryable`] returns `true`. Their
+//! arguments must implement `Clone`, so only mark idempotent operations.
+//!
+//! ```ignore
+//! #[instrumented_port]
+//! #[async_trait]
+//! pub trait UserRepositoryPort {
+//!     #[port(retry)]
+//!     async fn get_user(&self, id: String) -> Result<User, UserDomainError>;
+//! }
+//!
+//! let repository = InstrumentedUserRepository::new(UserRepository::new(db))
+//!     .with_retry(RetryPolicy::default());
+//! ```
+
+use std::time::Duration;
+
+pub use port_decorators_macros::instrumented_port;
+
+/// Errors that may succeed when the operation is retried (e.g. timeouts, lost connections).
+pub trait Retryable {
+    fn is_retryable(&self) -> bool;
+}
+
+/// How failed calls of `#[port(retry)]` methods are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first call. `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every following one.
+    pub initial_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy making a single attempt.
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_attempts: 1,
+        initial_backoff: Duration::ZERO,
+    };
+
+    /// Returns the delay before the retry following the given (1-based) failed attempt.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, retried after 50ms and 100ms.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(50),
+        }
+    }
+}
+
+/// Items used by the generated code. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait::async_trait;
+    pub use tokio::time::sleep;
+    pub use tracing;
+    pub use tracing::Instrument;
+}
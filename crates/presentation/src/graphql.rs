@@ -0,0 +1,252 @@
+//! GraphQL API of the users, next to the REST API.
+//!
+//! Queries and mutations call [`UserRpc`] like the RPC APIs, so they share the business logic,
+//! validation, authorization and error codes of the REST API. Mutations are authenticated by
+//! the `Authorization` header of the request, and errors carry their `code`, and the `errors`
+//! of the invalid fields, in their extensions.
+
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{Context, EmptySubscription, Enum, Error, ErrorExtensions, InputObject, Object, Schema, SimpleObject, Value, ID};
+use axum::extract::State;
+use axum::http::{header, HeaderMap};
+use axum::response::Html;
+use axum::routing::get;
+use axum::{Json, Router};
+
+use domain::user::model::{User, UserPage};
+
+use crate::handlers::user_handlers::{ApiError, CreateUserRequestBody, ListUsersQueryParams, SearchUsersQueryParams, SortOrderParam, UpdateUserRequestBody, UserSortParam};
+use crate::middleware::request_id::REQUEST_ID_HEADER;
+use crate::rpc::{CallError, RpcContext, UserRpc};
+
+/// Path the GraphQL API and its playground are served at.
+pub const GRAPHQL_PATH: &str = "/api/graphql";
+
+/// Schema of the GraphQL API.
+pub type UserSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// State of the GraphQL routes: the schema, resolving through the given [`UserRpc`].
+#[derive(Clone)]
+pub struct GraphQlState {
+    schema: UserSchema,
+}
+
+impl GraphQlState {
+    /// Creates a new `GraphQlState` serving the users of `rpc`.
+    pub fn new(rpc: UserRpc) -> Self {
+        Self { schema: Schema::build(QueryRoot, MutationRoot, EmptySubscription).data(rpc).finish() }
+    }
+
+    /// Returns the schema in the GraphQL schema definition language.
+    pub fn sdl(&self) -> String {
+        self.schema.sdl()
+    }
+}
+
+/// A user.
+#[derive(SimpleObject)]
+#[graphql(name = "User")]
+pub struct GraphQlUser {
+    pub id: ID,
+    pub name: String,
+    pub email: String,
+    pub age: u8,
+}
+
+impl From<User> for GraphQlUser {
+    fn from(user: User) -> Self {
+        Self {
+            id: ID(user.id().to_string()),
+            name: user.name().to_string(),
+            email: user.email().to_string(),
+            age: user.age(),
+        }
+    }
+}
+
+/// A page of users.
+#[derive(SimpleObject)]
+#[graphql(name = "UserPage")]
+pub struct GraphQlUserPage {
+    /// The users of the page, in order.
+    pub users: Vec<GraphQlUser>,
+    /// Number of users of all the pages.
+    pub total: u64,
+}
+
+impl From<UserPage> for GraphQlUserPage {
+    fn from(page: UserPage) -> Self {
+        Self { users: page.users.into_iter().map(GraphQlUser::from).collect(), total: page.total }
+    }
+}
+
+/// Field the users of a page are sorted by.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+pub enum UserSort {
+    Name,
+    Email,
+    Age,
+}
+
+impl From<UserSort> for UserSortParam {
+    fn from(sort: UserSort) -> Self {
+        match sort {
+            UserSort::Name => UserSortParam::Name,
+            UserSort::Email => UserSortParam::Email,
+            UserSort::Age => UserSortParam::Age,
+        }
+    }
+}
+
+/// Direction of the sort of a page of users.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl From<SortOrder> for SortOrderParam {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => SortOrderParam::Asc,
+            SortOrder::Desc => SortOrderParam::Desc,
+        }
+    }
+}
+
+/// The user to create.
+#[derive(InputObject)]
+pub struct CreateUserInput {
+    pub name: String,
+    pub email: String,
+    pub age: u8,
+    /// The password the user logs in with, if any. Only its hash is stored.
+    pub password: Option<String>,
+}
+
+/// The fields of a user to update. Fields left out are unchanged.
+#[derive(InputObject)]
+pub struct UpdateUserInput {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub age: Option<u8>,
+}
+
+/// Reads of the users, public like the `GET` user routes.
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// The user with the given id.
+    async fn user(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<GraphQlUser> {
+        let user = rpc(ctx).get_user(&id).await.map_err(graphql_error)?;
+        Ok(user.into())
+    }
+
+    /// A page of the users, 1 to 100 per page (default 20), sorted by name by default.
+    async fn users(&self, ctx: &Context<'_>, limit: Option<u32>, offset: Option<u64>, sort_by: Option<UserSort>, order: Option<SortOrder>) -> async_graphql::Result<GraphQlUserPage> {
+        let params = ListUsersQueryParams { limit, offset, sort_by: sort_by.map(Into::into), order: order.map(Into::into) };
+        let page = rpc(ctx).list_users(params).await.map_err(graphql_error)?;
+        Ok(page.into())
+    }
+
+    /// A page of the users matching every given criterion. Names and emails match when they
+    /// contain the text, ignoring case and accents, and ages are inclusive.
+    #[allow(clippy::too_many_arguments)]
+    async fn search_users(
+        &self,
+        ctx: &Context<'_>,
+        name: Option<String>,
+        email: Option<String>,
+        min_age: Option<u8>,
+        max_age: Option<u8>,
+        limit: Option<u32>,
+        offset: Option<u64>,
+    ) -> async_graphql::Result<GraphQlUserPage> {
+        let params = SearchUsersQueryParams { name, email, min_age, max_age, limit, offset };
+        let page = rpc(ctx).search_users(params).await.map_err(graphql_error)?;
+        Ok(page.into())
+    }
+}
+
+/// Changes of the users. Creations are public, while the other mutations require an access
+/// token with the `users:write` scope.
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    /// Creates a new user.
+    async fn create_user(&self, ctx: &Context<'_>, input: CreateUserInput) -> async_graphql::Result<GraphQlUser> {
+        let request = CreateUserRequestBody { name: input.name, email: input.email, age: input.age, password: input.password };
+        let user = rpc(ctx).create_user(request).await.map_err(graphql_error)?;
+        Ok(user.into())
+    }
+
+    /// Updates the user with the given id.
+    async fn update_user(&self, ctx: &Context<'_>, id: ID, input: UpdateUserInput) -> async_graphql::Result<GraphQlUser> {
+        let request = UpdateUserRequestBody { name: input.name, email: input.email, age: input.age };
+        let user = rpc(ctx).update_user(&call_context(ctx), &id, request).await.map_err(graphql_error)?;
+        Ok(user.into())
+    }
+
+    /// Soft-deletes the user with the given id, returning `true`. Not allowed while
+    /// impersonating.
+    async fn delete_user(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<bool> {
+        rpc(ctx).delete_user(&call_context(ctx), &id).await.map_err(graphql_error)?;
+        Ok(true)
+    }
+
+    /// Restores the deleted user with the given id.
+    async fn restore_user(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<GraphQlUser> {
+        let user = rpc(ctx).restore_user(&call_context(ctx), &id).await.map_err(graphql_error)?;
+        Ok(user.into())
+    }
+}
+
+fn rpc<'a>(ctx: &Context<'a>) -> &'a UserRpc {
+    ctx.data_unchecked::<UserRpc>()
+}
+
+/// Returns the context of the calls of a request, without a token when executed without one.
+fn call_context(ctx: &Context<'_>) -> RpcContext {
+    ctx.data_opt::<RpcContext>().cloned().unwrap_or_default()
+}
+
+/// Converts an error of a call into a GraphQL error, with the `code` of the error and the
+/// `errors` of the invalid fields, if any, in its extensions.
+fn graphql_error(error: ApiError) -> Error {
+    let CallError { code, message, errors } = CallError::from(error);
+    Error::new(message).extend_with(|_, extensions| {
+        extensions.set("code", code.to_string());
+        if !errors.is_empty() {
+            let errors = errors.into_iter().map(|error| serde_json::json!({ "field": error.field, "message": error.message })).collect();
+            if let Ok(errors) = Value::from_json(serde_json::Value::Array(errors)) {
+                extensions.set("errors", errors);
+            }
+        }
+    })
+}
+
+/// Routes of the GraphQL API served by `graphql`, to be nested at [`GRAPHQL_PATH`]: queries
+/// and mutations are posted, and the GraphiQL playground is served to browsers.
+pub fn graphql_routes<S>(graphql: GraphQlState) -> Router<S> {
+    Router::new().route("/", get(playground).post(execute)).with_state(graphql)
+}
+
+/// Executes a GraphQL request, with the access token and request id of its headers.
+async fn execute(State(graphql): State<GraphQlState>, headers: HeaderMap, Json(request): Json<async_graphql::Request>) -> Json<async_graphql::Response> {
+    let context = RpcContext {
+        token: headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string),
+        request_id: headers.get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok()).map(str::to_string),
+    };
+    Json(graphql.schema.execute(request.data(context)).await)
+}
+
+/// Serves the GraphiQL playground.
+async fn playground() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint(GRAPHQL_PATH).finish())
+}
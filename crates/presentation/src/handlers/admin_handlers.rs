@@ -0,0 +1,385 @@
+use std::time::Duration;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use application::flows::user_service::UserServiceTrait;
+use application::ports::capability::{Capabilities, DependencyStatus};
+use domain::user::model::{FacetCount, Role, User, UserFacets, UserStatus};
+
+use crate::handlers::user_handlers::{parse_user_id, ApiError, ApiSuccess, ListUsersQueryParams, UserCursorPosition, UserState};
+use crate::middleware::auth::AuthState;
+use crate::middleware::sampling::{Sampler, SamplingPolicy};
+use crate::pagination::{CursorQueryParams, Page};
+
+/// Status of a single optional dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct DependencyResponseData {
+    pub name: &'static str,
+    /// `up`, `down` or `disabled`.
+    #[schema(value_type = String)]
+    pub status: DependencyStatus,
+}
+
+/// List the optional dependencies and their status.
+///
+/// Dependencies that are not configured are reported as `disabled`.
+///
+/// # Responses
+///
+/// - 200 OK: the status of each dependency.
+pub async fn get_dependencies(State(capabilities): State<Capabilities>) -> ApiSuccess<Vec<DependencyResponseData>> {
+    let mut dependencies = Vec::new();
+    for capability in capabilities.all() {
+        dependencies.push(DependencyResponseData {
+            name: capability.name(),
+            status: capability.check().await,
+        });
+    }
+
+    ApiSuccess::new(StatusCode::OK, dependencies)
+}
+
+/// Get the current log sampling policy.
+///
+/// # Responses
+///
+/// - 200 OK: the current policy.
+pub async fn get_sampling_policy(State(sampler): State<Sampler>) -> ApiSuccess<SamplingPolicy> {
+    ApiSuccess::new(StatusCode::OK, sampler.policy())
+}
+
+/// Replace the log sampling policy at runtime.
+///
+/// # Responses
+///
+/// - 200 OK: the policy was applied.
+/// - 422 Unprocessable entity: a sampling rate is outside `0..=1`.
+pub async fn update_sampling_policy(
+    State(sampler): State<Sampler>,
+    Json(policy): Json<SamplingPolicy>,
+) -> Result<ApiSuccess<SamplingPolicy>, ApiError> {
+    sampler
+        .set_policy(policy)
+        .map_err(ApiError::UnprocessableEntity)
+        .map(|_| ApiSuccess::new(StatusCode::OK, sampler.policy()))
+}
+
+/// The body of a legal hold request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LegalHoldRequestBody {
+    pub legal_hold: bool,
+}
+
+/// The legal hold status of a User.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LegalHoldResponseData {
+    pub id: String,
+    pub legal_hold: bool,
+}
+
+/// Place a User under legal hold, or lift the hold.
+///
+/// Users under legal hold cannot be deleted until the hold is lifted.
+///
+/// # Responses
+///
+/// - 200 OK: the hold was applied or lifted.
+/// - 404 Not Found: the User was not found.
+/// - 500 Internal server error: Failed to update the hold.
+pub async fn set_legal_hold<S>(
+    State(state): State<UserState<S>>,
+    Path(id): Path<String>,
+    Json(body): Json<LegalHoldRequestBody>,
+) -> Result<ApiSuccess<LegalHoldResponseData>, ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    state
+        .user_service
+        .set_legal_hold(parse_user_id(&id)?, body.legal_hold)
+        .await
+        .map_err(ApiError::from)
+        .map(|user| {
+            ApiSuccess::new(
+                StatusCode::OK,
+                LegalHoldResponseData {
+                    id: user.id().to_string(),
+                    legal_hold: user.legal_hold(),
+                },
+            )
+        })
+}
+
+/// A role of Users.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoleParam {
+    Admin,
+    User,
+}
+
+impl From<RoleParam> for Role {
+    fn from(role: RoleParam) -> Self {
+        match role {
+            RoleParam::Admin => Role::Admin,
+            RoleParam::User => Role::User,
+        }
+    }
+}
+
+/// The body of a role request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RoleRequestBody {
+    /// `admin` or `user`.
+    pub role: RoleParam,
+}
+
+/// The role of a User.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RoleResponseData {
+    pub id: String,
+    /// `admin` or `user`.
+    pub role: &'static str,
+}
+
+/// Set the role of a User.
+///
+/// The role is granted to the User on their next login; tokens already issued keep their roles.
+///
+/// # Responses
+///
+/// - 200 OK: the role was set.
+/// - 404 Not Found: the User was not found.
+/// - 422 Unprocessable entity: the role is unknown.
+/// - 500 Internal server error: Failed to set the role.
+pub async fn set_role<S>(
+    State(state): State<UserState<S>>,
+    Path(id): Path<String>,
+    Json(body): Json<RoleRequestBody>,
+) -> Result<ApiSuccess<RoleResponseData>, ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    state
+        .user_service
+        .set_role(parse_user_id(&id)?, body.role.into())
+        .await
+        .map_err(ApiError::from)
+        .map(|user| ApiSuccess::new(StatusCode::OK, RoleResponseData { id: user.id().to_string(), role: user.role().as_str() }))
+}
+
+/// Delete a User for good, whether deleted already or not, along with the data of the User.
+///
+/// Users under legal hold cannot be deleted until the hold is lifted.
+///
+/// # Responses
+///
+/// - 204 No Content: the User was deleted.
+/// - 404 Not Found: the User was not found.
+/// - 423 Locked: the User is under legal hold.
+/// - 500 Internal server error: Failed to delete the User.
+pub async fn hard_delete_user<S>(
+    State(state): State<UserState<S>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    state
+        .user_service
+        .hard_delete_user(parse_user_id(&id)?)
+        .await
+        .map_err(ApiError::from)
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// A User as listed to admins, with their status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AdminUserResponseData {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub age: u8,
+    /// `active` or `legal_hold`.
+    pub status: &'static str,
+    /// `admin` or `user`.
+    pub role: &'static str,
+}
+
+impl From<&User> for AdminUserResponseData {
+    fn from(user: &User) -> Self {
+        Self {
+            id: user.id().to_string(),
+            name: user.name().to_string(),
+            email: user.email().to_string(),
+            age: user.age(),
+            status: UserStatus::of(user).as_str(),
+            role: user.role().as_str(),
+        }
+    }
+}
+
+/// Number of Users having a facet value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FacetCountResponseData {
+    pub value: String,
+    pub count: u64,
+}
+
+impl FacetCountResponseData {
+    fn list<V>(counts: Vec<FacetCount<V>>, value: impl Fn(V) -> String) -> Vec<Self> {
+        counts.into_iter().map(|c| Self { value: value(c.value), count: c.count }).collect()
+    }
+}
+
+/// Numbers of Users by status, by email domain and by age bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserFacetsResponseData {
+    /// Every status (`active`, `legal_hold`).
+    pub status: Vec<FacetCountResponseData>,
+    /// The 10 most common email domains, lowercased.
+    pub email_domain: Vec<FacetCountResponseData>,
+    /// Every age bucket (`under_18`, `18_24`, `25_34`, `35_44`, `45_54`, `55_64`, `65_plus`).
+    pub age_bucket: Vec<FacetCountResponseData>,
+}
+
+impl From<UserFacets> for UserFacetsResponseData {
+    fn from(facets: UserFacets) -> Self {
+        Self {
+            status: FacetCountResponseData::list(facets.by_status, |status| status.as_str().to_string()),
+            email_domain: FacetCountResponseData::list(facets.by_email_domain, |domain| domain),
+            age_bucket: FacetCountResponseData::list(facets.by_age_bucket, |bucket| bucket.as_str().to_string()),
+        }
+    }
+}
+
+/// The response body data field for a page of Users listed to admins, with the facets of all Users.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AdminUserListResponseData {
+    pub users: Vec<AdminUserResponseData>,
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u64,
+    /// Cursor of the next page, to pass as `cursor`, `null` on the last page.
+    pub next_cursor: Option<String>,
+    pub facets: UserFacetsResponseData,
+}
+
+/// List a page of Users with their status, along with the numbers of all Users by status, by
+/// email domain and by age bucket.
+///
+/// Takes the query parameters of `GET /api/users`, including its `cursor`.
+///
+/// # Responses
+///
+/// - 200 OK: the requested page of Users, with the total number of Users, the cursor of the
+///   next page and the facets.
+/// - 400 Bad Request: a query parameter could not be parsed, or the cursor is invalid.
+/// - 422 Unprocessable entity: the limit is out of range.
+/// - 500 Internal server error: Failed to list users.
+pub async fn list_users<S>(
+    State(state): State<UserState<S>>,
+    Query(params): Query<ListUsersQueryParams>,
+    Query(cursor): Query<CursorQueryParams>,
+) -> Result<ApiSuccess<AdminUserListResponseData>, ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    let mut query = params.into_domain()?;
+    query.after = UserCursorPosition::decode(cursor.cursor.as_deref(), &state.cursor_key, query.sort_by, query.direction)?;
+
+    let (page, facets) = tokio::try_join!(state.user_service.list_users(query.clone()), state.user_service.count_user_facets())?;
+    let total = page.total;
+    let page = Page::new(page.users, query.limit as usize, &state.cursor_key, |last| UserCursorPosition::of(last, query.sort_by, query.direction))
+        .map(|user| AdminUserResponseData::from(&user));
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        AdminUserListResponseData {
+            users: page.items,
+            total,
+            limit: query.limit,
+            offset: query.offset,
+            next_cursor: page.next_cursor,
+            facets: facets.into(),
+        },
+    ))
+}
+
+/// Lifetime of impersonation sessions when none is requested, in seconds.
+pub const DEFAULT_IMPERSONATION_TTL_SECS: u64 = 900;
+
+/// Longest impersonation session that can be requested, in seconds.
+pub const MAX_IMPERSONATION_TTL_SECS: u64 = 3600;
+
+/// The body of an impersonation request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ImpersonationRequestBody {
+    /// Id of the admin who will act on behalf of the User, recorded in the token and logs.
+    pub actor_id: String,
+    /// Id of the User to act on behalf of.
+    pub subject_id: String,
+    /// Lifetime of the session, in seconds (1 to 3600, default 900).
+    pub ttl_secs: Option<u64>,
+}
+
+/// The response body data field for a new impersonation session.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImpersonationResponseData {
+    pub access_token: String,
+    pub token_type: &'static str,
+    /// Lifetime of the access token, in seconds.
+    pub expires_in: u64,
+    pub actor_id: String,
+    pub subject_id: String,
+}
+
+/// Mint a time-boxed token letting an admin act on behalf of a User.
+///
+/// The token authenticates as the User on every route, except that Users cannot be deleted
+/// with it. Issuing the session and every request made with it are logged with both ids.
+///
+/// # Responses
+///
+/// - 201 Created: the body contains the impersonation token.
+/// - 404 Not Found: the User was not found.
+/// - 422 Unprocessable entity: the actor id is empty or the lifetime is out of range.
+/// - 500 Internal server error: Failed to issue the token.
+pub async fn create_impersonation<S>(
+    State(users): State<UserState<S>>,
+    State(auth): State<AuthState>,
+    Json(body): Json<ImpersonationRequestBody>,
+) -> Result<ApiSuccess<ImpersonationResponseData>, ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    if body.actor_id.trim().is_empty() {
+        return Err(ApiError::UnprocessableEntity("actor_id must not be empty".to_string()));
+    }
+    let ttl_secs = body.ttl_secs.unwrap_or(DEFAULT_IMPERSONATION_TTL_SECS);
+    if !(1..=MAX_IMPERSONATION_TTL_SECS).contains(&ttl_secs) {
+        return Err(ApiError::UnprocessableEntity(format!("ttl_secs must be between 1 and {}", MAX_IMPERSONATION_TTL_SECS)));
+    }
+
+    let subject = users.user_service.get_user(parse_user_id(&body.subject_id)?).await?;
+
+    let token = auth
+        .auth_service
+        .impersonate(body.actor_id.clone(), subject.id().to_string(), Duration::from_secs(ttl_secs))
+        .await?;
+
+    Ok(ApiSuccess::new(
+        StatusCode::CREATED,
+        ImpersonationResponseData {
+            access_token: token.token,
+            token_type: "Bearer",
+            expires_in: token.expires_in.as_secs(),
+            actor_id: body.actor_id,
+            subject_id: subject.id().to_string(),
+        },
+    ))
+}
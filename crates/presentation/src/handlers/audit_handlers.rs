@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use application::ports::audit::{AuditEntry, AuditLogPort, FieldChange};
+
+use crate::handlers::user_handlers::{parse_user_id, ApiError, ApiSuccess};
+
+/// The dependencies of the audit handlers.
+#[derive(Clone)]
+pub struct AuditState {
+    pub audit_log: Arc<dyn AuditLogPort + Send + Sync + 'static>,
+}
+
+/// An entry of the audit log of a User, in responses.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEntryResponseData {
+    /// Id of the user who made the change, `null` when not made by an authenticated user.
+    pub actor: Option<String>,
+    /// Id of the user the actor impersonated when making the change, `null` when the actor
+    /// acted as themselves.
+    pub impersonated_user_id: Option<String>,
+    /// `created`, `updated`, `deleted`, `restored` or `hard_deleted`.
+    pub action: &'static str,
+    /// The fields changed, with their values before and after the change.
+    pub changes: Vec<FieldChange>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl From<AuditEntry> for AuditEntryResponseData {
+    fn from(entry: AuditEntry) -> Self {
+        Self {
+            actor: entry.actor,
+            impersonated_user_id: entry.impersonated_user_id,
+            action: entry.action.as_str(),
+            changes: entry.changes,
+            recorded_at: entry.recorded_at,
+        }
+    }
+}
+
+/// The response body data field of the audit log of a User.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditLogResponseData {
+    pub user_id: String,
+    /// The changes of the User, oldest first.
+    pub entries: Vec<AuditEntryResponseData>,
+}
+
+/// Get the history of the changes of a User, oldest first. Requires the `users:write` scope.
+///
+/// The history is kept after the User is deleted for good. Unknown Users have an empty history.
+///
+/// # Responses
+///
+/// - 200 OK: the changes of the User.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `users:write` scope.
+/// - 404 Not Found: the ID is not the ID of a User.
+/// - 500 Internal server error: Failed to read the audit log.
+pub async fn get_user_audit(
+    State(state): State<AuditState>,
+    Path(id): Path<String>,
+) -> Result<ApiSuccess<AuditLogResponseData>, ApiError> {
+    let user_id = parse_user_id(&id)?;
+    let entries = state
+        .audit_log
+        .history(user_id)
+        .await
+        .map_err(|e| ApiError::InternalServerError(format!("failed to read audit log: {:#}", e)))?;
+
+    Ok(ApiSuccess::new(
+        StatusCode::OK,
+        AuditLogResponseData {
+            user_id: user_id.to_string(),
+            entries: entries.into_iter().map(AuditEntryResponseData::from).collect(),
+        },
+    ))
+}
@@ -0,0 +1,219 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Form, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use application::ports::auth::{all_scopes, parse_scopes, AuthError, KeySetPort};
+use domain::user::validation::ValidationErrors;
+
+use crate::handlers::device_handlers::OAuthErrorData;
+use crate::handlers::user_handlers::{ApiError, ApiErrorData, ApiResponseBody, ApiSuccess};
+use crate::middleware::auth::AuthState;
+
+/// The body of a login request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, ToSchema)]
+pub struct LoginRequestBody {
+    pub email: String,
+    pub password: String,
+    /// Space-delimited scopes requested for the token, e.g. `users:write passkeys`. Every scope
+    /// is granted when omitted.
+    #[serde(default)]
+    pub scope: Option<String>,
+}
+
+/// The response body data field for a successful login.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct LoginResponseData {
+    pub access_token: String,
+    pub token_type: &'static str,
+    /// Lifetime of the access token, in seconds.
+    pub expires_in: u64,
+    /// Space-delimited scopes of the access token.
+    pub scope: String,
+}
+
+/// Log in with an email and password, receiving a bearer access token.
+///
+/// # Responses
+///
+/// - 200 OK: the credentials are valid, the body contains the access token.
+/// - 400 Bad Request: a requested scope is unknown.
+/// - 401 Unauthorized: the credentials are invalid.
+/// - 500 Internal server error: Failed to issue the token.
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login",
+    request_body = LoginRequestBody,
+    responses(
+        (status = 200, description = "The credentials are valid, the body contains the access token.", body = ApiResponseBody<LoginResponseData>),
+        (status = 400, description = "A requested scope is unknown.", body = ApiResponseBody<ApiErrorData>),
+        (status = 401, description = "The credentials are invalid.", body = ApiResponseBody<ApiErrorData>),
+        (status = 500, description = "Failed to issue the token.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn login(
+    State(state): State<AuthState>,
+    Json(body): Json<LoginRequestBody>,
+) -> Result<ApiSuccess<LoginResponseData>, ApiError> {
+    let scopes = match &body.scope {
+        Some(scope) => parse_scopes(scope).map_err(|message| {
+            let mut errors = ValidationErrors::new();
+            errors.check("scope", Err(message));
+            errors
+        })?,
+        None => all_scopes(),
+    };
+    let scope = scopes.join(" ");
+
+    state
+        .auth_service
+        .login(body.email, body.password, scopes)
+        .await
+        .map_err(ApiError::from)
+        .map(|token| {
+            ApiSuccess::new(
+                StatusCode::OK,
+                LoginResponseData {
+                    access_token: token.token,
+                    token_type: "Bearer",
+                    expires_in: token.expires_in.as_secs(),
+                    scope,
+                },
+            )
+        })
+}
+
+/// The form of a token introspection request (RFC 7662, section 2.1).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct IntrospectionForm {
+    #[serde(default)]
+    pub token: String,
+    /// Ignored, as access tokens are the only tokens issued.
+    #[serde(default)]
+    pub token_type_hint: Option<String>,
+}
+
+/// The response to a token introspection request (RFC 7662, section 2.2). Inactive tokens
+/// only have `active` set.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct IntrospectionResponseData {
+    pub active: bool,
+    /// Space-delimited scopes of the token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<&'static str>,
+    /// Id of the user the token acts as.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    /// Expiry time, in seconds since the Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    /// Issue time, in seconds since the Unix epoch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    /// The admin acting on behalf of `sub`, for impersonation tokens (RFC 8693, section 4.1).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub act: Option<IntrospectionActorData>,
+    /// Roles of the user, omitted when it has none.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
+}
+
+/// The `act` member of introspection responses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IntrospectionActorData {
+    pub sub: String,
+}
+
+/// Introspect an access token issued by the server, for other services to validate the tokens
+/// they receive. Requires the `INTROSPECTION_TOKEN` bearer token.
+///
+/// The request is form-encoded and the response is not wrapped in the usual envelope, as
+/// expected by OAuth 2.0 resource servers.
+///
+/// # Responses
+///
+/// - 200 OK: whether the token is active and, if so, its subject, scopes and lifetime.
+/// - 400 Bad request: `invalid_request` when the token is missing.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 500 Internal server error: Failed to validate the token.
+pub async fn introspect(State(state): State<AuthState>, Form(form): Form<IntrospectionForm>) -> Response {
+    if form.token.is_empty() {
+        return OAuthErrorData::new("invalid_request", "token is required").into_response();
+    }
+
+    let data = match state.auth_service.authenticate(&form.token).await {
+        Ok(principal) => IntrospectionResponseData {
+            active: true,
+            scope: Some(principal.scopes.join(" ")),
+            token_type: Some("Bearer"),
+            sub: Some(principal.user_id),
+            exp: Some(unix_time(principal.expires_at)),
+            iat: Some(unix_time(principal.issued_at)),
+            act: principal.actor_id.map(|sub| IntrospectionActorData { sub }),
+            roles: principal.roles,
+        },
+        Err(AuthError::Unavailable) => {
+            return ApiError::InternalServerError("Failed to validate the token".to_string()).into_response();
+        }
+        Err(_) => IntrospectionResponseData::default(),
+    };
+    ([(header::CACHE_CONTROL, "no-store")], Json(data)).into_response()
+}
+
+/// Time the key set may be cached, shorter than the publication delay of new keys.
+const JWKS_CACHE_CONTROL: &str = "public, max-age=300";
+
+/// The key set validating access tokens (RFC 7517, section 5).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JwksData {
+    pub keys: Vec<JwkData>,
+}
+
+/// A P-256 public key validating ES256 access tokens (RFC 7518, section 6.2).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JwkData {
+    pub kty: &'static str,
+    pub crv: &'static str,
+    pub alg: &'static str,
+    #[serde(rename = "use")]
+    pub key_use: &'static str,
+    pub kid: String,
+    pub x: String,
+    pub y: String,
+}
+
+/// Get the public keys validating access tokens, for other services to validate them without
+/// calling the server. Tokens name their key in their `kid` header.
+///
+/// The key set is not wrapped in the usual envelope, as expected by JWT libraries.
+///
+/// # Responses
+///
+/// - 200 OK: the keys of the tokens currently valid, and of the tokens about to be issued.
+pub async fn jwks(State(key_set): State<Arc<dyn KeySetPort + Send + Sync>>) -> Response {
+    let keys = key_set
+        .public_keys()
+        .into_iter()
+        .map(|key| JwkData {
+            kty: "EC",
+            crv: "P-256",
+            alg: "ES256",
+            key_use: "sig",
+            kid: key.kid,
+            x: key.x,
+            y: key.y,
+        })
+        .collect();
+    ([(header::CACHE_CONTROL, JWKS_CACHE_CONTROL)], Json(JwksData { keys })).into_response()
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or_default()
+}
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use application::ports::clock::{ClockPort, ShiftedClock};
+
+use crate::handlers::user_handlers::{ApiError, ApiSuccess};
+
+/// The dependencies of the test clock handlers.
+#[derive(Clone)]
+pub struct ClockState {
+    /// The clock of the tokens and signing keys, shifted by the handlers.
+    pub clock: Arc<ShiftedClock>,
+}
+
+/// The body of a clock shift request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ClockShiftRequestBody {
+    /// Seconds to shift the clock by, backward when negative.
+    pub secs: i64,
+}
+
+/// The response body data field of the state of the test clock.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ClockResponseData {
+    /// The current time of the clock.
+    pub now: DateTime<Utc>,
+    /// Seconds the clock is ahead of the time of the system, negative when behind.
+    pub offset_secs: i64,
+}
+
+impl From<&ShiftedClock> for ClockResponseData {
+    fn from(clock: &ShiftedClock) -> Self {
+        Self { now: clock.now(), offset_secs: clock.offset().num_seconds() }
+    }
+}
+
+/// Get the current time of the test clock and its offset from the time of the system.
+///
+/// # Responses
+///
+/// - 200 OK: the state of the clock.
+pub async fn get_clock(State(state): State<ClockState>) -> ApiSuccess<ClockResponseData> {
+    ApiSuccess::new(StatusCode::OK, ClockResponseData::from(state.clock.as_ref()))
+}
+
+/// Shift the test clock forward or backward, so tokens and signing keys expire or rotate
+/// without waiting in real time. Shifts add up.
+///
+/// # Responses
+///
+/// - 200 OK: the clock was shifted.
+/// - 422 Unprocessable Entity: the shift is out of range.
+pub async fn shift_clock(State(state): State<ClockState>, Json(body): Json<ClockShiftRequestBody>) -> Result<ApiSuccess<ClockResponseData>, ApiError> {
+    let delta = TimeDelta::try_seconds(body.secs).ok_or_else(|| ApiError::UnprocessableEntity("Shift is out of range".to_string()))?;
+    let offset = state.clock.shift(delta);
+    tracing::warn!(clock.offset_secs = offset.num_seconds(), "test clock shifted");
+    Ok(ApiSuccess::new(StatusCode::OK, ClockResponseData::from(state.clock.as_ref())))
+}
+
+/// Put the test clock back to the time of the system.
+///
+/// # Responses
+///
+/// - 204 No Content: the clock was reset.
+pub async fn reset_clock(State(state): State<ClockState>) -> StatusCode {
+    state.clock.reset();
+    tracing::info!("test clock reset");
+    StatusCode::NO_CONTENT
+}
@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use application::flows::consent_service::{ConsentServiceTrait, DisabledConsentService};
+
+use domain::consent::{error::ConsentDomainError, model::{Consent, ConsentAction, ConsentType, RecordConsent}};
+
+use crate::handlers::user_handlers::{ApiError, ApiErrorData, ApiResponseBody, ApiSuccess};
+
+/// Maximum length of the policy version of a consent.
+const MAX_VERSION_LENGTH: usize = 64;
+
+/// Maximum length of the source of a consent.
+const MAX_SOURCE_LENGTH: usize = 255;
+
+/// The dependencies of the consent handlers.
+#[derive(Clone)]
+pub struct ConsentState {
+    pub consent_service: Arc<dyn ConsentServiceTrait + Send + Sync + 'static>,
+}
+
+impl Default for ConsentState {
+    /// Consent tracking disabled: recording fails and users have no consent records.
+    fn default() -> Self {
+        Self {
+            consent_service: Arc::new(DisabledConsentService),
+        }
+    }
+}
+
+impl From<ConsentDomainError> for ApiError {
+    fn from(e: ConsentDomainError) -> Self {
+        match e {
+            ConsentDomainError::UserNotFound => {
+                Self::NotFound("User not found".to_string())
+            }
+            ConsentDomainError::ConsentRecordFailed => {
+                Self::InternalServerError("Failed to record consent".to_string())
+            }
+            ConsentDomainError::ConsentListFailed => {
+                Self::InternalServerError("Failed to list consents".to_string())
+            }
+        }
+    }
+}
+
+/// Purpose of a consent, in requests and responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsentTypeParam {
+    Analytics,
+    MarketingEmail,
+}
+
+impl From<ConsentTypeParam> for ConsentType {
+    fn from(param: ConsentTypeParam) -> Self {
+        match param {
+            ConsentTypeParam::Analytics => ConsentType::Analytics,
+            ConsentTypeParam::MarketingEmail => ConsentType::MarketingEmail,
+        }
+    }
+}
+
+impl From<ConsentType> for ConsentTypeParam {
+    fn from(consent_type: ConsentType) -> Self {
+        match consent_type {
+            ConsentType::Analytics => ConsentTypeParam::Analytics,
+            ConsentType::MarketingEmail => ConsentTypeParam::MarketingEmail,
+        }
+    }
+}
+
+/// Whether a consent is granted or withdrawn, in requests and responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsentActionParam {
+    Granted,
+    Withdrawn,
+}
+
+impl From<ConsentActionParam> for ConsentAction {
+    fn from(param: ConsentActionParam) -> Self {
+        match param {
+            ConsentActionParam::Granted => ConsentAction::Granted,
+            ConsentActionParam::Withdrawn => ConsentAction::Withdrawn,
+        }
+    }
+}
+
+impl From<ConsentAction> for ConsentActionParam {
+    fn from(action: ConsentAction) -> Self {
+        match action {
+            ConsentAction::Granted => ConsentActionParam::Granted,
+            ConsentAction::Withdrawn => ConsentActionParam::Withdrawn,
+        }
+    }
+}
+
+/// The body of a consent recording request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, ToSchema)]
+pub struct RecordConsentRequestBody {
+    pub consent_type: ConsentTypeParam,
+    pub action: ConsentActionParam,
+    /// Version of the terms or policy the user was shown.
+    pub version: String,
+    /// Where the consent was collected (e.g. `signup_form`).
+    pub source: String,
+}
+
+/// A consent record, in responses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ConsentResponseData {
+    pub consent_type: ConsentTypeParam,
+    pub action: ConsentActionParam,
+    pub version: String,
+    pub source: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl From<Consent> for ConsentResponseData {
+    fn from(consent: Consent) -> Self {
+        Self {
+            consent_type: consent.consent_type.into(),
+            action: consent.action.into(),
+            version: consent.version,
+            source: consent.source,
+            recorded_at: consent.recorded_at.into(),
+        }
+    }
+}
+
+/// Record a grant or withdrawal of consent by a User. Requires the `consents:write` scope.
+///
+/// # Responses
+///
+/// - 201 Created: the consent was recorded.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `consents:write` scope.
+/// - 404 Not Found: the User was not found.
+/// - 422 Unprocessable entity: the version or source is empty or too long.
+/// - 500 Internal server error: Failed to record consent.
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/{id}/consents",
+    params(("id" = String, Path, description = "ID of the User")),
+    request_body = RecordConsentRequestBody,
+    responses(
+        (status = 201, description = "The consent was recorded.", body = ApiResponseBody<ConsentResponseData>),
+        (status = 401, description = "The bearer token is missing or invalid.", body = ApiResponseBody<ApiErrorData>),
+        (status = 403, description = "The token lacks the `consents:write` scope.", body = ApiResponseBody<ApiErrorData>),
+        (status = 404, description = "The User was not found.", body = ApiResponseBody<ApiErrorData>),
+        (status = 422, description = "The version or source is empty or too long.", body = ApiResponseBody<ApiErrorData>),
+        (status = 500, description = "Failed to record consent.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn record_consent(
+    State(state): State<ConsentState>,
+    Path(id): Path<String>,
+    Json(body): Json<RecordConsentRequestBody>,
+) -> Result<ApiSuccess<ConsentResponseData>, ApiError> {
+    if body.version.is_empty() || body.version.len() > MAX_VERSION_LENGTH {
+        return Err(ApiError::UnprocessableEntity(format!("version must be 1 to {} characters", MAX_VERSION_LENGTH)));
+    }
+    if body.source.is_empty() || body.source.len() > MAX_SOURCE_LENGTH {
+        return Err(ApiError::UnprocessableEntity(format!("source must be 1 to {} characters", MAX_SOURCE_LENGTH)));
+    }
+
+    let record_consent = RecordConsent {
+        user_id: id,
+        consent_type: body.consent_type.into(),
+        action: body.action.into(),
+        version: body.version,
+        source: body.source,
+    };
+
+    state
+        .consent_service
+        .record_consent(record_consent)
+        .await
+        .map_err(ApiError::from)
+        .map(|consent| ApiSuccess::new(StatusCode::CREATED, ConsentResponseData::from(consent)))
+}
+
+/// List the consent records of a User, oldest first.
+///
+/// The consent in effect for each type is the action of its last record.
+///
+/// # Responses
+///
+/// - 200 OK: the consent records of the User.
+/// - 404 Not Found: the User was not found.
+/// - 500 Internal server error: Failed to list consents.
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{id}/consents",
+    params(("id" = String, Path, description = "ID of the User")),
+    responses(
+        (status = 200, description = "The consent records of the User.", body = ApiResponseBody<Vec<ConsentResponseData>>),
+        (status = 404, description = "The User was not found.", body = ApiResponseBody<ApiErrorData>),
+        (status = 500, description = "Failed to list consents.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn list_consents(
+    State(state): State<ConsentState>,
+    Path(id): Path<String>,
+) -> Result<ApiSuccess<Vec<ConsentResponseData>>, ApiError> {
+    state
+        .consent_service
+        .list_consents(id)
+        .await
+        .map_err(ApiError::from)
+        .map(|consents| ApiSuccess::new(StatusCode::OK, consents.into_iter().map(ConsentResponseData::from).collect()))
+}
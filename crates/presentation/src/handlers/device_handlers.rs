@@ -0,0 +1,336 @@
+use std::sync::Arc;
+
+use axum::extract::{FromRef, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::{Form, Json};
+use serde::{Deserialize, Serialize};
+
+use application::flows::device_service::DeviceServiceTrait;
+use application::ports::auth::{all_scopes, AuthError};
+use application::ports::device::DeviceGrantError;
+
+use domain::outcome::ErrorClass;
+
+use crate::handlers::user_handlers::ApiError;
+use crate::middleware::auth::{AuthState, Devices, RequireScope};
+
+/// Grant type of token requests exchanging a device code (RFC 8628, section 3.4).
+pub const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+/// Maximum length of a user code accepted from the verification page URL.
+const MAX_USER_CODE_LENGTH: usize = 16;
+
+/// The dependencies of the device authorization handlers.
+#[derive(Clone)]
+pub struct DeviceState {
+    pub device_service: Arc<dyn DeviceServiceTrait + Send + Sync + 'static>,
+    /// Public URL of the verification page, returned to devices to show to their user.
+    pub verification_uri: String,
+}
+
+/// The state the device authorization routes are served with: the pending requests, and the
+/// authentication of the users approving them.
+#[derive(Clone)]
+pub struct DeviceRoutesState {
+    pub device: DeviceState,
+    pub auth: AuthState,
+}
+
+impl FromRef<DeviceRoutesState> for DeviceState {
+    fn from_ref(state: &DeviceRoutesState) -> Self {
+        state.device.clone()
+    }
+}
+
+impl FromRef<DeviceRoutesState> for AuthState {
+    fn from_ref(state: &DeviceRoutesState) -> Self {
+        state.auth.clone()
+    }
+}
+
+/// An error of the device authorization or token endpoint, in the OAuth 2.0 format
+/// (RFC 6749, section 5.2) expected by device clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OAuthErrorData {
+    pub error: &'static str,
+    pub error_description: &'static str,
+}
+
+impl OAuthErrorData {
+    pub(crate) fn new(error: &'static str, error_description: &'static str) -> Self {
+        Self { error, error_description }
+    }
+}
+
+impl IntoResponse for OAuthErrorData {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, [(header::CACHE_CONTROL, "no-store")], Json(self)).into_response()
+    }
+}
+
+fn oauth_error_response(e: DeviceGrantError) -> Response {
+    let description = match e {
+        DeviceGrantError::AuthorizationPending => "The user has not approved the device yet",
+        DeviceGrantError::SlowDown => "Polling too fast, the interval is increased by 5 seconds",
+        DeviceGrantError::AccessDenied => "The user denied the device",
+        DeviceGrantError::ExpiredToken => "The device code expired",
+        DeviceGrantError::InvalidGrant | DeviceGrantError::InvalidUserCode => "Unknown device code",
+        DeviceGrantError::Unavailable => {
+            return ApiError::InternalServerError("Failed to process the device authorization".to_string()).into_response();
+        }
+    };
+    let error = match e {
+        DeviceGrantError::InvalidUserCode => "invalid_grant",
+        e => e.class(),
+    };
+    OAuthErrorData::new(error, description).into_response()
+}
+
+/// The form of a device authorization request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DeviceCodeForm {
+    #[serde(default)]
+    pub client_id: String,
+}
+
+/// The response to a device authorization request (RFC 8628, section 3.2).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeviceCodeResponseData {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// The verification page with the user code filled in, e.g. to show as a QR code.
+    pub verification_uri_complete: String,
+    /// Lifetime of the codes, in seconds.
+    pub expires_in: u64,
+    /// Minimum time to wait between polls, in seconds.
+    pub interval: u64,
+}
+
+/// The form of a token request of a device.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct DeviceTokenForm {
+    #[serde(default)]
+    pub grant_type: String,
+    #[serde(default)]
+    pub device_code: String,
+    #[serde(default)]
+    pub client_id: String,
+}
+
+/// The response to a successful token request (RFC 6749, section 5.1).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeviceTokenResponseData {
+    pub access_token: String,
+    pub token_type: &'static str,
+    /// Lifetime of the access token, in seconds.
+    pub expires_in: u64,
+}
+
+/// The query of the verification page.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct VerificationQuery {
+    pub user_code: Option<String>,
+}
+
+/// The form posted by the verification page.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct VerificationForm {
+    pub user_code: String,
+    pub email: String,
+    pub password: String,
+    /// `approve` or `deny`.
+    pub action: String,
+}
+
+/// The body of a request approving or denying a device with a bearer token.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ApproveDeviceRequestBody {
+    pub user_code: String,
+    pub approved: bool,
+}
+
+/// Start the authorization of a device, e.g. a CLI tool, issuing its device and user codes.
+///
+/// The request is form-encoded and the response is not wrapped in the usual envelope, as
+/// expected by OAuth 2.0 device clients.
+///
+/// # Responses
+///
+/// - 200 OK: the body contains the codes, the verification page and the polling interval.
+/// - 400 Bad request: the `client_id` is missing (`invalid_request`).
+/// - 500 Internal server error: Failed to issue the codes.
+pub async fn device_code(State(state): State<DeviceState>, Form(form): Form<DeviceCodeForm>) -> Response {
+    if form.client_id.is_empty() {
+        return OAuthErrorData::new("invalid_request", "client_id is required").into_response();
+    }
+
+    match state.device_service.start(form.client_id).await {
+        Ok(codes) => {
+            let data = DeviceCodeResponseData {
+                verification_uri_complete: format!("{}?user_code={}", state.verification_uri, codes.user_code),
+                verification_uri: state.verification_uri,
+                device_code: codes.device_code,
+                user_code: codes.user_code,
+                expires_in: codes.expires_in.as_secs(),
+                interval: codes.interval.as_secs(),
+            };
+            ([(header::CACHE_CONTROL, "no-store")], Json(data)).into_response()
+        }
+        Err(e) => oauth_error_response(e),
+    }
+}
+
+/// Poll for the access token of a device, once its user approved it on the verification page.
+///
+/// The request is form-encoded and the response is not wrapped in the usual envelope, as
+/// expected by OAuth 2.0 device clients.
+///
+/// # Responses
+///
+/// - 200 OK: the device was approved, the body contains the access token.
+/// - 400 Bad request: `authorization_pending` until the user decides, `slow_down` when polling
+///   faster than the interval, `access_denied`, `expired_token`, `invalid_grant` for unknown or
+///   already exchanged device codes, `unsupported_grant_type` or `invalid_request`.
+/// - 500 Internal server error: Failed to issue the token.
+pub async fn token(State(state): State<DeviceState>, Form(form): Form<DeviceTokenForm>) -> Response {
+    if form.grant_type != DEVICE_CODE_GRANT_TYPE {
+        return OAuthErrorData::new("unsupported_grant_type", "Only the device_code grant type is supported").into_response();
+    }
+    if form.device_code.is_empty() || form.client_id.is_empty() {
+        return OAuthErrorData::new("invalid_request", "device_code and client_id are required").into_response();
+    }
+
+    match state.device_service.exchange(form.device_code, form.client_id).await {
+        Ok(token) => {
+            let data = DeviceTokenResponseData {
+                access_token: token.token,
+                token_type: "Bearer",
+                expires_in: token.expires_in.as_secs(),
+            };
+            ([(header::CACHE_CONTROL, "no-store")], Json(data)).into_response()
+        }
+        Err(e) => oauth_error_response(e),
+    }
+}
+
+/// Verification page, where users enter the code shown by their device and log in to approve
+/// or deny it.
+///
+/// # Responses
+///
+/// - 200 OK: the verification form, with the `user_code` of the query filled in.
+pub async fn verification_page(Query(query): Query<VerificationQuery>) -> Html<String> {
+    Html(render_verification_page(query.user_code.as_deref().unwrap_or(""), None))
+}
+
+/// Approve or deny a device from the verification page, with the email and password of the user.
+///
+/// # Responses
+///
+/// - 200 OK: the device was approved or denied.
+/// - 400 Bad request: the user code is invalid, expired or already used.
+/// - 401 Unauthorized: the credentials are invalid.
+/// - 403 Forbidden: the user must log in with a passkey.
+/// - 500 Internal server error: Failed to authenticate the user.
+pub async fn verify(
+    State(auth): State<AuthState>,
+    State(state): State<DeviceState>,
+    Form(form): Form<VerificationForm>,
+) -> (StatusCode, Html<String>) {
+    let page = |status, message| (status, Html(render_verification_page(&form.user_code, Some(message))));
+
+    let principal = match auth.auth_service.login(form.email.clone(), form.password.clone(), all_scopes()).await {
+        Ok(token) => auth.auth_service.authenticate(&token.token).await,
+        Err(e) => Err(e),
+    };
+    let principal = match principal {
+        Ok(principal) => principal,
+        Err(AuthError::InvalidCredentials) => return page(StatusCode::UNAUTHORIZED, "Invalid email or password."),
+        Err(AuthError::PasskeyRequired) => {
+            return page(StatusCode::FORBIDDEN, "Your account requires a passkey: approve the device from the application.");
+        }
+        Err(_) => return page(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong, please try again."),
+    };
+
+    let approved = form.action == "approve";
+    let result = if approved {
+        state.device_service.approve(form.user_code.clone(), principal.user_id, principal.roles).await
+    } else {
+        state.device_service.deny(form.user_code.clone()).await
+    };
+    match result {
+        Ok(()) if approved => page(StatusCode::OK, "Device approved. You can return to it."),
+        Ok(()) => page(StatusCode::OK, "Device denied."),
+        Err(DeviceGrantError::InvalidUserCode) => page(StatusCode::BAD_REQUEST, "This code is invalid or expired."),
+        Err(_) => page(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong, please try again."),
+    }
+}
+
+/// Approve or deny a device as the authenticated user, e.g. from an application whose users log
+/// in with a passkey or single sign-on. Requires the `devices` scope, and is not allowed while
+/// impersonating.
+///
+/// # Responses
+///
+/// - 204 No Content: the device was approved or denied.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `devices` scope, or is an impersonation token.
+/// - 404 Not Found: the user code is invalid, expired or already used.
+/// - 500 Internal server error: Failed to record the decision.
+pub async fn approve(
+    State(state): State<DeviceState>,
+    user: RequireScope<Devices>,
+    Json(body): Json<ApproveDeviceRequestBody>,
+) -> Result<StatusCode, ApiError> {
+    if user.is_impersonated() {
+        return Err(ApiError::Forbidden("Devices cannot be approved while impersonating".to_string()));
+    }
+
+    let result = if body.approved {
+        let user = user.into_inner();
+        state.device_service.approve(body.user_code, user.user_id, user.roles).await
+    } else {
+        state.device_service.deny(body.user_code).await
+    };
+    result
+        .map(|_| StatusCode::NO_CONTENT)
+        .map_err(|e| match e {
+            DeviceGrantError::InvalidUserCode => ApiError::NotFound("Unknown or expired user code".to_string()),
+            _ => ApiError::InternalServerError("Failed to record the device decision".to_string()),
+        })
+}
+
+/// Renders the verification form. The user code is reduced to the characters codes are made
+/// of, so it can be embedded in the page as is.
+fn render_verification_page(user_code: &str, message: Option<&str>) -> String {
+    let user_code: String = user_code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .take(MAX_USER_CODE_LENGTH)
+        .collect();
+    let message = message.map(|message| format!("<p>{}</p>", message)).unwrap_or_default();
+
+    format!(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>Connect a device</title>
+</head>
+<body>
+  <h1>Connect a device</h1>
+  {message}
+  <form method="post">
+    <label>Code shown by the device <input name="user_code" value="{user_code}" autocomplete="off" required /></label>
+    <label>Email <input name="email" type="email" autocomplete="username" required /></label>
+    <label>Password <input name="password" type="password" autocomplete="current-password" required /></label>
+    <button name="action" value="approve">Approve</button>
+    <button name="action" value="deny">Deny</button>
+  </form>
+</body>
+</html>
+"##
+    )
+}
@@ -0,0 +1,99 @@
+use axum::extract::State;
+use axum::response::Html;
+use axum::Json;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::handlers::{admin_handlers, auth_handlers, consent_handlers, health_handlers, user_handlers};
+use crate::http::API_V1;
+use crate::routes::RouteCatalog;
+
+/// The OpenAPI description of the public HTTP API, generated from the handler annotations.
+/// The tags, scopes and deprecation of the operations are those of the routes, added when the
+/// spec is served (see [`RouteCatalog::document`]).
+///
+/// Admin, audit, SCIM, SAML, device and WebAuthn routes are left out: they are optional and meant for
+/// operators, identity providers, browsers and OAuth device clients, not API clients.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "rust-web-server-template", description = "HTTP API of the template web server.", license(name = "MIT")),
+    paths(
+        user_handlers::create_user,
+        user_handlers::create_users_bulk,
+        user_handlers::list_users,
+        user_handlers::search_users,
+        user_handlers::get_user,
+        user_handlers::update_user,
+        user_handlers::delete_user,
+        user_handlers::restore_user,
+        consent_handlers::record_consent,
+        consent_handlers::list_consents,
+        auth_handlers::login,
+        health_handlers::healthz,
+        health_handlers::readyz,
+    ),
+    components(schemas(admin_handlers::DependencyResponseData)),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "users", description = "Users management."),
+        (name = "consents", description = "Consent records of users."),
+        (name = "auth", description = "Authentication."),
+        (name = "health", description = "Liveness and readiness probes."),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the `bearer_auth` scheme of the routes requiring authentication.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
+    }
+}
+
+/// Swagger UI page rendering the spec served at `/api/v1/docs/openapi.json`.
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>rust-web-server-template API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/api/v1/docs/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
+/// Get the OpenAPI spec of the HTTP API.
+///
+/// # Responses
+///
+/// - 200 OK: the OpenAPI 3.1 document.
+pub async fn openapi_json(State(catalog): State<RouteCatalog>) -> Json<utoipa::openapi::OpenApi> {
+    let mut openapi = ApiDoc::openapi();
+    catalog.document(&mut openapi, API_V1);
+    Json(openapi)
+}
+
+/// Browse the HTTP API with Swagger UI.
+///
+/// The Swagger UI assets are loaded from the unpkg CDN by the browser.
+///
+/// # Responses
+///
+/// - 200 OK: the Swagger UI page.
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI_HTML)
+}
@@ -0,0 +1,262 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use application::flows::group_service::{DisabledGroupService, GroupServiceTrait};
+
+use domain::group::{error::GroupDomainError, model::{Group, GroupRoles, SaveGroup, UpdateMembers}};
+
+use crate::handlers::user_handlers::{parse_user_id, ApiError, ApiSuccess};
+
+/// The dependencies of the group handlers.
+#[derive(Clone)]
+pub struct GroupState {
+    pub group_service: Arc<dyn GroupServiceTrait + Send + Sync + 'static>,
+}
+
+impl Default for GroupState {
+    /// Groups disabled: there are none, and saving fails.
+    fn default() -> Self {
+        Self {
+            group_service: Arc::new(DisabledGroupService),
+        }
+    }
+}
+
+impl From<GroupDomainError> for ApiError {
+    fn from(e: GroupDomainError) -> Self {
+        match e {
+            GroupDomainError::GroupNotFound => {
+                Self::NotFound("Group not found".to_string())
+            }
+            GroupDomainError::UserNotFound => {
+                Self::NotFound("User not found".to_string())
+            }
+            GroupDomainError::UnknownMembers(ids) => {
+                let ids: Vec<String> = ids.iter().map(ToString::to_string).collect();
+                Self::UnprocessableEntity(format!("Users not found: {}", ids.join(", ")))
+            }
+            GroupDomainError::GroupSaveFailed => {
+                Self::InternalServerError("Failed to save group".to_string())
+            }
+            GroupDomainError::GroupListFailed => {
+                Self::InternalServerError("Failed to list groups".to_string())
+            }
+            GroupDomainError::GroupDeletionFailed => {
+                Self::InternalServerError("Failed to delete group".to_string())
+            }
+            GroupDomainError::MembershipUpdateFailed => {
+                Self::InternalServerError("Failed to update group members".to_string())
+            }
+        }
+    }
+}
+
+/// The body of a group creation or update request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SaveGroupRequestBody {
+    /// Roles granted to the members.
+    #[serde(default)]
+    pub granted_roles: Vec<String>,
+    /// Roles denied to the members, even when granted directly or by another group.
+    #[serde(default)]
+    pub denied_roles: Vec<String>,
+}
+
+/// A group, in responses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GroupResponseData {
+    pub name: String,
+    pub granted_roles: Vec<String>,
+    pub denied_roles: Vec<String>,
+}
+
+impl From<Group> for GroupResponseData {
+    fn from(group: Group) -> Self {
+        Self {
+            name: group.name,
+            granted_roles: group.granted_roles,
+            denied_roles: group.denied_roles,
+        }
+    }
+}
+
+/// The body of a membership update request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct UpdateMembersRequestBody {
+    /// Ids of the users to add.
+    #[serde(default)]
+    pub add: Vec<String>,
+    /// Ids of the users to remove.
+    #[serde(default)]
+    pub remove: Vec<String>,
+}
+
+/// The members of a group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MembersResponseData {
+    pub name: String,
+    pub user_ids: Vec<String>,
+}
+
+/// The groups of a User and the roles they grant and deny.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserGroupRolesResponseData {
+    pub id: String,
+    pub groups: Vec<String>,
+    /// Roles granted by a group and denied by none.
+    pub granted_roles: Vec<String>,
+    /// Roles denied by a group, removed from the roles of the User even when granted directly.
+    pub denied_roles: Vec<String>,
+}
+
+/// List all groups, ordered by name.
+///
+/// # Responses
+///
+/// - 200 OK: the groups.
+/// - 500 Internal server error: Failed to list groups.
+pub async fn list_groups(State(state): State<GroupState>) -> Result<ApiSuccess<Vec<GroupResponseData>>, ApiError> {
+    state
+        .group_service
+        .list_groups()
+        .await
+        .map_err(ApiError::from)
+        .map(|groups| ApiSuccess::new(StatusCode::OK, groups.into_iter().map(GroupResponseData::from).collect()))
+}
+
+/// Create a group, or replace the roles of an existing one.
+///
+/// # Responses
+///
+/// - 200 OK: the group was saved.
+/// - 400 Bad Request: the name or a role is invalid, or a role is both granted and denied.
+/// - 500 Internal server error: Failed to save group.
+pub async fn save_group(
+    State(state): State<GroupState>,
+    Path(name): Path<String>,
+    Json(body): Json<SaveGroupRequestBody>,
+) -> Result<ApiSuccess<GroupResponseData>, ApiError> {
+    let group = SaveGroup::new(name, body.granted_roles, body.denied_roles)?;
+
+    state
+        .group_service
+        .save_group(group)
+        .await
+        .map_err(ApiError::from)
+        .map(|group| ApiSuccess::new(StatusCode::OK, GroupResponseData::from(group)))
+}
+
+/// Get a group by name.
+///
+/// # Responses
+///
+/// - 200 OK: the group.
+/// - 404 Not Found: the group was not found.
+/// - 500 Internal server error: Failed to get the group.
+pub async fn get_group(State(state): State<GroupState>, Path(name): Path<String>) -> Result<ApiSuccess<GroupResponseData>, ApiError> {
+    state
+        .group_service
+        .get_group(name)
+        .await
+        .map_err(ApiError::from)
+        .map(|group| ApiSuccess::new(StatusCode::OK, GroupResponseData::from(group)))
+}
+
+/// Delete a group. Its members lose the roles it granted and denied.
+///
+/// # Responses
+///
+/// - 204 No Content: the group was deleted.
+/// - 404 Not Found: the group was not found.
+/// - 500 Internal server error: Failed to delete group.
+pub async fn delete_group(State(state): State<GroupState>, Path(name): Path<String>) -> Result<StatusCode, ApiError> {
+    state
+        .group_service
+        .delete_group(name)
+        .await
+        .map_err(ApiError::from)
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// List the members of a group, ordered by id.
+///
+/// # Responses
+///
+/// - 200 OK: the ids of the members.
+/// - 404 Not Found: the group was not found.
+/// - 500 Internal server error: Failed to list the members.
+pub async fn list_members(State(state): State<GroupState>, Path(name): Path<String>) -> Result<ApiSuccess<MembersResponseData>, ApiError> {
+    state
+        .group_service
+        .list_members(name.clone())
+        .await
+        .map_err(ApiError::from)
+        .map(|ids| {
+            ApiSuccess::new(
+                StatusCode::OK,
+                MembersResponseData {
+                    name,
+                    user_ids: ids.iter().map(ToString::to_string).collect(),
+                },
+            )
+        })
+}
+
+/// Add and remove many members of a group at once.
+///
+/// The update is atomic: when any user to add does not exist, no member is added or removed.
+/// Adding members twice and removing non-members are no-ops.
+///
+/// # Responses
+///
+/// - 200 OK: the members after the update.
+/// - 400 Bad Request: an id is not a UUID, a user is both added and removed, or more than 1000 users are given.
+/// - 404 Not Found: the group was not found.
+/// - 422 Unprocessable entity: users to add were not found.
+/// - 500 Internal server error: Failed to update group members.
+pub async fn update_members(
+    State(state): State<GroupState>,
+    Path(name): Path<String>,
+    Json(body): Json<UpdateMembersRequestBody>,
+) -> Result<ApiSuccess<MembersResponseData>, ApiError> {
+    let members = UpdateMembers::new(body.add, body.remove)?;
+    state.group_service.update_members(name.clone(), members).await?;
+
+    list_members(State(state), Path(name)).await
+}
+
+/// Get the groups of a User and the roles they grant and deny.
+///
+/// Tokens issued to the User carry the roles granted to them directly and by their groups,
+/// except the roles denied by any group.
+///
+/// # Responses
+///
+/// - 200 OK: the groups and roles of the User.
+/// - 404 Not Found: the User was not found.
+/// - 500 Internal server error: Failed to list the groups.
+pub async fn get_user_group_roles(
+    State(state): State<GroupState>,
+    Path(id): Path<String>,
+) -> Result<ApiSuccess<UserGroupRolesResponseData>, ApiError> {
+    state
+        .group_service
+        .group_roles(parse_user_id(&id)?)
+        .await
+        .map_err(ApiError::from)
+        .map(|GroupRoles { groups, granted, denied }| {
+            ApiSuccess::new(
+                StatusCode::OK,
+                UserGroupRolesResponseData {
+                    id,
+                    groups,
+                    granted_roles: granted,
+                    denied_roles: denied,
+                },
+            )
+        })
+}
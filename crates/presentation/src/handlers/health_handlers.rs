@@ -0,0 +1,78 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use application::ports::capability::DependencyStatus;
+use application::ports::health::HealthChecks;
+
+use crate::handlers::admin_handlers::DependencyResponseData;
+use crate::handlers::user_handlers::{ApiResponseBody, ApiSuccess};
+
+/// The response body data field of the health endpoints.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct HealthResponseData {
+    /// `up`, `down` or `disabled`.
+    #[schema(value_type = String)]
+    pub status: DependencyStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<DependencyResponseData>,
+}
+
+/// Liveness probe: the process is up and serving HTTP.
+///
+/// Dependencies are not checked, so a database outage does not get the server restarted.
+///
+/// # Responses
+///
+/// - 200 OK: the server is alive.
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    responses(
+        (status = 200, description = "The server is alive.", body = ApiResponseBody<HealthResponseData>)
+    )
+)]
+pub async fn healthz() -> ApiSuccess<HealthResponseData> {
+    ApiSuccess::new(
+        StatusCode::OK,
+        HealthResponseData {
+            status: DependencyStatus::Up,
+            checks: Vec::new(),
+        },
+    )
+}
+
+/// Readiness probe: every required dependency is reachable.
+///
+/// # Responses
+///
+/// - 200 OK: all dependencies are up.
+/// - 503 Service unavailable: at least one dependency is down, see `checks`.
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "All dependencies are up.", body = ApiResponseBody<HealthResponseData>),
+        (status = 503, description = "At least one dependency is down, see `checks`.", body = ApiResponseBody<HealthResponseData>)
+    )
+)]
+pub async fn readyz(State(health_checks): State<HealthChecks>) -> ApiSuccess<HealthResponseData> {
+    let checks: Vec<DependencyResponseData> = health_checks
+        .check_all()
+        .await
+        .into_iter()
+        .map(|(name, status)| DependencyResponseData { name, status })
+        .collect();
+
+    let ready = checks.iter().all(|check| check.status == DependencyStatus::Up);
+    let (status_code, status) = if ready {
+        (StatusCode::OK, DependencyStatus::Up)
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, DependencyStatus::Down)
+    };
+
+    ApiSuccess::new(status_code, HealthResponseData { status, checks })
+}
@@ -0,0 +1,52 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::Serialize;
+
+use crate::handlers::user_handlers::{ApiError, ApiSuccess};
+use crate::middleware::in_flight::{InFlightRequest, InFlightRequests};
+
+/// The dependencies of the in-flight request handlers.
+#[derive(Clone, Default)]
+pub struct InFlightState {
+    /// The registry of the requests being handled, not tracked when `None`.
+    pub requests: Option<InFlightRequests>,
+}
+
+/// The response body data field for the requests being handled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InFlightRequestsResponseData {
+    pub requests: Vec<InFlightRequest>,
+}
+
+/// List the `/api` requests being handled by this replica, the longest running first, with
+/// their route, principal and the time they have been running for.
+///
+/// # Responses
+///
+/// - 200 OK: the requests being handled, this one included.
+/// - 404 Not found: in-flight requests are not tracked.
+pub async fn list_requests(State(state): State<InFlightState>) -> Result<ApiSuccess<InFlightRequestsResponseData>, ApiError> {
+    let requests = state.requests.ok_or_else(not_tracked)?;
+    Ok(ApiSuccess::new(StatusCode::OK, InFlightRequestsResponseData { requests: requests.list() }))
+}
+
+/// Cancel a request being handled by this replica, by its id in the list of the requests.
+///
+/// The request is answered with 503 Service Unavailable, and its handler is stopped.
+///
+/// # Responses
+///
+/// - 204 No Content: the request was cancelled.
+/// - 404 Not found: the request is not being handled, or in-flight requests are not tracked.
+pub async fn cancel_request(State(state): State<InFlightState>, Path(id): Path<String>) -> Result<StatusCode, ApiError> {
+    let requests = state.requests.ok_or_else(not_tracked)?;
+    // Ids that are not numbers name no request
+    match id.parse() {
+        Ok(id) if requests.cancel(id) => Ok(StatusCode::NO_CONTENT),
+        _ => Err(ApiError::NotFound("Request is not in flight".to_string())),
+    }
+}
+
+fn not_tracked() -> ApiError {
+    ApiError::NotFound("In-flight requests are not tracked".to_string())
+}
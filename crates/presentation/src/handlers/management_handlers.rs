@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use axum::extract::{FromRef, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use application::ports::capability::Capabilities;
+use application::ports::health::HealthChecks;
+
+use crate::handlers::in_flight_handlers::InFlightState;
+use crate::handlers::slo_handlers::{SloState, SlosResponseData};
+use crate::handlers::user_handlers::ApiSuccess;
+use crate::http::API_V1;
+use crate::middleware::admin::AdminToken;
+use crate::middleware::sampling::Sampler;
+
+/// Name of the server reported by the `info` endpoint.
+const SERVER_NAME: &str = "rust-web-server-template";
+
+/// Endpoints listed by the index of the management routes, relative to their base path.
+const ENDPOINTS: [(&str, &str); 8] = [
+    ("health", "/health"),
+    ("liveness", "/health/liveness"),
+    ("readiness", "/health/readiness"),
+    ("info", "/info"),
+    ("metrics", "/metrics"),
+    ("loggers", "/loggers"),
+    ("routes", "/routes"),
+    ("dependencies", "/dependencies"),
+];
+
+/// The settings of the management routes, grouping the operational endpoints under a base path.
+#[derive(Clone)]
+pub struct ManagementState {
+    /// Path the routes are nested under, e.g. `/actuator`.
+    pub base_path: String,
+    /// Bearer token required by the routes other than health.
+    pub token: AdminToken,
+    /// Port of a separate listener serving only the management routes. They are served next to
+    /// the API when `None`.
+    pub port: Option<u16>,
+    /// When the server started, reported by `info` and `metrics`.
+    pub started_at: DateTime<Utc>,
+}
+
+impl ManagementState {
+    /// Creates the settings of management routes nested under `base_path`, served next to the
+    /// API, of a server started now.
+    pub fn new(base_path: impl Into<String>, token: AdminToken) -> Self {
+        Self { base_path: base_path.into(), token, port: None, started_at: Utc::now() }
+    }
+
+    fn uptime_secs(&self) -> u64 {
+        (Utc::now() - self.started_at).num_seconds().max(0) as u64
+    }
+}
+
+/// A module of routes mounted by the router, as listed by the `routes` endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MountedRoutes {
+    /// Name of the module, e.g. `users`.
+    pub module: &'static str,
+    /// Path the module is mounted at.
+    pub path: String,
+    /// Middleware preset the module is served with, `null` for the modules served with the
+    /// layers of the whole router only.
+    pub preset: Option<&'static str>,
+}
+
+/// The state the management routes are served with: their settings, the routes of the router,
+/// and the subsystems they report on.
+#[derive(Clone)]
+pub struct ManagementRoutesState {
+    pub management: ManagementState,
+    pub routes: Arc<[MountedRoutes]>,
+    pub sampler: Sampler,
+    pub capabilities: Capabilities,
+    pub health_checks: HealthChecks,
+    pub slos: SloState,
+    pub in_flight: InFlightState,
+}
+
+impl FromRef<ManagementRoutesState> for ManagementState {
+    fn from_ref(state: &ManagementRoutesState) -> Self {
+        state.management.clone()
+    }
+}
+
+impl FromRef<ManagementRoutesState> for Sampler {
+    fn from_ref(state: &ManagementRoutesState) -> Self {
+        state.sampler.clone()
+    }
+}
+
+impl FromRef<ManagementRoutesState> for Capabilities {
+    fn from_ref(state: &ManagementRoutesState) -> Self {
+        state.capabilities.clone()
+    }
+}
+
+impl FromRef<ManagementRoutesState> for HealthChecks {
+    fn from_ref(state: &ManagementRoutesState) -> Self {
+        state.health_checks.clone()
+    }
+}
+
+/// A management endpoint, as listed by the index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EndpointResponseData {
+    pub name: &'static str,
+    pub href: String,
+}
+
+/// The response body data field of the index of the management routes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EndpointsResponseData {
+    pub endpoints: Vec<EndpointResponseData>,
+}
+
+/// List the management endpoints, with their paths.
+///
+/// # Responses
+///
+/// - 200 OK: the endpoints.
+pub async fn index(State(management): State<ManagementState>) -> ApiSuccess<EndpointsResponseData> {
+    let endpoints = ENDPOINTS
+        .iter()
+        .map(|&(name, path)| EndpointResponseData { name, href: format!("{}{}", management.base_path, path) })
+        .collect();
+    ApiSuccess::new(StatusCode::OK, EndpointsResponseData { endpoints })
+}
+
+/// The response body data field of the `info` endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InfoResponseData {
+    pub name: &'static str,
+    pub version: &'static str,
+    /// Prefixes of the versions of the API served.
+    pub api_versions: Vec<&'static str>,
+    pub started_at: DateTime<Utc>,
+    pub uptime_secs: u64,
+}
+
+/// Describe the server: its name, version, API versions and uptime.
+///
+/// # Responses
+///
+/// - 200 OK: the description of the server.
+pub async fn info(State(management): State<ManagementState>) -> ApiSuccess<InfoResponseData> {
+    ApiSuccess::new(
+        StatusCode::OK,
+        InfoResponseData {
+            name: SERVER_NAME,
+            version: env!("CARGO_PKG_VERSION"),
+            api_versions: vec![API_V1],
+            started_at: management.started_at,
+            uptime_secs: management.uptime_secs(),
+        },
+    )
+}
+
+/// The response body data field of the `metrics` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricsResponseData {
+    pub uptime_secs: u64,
+    /// Requests being handled, omitted when in-flight requests are not tracked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_in_flight: Option<usize>,
+    /// State of the latency objectives, as reported by the admin routes.
+    pub slos: SlosResponseData,
+}
+
+/// Report the metrics of this replica: its uptime, the requests being handled and the state
+/// of the latency objectives.
+///
+/// # Responses
+///
+/// - 200 OK: the metrics.
+pub async fn metrics(State(state): State<ManagementRoutesState>) -> ApiSuccess<MetricsResponseData> {
+    ApiSuccess::new(
+        StatusCode::OK,
+        MetricsResponseData {
+            uptime_secs: state.management.uptime_secs(),
+            requests_in_flight: state.in_flight.requests.as_ref().map(|requests| requests.list().len()),
+            slos: SlosResponseData::from(&state.slos),
+        },
+    )
+}
+
+/// The response body data field of the `routes` endpoint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RoutesResponseData {
+    pub routes: Vec<MountedRoutes>,
+}
+
+/// List the modules of routes mounted by the router, with their paths and presets.
+///
+/// # Responses
+///
+/// - 200 OK: the modules of routes.
+pub async fn routes(State(state): State<ManagementRoutesState>) -> ApiSuccess<RoutesResponseData> {
+    ApiSuccess::new(StatusCode::OK, RoutesResponseData { routes: state.routes.to_vec() })
+}
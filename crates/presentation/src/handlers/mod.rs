@@ -0,0 +1,18 @@
+pub mod admin_handlers;
+pub mod audit_handlers;
+pub mod auth_handlers;
+pub mod clock_handlers;
+pub mod consent_handlers;
+pub mod device_handlers;
+pub mod docs_handlers;
+pub mod group_handlers;
+pub mod health_handlers;
+pub mod in_flight_handlers;
+pub mod management_handlers;
+pub mod saml_handlers;
+pub mod scim_handlers;
+pub mod slo_handlers;
+pub mod stats_handlers;
+pub mod tap_handlers;
+pub mod tenant_handlers;
+pub mod user_handlers;pub mod webauthn_handlers;
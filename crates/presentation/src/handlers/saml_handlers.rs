@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::Form;
+use serde::Deserialize;
+
+use application::flows::saml_service::SamlServiceTrait;
+use application::ports::auth::ALL_SCOPES;
+
+use crate::handlers::auth_handlers::LoginResponseData;
+use crate::handlers::user_handlers::{ApiError, ApiSuccess};
+
+/// Media type of SAML metadata documents.
+pub const SAML_METADATA_CONTENT_TYPE: &str = "application/samlmetadata+xml";
+
+/// The dependencies of the SAML single sign-on handlers.
+#[derive(Clone)]
+pub struct SamlState {
+    pub saml_service: Arc<dyn SamlServiceTrait + Send + Sync + 'static>,
+    /// Page the browser is redirected to after logging in, with the access token in the URL
+    /// fragment. The token is returned as JSON when `None`.
+    pub login_redirect_url: Option<String>,
+}
+
+/// The form the identity provider posts to the assertion consumer service.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct AssertionConsumerForm {
+    #[serde(rename = "SAMLResponse")]
+    pub saml_response: String,
+}
+
+/// Get the metadata of the service provider, to register it with the identity provider.
+///
+/// # Responses
+///
+/// - 200 OK: the body contains the SAML metadata document.
+pub async fn metadata(State(state): State<SamlState>) -> Response {
+    ([(header::CONTENT_TYPE, SAML_METADATA_CONTENT_TYPE)], state.saml_service.metadata()).into_response()
+}
+
+/// Start a login, redirecting the browser to the identity provider.
+///
+/// # Responses
+///
+/// - 303 See Other: the `Location` header holds the login URL of the identity provider.
+/// - 500 Internal server error: Failed to build the authentication request.
+pub async fn login(State(state): State<SamlState>) -> Result<Redirect, ApiError> {
+    let url = state.saml_service.login_url().map_err(ApiError::from)?;
+    Ok(Redirect::to(&url))
+}
+
+/// Assertion consumer service: exchange the response posted by the identity provider for a
+/// bearer access token.
+///
+/// # Responses
+///
+/// - 200 OK: the response is valid, the body contains the access token.
+/// - 303 See Other: the response is valid and a login redirect URL is configured, the access
+///   token is in the fragment of the `Location` header.
+/// - 401 Unauthorized: the response is invalid, or its user is unknown.
+/// - 500 Internal server error: Failed to look up the user or to issue the token.
+pub async fn assertion_consumer(State(state): State<SamlState>, Form(form): Form<AssertionConsumerForm>) -> Result<Response, ApiError> {
+    let token = state.saml_service.login(form.saml_response).await.map_err(ApiError::from)?;
+
+    let data = LoginResponseData {
+        access_token: token.token,
+        token_type: "Bearer",
+        expires_in: token.expires_in.as_secs(),
+        scope: ALL_SCOPES.join(" "),
+    };
+    Ok(match &state.login_redirect_url {
+        Some(url) => Redirect::to(&format!(
+            "{}#access_token={}&token_type={}&expires_in={}",
+            url, data.access_token, data.token_type, data.expires_in
+        ))
+        .into_response(),
+        None => ApiSuccess::new(StatusCode::OK, data).into_response(),
+    })
+}
@@ -0,0 +1,593 @@
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{FromRequest, Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use application::flows::user_service::UserServiceTrait;
+
+use domain::user::{error::UserDomainError, model::{CreateUser, Email, ListUsers, SortDirection, UpdateUser, User, UserId, UserSortField}, validation::ValidationErrors};
+
+use crate::handlers::user_handlers::{parse_user_id, UserState, MAX_PAGE_LIMIT};
+use crate::middleware::error_reporting::ServerErrorDetail;
+
+/// Media type of SCIM requests and responses (RFC 7644, section 3.1).
+pub const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+
+/// Schema of the core attributes of a SCIM User.
+pub const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+
+/// Schema of the User attributes with no SCIM core equivalent.
+pub const USER_EXTENSION_SCHEMA: &str = "urn:ietf:params:scim:schemas:extension:rustweb:2.0:User";
+
+const LIST_RESPONSE_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+
+const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+/// A SCIM error response (RFC 7644, section 3.12).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScimError {
+    status: StatusCode,
+    scim_type: Option<&'static str>,
+    detail: String,
+}
+
+impl ScimError {
+    fn new(status: StatusCode, scim_type: Option<&'static str>, detail: impl Into<String>) -> Self {
+        Self { status, scim_type, detail: detail.into() }
+    }
+
+    fn bad_request(scim_type: &'static str, detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, Some(scim_type), detail)
+    }
+}
+
+impl From<UserDomainError> for ScimError {
+    fn from(e: UserDomainError) -> Self {
+        match e {
+            UserDomainError::InvalidUser(errors) => Self::from(errors),
+            UserDomainError::WeakPassword(violations) => {
+                let violations: Vec<String> = violations.into_iter().map(|violation| format!("password {}", violation.message)).collect();
+                Self::bad_request("invalidValue", violations.join(", "))
+            }
+            UserDomainError::UserNotFound => Self::new(StatusCode::NOT_FOUND, None, "User not found"),
+            UserDomainError::UserAlreadyExists => Self::new(StatusCode::CONFLICT, Some("uniqueness"), "User already exists"),
+            UserDomainError::UserUnderLegalHold => Self::new(StatusCode::LOCKED, None, "User is under legal hold"),
+            UserDomainError::InvalidCredentials => Self::new(StatusCode::UNAUTHORIZED, None, "Invalid credentials"),
+            UserDomainError::UserCreationFailed(_) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, None, "Failed to create user"),
+            UserDomainError::UserReadFailed(_) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, None, "Failed to read user"),
+            UserDomainError::UserUpdateFailed(_) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, None, "Failed to update user"),
+            UserDomainError::UserDeletionFailed(_) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, None, "Failed to delete user"),
+            UserDomainError::UserListFailed(_) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, None, "Failed to list users"),
+            UserDomainError::UserVersionMismatch => Self::new(StatusCode::PRECONDITION_FAILED, None, "User was changed since it was read"),
+            UserDomainError::UserConstraintViolation(_) => Self::bad_request("invalidValue", "User violates a constraint of the storage"),
+            UserDomainError::UserChangeConflict(_) => Self::new(StatusCode::CONFLICT, None, "User was changed concurrently, retry the request"),
+            UserDomainError::StorageTimeout(_) => Self::new(StatusCode::SERVICE_UNAVAILABLE, None, "Storage timed out"),
+        }
+    }
+}
+
+impl From<ValidationErrors> for ScimError {
+    fn from(e: ValidationErrors) -> Self {
+        let errors: Vec<String> = e
+            .errors()
+            .iter()
+            .map(|error| format!("{} {}", scim_attribute(error.field), error.message))
+            .collect();
+        Self::bad_request("invalidValue", errors.join(", "))
+    }
+}
+
+/// The body of a SCIM error response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimErrorData {
+    pub schemas: [&'static str; 1],
+    /// The HTTP status code, as a string.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scim_type: Option<&'static str>,
+    pub detail: String,
+}
+
+impl IntoResponse for ScimError {
+    fn into_response(self) -> Response {
+        let server_error = self.status.is_server_error().then(|| self.detail.clone());
+        let detail = match &server_error {
+            Some(detail) => {
+                tracing::error!("{}", detail);
+                "Internal server error".to_string()
+            }
+            None => self.detail,
+        };
+        let body = ScimErrorData {
+            schemas: [ERROR_SCHEMA],
+            status: self.status.as_u16().to_string(),
+            scim_type: self.scim_type,
+            detail,
+        };
+
+        let mut response = Scim(self.status, body).into_response();
+        if let Some(detail) = server_error {
+            response.extensions_mut().insert(ServerErrorDetail(detail));
+        }
+        response
+    }
+}
+
+/// A SCIM response, served as `application/scim+json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scim<T>(pub StatusCode, pub T);
+
+impl<T: Serialize> IntoResponse for Scim<T> {
+    fn into_response(self) -> Response {
+        (self.0, [(header::CONTENT_TYPE, SCIM_CONTENT_TYPE)], Json(self.1)).into_response()
+    }
+}
+
+/// JSON request body of a SCIM route, `application/scim+json` or `application/json`.
+///
+/// Bodies that cannot be deserialized are rejected with a SCIM `invalidSyntax` error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScimJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ScimJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ScimError;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|rejection| ScimError::bad_request("invalidSyntax", rejection.body_text()))?;
+        Ok(Self(body))
+    }
+}
+
+/// The SCIM name of a field of the domain User.
+fn scim_attribute(field: &str) -> &str {
+    match field {
+        "name" => "displayName",
+        "email" => "userName",
+        field => field,
+    }
+}
+
+/// The components of the name of a SCIM User.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimName {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatted: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub given_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub family_name: Option<String>,
+}
+
+impl ScimName {
+    /// The full name, `formatted` or else the given and family names.
+    fn full_name(&self) -> Option<String> {
+        if let Some(formatted) = &self.formatted {
+            return Some(formatted.clone());
+        }
+        let parts: Vec<&str> = [&self.given_name, &self.family_name].into_iter().flatten().map(String::as_str).collect();
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+}
+
+/// The attributes of a User with no SCIM core equivalent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScimUserExtension {
+    pub age: Option<u8>,
+}
+
+/// An email address of a SCIM User.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ScimEmail {
+    pub value: String,
+    pub primary: bool,
+}
+
+/// Resource metadata of a SCIM User.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimMeta {
+    pub resource_type: &'static str,
+    pub location: String,
+}
+
+/// A User, as a SCIM resource.
+///
+/// `userName` is the email of the User, `displayName` its name, and `age` lives in the
+/// [`USER_EXTENSION_SCHEMA`] extension. Users cannot be deactivated, so `active` is always true.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserResponseData {
+    pub schemas: [&'static str; 2],
+    pub id: String,
+    pub user_name: String,
+    pub display_name: String,
+    pub name: ScimName,
+    pub emails: Vec<ScimEmail>,
+    pub active: bool,
+    #[serde(rename = "urn:ietf:params:scim:schemas:extension:rustweb:2.0:User")]
+    pub extension: ScimUserExtension,
+    pub meta: ScimMeta,
+}
+
+impl From<&User> for ScimUserResponseData {
+    fn from(user: &User) -> Self {
+        Self {
+            schemas: [USER_SCHEMA, USER_EXTENSION_SCHEMA],
+            id: user.id().to_string(),
+            user_name: user.email().to_string(),
+            display_name: user.name().to_string(),
+            name: ScimName {
+                formatted: Some(user.name().to_string()),
+                ..ScimName::default()
+            },
+            emails: vec![ScimEmail {
+                value: user.email().to_string(),
+                primary: true,
+            }],
+            active: true,
+            extension: ScimUserExtension { age: Some(user.age()) },
+            meta: ScimMeta {
+                resource_type: "User",
+                location: format!("/scim/v2/Users/{}", user.id()),
+            },
+        }
+    }
+}
+
+/// The body of a SCIM User creation request.
+///
+/// The name is taken from `displayName`, or else from `name`. Other attributes are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserRequestBody {
+    pub user_name: String,
+    pub display_name: Option<String>,
+    pub name: Option<ScimName>,
+    pub active: Option<bool>,
+    #[serde(rename = "urn:ietf:params:scim:schemas:extension:rustweb:2.0:User")]
+    pub extension: Option<ScimUserExtension>,
+}
+
+impl ScimUserRequestBody {
+    /// Converts the body into the domain creation of a User.
+    pub fn into_domain(self) -> Result<CreateUser, ScimError> {
+        if self.active == Some(false) {
+            return Err(ScimError::bad_request("invalidValue", "Users cannot be created inactive"));
+        }
+        let name = self
+            .display_name
+            .or_else(|| self.name.as_ref().and_then(ScimName::full_name))
+            .ok_or_else(|| ScimError::bad_request("invalidValue", "displayName is required"))?;
+        let age = self
+            .extension
+            .and_then(|extension| extension.age)
+            .ok_or_else(|| ScimError::bad_request("invalidValue", format!("age of the {} extension is required", USER_EXTENSION_SCHEMA)))?;
+
+        Ok(CreateUser::new(name, self.user_name, age)?)
+    }
+}
+
+/// A single operation of a SCIM PATCH request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ScimPatchOperation {
+    /// `add`, `replace` or `remove`, case-insensitive.
+    pub op: String,
+    pub path: Option<String>,
+    pub value: Option<Value>,
+}
+
+/// The body of a SCIM PATCH request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ScimPatchRequestBody {
+    #[serde(rename = "Operations", alias = "operations")]
+    pub operations: Vec<ScimPatchOperation>,
+}
+
+/// The changes to a User collected from the operations of a PATCH request.
+#[derive(Debug, Default)]
+struct UserChanges {
+    name: Option<String>,
+    email: Option<String>,
+    age: Option<u8>,
+}
+
+impl UserChanges {
+    /// Sets the attribute at `path` to `value`. Attribute names are case-insensitive, and
+    /// attributes with no User equivalent are ignored.
+    fn set(&mut self, path: &str, value: Value) -> Result<(), ScimError> {
+        let path = path.to_ascii_lowercase();
+        let extension = USER_EXTENSION_SCHEMA.to_ascii_lowercase();
+
+        match path.as_str() {
+            "username" => self.email = Some(string_value(&path, value)?),
+            "displayname" | "name.formatted" => self.name = Some(string_value(&path, value)?),
+            "name" => {
+                let name: ScimName = serde_json::from_value(value).map_err(|e| ScimError::bad_request("invalidValue", format!("name: {}", e)))?;
+                if let Some(full_name) = name.full_name() {
+                    self.name = Some(full_name);
+                }
+            }
+            "active" if !bool_value(&path, &value)? => {
+                return Err(ScimError::bad_request("mutability", "Users cannot be deactivated, delete them instead"));
+            }
+            path if path == extension => {
+                let Value::Object(attributes) = value else {
+                    return Err(ScimError::bad_request("invalidValue", format!("{} must be an object", USER_EXTENSION_SCHEMA)));
+                };
+                for (name, value) in attributes {
+                    self.set(&format!("{}:{}", extension, name), value)?;
+                }
+            }
+            path if path.strip_prefix(extension.as_str()) == Some(":age") => {
+                let age = value
+                    .as_u64()
+                    .and_then(|age| u8::try_from(age).ok())
+                    .ok_or_else(|| ScimError::bad_request("invalidValue", "age must be an integer between 0 and 255"))?;
+                self.age = Some(age);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn string_value(path: &str, value: Value) -> Result<String, ScimError> {
+    match value {
+        Value::String(value) => Ok(value),
+        _ => Err(ScimError::bad_request("invalidValue", format!("{} must be a string", path))),
+    }
+}
+
+/// Reads a boolean, also accepting the `"True"`/`"False"` strings some identity providers send.
+fn bool_value(path: &str, value: &Value) -> Result<bool, ScimError> {
+    match value {
+        Value::Bool(value) => Ok(*value),
+        Value::String(value) if value.eq_ignore_ascii_case("true") => Ok(true),
+        Value::String(value) if value.eq_ignore_ascii_case("false") => Ok(false),
+        _ => Err(ScimError::bad_request("invalidValue", format!("{} must be a boolean", path))),
+    }
+}
+
+impl ScimPatchRequestBody {
+    /// Converts the operations into the domain update of the User with the given id.
+    ///
+    /// Only `add` and `replace` are supported: every attribute of a User is required.
+    pub fn into_domain(self, id: UserId) -> Result<UpdateUser, ScimError> {
+        let mut changes = UserChanges::default();
+
+        for operation in self.operations {
+            match operation.op.to_ascii_lowercase().as_str() {
+                "add" | "replace" => {}
+                "remove" => return Err(ScimError::bad_request("mutability", "Attributes of users cannot be removed")),
+                op => return Err(ScimError::bad_request("invalidSyntax", format!("Unsupported patch operation {}", op))),
+            }
+            let value = operation
+                .value
+                .ok_or_else(|| ScimError::bad_request("invalidValue", "Patch operations require a value"))?;
+
+            match operation.path {
+                Some(path) => changes.set(&path, value)?,
+                None => {
+                    let Value::Object(attributes) = value else {
+                        return Err(ScimError::bad_request("invalidValue", "Patch operations without a path require an object value"));
+                    };
+                    for (path, value) in attributes {
+                        changes.set(&path, value)?;
+                    }
+                }
+            }
+        }
+
+        Ok(UpdateUser::new(id, changes.name, changes.email, changes.age)?)
+    }
+}
+
+/// The query parameters of a SCIM User list request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListQueryParams {
+    /// Only `userName eq "<value>"` is supported.
+    pub filter: Option<String>,
+    /// 1-based index of the first User returned, default 1.
+    pub start_index: Option<u64>,
+    /// Maximum number of Users returned, at most 100.
+    pub count: Option<u32>,
+}
+
+/// Parses a `userName eq "<value>"` filter into the value, refusing any other filter.
+pub fn parse_user_name_filter(filter: &str) -> Result<String, ScimError> {
+    let unsupported = || ScimError::bad_request("invalidFilter", "Only userName eq \"<value>\" filters are supported");
+
+    let mut parts = filter.trim().splitn(3, ' ');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(attribute), Some(operator), Some(value))
+            if attribute.eq_ignore_ascii_case("userName") && operator.eq_ignore_ascii_case("eq") =>
+        {
+            serde_json::from_str::<String>(value.trim()).map_err(|_| unsupported())
+        }
+        _ => Err(unsupported()),
+    }
+}
+
+/// A page of SCIM resources.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListResponseData<T> {
+    pub schemas: [&'static str; 1],
+    pub total_results: u64,
+    pub start_index: u64,
+    pub items_per_page: usize,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<T>,
+}
+
+impl<T> ScimListResponseData<T> {
+    fn new(resources: Vec<T>, total_results: u64, start_index: u64) -> Self {
+        Self {
+            schemas: [LIST_RESPONSE_SCHEMA],
+            total_results,
+            start_index,
+            items_per_page: resources.len(),
+            resources,
+        }
+    }
+}
+
+/// Provision a User.
+///
+/// # Responses
+///
+/// - 201 Created: the User was provisioned.
+/// - 400 Bad Request: an attribute is missing or invalid.
+/// - 409 Conflict: a User with the same `userName` already exists.
+/// - 500 Internal server error: Failed to create user.
+pub async fn create_user<S>(
+    State(state): State<UserState<S>>,
+    ScimJson(body): ScimJson<ScimUserRequestBody>,
+) -> Result<Scim<ScimUserResponseData>, ScimError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    let create_user = body.into_domain()?;
+
+    state
+        .user_service
+        .create_user(create_user)
+        .await
+        .map_err(ScimError::from)
+        .map(|user| Scim(StatusCode::CREATED, ScimUserResponseData::from(&user)))
+}
+
+/// Get a User by ID.
+///
+/// # Responses
+///
+/// - 200 OK: the User was found.
+/// - 404 Not Found: the User was not found.
+/// - 500 Internal server error: Failed to get user.
+pub async fn get_user<S>(
+    State(state): State<UserState<S>>,
+    Path(id): Path<String>,
+) -> Result<Scim<ScimUserResponseData>, ScimError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    state
+        .user_service
+        .get_user(parse_user_id(&id)?)
+        .await
+        .map_err(ScimError::from)
+        .map(|user| Scim(StatusCode::OK, ScimUserResponseData::from(&user)))
+}
+
+/// List Users, optionally filtered by `userName`.
+///
+/// # Responses
+///
+/// - 200 OK: the requested page of Users, with the total number of matching Users.
+/// - 400 Bad Request: the filter is not supported, or a query parameter could not be parsed.
+/// - 500 Internal server error: Failed to list users.
+pub async fn list_users<S>(
+    State(state): State<UserState<S>>,
+    params: Result<Query<ScimListQueryParams>, QueryRejection>,
+) -> Result<Scim<ScimListResponseData<ScimUserResponseData>>, ScimError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    let Query(params) = params.map_err(|rejection| ScimError::bad_request("invalidValue", rejection.body_text()))?;
+    let start_index = params.start_index.unwrap_or(1).max(1);
+    let count = params.count.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+
+    let (users, total) = match params.filter {
+        Some(filter) => {
+            // No User has a malformed email, so filtering on one matches nothing
+            let matching = match Email::parse(parse_user_name_filter(&filter)?) {
+                Ok(email) => match state.user_service.get_user_by_email(email).await {
+                    Ok(user) => vec![user],
+                    Err(UserDomainError::UserNotFound) => Vec::new(),
+                    Err(e) => return Err(e.into()),
+                },
+                Err(_) => Vec::new(),
+            };
+            let total = matching.len() as u64;
+            let users = matching.into_iter().skip((start_index - 1) as usize).take(count as usize).collect();
+            (users, total)
+        }
+        None => {
+            // SCIM allows a count of 0 to only get the total, pages hold at least one user.
+            let query = ListUsers {
+                limit: count.max(1),
+                offset: start_index - 1,
+                sort_by: UserSortField::Email,
+                direction: SortDirection::Asc,
+                after: None,
+            };
+            let page = state.user_service.list_users(query).await?;
+            (page.users.into_iter().take(count as usize).collect::<Vec<_>>(), page.total)
+        }
+    };
+
+    let resources = users.iter().map(ScimUserResponseData::from).collect();
+    Ok(Scim(StatusCode::OK, ScimListResponseData::new(resources, total, start_index)))
+}
+
+/// Update the attributes of a User.
+///
+/// # Responses
+///
+/// - 200 OK: the User was updated.
+/// - 400 Bad Request: an operation is not supported, or a value is invalid.
+/// - 404 Not Found: the User was not found.
+/// - 500 Internal server error: Failed to update user.
+pub async fn patch_user<S>(
+    State(state): State<UserState<S>>,
+    Path(id): Path<String>,
+    ScimJson(body): ScimJson<ScimPatchRequestBody>,
+) -> Result<Scim<ScimUserResponseData>, ScimError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    let update_user = body.into_domain(parse_user_id(&id)?)?;
+
+    state
+        .user_service
+        .update_user(update_user)
+        .await
+        .map_err(ScimError::from)
+        .map(|user| Scim(StatusCode::OK, ScimUserResponseData::from(&user)))
+}
+
+/// Deprovision a User.
+///
+/// # Responses
+///
+/// - 204 No Content: the User was deleted.
+/// - 404 Not Found: the User was not found.
+/// - 423 Locked: the User is under legal hold.
+/// - 500 Internal server error: Failed to delete user.
+pub async fn delete_user<S>(
+    State(state): State<UserState<S>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ScimError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    state
+        .user_service
+        .delete_user(parse_user_id(&id)?)
+        .await
+        .map_err(ScimError::from)
+        .map(|_| StatusCode::NO_CONTENT)
+}
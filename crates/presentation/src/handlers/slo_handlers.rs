@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::Serialize;
+
+use application::flows::slo_tracker::{SloReport, SloTracker};
+
+use crate::handlers::user_handlers::ApiSuccess;
+
+/// The dependencies of the SLO handlers.
+#[derive(Clone, Default)]
+pub struct SloState {
+    /// The tracker of the objectives, requests are not tracked when `None`.
+    pub tracker: Option<Arc<SloTracker>>,
+}
+
+/// The state of a latency objective over the rolling window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SloResponseData {
+    pub name: String,
+    /// Method of the requests covered, all requests when `null`.
+    pub method: Option<String>,
+    /// Latency a request must be served within to be good.
+    pub latency_ms: u64,
+    /// Share of the requests that must be good, e.g. `0.99`.
+    pub target: f64,
+    /// Requests covered in the window.
+    pub total: u64,
+    /// Requests served within the latency, without a server error.
+    pub good: u64,
+    /// Share of the requests that were good, `null` without requests.
+    pub compliance: Option<f64>,
+    /// Share of the error budget of the window left, negative once the objective is missed.
+    pub error_budget_remaining: f64,
+    /// Rate the error budget is consumed at over the burn window, 1 consuming it exactly by
+    /// the end of the window.
+    pub burn_rate: f64,
+}
+
+impl From<SloReport> for SloResponseData {
+    fn from(report: SloReport) -> Self {
+        Self {
+            name: report.objective.name,
+            method: report.objective.method,
+            latency_ms: report.objective.latency.as_millis() as u64,
+            target: report.objective.target,
+            total: report.total,
+            good: report.good,
+            compliance: report.compliance,
+            error_budget_remaining: report.error_budget_remaining,
+            burn_rate: report.burn_rate,
+        }
+    }
+}
+
+/// The response body data field for the state of the objectives.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlosResponseData {
+    /// Rolling window of the compliance and the error budgets, omitted when not tracked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_secs: Option<u64>,
+    /// Window of the burn rates, omitted when not tracked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burn_window_secs: Option<u64>,
+    pub objectives: Vec<SloResponseData>,
+}
+
+impl From<&SloState> for SlosResponseData {
+    fn from(state: &SloState) -> Self {
+        match &state.tracker {
+            Some(tracker) => SlosResponseData {
+                window_secs: Some(tracker.window().as_secs()),
+                burn_window_secs: Some(tracker.burn_window().as_secs()),
+                objectives: tracker.report().into_iter().map(SloResponseData::from).collect(),
+            },
+            None => SlosResponseData { window_secs: None, burn_window_secs: None, objectives: Vec::new() },
+        }
+    }
+}
+
+/// Report the compliance of the `/api` requests with the latency objectives, and the
+/// consumption of their error budgets, over the rolling window of this replica.
+///
+/// # Responses
+///
+/// - 200 OK: the state of every objective, none when objectives are not configured.
+pub async fn get_slos(State(state): State<SloState>) -> ApiSuccess<SlosResponseData> {
+    ApiSuccess::new(StatusCode::OK, SlosResponseData::from(&state))
+}
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use chrono::{Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use application::ports::stats::{group_stats, DisabledUserStats, StatsPeriod, UserStats, UserStatsPort};
+
+use crate::handlers::user_handlers::{ApiError, ApiSuccess};
+
+/// Days reported when the request sets no `from`, the current day included.
+pub const DEFAULT_STATS_DAYS: u64 = 30;
+
+/// The dependencies of the stats handlers.
+#[derive(Clone)]
+pub struct StatsState {
+    pub stats: Arc<dyn UserStatsPort + Send + Sync + 'static>,
+}
+
+impl Default for StatsState {
+    /// Statistics disabled: no days are stored.
+    fn default() -> Self {
+        Self {
+            stats: Arc::new(DisabledUserStats),
+        }
+    }
+}
+
+/// A period the statistics are grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsGroupByParam {
+    Day,
+    Week,
+    Month,
+}
+
+impl From<StatsGroupByParam> for StatsPeriod {
+    fn from(group_by: StatsGroupByParam) -> Self {
+        match group_by {
+            StatsGroupByParam::Day => StatsPeriod::Day,
+            StatsGroupByParam::Week => StatsPeriod::Week,
+            StatsGroupByParam::Month => StatsPeriod::Month,
+        }
+    }
+}
+
+/// An encoding of the statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsFormatParam {
+    Json,
+    Csv,
+}
+
+/// The query parameters of a statistics request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StatsQueryParams {
+    /// First day reported (`YYYY-MM-DD`, UTC), default 29 days before `to`.
+    pub from: Option<NaiveDate>,
+    /// Last day reported, default the current day.
+    pub to: Option<NaiveDate>,
+    /// `day` (default), `week` (ISO weeks, starting on Monday) or `month`.
+    pub group_by: Option<StatsGroupByParam>,
+    /// `json` (default) or `csv`.
+    pub format: Option<StatsFormatParam>,
+}
+
+/// Numbers of Users of a period.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserStatsResponseData {
+    /// First day of the period.
+    pub period: NaiveDate,
+    /// Users created during the period.
+    pub signups: u64,
+    /// Users deleted during the period, and not restored since.
+    pub deletions: u64,
+    /// Users existing, and not deleted, at the end of the period.
+    pub active_users: u64,
+}
+
+impl From<&UserStats> for UserStatsResponseData {
+    fn from(stats: &UserStats) -> Self {
+        Self {
+            period: stats.period,
+            signups: stats.signups,
+            deletions: stats.deletions,
+            active_users: stats.active_users,
+        }
+    }
+}
+
+/// The response body data field for the statistics of Users.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatsResponseData {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// `day`, `week` or `month`.
+    pub group_by: &'static str,
+    /// The periods with statistics, in order. Days before the statistics were first projected
+    /// are left out.
+    pub periods: Vec<UserStatsResponseData>,
+}
+
+/// Report the numbers of Users signing up, deleted and active, by day, week or month, as JSON
+/// or CSV.
+///
+/// Statistics are materialized in the background, so the current day lags behind the Users.
+///
+/// # Responses
+///
+/// - 200 OK: the statistics of the periods from `from` to `to`, as JSON or as a CSV file.
+/// - 400 Bad Request: a query parameter could not be parsed.
+/// - 422 Unprocessable entity: `from` is after `to`.
+/// - 500 Internal server error: Failed to read the statistics.
+pub async fn get_stats(State(state): State<StatsState>, Query(params): Query<StatsQueryParams>) -> Result<Response, ApiError> {
+    let to = params.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = params.from.unwrap_or_else(|| to.checked_sub_days(Days::new(DEFAULT_STATS_DAYS - 1)).unwrap_or(NaiveDate::MIN));
+    if from > to {
+        return Err(ApiError::UnprocessableEntity("from must not be after to".to_string()));
+    }
+    let period = params.group_by.map_or(StatsPeriod::Day, StatsPeriod::from);
+
+    let days = state
+        .stats
+        .daily_stats(from, to)
+        .await
+        .map_err(|e| ApiError::InternalServerError(format!("failed to read user statistics: {:#}", e)))?;
+    let periods: Vec<UserStatsResponseData> = group_stats(&days, period).iter().map(UserStatsResponseData::from).collect();
+
+    Ok(match params.format {
+        Some(StatsFormatParam::Csv) => {
+            let disposition = format!("attachment; filename=\"user-stats-{}-{}.csv\"", from, to);
+            ([(header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()), (header::CONTENT_DISPOSITION, disposition)], stats_csv(&periods)).into_response()
+        }
+        Some(StatsFormatParam::Json) | None => ApiSuccess::new(StatusCode::OK, StatsResponseData { from, to, group_by: period.as_str(), periods }).into_response(),
+    })
+}
+
+/// Returns the CSV file of `periods`, with a header row.
+fn stats_csv(periods: &[UserStatsResponseData]) -> String {
+    let mut csv = String::from("period,signups,deletions,active_users\r\n");
+    for stats in periods {
+        csv.push_str(&format!("{},{},{},{}\r\n", stats.period, stats.signups, stats.deletions, stats.active_users));
+    }
+    csv
+}
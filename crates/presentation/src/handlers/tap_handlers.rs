@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::response::Response;
+use serde::Deserialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::handlers::user_handlers::ApiError;
+use crate::middleware::request_tap::{RequestSummary, RequestTap};
+
+/// The dependencies of the request tap handler.
+#[derive(Clone, Default)]
+pub struct TapState {
+    /// The feed of the requests, not published when `None`.
+    pub tap: Option<RequestTap>,
+}
+
+/// The query parameters of a request tap, selecting the requests streamed. Every parameter
+/// set must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct TapQueryParams {
+    /// Method of the requests, e.g. `POST`.
+    pub method: Option<String>,
+    /// Prefix of the paths of the requests, e.g. `/api/users`.
+    pub path_prefix: Option<String>,
+    /// Status of the responses, exact (e.g. `404`) or a class (e.g. `5xx`).
+    pub status: Option<String>,
+    /// Id of the user the requests were authenticated as.
+    pub principal: Option<String>,
+    /// Latency the requests were served in at least, in milliseconds.
+    pub min_latency_ms: Option<u64>,
+}
+
+/// Statuses of the responses a tap streams.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusFilter {
+    Exact(u16),
+    /// The hundreds of the statuses, e.g. 5 for `5xx`.
+    Class(u16),
+}
+
+/// The requests a tap streams, parsed from [`TapQueryParams`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TapFilter {
+    method: Option<String>,
+    path_prefix: Option<String>,
+    status: Option<StatusFilter>,
+    principal: Option<String>,
+    min_latency_ms: Option<u64>,
+}
+
+impl TryFrom<TapQueryParams> for TapFilter {
+    type Error = ApiError;
+
+    fn try_from(params: TapQueryParams) -> Result<Self, Self::Error> {
+        let status = match params.status.as_deref() {
+            None => None,
+            Some(status) => Some(match status.to_ascii_lowercase().strip_suffix("xx") {
+                Some(class) => StatusFilter::Class(class.parse().ok().filter(|class| (1..=5).contains(class)).ok_or_else(|| invalid_status(status))?),
+                None => StatusFilter::Exact(status.parse().ok().filter(|status| (100..=599).contains(status)).ok_or_else(|| invalid_status(status))?),
+            }),
+        };
+        Ok(Self { method: params.method, path_prefix: params.path_prefix, status, principal: params.principal, min_latency_ms: params.min_latency_ms })
+    }
+}
+
+fn invalid_status(status: &str) -> ApiError {
+    ApiError::UnprocessableEntity(format!("status must be a status like 404 or a class like 5xx, got {}", status))
+}
+
+impl TapFilter {
+    fn matches(&self, summary: &RequestSummary) -> bool {
+        self.method.as_deref().is_none_or(|method| method.eq_ignore_ascii_case(&summary.method))
+            && self.path_prefix.as_deref().is_none_or(|prefix| summary.path.starts_with(prefix))
+            && self.status.is_none_or(|status| match status {
+                StatusFilter::Exact(status) => summary.status == status,
+                StatusFilter::Class(class) => summary.status / 100 == class,
+            })
+            && self.principal.as_deref().is_none_or(|principal| summary.principal.as_deref() == Some(principal))
+            && self.min_latency_ms.is_none_or(|latency| summary.latency_ms >= latency)
+    }
+}
+
+/// Stream the summaries of the requests served by this replica over a WebSocket, as JSON
+/// text messages, from the connection on.
+///
+/// Summaries carry the method, route, path, status, latency and principal of the requests,
+/// without queries, headers or bodies. Those of slow connections falling behind are skipped.
+///
+/// # Responses
+///
+/// - 101 Switching Protocols: the stream of the requests matching the query parameters.
+/// - 404 Not found: the request tap is disabled.
+/// - 422 Unprocessable entity: `status` is neither a status nor a class of statuses.
+pub async fn watch_requests(State(state): State<TapState>, Query(params): Query<TapQueryParams>, upgrade: WebSocketUpgrade) -> Result<Response, ApiError> {
+    let tap = state.tap.ok_or_else(|| ApiError::NotFound("Request tap is disabled".to_string()))?;
+    let filter = TapFilter::try_from(params)?;
+
+    // Subscribed before the upgrade is answered, so the requests sent once connected are streamed
+    let summaries = tap.subscribe();
+    Ok(upgrade.on_upgrade(move |socket| stream_requests(socket, summaries, filter)))
+}
+
+async fn stream_requests(mut socket: WebSocket, mut summaries: broadcast::Receiver<Arc<RequestSummary>>, filter: TapFilter) {
+    loop {
+        tokio::select! {
+            summary = summaries.recv() => match summary {
+                Ok(summary) if filter.matches(&summary) => {
+                    let Ok(text) = serde_json::to_string(&*summary) else { continue };
+                    if socket.send(Message::Text(text.into())).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => tracing::debug!(skipped, "request tap fell behind, skipped requests"),
+                Err(RecvError::Closed) => return,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
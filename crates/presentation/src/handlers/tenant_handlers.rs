@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+
+use application::ports::tenant::{TenantBranding, TenantConfigPort, TenantSettings};
+
+use crate::handlers::user_handlers::{ApiError, ApiSuccess};
+use crate::middleware::tenant::is_valid_tenant_id;
+
+/// The dependencies of the tenant settings handlers.
+#[derive(Clone, Default)]
+pub struct TenantState {
+    /// The settings overridden by tenants, not supported when `None`.
+    pub config: Option<Arc<dyn TenantConfigPort + Send + Sync>>,
+}
+
+/// The body of a tenant settings update request, replacing all the settings of the tenant.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TenantSettingsRequestBody {
+    /// Requests each client of the tenant may send to each route per rate limit period.
+    pub rate_limit_requests: Option<u32>,
+    /// Features enabled or disabled for the tenant, by name.
+    #[serde(default)]
+    pub feature_flags: BTreeMap<String, bool>,
+    /// Secret signing the webhook deliveries of the tenant.
+    pub webhook_secret: Option<String>,
+    /// Branding of the error responses and e-mails of the tenant.
+    #[serde(default)]
+    pub branding: TenantBranding,
+}
+
+/// The settings of a tenant, in responses. The webhook secret is never returned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TenantSettingsResponseData {
+    pub tenant: String,
+    pub rate_limit_requests: Option<u32>,
+    pub feature_flags: BTreeMap<String, bool>,
+    /// Whether the tenant signs its webhook deliveries with its own secret.
+    pub webhook_secret_set: bool,
+    pub branding: TenantBranding,
+}
+
+impl TenantSettingsResponseData {
+    fn new(tenant: String, settings: TenantSettings) -> Self {
+        Self {
+            tenant,
+            rate_limit_requests: settings.rate_limit_requests,
+            feature_flags: settings.feature_flags,
+            webhook_secret_set: settings.webhook_secret.is_some(),
+            branding: settings.branding,
+        }
+    }
+}
+
+/// Get the settings a tenant overrides.
+///
+/// # Responses
+///
+/// - 200 OK: the settings of the tenant.
+/// - 404 Not Found: the tenant overrides no setting, or tenant settings are not supported.
+/// - 500 Internal server error: Failed to read tenant settings.
+pub async fn get_settings(State(state): State<TenantState>, Path(tenant): Path<String>) -> Result<ApiSuccess<TenantSettingsResponseData>, ApiError> {
+    let config = state.config.ok_or_else(not_supported)?;
+    match config.get(&tenant).await {
+        Ok(Some(settings)) => Ok(ApiSuccess::new(StatusCode::OK, TenantSettingsResponseData::new(tenant, settings))),
+        Ok(None) => Err(no_settings()),
+        Err(e) => Err(failed("read", e)),
+    }
+}
+
+/// Replace the settings a tenant overrides. Settings left out of the body are reset to the
+/// configuration of the deployment.
+///
+/// # Responses
+///
+/// - 200 OK: the settings were saved.
+/// - 422 Unprocessable Entity: the tenant id, the rate limit, the webhook secret or the branding is invalid.
+/// - 404 Not Found: tenant settings are not supported.
+/// - 500 Internal server error: Failed to save tenant settings.
+pub async fn save_settings(
+    State(state): State<TenantState>,
+    Path(tenant): Path<String>,
+    Json(body): Json<TenantSettingsRequestBody>,
+) -> Result<ApiSuccess<TenantSettingsResponseData>, ApiError> {
+    let config = state.config.ok_or_else(not_supported)?;
+    if !is_valid_tenant_id(&tenant) {
+        return Err(ApiError::UnprocessableEntity("Tenant id must be 1 to 64 letters, digits, '.', '_' or '-'".to_string()));
+    }
+    // Limits are stored as 32-bit signed integers
+    if body.rate_limit_requests.is_some_and(|requests| requests == 0 || requests > i32::MAX as u32) {
+        return Err(ApiError::UnprocessableEntity(format!("Rate limit must be between 1 and {}", i32::MAX)));
+    }
+    if body.webhook_secret.as_deref().is_some_and(str::is_empty) {
+        return Err(ApiError::UnprocessableEntity("Webhook secret must not be empty".to_string()));
+    }
+    validate_branding(&body.branding)?;
+
+    let settings = TenantSettings {
+        rate_limit_requests: body.rate_limit_requests,
+        feature_flags: body.feature_flags,
+        webhook_secret: body.webhook_secret,
+        branding: body.branding,
+    };
+    config.put(&tenant, settings.clone()).await.map_err(|e| failed("save", e))?;
+    tracing::info!(tenant.id = %tenant, "tenant settings saved");
+    Ok(ApiSuccess::new(StatusCode::OK, TenantSettingsResponseData::new(tenant, settings)))
+}
+
+/// Delete the settings a tenant overrides, so the configuration of the deployment applies to it.
+///
+/// # Responses
+///
+/// - 204 No Content: the settings were deleted.
+/// - 404 Not Found: the tenant overrides no setting, or tenant settings are not supported.
+/// - 500 Internal server error: Failed to delete tenant settings.
+pub async fn delete_settings(State(state): State<TenantState>, Path(tenant): Path<String>) -> Result<StatusCode, ApiError> {
+    let config = state.config.ok_or_else(not_supported)?;
+    match config.delete(&tenant).await {
+        Ok(true) => {
+            tracing::info!(tenant.id = %tenant, "tenant settings deleted");
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Ok(false) => Err(no_settings()),
+        Err(e) => Err(failed("delete", e)),
+    }
+}
+
+/// Checks that templates are given for error status codes, and that the support link is a web
+/// page or an e-mail address, as clients may follow it.
+fn validate_branding(branding: &TenantBranding) -> Result<(), ApiError> {
+    if branding.error_templates.iter().any(|(status, template)| !(400..=599).contains(status) || template.trim().is_empty()) {
+        return Err(ApiError::UnprocessableEntity("Error templates must be non-empty and given for status codes between 400 and 599".to_string()));
+    }
+    let is_link = |url: &str| ["https://", "http://", "mailto:"].iter().any(|scheme| url.len() > scheme.len() && url.starts_with(scheme));
+    if branding.support_url.as_deref().is_some_and(|url| !is_link(url)) {
+        return Err(ApiError::UnprocessableEntity("Support URL must be an http, https or mailto link".to_string()));
+    }
+    if [&branding.email_sender_name, &branding.email_footer].iter().any(|text| text.as_deref().is_some_and(|text| text.trim().is_empty())) {
+        return Err(ApiError::UnprocessableEntity("E-mail sender name and footer must not be empty".to_string()));
+    }
+    Ok(())
+}
+
+fn not_supported() -> ApiError {
+    ApiError::NotFound("Tenant settings are not supported".to_string())
+}
+
+fn no_settings() -> ApiError {
+    ApiError::NotFound("Tenant has no settings".to_string())
+}
+
+fn failed(action: &str, e: eyre::Report) -> ApiError {
+    tracing::error!("failed to {} tenant settings: {:#}", action, e);
+    ApiError::InternalServerError(format!("Failed to {} tenant settings", action))
+}
@@ -0,0 +1,1185 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::Json;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use application::flows::user_service::UserServiceTrait;
+use application::ports::auth::AuthError;
+use application::ports::purge::{user_surrogate_key, USERS_SURROGATE_KEY};
+
+use domain::user::{error::{StorageError, UserDomainError}, model::{CreateUser, ListUsers, SortDirection, UpdateUser, User, UserFilter, UserId, UserPage, UserPosition, UserSortField, UserSortKey}, validation::{validate_age, validate_email, validate_name, validate_password, PasswordViolation, ValidationErrors}};
+
+use crate::middleware::auth::AuthenticatedUser;
+use crate::middleware::error_reporting::ServerErrorDetail;
+use crate::middleware::http_cache::SurrogateKeys;
+use crate::middleware::validation::{Validate, ValidatedJson};
+use crate::pagination::{Cursor, CursorError, CursorKey, CursorQueryParams, Page};
+
+/// The dependencies of the user handlers.
+///
+/// Generic over the service, which is a trait object unless a concrete service is given.
+pub struct UserState<S: ?Sized = dyn UserServiceTrait + Send + Sync + 'static> {
+    pub user_service: Arc<S>,
+    /// Key signing the cursors of the lists of users.
+    pub cursor_key: CursorKey,
+}
+
+impl<S: ?Sized> Clone for UserState<S> {
+    fn clone(&self) -> Self {
+        Self {
+            user_service: self.user_service.clone(),
+            cursor_key: self.cursor_key.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiSuccess<T: Serialize + PartialEq>(StatusCode, Json<ApiResponseBody<T>>);
+
+impl<T> PartialEq for ApiSuccess<T>
+where
+    T: Serialize + PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 && self.1 .0 == other.1 .0
+    }
+}
+
+impl<T: Serialize + PartialEq> ApiSuccess<T> {
+    pub(crate) fn new(status: StatusCode, data: T) -> Self {
+        ApiSuccess(status, Json(ApiResponseBody::new(status, data)))
+    }
+}
+
+impl<T: Serialize + PartialEq> IntoResponse for ApiSuccess<T> {
+    fn into_response(self) -> Response {
+        (self.0, self.1).into_response()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApiError {
+    InternalServerError(String),
+    UnprocessableEntity(String),
+    NotFound(String),
+    /// The request conflicts with the current state of the resource (e.g. it already exists).
+    Conflict(String),
+    Unauthorized(String),
+    Forbidden(String),
+    Locked(String),
+    /// A condition of the request (e.g. `If-Match`) does not hold.
+    PreconditionFailed(String),
+    /// The request lacks a condition the route requires (e.g. `If-Match`).
+    PreconditionRequired(String),
+    /// The request body violates the constraints of its fields.
+    InvalidRequest(ValidationErrors),
+    /// The password of the request violates the rules of the password policy.
+    WeakPassword(Vec<PasswordViolation>),
+    /// A dependency of the server (e.g. the database) did not respond in time. The detail is
+    /// logged but not returned, like for [`ApiError::InternalServerError`].
+    ServiceUnavailable(String),
+}
+
+impl ApiError {
+    /// Returns the code identifying the kind of the error, reported by every protocol, e.g. in
+    /// the `code` of the error responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::InvalidRequest(_) => "invalid_request",
+            ApiError::WeakPassword(_) => "weak_password",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::UnprocessableEntity(_) => "unprocessable_entity",
+            ApiError::Locked(_) => "locked",
+            ApiError::PreconditionFailed(_) => "precondition_failed",
+            ApiError::PreconditionRequired(_) => "precondition_required",
+            ApiError::ServiceUnavailable(_) => "unavailable",
+            ApiError::InternalServerError(_) => "internal",
+        }
+    }
+}
+
+/// Returns the code of the errors responded with `status`, the code of the [`ApiError`]
+/// responded with it if any.
+fn status_error_code(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "invalid_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::REQUEST_TIMEOUT => "request_timeout",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::PRECONDITION_FAILED => "precondition_failed",
+        StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+        StatusCode::UNPROCESSABLE_ENTITY => "unprocessable_entity",
+        StatusCode::LOCKED => "locked",
+        StatusCode::PRECONDITION_REQUIRED => "precondition_required",
+        StatusCode::TOO_MANY_REQUESTS => "too_many_requests",
+        StatusCode::SERVICE_UNAVAILABLE => "unavailable",
+        status if status.is_server_error() => "internal",
+        _ => "invalid_request",
+    }
+}
+
+/// Returns `message` followed by the causes of `e`, for the logs of server faults.
+pub(crate) fn with_causes(message: &str, e: &dyn std::error::Error) -> String {
+    let mut detail = message.to_string();
+    let mut source = std::error::Error::source(e);
+    while let Some(cause) = source {
+        detail.push_str(": ");
+        detail.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    detail
+}
+
+impl From<UserDomainError> for ApiError {
+    fn from(e: UserDomainError) -> Self {
+        match &e {
+            // Client errors
+            UserDomainError::InvalidUser(errors) => Self::InvalidRequest(errors.clone()),
+            UserDomainError::WeakPassword(violations) => Self::WeakPassword(violations.clone()),
+            UserDomainError::InvalidCredentials => {
+                Self::Unauthorized("Invalid credentials".to_string())
+            }
+            UserDomainError::UserNotFound => {
+                Self::NotFound("User not found".to_string())
+            }
+            UserDomainError::UserAlreadyExists => {
+                Self::Conflict("User already exists".to_string())
+            }
+            UserDomainError::UserUnderLegalHold => {
+                Self::Locked("User is under legal hold".to_string())
+            }
+            UserDomainError::UserVersionMismatch => {
+                Self::PreconditionFailed("User was changed since it was read".to_string())
+            }
+            UserDomainError::UserConstraintViolation(_) => {
+                Self::UnprocessableEntity("User violates a constraint of the storage".to_string())
+            }
+            UserDomainError::UserChangeConflict(_) => {
+                Self::Conflict("User was changed concurrently, retry the request".to_string())
+            }
+            // Server faults
+            UserDomainError::UserCreationFailed(_) => {
+                Self::InternalServerError(with_causes("Failed to create user", &e))
+            }
+            UserDomainError::UserReadFailed(_) => {
+                Self::InternalServerError(with_causes("Failed to read user", &e))
+            }
+            UserDomainError::UserUpdateFailed(_) => {
+                Self::InternalServerError(with_causes("Failed to update user", &e))
+            }
+            UserDomainError::UserDeletionFailed(_) => {
+                Self::InternalServerError(with_causes("Failed to delete user", &e))
+            }
+            UserDomainError::UserListFailed(_) => {
+                Self::InternalServerError(with_causes("Failed to list users", &e))
+            }
+            UserDomainError::StorageTimeout(_) => {
+                Self::ServiceUnavailable(with_causes("Storage timed out", &e))
+            }
+        }
+    }
+}
+
+/// Parses the id of a User given by a client. Ids that are not UUIDs name no User, so they are
+/// reported like unknown ones.
+pub(crate) fn parse_user_id(id: &str) -> Result<UserId, UserDomainError> {
+    UserId::parse(id).map_err(|_| UserDomainError::UserNotFound)
+}
+
+/// The `ETag` header of a response returning `user`, the strong entity tag of its version.
+fn user_etag(user: &User) -> [(HeaderName, HeaderValue); 1] {
+    // A quoted number is always a valid header value
+    let etag = HeaderValue::try_from(format!("\"{}\"", user.version())).unwrap_or_else(|_| HeaderValue::from_static("\"\""));
+    [(header::ETAG, etag)]
+}
+
+/// Parses the `If-Match` header of an update into the version the User must be at, or `None`
+/// for `*`, which matches any version.
+///
+/// The header is required, so clients cannot overwrite the changes of each other by mistake.
+/// An entity tag other than a single strong one of a version matches no version of the User.
+fn expected_version(headers: &HeaderMap) -> Result<Option<u64>, ApiError> {
+    let if_match = headers
+        .get(header::IF_MATCH)
+        .ok_or_else(|| ApiError::PreconditionRequired("If-Match header with the ETag of the User is required".to_string()))?;
+    match if_match.to_str().map(str::trim) {
+        Ok("*") => Ok(None),
+        Ok(tag) => tag
+            .strip_prefix('"')
+            .and_then(|tag| tag.strip_suffix('"'))
+            .and_then(|version| version.parse().ok())
+            .map(Some)
+            .ok_or_else(|| ApiError::PreconditionFailed("If-Match matches no version of the User".to_string())),
+        Err(_) => Err(ApiError::PreconditionFailed("If-Match matches no version of the User".to_string())),
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(e: ValidationErrors) -> Self {
+        Self::InvalidRequest(e)
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::InvalidCredentials => Self::Unauthorized("Invalid credentials".to_string()),
+            AuthError::InvalidToken => Self::Unauthorized("Invalid or expired token".to_string()),
+            AuthError::UnknownSigningKey => Self::Unauthorized("Token signed by an unknown key".to_string()),
+            AuthError::KeySetUnavailable => Self::Unauthorized("Signing keys of the token issuer are unavailable".to_string()),
+            AuthError::PasskeyRequired => Self::Forbidden("Log in with a passkey".to_string()),
+            AuthError::Unavailable => Self::InternalServerError("Authentication is unavailable".to_string()),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        use ApiError::*;
+
+        match self {
+            InternalServerError(e) => {
+                tracing::error!("{}", e);
+                let mut response = (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiResponseBody::new_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Internal server error".to_string(),
+                    )),
+                )
+                    .into_response();
+                response.extensions_mut().insert(ServerErrorDetail(e));
+                response
+            }
+            ServiceUnavailable(e) => {
+                tracing::error!("{}", e);
+                let mut response = (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(ApiResponseBody::new_error(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "Service unavailable".to_string(),
+                    )),
+                )
+                    .into_response();
+                response.extensions_mut().insert(ServerErrorDetail(e));
+                response
+            }
+            UnprocessableEntity(message) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ApiResponseBody::new_error(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    message,
+                )),
+            )
+                .into_response(),
+            NotFound(message) => (
+                StatusCode::NOT_FOUND,
+                Json(ApiResponseBody::new_error(
+                    StatusCode::NOT_FOUND,
+                    message,
+                )),
+            )
+                .into_response(),
+            Conflict(message) => (
+                StatusCode::CONFLICT,
+                Json(ApiResponseBody::new_error(
+                    StatusCode::CONFLICT,
+                    message,
+                )),
+            )
+                .into_response(),
+            Unauthorized(message) => (
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Bearer")],
+                Json(ApiResponseBody::new_error(
+                    StatusCode::UNAUTHORIZED,
+                    message,
+                )),
+            )
+                .into_response(),
+            Forbidden(message) => (
+                StatusCode::FORBIDDEN,
+                Json(ApiResponseBody::new_error(
+                    StatusCode::FORBIDDEN,
+                    message,
+                )),
+            )
+                .into_response(),
+            Locked(message) => (
+                StatusCode::LOCKED,
+                Json(ApiResponseBody::new_error(
+                    StatusCode::LOCKED,
+                    message,
+                )),
+            )
+                .into_response(),
+            PreconditionFailed(message) => (
+                StatusCode::PRECONDITION_FAILED,
+                Json(ApiResponseBody::new_error(
+                    StatusCode::PRECONDITION_FAILED,
+                    message,
+                )),
+            )
+                .into_response(),
+            PreconditionRequired(message) => (
+                StatusCode::PRECONDITION_REQUIRED,
+                Json(ApiResponseBody::new_error(
+                    StatusCode::PRECONDITION_REQUIRED,
+                    message,
+                )),
+            )
+                .into_response(),
+            InvalidRequest(errors) => (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponseBody::new_validation_error(&errors)),
+            )
+                .into_response(),
+            WeakPassword(violations) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ApiResponseBody::new_password_policy_error(&violations)),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Generic response structure shared by all API responses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ApiResponseBody<T: Serialize + PartialEq> {
+    status_code: u16,
+    data: T,
+}
+
+impl<T: Serialize + PartialEq> ApiResponseBody<T> {
+    pub fn new(status_code: StatusCode, data: T) -> Self {
+        Self {
+            status_code: status_code.as_u16(),
+            data,
+        }
+    }
+}
+
+impl ApiResponseBody<ApiErrorData> {
+    /// Creates the body of an error response, whose code is the code of the errors responded
+    /// with `status_code`.
+    pub fn new_error(status_code: StatusCode, message: String) -> Self {
+        Self {
+            status_code: status_code.as_u16(),
+            data: ApiErrorData { code: status_error_code(status_code), message, errors: Vec::new(), support_url: None },
+        }
+    }
+
+    /// Creates the body of a 400 response listing the error of every invalid field.
+    pub fn new_validation_error(errors: &ValidationErrors) -> Self {
+        Self {
+            status_code: StatusCode::BAD_REQUEST.as_u16(),
+            data: ApiErrorData {
+                code: "invalid_request",
+                message: "Invalid request body".to_string(),
+                errors: errors
+                    .errors()
+                    .iter()
+                    .map(|error| FieldErrorData {
+                        field: error.field,
+                        message: error.message.clone(),
+                        rule: None,
+                    })
+                    .collect(),
+                support_url: None,
+            },
+        }
+    }
+
+    /// Creates the body of a 422 response listing every rule of the password policy the
+    /// password violates.
+    pub fn new_password_policy_error(violations: &[PasswordViolation]) -> Self {
+        Self {
+            status_code: StatusCode::UNPROCESSABLE_ENTITY.as_u16(),
+            data: ApiErrorData {
+                code: "weak_password",
+                message: "Password violates the password policy".to_string(),
+                errors: violations
+                    .iter()
+                    .map(|violation| FieldErrorData {
+                        field: "password",
+                        message: violation.message.clone(),
+                        rule: Some(violation.rule),
+                    })
+                    .collect(),
+                support_url: None,
+            },
+        }
+    }
+}
+
+/// The response data format for all error responses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ApiErrorData {
+    /// Code identifying the kind of the error, e.g. `not_found` or `unavailable`, which clients
+    /// can match on unlike the message.
+    pub code: &'static str,
+    pub message: String,
+    /// Errors of the invalid fields of the request, omitted when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldErrorData>,
+    /// Link to the support of the tenant of the request, see `TenantBranding`. Omitted when
+    /// the tenant sets none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub support_url: Option<String>,
+}
+
+/// The error of a single invalid request field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldErrorData {
+    pub field: &'static str,
+    pub message: String,
+    /// Rule of the password policy violated by the field, e.g. `min_length`, omitted for
+    /// other errors.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<&'static str>,
+}
+
+/// The body of a User creation request.
+#[derive(Clone, PartialEq, Eq, Deserialize, ToSchema)]
+pub struct CreateUserRequestBody {
+    pub name: String,
+    pub email: String,
+    pub age: u8,
+    /// The password the User logs in with, if any. Only its hash is stored.
+    #[serde(default)]
+    #[schema(format = Password, write_only, min_length = 8, max_length = 128)]
+    pub password: Option<String>,
+}
+
+impl std::fmt::Debug for CreateUserRequestBody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CreateUserRequestBody")
+            .field("name", &self.name)
+            .field("email", &self.email)
+            .field("age", &self.age)
+            .field("password", &self.password.as_ref().map(|_| "[redacted]"))
+            .finish()
+    }
+}
+
+impl CreateUserRequestBody {
+    /// Converts the body into the domain creation of a User, checking every field.
+    pub fn into_domain(self) -> Result<CreateUser, ValidationErrors> {
+        self.validate()?;
+        let create_user = CreateUser::new(self.name, self.email, self.age)?;
+        match self.password {
+            Some(password) => create_user.with_password(password),
+            None => Ok(create_user),
+        }
+    }
+}
+
+impl Validate for CreateUserRequestBody {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        errors.check("name", validate_name(&self.name));
+        errors.check("email", validate_email(&self.email));
+        errors.check("age", validate_age(self.age));
+        if let Some(password) = &self.password {
+            errors.check("password", validate_password(password));
+        }
+        errors.into_result(())
+    }
+}
+
+/// The response body data field for successful User creation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct CreateUserResponseData {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub age: u8,
+}
+
+/// The body of a User update request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, ToSchema)]
+pub struct UpdateUserRequestBody {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub age: Option<u8>,
+}
+
+/// The response body data field for successful User retrieval/update.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct UserResponseData {
+    pub id: String,
+    pub name: String,
+    pub email: String,
+    pub age: u8,
+}
+
+/// Default number of users per page.
+pub const DEFAULT_PAGE_LIMIT: u32 = 20;
+
+/// Maximum number of users per page.
+pub const MAX_PAGE_LIMIT: u32 = 100;
+
+/// Field the users of a list request are sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortParam {
+    Name,
+    Email,
+    Age,
+}
+
+/// Direction of the sort of a list request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrderParam {
+    Asc,
+    Desc,
+}
+
+/// The query parameters of a User list request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListUsersQueryParams {
+    /// Number of Users per page, 1 to 100, default 20.
+    pub limit: Option<u32>,
+    /// Number of Users skipped, default 0.
+    pub offset: Option<u64>,
+    /// Field the Users are sorted by, default `name`.
+    pub sort_by: Option<UserSortParam>,
+    /// Direction of the sort, default `asc`.
+    pub order: Option<SortOrderParam>,
+}
+
+/// The query parameters of a User search request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchUsersQueryParams {
+    /// Text the name contains, ignoring case and accents.
+    pub name: Option<String>,
+    /// Text the email contains, ignoring case and accents.
+    pub email: Option<String>,
+    /// Lowest age, inclusive.
+    pub min_age: Option<u8>,
+    /// Highest age, inclusive.
+    pub max_age: Option<u8>,
+    /// Number of Users per page, 1 to 100, default 20.
+    pub limit: Option<u32>,
+    /// Number of Users skipped, default 0.
+    pub offset: Option<u64>,
+}
+
+/// The response body data field for a page of Users.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct UserListResponseData {
+    pub users: Vec<UserResponseData>,
+    pub total: u64,
+    pub limit: u32,
+    pub offset: u64,
+    /// Cursor of the next page, to pass as `cursor`, `null` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Position of the page after a User within a cursor: the sort key and the id of the User, and
+/// the direction of the list, so a cursor only continues a list in the order it was issued for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserCursorPosition {
+    key: UserCursorKey,
+    desc: bool,
+    id: String,
+}
+
+/// Sort key of a User within a cursor, named after the field the list is sorted by.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum UserCursorKey {
+    Name(String),
+    Email(String),
+    Age(u8),
+}
+
+impl UserCursorPosition {
+    /// Returns the position of `user` in a list sorted by `sort_by` in `direction`.
+    pub fn of(user: &User, sort_by: UserSortField, direction: SortDirection) -> Self {
+        let key = match UserPosition::of(user, sort_by).key {
+            UserSortKey::Name(name) => UserCursorKey::Name(name),
+            UserSortKey::Email(email) => UserCursorKey::Email(email),
+            UserSortKey::Age(age) => UserCursorKey::Age(age),
+        };
+        Self { key, desc: direction == SortDirection::Desc, id: user.id().to_string() }
+    }
+
+    /// Decodes `cursor`, signed with `key`, into the position the requested page of a list
+    /// sorted by `sort_by` in `direction` starts after. Returns `None` without a cursor.
+    pub fn decode(cursor: Option<&str>, key: &CursorKey, sort_by: UserSortField, direction: SortDirection) -> Result<Option<UserPosition>, CursorError> {
+        let Some(cursor) = cursor else {
+            return Ok(None);
+        };
+        let position = Cursor::<Self>::decode(cursor, key)?.position;
+        let key = match (position.key, sort_by) {
+            (UserCursorKey::Name(name), UserSortField::Name) => UserSortKey::Name(name),
+            (UserCursorKey::Email(email), UserSortField::Email) => UserSortKey::Email(email),
+            (UserCursorKey::Age(age), UserSortField::Age) => UserSortKey::Age(age),
+            _ => return Err(CursorError::OtherList),
+        };
+        if position.desc != (direction == SortDirection::Desc) {
+            return Err(CursorError::OtherList);
+        }
+        let id = UserId::parse(&position.id).map_err(|_| CursorError::Malformed)?;
+        Ok(Some(UserPosition { key, id }))
+    }
+}
+
+impl ListUsersQueryParams {
+    /// Converts the parameters into the domain query, applying defaults and checking the limit.
+    pub fn into_domain(self) -> Result<ListUsers, ApiError> {
+        let limit = self.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if limit == 0 || limit > MAX_PAGE_LIMIT {
+            return Err(ApiError::UnprocessableEntity(format!("limit must be between 1 and {}", MAX_PAGE_LIMIT)));
+        }
+
+        Ok(ListUsers {
+            limit,
+            offset: self.offset.unwrap_or(0),
+            sort_by: match self.sort_by {
+                Some(UserSortParam::Name) | None => UserSortField::Name,
+                Some(UserSortParam::Email) => UserSortField::Email,
+                Some(UserSortParam::Age) => UserSortField::Age,
+            },
+            direction: match self.order {
+                Some(SortOrderParam::Asc) | None => SortDirection::Asc,
+                Some(SortOrderParam::Desc) => SortDirection::Desc,
+            },
+            after: None,
+        })
+    }
+}
+
+impl SearchUsersQueryParams {
+    /// Converts the parameters into the domain filter, applying defaults and checking the limit
+    /// and the age range. Blank texts match every User.
+    pub fn into_domain(self) -> Result<UserFilter, ApiError> {
+        let limit = self.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if limit == 0 || limit > MAX_PAGE_LIMIT {
+            return Err(ApiError::UnprocessableEntity(format!("limit must be between 1 and {}", MAX_PAGE_LIMIT)));
+        }
+        if self.min_age.zip(self.max_age).is_some_and(|(min_age, max_age)| min_age > max_age) {
+            return Err(ApiError::UnprocessableEntity("min_age must not be greater than max_age".to_string()));
+        }
+
+        let text = |text: Option<String>| text.map(|text| text.trim().to_string()).filter(|text| !text.is_empty());
+        Ok(UserFilter {
+            name: text(self.name),
+            email: text(self.email),
+            min_age: self.min_age,
+            max_age: self.max_age,
+            limit,
+            offset: self.offset.unwrap_or(0),
+            after: None,
+        })
+    }
+}
+
+impl UserListResponseData {
+    /// Returns the response of `page`, requested with `limit` and `offset` from a list sorted by
+    /// `sort_by` in `direction`, with the cursor of the next page signed with `key`.
+    fn new(page: UserPage, limit: u32, offset: u64, key: &CursorKey, sort_by: UserSortField, direction: SortDirection) -> Self {
+        let total = page.total;
+        let page = Page::new(page.users, limit as usize, key, |last| UserCursorPosition::of(last, sort_by, direction)).map(|user| UserResponseData::from(&user));
+        Self {
+            users: page.items,
+            total,
+            limit,
+            offset,
+            next_cursor: page.next_cursor,
+        }
+    }
+}
+
+impl From<&User> for CreateUserResponseData {
+    fn from(user: &User) -> Self {
+        Self {
+            id: user.id().to_string(),
+            name: user.name().to_string(),
+            email: user.email().to_string(),
+            age: user.age(),
+        }
+    }
+}
+
+impl Validate for UpdateUserRequestBody {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        if let Some(name) = &self.name {
+            errors.check("name", validate_name(name));
+        }
+        if let Some(email) = &self.email {
+            errors.check("email", validate_email(email));
+        }
+        if let Some(age) = self.age {
+            errors.check("age", validate_age(age));
+        }
+        errors.into_result(())
+    }
+}
+
+impl UpdateUserRequestBody {
+    /// Converts the body into the domain update of the User with the given id.
+    pub fn into_domain(self, id: UserId) -> Result<UpdateUser, ValidationErrors> {
+        UpdateUser::new(id, self.name, self.email, self.age)
+    }
+}
+
+impl From<&User> for UserResponseData {
+    fn from(user: &User) -> Self {
+        Self {
+            id: user.id().to_string(),
+            name: user.name().to_string(),
+            email: user.email().to_string(),
+            age: user.age(),
+        }
+    }
+}
+
+/// Create a new User.
+///
+/// # Responses
+///
+/// - 201 Created: the User was successfully created, with the `ETag` of its version.
+/// - 400 Bad Request: a field is invalid, the body lists the error of each invalid field.
+/// - 409 Conflict: A User with the same email already exists.
+/// - 422 Unprocessable entity: the password violates the password policy.
+/// - 500 Internal server error: Failed to create user.
+#[utoipa::path(
+    post,
+    path = "/api/v1/users",
+    request_body = CreateUserRequestBody,
+    responses(
+        (status = 201, description = "The User was successfully created.", body = ApiResponseBody<CreateUserResponseData>,
+            headers(("ETag" = String, description = "Entity tag of the version of the User"))),
+        (status = 400, description = "A field is invalid, the body lists the error of each invalid field.", body = ApiResponseBody<ApiErrorData>),
+        (status = 409, description = "A User with the same email already exists.", body = ApiResponseBody<ApiErrorData>),
+        (status = 422, description = "The password violates the password policy, the body lists every rule it violates.", body = ApiResponseBody<ApiErrorData>),
+        (status = 500, description = "Failed to create user.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn create_user<S>(
+    State(state): State<UserState<S>>,
+    ValidatedJson(body): ValidatedJson<CreateUserRequestBody>,
+) -> Result<([(HeaderName, HeaderValue); 1], ApiSuccess<CreateUserResponseData>), ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    let mut create_user = CreateUser::new(body.name, body.email, body.age)?;
+    if let Some(password) = body.password {
+        create_user = create_user.with_password(password)?;
+    }
+
+    state
+        .user_service
+        .create_user(create_user)
+        .await
+        .map_err(ApiError::from)
+        .map(|user| (user_etag(&user), ApiSuccess::new(StatusCode::CREATED, CreateUserResponseData::from(&user))))
+}
+
+/// Maximum number of Users of a bulk creation request.
+pub const MAX_BULK_USERS: usize = 1000;
+
+/// The outcome of the creation of one User of a bulk creation request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct BulkCreateUserResultData {
+    /// Position of the User in the request body.
+    pub index: usize,
+    /// The status the User would have been created with by `POST /api/users`: 201 when created.
+    pub status_code: u16,
+    /// The created User, omitted when the creation failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<CreateUserResponseData>,
+    /// Why the creation failed, omitted when the User was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ApiErrorData>,
+}
+
+impl BulkCreateUserResultData {
+    fn new(index: usize, result: Result<User, ApiError>) -> Self {
+        let code = result.as_ref().err().map_or("", ApiError::code);
+        let (status_code, message, errors) = match result {
+            Ok(user) => return Self { index, status_code: StatusCode::CREATED.as_u16(), user: Some(CreateUserResponseData::from(&user)), error: None },
+            Err(ApiError::InternalServerError(e)) => {
+                tracing::error!(index, "{}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string(), Vec::new())
+            }
+            Err(ApiError::ServiceUnavailable(e)) => {
+                tracing::error!(index, "{}", e);
+                (StatusCode::SERVICE_UNAVAILABLE, "Service unavailable".to_string(), Vec::new())
+            }
+            Err(ApiError::InvalidRequest(errors)) => (StatusCode::BAD_REQUEST, "Invalid user".to_string(), ApiResponseBody::new_validation_error(&errors).data.errors),
+            Err(ApiError::WeakPassword(violations)) => {
+                let data = ApiResponseBody::new_password_policy_error(&violations).data;
+                (StatusCode::UNPROCESSABLE_ENTITY, data.message, data.errors)
+            }
+            Err(ApiError::UnprocessableEntity(message)) => (StatusCode::UNPROCESSABLE_ENTITY, message, Vec::new()),
+            Err(ApiError::NotFound(message)) => (StatusCode::NOT_FOUND, message, Vec::new()),
+            Err(ApiError::Conflict(message)) => (StatusCode::CONFLICT, message, Vec::new()),
+            Err(ApiError::Unauthorized(message)) => (StatusCode::UNAUTHORIZED, message, Vec::new()),
+            Err(ApiError::Forbidden(message)) => (StatusCode::FORBIDDEN, message, Vec::new()),
+            Err(ApiError::Locked(message)) => (StatusCode::LOCKED, message, Vec::new()),
+            Err(ApiError::PreconditionFailed(message)) => (StatusCode::PRECONDITION_FAILED, message, Vec::new()),
+            Err(ApiError::PreconditionRequired(message)) => (StatusCode::PRECONDITION_REQUIRED, message, Vec::new()),
+        };
+        Self { index, status_code: status_code.as_u16(), user: None, error: Some(ApiErrorData { code, message, errors, support_url: None }) }
+    }
+}
+
+/// The response body data field for a bulk User creation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct BulkCreateUsersResponseData {
+    /// Number of Users created.
+    pub created: usize,
+    /// Number of Users that failed to be created.
+    pub failed: usize,
+    /// The outcome of every User, in the order of the request body.
+    pub results: Vec<BulkCreateUserResultData>,
+}
+
+/// Create Users in bulk, in batches, reporting the outcome of each one. Requires the
+/// `users:write` scope.
+///
+/// Users are validated and created like with `POST /api/users`, but a User failing to be
+/// created does not fail the others: the response lists the User created, or the error, of
+/// every item of the request body.
+///
+/// # Responses
+///
+/// - 200 OK: the outcome of every User, whether created or not.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `users:write` scope.
+/// - 422 Unprocessable entity: the body holds more than 1000 Users.
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/bulk",
+    request_body = Vec<CreateUserRequestBody>,
+    responses(
+        (status = 200, description = "The outcome of every User, whether created or not.", body = ApiResponseBody<BulkCreateUsersResponseData>),
+        (status = 401, description = "The bearer token is missing or invalid.", body = ApiResponseBody<ApiErrorData>),
+        (status = 403, description = "The token lacks the `users:write` scope.", body = ApiResponseBody<ApiErrorData>),
+        (status = 422, description = "The body holds more than 1000 Users.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn create_users_bulk<S>(
+    State(state): State<UserState<S>>,
+    Json(body): Json<Vec<CreateUserRequestBody>>,
+) -> Result<ApiSuccess<BulkCreateUsersResponseData>, ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    if body.len() > MAX_BULK_USERS {
+        return Err(ApiError::UnprocessableEntity(format!("at most {} users can be created at once", MAX_BULK_USERS)));
+    }
+
+    // Invalid Users are reported without being sent to the service
+    let mut results: Vec<Option<Result<User, ApiError>>> = Vec::with_capacity(body.len());
+    let mut users = Vec::with_capacity(body.len());
+    for item in body {
+        match item.into_domain() {
+            Ok(user) => {
+                users.push(user);
+                results.push(None);
+            }
+            Err(errors) => results.push(Some(Err(ApiError::InvalidRequest(errors)))),
+        }
+    }
+
+    let mut created = state.user_service.create_users_bulk(users).await.into_iter();
+    let results: Vec<BulkCreateUserResultData> = results
+        .into_iter()
+        .enumerate()
+        .map(|(index, result)| {
+            let result = result.unwrap_or_else(|| created.next().unwrap_or_else(|| Err(UserDomainError::UserCreationFailed(StorageError::msg("no result for the user")))).map_err(ApiError::from));
+            BulkCreateUserResultData::new(index, result)
+        })
+        .collect();
+
+    let created = results.iter().filter(|result| result.user.is_some()).count();
+    Ok(ApiSuccess::new(StatusCode::OK, BulkCreateUsersResponseData { created, failed: results.len() - created, results }))
+}
+
+/// Get a User by ID.
+///
+/// # Responses
+///
+/// - 200 OK: the User was found, with the `ETag` of its version.
+/// - 404 Not Found: the User was not found.
+/// - 500 Internal server error: Failed to get user.
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{id}",
+    params(("id" = String, Path, description = "ID of the User")),
+    responses(
+        (status = 200, description = "The User was found.", body = ApiResponseBody<UserResponseData>,
+            headers(("ETag" = String, description = "Entity tag of the version of the User"))),
+        (status = 404, description = "The User was not found.", body = ApiResponseBody<ApiErrorData>),
+        (status = 500, description = "Failed to get user.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn get_user<S>(
+    State(state): State<UserState<S>>,
+    Path(id): Path<String>,
+) -> Result<(SurrogateKeys, [(HeaderName, HeaderValue); 1], ApiSuccess<UserResponseData>), ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    state
+        .user_service
+        .get_user(parse_user_id(&id)?)
+        .await
+        .map_err(ApiError::from)
+        .map(|user| (SurrogateKeys(vec![user_surrogate_key(user.id())]), user_etag(&user), ApiSuccess::new(StatusCode::OK, UserResponseData::from(&user))))
+}
+
+/// List Users, one page at a time.
+///
+/// Query parameters: `limit` (1 to 100, default 20), `offset` (default 0), `sort_by`
+/// (`name`, `email` or `age`, default `name`) and `order` (`asc` or `desc`, default `asc`).
+/// `cursor`, the `next_cursor` of the previous page, starts the page after the last User of
+/// that page, so Users created or deleted in the meantime shift no User between pages.
+///
+/// # Responses
+///
+/// - 200 OK: the requested page of Users, with the total number of Users.
+/// - 400 Bad Request: a query parameter could not be parsed, or the cursor is invalid.
+/// - 422 Unprocessable entity: the limit is out of range.
+/// - 500 Internal server error: Failed to list users.
+#[utoipa::path(
+    get,
+    path = "/api/v1/users",
+    params(ListUsersQueryParams, CursorQueryParams),
+    responses(
+        (status = 200, description = "The requested page of Users, with the total number of Users.", body = ApiResponseBody<UserListResponseData>),
+        (status = 400, description = "A query parameter could not be parsed, or the cursor is invalid.", body = ApiResponseBody<ApiErrorData>),
+        (status = 422, description = "The limit is out of range.", body = ApiResponseBody<ApiErrorData>),
+        (status = 500, description = "Failed to list users.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn list_users<S>(
+    State(state): State<UserState<S>>,
+    Query(params): Query<ListUsersQueryParams>,
+    Query(cursor): Query<CursorQueryParams>,
+) -> Result<(SurrogateKeys, ApiSuccess<UserListResponseData>), ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    let mut query = params.into_domain()?;
+    query.after = UserCursorPosition::decode(cursor.cursor.as_deref(), &state.cursor_key, query.sort_by, query.direction)?;
+
+    state
+        .user_service
+        .list_users(query.clone())
+        .await
+        .map_err(ApiError::from)
+        .map(|page| {
+            let data = UserListResponseData::new(page, query.limit, query.offset, &state.cursor_key, query.sort_by, query.direction);
+            (users_surrogate_keys(), ApiSuccess::new(StatusCode::OK, data))
+        })
+}
+
+/// Search Users by name, email and age, one page at a time.
+///
+/// Query parameters: `name` and `email` (texts the field contains, ignoring case and accents),
+/// `min_age` and `max_age` (inclusive), `limit` (1 to 100, default 20) and `offset` (default 0).
+/// Matching Users are sorted by name. `cursor`, the `next_cursor` of the previous page, starts
+/// the page after the last User of that page.
+///
+/// # Responses
+///
+/// - 200 OK: the requested page of matching Users, with the total number of matching Users.
+/// - 400 Bad Request: a query parameter could not be parsed, or the cursor is invalid.
+/// - 422 Unprocessable entity: the limit is out of range, or `min_age` is greater than `max_age`.
+/// - 500 Internal server error: Failed to search users.
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/search",
+    params(SearchUsersQueryParams, CursorQueryParams),
+    responses(
+        (status = 200, description = "The requested page of matching Users, with the total number of matching Users.", body = ApiResponseBody<UserListResponseData>),
+        (status = 400, description = "A query parameter could not be parsed, or the cursor is invalid.", body = ApiResponseBody<ApiErrorData>),
+        (status = 422, description = "The limit is out of range, or `min_age` is greater than `max_age`.", body = ApiResponseBody<ApiErrorData>),
+        (status = 500, description = "Failed to search users.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn search_users<S>(
+    State(state): State<UserState<S>>,
+    Query(params): Query<SearchUsersQueryParams>,
+    Query(cursor): Query<CursorQueryParams>,
+) -> Result<(SurrogateKeys, ApiSuccess<UserListResponseData>), ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    let mut filter = params.into_domain()?;
+    filter.after = UserCursorPosition::decode(cursor.cursor.as_deref(), &state.cursor_key, UserSortField::Name, SortDirection::Asc)?;
+    let (limit, offset) = (filter.limit, filter.offset);
+
+    state
+        .user_service
+        .search_users(filter)
+        .await
+        .map_err(ApiError::from)
+        .map(|page| {
+            let data = UserListResponseData::new(page, limit, offset, &state.cursor_key, UserSortField::Name, SortDirection::Asc);
+            (users_surrogate_keys(), ApiSuccess::new(StatusCode::OK, data))
+        })
+}
+
+/// Surrogate keys of the lists of users. They are purged on any change, as a change may alter
+/// every page (e.g. a renamed user moving across pages), not only the pages listing the user.
+fn users_surrogate_keys() -> SurrogateKeys {
+    SurrogateKeys(vec![USERS_SURROGATE_KEY.to_string()])
+}
+
+/// Update a User. Requires the `users:write` scope.
+///
+/// The `If-Match` header must hold the `ETag` the User was read with, or `*` to update it
+/// whatever its version, so concurrent updates conflict rather than overwrite each other.
+///
+/// # Responses
+///
+/// - 200 OK: the User was successfully updated, with the `ETag` of its new version.
+/// - 400 Bad Request: a given field is invalid, the body lists the error of each invalid field.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `users:write` scope.
+/// - 404 Not Found: the User was not found.
+/// - 412 Precondition Failed: the User was changed since the version `If-Match` names.
+/// - 428 Precondition Required: the `If-Match` header is missing.
+/// - 500 Internal server error: Failed to update user.
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/{id}",
+    params(
+        ("id" = String, Path, description = "ID of the User"),
+        ("If-Match" = String, Header, description = "ETag of the version of the User the update applies to, or `*` for any version")
+    ),
+    request_body = UpdateUserRequestBody,
+    responses(
+        (status = 200, description = "The User was successfully updated.", body = ApiResponseBody<UserResponseData>,
+            headers(("ETag" = String, description = "Entity tag of the new version of the User"))),
+        (status = 400, description = "A given field is invalid, the body lists the error of each invalid field.", body = ApiResponseBody<ApiErrorData>),
+        (status = 401, description = "The bearer token is missing or invalid.", body = ApiResponseBody<ApiErrorData>),
+        (status = 403, description = "The token lacks the `users:write` scope.", body = ApiResponseBody<ApiErrorData>),
+        (status = 404, description = "The User was not found.", body = ApiResponseBody<ApiErrorData>),
+        (status = 412, description = "The User was changed since the version `If-Match` names.", body = ApiResponseBody<ApiErrorData>),
+        (status = 428, description = "The `If-Match` header is missing.", body = ApiResponseBody<ApiErrorData>),
+        (status = 500, description = "Failed to update user.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn update_user<S>(
+    State(state): State<UserState<S>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    ValidatedJson(body): ValidatedJson<UpdateUserRequestBody>,
+) -> Result<([(HeaderName, HeaderValue); 1], ApiSuccess<UserResponseData>), ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    let mut update_user = body.into_domain(parse_user_id(&id)?)?;
+    if let Some(version) = expected_version(&headers)? {
+        update_user = update_user.with_expected_version(version);
+    }
+
+    state
+        .user_service
+        .update_user(update_user)
+        .await
+        .map_err(ApiError::from)
+        .map(|user| (user_etag(&user), ApiSuccess::new(StatusCode::OK, UserResponseData::from(&user))))
+}
+
+/// Delete a User by ID. Requires the `users:write` scope, and is not allowed while impersonating.
+///
+/// The User is soft-deleted: it is no longer returned, but can be restored until an admin
+/// deletes it for good.
+///
+/// # Responses
+///
+/// - 204 No Content: the User was successfully deleted.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `users:write` scope, or is an impersonation token.
+/// - 404 Not Found: the User was not found.
+/// - 423 Locked: the User is under legal hold.
+/// - 500 Internal server error: Failed to delete user.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/{id}",
+    params(("id" = String, Path, description = "ID of the User")),
+    responses(
+        (status = 204, description = "The User was successfully deleted."),
+        (status = 401, description = "The bearer token is missing or invalid.", body = ApiResponseBody<ApiErrorData>),
+        (status = 403, description = "The token lacks the `users:write` scope, or is an impersonation token.", body = ApiResponseBody<ApiErrorData>),
+        (status = 404, description = "The User was not found.", body = ApiResponseBody<ApiErrorData>),
+        (status = 423, description = "The User is under legal hold.", body = ApiResponseBody<ApiErrorData>),
+        (status = 500, description = "Failed to delete user.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn delete_user<S>(
+    State(state): State<UserState<S>>,
+    user: AuthenticatedUser,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    if user.is_impersonated() {
+        return Err(ApiError::Forbidden("Users cannot be deleted while impersonating".to_string()));
+    }
+
+    state
+        .user_service
+        .delete_user(parse_user_id(&id)?)
+        .await
+        .map_err(ApiError::from)
+        .map(|_| StatusCode::NO_CONTENT)
+}
+
+/// Restore a deleted User by ID. Requires the `users:write` scope.
+///
+/// # Responses
+///
+/// - 200 OK: the User was successfully restored.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `users:write` scope.
+/// - 404 Not Found: no deleted User was found.
+/// - 500 Internal server error: Failed to restore user.
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/{id}/restore",
+    params(("id" = String, Path, description = "ID of the User")),
+    responses(
+        (status = 200, description = "The User was successfully restored.", body = ApiResponseBody<UserResponseData>),
+        (status = 401, description = "The bearer token is missing or invalid.", body = ApiResponseBody<ApiErrorData>),
+        (status = 403, description = "The token lacks the `users:write` scope.", body = ApiResponseBody<ApiErrorData>),
+        (status = 404, description = "No deleted User was found.", body = ApiResponseBody<ApiErrorData>),
+        (status = 500, description = "Failed to restore user.", body = ApiResponseBody<ApiErrorData>)
+    )
+)]
+pub async fn restore_user<S>(
+    State(state): State<UserState<S>>,
+    Path(id): Path<String>,
+) -> Result<ApiSuccess<UserResponseData>, ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    state
+        .user_service
+        .restore_user(parse_user_id(&id)?)
+        .await
+        .map_err(ApiError::from)
+        .map(|user| ApiSuccess::new(StatusCode::OK, UserResponseData::from(&user)))
+}
@@ -0,0 +1,301 @@
+use std::sync::Arc;
+
+use axum::extract::{FromRef, Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use application::flows::passkey_service::PasskeyServiceTrait;
+use application::ports::auth::ALL_SCOPES;
+use application::ports::webauthn::{PasskeyChallenge, PasskeyError};
+
+use domain::passkey::model::Passkey;
+use domain::user::model::Email;
+
+use crate::handlers::auth_handlers::LoginResponseData;
+use crate::handlers::user_handlers::{parse_user_id, ApiError, ApiSuccess};
+use crate::middleware::auth::{AuthState, Passkeys, RequireScope};
+
+/// Maximum length of the name of a passkey.
+const MAX_NAME_LENGTH: usize = 64;
+
+/// The dependencies of the WebAuthn handlers.
+#[derive(Clone)]
+pub struct WebAuthnState {
+    pub passkey_service: Arc<dyn PasskeyServiceTrait + Send + Sync + 'static>,
+}
+
+/// The state the WebAuthn routes are served with: passkeys, and the authentication of the users
+/// managing theirs.
+#[derive(Clone)]
+pub struct WebAuthnRoutesState {
+    pub webauthn: WebAuthnState,
+    pub auth: AuthState,
+}
+
+impl FromRef<WebAuthnRoutesState> for WebAuthnState {
+    fn from_ref(state: &WebAuthnRoutesState) -> Self {
+        state.webauthn.clone()
+    }
+}
+
+impl FromRef<WebAuthnRoutesState> for AuthState {
+    fn from_ref(state: &WebAuthnRoutesState) -> Self {
+        state.auth.clone()
+    }
+}
+
+impl From<PasskeyError> for ApiError {
+    fn from(e: PasskeyError) -> Self {
+        match e {
+            PasskeyError::UnknownCeremony => {
+                Self::UnprocessableEntity("Unknown or expired ceremony".to_string())
+            }
+            PasskeyError::InvalidResponse => {
+                Self::Unauthorized("Invalid passkey".to_string())
+            }
+            PasskeyError::NoPasskey => {
+                Self::Unauthorized("No passkey to log in with".to_string())
+            }
+            PasskeyError::UserNotFound => {
+                Self::NotFound("User not found".to_string())
+            }
+            PasskeyError::PasskeyNotFound => {
+                Self::NotFound("Passkey not found".to_string())
+            }
+            PasskeyError::PasskeyAlreadyExists => {
+                Self::UnprocessableEntity("Passkey already registered".to_string())
+            }
+            PasskeyError::Unavailable => {
+                Self::InternalServerError("Failed to process the passkey".to_string())
+            }
+        }
+    }
+}
+
+/// A started ceremony, in responses.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CeremonyResponseData {
+    /// Id to finish the ceremony with.
+    pub ceremony_id: String,
+    /// Options to pass to `navigator.credentials.create()` (registration) or
+    /// `navigator.credentials.get()` (login).
+    pub options: Value,
+}
+
+impl From<PasskeyChallenge> for CeremonyResponseData {
+    fn from(challenge: PasskeyChallenge) -> Self {
+        Self {
+            ceremony_id: challenge.ceremony_id,
+            options: challenge.options,
+        }
+    }
+}
+
+/// The body of a request finishing a passkey registration.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FinishRegistrationRequestBody {
+    pub ceremony_id: String,
+    /// Name the user gives the passkey, e.g. the device it is stored on.
+    pub name: String,
+    /// The credential created by `navigator.credentials.create()`, serialized as JSON.
+    pub credential: Value,
+}
+
+/// The body of a request starting a login with a passkey.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StartLoginRequestBody {
+    pub email: String,
+}
+
+/// The body of a request finishing a login with a passkey.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FinishLoginRequestBody {
+    pub ceremony_id: String,
+    /// The assertion returned by `navigator.credentials.get()`, serialized as JSON.
+    pub credential: Value,
+}
+
+/// A passkey of a user, in responses. Its credential is never returned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PasskeyResponseData {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<Passkey> for PasskeyResponseData {
+    fn from(passkey: Passkey) -> Self {
+        Self {
+            id: passkey.id,
+            name: passkey.name,
+            created_at: passkey.created_at.into(),
+            last_used_at: passkey.last_used_at.map(DateTime::from),
+        }
+    }
+}
+
+/// Start the registration of a passkey of the authenticated user. Requires the `passkeys` scope,
+/// and is not allowed while impersonating.
+///
+/// # Responses
+///
+/// - 200 OK: the body contains the ceremony id and the options to create the credential with.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `passkeys` scope, or is an impersonation token.
+/// - 404 Not Found: the User was not found.
+/// - 500 Internal server error: Failed to start the ceremony.
+pub async fn start_registration(
+    State(state): State<WebAuthnState>,
+    user: RequireScope<Passkeys>,
+) -> Result<ApiSuccess<CeremonyResponseData>, ApiError> {
+    if user.is_impersonated() {
+        return Err(ApiError::Forbidden("Passkeys cannot be registered while impersonating".to_string()));
+    }
+
+    state
+        .passkey_service
+        .start_registration(parse_user_id(&user.user_id)?)
+        .await
+        .map_err(ApiError::from)
+        .map(|challenge| ApiSuccess::new(StatusCode::OK, CeremonyResponseData::from(challenge)))
+}
+
+/// Finish the registration of a passkey of the authenticated user, storing its credential.
+/// Requires the `passkeys` scope, and is not allowed while impersonating.
+///
+/// # Responses
+///
+/// - 201 Created: the passkey was registered.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `passkeys` scope, or is an impersonation token.
+/// - 422 Unprocessable entity: the name is empty or too long, the ceremony is unknown or expired,
+///   the credential is invalid or already registered.
+/// - 500 Internal server error: Failed to store the passkey.
+pub async fn finish_registration(
+    State(state): State<WebAuthnState>,
+    user: RequireScope<Passkeys>,
+    Json(body): Json<FinishRegistrationRequestBody>,
+) -> Result<ApiSuccess<PasskeyResponseData>, ApiError> {
+    if user.is_impersonated() {
+        return Err(ApiError::Forbidden("Passkeys cannot be registered while impersonating".to_string()));
+    }
+    let name = body.name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(ApiError::UnprocessableEntity(format!("name must be 1 to {} characters", MAX_NAME_LENGTH)));
+    }
+
+    state
+        .passkey_service
+        .finish_registration(parse_user_id(&user.user_id)?, body.ceremony_id, name.to_string(), body.credential)
+        .await
+        .map_err(|e| match e {
+            // The user is authenticated: an invalid credential is a bad request, not a failed login
+            PasskeyError::InvalidResponse => ApiError::UnprocessableEntity("Invalid credential".to_string()),
+            e => ApiError::from(e),
+        })
+        .map(|passkey| ApiSuccess::new(StatusCode::CREATED, PasskeyResponseData::from(passkey)))
+}
+
+/// Start a login with a passkey of the User with the given email.
+///
+/// # Responses
+///
+/// - 200 OK: the body contains the ceremony id and the options to get the assertion with.
+/// - 400 Bad request: the email is invalid.
+/// - 401 Unauthorized: the User is unknown or has no passkey.
+/// - 500 Internal server error: Failed to start the ceremony.
+pub async fn start_login(
+    State(state): State<WebAuthnState>,
+    Json(body): Json<StartLoginRequestBody>,
+) -> Result<ApiSuccess<CeremonyResponseData>, ApiError> {
+    let email = Email::parse(body.email)?;
+
+    state
+        .passkey_service
+        .start_login(email)
+        .await
+        .map_err(ApiError::from)
+        .map(|challenge| ApiSuccess::new(StatusCode::OK, CeremonyResponseData::from(challenge)))
+}
+
+/// Finish a login with a passkey, exchanging the assertion for a bearer access token.
+///
+/// # Responses
+///
+/// - 200 OK: the assertion is valid, the body contains the access token.
+/// - 401 Unauthorized: the assertion is invalid, or its passkey was deleted.
+/// - 422 Unprocessable entity: the ceremony is unknown or expired.
+/// - 500 Internal server error: Failed to update the passkey or to issue the token.
+pub async fn finish_login(
+    State(state): State<WebAuthnState>,
+    Json(body): Json<FinishLoginRequestBody>,
+) -> Result<ApiSuccess<LoginResponseData>, ApiError> {
+    state
+        .passkey_service
+        .finish_login(body.ceremony_id, body.credential)
+        .await
+        .map_err(ApiError::from)
+        .map(|token| {
+            ApiSuccess::new(
+                StatusCode::OK,
+                LoginResponseData {
+                    access_token: token.token,
+                    token_type: "Bearer",
+                    expires_in: token.expires_in.as_secs(),
+                    scope: ALL_SCOPES.join(" "),
+                },
+            )
+        })
+}
+
+/// List the passkeys of the authenticated user, oldest first. Requires the `passkeys` scope.
+///
+/// # Responses
+///
+/// - 200 OK: the passkeys of the user.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `passkeys` scope.
+/// - 404 Not Found: the User was not found.
+/// - 500 Internal server error: Failed to list passkeys.
+pub async fn list_passkeys(
+    State(state): State<WebAuthnState>,
+    user: RequireScope<Passkeys>,
+) -> Result<ApiSuccess<Vec<PasskeyResponseData>>, ApiError> {
+    state
+        .passkey_service
+        .list_passkeys(parse_user_id(&user.user_id)?)
+        .await
+        .map_err(ApiError::from)
+        .map(|passkeys| ApiSuccess::new(StatusCode::OK, passkeys.into_iter().map(PasskeyResponseData::from).collect()))
+}
+
+/// Delete a passkey of the authenticated user. Requires the `passkeys` scope, and is not allowed
+/// while impersonating.
+///
+/// # Responses
+///
+/// - 204 No Content: the passkey was deleted.
+/// - 401 Unauthorized: the bearer token is missing or invalid.
+/// - 403 Forbidden: the token lacks the `passkeys` scope, or is an impersonation token.
+/// - 404 Not Found: the user has no passkey with this id.
+/// - 500 Internal server error: Failed to delete the passkey.
+pub async fn delete_passkey(
+    State(state): State<WebAuthnState>,
+    user: RequireScope<Passkeys>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if user.is_impersonated() {
+        return Err(ApiError::Forbidden("Passkeys cannot be deleted while impersonating".to_string()));
+    }
+
+    state
+        .passkey_service
+        .delete_passkey(parse_user_id(&user.user_id)?, id)
+        .await
+        .map_err(ApiError::from)
+        .map(|_| StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,863 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use eyre::Context;
+use axum::extract::{DefaultBodyLimit, FromRef};
+use axum::http::Method;
+use axum::{middleware, Router};
+use axum::routing::{delete, get, post, put};
+use serde::Serialize;
+use tokio::{net, sync::oneshot, time};
+use tokio_util::sync::CancellationToken;
+use tower_http::catch_panic::CatchPanicLayer;
+use tower_http::limit::RequestBodyLimitLayer;
+
+use application::flows::user_service::UserServiceTrait;
+use application::ports::auth::{KeySetPort, CONSENTS_WRITE_SCOPE, USERS_WRITE_SCOPE};
+use application::ports::capability::Capabilities;
+use application::ports::health::HealthChecks;
+
+use crate::handlers::{admin_handlers, audit_handlers::{self, AuditState}, auth_handlers, clock_handlers::{self, ClockState}, consent_handlers::{self, ConsentState}, device_handlers::{self, DeviceRoutesState, DeviceState}, docs_handlers, group_handlers::{self, GroupState}, health_handlers, in_flight_handlers::{self, InFlightState}, management_handlers::{self, ManagementRoutesState, ManagementState, MountedRoutes}, saml_handlers::{self, SamlState}, scim_handlers, slo_handlers::{self, SloState}, stats_handlers::{self, StatsState}, tap_handlers::{self, TapState}, tenant_handlers::{self, TenantState}, user_handlers::{self, UserState}, webauthn_handlers::{self, WebAuthnRoutesState, WebAuthnState}};
+#[cfg(feature = "graphql")]
+use crate::graphql::{graphql_routes, GraphQlState, GRAPHQL_PATH};
+use crate::middleware::{
+    admin::AdminToken,
+    audit::record_audit_actors,
+    auth::AuthState,
+    compression::CompressionPolicy,
+    cors::CorsPolicy,
+    encryption::JweKeys,
+    error_reporting::{panic_response, report_server_errors},
+    http_cache::vary_on_negotiated_headers,
+    idempotency::{replay_idempotent_requests, Idempotency},
+    load_shedding::LoadSheddingPolicy,
+    rate_limit::{RateLimitClass, RateLimiter},
+    request_id::{propagate_request_id_layer, request_id, set_request_id_layer},
+    sampling::{sample_requests, Sampler},
+    traffic_archive::TrafficArchiver,
+};
+use crate::pagination::CursorKey;
+use crate::presets::MiddlewarePreset;
+use crate::routes::{RouteCatalog, RouteMeta, Routes};
+use crate::versioning::{mount_versions, ApiVersion, Deprecation};
+
+/// Prefix of the routes of the first version of the API.
+pub const API_V1: &str = "/api/v1";
+
+/// When the unversioned routes (`/api/users`) were deprecated in favor of [`API_V1`], in
+/// seconds since the Unix epoch (2026-10-15).
+const UNVERSIONED_API_DEPRECATED_AT: i64 = 1_792_022_400;
+
+/// Generic response structure shared by all API responses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResponseBody<T: Serialize> {
+    status_code: u16,
+    data: T,
+}
+
+/// The response data format for all error responses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorResponseData {
+    pub message: String,
+}
+
+/// Configuration for the HTTP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpServerConfig {
+    pub port: u16,
+    /// Maximum time in-flight requests are given to complete once shutdown starts.
+    pub shutdown_timeout: Duration,
+    /// Cross-origin requests accepted from browser frontends. Browsers refuse cross-origin
+    /// responses when `None`.
+    pub cors: Option<CorsPolicy>,
+    /// Maximum size of request bodies, in bytes. Larger requests are answered with
+    /// 413 Payload Too Large.
+    pub max_body_bytes: usize,
+    /// Compression of the responses. Responses are sent uncompressed when `None`.
+    pub compression: Option<CompressionPolicy>,
+    /// Timeout and concurrency limit of the requests of the API, none by default.
+    pub load_shedding: LoadSheddingPolicy,
+}
+
+/// The application state the router is built from.
+///
+/// Handlers never extract `AppState` directly. Each one declares the sub-state it needs
+/// (e.g. [`UserState`], [`Sampler`]), derived from `AppState` through `FromRef`, so routers
+/// can be tested in isolation with only their own dependencies.
+///
+/// The user service is a trait object by default. Building the state from a concrete service
+/// (e.g. `Arc<UserService<UserRepository>>`) monomorphizes the handlers for it instead.
+pub struct AppState<S: ?Sized = dyn UserServiceTrait + Send + Sync + 'static> {
+    pub user_service: Arc<S>,
+    /// Runtime-adjustable sampling of request logs.
+    pub sampler: Sampler,
+    /// Bearer token required by the admin routes. Admin routes are not mounted when `None`.
+    pub admin_token: Option<Arc<str>>,
+    /// Bearer token required by the SCIM provisioning routes. SCIM routes are not mounted when `None`.
+    pub scim_token: Option<Arc<str>>,
+    /// Bearer token required by the token introspection route, which is not mounted when `None`.
+    pub introspection_token: Option<Arc<str>>,
+    /// Public keys validating access tokens, published at `/.well-known/jwks.json`. The route
+    /// is not mounted when `None`, e.g. when tokens are signed with a secret.
+    pub key_set: Option<Arc<dyn KeySetPort + Send + Sync>>,
+    /// Key signing the cursors of the lists of users, random by default.
+    pub cursor_key: CursorKey,
+    /// Authentication of the protected routes, disabled by default.
+    pub auth: AuthState,
+    /// Single sign-on through a SAML identity provider. SAML routes are not mounted when `None`.
+    pub saml: Option<SamlState>,
+    /// Device authorization grant for CLI tools. Device routes are not mounted when `None`.
+    pub device: Option<DeviceState>,
+    /// Passkey registration and login. WebAuthn routes are not mounted when `None`.
+    pub webauthn: Option<WebAuthnState>,
+    /// GraphQL API of the users, mounted at [`GRAPHQL_PATH`] unless `None`.
+    #[cfg(feature = "graphql")]
+    pub graphql: Option<GraphQlState>,
+    /// Consent records of users, disabled by default.
+    pub consents: ConsentState,
+    /// Groups of users and the roles they bundle, managed through the admin routes. Disabled by default.
+    pub groups: GroupState,
+    /// Daily statistics of the users, reported through the admin routes. Disabled by default.
+    pub stats: StatsState,
+    /// Latency objectives of the `/api` routes, reported through the admin routes. Requests are
+    /// not tracked by default.
+    pub slos: SloState,
+    /// Live feed of the summaries of the requests of the API and of the services calling it
+    /// (see [`MiddlewarePreset`]), streamed through the admin routes.
+    /// Requests are not published by default.
+    pub request_tap: TapState,
+    /// Registry of the requests of the API and of the services calling it being handled,
+    /// listed and cancelled through the admin routes. Requests are not tracked by default.
+    pub in_flight: InFlightState,
+    /// Settings overridden by the tenants named by the `X-Tenant-Id` header, consulted by the
+    /// rate limiter and managed through the admin routes. Tenants are not resolved by default.
+    pub tenants: TenantState,
+    /// Keys decrypting JWE request bodies of the routes of the public API. Bodies must be
+    /// plaintext when `None`.
+    pub jwe_keys: Option<JweKeys>,
+    /// Replay of the responses of the `POST` user routes to the retries sent with the same
+    /// `Idempotency-Key`. The header is ignored when `None`.
+    pub idempotency: Option<Idempotency>,
+    /// Rate limiting of the `/api` routes per client and route. Requests are not limited when `None`.
+    pub rate_limiter: Option<RateLimiter>,
+    /// Sampling of the `/api` requests and their responses to the traffic archive. Requests are
+    /// not archived when `None`.
+    pub traffic_archive: Option<TrafficArchiver>,
+    /// Operational endpoints (health, info, metrics, loggers, routes, dependencies) under a base
+    /// path. Management routes are not mounted when `None`.
+    pub management: Option<ManagementState>,
+    /// Audit log of the changes of the users, read through `/users/{id}/audit`. The audit route
+    /// is not mounted when `None`.
+    pub audit: Option<AuditState>,
+    /// Clock of the tokens and signing keys, shifted through `/admin/clock` in integration
+    /// environments. The clock routes are not mounted when `None`.
+    pub clock: Option<ClockState>,
+    /// Optional subsystems, disabled unless configured.
+    pub capabilities: Capabilities,
+    /// Required dependencies probed by the readiness endpoint.
+    pub health_checks: HealthChecks,
+}
+
+impl<S: ?Sized> AppState<S> {
+    /// Creates a new `AppState` with the default sampling policy, a random cursor key, admin, SCIM, introspection, key set, SAML, device, WebAuthn and GraphQL routes, authentication,
+    /// consent tracking, groups, statistics, SLO tracking, the request tap, in-flight request tracking, tenant settings, idempotency keys, rate limiting, traffic archiving, management routes, the audit route, the test clock and all optional subsystems disabled, and no health checks.
+    pub fn new(user_service: Arc<S>) -> Self {
+        Self {
+            user_service,
+            sampler: Sampler::default(),
+            admin_token: None,
+            scim_token: None,
+            introspection_token: None,
+            key_set: None,
+            cursor_key: CursorKey::random(),
+            auth: AuthState::default(),
+            saml: None,
+            device: None,
+            webauthn: None,
+            #[cfg(feature = "graphql")]
+            graphql: None,
+            consents: ConsentState::default(),
+            groups: GroupState::default(),
+            stats: StatsState::default(),
+            slos: SloState::default(),
+            request_tap: TapState::default(),
+            in_flight: InFlightState::default(),
+            tenants: TenantState::default(),
+            jwe_keys: None,
+            idempotency: None,
+            rate_limiter: None,
+            traffic_archive: None,
+            management: None,
+            audit: None,
+            clock: None,
+            capabilities: Capabilities::default(),
+            health_checks: HealthChecks::default(),
+        }
+    }
+}
+
+impl<S: ?Sized> Clone for AppState<S> {
+    fn clone(&self) -> Self {
+        Self {
+            user_service: self.user_service.clone(),
+            sampler: self.sampler.clone(),
+            admin_token: self.admin_token.clone(),
+            scim_token: self.scim_token.clone(),
+            introspection_token: self.introspection_token.clone(),
+            key_set: self.key_set.clone(),
+            cursor_key: self.cursor_key.clone(),
+            auth: self.auth.clone(),
+            saml: self.saml.clone(),
+            device: self.device.clone(),
+            webauthn: self.webauthn.clone(),
+            #[cfg(feature = "graphql")]
+            graphql: self.graphql.clone(),
+            consents: self.consents.clone(),
+            groups: self.groups.clone(),
+            stats: self.stats.clone(),
+            slos: self.slos.clone(),
+            request_tap: self.request_tap.clone(),
+            in_flight: self.in_flight.clone(),
+            tenants: self.tenants.clone(),
+            jwe_keys: self.jwe_keys.clone(),
+            idempotency: self.idempotency.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+            traffic_archive: self.traffic_archive.clone(),
+            management: self.management.clone(),
+            audit: self.audit.clone(),
+            clock: self.clock.clone(),
+            capabilities: self.capabilities.clone(),
+            health_checks: self.health_checks.clone(),
+        }
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for UserState<S> {
+    fn from_ref(state: &AppState<S>) -> Self {
+        UserState {
+            user_service: state.user_service.clone(),
+            cursor_key: state.cursor_key.clone(),
+        }
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for Sampler {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.sampler.clone()
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for Capabilities {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.capabilities.clone()
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for AuthState {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.auth.clone()
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for ConsentState {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.consents.clone()
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for GroupState {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.groups.clone()
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for StatsState {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.stats.clone()
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for SloState {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.slos.clone()
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for TapState {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.request_tap.clone()
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for InFlightState {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.in_flight.clone()
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for TenantState {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.tenants.clone()
+    }
+}
+
+impl<S: ?Sized> FromRef<AppState<S>> for HealthChecks {
+    fn from_ref(state: &AppState<S>) -> Self {
+        state.health_checks.clone()
+    }
+}
+
+/// The application's HTTP server. The underlying HTTP package is opaque to module consumers.
+pub struct HttpServer {
+    router: axum::Router,
+    listener: net::TcpListener,
+    /// The management routes and the listener of their own port, if any.
+    management: Option<(axum::Router, net::TcpListener)>,
+    shutdown_timeout: Duration,
+}
+
+impl HttpServer {
+    /// Returns a new HTTP server bound to the port specified in `config`.
+    pub async fn new<S>(state: AppState<S>, config: HttpServerConfig) -> eyre::Result<Self>
+    where
+        S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+    {
+        // The management routes with their own port are served by their own listener, with the
+        // same body limit
+        let management = match state.management.clone().and_then(|management| management.port.map(|port| (management, port))) {
+            Some((management, port)) => {
+                let router = management_router(state.clone(), &management)
+                    .layer(DefaultBodyLimit::disable())
+                    .layer(RequestBodyLimitLayer::new(config.max_body_bytes));
+                let listener = net::TcpListener::bind(format!("0.0.0.0:{}", port))
+                    .await
+                    .with_context(|| format!("failed to listen on {}", port))?;
+                Some((router, listener))
+            }
+            None => None,
+        };
+
+        // The limit applies to every body, including the ones read by middlewares, rather than
+        // only to the bodies of extractors
+        let mut router = router_with_load_shedding(state, &config.load_shedding)
+            .layer(DefaultBodyLimit::disable())
+            .layer(RequestBodyLimitLayer::new(config.max_body_bytes));
+        if let Some(compression) = &config.compression {
+            router = router.layer(compression.layer().context("invalid compression policy")?);
+        }
+        if let Some(cors) = &config.cors {
+            router = router.layer(cors.layer().context("invalid CORS policy")?);
+        }
+
+        let listener = net::TcpListener::bind(format!("0.0.0.0:{}", config.port))
+            .await
+            .with_context(|| format!("failed to listen on {}", config.port))?;
+
+        Ok(Self {
+            router,
+            listener,
+            management,
+            shutdown_timeout: config.shutdown_timeout,
+        })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns the address the management routes are served on, when they have their own port.
+    pub fn management_addr(&self) -> Option<std::io::Result<SocketAddr>> {
+        self.management.as_ref().map(|(_, listener)| listener.local_addr())
+    }
+
+    /// Runs the HTTP server until SIGTERM or SIGINT (Ctrl+C) is received, then drains it,
+    /// see [`HttpServer::run_until`].
+    pub async fn run(self) -> eyre::Result<()> {
+        self.run_until(shutdown_signal()).await
+    }
+
+    /// Runs the HTTP server until `shutdown` resolves, then stops accepting connections and
+    /// waits for in-flight requests to complete. The listener of the management routes, if
+    /// any, is drained along with the API.
+    ///
+    /// Returns without waiting for requests still running after the configured shutdown
+    /// timeout, so a stuck request cannot delay the shutdown past the orchestrator's kill
+    /// deadline. Such requests are aborted when the runtime shuts down.
+    pub async fn run_until<F>(self, shutdown: F) -> eyre::Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        tracing::debug!("listening on {}", self.listener.local_addr().unwrap());
+
+        let timeout = self.shutdown_timeout;
+        let (draining_tx, draining_rx) = oneshot::channel();
+        let draining = CancellationToken::new();
+        let shutdown = {
+            let draining = draining.clone();
+            async move {
+                shutdown.await;
+                tracing::info!("shutting down, draining in-flight requests");
+                let _ = draining_tx.send(());
+                draining.cancel();
+            }
+        };
+        let drain_deadline = async move {
+            match draining_rx.await {
+                Ok(()) => time::sleep(timeout).await,
+                // The server stopped on its own, before shutdown was requested.
+                Err(_) => std::future::pending().await,
+            }
+        };
+
+        let api = axum::serve(self.listener, self.router.into_make_service_with_connect_info::<SocketAddr>()).with_graceful_shutdown(shutdown);
+        let management = async move {
+            match self.management {
+                Some((router, listener)) => {
+                    tracing::debug!("serving the management routes on {}", listener.local_addr().unwrap());
+                    axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>()).with_graceful_shutdown(draining.cancelled_owned()).await
+                }
+                None => Ok(()),
+            }
+        };
+
+        tokio::select! {
+            result = async { tokio::try_join!(api.into_future(), management) } => {
+                result.context("received error from running server")?;
+            }
+            _ = drain_deadline => {
+                tracing::warn!("in-flight requests did not complete within {:?}, abandoning them", timeout);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolves when the process is asked to terminate, by SIGTERM or SIGINT (Ctrl+C).
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Builds the application router with all routes and middleware, without binding a listener.
+///
+/// Useful for exercising the HTTP API in-process, e.g. in tests.
+pub fn router<S>(state: AppState<S>) -> axum::Router
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    router_with_load_shedding(state, &LoadSheddingPolicy::default())
+}
+
+/// Builds the application router like [`router`], the requests of the API under `/api` being
+/// timed out and shed by `load_shedding`. The probes, the management routes and the other
+/// routes at the root are left out, so orchestrators keep reaching them while the API is
+/// saturated.
+pub fn router_with_load_shedding<S>(state: AppState<S>, load_shedding: &LoadSheddingPolicy) -> axum::Router
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    let mut users = user_routes();
+    if let Some(idempotency) = &state.idempotency {
+        users = users.map_router(|users| users.route_layer(middleware::from_fn_with_state(idempotency.clone(), replay_idempotent_requests)));
+    }
+    // Requests are decrypted before their fingerprint is computed
+    let public = users.merge(consent_routes()).merge(auth_routes());
+    let catalog = public.catalog();
+    let public = public.merge(docs_routes(catalog));
+    let mut api = MiddlewarePreset::PublicApi.apply_routes(public, &state);
+    if let Some(audit) = &state.audit {
+        api = api.merge(MiddlewarePreset::PublicApi.apply_routes(audit_routes(audit.clone()), &state));
+    }
+    if let Some(token) = &state.introspection_token {
+        api = api.merge(MiddlewarePreset::InternalApi(AdminToken(token.clone())).apply(introspection_routes(), &state));
+    }
+    if let Some(saml) = &state.saml {
+        api = api.nest("/auth/saml", MiddlewarePreset::PublicApi.apply(saml_routes(saml.clone()), &state));
+    }
+    if let Some(device) = &state.device {
+        api = api.nest("/auth/device", MiddlewarePreset::PublicApi.apply(device_routes(device.clone(), state.auth.clone()), &state));
+    }
+    if let Some(webauthn) = &state.webauthn {
+        api = api.nest("/auth/webauthn", MiddlewarePreset::PublicApi.apply(webauthn_routes(webauthn.clone(), state.auth.clone()), &state));
+    }
+    if let Some(token) = &state.admin_token {
+        let mut admin = admin_routes();
+        if let Some(clock) = &state.clock {
+            admin = admin.merge(clock_routes(clock.clone()));
+        }
+        api = api.nest("/admin", MiddlewarePreset::Admin(AdminToken(token.clone())).apply(admin, &state));
+    }
+    // Both versions share the limit
+    let api = load_shedding.apply(api);
+
+    // The unversioned routes of the clients predating versions serve the first version
+    let unversioned = Deprecation::new(DateTime::<Utc>::from_timestamp(UNVERSIONED_API_DEPRECATED_AT, 0).unwrap_or_default(), API_V1);
+    let mut app = mount_versions(
+        axum::Router::new().merge(health_routes()),
+        [ApiVersion::new(API_V1, api.clone()), ApiVersion::new("/api", api).deprecated(unversioned)],
+    );
+    if let Some(key_set) = &state.key_set {
+        app = app.merge(jwks_routes(key_set.clone()));
+    }
+    #[cfg(feature = "graphql")]
+    if let Some(graphql) = &state.graphql {
+        app = app.nest(GRAPHQL_PATH, MiddlewarePreset::PublicApi.apply(graphql_routes(graphql.clone()), &state));
+    }
+    if let Some(token) = &state.scim_token {
+        app = app.nest("/scim/v2", MiddlewarePreset::InternalApi(AdminToken(token.clone())).apply(scim_routes(), &state));
+    }
+    // Management routes with their own port are served by their own listener instead
+    if let Some(management) = state.management.as_ref().filter(|management| management.port.is_none()) {
+        app = app.merge(mount_management(management, &state));
+    }
+
+    with_router_layers(app, state)
+}
+
+/// Serves the management routes of `management` on their own, within the layers of the whole
+/// router, for the listener of their port.
+pub fn management_router<S>(state: AppState<S>, management: &ManagementState) -> axum::Router
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    with_router_layers(mount_management(management, &state), state)
+}
+
+/// Returns the modules of routes mounted by [`router`] for `state`, with their paths and
+/// presets, as listed by the `routes` management endpoint.
+pub fn mounted_routes<S: ?Sized>(state: &AppState<S>) -> Vec<MountedRoutes> {
+    let mount = |module: &'static str, path: String, preset: Option<&'static str>| MountedRoutes { module, path, preset };
+
+    let mut routes = vec![mount("health", "/".to_string(), None)];
+    for prefix in [API_V1, "/api"] {
+        for module in ["users", "consents", "auth", "docs"] {
+            routes.push(mount(module, prefix.to_string(), Some("PublicApi")));
+        }
+        if state.audit.is_some() {
+            routes.push(mount("audit", prefix.to_string(), Some("PublicApi")));
+        }
+        if state.introspection_token.is_some() {
+            routes.push(mount("introspection", prefix.to_string(), Some("InternalApi")));
+        }
+        if state.saml.is_some() {
+            routes.push(mount("saml", format!("{}/auth/saml", prefix), Some("PublicApi")));
+        }
+        if state.device.is_some() {
+            routes.push(mount("device", format!("{}/auth/device", prefix), Some("PublicApi")));
+        }
+        if state.webauthn.is_some() {
+            routes.push(mount("webauthn", format!("{}/auth/webauthn", prefix), Some("PublicApi")));
+        }
+        if state.admin_token.is_some() {
+            routes.push(mount("admin", format!("{}/admin", prefix), Some("Admin")));
+        }
+    }
+    if state.key_set.is_some() {
+        routes.push(mount("jwks", "/.well-known/jwks.json".to_string(), None));
+    }
+    #[cfg(feature = "graphql")]
+    if state.graphql.is_some() {
+        routes.push(mount("graphql", GRAPHQL_PATH.to_string(), Some("PublicApi")));
+    }
+    if state.scim_token.is_some() {
+        routes.push(mount("scim", "/scim/v2".to_string(), Some("InternalApi")));
+    }
+    if let Some(management) = &state.management {
+        routes.push(mount("management", management.base_path.clone(), Some("InternalApi")));
+    }
+    routes
+}
+
+/// Management routes of `management`, nested under its base path: the health endpoints are
+/// public like the probes, and the others are served with the [`MiddlewarePreset::InternalApi`]
+/// of the management token.
+fn mount_management<T, S>(management: &ManagementState, state: &AppState<S>) -> Router<T>
+where
+    T: Clone + Send + Sync + 'static,
+    S: ?Sized,
+{
+    let routes = ManagementRoutesState {
+        management: management.clone(),
+        routes: mounted_routes(state).into(),
+        sampler: state.sampler.clone(),
+        capabilities: state.capabilities.clone(),
+        health_checks: state.health_checks.clone(),
+        slos: state.slos.clone(),
+        in_flight: state.in_flight.clone(),
+    };
+    let protected = MiddlewarePreset::InternalApi(management.token.clone()).apply(management_routes(routes.clone()), state);
+    Router::new().nest(&management.base_path, management_health_routes(routes).merge(protected))
+}
+
+/// Serves `app` within the layers of the whole router: request ids, tracing, sampling of the
+/// logs, error reporting and the actors of the audit log.
+fn with_router_layers<S>(app: Router<AppState<S>>, state: AppState<S>) -> axum::Router
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    // Request spans are nested under the span the router is created in (e.g. pod metadata),
+    // because connection tasks are spawned without inheriting the current span. The spans of
+    // the handlers and services are nested under the request span, which carries its id and,
+    // when spans are exported, continues the trace of the caller.
+    let parent_span = tracing::Span::current();
+    let trace_layer = tower_http::trace::TraceLayer::new_for_http().make_span_with(
+        move |request: &axum::extract::Request<_>| {
+            let uri = request.uri().to_string();
+            let request_id = request_id(request).unwrap_or_default();
+            let span = tracing::info_span!(parent: &parent_span, "http_request", otel.kind = "server", request_id, method = ?request.method(), uri, sampled = tracing::field::Empty);
+            #[cfg(feature = "otel")]
+            crate::middleware::trace_context::set_remote_parent(&span, request.headers());
+            span
+        },
+    );
+
+    app
+        .layer(middleware::from_fn(vary_on_negotiated_headers))
+        .layer(middleware::from_fn(record_audit_actors))
+        .layer(CatchPanicLayer::custom(panic_response))
+        .layer(middleware::from_fn_with_state(state.capabilities.error_reporter.clone(), report_server_errors))
+        .layer(middleware::from_fn_with_state(state.sampler.clone(), sample_requests))
+        .layer(trace_layer)
+        .layer(propagate_request_id_layer())
+        .layer(set_request_id_layer())
+        .with_state(state)
+}
+
+/// Liveness (`/healthz`) and readiness (`/readyz`) probes, to be mounted at the root.
+pub fn health_routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    HealthChecks: FromRef<S>,
+{
+    Router::new()
+        .route("/healthz", get(health_handlers::healthz))
+        .route("/readyz", get(health_handlers::readyz))
+}
+
+/// Health endpoints of the management routes served with `state`, to be nested under their
+/// base path: `/health` and `/health/readiness` probe the dependencies like `/readyz`, and
+/// `/health/liveness` answers like `/healthz`.
+pub fn management_health_routes<S>(state: ManagementRoutesState) -> Router<S> {
+    Router::new()
+        .route("/health", get(health_handlers::readyz))
+        .route("/health/liveness", get(health_handlers::healthz))
+        .route("/health/readiness", get(health_handlers::readyz))
+        .with_state(state)
+}
+
+/// Operational endpoints of the management routes served with `state`, to be nested under
+/// their base path with the [`MiddlewarePreset::InternalApi`] of the management token.
+pub fn management_routes<S>(state: ManagementRoutesState) -> Router<S> {
+    Router::new()
+        .route("/", get(management_handlers::index))
+        .route("/info", get(management_handlers::info))
+        .route("/metrics", get(management_handlers::metrics))
+        .route("/loggers", get(admin_handlers::get_sampling_policy).put(admin_handlers::update_sampling_policy))
+        .route("/routes", get(management_handlers::routes))
+        .route("/dependencies", get(admin_handlers::get_dependencies))
+        .with_state(state)
+}
+
+/// Routes of the user API served by the service `U`, to be nested under a version (`/api/v1`).
+pub fn user_routes<U, S>() -> Routes<S>
+where
+    U: UserServiceTrait + Send + Sync + ?Sized + 'static,
+    S: Clone + Send + Sync + 'static,
+    UserState<U>: FromRef<S>,
+    AuthState: FromRef<S>,
+{
+    let read = || RouteMeta::new().tag("users");
+    let write = || read().scope(USERS_WRITE_SCOPE);
+    Routes::new()
+        .route(Method::POST, "/users", user_handlers::create_user::<U>, read())
+        .route(Method::GET, "/users", user_handlers::list_users::<U>, read())
+        .route(Method::POST, "/users/bulk", user_handlers::create_users_bulk::<U>, write().rate_limit(RateLimitClass::Bulk))
+        .route(Method::GET, "/users/search", user_handlers::search_users::<U>, read())
+        .route(Method::GET, "/users/{id}", user_handlers::get_user::<U>, read())
+        .route(Method::PUT, "/users/{id}", user_handlers::update_user::<U>, write())
+        .route(Method::DELETE, "/users/{id}", user_handlers::delete_user::<U>, write())
+        .route(Method::POST, "/users/{id}/restore", user_handlers::restore_user::<U>, write())
+}
+
+/// Routes of the consent records of users, to be nested under a version (`/api/v1`).
+pub fn consent_routes<S>() -> Routes<S>
+where
+    S: Clone + Send + Sync + 'static,
+    ConsentState: FromRef<S>,
+    AuthState: FromRef<S>,
+{
+    Routes::new()
+        .route(Method::POST, "/users/{id}/consents", consent_handlers::record_consent, RouteMeta::new().tag("consents").scope(CONSENTS_WRITE_SCOPE))
+        .route(Method::GET, "/users/{id}/consents", consent_handlers::list_consents, RouteMeta::new().tag("consents"))
+}
+
+/// Routes of the authentication API, to be nested under a version (`/api/v1`).
+pub fn auth_routes<S>() -> Routes<S>
+where
+    S: Clone + Send + Sync + 'static,
+    AuthState: FromRef<S>,
+{
+    Routes::new().route(Method::POST, "/auth/login", auth_handlers::login, RouteMeta::new().tag("auth").rate_limit(RateLimitClass::Credentials))
+}
+
+/// Token introspection (RFC 7662), to be nested under a version (`/api/v1`) with the
+/// [`MiddlewarePreset::InternalApi`] of the introspection token.
+pub fn introspection_routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    AuthState: FromRef<S>,
+{
+    Router::new().route("/auth/introspect", post(auth_handlers::introspect))
+}
+
+/// The public keys of `key_set` (`/.well-known/jwks.json`), to be mounted at the root.
+pub fn jwks_routes<S>(key_set: Arc<dyn KeySetPort + Send + Sync>) -> Router<S> {
+    Router::new().route("/.well-known/jwks.json", get(auth_handlers::jwks)).with_state(key_set)
+}
+
+/// SAML single sign-on served by `saml`: metadata, login and assertion consumer service, to be
+/// nested under `/auth/saml` of a version.
+pub fn saml_routes<S>(saml: SamlState) -> Router<S> {
+    Router::new()
+        .route("/metadata", get(saml_handlers::metadata))
+        .route("/login", get(saml_handlers::login))
+        .route("/acs", post(saml_handlers::assertion_consumer))
+        .with_state(saml)
+}
+
+/// Device authorization grant served by `device`: the device authorization and token
+/// endpoints, and the verification page where users authenticated by `auth` approve devices,
+/// to be nested under `/auth/device` of a version.
+pub fn device_routes<S>(device: DeviceState, auth: AuthState) -> Router<S> {
+    Router::new()
+        .route("/", get(device_handlers::verification_page).post(device_handlers::verify))
+        .route("/code", post(device_handlers::device_code))
+        .route("/token", post(device_handlers::token))
+        .route("/approve", post(device_handlers::approve))
+        .with_state(DeviceRoutesState { device, auth })
+}
+
+/// History of the changes of the users read from `audit`, to be merged into a version
+/// (`/api/v1`).
+pub fn audit_routes<S>(audit: AuditState) -> Routes<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Routes::new()
+        .route(Method::GET, "/users/{id}/audit", audit_handlers::get_user_audit, RouteMeta::new().tag("users").scope(USERS_WRITE_SCOPE))
+        .with_state(audit)
+}
+
+/// Shifting of the test `clock`, to be merged into the admin routes of a version.
+pub fn clock_routes<S>(clock: ClockState) -> Router<S> {
+    Router::new()
+        .route("/clock", get(clock_handlers::get_clock).delete(clock_handlers::reset_clock))
+        .route("/clock/shift", post(clock_handlers::shift_clock))
+        .with_state(clock)
+}
+
+/// Passkey registration and login served by `webauthn`, and management of the passkeys of the
+/// users authenticated by `auth`, to be nested under `/auth/webauthn` of a version.
+pub fn webauthn_routes<S>(webauthn: WebAuthnState, auth: AuthState) -> Router<S> {
+    Router::new()
+        .route("/register/start", post(webauthn_handlers::start_registration))
+        .route("/register/finish", post(webauthn_handlers::finish_registration))
+        .route("/login/start", post(webauthn_handlers::start_login))
+        .route("/login/finish", post(webauthn_handlers::finish_login))
+        .route("/credentials", get(webauthn_handlers::list_passkeys))
+        .route("/credentials/{id}", delete(webauthn_handlers::delete_passkey))
+        .with_state(WebAuthnRoutesState { webauthn, auth })
+}
+
+/// Swagger UI (`/docs`) and the OpenAPI spec it renders (`/docs/openapi.json`), completed with
+/// the metadata of the routes of `catalog`, to be nested under a version (`/api/v1`).
+pub fn docs_routes<S>(catalog: RouteCatalog) -> Routes<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Routes::new()
+        .route(Method::GET, "/docs", docs_handlers::swagger_ui, RouteMeta::new())
+        .route(Method::GET, "/docs/openapi.json", docs_handlers::openapi_json, RouteMeta::new())
+        .with_state(catalog)
+}
+
+/// SCIM 2.0 provisioning of the users of the service `U` by identity providers, to be nested
+/// under `/scim/v2` with the [`MiddlewarePreset::InternalApi`] of the SCIM token.
+pub fn scim_routes<U, S>() -> Router<S>
+where
+    U: UserServiceTrait + Send + Sync + ?Sized + 'static,
+    S: Clone + Send + Sync + 'static,
+    UserState<U>: FromRef<S>,
+{
+    Router::new()
+        .route("/Users", post(scim_handlers::create_user::<U>).get(scim_handlers::list_users::<U>))
+        .route(
+            "/Users/{id}",
+            get(scim_handlers::get_user::<U>).patch(scim_handlers::patch_user::<U>).delete(scim_handlers::delete_user::<U>),
+        )
+}
+
+/// Routes of the admin API managing users of the service `U`, to be nested under `/admin` of a
+/// version with the [`MiddlewarePreset::Admin`] of the admin token.
+pub fn admin_routes<U, S>() -> Router<S>
+where
+    U: UserServiceTrait + Send + Sync + ?Sized + 'static,
+    S: Clone + Send + Sync + 'static,
+    Sampler: FromRef<S>,
+    Capabilities: FromRef<S>,
+    UserState<U>: FromRef<S>,
+    AuthState: FromRef<S>,
+    GroupState: FromRef<S>,
+    StatsState: FromRef<S>,
+    SloState: FromRef<S>,
+    TapState: FromRef<S>,
+    InFlightState: FromRef<S>,
+    TenantState: FromRef<S>,
+{
+    Router::new()
+        .route("/dependencies", get(admin_handlers::get_dependencies))
+        .route("/logging/sampling", get(admin_handlers::get_sampling_policy).put(admin_handlers::update_sampling_policy))
+        .route("/users", get(admin_handlers::list_users::<U>))
+        .route("/users/{id}", delete(admin_handlers::hard_delete_user::<U>))
+        .route("/users/{id}/legal-hold", put(admin_handlers::set_legal_hold::<U>))
+        .route("/users/{id}/role", put(admin_handlers::set_role::<U>))
+        .route("/users/{id}/roles", get(group_handlers::get_user_group_roles))
+        .route("/impersonations", post(admin_handlers::create_impersonation::<U>))
+        .route("/groups", get(group_handlers::list_groups))
+        .route("/groups/{name}", get(group_handlers::get_group).put(group_handlers::save_group).delete(group_handlers::delete_group))
+        .route("/groups/{name}/members", get(group_handlers::list_members).patch(group_handlers::update_members))
+        .route("/stats", get(stats_handlers::get_stats))
+        .route("/slos", get(slo_handlers::get_slos))
+        .route("/tap", get(tap_handlers::watch_requests))
+        .route("/requests", get(in_flight_handlers::list_requests))
+        .route("/requests/{id}", delete(in_flight_handlers::cancel_request))
+        .route("/tenants/{tenant}/settings", get(tenant_handlers::get_settings).put(tenant_handlers::save_settings).delete(tenant_handlers::delete_settings))
+}
@@ -0,0 +1,10 @@
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod http;
+pub mod handlers;
+pub mod middleware;
+pub mod pagination;
+pub mod presets;
+pub mod routes;
+pub mod rpc;
+pub mod versioning;
\ No newline at end of file
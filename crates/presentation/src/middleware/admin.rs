@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+use application::ports::audit::record_actor;
+
+use crate::handlers::user_handlers::ApiResponseBody;
+use crate::middleware::http_cache::Negotiated;
+
+/// The bearer token expected by [`require_admin_token`].
+#[derive(Clone)]
+pub struct AdminToken(pub Arc<str>);
+
+/// The actor of the changes made through the admin routes, in the audit log. The admin token is
+/// shared by every operator, so it identifies no user.
+pub const ADMIN_TOKEN_ACTOR: &str = "admin-token";
+
+/// Middleware guarding the admin routes with the static `ADMIN_TOKEN` bearer token.
+pub async fn require_admin_token(State(AdminToken(expected)): State<AdminToken>, request: Request, next: Next) -> Response {
+    Negotiated::record(request.extensions(), header::AUTHORIZATION);
+    let provided = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided {
+        Some(provided) if constant_time_eq(expected.as_bytes(), provided.as_bytes()) => next.run(request).await,
+        _ => (
+            StatusCode::UNAUTHORIZED,
+            Json(ApiResponseBody::new_error(StatusCode::UNAUTHORIZED, "Unauthorized".to_string())),
+        )
+            .into_response(),
+    }
+}
+
+/// Middleware recording [`ADMIN_TOKEN_ACTOR`] as the actor of the changes made by the requests
+/// of the admin routes, once let through by [`require_admin_token`].
+pub async fn record_admin_token_actor(request: Request, next: Next) -> Response {
+    record_actor(ADMIN_TOKEN_ACTOR, None);
+    next.run(request).await
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,12 @@
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+
+use application::ports::audit::{AuditActor, AUDIT_ACTOR};
+
+/// Middleware handling each request with an actor of its own, recorded once the request is
+/// authenticated, so the changes the request makes are recorded in the audit log with the
+/// user making them.
+pub async fn record_audit_actors(request: Request, next: Next) -> Response {
+    AUDIT_ACTOR.scope(AuditActor::default(), next.run(request)).await
+}
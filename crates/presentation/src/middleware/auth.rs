@@ -0,0 +1,281 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::{header, request::Parts, Extensions};
+
+use application::flows::auth_service::{AuthService, AuthServiceTrait};
+use application::ports::audit::record_actor;
+use application::ports::auth::{DisabledAuthenticator, DisabledTokens, Principal, CONSENTS_WRITE_SCOPE, DEVICES_SCOPE, PASSKEYS_SCOPE, USERS_WRITE_SCOPE};
+use domain::user::model::Role;
+
+use crate::handlers::user_handlers::ApiError;
+use crate::middleware::http_cache::Negotiated;
+
+/// The dependencies of the login handler and of the [`AuthenticatedUser`] extractor.
+#[derive(Clone)]
+pub struct AuthState {
+    pub auth_service: Arc<dyn AuthServiceTrait + Send + Sync + 'static>,
+}
+
+impl Default for AuthState {
+    /// Authentication disabled: logins fail and authenticated routes reject every request.
+    fn default() -> Self {
+        Self {
+            auth_service: Arc::new(AuthService::new(Arc::new(DisabledAuthenticator), Arc::new(DisabledTokens))),
+        }
+    }
+}
+
+/// The user authenticated by the bearer access token of the request.
+///
+/// Taking it as a handler argument makes the route require authentication: requests without
+/// a valid token are rejected with 401 before the handler runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthenticatedUser {
+    pub user_id: String,
+    /// Id of the admin acting on behalf of the user, when authenticated by an impersonation token.
+    pub actor_id: Option<String>,
+    /// Roles granted to the user when logging in.
+    pub roles: Vec<String>,
+    /// Scopes of the token the request was authenticated with.
+    pub scopes: Vec<String>,
+}
+
+impl AuthenticatedUser {
+    /// Returns whether an admin is acting on behalf of the user.
+    pub fn is_impersonated(&self) -> bool {
+        self.actor_id.is_some()
+    }
+
+    /// Returns the id of the user making the request: the admin when impersonating, or else
+    /// the user.
+    pub fn acting_user_id(&self) -> &str {
+        self.actor_id.as_deref().unwrap_or(&self.user_id)
+    }
+
+    /// Returns the id of the user impersonated by the admin making the request, `None` unless
+    /// impersonating.
+    pub fn impersonated_user_id(&self) -> Option<&str> {
+        self.actor_id.as_ref().map(|_| self.user_id.as_str())
+    }
+
+    /// Returns whether the user was granted the given role.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|granted| granted == role)
+    }
+
+    /// Returns whether the token of the request has the given scope.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|granted| granted == scope)
+    }
+}
+
+impl<S> FromRequestParts<S> for AuthenticatedUser
+where
+    S: Send + Sync,
+    AuthState: FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Negotiated::record(&parts.extensions, header::AUTHORIZATION);
+        // Already authenticated by the scopes of the route (see `crate::routes`)
+        if let Some(user) = parts.extensions.get::<AuthenticatedUser>() {
+            return Ok(user.clone());
+        }
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::Unauthorized("Missing bearer token".to_string()))?;
+
+        let principal = AuthState::from_ref(state).auth_service.authenticate(token).await?;
+        RecordedPrincipal::record(&parts.extensions, &principal.user_id);
+
+        // Requests made while impersonating are logged with both identities, whatever the sampling
+        if let Some(actor_id) = &principal.actor_id {
+            tracing::info!(user.id = %principal.user_id, actor.id = %actor_id, method = %parts.method, uri = %parts.uri, "request made on behalf of user");
+        }
+
+        let user = AuthenticatedUser::from(principal);
+        record_actor(user.acting_user_id(), user.impersonated_user_id());
+        Ok(user)
+    }
+}
+
+/// The id of the user a request was authenticated as, recorded by the [`AuthenticatedUser`]
+/// extractor for the middlewares reporting on requests, e.g. the request tap.
+#[derive(Debug, Clone, Default)]
+pub struct RecordedPrincipal(Arc<Mutex<Option<String>>>);
+
+impl RecordedPrincipal {
+    /// Returns the principal of the request with `extensions`, attaching one to it first unless
+    /// another middleware did, so the user is recorded once the request is authenticated.
+    pub fn attach(extensions: &mut Extensions) -> Self {
+        extensions.get_or_insert_default::<RecordedPrincipal>().clone()
+    }
+
+    /// Records that the request with `extensions` was authenticated as `user_id`. Does nothing
+    /// when no principal is attached to the request.
+    pub fn record(extensions: &Extensions, user_id: &str) {
+        if let Some(principal) = extensions.get::<RecordedPrincipal>() {
+            *principal.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(user_id.to_string());
+        }
+    }
+
+    /// Returns the id of the user the request was authenticated as, `None` until it is.
+    pub fn user_id(&self) -> Option<String> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl From<Principal> for AuthenticatedUser {
+    fn from(principal: Principal) -> Self {
+        Self {
+            user_id: principal.user_id,
+            actor_id: principal.actor_id,
+            roles: principal.roles,
+            scopes: principal.scopes,
+        }
+    }
+}
+
+/// A scope a route requires, checked by the [`RequireScope`] extractor.
+pub trait RequiredScope {
+    /// The scope, e.g. `users:write`.
+    const SCOPE: &'static str;
+}
+
+/// Requires the `users:write` scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsersWrite;
+
+impl RequiredScope for UsersWrite {
+    const SCOPE: &'static str = USERS_WRITE_SCOPE;
+}
+
+/// Requires the `consents:write` scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsentsWrite;
+
+impl RequiredScope for ConsentsWrite {
+    const SCOPE: &'static str = CONSENTS_WRITE_SCOPE;
+}
+
+/// Requires the `passkeys` scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Passkeys;
+
+impl RequiredScope for Passkeys {
+    const SCOPE: &'static str = PASSKEYS_SCOPE;
+}
+
+/// Requires the `devices` scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Devices;
+
+impl RequiredScope for Devices {
+    const SCOPE: &'static str = DEVICES_SCOPE;
+}
+
+/// The [`AuthenticatedUser`] of a request whose token has the scope `S`.
+///
+/// Taking it as a handler argument makes the route require both authentication and the scope:
+/// requests without a valid token are rejected with 401, and tokens without the scope with 403.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequireScope<S: RequiredScope> {
+    user: AuthenticatedUser,
+    scope: PhantomData<S>,
+}
+
+impl<S: RequiredScope> RequireScope<S> {
+    /// Returns the authenticated user.
+    pub fn into_inner(self) -> AuthenticatedUser {
+        self.user
+    }
+}
+
+impl<S: RequiredScope> Deref for RequireScope<S> {
+    type Target = AuthenticatedUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+impl<S, St> FromRequestParts<St> for RequireScope<S>
+where
+    S: RequiredScope,
+    St: Send + Sync,
+    AuthState: FromRef<St>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &St) -> Result<Self, Self::Rejection> {
+        let user = AuthenticatedUser::from_request_parts(parts, state).await?;
+        if !user.has_scope(S::SCOPE) {
+            return Err(ApiError::Forbidden(format!("The token lacks the {} scope", S::SCOPE)));
+        }
+        Ok(RequireScope { user, scope: PhantomData })
+    }
+}
+
+/// A role a route requires, checked by the [`RequireRole`] extractor.
+pub trait RequiredRole {
+    /// The role, e.g. [`Role::Admin`].
+    const ROLE: Role;
+}
+
+/// Requires the `admin` role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Admin;
+
+impl RequiredRole for Admin {
+    const ROLE: Role = Role::Admin;
+}
+
+/// The [`AuthenticatedUser`] of a request whose token carries the role `R`.
+///
+/// Taking it as a handler argument makes the route require both authentication and the role:
+/// requests without a valid token are rejected with 401, and users without the role with 403.
+/// Roles are those the token was issued with, so a change of role applies from the next login.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequireRole<R: RequiredRole> {
+    user: AuthenticatedUser,
+    role: PhantomData<R>,
+}
+
+impl<R: RequiredRole> RequireRole<R> {
+    /// Returns the authenticated user.
+    pub fn into_inner(self) -> AuthenticatedUser {
+        self.user
+    }
+}
+
+impl<R: RequiredRole> Deref for RequireRole<R> {
+    type Target = AuthenticatedUser;
+
+    fn deref(&self) -> &Self::Target {
+        &self.user
+    }
+}
+
+impl<R, St> FromRequestParts<St> for RequireRole<R>
+where
+    R: RequiredRole,
+    St: Send + Sync,
+    AuthState: FromRef<St>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &St) -> Result<Self, Self::Rejection> {
+        let user = AuthenticatedUser::from_request_parts(parts, state).await?;
+        if !user.has_role(R::ROLE.as_str()) {
+            return Err(ApiError::Forbidden(format!("The user lacks the {} role", R::ROLE)));
+        }
+        Ok(RequireRole { user, role: PhantomData })
+    }
+}
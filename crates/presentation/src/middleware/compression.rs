@@ -0,0 +1,29 @@
+use tower_http::compression::CompressionLayer;
+
+/// Encodings responses may be compressed with, as accepted by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionPolicy {
+    /// Enabled encodings among `gzip`, `br`, `zstd` and `deflate`.
+    pub encodings: Vec<String>,
+}
+
+impl CompressionPolicy {
+    /// Builds the layer compressing responses with the enabled encoding the client prefers,
+    /// according to its `accept-encoding` header. Small responses, images and event streams
+    /// are left uncompressed.
+    ///
+    /// Fails on unknown encodings.
+    pub fn layer(&self) -> eyre::Result<CompressionLayer> {
+        let mut layer = CompressionLayer::new().no_gzip().no_br().no_zstd().no_deflate();
+        for encoding in &self.encodings {
+            layer = match encoding.to_lowercase().as_str() {
+                "gzip" => layer.gzip(true),
+                "br" => layer.br(true),
+                "zstd" => layer.zstd(true),
+                "deflate" => layer.deflate(true),
+                _ => eyre::bail!("unknown compression encoding {}", encoding),
+            };
+        }
+        Ok(layer)
+    }
+}
@@ -0,0 +1,75 @@
+use axum::http::{header, HeaderName, HeaderValue, Method};
+use eyre::Context;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+use crate::middleware::request_id::REQUEST_ID_HEADER;
+use crate::versioning::{DEPRECATION, SUNSET};
+
+/// Entry of the allowed origins, methods or headers allowing any value.
+pub const WILDCARD: &str = "*";
+
+/// Cross-origin requests accepted from browser frontends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsPolicy {
+    /// Origins allowed to call the API, e.g. `https://app.example.com`, or `*` for any origin.
+    pub allowed_origins: Vec<String>,
+    /// Methods of the cross-origin requests, or `*` for any method.
+    pub allowed_methods: Vec<String>,
+    /// Headers the cross-origin requests may set, or `*` for any header.
+    pub allowed_headers: Vec<String>,
+    /// Whether cross-origin requests may carry credentials (cookies, HTTP authentication).
+    pub allow_credentials: bool,
+}
+
+impl CorsPolicy {
+    /// Builds the layer answering preflight requests and adding the CORS headers to responses.
+    /// Frontends may read the `x-request-id` header of responses, e.g. to report an error.
+    ///
+    /// Fails on invalid origins, methods or headers, and when credentials are allowed for any
+    /// origin, which browsers refuse.
+    pub fn layer(&self) -> eyre::Result<CorsLayer> {
+        let origins = if self.allowed_origins.iter().any(|origin| origin == WILDCARD) {
+            if self.allow_credentials {
+                eyre::bail!("credentials cannot be allowed for any origin, list the allowed origins instead");
+            }
+            AllowOrigin::any()
+        } else {
+            let origins = self
+                .allowed_origins
+                .iter()
+                .map(|origin| HeaderValue::from_str(origin).with_context(|| format!("invalid CORS origin {}", origin)))
+                .collect::<eyre::Result<Vec<_>>>()?;
+            AllowOrigin::list(origins)
+        };
+
+        // With credentials, browsers take `*` literally, so wildcards mirror the request instead
+        let methods = if self.allowed_methods.iter().any(|method| method == WILDCARD) {
+            if self.allow_credentials { AllowMethods::mirror_request() } else { AllowMethods::any() }
+        } else {
+            let methods = self
+                .allowed_methods
+                .iter()
+                .map(|method| method.to_uppercase().parse::<Method>().with_context(|| format!("invalid CORS method {}", method)))
+                .collect::<eyre::Result<Vec<_>>>()?;
+            AllowMethods::list(methods)
+        };
+
+        let headers = if self.allowed_headers.iter().any(|header| header == WILDCARD) {
+            if self.allow_credentials { AllowHeaders::mirror_request() } else { AllowHeaders::any() }
+        } else {
+            let headers = self
+                .allowed_headers
+                .iter()
+                .map(|header| header.parse::<HeaderName>().with_context(|| format!("invalid CORS header {}", header)))
+                .collect::<eyre::Result<Vec<_>>>()?;
+            AllowHeaders::list(headers)
+        };
+
+        Ok(CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .expose_headers([REQUEST_ID_HEADER, header::ETAG, DEPRECATION, SUNSET, header::LINK])
+            .allow_credentials(self.allow_credentials))
+    }
+}
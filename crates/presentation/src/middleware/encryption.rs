@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Deserialize;
+
+use crate::handlers::user_handlers::ApiResponseBody;
+
+/// Content type of JWE compact serialization request bodies.
+pub const JOSE_CONTENT_TYPE: &str = "application/jose";
+
+/// Largest encrypted body accepted, matching axum's default body limit.
+const MAX_ENCRYPTED_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+/// Length of AES-256 keys, in bytes.
+const KEY_LENGTH: usize = 32;
+
+/// Length of AES-GCM initialization vectors, in bytes.
+const IV_LENGTH: usize = 12;
+
+/// Length of AES-GCM authentication tags, in bytes.
+const TAG_LENGTH: usize = 16;
+
+/// Symmetric keys decrypting JWE request bodies, by key id.
+///
+/// Several keys can be active at once so clients can move to a new key before the old one
+/// is removed.
+#[derive(Clone)]
+pub struct JweKeys(Arc<HashMap<String, Key<Aes256Gcm>>>);
+
+impl JweKeys {
+    /// Creates the key set from `(key id, base64url-encoded 256-bit key)` pairs.
+    pub fn from_base64<I>(keys: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut decoded = HashMap::new();
+        for (kid, key) in keys {
+            let key = URL_SAFE_NO_PAD
+                .decode(key.trim_end_matches('='))
+                .map_err(|e| format!("JWE key {} is not valid base64url: {}", kid, e))?;
+            if key.len() != KEY_LENGTH {
+                return Err(format!("JWE key {} must be {} bytes, got {}", kid, KEY_LENGTH, key.len()));
+            }
+            decoded.insert(kid, *Key::<Aes256Gcm>::from_slice(&key));
+        }
+        if decoded.is_empty() {
+            return Err("at least one JWE key is required".to_string());
+        }
+        Ok(Self(Arc::new(decoded)))
+    }
+
+    /// Decrypts a JWE in compact serialization, returning the plaintext and its content type.
+    fn decrypt(&self, jwe: &str) -> Option<(Vec<u8>, Option<String>)> {
+        let [protected, encrypted_key, iv, ciphertext, tag] = jwe.trim().split('.').collect::<Vec<_>>().try_into().ok()?;
+
+        let header: JweHeader = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(protected).ok()?).ok()?;
+        if header.alg != "dir" || header.enc != "A256GCM" || !encrypted_key.is_empty() {
+            return None;
+        }
+
+        let iv = URL_SAFE_NO_PAD.decode(iv).ok().filter(|iv| iv.len() == IV_LENGTH)?;
+        let tag = URL_SAFE_NO_PAD.decode(tag).ok().filter(|tag| tag.len() == TAG_LENGTH)?;
+        let mut sealed = URL_SAFE_NO_PAD.decode(ciphertext).ok()?;
+        sealed.extend_from_slice(&tag);
+
+        // The protected header, as transmitted, is authenticated as additional data.
+        let decrypt = |key: &Key<Aes256Gcm>| {
+            Aes256Gcm::new(key)
+                .decrypt(Nonce::from_slice(&iv), Payload { msg: &sealed, aad: protected.as_bytes() })
+                .ok()
+        };
+        let plaintext = match &header.kid {
+            Some(kid) => decrypt(self.0.get(kid)?),
+            None => self.0.values().find_map(decrypt),
+        }?;
+
+        Some((plaintext, header.cty))
+    }
+}
+
+/// The protected header of a JWE.
+#[derive(Deserialize)]
+struct JweHeader {
+    alg: String,
+    enc: String,
+    kid: Option<String>,
+    cty: Option<String>,
+}
+
+/// Middleware decrypting request bodies sent as JWE (`Content-Type: application/jose`).
+///
+/// Only direct encryption with a shared key (`alg: dir`, `enc: A256GCM`) is supported. The
+/// decrypted body replaces the request body and its content type is taken from the `cty`
+/// header parameter, defaulting to JSON. Plaintext requests are passed through unchanged.
+pub async fn decrypt_jwe_requests(State(keys): State<JweKeys>, request: Request, next: Next) -> Response {
+    let encrypted = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case(JOSE_CONTENT_TYPE));
+    if !encrypted {
+        return next.run(request).await;
+    }
+
+    let (mut parts, body) = request.into_parts();
+    let decrypted = match to_bytes(body, MAX_ENCRYPTED_BODY_BYTES).await {
+        Ok(bytes) => std::str::from_utf8(&bytes).ok().and_then(|jwe| keys.decrypt(jwe)),
+        Err(_) => None,
+    };
+    let Some((plaintext, content_type)) = decrypted else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponseBody::new_error(StatusCode::BAD_REQUEST, "Invalid encrypted payload".to_string())),
+        )
+            .into_response();
+    };
+
+    let content_type = content_type
+        .and_then(|value| HeaderValue::from_str(&value).ok())
+        .unwrap_or(HeaderValue::from_static("application/json"));
+    parts.headers.insert(header::CONTENT_TYPE, content_type);
+    parts.headers.remove(header::CONTENT_LENGTH);
+
+    next.run(Request::from_parts(parts, Body::from(plaintext))).await
+}
@@ -0,0 +1,63 @@
+use std::any::Any;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+use application::ports::error_reporter::{ErrorLevel, ErrorReport, ErrorReporterPort, RequestContext, REQUEST_CONTEXT};
+
+use crate::handlers::user_handlers::ApiResponseBody;
+
+/// Response extension carrying the internal cause of a server error, which is reported
+/// but never sent to the client.
+#[derive(Debug, Clone)]
+pub(crate) struct ServerErrorDetail(pub String);
+
+/// Response extension marking server errors caused by a panic, which are already
+/// reported by the panic hook.
+#[derive(Debug, Clone, Copy)]
+struct PanicResponse;
+
+/// Middleware reporting server errors (5xx) with the method and path of the request.
+///
+/// The request context is also made available to the panic hook while the request is handled.
+pub async fn report_server_errors(
+    State(reporter): State<Arc<dyn ErrorReporterPort + Send + Sync + 'static>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let context = RequestContext {
+        method: request.method().to_string(),
+        path: request.uri().path().to_string(),
+    };
+
+    let response = REQUEST_CONTEXT.scope(context.clone(), next.run(request)).await;
+
+    if response.status().is_server_error() && response.extensions().get::<PanicResponse>().is_none() {
+        let message = match response.extensions().get::<ServerErrorDetail>() {
+            Some(detail) => detail.0.clone(),
+            None => response.status().to_string(),
+        };
+        reporter.report(ErrorReport {
+            level: ErrorLevel::Error,
+            message,
+            request: Some(context),
+        });
+    }
+
+    response
+}
+
+/// Converts a panic in a request handler into a 500 response, so the connection is not dropped.
+pub fn panic_response(_panic: Box<dyn Any + Send + 'static>) -> Response {
+    let mut response = (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ApiResponseBody::new_error(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())),
+    )
+        .into_response();
+    response.extensions_mut().insert(PanicResponse);
+    response
+}
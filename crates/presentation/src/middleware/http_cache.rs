@@ -0,0 +1,83 @@
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+use axum::extract::Request;
+use axum::http::{header, Extensions, HeaderName, HeaderValue};
+use axum::middleware::Next;
+use axum::response::{IntoResponseParts, Response, ResponseParts};
+
+/// Header listing the surrogate keys of a response, separated by spaces, by which shared
+/// caches purge it.
+pub const SURROGATE_KEY: HeaderName = HeaderName::from_static("surrogate-key");
+
+/// Header listing the surrogate keys of a response, separated by commas, the way Cloudflare
+/// expects them.
+pub const CACHE_TAG: HeaderName = HeaderName::from_static("cache-tag");
+
+/// Request headers the response of a request was chosen by, listed in its `Vary` header by
+/// [`vary_on_negotiated_headers`].
+///
+/// Extractors and middleware whose outcome depends on a request header record it with
+/// [`Negotiated::record`] (e.g. [`crate::middleware::auth::AuthenticatedUser`] records
+/// `Authorization`), so shared caches do not serve a response to requests it was not meant for.
+#[derive(Debug, Clone, Default)]
+pub struct Negotiated(Arc<Mutex<Vec<HeaderName>>>);
+
+impl Negotiated {
+    /// Records that the response of the request with `extensions` depends on `name`. Does
+    /// nothing when the request is not handled by [`vary_on_negotiated_headers`].
+    pub fn record(extensions: &Extensions, name: HeaderName) {
+        if let Some(negotiated) = extensions.get::<Negotiated>() {
+            let mut names = negotiated.0.lock().unwrap_or_else(|e| e.into_inner());
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+}
+
+/// Middleware adding the request headers recorded in [`Negotiated`] to the `Vary` header of
+/// the response, alongside the ones other layers (e.g. CORS) set.
+pub async fn vary_on_negotiated_headers(mut request: Request, next: Next) -> Response {
+    let negotiated = Negotiated::default();
+    request.extensions_mut().insert(negotiated.clone());
+
+    let mut response = next.run(request).await;
+    let names = std::mem::take(&mut *negotiated.0.lock().unwrap_or_else(|e| e.into_inner()));
+    for name in names {
+        let listed = response
+            .headers()
+            .get_all(header::VARY)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|listed| listed.trim().eq_ignore_ascii_case(name.as_str()) || listed.trim() == "*");
+        if !listed {
+            response.headers_mut().append(header::VARY, HeaderValue::from(name));
+        }
+    }
+    response
+}
+
+/// Surrogate keys of a response, e.g. `user-<id>`, set in its [`SURROGATE_KEY`] and
+/// [`CACHE_TAG`] headers.
+///
+/// Handlers return them along with their response; the keys of the responses altered by a
+/// change are purged through the [`application::ports::purge::PurgePort`] once it is made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurrogateKeys(pub Vec<String>);
+
+impl IntoResponseParts for SurrogateKeys {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut parts: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        match (HeaderValue::try_from(self.0.join(" ")), HeaderValue::try_from(self.0.join(","))) {
+            (Ok(surrogate_key), Ok(cache_tag)) => {
+                parts.headers_mut().insert(SURROGATE_KEY, surrogate_key);
+                parts.headers_mut().insert(CACHE_TAG, cache_tag);
+            }
+            (Err(e), _) | (_, Err(e)) => tracing::warn!("invalid surrogate keys: {}", e),
+        }
+        Ok(parts)
+    }
+}
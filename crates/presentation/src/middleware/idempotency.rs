@@ -0,0 +1,203 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use sha2::{Digest, Sha256};
+
+use application::ports::idempotency::{IdempotencyPort, Reservation, StoredResponse};
+
+use crate::handlers::user_handlers::ApiResponseBody;
+
+/// Header carrying the idempotency key chosen by the client for a request and its retries.
+pub const IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+
+/// Header set on the responses replayed from an earlier request.
+pub const IDEMPOTENT_REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Headers of the responses stored and replayed along their body, so a retry of a creation
+/// can be followed by a conditional update, like the first attempt.
+const REPLAYED_HEADERS: [HeaderName; 2] = [header::ETAG, header::LOCATION];
+
+/// Longest idempotency key accepted, enough for a UUID or a hash with a prefix.
+const MAX_KEY_LENGTH: usize = 255;
+
+/// Time a key is held for while its request is handled, unless set with
+/// [`Idempotency::with_lease`]. Longer than the default request timeout.
+pub const DEFAULT_LEASE: Duration = Duration::from_secs(60);
+
+/// Storage of the idempotency keys and the times they are kept for.
+#[derive(Clone)]
+pub struct Idempotency {
+    store: Arc<dyn IdempotencyPort + Send + Sync>,
+    ttl: Duration,
+    lease: Duration,
+}
+
+impl Idempotency {
+    /// Creates a new `Idempotency` replaying the responses stored in `store` for `ttl`.
+    pub fn new(store: Arc<dyn IdempotencyPort + Send + Sync>, ttl: Duration) -> Self {
+        Self { store, ttl, lease: DEFAULT_LEASE }
+    }
+
+    /// Holds the keys of the requests being handled for `lease`, after which their retries are
+    /// handled again, in case the key could not be released (e.g. the replica crashed).
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+}
+
+/// A key reserved for the request being handled, released when dropped before its response
+/// is stored, e.g. when the client disconnects or the request times out.
+struct HeldKey {
+    store: Arc<dyn IdempotencyPort + Send + Sync>,
+    key: Option<String>,
+}
+
+impl HeldKey {
+    /// Stores `response`, replayed for `ttl`.
+    async fn complete(mut self, response: StoredResponse, ttl: Duration) {
+        let Some(key) = self.key.take() else { return };
+        // The request was handled, a retry failing to be replayed would be handled again
+        if let Err(e) = self.store.complete(&key, response, ttl).await {
+            tracing::warn!("failed to store idempotent response: {:#}", e);
+        }
+    }
+
+    /// Releases the key, so the request can be retried.
+    async fn release(mut self) {
+        let Some(key) = self.key.take() else { return };
+        release(self.store.as_ref(), &key).await;
+    }
+}
+
+impl Drop for HeldKey {
+    fn drop(&mut self) {
+        let Some(key) = self.key.take() else { return };
+        // The request future is dropped, the key is released by a task of its own
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else { return };
+        let store = self.store.clone();
+        runtime.spawn(async move { release(store.as_ref(), &key).await });
+    }
+}
+
+async fn release(store: &(dyn IdempotencyPort + Send + Sync), key: &str) {
+    if let Err(e) = store.release(key).await {
+        tracing::warn!("failed to release idempotency key: {:#}", e);
+    }
+}
+
+/// Middleware replaying the response of a `POST` request to its retries, identified by the
+/// `Idempotency-Key` header.
+///
+/// Keys are scoped to the credentials of the client, so clients cannot replay the responses
+/// of each other. A key reused for another request is rejected with 422 Unprocessable Entity,
+/// and a retry sent while the request is still handled with 409 Conflict. Responses are
+/// stored unless they are server errors, which release the key so the request can be retried,
+/// as do requests ending without a response (dropped on timeout or disconnection). Requests
+/// without the header, and other methods, are passed through unchanged.
+pub async fn replay_idempotent_requests(State(idempotency): State<Idempotency>, request: Request, next: Next) -> Response {
+    if request.method() != Method::POST {
+        return next.run(request).await;
+    }
+    let Some(key) = request.headers().get(&IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => scoped_key(request.headers().get(header::AUTHORIZATION), key),
+        _ => return error(StatusCode::BAD_REQUEST, format!("Idempotency-Key must be 1 to {} visible ASCII characters", MAX_KEY_LENGTH)),
+    };
+
+    let (parts, body) = request.into_parts();
+    // The body size is bounded by the request body limit of the server
+    let Ok(body) = to_bytes(body, usize::MAX).await else {
+        return error(StatusCode::BAD_REQUEST, "Invalid request body".to_string());
+    };
+    let fingerprint = fingerprint(&parts.method, parts.uri.path_and_query().map_or("", |path| path.as_str()), &body);
+
+    let held = match idempotency.store.reserve(&key, &fingerprint, idempotency.lease).await {
+        Ok(Reservation::Reserved) => HeldKey { store: idempotency.store.clone(), key: Some(key) },
+        Ok(Reservation::InProgress { fingerprint: stored }) | Ok(Reservation::Completed { fingerprint: stored, .. }) if stored != fingerprint => {
+            return error(StatusCode::UNPROCESSABLE_ENTITY, "Idempotency-Key was used for another request".to_string());
+        }
+        Ok(Reservation::InProgress { .. }) => {
+            return error(StatusCode::CONFLICT, "A request with this Idempotency-Key is in progress".to_string());
+        }
+        Ok(Reservation::Completed { response, .. }) => return replay(response),
+        Err(e) => {
+            tracing::error!("failed to reserve idempotency key: {:#}", e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string());
+        }
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status().is_server_error() {
+        held.release().await;
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, usize::MAX).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("failed to read response to store: {:#}", e);
+            held.release().await;
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string());
+        }
+    };
+    let stored = StoredResponse {
+        status: parts.status.as_u16(),
+        content_type: parts.headers.get(header::CONTENT_TYPE).and_then(|value| value.to_str().ok()).map(str::to_string),
+        headers: REPLAYED_HEADERS
+            .iter()
+            .filter_map(|name| Some((name.to_string(), parts.headers.get(name)?.to_str().ok()?.to_string())))
+            .collect(),
+        body: body.to_vec(),
+    };
+    held.complete(stored, idempotency.ttl).await;
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// Scopes `key` to the `authorization` of the client, hashed so credentials are not stored.
+fn scoped_key(authorization: Option<&HeaderValue>, key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(authorization.map_or(&b""[..], HeaderValue::as_bytes));
+    hasher.update([0]);
+    hasher.update(key.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Hashes what identifies a request: its method, path, query and body.
+fn fingerprint(method: &Method, path: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_str().as_bytes());
+    hasher.update([0]);
+    hasher.update(path.as_bytes());
+    hasher.update([0]);
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::OK);
+    if let Some(content_type) = stored.content_type.and_then(|value| HeaderValue::from_str(&value).ok()) {
+        response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+    }
+    for (name, value) in stored.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    response.headers_mut().insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+fn error(status: StatusCode, message: String) -> Response {
+    (status, Json(ApiResponseBody::new_error(status, message))).into_response()
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::extract::{FromRequestParts, MatchedPath, Request, State};
+use axum::http::{request::Parts, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio_util::sync::CancellationToken;
+
+use crate::handlers::user_handlers::ApiResponseBody;
+use crate::middleware::auth::RecordedPrincipal;
+use crate::middleware::request_id::request_id;
+use crate::middleware::request_tap::sanitized_path;
+
+/// A request being handled, as listed by [`InFlightRequests::list`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InFlightRequest {
+    /// Id of the request in the registry of this replica, to cancel it with.
+    pub id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    pub method: String,
+    /// Template of the route, e.g. `/api/users/{id}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route: Option<String>,
+    /// Path of the request, with the segments that look like e-mail addresses redacted.
+    pub path: String,
+    /// Id of the user the request was authenticated as, omitted until it is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub elapsed_ms: u64,
+}
+
+struct Entry {
+    request_id: Option<String>,
+    method: String,
+    route: Option<String>,
+    path: String,
+    principal: RecordedPrincipal,
+    started_at: DateTime<Utc>,
+    started: Instant,
+    cancellation: CancellationToken,
+}
+
+/// Registry of the requests being handled by this replica, for on-call engineers to find the
+/// requests that are stuck and cancel them.
+#[derive(Clone, Default)]
+pub struct InFlightRequests {
+    next_id: Arc<AtomicU64>,
+    entries: Arc<Mutex<HashMap<u64, Entry>>>,
+}
+
+impl InFlightRequests {
+    /// Creates a new, empty `InFlightRequests`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the requests being handled, the longest running first.
+    pub fn list(&self) -> Vec<InFlightRequest> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let mut requests: Vec<_> = entries
+            .iter()
+            .map(|(id, entry)| InFlightRequest {
+                id: *id,
+                request_id: entry.request_id.clone(),
+                method: entry.method.clone(),
+                route: entry.route.clone(),
+                path: entry.path.clone(),
+                principal: entry.principal.user_id(),
+                started_at: entry.started_at,
+                elapsed_ms: entry.started.elapsed().as_millis() as u64,
+            })
+            .collect();
+        requests.sort_by(|a, b| b.elapsed_ms.cmp(&a.elapsed_ms).then(a.id.cmp(&b.id)));
+        requests
+    }
+
+    /// Cancels the request `id`, which is answered with 503 Service Unavailable right away.
+    /// Returns whether the request was being handled.
+    pub fn cancel(&self, id: u64) -> bool {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(&id) {
+            Some(entry) => {
+                entry.cancellation.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn register(&self, entry: Entry) -> Registration {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).insert(id, entry);
+        Registration { requests: self.clone(), id }
+    }
+}
+
+/// Removes a request from the registry once handled, or once its connection is closed.
+struct Registration {
+    requests: InFlightRequests,
+    id: u64,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.requests.entries.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+    }
+}
+
+/// The cancellation of the request being handled, cancelled when an admin cancels the request.
+///
+/// The handler of a cancelled request is dropped, which stops it at its next await point.
+/// Handlers pass the token down to work that outlives them (e.g. spawned tasks), so it stops
+/// along with the request. Requests not tracked by [`track_in_flight_requests`] get a token
+/// that is never cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct RequestCancellation(pub CancellationToken);
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestCancellation {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<RequestCancellation>().cloned().unwrap_or_default())
+    }
+}
+
+/// Middleware registering each request in `requests` while it is handled, and answering it
+/// with 503 Service Unavailable as soon as it is cancelled.
+pub async fn track_in_flight_requests(State(requests): State<InFlightRequests>, mut request: Request, next: Next) -> Response {
+    let cancellation = CancellationToken::new();
+    let entry = Entry {
+        request_id: request_id(&request).map(str::to_string),
+        method: request.method().to_string(),
+        route: request.extensions().get::<MatchedPath>().map(|matched| matched.as_str().to_string()),
+        path: sanitized_path(&request),
+        principal: RecordedPrincipal::attach(request.extensions_mut()),
+        started_at: Utc::now(),
+        started: Instant::now(),
+        cancellation: cancellation.clone(),
+    };
+    request.extensions_mut().insert(RequestCancellation(cancellation.clone()));
+    let registration = requests.register(entry);
+
+    tokio::select! {
+        response = next.run(request) => response,
+        _ = cancellation.cancelled() => {
+            tracing::warn!(in_flight.id = registration.id, "request cancelled by an admin");
+            let status = StatusCode::SERVICE_UNAVAILABLE;
+            (status, Json(ApiResponseBody::new_error(status, "Request was cancelled".to_string()))).into_response()
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use axum::error_handling::HandleErrorLayer;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{BoxError, Json, Router};
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower::load_shed::error::Overloaded;
+use tower::timeout::error::Elapsed;
+use tower::ServiceBuilder;
+
+use crate::handlers::user_handlers::ApiResponseBody;
+
+/// Seconds clients are asked to wait before retrying the requests shed under load.
+const RETRY_AFTER_SECS: &str = "1";
+
+/// Bounds of the work the server takes on, so slow dependencies (e.g. database queries) cannot
+/// pile up requests without bound.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadSheddingPolicy {
+    /// Maximum time a request is given to be answered. Requests answered later are abandoned
+    /// and answered with 408 Request Timeout. Requests are not timed out when `None`.
+    pub request_timeout: Option<Duration>,
+    /// Maximum number of requests handled at once. Requests beyond it are rejected right away
+    /// with 503 Service Unavailable rather than queued. Unlimited when `None`.
+    pub max_concurrent_requests: Option<usize>,
+}
+
+impl LoadSheddingPolicy {
+    /// Applies the timeout and the concurrency limit of the policy to every route of `router`.
+    /// Rejected and timed out requests are answered with the error body of the API.
+    pub fn apply<S>(&self, mut router: Router<S>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        if let Some(timeout) = self.request_timeout {
+            router = router.layer(ServiceBuilder::new().layer(HandleErrorLayer::new(shed_response)).timeout(timeout));
+        }
+        // The limit is shared by the routes, which are layered one by one
+        if let Some(max) = self.max_concurrent_requests {
+            router = router.layer(ServiceBuilder::new().layer(HandleErrorLayer::new(shed_response)).load_shed().layer(GlobalConcurrencyLimitLayer::new(max)));
+        }
+        router
+    }
+}
+
+/// Converts the errors of the timeout and of the load shedding into responses.
+async fn shed_response(e: BoxError) -> Response {
+    if e.is::<Elapsed>() {
+        let status = StatusCode::REQUEST_TIMEOUT;
+        return (status, Json(ApiResponseBody::new_error(status, "Request timed out".to_string()))).into_response();
+    }
+    if e.is::<Overloaded>() {
+        let status = StatusCode::SERVICE_UNAVAILABLE;
+        return (status, [(header::RETRY_AFTER, RETRY_AFTER_SECS)], Json(ApiResponseBody::new_error(status, "Too many concurrent requests".to_string()))).into_response();
+    }
+    tracing::error!("failed to handle request: {}", e);
+    let status = StatusCode::INTERNAL_SERVER_ERROR;
+    (status, Json(ApiResponseBody::new_error(status, "Internal server error".to_string()))).into_response()
+}
@@ -0,0 +1,21 @@
+pub mod admin;
+pub mod audit;
+pub mod auth;
+pub mod compression;
+pub mod cors;
+pub mod encryption;
+pub mod error_reporting;
+pub mod http_cache;
+pub mod idempotency;
+pub mod in_flight;
+pub mod load_shedding;
+pub mod rate_limit;
+pub mod request_id;
+pub mod request_tap;
+pub mod sampling;
+pub mod slo;
+pub mod tenant;
+#[cfg(feature = "otel")]
+pub mod trace_context;
+pub mod traffic_archive;
+pub mod validation;
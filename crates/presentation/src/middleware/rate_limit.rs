@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, MatchedPath, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+
+use application::ports::throttle::ThrottlePort;
+
+use crate::handlers::user_handlers::ApiResponseBody;
+use crate::middleware::tenant::Tenant;
+use crate::routes::RouteMeta;
+use crate::versioning::unversioned_route;
+
+/// Number of tracked buckets above which idle buckets are dropped.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Requests accepted from each client on each route.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    /// Requests a client may send to a route in each period, in bursts or spread out.
+    pub requests: u32,
+    /// Period over which `requests` are allowed.
+    pub period: Duration,
+    /// Per-route overrides of `requests`, matched by the longest path prefix.
+    pub route_overrides: Vec<RouteRateLimit>,
+    /// Per-class overrides of `requests`, applying to the routes declared with the class (see
+    /// [`RouteMeta`]) that no route override matches.
+    pub class_overrides: Vec<ClassRateLimit>,
+    /// Whether clients are identified by the last address of the `X-Forwarded-For` header,
+    /// set by the reverse proxy in front of the server, rather than by the peer address.
+    pub trust_forwarded_for: bool,
+}
+
+/// Rate limit override for all routes starting with `path_prefix`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteRateLimit {
+    pub path_prefix: String,
+    pub requests: u32,
+}
+
+/// Class of the rate limit of a route, declared with the route, so routes of a kind can be
+/// limited apart from the others without listing their paths.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RateLimitClass {
+    /// Routes limited by the limit of the policy.
+    #[default]
+    Standard,
+    /// Routes checking credentials, e.g. logins, where guesses are to be slowed down.
+    Credentials,
+    /// Routes doing the work of many requests at once, e.g. bulk creations.
+    Bulk,
+}
+
+impl RateLimitClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RateLimitClass::Standard => "standard",
+            RateLimitClass::Credentials => "credentials",
+            RateLimitClass::Bulk => "bulk",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "standard" => Some(RateLimitClass::Standard),
+            "credentials" => Some(RateLimitClass::Credentials),
+            "bulk" => Some(RateLimitClass::Bulk),
+            _ => None,
+        }
+    }
+}
+
+/// Rate limit override for all routes of `class`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassRateLimit {
+    pub class: RateLimitClass,
+    pub requests: u32,
+}
+
+impl RateLimitPolicy {
+    fn requests_for(&self, path: &str, class: RateLimitClass) -> u32 {
+        self.route_overrides
+            .iter()
+            .filter(|o| path.starts_with(&o.path_prefix))
+            .max_by_key(|o| o.path_prefix.len())
+            .map(|o| o.requests)
+            .or_else(|| self.class_overrides.iter().find(|o| o.class == class).map(|o| o.requests))
+            .unwrap_or(self.requests)
+    }
+}
+
+/// Token bucket of a client on a route, refilled continuously over the period.
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Temporary division of every limit, see [`ThrottlePort`].
+#[derive(Clone, Copy)]
+struct Tightening {
+    divisor: u32,
+    until: Instant,
+}
+
+/// Client, tenant and route a bucket is kept for.
+type BucketKey = (IpAddr, Option<String>, String);
+
+struct Buckets {
+    by_client: HashMap<BucketKey, Bucket>,
+    prune_at: usize,
+    tightening: Option<Tightening>,
+}
+
+/// In-memory token buckets enforcing a [`RateLimitPolicy`].
+///
+/// Buckets are kept per replica, so a client spreading its requests over `n` replicas is
+/// allowed up to `n` times the limit. Limits can be tightened for a while through
+/// [`ThrottlePort`], e.g. when an anomaly is detected. Requests of a [`Tenant`] overriding
+/// the rate limit are limited by the limit of the tenant on every route, in buckets of their own.
+#[derive(Clone)]
+pub struct RateLimiter {
+    policy: Arc<RateLimitPolicy>,
+    buckets: Arc<Mutex<Buckets>>,
+}
+
+impl RateLimiter {
+    /// Creates a new `RateLimiter` enforcing `policy`. Fails when a limit or the period is zero.
+    pub fn new(policy: RateLimitPolicy) -> eyre::Result<Self> {
+        if policy.period.is_zero() {
+            eyre::bail!("rate limit period must be positive");
+        }
+        let limits = std::iter::once(("requests", policy.requests))
+            .chain(policy.route_overrides.iter().map(|o| (o.path_prefix.as_str(), o.requests)))
+            .chain(policy.class_overrides.iter().map(|o| (o.class.as_str(), o.requests)));
+        for (name, requests) in limits {
+            if requests == 0 {
+                eyre::bail!("rate limit of {} must be positive", name);
+            }
+        }
+
+        Ok(Self {
+            policy: Arc::new(policy),
+            buckets: Arc::new(Mutex::new(Buckets { by_client: HashMap::new(), prune_at: PRUNE_THRESHOLD, tightening: None })),
+        })
+    }
+
+    /// Takes a token from the bucket of `client` of `tenant` on `route` (a path, or the
+    /// template of the matched route) of `class`, or returns the time until one is available.
+    fn acquire(&self, client: IpAddr, tenant: Option<&Tenant>, route: &str, class: RateLimitClass, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let divisor = match buckets.tightening {
+            Some(tightening) if now < tightening.until => tightening.divisor,
+            _ => 1,
+        };
+        // Buckets holding more tokens than a tightened capacity are capped by their next refill
+        let requests = tenant.and_then(|tenant| tenant.settings.rate_limit_requests).unwrap_or_else(|| self.policy.requests_for(route, class));
+        let capacity = f64::from((requests / divisor).max(1));
+        let refill_per_sec = capacity / self.policy.period.as_secs_f64();
+        let refill = |bucket: &Bucket| (bucket.tokens + now.saturating_duration_since(bucket.updated).as_secs_f64() * refill_per_sec).min(capacity);
+
+        if buckets.by_client.len() >= buckets.prune_at {
+            // Full buckets behave like new ones, so dropping them forgets nothing
+            buckets.by_client.retain(|_, bucket| refill(bucket) < capacity);
+            buckets.prune_at = (buckets.by_client.len() * 2).max(PRUNE_THRESHOLD);
+        }
+
+        let bucket = buckets
+            .by_client
+            .entry((client, tenant.map(|tenant| tenant.id.clone()), route.to_string()))
+            .or_insert(Bucket { tokens: capacity, updated: now });
+        bucket.tokens = refill(bucket);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / refill_per_sec))
+        }
+    }
+
+    fn client_ip(&self, request: &Request) -> IpAddr {
+        let forwarded = self.policy.trust_forwarded_for.then(|| forwarded_for(request.headers())).flatten();
+        let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+        // Requests served without connection info (e.g. in tests) share a single bucket
+        forwarded.or(peer).unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+    }
+}
+
+impl ThrottlePort for RateLimiter {
+    fn tighten(&self, divisor: u32, duration: Duration) {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let tightening = match buckets.tightening {
+            Some(current) if now < current.until => Tightening {
+                divisor: current.divisor.max(divisor),
+                until: current.until.max(now + duration),
+            },
+            _ => Tightening { divisor, until: now + duration },
+        };
+        buckets.tightening = Some(tightening);
+        tracing::warn!(divisor = tightening.divisor, duration_secs = duration.as_secs(), "rate limits tightened");
+    }
+}
+
+/// Returns the last address of the `X-Forwarded-For` header, the one appended by the proxy
+/// in front of the server; earlier ones are set by the client and can be forged.
+fn forwarded_for(headers: &HeaderMap) -> Option<IpAddr> {
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .next_back()
+        .and_then(|addr| addr.trim().parse().ok())
+}
+
+/// Middleware rejecting requests beyond the rate limit of their client, tenant and route with
+/// `429 Too Many Requests` and a `Retry-After` header. Routes are limited by the limit of their
+/// class when their metadata is attached to the request, see [`RouteMeta`].
+pub async fn limit_requests(State(limiter): State<RateLimiter>, request: Request, next: Next) -> Response {
+    let client = limiter.client_ip(&request);
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(matched) => matched.as_str(),
+        None => request.uri().path(),
+    };
+    // Every version of a route shares its limit, and the overrides of its unversioned path
+    let route = unversioned_route(route).into_owned();
+
+    let class = request.extensions().get::<Arc<RouteMeta>>().map_or(RateLimitClass::Standard, |meta| meta.rate_limit);
+
+    match limiter.acquire(client, request.extensions().get::<Tenant>(), &route, class, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            tracing::debug!(client.address = %client, http.route = %route, "rate limit exceeded");
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(ApiResponseBody::new_error(StatusCode::TOO_MANY_REQUESTS, "Too many requests".to_string())),
+            )
+                .into_response();
+            let seconds = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+            response
+        }
+    }
+}
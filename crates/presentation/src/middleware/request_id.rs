@@ -0,0 +1,24 @@
+use axum::extract::Request;
+use axum::http::HeaderName;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, RequestId, SetRequestIdLayer};
+
+/// Header carrying the id of a request, in requests and their responses.
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Layer giving requests without an `x-request-id` header a new UUID as their id.
+///
+/// Ids set by the client, or by the proxy in front of the server, are kept, so logs can be
+/// correlated across services.
+pub fn set_request_id_layer() -> SetRequestIdLayer<MakeRequestUuid> {
+    SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid)
+}
+
+/// Layer copying the `x-request-id` header of requests to their responses.
+pub fn propagate_request_id_layer() -> PropagateRequestIdLayer {
+    PropagateRequestIdLayer::new(REQUEST_ID_HEADER)
+}
+
+/// Returns the id of `request`, once set by [`set_request_id_layer`].
+pub fn request_id<B>(request: &Request<B>) -> Option<&str> {
+    request.extensions().get::<RequestId>().and_then(|id| id.header_value().to_str().ok())
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::{MatchedPath, OriginalUri, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::middleware::auth::RecordedPrincipal;
+use crate::middleware::request_id::request_id;
+
+/// Summary of a served request streamed to the subscribers of the [`RequestTap`].
+///
+/// Summaries are sanitized: they carry no query, header or body, and path segments that look
+/// like e-mail addresses are redacted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RequestSummary {
+    pub timestamp: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    pub method: String,
+    /// Template of the route, e.g. `/api/users/{id}`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub route: Option<String>,
+    pub path: String,
+    pub status: u16,
+    pub latency_ms: u64,
+    /// Id of the user the request was authenticated as, omitted for anonymous requests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+}
+
+/// Live feed of the summaries of the served requests, for on-call engineers to watch the
+/// traffic of a replica without access to its logs.
+///
+/// Summaries are only built while someone is subscribed. Subscribers falling more than the
+/// capacity behind skip the summaries they missed.
+#[derive(Clone)]
+pub struct RequestTap {
+    sender: broadcast::Sender<Arc<RequestSummary>>,
+}
+
+impl RequestTap {
+    /// Creates a new `RequestTap` buffering up to `capacity` summaries per subscriber.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Subscribes to the summaries of the requests served from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<RequestSummary>> {
+        self.sender.subscribe()
+    }
+
+    fn is_watched(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+}
+
+/// Middleware publishing the summary of each request to `tap` while it has subscribers.
+pub async fn tap_requests(State(tap): State<RequestTap>, mut request: Request, next: Next) -> Response {
+    if !tap.is_watched() {
+        return next.run(request).await;
+    }
+
+    let timestamp = Utc::now();
+    let started = Instant::now();
+    let request_id = request_id(&request).map(str::to_string);
+    let method = request.method().to_string();
+    let route = request.extensions().get::<MatchedPath>().map(|matched| matched.as_str().to_string());
+    let path = sanitized_path(&request);
+    let principal = RecordedPrincipal::attach(request.extensions_mut());
+
+    let response = next.run(request).await;
+    let principal = principal.user_id();
+    // Subscribers may have left meanwhile
+    let _ = tap.sender.send(Arc::new(RequestSummary {
+        timestamp,
+        request_id,
+        method,
+        route,
+        path,
+        status: response.status().as_u16(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        principal,
+    }));
+    response
+}
+
+/// Returns the path of `request`, with the segments that look like e-mail addresses redacted.
+pub(crate) fn sanitized_path(request: &Request) -> String {
+    // The URI of the request is relative to the router it is nested in
+    let path = request.extensions().get::<OriginalUri>().map_or(request.uri().path(), |uri| uri.path());
+    path.split('/')
+        .map(|segment| if segment.contains('@') || segment.contains("%40") { "redacted" } else { segment })
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -0,0 +1,132 @@
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+
+/// Name of the `http_request` span field carrying the head sampling decision.
+///
+/// The telemetry subscriber only emits debug/trace events of requests where this is `true`.
+pub const SAMPLED_FIELD: &str = "sampled";
+
+/// Sampling rates for request logs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamplingPolicy {
+    /// Fraction of successful requests that are logged, including their debug logs.
+    pub success_rate: f64,
+    /// Fraction of failed (5xx) requests that are logged.
+    pub error_rate: f64,
+    /// Per-route overrides of `success_rate`, matched by the longest path prefix.
+    #[serde(default)]
+    pub route_overrides: Vec<RouteSamplingOverride>,
+}
+
+/// Sampling rate override for all routes starting with `path_prefix`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteSamplingOverride {
+    pub path_prefix: String,
+    pub success_rate: f64,
+}
+
+impl Default for SamplingPolicy {
+    fn default() -> Self {
+        Self {
+            success_rate: 0.01,
+            error_rate: 1.0,
+            route_overrides: Vec::new(),
+        }
+    }
+}
+
+impl SamplingPolicy {
+    /// Checks that all rates are within `0.0..=1.0`.
+    pub fn validate(&self) -> Result<(), String> {
+        let rates = [("success_rate", self.success_rate), ("error_rate", self.error_rate)]
+            .into_iter()
+            .chain(self.route_overrides.iter().map(|o| (o.path_prefix.as_str(), o.success_rate)));
+
+        for (name, rate) in rates {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(format!("sampling rate of {} must be between 0 and 1, got {}", name, rate));
+            }
+        }
+        Ok(())
+    }
+
+    fn success_rate_for(&self, path: &str) -> f64 {
+        self.route_overrides
+            .iter()
+            .filter(|o| path.starts_with(&o.path_prefix))
+            .max_by_key(|o| o.path_prefix.len())
+            .map_or(self.success_rate, |o| o.success_rate)
+    }
+}
+
+/// Shared, runtime-adjustable sampling policy.
+///
+/// Decisions are made in two steps: a head decision when the request starts (using the
+/// success rate of its route) gates its debug logs, and a tail decision when it completes
+/// keeps failed requests at `error_rate` regardless of the head decision.
+#[derive(Debug, Clone, Default)]
+pub struct Sampler {
+    policy: Arc<RwLock<SamplingPolicy>>,
+}
+
+impl Sampler {
+    /// Creates a new `Sampler` with the given policy.
+    pub fn new(policy: SamplingPolicy) -> Self {
+        Self {
+            policy: Arc::new(RwLock::new(policy)),
+        }
+    }
+
+    /// Returns a copy of the current policy.
+    pub fn policy(&self) -> SamplingPolicy {
+        self.policy.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Replaces the current policy after validating it.
+    pub fn set_policy(&self, policy: SamplingPolicy) -> Result<(), String> {
+        policy.validate()?;
+        *self.policy.write().unwrap_or_else(|e| e.into_inner()) = policy;
+        Ok(())
+    }
+
+    fn sample_head(&self, path: &str) -> bool {
+        sample(self.policy.read().unwrap_or_else(|e| e.into_inner()).success_rate_for(path))
+    }
+
+    fn sample_error(&self) -> bool {
+        sample(self.policy.read().unwrap_or_else(|e| e.into_inner()).error_rate)
+    }
+}
+
+pub(crate) fn sample(rate: f64) -> bool {
+    rate >= 1.0 || (rate > 0.0 && rand::random::<f64>() < rate)
+}
+
+/// Middleware applying the sampling policy to each request.
+///
+/// Records the head decision on the request span and logs the completion of sampled
+/// successful requests and of failed requests kept by the tail decision.
+pub async fn sample_requests(State(sampler): State<Sampler>, request: Request, next: Next) -> Response {
+    let sampled = sampler.sample_head(request.uri().path());
+    tracing::Span::current().record(SAMPLED_FIELD, sampled);
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let status = response.status().as_u16();
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    if response.status().is_server_error() {
+        if sampler.sample_error() {
+            tracing::warn!(status, latency_ms, "request failed");
+        }
+    } else if sampled {
+        tracing::info!(status, latency_ms, "request completed");
+    }
+
+    response
+}
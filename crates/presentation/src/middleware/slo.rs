@@ -0,0 +1,17 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+use application::flows::slo_tracker::SloTracker;
+
+/// Middleware recording the method, status and latency of each request in `tracker`.
+pub async fn track_slos(State(tracker): State<Arc<SloTracker>>, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let started = Instant::now();
+    let response = next.run(request).await;
+    tracker.record(method.as_str(), response.status().as_u16(), started.elapsed());
+    response
+}
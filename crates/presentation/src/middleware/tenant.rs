@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body, HttpBody};
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderName, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde_json::Value;
+
+use application::ports::tenant::{TenantBranding, TenantConfigPort, TenantSettings};
+
+use crate::handlers::user_handlers::ApiResponseBody;
+
+/// Header naming the tenant a request is sent on behalf of.
+pub const TENANT_HEADER: HeaderName = HeaderName::from_static("x-tenant-id");
+
+/// Longest tenant id accepted.
+const MAX_TENANT_ID_LENGTH: usize = 64;
+
+/// Largest error response body branded, larger ones are passed on as they are.
+const MAX_BRANDED_BODY_BYTES: usize = 64 * 1024;
+
+/// The tenant of the request being handled and its settings, in the extensions of the
+/// requests sent with the [`TENANT_HEADER`].
+#[derive(Debug, Clone)]
+pub struct Tenant {
+    pub id: String,
+    /// Settings overridden by the tenant, empty when it overrides none.
+    pub settings: Arc<TenantSettings>,
+}
+
+/// Returns whether `id` is a valid tenant id: 1 to 64 letters, digits, `.`, `_` or `-`.
+pub(crate) fn is_valid_tenant_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_TENANT_ID_LENGTH
+        && id.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'-'))
+}
+
+/// Middleware resolving the tenant named by the [`TENANT_HEADER`] into a [`Tenant`] extension,
+/// read by the middleware and handlers within.
+///
+/// The header is trusted: it is expected to be set by the gateway in front of the server,
+/// which authenticates tenants. Invalid ids are rejected with 400 Bad Request. When the
+/// settings cannot be read, the tenant gets the configuration of the deployment. The JSON
+/// error responses of tenants with a [`TenantBranding`] get its message templates and
+/// support link.
+pub async fn resolve_tenants(State(config): State<Arc<dyn TenantConfigPort + Send + Sync>>, mut request: Request, next: Next) -> Response {
+    let Some(id) = request.headers().get(&TENANT_HEADER) else {
+        return next.run(request).await;
+    };
+    let id = match id.to_str() {
+        Ok(id) if is_valid_tenant_id(id) => id.to_string(),
+        _ => {
+            let status = StatusCode::BAD_REQUEST;
+            let message = format!("X-Tenant-Id must be 1 to {} letters, digits, '.', '_' or '-'", MAX_TENANT_ID_LENGTH);
+            return (status, Json(ApiResponseBody::new_error(status, message))).into_response();
+        }
+    };
+
+    let settings = match config.get(&id).await {
+        Ok(settings) => settings.unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!(tenant.id = %id, "failed to read tenant settings, using the defaults: {:#}", e);
+            TenantSettings::default()
+        }
+    };
+    let settings = Arc::new(settings);
+    request.extensions_mut().insert(Tenant { id, settings: settings.clone() });
+    let response = next.run(request).await;
+    let is_error = response.status().is_client_error() || response.status().is_server_error();
+    if is_error && !settings.branding.is_empty() {
+        return brand_error(response, &settings.branding).await;
+    }
+    response
+}
+
+/// Rewrites the message of a JSON error response with the template of `branding` for its
+/// status, and adds the support link of `branding` next to it.
+async fn brand_error(response: Response, branding: &TenantBranding) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let fits = response.body().size_hint().upper().is_some_and(|upper| upper <= MAX_BRANDED_BODY_BYTES as u64);
+    if !is_json || !fits {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, MAX_BRANDED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::debug!("failed to read error response to brand: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let Ok(mut json) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let Some(data) = json.get_mut("data").and_then(Value::as_object_mut) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+
+    if let Some(message) = data.get("message").and_then(Value::as_str) {
+        let message = branding.error_message(parts.status.as_u16(), message);
+        data.insert("message".to_string(), Value::String(message));
+    }
+    if let Some(support_url) = &branding.support_url {
+        data.insert("support_url".to_string(), Value::String(support_url.clone()));
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(json.to_string()))
+}
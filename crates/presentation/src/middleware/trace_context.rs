@@ -0,0 +1,13 @@
+use axum::http::HeaderMap;
+use opentelemetry_http::HeaderExtractor;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Makes `span` a child of the W3C trace context (`traceparent` and `tracestate` headers) of
+/// a request, so the spans of the server join the trace of the caller.
+///
+/// Requests without a valid `traceparent` start a new trace.
+pub fn set_remote_parent(span: &tracing::Span, headers: &HeaderMap) {
+    let context = opentelemetry::global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    // Fails only when spans are not exported, in which case there is nothing to join
+    let _ = span.set_parent(context);
+}
@@ -0,0 +1,206 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+use axum::body::{to_bytes, Body, HttpBody};
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::Response;
+use serde_json::Value;
+
+use application::ports::traffic_archive::{ArchivedExchange, TrafficArchivePort};
+
+use crate::middleware::request_id::request_id;
+use crate::middleware::sampling::sample;
+
+/// Headers carrying credentials, whose values are redacted.
+const SECRET_HEADERS: [header::HeaderName; 4] = [header::AUTHORIZATION, header::PROXY_AUTHORIZATION, header::COOKIE, header::SET_COOKIE];
+
+/// Fragments of the names of JSON fields and query parameters holding secrets, whose values
+/// are redacted.
+const SECRET_FIELDS: [&str; 6] = ["password", "secret", "token", "credential", "device_code", "user_code"];
+
+/// Placeholder replacing secrets.
+const REDACTED: &str = "[redacted]";
+
+/// Settings of the sampling of traffic to the archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrafficArchivePolicy {
+    /// Fraction of the requests archived with their responses.
+    pub sample_rate: f64,
+    /// Largest body archived; larger bodies, and bodies of unknown length, are left out.
+    pub max_body_bytes: usize,
+}
+
+/// Sampler of the requests of the `/api` routes to a [`TrafficArchivePort`].
+///
+/// Exchanges are sanitized before they are archived: secret headers, fields and query
+/// parameters are redacted, bodies other than JSON are left out, and e-mail addresses are
+/// replaced with pseudonyms. Pseudonyms are stable for the lifetime of the process, so
+/// requests on the same user can still be correlated and replayed, but cannot be linked
+/// back to the address.
+#[derive(Clone)]
+pub struct TrafficArchiver {
+    policy: Arc<TrafficArchivePolicy>,
+    archive: Arc<dyn TrafficArchivePort + Send + Sync + 'static>,
+    pseudonyms: RandomState,
+}
+
+impl TrafficArchiver {
+    /// Creates a new `TrafficArchiver` archiving to `archive`. Fails when the sample rate is
+    /// not within `0.0..=1.0`.
+    pub fn new(policy: TrafficArchivePolicy, archive: Arc<dyn TrafficArchivePort + Send + Sync + 'static>) -> eyre::Result<Self> {
+        if !(0.0..=1.0).contains(&policy.sample_rate) {
+            eyre::bail!("traffic archive sample rate must be between 0 and 1, got {}", policy.sample_rate);
+        }
+
+        Ok(Self { policy: Arc::new(policy), archive, pseudonyms: RandomState::new() })
+    }
+
+    /// Buffers `body` when it is JSON and small enough, returning the body to pass on and the
+    /// sanitized copy to archive.
+    async fn capture(&self, headers: &HeaderMap, body: Body) -> (Body, Option<Vec<u8>>) {
+        let is_json = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/json"));
+        let fits = body.size_hint().upper().is_some_and(|upper| upper <= self.policy.max_body_bytes as u64);
+        if !is_json || !fits {
+            return (body, None);
+        }
+
+        match to_bytes(body, self.policy.max_body_bytes).await {
+            Ok(bytes) => {
+                let sanitized = serde_json::from_slice(&bytes).ok().map(|mut json| {
+                    self.sanitize_json(&mut json);
+                    json.to_string().into_bytes()
+                });
+                (Body::from(bytes), sanitized)
+            }
+            Err(e) => {
+                tracing::debug!("failed to read body of archived exchange: {}", e);
+                (Body::empty(), None)
+            }
+        }
+    }
+
+    fn sanitize_headers(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if SECRET_HEADERS.contains(name) {
+                    REDACTED.to_string()
+                } else {
+                    self.sanitize_text(&String::from_utf8_lossy(value.as_bytes()))
+                };
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+
+    fn sanitize_query(&self, query: &str) -> String {
+        query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if is_secret(name) => format!("{}={}", name, REDACTED),
+                _ => self.sanitize_text(pair),
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    fn sanitize_json(&self, value: &mut Value) {
+        match value {
+            Value::Object(fields) => {
+                for (name, value) in fields {
+                    if is_secret(name) && !value.is_object() && !value.is_array() {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.sanitize_json(value);
+                    }
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|value| self.sanitize_json(value)),
+            Value::String(text) => *text = self.sanitize_text(text),
+            _ => {}
+        }
+    }
+
+    /// Replaces the e-mail addresses in `text` (including URL-encoded ones) with pseudonyms.
+    fn sanitize_text(&self, text: &str) -> String {
+        if !text.contains('@') && !text.contains("%40") {
+            return text.to_string();
+        }
+        text.split_inclusive(|c: char| !is_email_char(c))
+            .map(|word| {
+                let (address, separator) = match word.char_indices().last() {
+                    Some((i, c)) if !is_email_char(c) => word.split_at(i),
+                    _ => (word, ""),
+                };
+                let decoded = address.replace("%40", "@");
+                match decoded.split_once('@') {
+                    Some((local, domain)) if !local.is_empty() && domain.contains('.') => format!("{}{}", self.pseudonym(&decoded.to_lowercase()), separator),
+                    _ => word.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    fn pseudonym(&self, email: &str) -> String {
+        format!("user-{:016x}@example.invalid", self.pseudonyms.hash_one(email))
+    }
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_FIELDS.iter().any(|fragment| name.contains(fragment))
+}
+
+fn is_email_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '@' | '.' | '_' | '-' | '+' | '%')
+}
+
+/// Middleware archiving a sample of the requests and their responses, sanitized.
+///
+/// Requests that are not sampled are passed on untouched; the bodies of sampled ones are
+/// buffered, within [`TrafficArchivePolicy::max_body_bytes`].
+pub async fn archive_traffic(State(archiver): State<TrafficArchiver>, request: Request, next: Next) -> Response {
+    if !sample(archiver.policy.sample_rate) {
+        return next.run(request).await;
+    }
+
+    let received_at = SystemTime::now();
+    let started = Instant::now();
+    let request_id = request_id(&request).map(str::to_string);
+    let route = request.extensions().get::<MatchedPath>().map(|matched| matched.as_str().to_string());
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let query = request.uri().query().map(|query| archiver.sanitize_query(query));
+    let request_headers = archiver.sanitize_headers(request.headers());
+
+    let (parts, body) = request.into_parts();
+    let (body, request_body) = archiver.capture(&parts.headers, body).await;
+    let response = next.run(Request::from_parts(parts, body)).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let (parts, body) = response.into_parts();
+    let (body, response_body) = archiver.capture(&parts.headers, body).await;
+    archiver.archive.archive(ArchivedExchange {
+        received_at,
+        request_id,
+        method,
+        route,
+        path,
+        query,
+        request_headers,
+        request_body,
+        status: parts.status.as_u16(),
+        response_headers: archiver.sanitize_headers(&parts.headers),
+        response_body,
+        latency_ms,
+    });
+
+    Response::from_parts(parts, body)
+}
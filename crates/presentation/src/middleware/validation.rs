@@ -0,0 +1,35 @@
+use axum::extract::{FromRequest, Request};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+
+use domain::user::validation::ValidationErrors;
+
+use crate::handlers::user_handlers::ApiError;
+
+/// Request bodies checking their fields beyond what deserialization enforces.
+pub trait Validate {
+    /// Returns every constraint the body violates.
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// JSON request body that passed [`Validate::validate`].
+///
+/// Bodies violating a constraint are rejected with 400 and the errors of every field, before
+/// the handler runs. Bodies that cannot be deserialized are rejected like with [`Json`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<T>::from_request(request, state).await.map_err(IntoResponse::into_response)?;
+        body.validate().map_err(|errors| ApiError::InvalidRequest(errors).into_response())?;
+        Ok(Self(body))
+    }
+}
@@ -0,0 +1,162 @@
+//! Cursor-based pagination shared by the list endpoints.
+//!
+//! A [`Cursor`] carries the position after the last item of a page, e.g. the sort key and id
+//! of the last user, rather than an offset, so the next page starts after that item whatever
+//! was inserted or deleted before it in the meantime. Cursors are opaque to clients: the
+//! position is serialized as JSON, encoded as base64url and signed with a [`CursorKey`], so
+//! clients can neither read nor forge positions, and pages are served within the [`Page`]
+//! envelope with the cursor of the next page.
+
+use std::sync::Arc;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use utoipa::IntoParams;
+
+use domain::user::validation::ValidationErrors;
+
+use crate::handlers::user_handlers::ApiError;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Name of the query parameter carrying the cursor of the requested page.
+pub const CURSOR_PARAM: &str = "cursor";
+
+/// The query parameter of the list requests carrying the cursor of the requested page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CursorQueryParams {
+    /// Cursor of the requested page, the `next_cursor` of the previous page. The first page
+    /// is returned without.
+    pub cursor: Option<String>,
+}
+
+/// Key signing the cursors. Replicas serving the same clients must share it, or cursors issued
+/// by one are refused by the others.
+#[derive(Clone)]
+pub struct CursorKey(Arc<[u8]>);
+
+impl CursorKey {
+    /// Creates a key from a secret of any length.
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self(secret.as_ref().into())
+    }
+
+    /// Creates a random key, for a single replica: its cursors are refused after a restart.
+    pub fn random() -> Self {
+        Self::new(rand::random::<[u8; 32]>())
+    }
+
+    /// Returns the MAC of `payload`, not finalized so it can be compared in constant time.
+    fn mac(&self, payload: &[u8]) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.0).expect("HMAC accepts keys of any length");
+        mac.update(payload);
+        mac
+    }
+}
+
+impl std::fmt::Debug for CursorKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CursorKey([redacted])")
+    }
+}
+
+/// Why a cursor sent by a client was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CursorError {
+    /// The cursor is not a cursor of this API.
+    Malformed,
+    /// The cursor was altered, or signed with another key.
+    InvalidSignature,
+    /// The cursor was issued for a list in another order.
+    OtherList,
+}
+
+impl std::fmt::Display for CursorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CursorError::Malformed => f.write_str("is not a valid cursor"),
+            CursorError::InvalidSignature => f.write_str("is not a cursor issued by this API"),
+            CursorError::OtherList => f.write_str("continues a list in another order"),
+        }
+    }
+}
+
+impl std::error::Error for CursorError {}
+
+/// Cursors are reported like the other invalid parameters, as an error of the `cursor` field.
+impl From<CursorError> for ApiError {
+    fn from(error: CursorError) -> Self {
+        let mut errors = ValidationErrors::new();
+        errors.check(CURSOR_PARAM, Err(error.to_string()));
+        ApiError::InvalidRequest(errors)
+    }
+}
+
+/// The position of a page in a list, after the item of the given `position`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor<T> {
+    pub position: T,
+}
+
+impl<T> Cursor<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Creates the cursor of the page after the item of `position`.
+    pub fn new(position: T) -> Self {
+        Self { position }
+    }
+
+    /// Encodes the cursor as `<payload>.<signature>`, both base64url-encoded.
+    pub fn encode(&self, key: &CursorKey) -> String {
+        let payload = serde_json::to_vec(&self.position).expect("positions serialize to JSON");
+        let signature = key.mac(&payload).finalize().into_bytes();
+        format!("{}.{}", URL_SAFE_NO_PAD.encode(&payload), URL_SAFE_NO_PAD.encode(signature))
+    }
+
+    /// Decodes a cursor encoded by [`Cursor::encode`] with the same key, checking its signature
+    /// before reading its position.
+    pub fn decode(cursor: &str, key: &CursorKey) -> Result<Self, CursorError> {
+        let (payload, signature) = cursor.split_once('.').ok_or(CursorError::Malformed)?;
+        let payload = URL_SAFE_NO_PAD.decode(payload).map_err(|_| CursorError::Malformed)?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| CursorError::Malformed)?;
+        key.mac(&payload).verify_slice(&signature).map_err(|_| CursorError::InvalidSignature)?;
+
+        let position = serde_json::from_slice(&payload).map_err(|_| CursorError::Malformed)?;
+        Ok(Self { position })
+    }
+}
+
+/// A page of a list, with the cursor of the next page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Page<T> {
+    /// The items of the page, in order.
+    pub items: Vec<T>,
+    /// Cursor of the next page, `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Creates the page of `items` requested with `limit`, whose next page starts after the
+    /// `position` of its last item. A page shorter than `limit` is the last one.
+    pub fn new<P>(items: Vec<T>, limit: usize, key: &CursorKey, position: impl FnOnce(&T) -> P) -> Self
+    where
+        P: Serialize + DeserializeOwned,
+    {
+        let next_cursor = match items.last() {
+            Some(last) if items.len() >= limit => Some(Cursor::new(position(last)).encode(key)),
+            _ => None,
+        };
+        Self { items, next_cursor }
+    }
+
+    /// Converts the items of the page, keeping its cursor.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page { items: self.items.into_iter().map(f).collect(), next_cursor: self.next_cursor }
+    }
+}
@@ -0,0 +1,146 @@
+//! Middleware presets: the layers a module of routes is served with, by kind of caller.
+//!
+//! Modules are mounted by [`router`](crate::http::router) with the preset of their callers
+//! rather than with a list of layers, so a new module gets the cross-cutting behavior of its
+//! kind (authentication of the callers, rate limiting, tracking, archiving) in the right order.
+//! Layers whose subsystem is disabled in the [`AppState`] are left out. Every preset sits
+//! within the layers of the whole router: request ids, tracing, sampling and error reporting.
+//! Modules declared with [`Routes`] are served with [`MiddlewarePreset::apply_routes`], their
+//! layers reading the [`RouteMeta`](crate::routes::RouteMeta) of the routes.
+
+use axum::{middleware, Router};
+
+use crate::http::AppState;
+use crate::routes::Routes;
+use crate::middleware::{
+    admin::{record_admin_token_actor, require_admin_token, AdminToken},
+    encryption::decrypt_jwe_requests,
+    in_flight::track_in_flight_requests,
+    rate_limit::limit_requests,
+    request_tap::tap_requests,
+    slo::track_slos,
+    tenant::resolve_tenants,
+    traffic_archive::archive_traffic,
+};
+
+/// Middleware preset of a module of routes.
+#[derive(Clone)]
+pub enum MiddlewarePreset {
+    /// Routes of the clients of the API: decryption of JWE bodies, archiving of the traffic,
+    /// tracking of the requests in flight, resolution of the tenant, rate limiting, SLO tracking
+    /// and the request tap.
+    PublicApi,
+    /// Routes of the services holding the token, such as identity providers: tracked in flight
+    /// and tapped, but neither rate limited, archived nor counted in the SLOs of the API.
+    InternalApi(AdminToken),
+    /// Routes of the operators holding the admin token, with the layers of the public API
+    /// except the decryption of bodies. Their changes are audited as made by the admin token.
+    Admin(AdminToken),
+    /// Routes receiving the webhooks of third parties, whose handlers check the signatures of
+    /// the calls: the layers of the public API, except the decryption of bodies and the tenants.
+    WebhookReceiver,
+}
+
+/// A layer of a preset.
+enum Layer {
+    RequireToken(AdminToken),
+    RecordAdminActor,
+    DecryptJwe,
+    ArchiveTraffic,
+    TrackInFlight,
+    LimitRate,
+    ResolveTenant,
+    TrackSlos,
+    TapRequests,
+}
+
+impl MiddlewarePreset {
+    /// Returns the layers of the preset, from the innermost.
+    fn layers(self) -> Vec<Layer> {
+        // Archived exchanges include the responses of the rate limiter. Requests are tracked in
+        // flight within the rate limiter, SLO tracking and the tap, which see the responses of
+        // cancelled requests like any other. Tenants are resolved before the rate limiter, which
+        // applies their limits, and requests rejected by the rate limiter count as served.
+        match self {
+            MiddlewarePreset::PublicApi => vec![
+                Layer::DecryptJwe,
+                Layer::ArchiveTraffic,
+                Layer::TrackInFlight,
+                Layer::LimitRate,
+                Layer::ResolveTenant,
+                Layer::TrackSlos,
+                Layer::TapRequests,
+            ],
+            MiddlewarePreset::InternalApi(token) => vec![Layer::RequireToken(token), Layer::TrackInFlight, Layer::TapRequests],
+            MiddlewarePreset::Admin(token) => vec![
+                Layer::RecordAdminActor,
+                Layer::RequireToken(token),
+                Layer::ArchiveTraffic,
+                Layer::TrackInFlight,
+                Layer::LimitRate,
+                Layer::ResolveTenant,
+                Layer::TrackSlos,
+                Layer::TapRequests,
+            ],
+            MiddlewarePreset::WebhookReceiver => vec![
+                Layer::ArchiveTraffic,
+                Layer::TrackInFlight,
+                Layer::LimitRate,
+                Layer::TrackSlos,
+                Layer::TapRequests,
+            ],
+        }
+    }
+
+    /// Serves `routes` with the layers of the preset whose subsystem is enabled in `state`.
+    pub fn apply<S, U>(self, routes: Router<S>, state: &AppState<U>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+        U: ?Sized,
+    {
+        self.layers().into_iter().fold(routes, |routes, layer| match layer {
+            Layer::RequireToken(token) => routes.layer(middleware::from_fn_with_state(token, require_admin_token)),
+            Layer::RecordAdminActor => routes.layer(middleware::from_fn(record_admin_token_actor)),
+            Layer::DecryptJwe => match &state.jwe_keys {
+                Some(keys) => routes.layer(middleware::from_fn_with_state(keys.clone(), decrypt_jwe_requests)),
+                None => routes,
+            },
+            Layer::ArchiveTraffic => match &state.traffic_archive {
+                Some(archiver) => routes.layer(middleware::from_fn_with_state(archiver.clone(), archive_traffic)),
+                None => routes,
+            },
+            Layer::TrackInFlight => match &state.in_flight.requests {
+                Some(requests) => routes.route_layer(middleware::from_fn_with_state(requests.clone(), track_in_flight_requests)),
+                None => routes,
+            },
+            Layer::LimitRate => match &state.rate_limiter {
+                Some(limiter) => routes.route_layer(middleware::from_fn_with_state(limiter.clone(), limit_requests)),
+                None => routes,
+            },
+            Layer::ResolveTenant => match &state.tenants.config {
+                Some(config) => routes.route_layer(middleware::from_fn_with_state(config.clone(), resolve_tenants)),
+                None => routes,
+            },
+            Layer::TrackSlos => match &state.slos.tracker {
+                Some(tracker) => routes.route_layer(middleware::from_fn_with_state(tracker.clone(), track_slos)),
+                None => routes,
+            },
+            Layer::TapRequests => match &state.request_tap.tap {
+                Some(tap) => routes.route_layer(middleware::from_fn_with_state(tap.clone(), tap_requests)),
+                None => routes,
+            },
+        })
+    }
+
+    /// Serves the declared `routes` with the layers of the preset, like [`apply`](Self::apply),
+    /// the requests lacking the scopes of their route being rejected within the layers, and
+    /// the metadata of their route attached to the requests for the layers to read.
+    pub fn apply_routes<S, U>(self, routes: Routes<S>, state: &AppState<U>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+        U: ?Sized,
+    {
+        let catalog = routes.catalog();
+        catalog.attach(self.apply(routes.into_router(state.auth.clone()), state))
+    }
+}
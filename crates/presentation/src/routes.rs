@@ -0,0 +1,340 @@
+//! Declarative metadata of the routes: the scopes a route requires, the class of its rate
+//! limit, the tags it is documented under and its deprecation, declared once with the route.
+//!
+//! Modules of routes declared through [`Routes`] are served with a catalog of their metadata,
+//! consumed rather than repeated by the layers and the docs:
+//!
+//! - requests are attached the [`RouteMeta`] of their route before the layers of their
+//!   [`MiddlewarePreset`](crate::presets::MiddlewarePreset), e.g. for the rate limiter to apply
+//!   the limit of the class of the route;
+//! - within the layers of the preset, requests without the scopes of their route are rejected,
+//!   and the responses of deprecated routes announce it like the deprecated versions;
+//! - the OpenAPI spec takes the tags, scopes and deprecation of its operations from the catalog;
+//! - `OPTIONS` requests are answered with the methods of their path in the `Allow` header,
+//!   unless an `OPTIONS` route is declared for the path. `HEAD` requests are served by the
+//!   `GET` routes, without the body of their responses.
+
+use std::sync::Arc;
+
+use axum::extract::{FromRequestParts, MatchedPath, Request, State};
+use axum::handler::Handler;
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{on, MethodFilter};
+use axum::Router;
+use utoipa::openapi::path::{Operation, PathItem};
+use utoipa::openapi::security::SecurityRequirement;
+use utoipa::openapi::{Deprecated, OpenApi};
+
+use crate::handlers::user_handlers::ApiError;
+use crate::middleware::auth::{AuthState, AuthenticatedUser};
+use crate::middleware::rate_limit::RateLimitClass;
+use crate::versioning::{insert_deprecation_headers, Deprecation};
+
+/// Name of the security scheme of the bearer access tokens in the OpenAPI spec.
+const BEARER_AUTH: &str = "bearer_auth";
+
+/// Metadata of a route, declared with it through [`Routes::route`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteMeta {
+    /// Scopes the access token of the requests must have. Routes without scopes do not require
+    /// authentication, though their handlers may.
+    pub scopes: Vec<&'static str>,
+    /// Class of the rate limit of the route.
+    pub rate_limit: RateLimitClass,
+    /// Tags the operation is documented under.
+    pub tags: Vec<&'static str>,
+    /// Deprecation of the route, whose successor is the path of the route replacing it.
+    pub deprecation: Option<Deprecation>,
+}
+
+impl RouteMeta {
+    /// Creates the metadata of a public route of the standard rate limit class, untagged and
+    /// not deprecated.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires `scope` from the access token of the requests.
+    pub fn scope(mut self, scope: &'static str) -> Self {
+        self.scopes.push(scope);
+        self
+    }
+
+    /// Limits the requests of the route by the limit of `class`.
+    pub fn rate_limit(mut self, class: RateLimitClass) -> Self {
+        self.rate_limit = class;
+        self
+    }
+
+    /// Documents the operation under `tag`.
+    pub fn tag(mut self, tag: &'static str) -> Self {
+        self.tags.push(tag);
+        self
+    }
+
+    /// Announces the deprecation of the route in the headers of its responses and in the docs.
+    pub fn deprecated(mut self, deprecation: Deprecation) -> Self {
+        self.deprecation = Some(deprecation);
+        self
+    }
+
+    /// Completes the documentation of `operation` with the tags, scopes and deprecation of the
+    /// route.
+    fn document(&self, operation: &mut Operation) {
+        if !self.tags.is_empty() {
+            operation.tags = Some(self.tags.iter().map(|tag| tag.to_string()).collect());
+        }
+        if !self.scopes.is_empty() {
+            operation.security = Some(vec![SecurityRequirement::new(BEARER_AUTH, self.scopes.iter().copied())]);
+        }
+        if self.deprecation.is_some() {
+            operation.deprecated = Some(Deprecated::True);
+        }
+    }
+}
+
+/// A route declared with its metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeclaredRoute {
+    pub method: Method,
+    /// Path of the route relative to the router it is declared in, e.g. `/users/{id}`.
+    pub path: String,
+    pub meta: Arc<RouteMeta>,
+}
+
+/// The metadata of the routes of a module, looked up by the routes of the requests.
+#[derive(Debug, Clone, Default)]
+pub struct RouteCatalog(Arc<[DeclaredRoute]>);
+
+impl RouteCatalog {
+    /// Returns the declared routes.
+    pub fn routes(&self) -> &[DeclaredRoute] {
+        &self.0
+    }
+
+    /// Returns the route declared for `method` whose path ends the matched path `route`, the
+    /// longest when several do, as modules are declared relative to where they are mounted.
+    /// `HEAD` requests are served by the `GET` routes.
+    pub fn find(&self, method: &Method, route: &str) -> Option<&DeclaredRoute> {
+        let routed = |declared: &DeclaredRoute| declared.method == method || (method == Method::HEAD && declared.method == Method::GET);
+        self.0
+            .iter()
+            .filter(|declared| routed(declared) && route.ends_with(&declared.path))
+            .max_by_key(|declared| declared.path.len())
+    }
+
+    /// Returns the methods allowed on the declared path `path`: the methods of its routes, in
+    /// the order they are declared, `HEAD` after `GET`, and `OPTIONS`.
+    pub fn allowed_methods(&self, path: &str) -> Vec<Method> {
+        let mut methods = Vec::new();
+        for declared in self.0.iter().filter(|declared| declared.path == path) {
+            let derived = (declared.method == Method::GET).then_some(Method::HEAD);
+            for method in std::iter::once(declared.method.clone()).chain(derived) {
+                if !methods.contains(&method) {
+                    methods.push(method);
+                }
+            }
+        }
+        if !methods.contains(&Method::OPTIONS) {
+            methods.push(Method::OPTIONS);
+        }
+        methods
+    }
+
+    /// Returns the declared paths, each once, in the order their first route is declared.
+    fn paths(&self) -> Vec<&str> {
+        let mut paths: Vec<&str> = Vec::new();
+        for declared in self.0.iter() {
+            if !paths.contains(&declared.path.as_str()) {
+                paths.push(&declared.path);
+            }
+        }
+        paths
+    }
+
+    /// Returns the metadata of the route of `request`, if declared.
+    fn meta_of(&self, request: &Request) -> Option<Arc<RouteMeta>> {
+        let route = request.extensions().get::<MatchedPath>()?;
+        self.find(request.method(), route.as_str()).map(|declared| declared.meta.clone())
+    }
+
+    /// Attaches the metadata of their route to the requests of `routes`, for the layers of
+    /// `routes` to read it. Applied once every other layer of the routes is.
+    pub fn attach<S>(&self, routes: Router<S>) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        routes.route_layer(middleware::from_fn_with_state(self.clone(), attach_route_meta))
+    }
+
+    /// Completes the operations of `openapi` documenting the declared routes, mounted under
+    /// `prefix`, with their metadata.
+    pub fn document(&self, openapi: &mut OpenApi, prefix: &str) {
+        for declared in self.0.iter() {
+            let Some(item) = openapi.paths.paths.get_mut(&format!("{}{}", prefix, declared.path)) else {
+                continue;
+            };
+            if let Some(operation) = operation_mut(item, &declared.method) {
+                declared.meta.document(operation);
+            }
+        }
+    }
+}
+
+/// Returns the operation of `item` for `method`, if documented.
+fn operation_mut<'a>(item: &'a mut PathItem, method: &Method) -> Option<&'a mut Operation> {
+    match method.as_str() {
+        "GET" => item.get.as_mut(),
+        "PUT" => item.put.as_mut(),
+        "POST" => item.post.as_mut(),
+        "DELETE" => item.delete.as_mut(),
+        "PATCH" => item.patch.as_mut(),
+        "HEAD" => item.head.as_mut(),
+        "OPTIONS" => item.options.as_mut(),
+        "TRACE" => item.trace.as_mut(),
+        _ => None,
+    }
+}
+
+/// A module of routes, each declared with its [`RouteMeta`].
+pub struct Routes<S> {
+    router: Router<S>,
+    declared: Vec<DeclaredRoute>,
+}
+
+impl<S> Default for Routes<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self { router: Router::new(), declared: Vec::new() }
+    }
+}
+
+impl<S> Routes<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    /// Creates a new, empty `Routes` instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Routes the `method` requests of `path` to `handler`, declaring the route with `meta`.
+    ///
+    /// # Panics
+    ///
+    /// When `method` cannot be routed, like routes that axum refuses.
+    pub fn route<H, T>(mut self, method: Method, path: &str, handler: H, meta: RouteMeta) -> Self
+    where
+        H: Handler<T, S>,
+        T: 'static,
+    {
+        let filter = MethodFilter::try_from(method.clone()).unwrap_or_else(|e| panic!("cannot route {} {}: {}", method, path, e));
+        self.router = self.router.route(path, on(filter, handler));
+        self.declared.push(DeclaredRoute { method, path: path.to_string(), meta: Arc::new(meta) });
+        self
+    }
+
+    /// Merges the routes of `other`, with their metadata.
+    pub fn merge(mut self, other: Routes<S>) -> Self {
+        self.router = self.router.merge(other.router);
+        self.declared.extend(other.declared);
+        self
+    }
+
+    /// Applies `f` to the router of the routes, e.g. to add a layer to the routes declared so far.
+    pub fn map_router(mut self, f: impl FnOnce(Router<S>) -> Router<S>) -> Self {
+        self.router = f(self.router);
+        self
+    }
+
+    /// Provides the routes with `state`, keeping their metadata.
+    pub fn with_state<T>(self, state: S) -> Routes<T> {
+        Routes { router: self.router.with_state(state), declared: self.declared }
+    }
+
+    /// Returns the catalog of the metadata of the routes.
+    pub fn catalog(&self) -> RouteCatalog {
+        RouteCatalog(self.declared.clone().into())
+    }
+
+    /// Returns the router of the routes, rejecting the requests lacking the scopes of their
+    /// route, as authenticated by `auth`, and announcing the deprecation of deprecated routes.
+    /// The `OPTIONS` requests of the paths without an `OPTIONS` route are answered with the
+    /// methods allowed on the path.
+    pub fn into_router(self, auth: AuthState) -> Router<S> {
+        let catalog = self.catalog();
+        let router = route_options(self.router, &catalog);
+        let guard = RouteGuard { catalog, auth };
+        router.route_layer(middleware::from_fn_with_state(guard, guard_routes))
+    }
+}
+
+/// Routes the `OPTIONS` requests of every path of `catalog` without a declared `OPTIONS` route
+/// to a `204 No Content` listing the methods allowed on the path in its `Allow` header.
+fn route_options<S>(mut router: Router<S>, catalog: &RouteCatalog) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    for path in catalog.paths() {
+        if catalog.routes().iter().any(|declared| declared.path == path && declared.method == Method::OPTIONS) {
+            continue;
+        }
+        let methods = catalog.allowed_methods(path);
+        let methods: Vec<&str> = methods.iter().map(Method::as_str).collect();
+        // Method names are tokens, always valid in a header value
+        let allow = HeaderValue::from_str(&methods.join(", ")).unwrap_or_else(|_| HeaderValue::from_static("OPTIONS"));
+        let handler = move || async move { (StatusCode::NO_CONTENT, [(header::ALLOW, allow)]) };
+        router = router.route(path, on(MethodFilter::OPTIONS, handler));
+    }
+    router
+}
+
+/// The state of [`guard_routes`].
+#[derive(Clone)]
+struct RouteGuard {
+    catalog: RouteCatalog,
+    auth: AuthState,
+}
+
+/// Middleware attaching the [`RouteMeta`] of their route to requests.
+async fn attach_route_meta(State(catalog): State<RouteCatalog>, mut request: Request, next: Next) -> Response {
+    if let Some(meta) = catalog.meta_of(&request) {
+        request.extensions_mut().insert(meta);
+    }
+    next.run(request).await
+}
+
+/// Middleware rejecting the requests lacking a scope of their route, with 401 when they are not
+/// authenticated and 403 otherwise, like [`RequireScope`](crate::middleware::auth::RequireScope).
+/// The [`AuthenticatedUser`] is attached to the requests let through, so handlers extract it
+/// without authenticating the request again.
+async fn guard_routes(State(guard): State<RouteGuard>, request: Request, next: Next) -> Response {
+    let Some(meta) = guard.catalog.meta_of(&request) else {
+        return next.run(request).await;
+    };
+
+    let request = if meta.scopes.is_empty() {
+        request
+    } else {
+        let (mut parts, body) = request.into_parts();
+        let user = match AuthenticatedUser::from_request_parts(&mut parts, &guard.auth).await {
+            Ok(user) => user,
+            Err(e) => return e.into_response(),
+        };
+        if let Some(scope) = meta.scopes.iter().find(|scope| !user.has_scope(scope)) {
+            return ApiError::Forbidden(format!("The token lacks the {} scope", scope)).into_response();
+        }
+        parts.extensions.insert(user);
+        Request::from_parts(parts, body)
+    };
+
+    let mut response = next.run(request).await;
+    if let Some(deprecation) = &meta.deprecation {
+        insert_deprecation_headers(response.headers_mut(), deprecation, &deprecation.successor);
+    }
+    response
+}
@@ -0,0 +1,161 @@
+//! JSON commands changing users, consumed from a message broker by a
+//! [`CommandHandlerPort`] consumer, and executed like the calls of the other RPC APIs.
+//!
+//! A command names its operation in `command`, and is answered with a reply carrying the
+//! same `correlation_id`:
+//!
+//! ```json
+//! { "correlation_id": "c-1", "command": "create_user", "user": { "name": "Alice", "email": "alice@example.com", "age": 30 } }
+//! { "correlation_id": "c-2", "command": "update_user", "token": "…", "id": "…", "user": { "age": 31 } }
+//! ```
+//!
+//! Replies carry the user, or an error with the codes of the Thrift API:
+//!
+//! ```json
+//! { "correlation_id": "c-1", "user": { "id": "…", "name": "Alice", "email": "alice@example.com", "age": 30 } }
+//! { "correlation_id": "c-2", "error": { "code": "unauthorized", "message": "Missing access token" } }
+//! ```
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+
+use application::flows::user_service::UserServiceTrait;
+use application::ports::audit::{AuditActor, AUDIT_ACTOR};
+use application::ports::commands::{CommandHandlerPort, CommandReply};
+use domain::user::model::User;
+
+use crate::handlers::user_handlers::{ApiError, CreateUserRequestBody, FieldErrorData, UpdateUserRequestBody, UserResponseData};
+use crate::rpc::{CallError, RpcContext, UserRpc};
+
+/// Executes the JSON commands of the users through [`UserRpc`], so they are validated and
+/// authorized like the calls of the other APIs: `update_user` requires a `token` with the
+/// `users:write` scope.
+pub struct UserCommandHandler<S: ?Sized = dyn UserServiceTrait + Send + Sync + 'static> {
+    rpc: UserRpc<S>,
+}
+
+impl<S> UserCommandHandler<S>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    /// Creates a new `UserCommandHandler` instance.
+    pub fn new(rpc: UserRpc<S>) -> Self {
+        Self { rpc }
+    }
+
+    async fn execute(&self, context: &RpcContext, command: Command) -> Result<User, ApiError> {
+        match command {
+            Command::CreateUser { user } => self.rpc.create_user(user).await,
+            Command::UpdateUser { id, user } => self.rpc.update_user(context, &id, user).await,
+        }
+    }
+}
+
+#[async_trait]
+impl<S> CommandHandlerPort for UserCommandHandler<S>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    async fn handle(&self, payload: &[u8]) -> CommandReply {
+        let envelope = match serde_json::from_slice::<CommandEnvelope>(payload) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                tracing::warn!("invalid command: {}", e);
+                let correlation_id = correlation_id(payload);
+                let error = ErrorBody { code: "invalid_request", message: format!("Invalid command: {}", e), errors: Vec::new() };
+                return reply(correlation_id, Outcome::Error(error));
+            }
+        };
+
+        let context = RpcContext { token: envelope.token, request_id: envelope.request_id };
+        let span = tracing::info_span!("command", otel.kind = "consumer", command = envelope.command.name(), correlation_id = envelope.correlation_id.as_deref().unwrap_or_default(), request_id = context.request_id.as_deref().unwrap_or_default(), outcome = tracing::field::Empty, error.class = tracing::field::Empty);
+        let result = AUDIT_ACTOR.scope(AuditActor::default(), self.execute(&context, envelope.command)).instrument(span.clone()).await;
+        let outcome = match result {
+            Ok(user) => {
+                span.record("outcome", "success");
+                Outcome::User(UserResponseData::from(&user))
+            }
+            Err(e) => {
+                span.record("outcome", "error").record("error.class", e.code());
+                Outcome::Error(span.in_scope(|| ErrorBody::from(CallError::from(e))))
+            }
+        };
+        reply(envelope.correlation_id, outcome)
+    }
+}
+
+/// A command, with the identity it is executed with.
+#[derive(Deserialize)]
+struct CommandEnvelope {
+    #[serde(default)]
+    correlation_id: Option<String>,
+    /// Access token of the user the command is executed for.
+    #[serde(default)]
+    token: Option<String>,
+    /// Id of the request the command is part of, recorded in the span of the command.
+    #[serde(default)]
+    request_id: Option<String>,
+    #[serde(flatten)]
+    command: Command,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Command {
+    CreateUser { user: CreateUserRequestBody },
+    UpdateUser { id: String, user: UpdateUserRequestBody },
+}
+
+impl Command {
+    fn name(&self) -> &'static str {
+        match self {
+            Command::CreateUser { .. } => "create_user",
+            Command::UpdateUser { .. } => "update_user",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReplyBody {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    correlation_id: Option<String>,
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Outcome {
+    User(UserResponseData),
+    Error(ErrorBody),
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    code: &'static str,
+    message: String,
+    /// Errors of the invalid fields of the command, omitted when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<FieldErrorData>,
+}
+
+impl From<CallError> for ErrorBody {
+    fn from(error: CallError) -> Self {
+        let errors = error.errors.into_iter().map(|error| FieldErrorData { field: error.field, message: error.message, rule: None }).collect();
+        Self { code: error.code, message: error.message, errors }
+    }
+}
+
+fn reply(correlation_id: Option<String>, outcome: Outcome) -> CommandReply {
+    let body = ReplyBody { correlation_id: correlation_id.clone(), outcome };
+    let payload = serde_json::to_vec(&body).expect("command replies serialize to JSON");
+    CommandReply { correlation_id, payload }
+}
+
+/// Returns the `correlation_id` of a command that failed to decode, so its sender still gets
+/// the error.
+fn correlation_id(payload: &[u8]) -> Option<String> {
+    let value = serde_json::from_slice::<serde_json::Value>(payload).ok()?;
+    value.get("correlation_id")?.as_str().map(str::to_string)
+}
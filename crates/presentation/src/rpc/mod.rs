@@ -0,0 +1,160 @@
+//! Internal RPC APIs serving the user service to other backends, next to the HTTP API.
+//!
+//! Every protocol calls [`UserRpc`], which serves the same [`UserServiceTrait`] as the HTTP
+//! handlers, authenticates calls with the same [`AuthState`], and validates requests and
+//! reports errors like the HTTP API, through [`ApiError`]. Protocols only decode calls and
+//! encode their results, so another one can be plugged in next to [`thrift`] and
+//! [`commands`].
+
+pub mod commands;
+#[cfg(feature = "thrift")]
+pub mod thrift;
+
+use std::sync::Arc;
+
+use application::flows::user_service::UserServiceTrait;
+use application::ports::audit::record_actor;
+use application::ports::auth::USERS_WRITE_SCOPE;
+use domain::user::model::{CreateUser, User, UserPage};
+use domain::user::validation::FieldError;
+
+use crate::handlers::user_handlers::{parse_user_id, ApiError, CreateUserRequestBody, ListUsersQueryParams, SearchUsersQueryParams, UpdateUserRequestBody};
+use crate::middleware::auth::{AuthState, AuthenticatedUser};
+use crate::middleware::validation::Validate;
+
+/// The identity a call is made with and the request it is part of, propagated by the caller
+/// like the `Authorization` and `x-request-id` headers of the HTTP API.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct RpcContext {
+    /// Access token of the user the call is made for.
+    pub token: Option<String>,
+    /// Id of the request the call is part of, recorded in the span of the call.
+    pub request_id: Option<String>,
+}
+
+impl std::fmt::Debug for RpcContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RpcContext")
+            .field("token", &self.token.as_ref().map(|_| "[redacted]"))
+            .field("request_id", &self.request_id)
+            .finish()
+    }
+}
+
+/// The user operations of the RPC APIs, mirroring the user routes of the HTTP API.
+///
+/// Reads are public, while changes require an access token with the `users:write` scope.
+pub struct UserRpc<S: ?Sized = dyn UserServiceTrait + Send + Sync + 'static> {
+    user_service: Arc<S>,
+    auth: AuthState,
+}
+
+impl<S> UserRpc<S>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    /// Creates a new `UserRpc` instance.
+    pub fn new(user_service: Arc<S>, auth: AuthState) -> Self {
+        Self { user_service, auth }
+    }
+
+    /// Creates a new user.
+    pub async fn create_user(&self, request: CreateUserRequestBody) -> Result<User, ApiError> {
+        request.validate()?;
+        let mut create_user = CreateUser::new(request.name, request.email, request.age)?;
+        if let Some(password) = request.password {
+            create_user = create_user.with_password(password)?;
+        }
+        Ok(self.user_service.create_user(create_user).await?)
+    }
+
+    /// Returns the user with the given id.
+    pub async fn get_user(&self, id: &str) -> Result<User, ApiError> {
+        Ok(self.user_service.get_user(parse_user_id(id)?).await?)
+    }
+
+    /// Lists a page of users.
+    pub async fn list_users(&self, params: ListUsersQueryParams) -> Result<UserPage, ApiError> {
+        Ok(self.user_service.list_users(params.into_domain()?).await?)
+    }
+
+    /// Searches a page of the users matching the parameters.
+    pub async fn search_users(&self, params: SearchUsersQueryParams) -> Result<UserPage, ApiError> {
+        Ok(self.user_service.search_users(params.into_domain()?).await?)
+    }
+
+    /// Updates the user with the given id. Requires the `users:write` scope.
+    pub async fn update_user(&self, context: &RpcContext, id: &str, request: UpdateUserRequestBody) -> Result<User, ApiError> {
+        self.require_scope(context, USERS_WRITE_SCOPE).await?;
+        request.validate()?;
+        let update_user = request.into_domain(parse_user_id(id)?)?;
+        Ok(self.user_service.update_user(update_user).await?)
+    }
+
+    /// Soft-deletes the user with the given id. Requires the `users:write` scope, and is not
+    /// allowed while impersonating.
+    pub async fn delete_user(&self, context: &RpcContext, id: &str) -> Result<(), ApiError> {
+        let user = self.require_scope(context, USERS_WRITE_SCOPE).await?;
+        if user.is_impersonated() {
+            return Err(ApiError::Forbidden("Users cannot be deleted while impersonating".to_string()));
+        }
+        Ok(self.user_service.delete_user(parse_user_id(id)?).await?)
+    }
+
+    /// Restores the deleted user with the given id. Requires the `users:write` scope.
+    pub async fn restore_user(&self, context: &RpcContext, id: &str) -> Result<User, ApiError> {
+        self.require_scope(context, USERS_WRITE_SCOPE).await?;
+        Ok(self.user_service.restore_user(parse_user_id(id)?).await?)
+    }
+
+    /// Returns the user authenticated by the token of the call, failing with
+    /// [`ApiError::Unauthorized`] without a valid token, and with [`ApiError::Forbidden`] when
+    /// the token lacks `scope`.
+    async fn require_scope(&self, context: &RpcContext, scope: &str) -> Result<AuthenticatedUser, ApiError> {
+        let token = context.token.as_deref().ok_or_else(|| ApiError::Unauthorized("Missing access token".to_string()))?;
+        let user = AuthenticatedUser::from(self.auth.auth_service.authenticate(token).await?);
+
+        // Calls made while impersonating are logged with both identities, whatever the sampling
+        if let Some(actor_id) = &user.actor_id {
+            tracing::info!(user.id = %user.user_id, actor.id = %actor_id, "call made on behalf of user");
+        }
+        if !user.has_scope(scope) {
+            return Err(ApiError::Forbidden(format!("The token lacks the {} scope", scope)));
+        }
+        record_actor(user.acting_user_id(), user.impersonated_user_id());
+        Ok(user)
+    }
+}
+
+/// An error of a call, as reported to the caller by every protocol. Internal errors are
+/// logged, and reported without their details, like by the HTTP API.
+pub(crate) struct CallError {
+    pub code: &'static str,
+    pub message: String,
+    /// Errors of the invalid fields of the request, for `invalid_request` and `weak_password`
+    /// errors.
+    pub errors: Vec<FieldError>,
+}
+
+impl From<ApiError> for CallError {
+    fn from(error: ApiError) -> Self {
+        let code = error.code();
+        let (message, errors) = match error {
+            ApiError::InvalidRequest(errors) => ("Invalid request".to_string(), errors.errors().to_vec()),
+            ApiError::WeakPassword(violations) => {
+                let errors = violations.into_iter().map(|violation| FieldError { field: "password", message: violation.message }).collect();
+                ("Password violates the password policy".to_string(), errors)
+            }
+            ApiError::InternalServerError(e) => {
+                tracing::error!("{}", e);
+                ("Internal server error".to_string(), Vec::new())
+            }
+            ApiError::ServiceUnavailable(e) => {
+                tracing::error!("{}", e);
+                ("Service unavailable".to_string(), Vec::new())
+            }
+            ApiError::Unauthorized(message) | ApiError::Forbidden(message) | ApiError::NotFound(message) | ApiError::Conflict(message) | ApiError::UnprocessableEntity(message) | ApiError::Locked(message) | ApiError::PreconditionFailed(message) | ApiError::PreconditionRequired(message) => (message, Vec::new()),
+        };
+        Self { code, message, errors }
+    }
+}
@@ -0,0 +1,599 @@
+//! Thrift server of the user service, for backends already calling each other over Thrift,
+//! with the IDL [`USER_SERVICE_THRIFT_IDL`].
+//!
+//! Calls are framed (4-byte big-endian length prefix, as with `TFramedTransport`) and encoded
+//! with the strict binary protocol. The messages are encoded by hand rather than generated
+//! from the IDL, so building does not need the Thrift compiler; they must be kept in sync with
+//! the IDL.
+
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::Context;
+use thrift::protocol::{TBinaryInputProtocol, TBinaryOutputProtocol, TFieldIdentifier, TInputProtocol, TListIdentifier, TMessageIdentifier, TMessageType, TOutputProtocol, TStructIdentifier, TType};
+use thrift::{ApplicationError, ApplicationErrorKind, ProtocolErrorKind};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time;
+use tracing::Instrument;
+
+use application::flows::user_service::UserServiceTrait;
+use application::ports::audit::{AuditActor, AUDIT_ACTOR};
+use domain::user::model::{User, UserPage};
+
+use crate::handlers::user_handlers::{ApiError, CreateUserRequestBody, ListUsersQueryParams, SearchUsersQueryParams, SortOrderParam, UpdateUserRequestBody, UserSortParam};
+use crate::rpc::{CallError, RpcContext, UserRpc};
+
+/// Thrift IDL of the user service.
+pub const USER_SERVICE_THRIFT_IDL: &str = r#"namespace rs rustweb.users
+
+/// Identity of the caller and request the call is part of.
+struct RequestContext {
+  /// Access token of the user the call is made for, as in the Authorization header of the HTTP API.
+  1: optional string token
+  /// Id of the request, as in the x-request-id header of the HTTP API.
+  2: optional string request_id
+}
+
+struct User {
+  1: required string id
+  2: required string name
+  3: required string email
+  4: required i16 age
+}
+
+struct UserPage {
+  1: required list<User> users
+  /// Number of users of all the pages.
+  2: required i64 total
+}
+
+struct CreateUserRequest {
+  1: required string name
+  2: required string email
+  3: required i16 age
+  4: optional string password
+}
+
+struct UpdateUserRequest {
+  1: optional string name
+  2: optional string email
+  3: optional i16 age
+}
+
+enum UserSortField {
+  NAME = 1,
+  EMAIL = 2,
+  AGE = 3,
+}
+
+enum SortOrder {
+  ASC = 1,
+  DESC = 2,
+}
+
+struct ListUsersRequest {
+  /// 1 to 100, default 20.
+  1: optional i32 limit
+  2: optional i64 offset
+  3: optional UserSortField sort_by
+  4: optional SortOrder order
+}
+
+struct SearchUsersRequest {
+  1: optional string name
+  2: optional string email
+  3: optional i16 min_age
+  4: optional i16 max_age
+  5: optional i32 limit
+  6: optional i64 offset
+}
+
+struct FieldError {
+  1: required string field
+  2: required string message
+}
+
+exception UserError {
+  /// invalid_request, weak_password, unauthorized, forbidden, not_found, unprocessable_entity, locked or internal.
+  1: required string code
+  2: required string message
+  /// Errors of the invalid fields, for invalid_request and weak_password.
+  3: optional list<FieldError> errors
+}
+
+service UserService {
+  User createUser(1: RequestContext context, 2: CreateUserRequest request) throws (1: UserError error)
+  User getUser(1: RequestContext context, 2: string id) throws (1: UserError error)
+  UserPage listUsers(1: RequestContext context, 2: ListUsersRequest request) throws (1: UserError error)
+  UserPage searchUsers(1: RequestContext context, 2: SearchUsersRequest request) throws (1: UserError error)
+  /// Requires the users:write scope.
+  User updateUser(1: RequestContext context, 2: string id, 3: UpdateUserRequest request) throws (1: UserError error)
+  /// Requires the users:write scope, and is not allowed while impersonating.
+  void deleteUser(1: RequestContext context, 2: string id) throws (1: UserError error)
+  /// Requires the users:write scope.
+  User restoreUser(1: RequestContext context, 2: string id) throws (1: UserError error)
+}
+"#;
+
+/// Configuration for the Thrift server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThriftServerConfig {
+    pub port: u16,
+    /// Maximum size of the frames of the calls, in bytes. Connections sending larger frames are closed.
+    pub max_frame_bytes: usize,
+    /// Maximum time calls in flight are given to complete once shutdown starts.
+    pub shutdown_timeout: Duration,
+}
+
+/// Thrift server of the user service.
+pub struct ThriftServer<S: ?Sized> {
+    rpc: Arc<UserRpc<S>>,
+    listener: TcpListener,
+    max_frame_bytes: usize,
+    shutdown_timeout: Duration,
+}
+
+impl<S> ThriftServer<S>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    /// Returns a new Thrift server bound to the port specified in `config`.
+    pub async fn new(rpc: UserRpc<S>, config: ThriftServerConfig) -> eyre::Result<Self> {
+        let listener = TcpListener::bind(format!("0.0.0.0:{}", config.port))
+            .await
+            .with_context(|| format!("failed to listen on {}", config.port))?;
+
+        Ok(Self {
+            rpc: Arc::new(rpc),
+            listener,
+            max_frame_bytes: config.max_frame_bytes,
+            shutdown_timeout: config.shutdown_timeout,
+        })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Starts serving calls in the background, until [`ThriftServerTask::shutdown`].
+    ///
+    /// The spans of the calls are nested under the current span.
+    pub fn spawn(self) -> ThriftServerTask {
+        let (shutdown, stopped) = watch::channel(false);
+        let task = tokio::spawn(self.serve(stopped).in_current_span());
+        ThriftServerTask { shutdown, task }
+    }
+
+    async fn serve(self, mut stopped: watch::Receiver<bool>) {
+        tracing::debug!("serving Thrift calls on {}", self.listener.local_addr().unwrap());
+
+        let mut connections = JoinSet::new();
+        loop {
+            tokio::select! {
+                // The task was dropped without being shut down
+                changed = stopped.changed() => if changed.is_err() || *stopped.borrow() { break },
+                accepted = self.listener.accept() => match accepted {
+                    Ok((stream, _)) => {
+                        let connection = serve_connection(self.rpc.clone(), stream, self.max_frame_bytes, stopped.clone());
+                        connections.spawn(connection.in_current_span());
+                    }
+                    Err(e) => tracing::warn!("failed to accept Thrift connection: {}", e),
+                },
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            }
+        }
+
+        // Idle connections are closed right away, the others once their call completes
+        let drain = async { while connections.join_next().await.is_some() {} };
+        if time::timeout(self.shutdown_timeout, drain).await.is_err() {
+            tracing::warn!("Thrift calls still running after {:?}, aborting them", self.shutdown_timeout);
+            connections.abort_all();
+        }
+    }
+}
+
+/// The background task of a running [`ThriftServer`].
+pub struct ThriftServerTask {
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl ThriftServerTask {
+    /// Stops accepting connections and waits for the calls in flight to complete, up to the
+    /// configured shutdown timeout.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            tracing::error!("Thrift server task failed: {}", e);
+        }
+    }
+}
+
+/// Answers the calls of a connection one at a time, until the client closes it or the server
+/// shuts down.
+async fn serve_connection<S>(rpc: Arc<UserRpc<S>>, mut stream: TcpStream, max_frame_bytes: usize, mut stopped: watch::Receiver<bool>)
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    loop {
+        let frame = tokio::select! {
+            changed = stopped.changed() => if changed.is_err() || *stopped.borrow() { return } else { continue },
+            frame = read_frame(&mut stream, max_frame_bytes) => frame,
+        };
+        let frame = match frame {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("failed to read Thrift call: {}", e);
+                return;
+            }
+        };
+
+        let reply = match handle_call(&rpc, &frame).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                tracing::warn!("failed to decode Thrift call: {}", e);
+                return;
+            }
+        };
+        let Ok(length) = u32::try_from(reply.len()) else { return };
+        if let Err(e) = write_frame(&mut stream, length, &reply).await {
+            tracing::warn!("failed to send Thrift reply: {}", e);
+            return;
+        }
+    }
+}
+
+/// Reads the next frame, returning `None` when the client closed the connection.
+async fn read_frame(stream: &mut TcpStream, max_frame_bytes: usize) -> std::io::Result<Option<Vec<u8>>> {
+    let length = match stream.read_u32().await {
+        Ok(length) => length as usize,
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if length > max_frame_bytes {
+        return Err(std::io::Error::new(ErrorKind::InvalidData, format!("frame of {} bytes exceeds the limit of {} bytes", length, max_frame_bytes)));
+    }
+
+    let mut frame = vec![0; length];
+    stream.read_exact(&mut frame).await?;
+    Ok(Some(frame))
+}
+
+async fn write_frame(stream: &mut TcpStream, length: u32, frame: &[u8]) -> std::io::Result<()> {
+    stream.write_u32(length).await?;
+    stream.write_all(frame).await?;
+    stream.flush().await
+}
+
+type Input<'a> = TBinaryInputProtocol<&'a [u8]>;
+
+type Output<'a> = TBinaryOutputProtocol<&'a mut Vec<u8>>;
+
+/// A decoded call of the user service.
+enum Call {
+    CreateUser(CreateUserRequestBody),
+    GetUser(String),
+    ListUsers(ListUsersQueryParams),
+    SearchUsers(SearchUsersQueryParams),
+    UpdateUser(String, UpdateUserRequestBody),
+    DeleteUser(String),
+    RestoreUser(String),
+}
+
+/// The result of a successful call.
+enum Success {
+    User(User),
+    Page(UserPage),
+    Void,
+}
+
+/// Answers the call of `frame`, returning the reply frame.
+///
+/// Calls that cannot be decoded or of unknown methods are answered with a
+/// `TApplicationException`. Fails when the frame is not a message at all.
+async fn handle_call<S>(rpc: &UserRpc<S>, frame: &[u8]) -> thrift::Result<Vec<u8>>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    let mut input = TBinaryInputProtocol::new(frame, true);
+    let message = input.read_message_begin()?;
+    let call = match message.message_type {
+        TMessageType::Call => read_call(&message.name, &mut input).and_then(|call| {
+            input.read_message_end()?;
+            Ok(call)
+        }),
+        message_type => Err(thrift::new_application_error(ApplicationErrorKind::InvalidMessageType, format!("unexpected message type {}", message_type))),
+    };
+
+    let mut reply = Vec::new();
+    let mut output = TBinaryOutputProtocol::new(&mut reply, true);
+    match call {
+        Ok((context, call)) => {
+            let span = tracing::info_span!("rpc_call", otel.kind = "server", rpc.system = "thrift", rpc.method = %message.name, request_id = context.request_id.as_deref().unwrap_or_default(), outcome = tracing::field::Empty, error.class = tracing::field::Empty);
+            let result = AUDIT_ACTOR.scope(AuditActor::default(), execute(rpc, &context, call)).instrument(span.clone()).await;
+            match &result {
+                Ok(_) => span.record("outcome", "success"),
+                Err(e) => span.record("outcome", "error").record("error.class", e.code()),
+            };
+
+            output.write_message_begin(&TMessageIdentifier::new(&message.name, TMessageType::Reply, message.sequence_number))?;
+            write_result(&mut output, &message.name, result)?;
+        }
+        Err(e) => {
+            let error = match e {
+                thrift::Error::Application(error) => error,
+                e => ApplicationError::new(ApplicationErrorKind::ProtocolError, e.to_string()),
+            };
+            tracing::warn!(rpc.method = %message.name, "invalid Thrift call: {}", error.message);
+            output.write_message_begin(&TMessageIdentifier::new(&message.name, TMessageType::Exception, message.sequence_number))?;
+            thrift::Error::write_application_error_to_out_protocol(&error, &mut output)?;
+        }
+    }
+    output.write_message_end()?;
+    output.flush()?;
+    Ok(reply)
+}
+
+async fn execute<S>(rpc: &UserRpc<S>, context: &RpcContext, call: Call) -> Result<Success, ApiError>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    match call {
+        Call::CreateUser(request) => rpc.create_user(request).await.map(Success::User),
+        Call::GetUser(id) => rpc.get_user(&id).await.map(Success::User),
+        Call::ListUsers(params) => rpc.list_users(params).await.map(Success::Page),
+        Call::SearchUsers(params) => rpc.search_users(params).await.map(Success::Page),
+        Call::UpdateUser(id, request) => rpc.update_user(context, &id, request).await.map(Success::User),
+        Call::DeleteUser(id) => rpc.delete_user(context, &id).await.map(|_| Success::Void),
+        Call::RestoreUser(id) => rpc.restore_user(context, &id).await.map(Success::User),
+    }
+}
+
+/// Reads the arguments of a call of `method`: the context in field 1, then the fields of the method.
+fn read_call(method: &str, input: &mut Input) -> thrift::Result<(RpcContext, Call)> {
+    let (mut context, mut id, mut create_user, mut update_user, mut list_users, mut search_users) = (None, None, None, None, None, None);
+    read_struct(input, |input, field, field_type| {
+        match (method, field, field_type) {
+            (_, 1, TType::Struct) => context = Some(read_context(input)?),
+            ("getUser" | "updateUser" | "deleteUser" | "restoreUser", 2, TType::String) => id = Some(input.read_string()?),
+            ("createUser", 2, TType::Struct) => create_user = Some(read_create_user_request(input)?),
+            ("updateUser", 3, TType::Struct) => update_user = Some(read_update_user_request(input)?),
+            ("listUsers", 2, TType::Struct) => list_users = Some(read_list_users_request(input)?),
+            ("searchUsers", 2, TType::Struct) => search_users = Some(read_search_users_request(input)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+
+    let call = match method {
+        "createUser" => Call::CreateUser(required(create_user, "request")?),
+        "getUser" => Call::GetUser(required(id, "id")?),
+        "listUsers" => Call::ListUsers(required(list_users, "request")?),
+        "searchUsers" => Call::SearchUsers(required(search_users, "request")?),
+        "updateUser" => Call::UpdateUser(required(id, "id")?, required(update_user, "request")?),
+        "deleteUser" => Call::DeleteUser(required(id, "id")?),
+        "restoreUser" => Call::RestoreUser(required(id, "id")?),
+        _ => return Err(thrift::new_application_error(ApplicationErrorKind::UnknownMethod, format!("unknown method {}", method))),
+    };
+    Ok((context.unwrap_or_default(), call))
+}
+
+/// Reads the fields of a struct, passing each one to `read_field`, which returns `false` for
+/// the fields it does not know, to skip them.
+fn read_struct(input: &mut Input, mut read_field: impl FnMut(&mut Input, i16, TType) -> thrift::Result<bool>) -> thrift::Result<()> {
+    input.read_struct_begin()?;
+    loop {
+        let field = input.read_field_begin()?;
+        if field.field_type == TType::Stop {
+            break;
+        }
+        let known = match field.id {
+            Some(id) => read_field(input, id, field.field_type)?,
+            None => false,
+        };
+        if !known {
+            input.skip(field.field_type)?;
+        }
+        input.read_field_end()?;
+    }
+    input.read_struct_end()
+}
+
+fn required<T>(value: Option<T>, field: &str) -> thrift::Result<T> {
+    value.ok_or_else(|| thrift::new_protocol_error(ProtocolErrorKind::InvalidData, format!("missing required field {}", field)))
+}
+
+/// Converts a count of the call, failing when it is negative.
+fn unsigned<T: TryFrom<i64>>(value: i64, field: &str) -> thrift::Result<T> {
+    T::try_from(value).map_err(|_| thrift::new_protocol_error(ProtocolErrorKind::InvalidData, format!("{} must not be negative", field)))
+}
+
+/// Converts the age of a user. Ages out of the range of `u8` become 0, which fails validation
+/// like any other age below the minimum.
+fn age(age: i16) -> u8 {
+    u8::try_from(age).unwrap_or(0)
+}
+
+fn read_context(input: &mut Input) -> thrift::Result<RpcContext> {
+    let mut context = RpcContext::default();
+    read_struct(input, |input, field, field_type| {
+        match (field, field_type) {
+            (1, TType::String) => context.token = Some(input.read_string()?),
+            (2, TType::String) => context.request_id = Some(input.read_string()?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(context)
+}
+
+fn read_create_user_request(input: &mut Input) -> thrift::Result<CreateUserRequestBody> {
+    let (mut name, mut email, mut age, mut password) = (None, None, None, None);
+    read_struct(input, |input, field, field_type| {
+        match (field, field_type) {
+            (1, TType::String) => name = Some(input.read_string()?),
+            (2, TType::String) => email = Some(input.read_string()?),
+            (3, TType::I16) => age = Some(input.read_i16()?),
+            (4, TType::String) => password = Some(input.read_string()?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(CreateUserRequestBody { name: required(name, "name")?, email: required(email, "email")?, age: self::age(required(age, "age")?), password })
+}
+
+fn read_update_user_request(input: &mut Input) -> thrift::Result<UpdateUserRequestBody> {
+    let mut request = UpdateUserRequestBody { name: None, email: None, age: None };
+    read_struct(input, |input, field, field_type| {
+        match (field, field_type) {
+            (1, TType::String) => request.name = Some(input.read_string()?),
+            (2, TType::String) => request.email = Some(input.read_string()?),
+            (3, TType::I16) => request.age = Some(age(input.read_i16()?)),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(request)
+}
+
+fn read_list_users_request(input: &mut Input) -> thrift::Result<ListUsersQueryParams> {
+    let mut params = ListUsersQueryParams { limit: None, offset: None, sort_by: None, order: None };
+    read_struct(input, |input, field, field_type| {
+        match (field, field_type) {
+            (1, TType::I32) => params.limit = Some(unsigned(input.read_i32()?.into(), "limit")?),
+            (2, TType::I64) => params.offset = Some(unsigned(input.read_i64()?, "offset")?),
+            (3, TType::I32) => {
+                params.sort_by = Some(match input.read_i32()? {
+                    1 => UserSortParam::Name,
+                    2 => UserSortParam::Email,
+                    3 => UserSortParam::Age,
+                    other => return Err(thrift::new_protocol_error(ProtocolErrorKind::InvalidData, format!("unknown UserSortField {}", other))),
+                })
+            }
+            (4, TType::I32) => {
+                params.order = Some(match input.read_i32()? {
+                    1 => SortOrderParam::Asc,
+                    2 => SortOrderParam::Desc,
+                    other => return Err(thrift::new_protocol_error(ProtocolErrorKind::InvalidData, format!("unknown SortOrder {}", other))),
+                })
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(params)
+}
+
+fn read_search_users_request(input: &mut Input) -> thrift::Result<SearchUsersQueryParams> {
+    let mut params = SearchUsersQueryParams { name: None, email: None, min_age: None, max_age: None, limit: None, offset: None };
+    // Ages out of the range of `u8` match the same users as the nearest bound
+    let bound = |age: i16| age.clamp(0, u8::MAX.into()) as u8;
+    read_struct(input, |input, field, field_type| {
+        match (field, field_type) {
+            (1, TType::String) => params.name = Some(input.read_string()?),
+            (2, TType::String) => params.email = Some(input.read_string()?),
+            (3, TType::I16) => params.min_age = Some(bound(input.read_i16()?)),
+            (4, TType::I16) => params.max_age = Some(bound(input.read_i16()?)),
+            (5, TType::I32) => params.limit = Some(unsigned(input.read_i32()?.into(), "limit")?),
+            (6, TType::I64) => params.offset = Some(unsigned(input.read_i64()?, "offset")?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    })?;
+    Ok(params)
+}
+
+/// Writes the result struct of `method`: the value returned in field 0, or the `UserError`
+/// thrown in field 1.
+fn write_result(output: &mut Output, method: &str, result: Result<Success, ApiError>) -> thrift::Result<()> {
+    output.write_struct_begin(&TStructIdentifier::new(format!("{}_result", method)))?;
+    match result {
+        Ok(Success::User(user)) => {
+            output.write_field_begin(&TFieldIdentifier::new("success", TType::Struct, 0))?;
+            write_user(output, &user)?;
+            output.write_field_end()?;
+        }
+        Ok(Success::Page(page)) => {
+            output.write_field_begin(&TFieldIdentifier::new("success", TType::Struct, 0))?;
+            write_user_page(output, &page)?;
+            output.write_field_end()?;
+        }
+        Ok(Success::Void) => {}
+        Err(error) => {
+            output.write_field_begin(&TFieldIdentifier::new("error", TType::Struct, 1))?;
+            write_user_error(output, error)?;
+            output.write_field_end()?;
+        }
+    }
+    output.write_field_stop()?;
+    output.write_struct_end()
+}
+
+fn write_string_field(output: &mut Output, name: &str, id: i16, value: &str) -> thrift::Result<()> {
+    output.write_field_begin(&TFieldIdentifier::new(name, TType::String, id))?;
+    output.write_string(value)?;
+    output.write_field_end()
+}
+
+fn write_user(output: &mut Output, user: &User) -> thrift::Result<()> {
+    output.write_struct_begin(&TStructIdentifier::new("User"))?;
+    write_string_field(output, "id", 1, &user.id().to_string())?;
+    write_string_field(output, "name", 2, user.name())?;
+    write_string_field(output, "email", 3, user.email().as_str())?;
+    output.write_field_begin(&TFieldIdentifier::new("age", TType::I16, 4))?;
+    output.write_i16(user.age().into())?;
+    output.write_field_end()?;
+    output.write_field_stop()?;
+    output.write_struct_end()
+}
+
+fn write_user_page(output: &mut Output, page: &UserPage) -> thrift::Result<()> {
+    output.write_struct_begin(&TStructIdentifier::new("UserPage"))?;
+    output.write_field_begin(&TFieldIdentifier::new("users", TType::List, 1))?;
+    output.write_list_begin(&TListIdentifier::new(TType::Struct, list_size(page.users.len())?))?;
+    for user in &page.users {
+        write_user(output, user)?;
+    }
+    output.write_list_end()?;
+    output.write_field_end()?;
+    output.write_field_begin(&TFieldIdentifier::new("total", TType::I64, 2))?;
+    output.write_i64(page.total.try_into().unwrap_or(i64::MAX))?;
+    output.write_field_end()?;
+    output.write_field_stop()?;
+    output.write_struct_end()
+}
+
+/// Writes the `UserError` of an error.
+fn write_user_error(output: &mut Output, error: ApiError) -> thrift::Result<()> {
+    let CallError { code, message, errors } = CallError::from(error);
+
+    output.write_struct_begin(&TStructIdentifier::new("UserError"))?;
+    write_string_field(output, "code", 1, code)?;
+    write_string_field(output, "message", 2, &message)?;
+    if !errors.is_empty() {
+        output.write_field_begin(&TFieldIdentifier::new("errors", TType::List, 3))?;
+        output.write_list_begin(&TListIdentifier::new(TType::Struct, list_size(errors.len())?))?;
+        for error in &errors {
+            output.write_struct_begin(&TStructIdentifier::new("FieldError"))?;
+            write_string_field(output, "field", 1, error.field)?;
+            write_string_field(output, "message", 2, &error.message)?;
+            output.write_field_stop()?;
+            output.write_struct_end()?;
+        }
+        output.write_list_end()?;
+        output.write_field_end()?;
+    }
+    output.write_field_stop()?;
+    output.write_struct_end()
+}
+
+fn list_size(len: usize) -> thrift::Result<i32> {
+    i32::try_from(len).map_err(|_| thrift::new_protocol_error(ProtocolErrorKind::SizeLimit, format!("list of {} elements", len)))
+}
@@ -0,0 +1,127 @@
+//! Versions of the HTTP API, mounted side by side under `/api/{version}`.
+//!
+//! A breaking change of the requests or responses of a route is made in a new version, with
+//! handlers and DTOs of its own, while the previous versions keep serving theirs. Responses of
+//! deprecated versions announce it in a `Deprecation` header (RFC 9745) and, once the date is
+//! decided, a `Sunset` header (RFC 8594), and link the same path in the version replacing them.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::Router;
+use chrono::{DateTime, Utc};
+
+/// Header of the date a version was deprecated at, as `@<unix seconds>`.
+pub const DEPRECATION: HeaderName = HeaderName::from_static("deprecation");
+
+/// Header of the date a deprecated version stops being served, as an HTTP date.
+pub const SUNSET: HeaderName = HeaderName::from_static("sunset");
+
+/// Prefix of the paths of every version of the API.
+const API_PREFIX: &str = "/api";
+
+/// Deprecation of a version of the API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    /// When the version was deprecated.
+    pub since: DateTime<Utc>,
+    /// When the version stops being served, if decided.
+    pub sunset: Option<DateTime<Utc>>,
+    /// Prefix of the version replacing the deprecated one, e.g. `/api/v2`. For a deprecated
+    /// route (see [`RouteMeta`](crate::routes::RouteMeta)), the path of the route replacing it.
+    pub successor: String,
+}
+
+impl Deprecation {
+    /// Creates a new `Deprecation` since `since`, in favor of the version under `successor`,
+    /// without a sunset date.
+    pub fn new(since: DateTime<Utc>, successor: impl Into<String>) -> Self {
+        Self { since, sunset: None, successor: successor.into() }
+    }
+
+    /// Announces that the version stops being served at `sunset`.
+    pub fn with_sunset(mut self, sunset: DateTime<Utc>) -> Self {
+        self.sunset = Some(sunset);
+        self
+    }
+}
+
+/// A version of the API: its routes, served under `prefix`, e.g. `/api/v1`.
+pub struct ApiVersion<S> {
+    prefix: &'static str,
+    routes: Router<S>,
+    deprecation: Option<Deprecation>,
+}
+
+impl<S> ApiVersion<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    /// Creates a new `ApiVersion` serving `routes` under `prefix`.
+    pub fn new(prefix: &'static str, routes: Router<S>) -> Self {
+        Self { prefix, routes, deprecation: None }
+    }
+
+    /// Announces the deprecation of the version in the headers of its responses.
+    pub fn deprecated(mut self, deprecation: Deprecation) -> Self {
+        self.deprecation = Some(deprecation);
+        self
+    }
+}
+
+/// Mounts every one of `versions` in `app`, side by side.
+pub fn mount_versions<S>(app: Router<S>, versions: impl IntoIterator<Item = ApiVersion<S>>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    versions.into_iter().fold(app, |app, version| {
+        let routes = match version.deprecation {
+            Some(deprecation) => version.routes.layer(middleware::from_fn_with_state(Arc::new(deprecation), announce_deprecation)),
+            None => version.routes,
+        };
+        app.nest(version.prefix, routes)
+    })
+}
+
+/// Middleware adding the `Deprecation`, `Sunset` and successor `Link` headers of a deprecated
+/// version to its responses.
+async fn announce_deprecation(State(deprecation): State<Arc<Deprecation>>, request: Request, next: Next) -> Response {
+    // Within the version, the path is stripped of the prefix of the version
+    let successor = format!("{}{}", deprecation.successor, request.uri().path());
+    let mut response = next.run(request).await;
+    insert_deprecation_headers(response.headers_mut(), &deprecation, &successor);
+    response
+}
+
+/// Adds the `Deprecation`, `Sunset` and `Link` headers of `deprecation` to `headers`, linking
+/// the `successor` path.
+pub(crate) fn insert_deprecation_headers(headers: &mut HeaderMap, deprecation: &Deprecation, successor: &str) {
+    if let Ok(value) = HeaderValue::from_str(&format!("@{}", deprecation.since.timestamp())) {
+        headers.insert(DEPRECATION, value);
+    }
+    if let Some(sunset) = deprecation.sunset
+        && let Ok(value) = HeaderValue::from_str(&sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+    {
+        headers.insert(SUNSET, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&format!("<{}>; rel=\"successor-version\"", successor)) {
+        headers.append(header::LINK, value);
+    }
+}
+
+/// Returns `route` without the version following `/api`, e.g. `/api/users/{id}` for
+/// `/api/v1/users/{id}`, so a route is identified alike in every version.
+pub fn unversioned_route(route: &str) -> Cow<'_, str> {
+    let Some(rest) = route.strip_prefix(API_PREFIX).and_then(|rest| rest.strip_prefix("/v")) else {
+        return Cow::Borrowed(route);
+    };
+    let (version, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    if version.is_empty() || !version.bytes().all(|b| b.is_ascii_digit()) {
+        return Cow::Borrowed(route);
+    }
+    Cow::Owned(format!("{}{}", API_PREFIX, path))
+}
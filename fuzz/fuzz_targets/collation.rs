@@ -0,0 +1,14 @@
+//! Checks that case- and accent-insensitive folding never panics and stays consistent.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use rust_web_server_lib::domain::collation;
+
+fuzz_target!(|value: &str| {
+    let folded = collation::fold(value);
+    assert_eq!(collation::fold(&folded), folded);
+    assert!(collation::eq(value, value));
+    assert_eq!(collation::cmp(value, value), std::cmp::Ordering::Equal);
+});
@@ -0,0 +1,25 @@
+//! Decodes arbitrary strings as pagination cursors signed with a fixed key.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use rust_web_server_lib::presentation::pagination::{Cursor, CursorKey};
+
+/// Position of a user in a list sorted by name: its name and id.
+type NamePosition = (String, String);
+
+fuzz_target!(|data: &str| {
+    let key = CursorKey::new("fuzz-cursor-secret");
+
+    // Cursors are refused unless signed with the key, and the accepted ones encode back to a
+    // cursor of the same position
+    if let Ok(cursor) = Cursor::<NamePosition>::decode(data, &key) {
+        assert_eq!(Cursor::<NamePosition>::decode(&cursor.encode(&key), &key).unwrap(), cursor);
+    }
+
+    let cursor = Cursor::new((data.to_string(), data.to_string()));
+    let encoded = cursor.encode(&key);
+    assert_eq!(Cursor::<NamePosition>::decode(&encoded, &key).unwrap(), cursor);
+    assert!(Cursor::<NamePosition>::decode(&encoded, &CursorKey::new("other-secret")).is_err());
+});
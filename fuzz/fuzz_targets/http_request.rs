@@ -0,0 +1,52 @@
+//! Feeds arbitrary method/path/body combinations through the full router (extractors,
+//! path parsing, JSON rejection handling) backed by the in-memory repository.
+
+#![no_main]
+
+use std::sync::{Arc, OnceLock};
+
+use axum::body::Body;
+use axum::http::{Method, Request};
+use libfuzzer_sys::fuzz_target;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+const METHODS: [Method; 5] = [Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::PATCH];
+
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| tokio::runtime::Builder::new_current_thread().build().unwrap())
+}
+
+fn app() -> &'static axum::Router {
+    static APP: OnceLock<axum::Router> = OnceLock::new();
+    APP.get_or_init(|| router(AppState::new(Arc::new(UserService::new(Arc::new(InMemoryUserRepository::new()))))))
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, rest)) = data.split_first() else {
+        return;
+    };
+    // The path and body are separated by the first newline.
+    let split = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+    let (path, body) = rest.split_at(split);
+
+    let Ok(path) = std::str::from_utf8(path) else {
+        return;
+    };
+    let Ok(request) = Request::builder()
+        .method(METHODS[selector as usize % METHODS.len()].clone())
+        .uri(format!("/api/{}", path))
+        .header("content-type", "application/json")
+        .body(Body::from(body.get(1..).unwrap_or_default().to_vec()))
+    else {
+        return;
+    };
+
+    runtime().block_on(async {
+        let _ = app().clone().oneshot(request).await;
+    });
+});
@@ -0,0 +1,17 @@
+//! Deserializes and validates arbitrary bytes as the HTTP request DTOs.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use rust_web_server_lib::presentation::handlers::user_handlers::{CreateUserRequestBody, UpdateUserRequestBody};
+use rust_web_server_lib::presentation::middleware::validation::Validate;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(body) = serde_json::from_slice::<CreateUserRequestBody>(data) {
+        let _ = body.validate();
+    }
+    if let Ok(body) = serde_json::from_slice::<UpdateUserRequestBody>(data) {
+        let _ = body.validate();
+    }
+});
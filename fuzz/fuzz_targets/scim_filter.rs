@@ -0,0 +1,15 @@
+//! Parses arbitrary strings as the `filter` of SCIM User list requests.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use rust_web_server_lib::presentation::handlers::scim_handlers::parse_user_name_filter;
+
+fuzz_target!(|filter: &str| {
+    if let Ok(user_name) = parse_user_name_filter(filter) {
+        // The value quoted back into a filter parses to the same value
+        let quoted = serde_json::to_string(&user_name).unwrap();
+        assert_eq!(parse_user_name_filter(&format!("userName eq {}", quoted)).unwrap(), user_name);
+    }
+});
@@ -0,0 +1,35 @@
+//! Decodes arbitrary strings, and arbitrary JSON signed with the key, as cursors of user lists.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use rust_web_server_lib::domain::user::model::{SortDirection, UserSortField};
+use rust_web_server_lib::presentation::handlers::user_handlers::UserCursorPosition;
+use rust_web_server_lib::presentation::pagination::{Cursor, CursorKey};
+
+const SORTS: [(UserSortField, SortDirection); 6] = [
+    (UserSortField::Name, SortDirection::Asc),
+    (UserSortField::Name, SortDirection::Desc),
+    (UserSortField::Email, SortDirection::Asc),
+    (UserSortField::Email, SortDirection::Desc),
+    (UserSortField::Age, SortDirection::Asc),
+    (UserSortField::Age, SortDirection::Desc),
+];
+
+fuzz_target!(|data: &str| {
+    let key = CursorKey::new("fuzz-cursor-secret");
+
+    // Forged cursors never pass the signature
+    for (sort_by, direction) in SORTS {
+        let _ = UserCursorPosition::decode(Some(data), &key, sort_by, direction);
+    }
+
+    // Positions of validly signed cursors are checked against the list they continue
+    if let Ok(position) = serde_json::from_str::<serde_json::Value>(data) {
+        let cursor = Cursor::new(position).encode(&key);
+        for (sort_by, direction) in SORTS {
+            let _ = UserCursorPosition::decode(Some(&cursor), &key, sort_by, direction);
+        }
+    }
+});
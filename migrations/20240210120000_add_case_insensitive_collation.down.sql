@@ -0,0 +1,9 @@
+-- Revert name/email to the default collation
+DROP INDEX IF EXISTS users_email_idx;
+DROP INDEX IF EXISTS users_name_idx;
+
+ALTER TABLE users
+    ALTER COLUMN name TYPE VARCHAR(255) COLLATE "default",
+    ALTER COLUMN email TYPE VARCHAR(255) COLLATE "default";
+
+DROP COLLATION IF EXISTS ignore_accent_case;
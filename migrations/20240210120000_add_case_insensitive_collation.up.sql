@@ -0,0 +1,13 @@
+-- Case- and accent-insensitive collation so that "José" matches "jose"
+CREATE COLLATION IF NOT EXISTS ignore_accent_case (
+    provider = icu,
+    locale = 'und-u-ks-level1',
+    deterministic = false
+);
+
+ALTER TABLE users
+    ALTER COLUMN name TYPE VARCHAR(255) COLLATE ignore_accent_case,
+    ALTER COLUMN email TYPE VARCHAR(255) COLLATE ignore_accent_case;
+
+CREATE INDEX users_name_idx ON users (name);
+CREATE INDEX users_email_idx ON users (email);
@@ -0,0 +1,3 @@
+-- Remove the legal hold flag
+ALTER TABLE users
+    DROP COLUMN IF EXISTS legal_hold;
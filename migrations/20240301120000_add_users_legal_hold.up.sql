@@ -0,0 +1,3 @@
+-- Users under legal hold cannot be deleted until the hold is lifted
+ALTER TABLE users
+    ADD COLUMN legal_hold BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+-- Drop user_consents table
+DROP TABLE IF EXISTS user_consents;
@@ -0,0 +1,12 @@
+-- Append-only history of consent grants and withdrawals
+CREATE TABLE user_consents (
+    id BIGSERIAL PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    consent_type VARCHAR(64) NOT NULL,
+    action VARCHAR(16) NOT NULL,
+    version VARCHAR(64) NOT NULL,
+    source VARCHAR(255) NOT NULL,
+    recorded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX user_consents_user_id_idx ON user_consents (user_id, recorded_at);
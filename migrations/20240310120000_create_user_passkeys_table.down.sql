@@ -0,0 +1,2 @@
+-- Drop user_passkeys table
+DROP TABLE IF EXISTS user_passkeys;
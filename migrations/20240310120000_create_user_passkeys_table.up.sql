@@ -0,0 +1,12 @@
+-- WebAuthn credentials (passkeys) users log in with. Credential ids are at most 1023 bytes,
+-- 1364 characters once base64url-encoded
+CREATE TABLE user_passkeys (
+    id VARCHAR(1364) PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name VARCHAR(64) NOT NULL,
+    credential TEXT NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX user_passkeys_user_id_idx ON user_passkeys (user_id, created_at);
@@ -0,0 +1,9 @@
+-- Domain events recorded by the services, removed once published to the message broker.
+-- Ids increase with the order events were recorded in, which is the order they are published in
+CREATE TABLE event_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    event_type VARCHAR(64) NOT NULL,
+    aggregate_id VARCHAR(255) NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,8 @@
+-- Keys signing access tokens (ES256), shared by the replicas. Keys are removed once the tokens
+-- they signed expired
+CREATE TABLE jwt_signing_keys (
+    kid VARCHAR(64) PRIMARY KEY,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    -- PKCS#8 key pair, encrypted with a key derived from JWT_SECRET
+    encrypted_key BYTEA NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Drop group_members and groups tables
+DROP TABLE IF EXISTS group_members;
+DROP TABLE IF EXISTS groups;
@@ -0,0 +1,18 @@
+-- Groups bundling roles granted to, or denied to, their members
+CREATE TABLE groups (
+    name VARCHAR(64) PRIMARY KEY,
+    granted_roles TEXT[] NOT NULL DEFAULT '{}',
+    denied_roles TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE group_members (
+    group_name VARCHAR(64) NOT NULL REFERENCES groups (name) ON DELETE CASCADE,
+    user_id VARCHAR(255) NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    added_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (group_name, user_id)
+);
+
+-- Groups of a user, looked up when issuing their tokens
+CREATE INDEX group_members_user_id_idx ON group_members (user_id);
@@ -0,0 +1,5 @@
+-- Remove soft-deleted users along with their deletion timestamp
+DELETE FROM users
+    WHERE deleted_at IS NOT NULL;
+ALTER TABLE users
+    DROP COLUMN IF EXISTS deleted_at;
@@ -0,0 +1,3 @@
+-- Deleted users are kept, left out of reads, until restored or deleted for good
+ALTER TABLE users
+    ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
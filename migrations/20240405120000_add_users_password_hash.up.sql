@@ -0,0 +1,3 @@
+-- PHC string of the hash of the password of a user, if they have one
+ALTER TABLE users
+    ADD COLUMN password_hash TEXT;
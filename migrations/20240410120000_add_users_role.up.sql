@@ -0,0 +1,3 @@
+-- Role of every user, granted to them on login; existing users are regular users
+ALTER TABLE users
+    ADD COLUMN role VARCHAR(16) NOT NULL DEFAULT 'user' CHECK (role IN ('admin', 'user'));
@@ -0,0 +1,2 @@
+-- Drop user_daily_stats table
+DROP TABLE IF EXISTS user_daily_stats;
@@ -0,0 +1,9 @@
+-- Numbers of users of every day (UTC), materialized by the stats projector. Days are kept as
+-- computed, so they survive the users deleted for good since
+CREATE TABLE user_daily_stats (
+    day DATE PRIMARY KEY,
+    signups BIGINT NOT NULL,
+    deletions BIGINT NOT NULL,
+    active_users BIGINT NOT NULL,
+    computed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
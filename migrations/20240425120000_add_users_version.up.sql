@@ -0,0 +1,3 @@
+-- Version of every user, incremented by every change so concurrent updates can be detected
+ALTER TABLE users
+    ADD COLUMN version BIGINT NOT NULL DEFAULT 1;
@@ -0,0 +1,2 @@
+-- Drop tenant_settings table
+DROP TABLE IF EXISTS tenant_settings;
@@ -0,0 +1,8 @@
+-- Settings overridden by tenants, the deployment-wide configuration applying to the others
+CREATE TABLE tenant_settings (
+    tenant_id TEXT PRIMARY KEY,
+    rate_limit_requests INTEGER CHECK (rate_limit_requests > 0),
+    feature_flags JSONB NOT NULL DEFAULT '{}',
+    webhook_secret TEXT,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
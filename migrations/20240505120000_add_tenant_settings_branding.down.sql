@@ -0,0 +1,2 @@
+ALTER TABLE tenant_settings
+    DROP COLUMN IF EXISTS branding;
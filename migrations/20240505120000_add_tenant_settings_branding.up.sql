@@ -0,0 +1,3 @@
+-- Branding of the error responses and e-mails of every tenant
+ALTER TABLE tenant_settings
+    ADD COLUMN branding JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,15 @@
+-- Background jobs, claimed by the workers of every replica with SKIP LOCKED
+CREATE TABLE jobs (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    -- Time the job is due, pushed back while it is claimed and after it fails
+    run_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_error TEXT,
+    -- Set once the job has failed for good, when it is no longer claimed
+    failed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX jobs_due_idx ON jobs (run_at, id) WHERE failed_at IS NULL;
@@ -0,0 +1,12 @@
+-- Changes of the users, with their actor and the fields they changed. Entries are kept after
+-- the users are deleted for good, so the log has no foreign key to the users
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    user_id VARCHAR(255) NOT NULL,
+    actor TEXT,
+    action TEXT NOT NULL,
+    changes JSONB NOT NULL,
+    recorded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX audit_log_user_id_idx ON audit_log (user_id, id);
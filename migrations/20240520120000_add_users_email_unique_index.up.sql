@@ -0,0 +1,4 @@
+-- One user per email, compared ignoring case and accents like lookups. Deleted users release
+-- their email, which restoring them takes back unless another user registered it meanwhile
+DROP INDEX IF EXISTS users_email_idx;
+CREATE UNIQUE INDEX users_email_key ON users (email) WHERE deleted_at IS NULL;
@@ -0,0 +1,2 @@
+-- Drop the impersonated user of audit_log entries
+ALTER TABLE audit_log DROP COLUMN IF EXISTS impersonated_user_id;
@@ -0,0 +1,3 @@
+-- Id of the user the actor impersonated when making the change, NULL when the actor acted as
+-- themselves
+ALTER TABLE audit_log ADD COLUMN impersonated_user_id VARCHAR(255);
@@ -0,0 +1,2 @@
+-- Drop the hash chain of audit_log entries
+ALTER TABLE audit_log DROP COLUMN IF EXISTS hash, DROP COLUMN IF EXISTS prev_hash;
@@ -0,0 +1,4 @@
+-- Hash chain of the audit log: each entry stores the hash of the entry before it, in the order
+-- of the ids, and its own hash, over the previous hash and its contents. Entries recorded
+-- before the chain have neither
+ALTER TABLE audit_log ADD COLUMN prev_hash TEXT, ADD COLUMN hash TEXT;
@@ -0,0 +1,2 @@
+-- Drop audit_log_chain_head table
+DROP TABLE IF EXISTS audit_log_chain_head;
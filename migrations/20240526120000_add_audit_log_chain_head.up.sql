@@ -0,0 +1,9 @@
+-- Head of the hash chain of the audit log: the hash of its last entry, locked and updated by
+-- every append, so an append reads the head last committed whatever the snapshot of its
+-- transaction, or fails with a serialization failure in a serializable one
+CREATE TABLE audit_log_chain_head (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    hash TEXT
+);
+
+INSERT INTO audit_log_chain_head (hash) VALUES ((SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1));
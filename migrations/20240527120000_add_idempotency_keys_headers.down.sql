@@ -0,0 +1,2 @@
+-- Drop the headers of the stored responses
+ALTER TABLE idempotency_keys DROP COLUMN IF EXISTS headers;
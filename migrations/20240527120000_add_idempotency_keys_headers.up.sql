@@ -0,0 +1,3 @@
+-- Headers of the stored responses replayed along their body (ETag, Location), as an array of
+-- [name, value] pairs
+ALTER TABLE idempotency_keys ADD COLUMN headers JSONB;
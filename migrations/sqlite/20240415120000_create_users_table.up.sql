@@ -0,0 +1,20 @@
+-- Users, as in the PostgreSQL schema. SQLite has no accent-insensitive collation, so names and
+-- emails are also stored folded like `domain::collation::fold`, in `name_key` and `email_key`,
+-- which lookups, searches and sorts use instead
+CREATE TABLE users (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    name_key TEXT NOT NULL,
+    email TEXT NOT NULL,
+    email_key TEXT NOT NULL,
+    age INTEGER NOT NULL,
+    legal_hold BOOLEAN NOT NULL DEFAULT FALSE,
+    role TEXT NOT NULL DEFAULT 'user' CHECK (role IN ('admin', 'user')),
+    password_hash TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+    deleted_at TEXT
+);
+
+CREATE INDEX users_name_key_idx ON users (name_key);
+CREATE INDEX users_email_key_idx ON users (email_key);
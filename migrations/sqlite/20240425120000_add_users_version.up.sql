@@ -0,0 +1,2 @@
+-- Version of every user, incremented by every change so concurrent updates can be detected
+ALTER TABLE users ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
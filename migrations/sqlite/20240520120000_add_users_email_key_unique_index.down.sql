@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS users_email_key_idx;
+CREATE INDEX users_email_key_idx ON users (email_key);
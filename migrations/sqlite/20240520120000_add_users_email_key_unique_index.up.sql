@@ -0,0 +1,3 @@
+-- One user per email, as in the PostgreSQL schema, compared by their folded `email_key`
+DROP INDEX IF EXISTS users_email_key_idx;
+CREATE UNIQUE INDEX users_email_key_idx ON users (email_key) WHERE deleted_at IS NULL;
@@ -0,0 +1,212 @@
+use std::io::BufRead;
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use eyre::Context;
+use sqlx::postgres::PgPoolOptions;
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::domain::user::error::UserDomainError;
+use rust_web_server_lib::domain::user::model::{CreateUser, ListUsers, User, UserId};
+use rust_web_server_lib::infra::auth::password::Argon2PasswordHasher;
+use rust_web_server_lib::infra::config::{load_database_url, load_outbox_enabled, ConfigSource};
+use rust_web_server_lib::infra::storage::adapter::is_sqlite_url;
+use rust_web_server_lib::infra::storage::adapter::postgres::audit_log::{ChainVerification, PostgresAuditLog};
+use rust_web_server_lib::infra::storage::adapter::postgres::unit_of_work::PostgresUnitOfWork;
+use rust_web_server_lib::infra::storage::adapter::postgres::{create_postgres_repositories, run_migrations, Db};
+use rust_web_server_lib::presentation::handlers::user_handlers::{DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT};
+
+/// Maximum time spent waiting for the database to accept a connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns the command line of the CLI.
+fn cli() -> Command {
+    let id = || Arg::new("id").required(true).value_parser(value_parser!(UserId)).help("Id of the user");
+    Command::new("rustweb-cli")
+        .about("Manages the users stored in the database of DATABASE_URL, configured like the server, and verifies their audit log")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(Command::new("migrate").about("Applies the pending migrations to the database"))
+        .subcommand(
+            Command::new("users")
+                .about("Creates, reads and deletes users")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("create")
+                        .about("Creates a user and prints it")
+                        .arg(Arg::new("name").long("name").required(true))
+                        .arg(Arg::new("email").long("email").required(true))
+                        .arg(Arg::new("age").long("age").required(true).value_parser(value_parser!(u8)))
+                        .arg(
+                            Arg::new("password-stdin")
+                                .long("password-stdin")
+                                .action(ArgAction::SetTrue)
+                                .help("Read the password of the user from the first line of stdin"),
+                        ),
+                )
+                .subcommand(Command::new("get").about("Prints a user").arg(id()))
+                .subcommand(
+                    Command::new("list")
+                        .about("Prints a page of the users, ordered by name, and their total on stderr")
+                        .arg(
+                            Arg::new("limit")
+                                .long("limit")
+                                .value_parser(value_parser!(u32).range(1..=i64::from(MAX_PAGE_LIMIT)))
+                                .help(format!("Maximum number of users printed, up to {} (default {})", MAX_PAGE_LIMIT, DEFAULT_PAGE_LIMIT)),
+                        )
+                        .arg(Arg::new("offset").long("offset").value_parser(value_parser!(u64)).help("Number of users skipped (default 0)")),
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("Soft-deletes a user")
+                        .arg(id())
+                        .arg(Arg::new("hard").long("hard").action(ArgAction::SetTrue).help("Erase the user instead")),
+                ),
+        )
+        .subcommand(
+            Command::new("audit")
+                .about("Checks the audit log")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(Command::new("verify").about("Verifies the hash chain of the audit log, failing if an entry was tampered with")),
+        )
+}
+
+/// Manages the users and the schema of the database from the command line, for operators and
+/// for seeding environments.
+///
+/// Reads `DATABASE_URL` and `OUTBOX_ENABLED` like the server, from `CONFIG_FILE` and the
+/// environment, and goes through the service layer of the server, so users are validated,
+/// their passwords hashed and their events recorded in the outbox when `OUTBOX_ENABLED` is
+/// true. Users are printed on stdout, one per line as tab-separated id, name, email, age and
+/// role. Failures are printed on stderr and make the process exit with status 1. Logs are not
+/// written, to keep stdout for the users.
+///
+/// `audit verify` walks the hash chain of the audit log, printing the number of chained
+/// entries, of entries predating the chain and the hash of the last entry, tab-separated, or
+/// failing with the first entry breaking the chain.
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let matches = cli().get_matches();
+    let source = ConfigSource::from_env()?;
+    let database_url = load_database_url(&source)?;
+    if is_sqlite_url(database_url.expose_secret()) {
+        eyre::bail!("the CLI supports PostgreSQL databases only");
+    }
+    let outbox_enabled = load_outbox_enabled(&source)?;
+
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .acquire_timeout(CONNECT_TIMEOUT)
+        .connect(database_url.expose_secret())
+        .await
+        .context("failed to connect to database")?;
+    let db = Arc::new(pool);
+    let result = run(db.clone(), outbox_enabled, &matches).await;
+    db.close().await;
+    result
+}
+
+async fn run(db: Db, outbox_enabled: bool, matches: &ArgMatches) -> eyre::Result<()> {
+    match matches.subcommand() {
+        Some(("migrate", _)) => run_migrations(&db).await,
+        Some(("users", matches)) => {
+            let user_repository = create_postgres_repositories(db.clone())?.user_repository;
+            let user_service = match outbox_enabled {
+                true => UserService::new(user_repository).with_unit_of_work(Arc::new(PostgresUnitOfWork::new(db))),
+                false => UserService::new(user_repository),
+            };
+            let user_service = user_service.with_password_hasher(Arc::new(Argon2PasswordHasher::default()));
+            run_users(&user_service, matches).await
+        }
+        Some(("audit", matches)) => run_audit(db, matches).await,
+        _ => unreachable!("a subcommand is required"),
+    }
+}
+
+async fn run_audit(db: Db, matches: &ArgMatches) -> eyre::Result<()> {
+    match matches.subcommand() {
+        Some(("verify", _)) => match PostgresAuditLog::new(db).verify_chain().await? {
+            ChainVerification::Intact { entries, unchained, last_hash } => {
+                println!("{}\t{}\t{}", entries, unchained, last_hash.unwrap_or_default());
+                Ok(())
+            }
+            ChainVerification::Broken { id } => eyre::bail!("the audit log was tampered with at entry {} or before", id),
+        },
+        _ => unreachable!("a subcommand is required"),
+    }
+}
+
+async fn run_users(user_service: &(impl UserServiceTrait + ?Sized), matches: &ArgMatches) -> eyre::Result<()> {
+    match matches.subcommand() {
+        Some(("create", matches)) => {
+            let name = matches.get_one::<String>("name").cloned().unwrap_or_default();
+            let email = matches.get_one::<String>("email").cloned().unwrap_or_default();
+            let age = matches.get_one::<u8>("age").copied().unwrap_or_default();
+            let mut user = CreateUser::new(name, email, age).map_err(|e| failed(e.into()))?;
+            if matches.get_flag("password-stdin") {
+                let mut password = String::new();
+                std::io::stdin().lock().read_line(&mut password).context("failed to read the password from stdin")?;
+                let password = password.trim_end_matches(['\r', '\n']).to_string();
+                user = user.with_password(password).map_err(|e| failed(e.into()))?;
+            }
+            print_user(&user_service.create_user(user).await.map_err(failed)?);
+        }
+        Some(("get", matches)) => {
+            print_user(&user_service.get_user(user_id(matches)).await.map_err(failed)?);
+        }
+        Some(("list", matches)) => {
+            let query = ListUsers {
+                limit: matches.get_one::<u32>("limit").copied().unwrap_or(DEFAULT_PAGE_LIMIT),
+                offset: matches.get_one::<u64>("offset").copied().unwrap_or_default(),
+                sort_by: Default::default(),
+                direction: Default::default(),
+                after: None,
+            };
+            let page = user_service.list_users(query).await.map_err(failed)?;
+            page.users.iter().for_each(print_user);
+            eprintln!("{} of {} users", page.users.len(), page.total);
+        }
+        Some(("delete", matches)) if matches.get_flag("hard") => {
+            user_service.hard_delete_user(user_id(matches)).await.map_err(failed)?;
+        }
+        Some(("delete", matches)) => {
+            user_service.delete_user(user_id(matches)).await.map_err(failed)?;
+        }
+        _ => unreachable!("a subcommand is required"),
+    }
+    Ok(())
+}
+
+fn user_id(matches: &ArgMatches) -> UserId {
+    *matches.get_one::<UserId>("id").expect("the id is required")
+}
+
+fn print_user(user: &User) {
+    println!("{}\t{}\t{}\t{}\t{}", user.id(), user.name(), user.email(), user.age(), user.role().as_str());
+}
+
+fn failed(e: UserDomainError) -> eyre::Report {
+    let message = match e {
+        UserDomainError::InvalidUser(errors) => return eyre::eyre!("invalid user: {}", errors),
+        UserDomainError::WeakPassword(violations) => {
+            let violations: Vec<String> = violations.into_iter().map(|violation| violation.message).collect();
+            return eyre::eyre!("weak password: {}", violations.join(", "));
+        }
+        UserDomainError::UserNotFound => "user not found",
+        UserDomainError::UserAlreadyExists => "a user with this email already exists",
+        UserDomainError::UserUnderLegalHold => "the user is under legal hold",
+        UserDomainError::InvalidCredentials | UserDomainError::UserVersionMismatch => "failed to update the user",
+        UserDomainError::UserConstraintViolation(cause) => return eyre::Report::new(cause).wrap_err("the user violates a constraint of the database"),
+        UserDomainError::UserChangeConflict(cause) => return eyre::Report::new(cause).wrap_err("the user was changed concurrently, retry the command"),
+        UserDomainError::StorageTimeout(cause) => return eyre::Report::new(cause).wrap_err("the database timed out"),
+        UserDomainError::UserCreationFailed(cause) => return eyre::Report::new(cause).wrap_err("failed to create the user"),
+        UserDomainError::UserReadFailed(cause) => return eyre::Report::new(cause).wrap_err("failed to read the user"),
+        UserDomainError::UserDeletionFailed(cause) => return eyre::Report::new(cause).wrap_err("failed to delete the user"),
+        UserDomainError::UserListFailed(cause) => return eyre::Report::new(cause).wrap_err("failed to list the users"),
+        UserDomainError::UserUpdateFailed(cause) => return eyre::Report::new(cause).wrap_err("failed to update the user"),
+    };
+    eyre::eyre!(message)
+}
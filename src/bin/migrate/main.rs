@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+use eyre::Context;
+use sqlx::postgres::PgPoolOptions;
+
+use rust_web_server_lib::infra::config::{load_database_url, load_log_format, ConfigSource};
+use rust_web_server_lib::infra::storage::adapter::postgres::run_migrations;
+use rust_web_server_lib::infra::telemetry::init_tracing;
+use rust_web_server_lib::presentation::middleware::sampling::SAMPLED_FIELD;
+
+/// Maximum time spent waiting for the database to accept a connection.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Applies the pending migrations to the database at `DATABASE_URL` and exits.
+///
+/// Intended for CI pipelines and deploy jobs that migrate the schema once before rolling out
+/// the server, instead of every replica migrating at startup with `RUN_MIGRATIONS`. A failed
+/// migration surfaces as an error, which makes the process exit with status 1.
+#[tokio::main]
+async fn main() -> eyre::Result<()> {
+    let source = ConfigSource::from_env()?;
+    init_tracing(SAMPLED_FIELD, load_log_format(&source)?, None)?;
+
+    let database_url = load_database_url(&source)?;
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .acquire_timeout(CONNECT_TIMEOUT)
+        .connect(database_url.expose_secret())
+        .await
+        .context("failed to connect to database")?;
+
+    let result = run_migrations(&pool).await;
+    pool.close().await;
+    result
+}
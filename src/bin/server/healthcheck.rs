@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, Request, Uri};
+use eyre::{Context, OptionExt};
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpStream;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 3;
+
+const USAGE: &str = "usage: rustweb-server-bin healthcheck --url <url> [--timeout <secs>]";
+
+/// Performs a single readiness call and fails unless the server answers with a 2xx status.
+///
+/// Intended as a Docker `HEALTHCHECK` command for images that ship without curl/wget.
+/// Only plain `http://` URLs are supported, as the check targets the server from inside
+/// its own container. A failed check surfaces as an error, which makes the process exit
+/// with status 1.
+pub async fn run(args: &[String]) -> eyre::Result<()> {
+    let mut url = None;
+    let mut timeout_secs = DEFAULT_TIMEOUT_SECS;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--url" => url = Some(args.next().ok_or_eyre(USAGE)?.clone()),
+            "--timeout" => {
+                timeout_secs = args
+                    .next()
+                    .ok_or_eyre(USAGE)?
+                    .parse()
+                    .context("invalid --timeout value")?
+            }
+            other => eyre::bail!("unexpected argument {}\n{}", other, USAGE),
+        }
+    }
+    let url = url.ok_or_eyre(USAGE)?;
+    let uri: Uri = url.parse().with_context(|| format!("invalid --url value {}", url))?;
+    if uri.scheme_str() != Some("http") {
+        eyre::bail!("healthcheck only supports http:// urls, got {}", url);
+    }
+
+    let status = tokio::time::timeout(Duration::from_secs(timeout_secs), get(&uri))
+        .await
+        .with_context(|| format!("healthcheck request to {} timed out", url))?
+        .with_context(|| format!("healthcheck request to {} failed", url))?;
+
+    if !status.is_success() {
+        eyre::bail!("healthcheck {} returned {}", url, status);
+    }
+
+    Ok(())
+}
+
+async fn get(uri: &Uri) -> eyre::Result<axum::http::StatusCode> {
+    let authority = uri.authority().ok_or_eyre("url is missing a host")?;
+    let stream = TcpStream::connect((authority.host(), authority.port_u16().unwrap_or(80))).await?;
+
+    let (mut sender, connection) = hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+    tokio::spawn(connection);
+
+    let path = uri.path_and_query().map_or("/", |path| path.as_str());
+    let request = Request::get(path)
+        .header(header::HOST, authority.as_str())
+        .body(Body::empty())?;
+
+    Ok(sender.send_request(request).await?.status())
+}
@@ -1,32 +1,702 @@
+mod healthcheck;
+mod subsystems;
+
 use std::sync::Arc;
+use std::time::Duration;
+
+use eyre::Context;
+use port_decorators::RetryPolicy;
+use tracing::Instrument;
 
-use rust_web_server_lib::application::flows::user_service::UserService;
-use rust_web_server_lib::infra::config::Config;
-use rust_web_server_lib::infra::storage::adapter::postgres::{create_postgres_repositories, db_connect};
-use rust_web_server_lib::presentation::http::{HttpServer, HttpServerConfig};
+use rust_web_server_lib::application::flows::auth_service::AuthService;
+use rust_web_server_lib::application::flows::consent_service::ConsentService;
+use rust_web_server_lib::application::flows::device_service::DeviceService;
+use rust_web_server_lib::application::flows::group_service::GroupService;
+use rust_web_server_lib::application::flows::passkey_service::PasskeyService;
+use rust_web_server_lib::application::flows::saml_service::SamlService;
+use rust_web_server_lib::application::flows::anomaly_detector::{AnomalyDetectionPolicy, MutationAnomalyDetector, StrictRateLimit};
+use rust_web_server_lib::application::flows::slo_tracker::{SloObjective, SloPolicy, SloTracker};
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::jobs::welcome_email::WelcomeEmailJob;
+use rust_web_server_lib::application::jobs::{JobHandlers, JobQueuePort};
+use rust_web_server_lib::application::ports::audit::AuditLogPort;
+use rust_web_server_lib::application::ports::auth::{AuthenticatorPort, DisabledAuthenticator, DisabledTokens, KeySetPort, TokenPort};
+use rust_web_server_lib::application::ports::cache::{CachePort, DisabledCache};
+use rust_web_server_lib::application::ports::capability::Capabilities;
+use rust_web_server_lib::application::ports::clock::{ClockPort, ShiftedClock, SystemClock};
+use rust_web_server_lib::application::ports::email::{DisabledEmailSender, EmailSenderPort};
+use rust_web_server_lib::application::ports::error_reporter::{DisabledErrorReporter, ErrorReporterPort};
+use rust_web_server_lib::application::ports::health::{HealthCheckPort, HealthChecks};
+use rust_web_server_lib::application::ports::stats::UserStatsPort;
+use rust_web_server_lib::domain::consent::repository::{ConsentRepositoryPort, InstrumentedConsentRepository};
+use rust_web_server_lib::domain::group::repository::{GroupRepositoryPort, InstrumentedGroupRepository};
+use rust_web_server_lib::domain::passkey::repository::{InstrumentedPasskeyRepository, PasskeyRepositoryPort};
+use rust_web_server_lib::domain::user::repository::{InstrumentedUserRepository, UserRepositoryPort};
+use rust_web_server_lib::infra::alerting::LogAlerts;
+use rust_web_server_lib::infra::auth::device::InMemoryDeviceGrants;
+use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+use rust_web_server_lib::infra::auth::password::Argon2PasswordHasher;
+use rust_web_server_lib::infra::auth::signing_keys::{KeyRotation, SigningKeys};
+use rust_web_server_lib::infra::auth::{PasswordFallback, WebAuthnConfig};
+use rust_web_server_lib::features::check_features;
+use rust_web_server_lib::infra::config::{Config, ConfigSource};
+use rust_web_server_lib::infra::error_reporting::install_panic_hook;
+use rust_web_server_lib::infra::jobs::JobWorker;
+use rust_web_server_lib::infra::kubernetes::termination::wait_for_termination;
+use rust_web_server_lib::infra::outbox::OutboxDispatcher;
+use rust_web_server_lib::infra::stats::StatsProjector;
+use rust_web_server_lib::infra::storage::cached_tenant_config::CachedTenantConfig;
+use rust_web_server_lib::infra::storage::cached_user_repository::{CachedUnitOfWork, CachedUserRepository};
+use rust_web_server_lib::infra::storage::StorageRepositories;
+use rust_web_server_lib::infra::storage::adapter::is_sqlite_url;
+use rust_web_server_lib::infra::storage::adapter::postgres::audit_log::PostgresAuditLog;
+use rust_web_server_lib::infra::storage::adapter::postgres::health_check::PostgresHealthCheck;
+use rust_web_server_lib::infra::storage::adapter::postgres::idempotency::PostgresIdempotencyStore;
+use rust_web_server_lib::infra::storage::adapter::postgres::job_queue::PostgresJobQueue;
+use rust_web_server_lib::infra::storage::adapter::postgres::tenant_config::PostgresTenantConfig;
+use rust_web_server_lib::infra::storage::adapter::postgres::outbox::PostgresOutbox;
+use rust_web_server_lib::infra::storage::adapter::postgres::signing_keys::PostgresSigningKeyStore;
+use rust_web_server_lib::infra::storage::adapter::postgres::stats::PostgresUserStats;
+use rust_web_server_lib::infra::storage::adapter::postgres::unit_of_work::PostgresUnitOfWork;
+use rust_web_server_lib::infra::storage::adapter::postgres::{create_postgres_repositories, db_connect, db_connect_discovered, run_migrations, spawn_discovery_refresh, Db};
+#[cfg(feature = "sqlite")]
+use rust_web_server_lib::infra::storage::adapter::sqlite::{health_check::SqliteHealthCheck, Db as SqliteDb};
+use rust_web_server_lib::infra::telemetry::{init_tracing, Tracing};
+use rust_web_server_lib::presentation::handlers::admin_handlers::MAX_IMPERSONATION_TTL_SECS;
+use rust_web_server_lib::presentation::handlers::audit_handlers::AuditState;
+use rust_web_server_lib::presentation::handlers::clock_handlers::ClockState;
+use rust_web_server_lib::presentation::handlers::consent_handlers::ConsentState;
+use rust_web_server_lib::presentation::handlers::device_handlers::DeviceState;
+use rust_web_server_lib::presentation::handlers::group_handlers::GroupState;
+use rust_web_server_lib::presentation::handlers::in_flight_handlers::InFlightState;
+use rust_web_server_lib::presentation::handlers::management_handlers::ManagementState;
+use rust_web_server_lib::presentation::handlers::saml_handlers::SamlState;
+use rust_web_server_lib::presentation::handlers::slo_handlers::SloState;
+use rust_web_server_lib::presentation::handlers::stats_handlers::StatsState;
+use rust_web_server_lib::presentation::handlers::tap_handlers::TapState;
+use rust_web_server_lib::presentation::handlers::tenant_handlers::TenantState;
+use rust_web_server_lib::presentation::handlers::webauthn_handlers::WebAuthnState;
+use rust_web_server_lib::presentation::http::{shutdown_signal, AppState, HttpServer, HttpServerConfig};
+use rust_web_server_lib::presentation::middleware::admin::AdminToken;
+use rust_web_server_lib::presentation::middleware::auth::AuthState;
+use rust_web_server_lib::presentation::middleware::compression::CompressionPolicy;
+use rust_web_server_lib::presentation::middleware::cors::CorsPolicy;
+use rust_web_server_lib::presentation::middleware::idempotency::Idempotency;
+use rust_web_server_lib::presentation::middleware::load_shedding::LoadSheddingPolicy;
+use rust_web_server_lib::presentation::middleware::in_flight::InFlightRequests;
+use rust_web_server_lib::presentation::middleware::request_tap::RequestTap;
+use rust_web_server_lib::presentation::middleware::rate_limit::{ClassRateLimit, RateLimitClass, RateLimitPolicy, RateLimiter, RouteRateLimit};
+use rust_web_server_lib::presentation::middleware::encryption::JweKeys;
+use rust_web_server_lib::presentation::middleware::sampling::{RouteSamplingOverride, Sampler, SamplingPolicy, SAMPLED_FIELD};
+use rust_web_server_lib::presentation::middleware::traffic_archive::{TrafficArchivePolicy, TrafficArchiver};
+use rust_web_server_lib::presentation::pagination::CursorKey;
+use rust_web_server_lib::presentation::rpc::commands::UserCommandHandler;
+use rust_web_server_lib::presentation::rpc::UserRpc;
 
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
-    let config = Config::from_env()?;
+    // `healthcheck` mode performs a single readiness call and exits, without loading config
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("healthcheck") {
+        return healthcheck::run(&args[1..]).await;
+    }
+
+    let config = Config::load(&ConfigSource::from_env()?)?;
+    // Refuse settings of subsystems left out of the build, before starting any of them
+    check_features(&config)?;
+
+    // Initialize tracing subscriber for request logging, keeping debug logs of sampled requests only
+    // and exporting spans over OTLP when configured
+    let telemetry = init_tracing(SAMPLED_FIELD, config.log_format, config.otlp.as_ref())?;
+
+    // Report server errors and panics to Sentry when configured
+    let error_reporter: Arc<dyn ErrorReporterPort + Send + Sync> = match &config.sentry {
+        Some(sentry) => subsystems::sentry_reporter(sentry.clone())?,
+        None => Arc::new(DisabledErrorReporter),
+    };
+    install_panic_hook(error_reporter.clone());
+
+    // Attach pod metadata to all request logs when running in Kubernetes
+    let span = config
+        .kubernetes
+        .as_ref()
+        .map(|kubernetes| kubernetes.pod.span())
+        .unwrap_or_else(tracing::Span::none);
+
+    // Store the users in a SQLite database when DATABASE_URL is a `sqlite:` URL, for demos and CI
+    if is_sqlite_url(config.database_url.expose_secret()) {
+        return subsystems::serve_sqlite(config, telemetry, error_reporter, span).await;
+    }
+
+    // Connect to the database, following its SRV record when discovery is configured
+    let db = match &config.database_discovery {
+        Some(discovery_config) => {
+            let discovery = subsystems::dns_discovery()?;
+            let db = db_connect_discovered(&config, discovery.as_ref(), discovery_config).await?;
+            spawn_discovery_refresh(db.clone(), config.clone(), discovery, discovery_config.clone());
+            db
+        }
+        None => db_connect(&config).await?,
+    };
+
+    // Bring the schema up to date before serving, unless migrations are run by a separate job
+    if config.run_migrations {
+        run_migrations(&db).await?;
+    }
 
-    // Initialize tracing subscriber for request logging
-    tracing_subscriber::fmt::init();
+    // Create repositories, keeping a handle to the pool to close it on shutdown
+    let repositories = create_postgres_repositories(db.clone())?;
+    serve(config, telemetry, error_reporter, span, Database::Postgres(db), repositories).await
+}
+
+/// Serves the API with the `repositories` stored in `database` until SIGTERM/SIGINT, then
+/// shuts the server down.
+async fn serve<UR, CR, PR, GR>(
+    config: Config,
+    telemetry: Tracing,
+    error_reporter: Arc<dyn ErrorReporterPort + Send + Sync>,
+    span: tracing::Span,
+    database: Database,
+    repositories: StorageRepositories<UR, CR, PR, GR>,
+) -> eyre::Result<()>
+where
+    UR: UserRepositoryPort + Send + Sync + 'static,
+    CR: ConsentRepositoryPort + Send + Sync + 'static,
+    PR: PasskeyRepositoryPort + Send + Sync + 'static,
+    GR: GroupRepositoryPort + Send + Sync + 'static,
+{
+    // Participate in leader election among replicas when configured
+    let leader_election = match &config.kubernetes {
+        Some(kubernetes) => match &kubernetes.leader_election {
+            Some(leader_election) => Some(subsystems::leader_election(&kubernetes.pod, leader_election.clone())?),
+            None => None,
+        },
+        None => None,
+    };
 
-    // Connect to the database
-    let db = db_connect(&config).await;
+    // Cache users read by id in Redis when configured, with every call going to the database otherwise
+    let (cache, cache_ttl): (Arc<dyn CachePort + Send + Sync>, Duration) = match &config.cache {
+        Some(cache) => (subsystems::redis_cache(cache.clone())?, Duration::from_secs(cache.ttl_secs)),
+        None => (Arc::new(DisabledCache), Duration::ZERO),
+    };
 
-    // Create repositories
-    let repositories = create_postgres_repositories(db)?;
+    // Limit the requests of each client when configured
+    let rate_limiter = match &config.rate_limit {
+        Some(rate_limit) => Some(
+            RateLimiter::new(RateLimitPolicy {
+                requests: rate_limit.requests,
+                period: Duration::from_secs(rate_limit.period_secs),
+                route_overrides: rate_limit
+                    .route_overrides
+                    .iter()
+                    .map(|(path_prefix, requests)| RouteRateLimit { path_prefix: path_prefix.clone(), requests: *requests })
+                    .collect(),
+                class_overrides: rate_limit
+                    .class_overrides
+                    .iter()
+                    .map(|(class, requests)| {
+                        RateLimitClass::parse(class)
+                            .map(|class| ClassRateLimit { class, requests: *requests })
+                            .ok_or_else(|| eyre::eyre!("unknown rate limit class {}", class))
+                    })
+                    .collect::<eyre::Result<_>>()
+                    .context("invalid RATE_LIMIT_CLASS_OVERRIDES")?,
+                trust_forwarded_for: rate_limit.trust_forwarded_for,
+            })
+            .context("invalid rate limit")?,
+        ),
+        None => None,
+    };
 
-    // Create user service with the repository
-    let user_service = Arc::new(UserService::new(Arc::new(repositories.user_repository)));
+    // Archive a sample of the API traffic to object storage when configured
+    let (traffic_archive_writer, traffic_archive) = match &config.traffic_archive {
+        Some(traffic_archive) => {
+            let writer = subsystems::traffic_archive_writer(traffic_archive)?;
+            let archiver = TrafficArchiver::new(
+                TrafficArchivePolicy { sample_rate: traffic_archive.sample_rate, max_body_bytes: traffic_archive.max_body_bytes },
+                writer.archive(),
+            )
+            .context("invalid traffic archive")?;
+            (Some(writer), Some(archiver))
+        }
+        None => (None, None),
+    };
+
+    // Alert on anomalous rates of user creations and deletions when enabled, tightening the
+    // rate limits too when configured
+    let anomaly_detector = match &config.anomaly_detection {
+        Some(anomaly_detection) => {
+            let policy = AnomalyDetectionPolicy {
+                window: Duration::from_secs(anomaly_detection.window_secs),
+                history: anomaly_detection.history_windows,
+                z_threshold: anomaly_detection.z_threshold,
+                min_mutations: anomaly_detection.min_mutations,
+                strict_rate_limit: anomaly_detection.rate_limit_divisor.map(|divisor| StrictRateLimit {
+                    divisor,
+                    duration: Duration::from_secs(anomaly_detection.rate_limit_secs),
+                }),
+            };
+            let detector = MutationAnomalyDetector::new(policy, Arc::new(LogAlerts)).context("invalid anomaly detection")?;
+            Some(Arc::new(match &rate_limiter {
+                Some(rate_limiter) => detector.with_throttle(Arc::new(rate_limiter.clone())),
+                None => detector,
+            }))
+        }
+        None => None,
+    };
+
+    // Track the latency objectives of the API when configured, alerting on fast error budget burns
+    let slo_tracker = match &config.slo {
+        Some(slo) => {
+            let policy = SloPolicy {
+                objectives: slo
+                    .objectives
+                    .iter()
+                    .map(|objective| SloObjective {
+                        name: objective.name.clone(),
+                        method: objective.method.clone(),
+                        latency: Duration::from_millis(objective.latency_ms),
+                        target: objective.target_percent / 100.0,
+                    })
+                    .collect(),
+                window: Duration::from_secs(slo.window_secs),
+                bucket: Duration::from_secs(slo.bucket_secs),
+                burn_window: Duration::from_secs(slo.burn_window_secs),
+                burn_rate_threshold: slo.burn_rate_threshold,
+                min_requests: slo.min_requests,
+            };
+            Some(Arc::new(SloTracker::new(policy, Arc::new(LogAlerts)).context("invalid SLO tracking")?))
+        }
+        None => None,
+    };
+
+    // Create user service with the repository, both wired statically (no trait objects),
+    // publishing the events of the users through the outbox, to Kafka or to MQTT when enabled.
+    // Events are recorded in the outbox in the transaction of the change they follow
+    let user_repository = InstrumentedUserRepository::new(repositories.user_repository).with_retry(RetryPolicy::default());
+    let user_repository = Arc::new(CachedUserRepository::new(user_repository, cache.clone(), cache_ttl));
+    let outbox = match &config.outbox {
+        Some(outbox) => Some(Arc::new(PostgresOutbox::new(database.postgres("OUTBOX_ENABLED")?.clone()).with_format(outbox.format))),
+        None => None,
+    };
+    let event_publisher = match (&config.kafka, &config.mqtt) {
+        (Some(_), Some(_)) => eyre::bail!("KAFKA_BROKERS and MQTT_BROKER are both set, but user events are published to only one of them"),
+        (Some(kafka), None) => Some(subsystems::kafka_event_publisher(kafka)?),
+        (None, Some(mqtt)) => Some(subsystems::mqtt_event_publisher(mqtt)?),
+        (None, None) => None,
+    };
+    let user_service = match (&outbox, event_publisher) {
+        (Some(_), Some(_)) => eyre::bail!("OUTBOX_ENABLED and KAFKA_BROKERS or MQTT_BROKER are set, but user events are published through only one of them"),
+        (Some(_), None) => {
+            let unit_of_work = CachedUnitOfWork::new(PostgresUnitOfWork::new(database.postgres("OUTBOX_ENABLED")?.clone()), cache.clone());
+            UserService::new(user_repository.clone()).with_unit_of_work(Arc::new(unit_of_work))
+        }
+        (None, Some(event_publisher)) => UserService::new(user_repository.clone()).with_event_publisher(event_publisher),
+        (None, None) => UserService::new(user_repository.clone()),
+    };
+    let user_service = user_service
+        .with_password_hasher(Arc::new(Argon2PasswordHasher::default()))
+        .with_password_policy(subsystems::password_policy(&config.password_policy)?);
+    // Enqueue the jobs following changes of users, such as their welcome e-mails, when enabled
+    let job_queue: Option<Arc<dyn JobQueuePort + Send + Sync>> = match &config.jobs {
+        Some(_) => Some(Arc::new(PostgresJobQueue::new(database.postgres("JOBS_ENABLED")?.clone()))),
+        None => None,
+    };
+    let user_service = match &job_queue {
+        Some(job_queue) => user_service.with_job_queue(job_queue.clone()),
+        None => user_service,
+    };
+    // Send e-mails through the SMTP server when configured, by the jobs if enabled or else right away
+    let email: Arc<dyn EmailSenderPort + Send + Sync> = match &config.smtp {
+        Some(smtp) => subsystems::smtp_sender(smtp)?,
+        None => Arc::new(DisabledEmailSender),
+    };
+    let user_service = user_service.with_email_sender(email.clone());
+    let user_service = match anomaly_detector {
+        Some(anomaly_detector) => user_service.with_anomaly_detector(anomaly_detector),
+        None => user_service,
+    };
+    // Purge the responses cached by Varnish, Cloudflare and Fastly on changes of users when configured
+    let user_service = match &config.purge {
+        Some(purge) => user_service.with_purge(subsystems::http_purger(purge.clone())?),
+        None => user_service,
+    };
+    let user_service = match &config.cloudflare_purge {
+        Some(purge) => user_service.with_purge(subsystems::cloudflare_purger(purge.clone())?),
+        None => user_service,
+    };
+    let user_service = match &config.fastly_purge {
+        Some(purge) => user_service.with_purge(subsystems::fastly_purger(purge.clone())?),
+        None => user_service,
+    };
+    // Record the changes of users, with who made them, in the audit log when enabled
+    let audit_log: Option<Arc<dyn AuditLogPort + Send + Sync>> = if config.audit_log {
+        Some(Arc::new(PostgresAuditLog::new(database.postgres("AUDIT_LOG_ENABLED")?.clone())))
+    } else {
+        None
+    };
+    let user_service = match &audit_log {
+        Some(audit_log) => user_service.with_audit_log(audit_log.clone()),
+        None => user_service,
+    };
+    let user_service = Arc::new(user_service);
+
+    // Create consent service, also the `ConsentPort` of features requiring consent
+    let consent_repository = InstrumentedConsentRepository::new(repositories.consent_repository).with_retry(RetryPolicy::default());
+    let consent_service = Arc::new(ConsentService::new(Arc::new(consent_repository), user_repository.clone()));
+
+    // Create group service, also the `GroupRolesPort` through which issued tokens carry the roles of groups
+    let group_repository = InstrumentedGroupRepository::new(repositories.group_repository).with_retry(RetryPolicy::default());
+    let group_service = Arc::new(GroupService::new(Arc::new(group_repository), user_repository.clone()));
+
+    // Create passkey storage, used by the WebAuthn routes and the password fallback policy
+    let passkey_repository = Arc::new(InstrumentedPasskeyRepository::new(repositories.passkey_repository).with_retry(RetryPolicy::default()));
+
+    // Create the request log sampler, adjustable at runtime through the admin routes
+    let sampler = Sampler::new(SamplingPolicy {
+        success_rate: config.sampling.success_rate,
+        error_rate: config.sampling.error_rate,
+        route_overrides: config
+            .sampling
+            .route_overrides
+            .iter()
+            .map(|(path_prefix, success_rate)| RouteSamplingOverride {
+                path_prefix: path_prefix.clone(),
+                success_rate: *success_rate,
+            })
+            .collect(),
+    });
+    sampler.policy().validate().map_err(|e| eyre::eyre!(e))?;
+
+    // Check credentials against the directory when configured; users have no credentials of
+    // their own yet, so every login attempt is rejected otherwise
+    let authenticator: Arc<dyn AuthenticatorPort + Send + Sync> = match &config.ldap {
+        Some(ldap) => subsystems::ldap_authenticator(ldap.clone())?,
+        None => Arc::new(DisabledAuthenticator),
+    };
+
+    // Let the admin routes shift the clock of the tokens, signing keys and device codes when
+    // enabled, so their expiry can be tested without waiting
+    let test_clock = config.test_clock.then(|| Arc::new(ShiftedClock::new()));
+    let clock: Arc<dyn ClockPort + Send + Sync> = match &test_clock {
+        Some(test_clock) => {
+            tracing::warn!("TEST_CLOCK_ENABLED is set: the admin routes can shift the clock of the tokens, never enable it in production");
+            test_clock.clone()
+        }
+        None => Arc::new(SystemClock),
+    };
+
+    // Sign access tokens with the JWT secret, or with rotating keys shared through the database
+    // when configured, loading them before serving
+    let signing_keys = match (&config.jwt, &config.jwt_signing_keys) {
+        (Some(jwt), Some(signing_keys)) => {
+            let store = Arc::new(PostgresSigningKeyStore::new(database.postgres("JWT_KEY_ROTATION_INTERVAL_SECS")?.clone()));
+            let max_token_lifetime = Duration::from_secs(jwt.expiry_secs.max(MAX_IMPERSONATION_TTL_SECS));
+            let keys = Arc::new(SigningKeys::new(store, jwt.secret.expose_secret(), signing_keys, max_token_lifetime)?.with_clock(clock.clone()));
+            keys.refresh().await.context("failed to load signing keys")?;
+            Some(keys)
+        }
+        (None, Some(_)) => eyre::bail!("JWT_KEY_ROTATION_INTERVAL_SECS is set, but JWT_SECRET is not"),
+        (_, None) => None,
+    };
+    let tokens = config.jwt.as_ref().map(|jwt| match &signing_keys {
+        Some(keys) => Arc::new(JwtTokens::with_signing_keys(jwt, keys.clone()).with_clock(clock.clone())),
+        None => Arc::new(JwtTokens::new(jwt).with_clock(clock.clone())),
+    });
+
+    // Also accept the access tokens of an external identity provider when configured
+    let external_tokens = match &config.oidc {
+        Some(oidc) => Some(subsystems::oidc_tokens(oidc.clone()).await?),
+        None => None,
+    };
+
+    // Issue and verify access tokens when a JWT secret is configured, and verify those of the
+    // external identity provider
+    let auth = match (&tokens, external_tokens) {
+        (None, None) => {
+            tracing::warn!("JWT_SECRET is not set, authenticated routes reject every request");
+            AuthState::default()
+        }
+        (tokens, external_tokens) => {
+            let tokens: Arc<dyn TokenPort + Send + Sync> = match tokens {
+                Some(tokens) => tokens.clone(),
+                None => Arc::new(DisabledTokens),
+            };
+            let mut auth_service = AuthService::new(authenticator, tokens).with_group_roles(group_service.clone());
+            if let Some(external_tokens) = external_tokens {
+                auth_service = auth_service.with_external_tokens(external_tokens);
+            }
+            // Users who registered a passkey must log in with it when the password fallback is disabled
+            if let Some(WebAuthnConfig { password_fallback: PasswordFallback::Disabled, .. }) = &config.webauthn {
+                auth_service = auth_service.without_password_fallback(passkey_repository.clone());
+            }
+            AuthState {
+                auth_service: Arc::new(auth_service),
+            }
+        }
+    };
+
+    // Let CLI tools obtain access tokens approved by their user in a browser when configured
+    let device = match &config.device_grant {
+        Some(device_grant) => {
+            let tokens = tokens.clone().ok_or_else(|| eyre::eyre!("DEVICE_VERIFICATION_URI is set, but JWT_SECRET is not"))?;
+            Some(DeviceState {
+                device_service: Arc::new(DeviceService::new(Arc::new(InMemoryDeviceGrants::new(device_grant).with_clock(clock.clone())), tokens)),
+                verification_uri: device_grant.verification_uri.clone(),
+            })
+        }
+        None => None,
+    };
+
+    // Register passkeys and log users in with them when configured, handing the session over
+    // as an access token
+    let webauthn = match &config.webauthn {
+        Some(webauthn) => {
+            let tokens = tokens.clone().ok_or_else(|| eyre::eyre!("WEBAUTHN_RP_ID is set, but JWT_SECRET is not"))?;
+            let relying_party = subsystems::webauthn_relying_party(webauthn)?;
+            Some(WebAuthnState {
+                passkey_service: Arc::new(PasskeyService::new(relying_party, passkey_repository, user_repository.clone(), tokens).with_group_roles(group_service.clone())),
+            })
+        }
+        None => None,
+    };
+
+    // Delegate logins to a SAML identity provider when configured. The session is handed over
+    // as an access token, and authentication requests are authenticated with the JWT secret
+    // so that any replica accepts the responses
+    let saml = match &config.saml {
+        Some(saml) => {
+            let (jwt, tokens) = config.jwt.as_ref().zip(tokens.clone()).ok_or_else(|| eyre::eyre!("SAML_IDP_SSO_URL is set, but JWT_SECRET is not"))?;
+            let service_provider = subsystems::saml_service_provider(saml.clone(), jwt.secret.expose_secret().as_bytes())?;
+            Some(SamlState {
+                saml_service: Arc::new(SamlService::new(service_provider, user_repository, tokens).with_group_roles(group_service.clone())),
+                login_redirect_url: saml.login_redirect_url.clone(),
+            })
+        }
+        None => None,
+    };
+
+    let capabilities = Capabilities {
+        cache,
+        email,
+        error_reporter,
+        ..Capabilities::default()
+    };
+
+    // Serve the user service to internal backends over Thrift when configured, with the same
+    // authentication as the HTTP API
+    let thrift_server = match config.thrift_port {
+        Some(port) => {
+            let rpc = UserRpc::new(user_service.clone(), auth.clone());
+            let shutdown_timeout = Duration::from_secs(config.shutdown_timeout_secs);
+            Some(subsystems::thrift_server(rpc, port, config.max_body_bytes, shutdown_timeout).instrument(span.clone()).await?)
+        }
+        None => None,
+    };
+
+    // Execute the commands consumed from Kafka when configured, like the calls of the RPC APIs
+    let command_consumer = match &config.commands {
+        Some(commands) => {
+            let handler = Arc::new(UserCommandHandler::new(UserRpc::new(user_service.clone(), auth.clone())));
+            Some(span.in_scope(|| subsystems::kafka_command_consumer(commands, handler))?)
+        }
+        None => None,
+    };
+
+    // Materialize the daily statistics of the users in the background when enabled
+    let (stats, stats_projector) = match &config.stats {
+        Some(stats_config) => {
+            let stats: Arc<dyn UserStatsPort + Send + Sync> = Arc::new(PostgresUserStats::new(database.postgres("STATS_ENABLED")?.clone()));
+            let projector = StatsProjector::spawn(stats.clone(), stats_config.clone());
+            (StatsState { stats }, Some(projector))
+        }
+        None => (StatsState::default(), None),
+    };
+
+    let idempotency = match &config.idempotency {
+        Some(idempotency) => Some(Idempotency::new(
+            Arc::new(PostgresIdempotencyStore::new(database.postgres("IDEMPOTENCY_ENABLED")?.clone())),
+            Duration::from_secs(idempotency.ttl_secs),
+        )
+        .with_lease(Duration::from_secs(idempotency.lease_secs))),
+        None => None,
+    };
+
+    let tenants = TenantState {
+        config: match &config.tenant_settings {
+            Some(tenant_settings) => Some(Arc::new(CachedTenantConfig::new(
+                PostgresTenantConfig::new(database.postgres("TENANT_SETTINGS_ENABLED")?.clone()),
+                Duration::from_secs(tenant_settings.cache_ttl_secs),
+            ))),
+            None => None,
+        },
+    };
+
+    let state = AppState {
+        sampler,
+        admin_token: config.admin_token.as_ref().map(|token| token.expose_secret().as_str().into()),
+        scim_token: config.scim_token.as_ref().map(|token| token.expose_secret().as_str().into()),
+        introspection_token: config.introspection_token.as_ref().map(|token| token.expose_secret().as_str().into()),
+        key_set: signing_keys.clone().map(|keys| keys as Arc<dyn KeySetPort + Send + Sync>),
+        cursor_key: config.pagination_cursor_secret.as_ref().map_or_else(CursorKey::random, |secret| CursorKey::new(secret.expose_secret())),
+        auth,
+        saml,
+        device,
+        webauthn,
+        consents: ConsentState { consent_service },
+        groups: GroupState { group_service },
+        stats,
+        slos: SloState { tracker: slo_tracker },
+        request_tap: TapState { tap: config.request_tap.as_ref().map(|tap| RequestTap::new(tap.buffer)) },
+        in_flight: InFlightState { requests: config.in_flight_requests.then(InFlightRequests::new) },
+        tenants,
+        jwe_keys: match config.jwe_keys.as_slice() {
+            [] => None,
+            keys => Some(JweKeys::from_base64(keys.iter().map(|(kid, key)| (kid.clone(), key.expose_secret().clone()))).map_err(|e| eyre::eyre!(e))?),
+        },
+        idempotency,
+        rate_limiter: rate_limiter.clone(),
+        traffic_archive,
+        management: config.management.as_ref().map(|management| ManagementState {
+            port: management.port,
+            ..ManagementState::new(management.base_path.clone(), AdminToken(management.token.expose_secret().as_str().into()))
+        }),
+        audit: audit_log.map(|audit_log| AuditState { audit_log }),
+        clock: test_clock.map(|clock| ClockState { clock }),
+        capabilities: capabilities.clone(),
+        health_checks: HealthChecks::new(vec![database.health_check()]),
+        ..AppState::new(user_service)
+    };
+
+    // Serve the GraphQL API of the users when enabled, through the calls of the RPC APIs
+    let state = if config.graphql {
+        let user_service: Arc<dyn UserServiceTrait + Send + Sync> = state.user_service.clone();
+        let rpc = UserRpc::new(user_service, state.auth.clone());
+        subsystems::with_graphql(state, rpc)?
+    } else {
+        state
+    };
+
+    // Dispatch the outbox to the message broker. Without a broker, events accumulate in the
+    // outbox until one is configured
+    let outbox_dispatcher = match (outbox, &config.outbox) {
+        (Some(outbox), Some(outbox_config)) if capabilities.messaging.is_enabled() => {
+            Some(OutboxDispatcher::spawn(outbox, capabilities.messaging.clone(), outbox_config.clone()))
+        }
+        (Some(_), _) => {
+            tracing::warn!("OUTBOX_ENABLED is set, but messaging is disabled: user events are recorded, not published");
+            None
+        }
+        (None, _) => None,
+    };
+
+    // Run the jobs enqueued by every replica. Without SMTP_HOST, welcome e-mails are logged
+    let job_worker = match (job_queue, &config.jobs) {
+        (Some(job_queue), Some(jobs_config)) => {
+            let handlers = JobHandlers::new().with(Arc::new(WelcomeEmailJob::new(capabilities.email.clone())));
+            Some(JobWorker::spawn(job_queue, handlers, jobs_config.clone()))
+        }
+        _ => None,
+    };
+
+    // Rotate the signing keys, and load the keys rotated by other replicas
+    let key_rotation = signing_keys.map(KeyRotation::spawn);
 
     // Create HTTP server configuration
     let server_config = HttpServerConfig {
-        port: &config.server_port,
+        port: config.server_port,
+        shutdown_timeout: Duration::from_secs(config.shutdown_timeout_secs),
+        cors: config.cors.as_ref().map(|cors| CorsPolicy {
+            allowed_origins: cors.allowed_origins.clone(),
+            allowed_methods: cors.allowed_methods.clone(),
+            allowed_headers: cors.allowed_headers.clone(),
+            allow_credentials: cors.allow_credentials,
+        }),
+        max_body_bytes: config.max_body_bytes,
+        compression: (!config.compression_encodings.is_empty())
+            .then(|| CompressionPolicy { encodings: config.compression_encodings.clone() }),
+        load_shedding: LoadSheddingPolicy {
+            request_timeout: config.request_timeout_secs.map(Duration::from_secs),
+            max_concurrent_requests: config.max_concurrent_requests,
+        },
     };
 
-    // Create and run the HTTP server
-    let http_server = HttpServer::new(user_service, server_config).await?;
-    http_server.run().await
+    // Create and run the HTTP server until SIGTERM/SIGINT, delaying the drain when running in Kubernetes
+    let http_server = HttpServer::new(state, server_config).instrument(span.clone()).await?;
+    let result = match &config.kubernetes {
+        Some(kubernetes) => {
+            let delay = Duration::from_secs(kubernetes.termination_delay_secs);
+            http_server.run_until(wait_for_termination(shutdown_signal(), delay)).instrument(span).await
+        }
+        None => http_server.run().instrument(span).await,
+    };
+
+    if let Some(thrift_server) = thrift_server {
+        thrift_server.shutdown().await;
+    }
+    if let Some(command_consumer) = command_consumer {
+        command_consumer.shutdown().await;
+    }
+    if let Some(leader_election) = leader_election {
+        leader_election.shutdown().await;
+    }
+    if let Some(outbox_dispatcher) = outbox_dispatcher {
+        outbox_dispatcher.shutdown().await;
+    }
+    if let Some(job_worker) = job_worker {
+        job_worker.shutdown().await;
+    }
+    if let Some(key_rotation) = key_rotation {
+        key_rotation.shutdown().await;
+    }
+    if let Some(stats_projector) = stats_projector {
+        stats_projector.shutdown().await;
+    }
+    if let Some(traffic_archive_writer) = traffic_archive_writer {
+        traffic_archive_writer.shutdown().await;
+    }
+
+    // Close the database connections, without waiting for requests abandoned by the drain
+    if tokio::time::timeout(Duration::from_secs(config.shutdown_timeout_secs), database.close()).await.is_err() {
+        tracing::warn!("timed out closing the database connections");
+    }
+    telemetry.shutdown();
+
+    result
+}
+
+/// Database the server stores its data in.
+enum Database {
+    Postgres(Db),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteDb),
+}
+
+impl Database {
+    /// Returns the PostgreSQL pool, which the outbox and the shared signing keys are stored
+    /// in, failing when `variable`, which enables them, is set with another database.
+    #[cfg_attr(not(feature = "sqlite"), allow(unused_variables))]
+    fn postgres(&self, variable: &str) -> eyre::Result<&Db> {
+        match self {
+            Database::Postgres(db) => Ok(db),
+            #[cfg(feature = "sqlite")]
+            Database::Sqlite(_) => eyre::bail!("{} is set, but is only supported with PostgreSQL", variable),
+        }
+    }
+
+    fn health_check(&self) -> Arc<dyn HealthCheckPort + Send + Sync> {
+        match self {
+            Database::Postgres(db) => Arc::new(PostgresHealthCheck::new(db.clone())),
+            #[cfg(feature = "sqlite")]
+            Database::Sqlite(db) => Arc::new(SqliteHealthCheck::new(db.clone())),
+        }
+    }
+
+    async fn close(&self) {
+        match self {
+            Database::Postgres(db) => db.close().await,
+            #[cfg(feature = "sqlite")]
+            Database::Sqlite(db) => db.close().await,
+        }
+    }
 }
@@ -0,0 +1,361 @@
+//! Constructors of the optional subsystems, which fail with a descriptive error when the
+//! subsystem is configured but the server was built without its Cargo feature. Such settings
+//! are refused by [`check_features`](rust_web_server_lib::features::check_features) at
+//! startup already.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use rust_web_server_lib::application::flows::password_policy::{BreachedPasswordRule, MinLengthRule, PasswordPolicy, PasswordRule};
+use rust_web_server_lib::application::flows::user_service::UserServiceTrait;
+use rust_web_server_lib::application::ports::auth::{AuthenticatorPort, ExternalTokenPort, SamlServiceProviderPort};
+use rust_web_server_lib::application::ports::breached_passwords::BreachedPasswordsPort;
+use rust_web_server_lib::application::ports::cache::CachePort;
+use rust_web_server_lib::application::ports::commands::CommandHandlerPort;
+use rust_web_server_lib::application::ports::email::EmailSenderPort;
+use rust_web_server_lib::application::ports::error_reporter::ErrorReporterPort;
+use rust_web_server_lib::application::ports::events::EventPublisherPort;
+use rust_web_server_lib::application::ports::purge::PurgePort;
+#[cfg(not(feature = "archive"))]
+use rust_web_server_lib::application::ports::traffic_archive::TrafficArchivePort;
+use rust_web_server_lib::application::ports::webauthn::WebAuthnPort;
+use rust_web_server_lib::infra::auth::{BreachCheckConfig, LdapConfig, OidcConfig, PasswordPolicyConfig, SamlConfig, WebAuthnConfig};
+use rust_web_server_lib::infra::cache::CacheConfig;
+use rust_web_server_lib::infra::config::Config;
+use rust_web_server_lib::infra::discovery::ServiceDiscoveryPort;
+use rust_web_server_lib::infra::email::SmtpConfig;
+use rust_web_server_lib::infra::error_reporting::SentryConfig;
+use rust_web_server_lib::infra::kubernetes::{LeaderElectionConfig, PodMetadata};
+use rust_web_server_lib::infra::messaging::{CommandConsumerConfig, KafkaConfig, MqttConfig};
+use rust_web_server_lib::infra::purge::{CloudflarePurgeConfig, FastlyPurgeConfig, PurgeConfig};
+use rust_web_server_lib::infra::telemetry::Tracing;
+use rust_web_server_lib::infra::traffic_archive::TrafficArchiveConfig;
+use rust_web_server_lib::presentation::http::AppState;
+use rust_web_server_lib::presentation::rpc::UserRpc;
+
+#[cfg(feature = "archive")]
+pub use rust_web_server_lib::infra::traffic_archive::parquet::TrafficArchiveWriter;
+#[cfg(feature = "kubernetes")]
+pub use rust_web_server_lib::infra::kubernetes::leader_election::LeaderElection;
+#[cfg(feature = "kafka")]
+pub use rust_web_server_lib::infra::messaging::commands::KafkaCommandConsumer;
+#[cfg(feature = "thrift")]
+pub use rust_web_server_lib::presentation::rpc::thrift::ThriftServerTask;
+
+#[cfg(feature = "discovery")]
+pub fn dns_discovery() -> eyre::Result<Arc<dyn ServiceDiscoveryPort + Send + Sync>> {
+    use rust_web_server_lib::infra::discovery::dns::DnsSrvDiscovery;
+
+    Ok(Arc::new(DnsSrvDiscovery::from_system_conf()?))
+}
+
+#[cfg(not(feature = "discovery"))]
+pub fn dns_discovery() -> eyre::Result<Arc<dyn ServiceDiscoveryPort + Send + Sync>> {
+    eyre::bail!("DATABASE_SRV_RECORD is set, but the server was built without the `discovery` feature")
+}
+
+#[cfg(feature = "redis")]
+pub fn redis_cache(config: CacheConfig) -> eyre::Result<Arc<dyn CachePort + Send + Sync>> {
+    use rust_web_server_lib::infra::cache::redis::RedisCache;
+
+    Ok(Arc::new(RedisCache::new(&config)?))
+}
+
+#[cfg(not(feature = "redis"))]
+pub fn redis_cache(_config: CacheConfig) -> eyre::Result<Arc<dyn CachePort + Send + Sync>> {
+    eyre::bail!("CACHE_URL is set, but the server was built without the `redis` feature")
+}
+
+#[cfg(feature = "sentry")]
+pub fn sentry_reporter(config: SentryConfig) -> eyre::Result<Arc<dyn ErrorReporterPort + Send + Sync>> {
+    use rust_web_server_lib::infra::error_reporting::sentry::SentryErrorReporter;
+
+    Ok(Arc::new(SentryErrorReporter::spawn(config)?))
+}
+
+#[cfg(not(feature = "sentry"))]
+pub fn sentry_reporter(_config: SentryConfig) -> eyre::Result<Arc<dyn ErrorReporterPort + Send + Sync>> {
+    eyre::bail!("SENTRY_DSN is set, but the server was built without the `sentry` feature")
+}
+
+#[cfg(feature = "smtp")]
+pub fn smtp_sender(config: &SmtpConfig) -> eyre::Result<Arc<dyn EmailSenderPort + Send + Sync>> {
+    use rust_web_server_lib::infra::email::smtp::SmtpEmailSender;
+
+    Ok(Arc::new(SmtpEmailSender::new(config)?))
+}
+
+#[cfg(not(feature = "smtp"))]
+pub fn smtp_sender(_config: &SmtpConfig) -> eyre::Result<Arc<dyn EmailSenderPort + Send + Sync>> {
+    eyre::bail!("SMTP_HOST is set, but the server was built without the `smtp` feature")
+}
+
+/// Builds the password policy of `config`: its minimum length, and its minimum strength and
+/// breach check when set.
+pub fn password_policy(config: &PasswordPolicyConfig) -> eyre::Result<PasswordPolicy> {
+    let mut policy = PasswordPolicy::new().with_rule(Arc::new(MinLengthRule::new(config.min_length)));
+    if let Some(min_strength) = config.min_strength {
+        policy = policy.with_rule(zxcvbn_strength_rule(min_strength)?);
+    }
+    if let Some(breach_check) = &config.breach_check {
+        policy = policy.with_rule(Arc::new(BreachedPasswordRule::new(hibp_breached_passwords(breach_check)?)));
+    }
+    Ok(policy)
+}
+
+#[cfg(feature = "zxcvbn")]
+fn zxcvbn_strength_rule(min_strength: u8) -> eyre::Result<Arc<dyn PasswordRule + Send + Sync>> {
+    use rust_web_server_lib::infra::auth::zxcvbn::ZxcvbnStrengthRule;
+
+    Ok(Arc::new(ZxcvbnStrengthRule::new(min_strength)))
+}
+
+#[cfg(not(feature = "zxcvbn"))]
+fn zxcvbn_strength_rule(_min_strength: u8) -> eyre::Result<Arc<dyn PasswordRule + Send + Sync>> {
+    eyre::bail!("PASSWORD_MIN_STRENGTH is set, but the server was built without the `zxcvbn` feature")
+}
+
+#[cfg(feature = "hibp")]
+fn hibp_breached_passwords(config: &BreachCheckConfig) -> eyre::Result<Arc<dyn BreachedPasswordsPort + Send + Sync>> {
+    use rust_web_server_lib::infra::auth::hibp::HibpBreachedPasswords;
+
+    Ok(Arc::new(HibpBreachedPasswords::new(config)?))
+}
+
+#[cfg(not(feature = "hibp"))]
+fn hibp_breached_passwords(_config: &BreachCheckConfig) -> eyre::Result<Arc<dyn BreachedPasswordsPort + Send + Sync>> {
+    eyre::bail!("PASSWORD_BREACH_CHECK is set, but the server was built without the `hibp` feature")
+}
+
+#[cfg(feature = "purge")]
+pub fn http_purger(config: PurgeConfig) -> eyre::Result<Arc<dyn PurgePort + Send + Sync>> {
+    use rust_web_server_lib::infra::purge::http::HttpPurger;
+
+    Ok(Arc::new(HttpPurger::spawn(config)?))
+}
+
+#[cfg(not(feature = "purge"))]
+pub fn http_purger(_config: PurgeConfig) -> eyre::Result<Arc<dyn PurgePort + Send + Sync>> {
+    eyre::bail!("PURGE_URL is set, but the server was built without the `purge` feature")
+}
+
+#[cfg(feature = "purge")]
+pub fn cloudflare_purger(config: CloudflarePurgeConfig) -> eyre::Result<Arc<dyn PurgePort + Send + Sync>> {
+    use rust_web_server_lib::infra::purge::cloudflare::CloudflarePurger;
+
+    Ok(Arc::new(CloudflarePurger::spawn(config)?))
+}
+
+#[cfg(not(feature = "purge"))]
+pub fn cloudflare_purger(_config: CloudflarePurgeConfig) -> eyre::Result<Arc<dyn PurgePort + Send + Sync>> {
+    eyre::bail!("CLOUDFLARE_ZONE_ID is set, but the server was built without the `purge` feature")
+}
+
+#[cfg(feature = "purge")]
+pub fn fastly_purger(config: FastlyPurgeConfig) -> eyre::Result<Arc<dyn PurgePort + Send + Sync>> {
+    use rust_web_server_lib::infra::purge::fastly::FastlyPurger;
+
+    Ok(Arc::new(FastlyPurger::spawn(config)?))
+}
+
+#[cfg(not(feature = "purge"))]
+pub fn fastly_purger(_config: FastlyPurgeConfig) -> eyre::Result<Arc<dyn PurgePort + Send + Sync>> {
+    eyre::bail!("FASTLY_SERVICE_ID is set, but the server was built without the `purge` feature")
+}
+
+#[cfg(feature = "ldap")]
+pub fn ldap_authenticator(config: LdapConfig) -> eyre::Result<Arc<dyn AuthenticatorPort + Send + Sync>> {
+    use rust_web_server_lib::infra::auth::ldap::LdapAuthenticator;
+
+    Ok(Arc::new(LdapAuthenticator::new(config)))
+}
+
+#[cfg(not(feature = "ldap"))]
+pub fn ldap_authenticator(_config: LdapConfig) -> eyre::Result<Arc<dyn AuthenticatorPort + Send + Sync>> {
+    eyre::bail!("LDAP_URL is set, but the server was built without the `ldap` feature")
+}
+
+#[cfg(feature = "oidc")]
+pub async fn oidc_tokens(config: OidcConfig) -> eyre::Result<Arc<dyn ExternalTokenPort + Send + Sync>> {
+    use rust_web_server_lib::infra::auth::oidc::OidcTokens;
+
+    Ok(OidcTokens::spawn(config).await?)
+}
+
+#[cfg(not(feature = "oidc"))]
+pub async fn oidc_tokens(_config: OidcConfig) -> eyre::Result<Arc<dyn ExternalTokenPort + Send + Sync>> {
+    eyre::bail!("OIDC_ISSUER is set, but the server was built without the `oidc` feature")
+}
+
+#[cfg(feature = "saml")]
+pub fn saml_service_provider(config: SamlConfig, request_key: &[u8]) -> eyre::Result<Arc<dyn SamlServiceProviderPort + Send + Sync>> {
+    use rust_web_server_lib::infra::auth::saml::SamlServiceProvider;
+
+    Ok(Arc::new(SamlServiceProvider::new(config, request_key)?))
+}
+
+#[cfg(not(feature = "saml"))]
+pub fn saml_service_provider(_config: SamlConfig, _request_key: &[u8]) -> eyre::Result<Arc<dyn SamlServiceProviderPort + Send + Sync>> {
+    eyre::bail!("SAML_IDP_SSO_URL is set, but the server was built without the `saml` feature")
+}
+
+#[cfg(feature = "webauthn")]
+pub fn webauthn_relying_party(config: &WebAuthnConfig) -> eyre::Result<Arc<dyn WebAuthnPort + Send + Sync>> {
+    use rust_web_server_lib::infra::auth::webauthn::WebAuthnRelyingParty;
+
+    Ok(Arc::new(WebAuthnRelyingParty::new(config)?))
+}
+
+#[cfg(not(feature = "webauthn"))]
+pub fn webauthn_relying_party(_config: &WebAuthnConfig) -> eyre::Result<Arc<dyn WebAuthnPort + Send + Sync>> {
+    eyre::bail!("WEBAUTHN_RP_ID is set, but the server was built without the `webauthn` feature")
+}
+
+#[cfg(feature = "kafka")]
+pub fn kafka_event_publisher(config: &KafkaConfig) -> eyre::Result<Arc<dyn EventPublisherPort + Send + Sync>> {
+    use rust_web_server_lib::infra::messaging::kafka::KafkaEventPublisher;
+
+    Ok(Arc::new(KafkaEventPublisher::new(config)?))
+}
+
+#[cfg(not(feature = "kafka"))]
+pub fn kafka_event_publisher(_config: &KafkaConfig) -> eyre::Result<Arc<dyn EventPublisherPort + Send + Sync>> {
+    eyre::bail!("KAFKA_BROKERS is set, but the server was built without the `kafka` feature")
+}
+
+#[cfg(feature = "kafka")]
+pub fn kafka_command_consumer(config: &CommandConsumerConfig, handler: Arc<dyn CommandHandlerPort + Send + Sync>) -> eyre::Result<KafkaCommandConsumer> {
+    KafkaCommandConsumer::spawn(config, handler)
+}
+
+/// Stand-in for the command consumer when built without the `kafka` feature.
+#[cfg(not(feature = "kafka"))]
+pub enum KafkaCommandConsumer {}
+
+#[cfg(not(feature = "kafka"))]
+impl KafkaCommandConsumer {
+    pub async fn shutdown(self) {}
+}
+
+#[cfg(not(feature = "kafka"))]
+pub fn kafka_command_consumer(_config: &CommandConsumerConfig, _handler: Arc<dyn CommandHandlerPort + Send + Sync>) -> eyre::Result<KafkaCommandConsumer> {
+    eyre::bail!("COMMANDS_KAFKA_BROKERS is set, but the server was built without the `kafka` feature")
+}
+
+#[cfg(feature = "mqtt")]
+pub fn mqtt_event_publisher(config: &MqttConfig) -> eyre::Result<Arc<dyn EventPublisherPort + Send + Sync>> {
+    use rust_web_server_lib::infra::messaging::mqtt::MqttEventPublisher;
+
+    Ok(Arc::new(MqttEventPublisher::new(config)?))
+}
+
+#[cfg(not(feature = "mqtt"))]
+pub fn mqtt_event_publisher(_config: &MqttConfig) -> eyre::Result<Arc<dyn EventPublisherPort + Send + Sync>> {
+    eyre::bail!("MQTT_BROKER is set, but the server was built without the `mqtt` feature")
+}
+
+#[cfg(feature = "kubernetes")]
+pub fn leader_election(pod: &PodMetadata, config: LeaderElectionConfig) -> eyre::Result<LeaderElection> {
+    use rust_web_server_lib::infra::kubernetes::client::KubeClient;
+
+    Ok(LeaderElection::spawn(KubeClient::in_cluster()?, pod, config))
+}
+
+/// Stand-in for the leader election handle when built without the `kubernetes` feature.
+#[cfg(not(feature = "kubernetes"))]
+pub enum LeaderElection {}
+
+#[cfg(not(feature = "kubernetes"))]
+impl LeaderElection {
+    pub async fn shutdown(self) {}
+}
+
+#[cfg(not(feature = "kubernetes"))]
+pub fn leader_election(_pod: &PodMetadata, _config: LeaderElectionConfig) -> eyre::Result<LeaderElection> {
+    eyre::bail!("LEADER_ELECTION_LEASE_NAME is set, but the server was built without the `kubernetes` feature")
+}
+
+#[cfg(feature = "archive")]
+pub fn traffic_archive_writer(config: &TrafficArchiveConfig) -> eyre::Result<TrafficArchiveWriter> {
+    TrafficArchiveWriter::spawn(config)
+}
+
+/// Stand-in for the traffic archive writer when built without the `archive` feature.
+#[cfg(not(feature = "archive"))]
+pub enum TrafficArchiveWriter {}
+
+#[cfg(not(feature = "archive"))]
+impl TrafficArchiveWriter {
+    pub fn archive(&self) -> Arc<dyn TrafficArchivePort + Send + Sync> {
+        match *self {}
+    }
+
+    pub async fn shutdown(self) {}
+}
+
+#[cfg(not(feature = "archive"))]
+pub fn traffic_archive_writer(_config: &TrafficArchiveConfig) -> eyre::Result<TrafficArchiveWriter> {
+    eyre::bail!("TRAFFIC_ARCHIVE_URL is set, but the server was built without the `archive` feature")
+}
+
+#[cfg(feature = "thrift")]
+pub async fn thrift_server<S>(rpc: UserRpc<S>, port: u16, max_frame_bytes: usize, shutdown_timeout: Duration) -> eyre::Result<ThriftServerTask>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    use rust_web_server_lib::presentation::rpc::thrift::{ThriftServer, ThriftServerConfig};
+
+    let config = ThriftServerConfig { port, max_frame_bytes, shutdown_timeout };
+    Ok(ThriftServer::new(rpc, config).await?.spawn())
+}
+
+/// Stand-in for the Thrift server task when built without the `thrift` feature.
+#[cfg(not(feature = "thrift"))]
+pub enum ThriftServerTask {}
+
+#[cfg(not(feature = "thrift"))]
+impl ThriftServerTask {
+    pub async fn shutdown(self) {}
+}
+
+#[cfg(not(feature = "thrift"))]
+pub async fn thrift_server<S>(_rpc: UserRpc<S>, _port: u16, _max_frame_bytes: usize, _shutdown_timeout: Duration) -> eyre::Result<ThriftServerTask>
+where
+    S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+{
+    eyre::bail!("THRIFT_PORT is set, but the server was built without the `thrift` feature")
+}
+
+/// Serves the GraphQL API of the users of `rpc` with `state`.
+#[cfg(feature = "graphql")]
+pub fn with_graphql<S: ?Sized>(state: AppState<S>, rpc: UserRpc) -> eyre::Result<AppState<S>> {
+    use rust_web_server_lib::presentation::graphql::GraphQlState;
+
+    Ok(AppState { graphql: Some(GraphQlState::new(rpc)), ..state })
+}
+
+#[cfg(not(feature = "graphql"))]
+pub fn with_graphql<S: ?Sized>(_state: AppState<S>, _rpc: UserRpc) -> eyre::Result<AppState<S>> {
+    eyre::bail!("GRAPHQL_ENABLED is set, but the server was built without the `graphql` feature")
+}
+
+/// Serves the API with the users stored in the SQLite database of `DATABASE_URL`.
+#[cfg(feature = "sqlite")]
+pub async fn serve_sqlite(config: Config, telemetry: Tracing, error_reporter: Arc<dyn ErrorReporterPort + Send + Sync>, span: tracing::Span) -> eyre::Result<()> {
+    use rust_web_server_lib::infra::storage::adapter::sqlite::{create_sqlite_repositories, db_connect, run_migrations};
+
+    if config.database_discovery.is_some() {
+        eyre::bail!("DATABASE_SRV_RECORD is set, but DATABASE_URL is a SQLite URL");
+    }
+    let db = db_connect(config.database_url.expose_secret(), &config.database_pool).await?;
+    if config.run_migrations {
+        run_migrations(&db).await?;
+    }
+    let repositories = create_sqlite_repositories(db.clone())?;
+    crate::serve(config, telemetry, error_reporter, span, crate::Database::Sqlite(db), repositories).await
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub async fn serve_sqlite(_config: Config, _telemetry: Tracing, _error_reporter: Arc<dyn ErrorReporterPort + Send + Sync>, _span: tracing::Span) -> eyre::Result<()> {
+    eyre::bail!("DATABASE_URL is a SQLite URL, but the server was built without the `sqlite` feature")
+}
@@ -1 +0,0 @@
-pub mod user_service;
\ No newline at end of file
@@ -1,3 +0,0 @@
-pub mod flows;
-pub mod error;
-pub mod dto;
\ No newline at end of file
@@ -1 +0,0 @@
-pub mod user;
\ No newline at end of file
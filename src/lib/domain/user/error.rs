@@ -1,9 +0,0 @@
-
-
-pub enum UserDomainError {
-    UserNotFound,
-    UserAlreadyExists,
-    UserCreationFailed,
-    UserUpdateFailed,
-    UserDeletionFailed,
-}
\ No newline at end of file
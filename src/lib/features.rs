@@ -0,0 +1,79 @@
+//! Compatibility of the configuration with the Cargo features the server was built with.
+//!
+//! Optional subsystems are compiled in by their feature, and configured by their variables. A
+//! subsystem configured in a build without its feature is refused at startup, listing every
+//! such setting at once, rather than ignored or found one at a time.
+
+use std::fmt;
+
+use infra::config::Config;
+use infra::storage::adapter::is_sqlite_url;
+
+/// A subsystem configured by a setting, in a build without the feature of the subsystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingFeature {
+    /// Variable configuring the subsystem, e.g. `KAFKA_BROKERS`.
+    pub setting: &'static str,
+    /// Cargo feature of the subsystem, e.g. `kafka`.
+    pub feature: &'static str,
+}
+
+impl fmt::Display for MissingFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is set, but the server was built without the `{}` feature", self.setting, self.feature)
+    }
+}
+
+/// Returns the subsystems configured by `config` whose features the server was built without.
+pub fn missing_features(config: &Config) -> Vec<MissingFeature> {
+    let leader_election = config.kubernetes.as_ref().is_some_and(|kubernetes| kubernetes.leader_election.is_some());
+    // (setting, feature, whether the feature is built, whether the setting is set)
+    let subsystems = [
+        ("DATABASE_SRV_RECORD", "discovery", cfg!(feature = "discovery"), config.database_discovery.is_some()),
+        ("DATABASE_URL=sqlite:", "sqlite", cfg!(feature = "sqlite"), is_sqlite_url(config.database_url.expose_secret())),
+        ("OTEL_EXPORTER_OTLP_ENDPOINT", "otel", cfg!(feature = "otel"), config.otlp.is_some()),
+        ("SENTRY_DSN", "sentry", cfg!(feature = "sentry"), config.sentry.is_some()),
+        ("KAFKA_BROKERS", "kafka", cfg!(feature = "kafka"), config.kafka.is_some()),
+        ("COMMANDS_KAFKA_BROKERS", "kafka", cfg!(feature = "kafka"), config.commands.is_some()),
+        ("MQTT_BROKER", "mqtt", cfg!(feature = "mqtt"), config.mqtt.is_some()),
+        ("LEADER_ELECTION_LEASE_NAME", "kubernetes", cfg!(feature = "kubernetes"), leader_election),
+        ("THRIFT_PORT", "thrift", cfg!(feature = "thrift"), config.thrift_port.is_some()),
+        ("GRAPHQL_ENABLED", "graphql", cfg!(feature = "graphql"), config.graphql),
+        ("CACHE_URL", "redis", cfg!(feature = "redis"), config.cache.is_some()),
+        ("PURGE_URL", "purge", cfg!(feature = "purge"), config.purge.is_some()),
+        ("CLOUDFLARE_ZONE_ID", "purge", cfg!(feature = "purge"), config.cloudflare_purge.is_some()),
+        ("FASTLY_SERVICE_ID", "purge", cfg!(feature = "purge"), config.fastly_purge.is_some()),
+        ("TRAFFIC_ARCHIVE_URL", "archive", cfg!(feature = "archive"), config.traffic_archive.is_some()),
+        ("OIDC_ISSUER", "oidc", cfg!(feature = "oidc"), config.oidc.is_some()),
+        ("LDAP_URL", "ldap", cfg!(feature = "ldap"), config.ldap.is_some()),
+        ("SAML_IDP_SSO_URL", "saml", cfg!(feature = "saml"), config.saml.is_some()),
+        ("WEBAUTHN_RP_ID", "webauthn", cfg!(feature = "webauthn"), config.webauthn.is_some()),
+        ("PASSWORD_MIN_STRENGTH", "zxcvbn", cfg!(feature = "zxcvbn"), config.password_policy.min_strength.is_some()),
+        ("PASSWORD_BREACH_CHECK", "hibp", cfg!(feature = "hibp"), config.password_policy.breach_check.is_some()),
+        ("SMTP_HOST", "smtp", cfg!(feature = "smtp"), config.smtp.is_some()),
+    ];
+
+    subsystems
+        .into_iter()
+        .filter(|(_, _, built, set)| *set && !built)
+        .map(|(setting, feature, _, _)| MissingFeature { setting, feature })
+        .collect()
+}
+
+/// Fails with every subsystem configured by `config` whose feature the server was built
+/// without, and the features to build it with.
+pub fn check_features(config: &Config) -> eyre::Result<()> {
+    let missing = missing_features(config);
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let mut features: Vec<&str> = missing.iter().map(|missing| missing.feature).collect();
+    features.dedup();
+    let settings: Vec<String> = missing.iter().map(|missing| format!("  - {}", missing)).collect();
+    eyre::bail!(
+        "configuration requires features the server was built without:\n{}\nBuild with `--features {}`, or unset these variables.",
+        settings.join("\n"),
+        features.join(",")
+    )
+}
@@ -1,2 +0,0 @@
-pub mod storage;
-pub mod config;
\ No newline at end of file
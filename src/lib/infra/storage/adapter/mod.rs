@@ -1 +0,0 @@
-pub mod postgres;
\ No newline at end of file
@@ -1,4 +1,13 @@
-pub mod application;
-pub mod domain;
-pub mod infra;
-pub mod presentation;
\ No newline at end of file
+//! Facade over the layer crates of the workspace, so the template can be used as a single
+//! dependency. Each layer lives in its own crate under `crates/`, which enforces the
+//! dependency direction: `domain` <- `application` <- `infra` / `presentation`.
+
+pub use application;
+pub use domain;
+pub use infra;
+pub use presentation;
+
+pub mod features;
+
+#[cfg(feature = "testing")]
+pub mod testing;
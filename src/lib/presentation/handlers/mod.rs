@@ -1 +0,0 @@
-pub mod user_handlers;
\ No newline at end of file
@@ -1,2 +0,0 @@
-pub mod http;
-pub mod handlers;
\ No newline at end of file
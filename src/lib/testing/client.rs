@@ -0,0 +1,138 @@
+//! A client of the full router, for tests of the HTTP API without a listener or a database.
+
+use std::sync::Arc;
+
+use axum::body::{Body, Bytes};
+use axum::http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use crate::application::flows::user_service::{UserService, UserServiceTrait};
+use crate::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use crate::presentation::http::{router, AppState};
+
+/// Sends requests to the router built by [`router`], in-process.
+///
+/// The state is in memory: a `UserService` over an [`InMemoryUserRepository`] by default, or
+/// any service, e.g. a [`MockUserService`](crate::testing::MockUserService). Every other
+/// dependency of the state is disabled unless given with [`TestClient::with_state`].
+#[derive(Clone)]
+pub struct TestClient {
+    router: axum::Router,
+    headers: HeaderMap,
+}
+
+impl TestClient {
+    /// Creates a new `TestClient` of a router serving an empty in-memory repository.
+    pub fn new() -> Self {
+        Self::with_user_service(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    }
+
+    /// Creates a new `TestClient` of a router serving `user_service`.
+    pub fn with_user_service(user_service: Arc<dyn UserServiceTrait + Send + Sync + 'static>) -> Self {
+        Self::with_state(AppState::new(user_service))
+    }
+
+    /// Creates a new `TestClient` of a router built from `state`, e.g. to mount the admin routes
+    /// or enable authentication.
+    pub fn with_state<S>(state: AppState<S>) -> Self
+    where
+        S: UserServiceTrait + Send + Sync + ?Sized + 'static,
+    {
+        Self { router: router(state), headers: HeaderMap::new() }
+    }
+
+    /// Sends `Authorization: Bearer <token>` with every request.
+    pub fn with_bearer_token(self, token: &str) -> Self {
+        self.with_header(header::AUTHORIZATION, &format!("Bearer {}", token))
+    }
+
+    /// Sends the header `name` with every request, replacing the value set before, if any.
+    ///
+    /// # Panics
+    ///
+    /// If `value` is not a valid header value.
+    pub fn with_header(mut self, name: header::HeaderName, value: &str) -> Self {
+        self.headers.insert(name, HeaderValue::from_str(value).expect("invalid header value"));
+        self
+    }
+
+    /// Sends a `GET` request to `uri`.
+    pub async fn get(&self, uri: &str) -> TestResponse {
+        self.request(Method::GET, uri, None).await
+    }
+
+    /// Sends a `POST` request to `uri`, with `body` as JSON.
+    pub async fn post(&self, uri: &str, body: Value) -> TestResponse {
+        self.request(Method::POST, uri, Some(body)).await
+    }
+
+    /// Sends a `PUT` request to `uri`, with `body` as JSON.
+    pub async fn put(&self, uri: &str, body: Value) -> TestResponse {
+        self.request(Method::PUT, uri, Some(body)).await
+    }
+
+    /// Sends a `PATCH` request to `uri`, with `body` as JSON.
+    pub async fn patch(&self, uri: &str, body: Value) -> TestResponse {
+        self.request(Method::PATCH, uri, Some(body)).await
+    }
+
+    /// Sends a `DELETE` request to `uri`.
+    pub async fn delete(&self, uri: &str) -> TestResponse {
+        self.request(Method::DELETE, uri, None).await
+    }
+
+    /// Sends a `method` request to `uri`, with `body` as JSON if any.
+    pub async fn request(&self, method: Method, uri: &str, body: Option<Value>) -> TestResponse {
+        let builder = Request::builder().method(method).uri(uri);
+        let request = match body {
+            Some(body) => builder.header(header::CONTENT_TYPE, "application/json").body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        };
+        self.send(request.expect("invalid request")).await
+    }
+
+    /// Sends `request`, with the headers of the client it does not set itself.
+    pub async fn send(&self, mut request: Request<Body>) -> TestResponse {
+        for (name, value) in &self.headers {
+            if !request.headers().contains_key(name) {
+                request.headers_mut().insert(name, value.clone());
+            }
+        }
+        let response = self.router.clone().oneshot(request).await.unwrap_or_else(|e| match e {});
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("failed to read the response body");
+        TestResponse { status, headers, body }
+    }
+}
+
+impl Default for TestClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A response received by a [`TestClient`], with its whole body.
+#[derive(Debug, Clone)]
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    /// Returns the body parsed as JSON.
+    ///
+    /// # Panics
+    ///
+    /// If the body is not JSON.
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| panic!("response body is not JSON ({}): {}", e, self.text()))
+    }
+
+    /// Returns the body as text, replacing invalid UTF-8.
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
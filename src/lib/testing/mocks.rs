@@ -0,0 +1,104 @@
+//! Hand-rolled mocks of the user repository and service.
+//!
+//! Every method answers with the closure set by its `on_` method, e.g.
+//! [`MockUserRepository::on_get_user`], and panics when called without one, so a test states
+//! exactly the calls it expects. The names of the methods called are recorded in order.
+
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::application::flows::user_service::UserServiceTrait;
+use crate::domain::user::{error::UserDomainError, model::{CreateUser, Email, ListUsers, PasswordHash, Role, UpdateUser, User, UserCredentials, UserFacets, UserFilter, UserId, UserPage}, repository::UserRepositoryPort};
+
+/// Defines a mock implementing the async trait `$port`, with a field holding the answer of
+/// each method and the `on_` method setting it.
+macro_rules! mock {
+    (
+        $(#[$meta:meta])*
+        $mock:ident: $port:ident {
+            $( fn $method:ident, $on:ident ( $($arg:ident: $ty:ty),* ) -> $ret:ty; )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Default)]
+        pub struct $mock {
+            calls: Mutex<Vec<&'static str>>,
+            $( $method: Option<Box<dyn Fn($($ty),*) -> $ret + Send + Sync>>, )*
+        }
+
+        impl $mock {
+            /// Creates a new mock expecting no calls.
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Returns the names of the methods called, in the order of the calls.
+            pub fn calls(&self) -> Vec<&'static str> {
+                self.calls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+            }
+
+            $(
+                #[doc = concat!("Answers the calls of `", stringify!($method), "` with `answer`.")]
+                pub fn $on(mut self, answer: impl Fn($($ty),*) -> $ret + Send + Sync + 'static) -> Self {
+                    self.$method = Some(Box::new(answer));
+                    self
+                }
+            )*
+        }
+
+        #[async_trait]
+        impl $port for $mock {
+            $(
+                async fn $method(&self, $($arg: $ty),*) -> $ret {
+                    self.calls.lock().unwrap_or_else(|e| e.into_inner()).push(stringify!($method));
+                    match &self.$method {
+                        Some(answer) => answer($($arg),*),
+                        None => panic!(concat!("unexpected call to ", stringify!($mock), "::", stringify!($method))),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+mock! {
+    /// Mock of [`UserRepositoryPort`], for tests of services without a database.
+    ///
+    /// Bulk creations call `create_user` for every user, like the default `create_users`.
+    MockUserRepository: UserRepositoryPort {
+        fn create_user, on_create_user(user: CreateUser, password_hash: Option<PasswordHash>) -> Result<User, UserDomainError>;
+        fn get_user, on_get_user(id: UserId) -> Result<User, UserDomainError>;
+        fn get_user_by_email, on_get_user_by_email(email: Email) -> Result<User, UserDomainError>;
+        fn get_user_credentials, on_get_user_credentials(email: Email) -> Result<UserCredentials, UserDomainError>;
+        fn list_users, on_list_users(query: ListUsers) -> Result<UserPage, UserDomainError>;
+        fn search_users, on_search_users(filter: UserFilter) -> Result<UserPage, UserDomainError>;
+        fn count_user_facets, on_count_user_facets() -> Result<UserFacets, UserDomainError>;
+        fn update_user, on_update_user(user: UpdateUser) -> Result<User, UserDomainError>;
+        fn delete_user, on_delete_user(id: UserId) -> Result<(), UserDomainError>;
+        fn restore_user, on_restore_user(id: UserId) -> Result<User, UserDomainError>;
+        fn hard_delete_user, on_hard_delete_user(id: UserId) -> Result<(), UserDomainError>;
+        fn set_legal_hold, on_set_legal_hold(id: UserId, legal_hold: bool) -> Result<User, UserDomainError>;
+        fn set_role, on_set_role(id: UserId, role: Role) -> Result<User, UserDomainError>;
+    }
+}
+
+mock! {
+    /// Mock of [`UserServiceTrait`], for tests of handlers without a repository.
+    MockUserService: UserServiceTrait {
+        fn create_user, on_create_user(user: CreateUser) -> Result<User, UserDomainError>;
+        fn create_users_bulk, on_create_users_bulk(users: Vec<CreateUser>) -> Vec<Result<User, UserDomainError>>;
+        fn get_user, on_get_user(id: UserId) -> Result<User, UserDomainError>;
+        fn get_user_by_email, on_get_user_by_email(email: Email) -> Result<User, UserDomainError>;
+        fn verify_credentials, on_verify_credentials(email: Email, password: String) -> Result<User, UserDomainError>;
+        fn list_users, on_list_users(query: ListUsers) -> Result<UserPage, UserDomainError>;
+        fn search_users, on_search_users(filter: UserFilter) -> Result<UserPage, UserDomainError>;
+        fn count_user_facets, on_count_user_facets() -> Result<UserFacets, UserDomainError>;
+        fn update_user, on_update_user(user: UpdateUser) -> Result<User, UserDomainError>;
+        fn delete_user, on_delete_user(id: UserId) -> Result<(), UserDomainError>;
+        fn restore_user, on_restore_user(id: UserId) -> Result<User, UserDomainError>;
+        fn hard_delete_user, on_hard_delete_user(id: UserId) -> Result<(), UserDomainError>;
+        fn set_legal_hold, on_set_legal_hold(id: UserId, legal_hold: bool) -> Result<User, UserDomainError>;
+        fn set_role, on_set_role(id: UserId, role: Role) -> Result<User, UserDomainError>;
+    }
+}
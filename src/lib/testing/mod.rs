@@ -0,0 +1,12 @@
+//! Test doubles and helpers for tests of the template and of the services built on it,
+//! without a database.
+//!
+//! [`MockUserRepository`] and [`MockUserService`] answer the calls a test expects, and
+//! [`TestClient`] sends requests to the full router, over in-memory state by default.
+//! Tests against PostgreSQL use `infra::storage::adapter::postgres::test_db::TestDb` instead.
+
+mod client;
+mod mocks;
+
+pub use client::{TestClient, TestResponse};
+pub use mocks::{MockUserRepository, MockUserService};
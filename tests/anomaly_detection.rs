@@ -0,0 +1,141 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rust_web_server_lib::application::flows::anomaly_detector::{AnomalyDetectionPolicy, MutationAnomalyDetector, StrictRateLimit, UserMutation, USER_MUTATION_RATE_ALERT};
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::ports::alert::{Alert, AlertPort};
+use rust_web_server_lib::application::ports::throttle::ThrottlePort;
+use rust_web_server_lib::domain::user::model::CreateUser;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct RecordingAlerts(Mutex<Vec<Alert>>);
+
+impl AlertPort for RecordingAlerts {
+    fn raise(&self, alert: Alert) {
+        self.0.lock().unwrap().push(alert);
+    }
+}
+
+impl RecordingAlerts {
+    fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+}
+
+#[derive(Default)]
+struct RecordingThrottle(Mutex<Vec<(u32, Duration)>>);
+
+impl ThrottlePort for RecordingThrottle {
+    fn tighten(&self, divisor: u32, duration: Duration) {
+        self.0.lock().unwrap().push((divisor, duration));
+    }
+}
+
+fn policy() -> AnomalyDetectionPolicy {
+    AnomalyDetectionPolicy {
+        window: WINDOW,
+        history: 10,
+        z_threshold: 3.0,
+        min_mutations: 10,
+        strict_rate_limit: Some(StrictRateLimit { divisor: 4, duration: Duration::from_secs(600) }),
+    }
+}
+
+fn detector(policy: AnomalyDetectionPolicy) -> (MutationAnomalyDetector, Arc<RecordingAlerts>, Arc<RecordingThrottle>) {
+    let alerts = Arc::new(RecordingAlerts::default());
+    let throttle = Arc::new(RecordingThrottle::default());
+    let detector = MutationAnomalyDetector::new(policy, alerts.clone()).unwrap().with_throttle(throttle.clone());
+    (detector, alerts, throttle)
+}
+
+/// Records `count` mutations in each of `windows` windows from `start`, returning the start of
+/// the next window.
+fn record_windows(detector: &MutationAnomalyDetector, mutation: UserMutation, start: Instant, windows: u32, count: u32) -> Instant {
+    for window in 0..windows {
+        for i in 0..count {
+            detector.record_at(mutation, start + WINDOW * window + Duration::from_millis(u64::from(i)));
+        }
+    }
+    start + WINDOW * windows
+}
+
+#[test]
+fn alerts_once_per_window_on_spikes() {
+    let (detector, alerts, throttle) = detector(policy());
+    let now = record_windows(&detector, UserMutation::Deletion, Instant::now(), 10, 5);
+
+    record_windows(&detector, UserMutation::Deletion, now, 1, 30);
+
+    assert_eq!(alerts.len(), 1);
+    let alert = alerts.0.lock().unwrap()[0].clone();
+    assert_eq!(alert.name, USER_MUTATION_RATE_ALERT);
+    assert!(alert.message.starts_with("10 user deletions in the current 60s window"), "{}", alert.message);
+    assert_eq!(*throttle.0.lock().unwrap(), vec![(4, Duration::from_secs(600))]);
+}
+
+#[test]
+fn ignores_steady_rates_and_small_spikes() {
+    let (detector, alerts, _) = detector(policy());
+    let now = record_windows(&detector, UserMutation::Creation, Instant::now(), 10, 5);
+    let now = record_windows(&detector, UserMutation::Creation, now, 5, 6);
+
+    // Anomalous, but below the minimum number of mutations
+    record_windows(&detector, UserMutation::Creation, now, 1, 9);
+
+    assert_eq!(alerts.len(), 0);
+}
+
+#[test]
+fn counts_quiet_windows_as_empty() {
+    let (detector, alerts, _) = detector(policy());
+    let now = record_windows(&detector, UserMutation::Creation, Instant::now(), 10, 20);
+
+    // After an hour without creations, the history is empty windows and the spike stands out
+    record_windows(&detector, UserMutation::Creation, now + WINDOW * 60, 1, 10);
+
+    assert_eq!(alerts.len(), 1);
+}
+
+#[test]
+fn waits_for_enough_history() {
+    let (detector, alerts, _) = detector(policy());
+    let now = record_windows(&detector, UserMutation::Creation, Instant::now(), 3, 1);
+
+    record_windows(&detector, UserMutation::Creation, now, 1, 50);
+
+    assert_eq!(alerts.len(), 0);
+}
+
+#[test]
+fn rejects_invalid_policies() {
+    let alerts = Arc::new(RecordingAlerts::default());
+    let invalid = [
+        AnomalyDetectionPolicy { window: Duration::ZERO, ..policy() },
+        AnomalyDetectionPolicy { history: 2, ..policy() },
+        AnomalyDetectionPolicy { z_threshold: 0.0, ..policy() },
+        AnomalyDetectionPolicy { strict_rate_limit: Some(StrictRateLimit { divisor: 0, duration: WINDOW }), ..policy() },
+    ];
+
+    for policy in invalid {
+        assert!(MutationAnomalyDetector::new(policy, alerts.clone()).is_err());
+    }
+}
+
+#[tokio::test]
+async fn user_service_records_creations() {
+    let (detector, alerts, _) = detector(AnomalyDetectionPolicy { history: 5, z_threshold: 2.0, min_mutations: 3, ..policy() });
+    let detector = Arc::new(detector);
+    // One creation per window in the past windows
+    record_windows(&detector, UserMutation::Creation, Instant::now() - WINDOW * 6, 5, 1);
+    let service = UserService::new(InMemoryUserRepository::new()).with_anomaly_detector(detector.clone());
+
+    for i in 0..3 {
+        let user = CreateUser::new("Jane Doe".to_string(), format!("jane{}@example.com", i), 30).unwrap();
+        service.create_user(user).await.unwrap();
+    }
+
+    assert_eq!(alerts.len(), 1);
+}
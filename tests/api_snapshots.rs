@@ -0,0 +1,630 @@
+//! Snapshot tests of the JSON returned by every endpoint.
+//!
+//! Dynamic fields (generated ids) are redacted so snapshots only change when the
+//! response envelope or serialization changes. Review changes with `cargo insta review`.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::extract::FromRef;
+use axum::http::{Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::auth_service::AuthService;
+use rust_web_server_lib::application::flows::consent_service::ConsentService;
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::{all_scopes, AuthError, Authentication, AuthenticatorPort, TokenPort};
+use rust_web_server_lib::application::ports::capability::DependencyStatus;
+use rust_web_server_lib::application::ports::health::{HealthCheckPort, HealthChecks};
+use rust_web_server_lib::domain::user::{error::{StorageError, UserDomainError}, model::{CreateUser, Email, ListUsers, PasswordHash, Role, UpdateUser, User, UserCredentials, UserFacets, UserFilter, UserId, UserPage}, repository::UserRepositoryPort};
+use rust_web_server_lib::infra::auth::{jwt::JwtTokens, JwtConfig};
+use rust_web_server_lib::infra::storage::adapter::in_memory::consent_repository::InMemoryConsentRepository;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::consent_handlers::ConsentState;
+use rust_web_server_lib::presentation::handlers::user_handlers::UserState;
+use rust_web_server_lib::presentation::http::{router, user_routes, AppState};
+use rust_web_server_lib::presentation::middleware::auth::AuthState;
+use rust_web_server_lib::presentation::pagination::CursorKey;
+
+/// Id of a user the failing repository is asked about.
+const ANY_ID: &str = "6f1c2b4e-8f7a-4c1d-9a3e-2b5d7c9e1f0a";
+
+/// Repository that fails every operation, used to snapshot server-side error responses.
+struct FailingUserRepository(fn() -> UserDomainError);
+
+#[async_trait]
+impl UserRepositoryPort for FailingUserRepository {
+    async fn create_user(&self, _user: CreateUser, _password_hash: Option<PasswordHash>) -> Result<User, UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn get_user(&self, _id: UserId) -> Result<User, UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn get_user_by_email(&self, _email: Email) -> Result<User, UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn get_user_credentials(&self, _email: Email) -> Result<UserCredentials, UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn list_users(&self, _query: ListUsers) -> Result<UserPage, UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn search_users(&self, _filter: UserFilter) -> Result<UserPage, UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn update_user(&self, _user: UpdateUser) -> Result<User, UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn delete_user(&self, _id: UserId) -> Result<(), UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn restore_user(&self, _id: UserId) -> Result<User, UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn hard_delete_user(&self, _id: UserId) -> Result<(), UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn set_legal_hold(&self, _id: UserId, _legal_hold: bool) -> Result<User, UserDomainError> {
+        Err((self.0)())
+    }
+
+    async fn set_role(&self, _id: UserId, _role: Role) -> Result<User, UserDomainError> {
+        Err((self.0)())
+    }
+}
+
+/// Health check always reporting the same status.
+struct StaticHealthCheck(DependencyStatus);
+
+#[async_trait]
+impl HealthCheckPort for StaticHealthCheck {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn check(&self) -> DependencyStatus {
+        self.0
+    }
+}
+
+/// Authenticator accepting a single email and password.
+struct StaticAuthenticator;
+
+#[async_trait]
+impl AuthenticatorPort for StaticAuthenticator {
+    async fn authenticate(&self, username: String, password: String) -> Result<Authentication, AuthError> {
+        if username == "jane@example.com" && password == "correct horse" {
+            Ok(Authentication { user_id: "user-1".to_string(), roles: Vec::new() })
+        } else {
+            Err(AuthError::InvalidCredentials)
+        }
+    }
+}
+
+/// State of the user routes alone: the user service and authentication.
+#[derive(Clone)]
+struct UserRoutesState {
+    users: UserState,
+    auth: AuthState,
+}
+
+impl FromRef<UserRoutesState> for UserState {
+    fn from_ref(state: &UserRoutesState) -> Self {
+        state.users.clone()
+    }
+}
+
+impl FromRef<UserRoutesState> for AuthState {
+    fn from_ref(state: &UserRoutesState) -> Self {
+        state.auth.clone()
+    }
+}
+
+fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "snapshot-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+fn auth_state() -> AuthState {
+    AuthState {
+        auth_service: Arc::new(AuthService::new(Arc::new(StaticAuthenticator), Arc::new(jwt_tokens()))),
+    }
+}
+
+/// A valid access token of the test user.
+fn token() -> String {
+    jwt_tokens().issue("user-1", &[], &all_scopes()).unwrap().token
+}
+
+fn in_memory_app() -> axum::Router {
+    let users = Arc::new(InMemoryUserRepository::new());
+    router(AppState {
+        auth: auth_state(),
+        consents: ConsentState {
+            consent_service: Arc::new(ConsentService::new(Arc::new(InMemoryConsentRepository::new()), users.clone())),
+        },
+        ..AppState::new(Arc::new(UserService::new(users)))
+    })
+}
+
+/// Only the user routes, with nothing but the user service and authentication as state.
+fn failing_app(error: fn() -> UserDomainError) -> axum::Router {
+    let state = UserRoutesState {
+        users: UserState {
+            user_service: Arc::new(UserService::new(Arc::new(FailingUserRepository(error)))),
+            cursor_key: CursorKey::random(),
+        },
+        auth: auth_state(),
+    };
+    axum::Router::new().nest("/api", user_routes().into_router(auth_state()).with_state(state))
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    send_as(app, None, method, uri, body).await
+}
+
+/// Sends a request authenticated with the bearer `token`, if any.
+async fn send_as(app: &axum::Router, token: Option<&str>, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json");
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    let request = request
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+
+    respond(app, request).await
+}
+
+/// Updates the user at `uri` as the bearer `token`, whatever its version.
+async fn update_as(app: &axum::Router, token: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(Method::PUT)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("authorization", format!("Bearer {}", token))
+        .header("if-match", "*")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    respond(app, request).await
+}
+
+async fn respond(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes).unwrap() };
+
+    (status, body)
+}
+
+async fn create(app: &axum::Router) -> String {
+    let (_, body) = send(app, Method::POST, "/api/users", Some(json!({"name": "Jane", "email": "jane@example.com", "age": 30}))).await;
+    body["data"]["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn create_user_success() {
+    let app = in_memory_app();
+
+    let (status, body) = send(&app, Method::POST, "/api/users", Some(json!({"name": "Jane", "email": "jane@example.com", "age": 30}))).await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    insta::assert_json_snapshot!(body, { ".data.id" => "[id]" });
+}
+
+#[tokio::test]
+async fn create_user_already_exists() {
+    let app = failing_app(|| UserDomainError::UserAlreadyExists);
+
+    let (status, body) = send(&app, Method::POST, "/api/users", Some(json!({"name": "Jane", "email": "jane@example.com", "age": 30}))).await;
+
+    assert_eq!(status, StatusCode::CONFLICT);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn create_user_failed() {
+    let app = failing_app(|| UserDomainError::UserCreationFailed(StorageError::msg("storage failed")));
+
+    let (status, body) = send(&app, Method::POST, "/api/users", Some(json!({"name": "Jane", "email": "jane@example.com", "age": 30}))).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn create_user_invalid() {
+    let app = in_memory_app();
+
+    let (status, body) = send(&app, Method::POST, "/api/users", Some(json!({"name": " ", "email": "jane.example.com", "age": 0}))).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn get_user_success() {
+    let app = in_memory_app();
+    let id = create(&app).await;
+
+    let (status, body) = send(&app, Method::GET, &format!("/api/users/{}", id), None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body, { ".data.id" => "[id]" });
+}
+
+#[tokio::test]
+async fn get_user_not_found() {
+    let app = in_memory_app();
+
+    let (status, body) = send(&app, Method::GET, "/api/users/missing", None).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn get_user_read_failed() {
+    let app = failing_app(|| UserDomainError::UserReadFailed(StorageError::msg("storage failed")));
+
+    let (status, body) = send(&app, Method::GET, &format!("/api/users/{}", ANY_ID), None).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn list_users_success() {
+    let app = in_memory_app();
+    for (name, age) in [("Carol", 41), ("alice", 25), ("Bob", 33)] {
+        let email = format!("{}@example.com", name.to_lowercase());
+        send(&app, Method::POST, "/api/users", Some(json!({"name": name, "email": email, "age": age}))).await;
+    }
+
+    let (status, body) = send(&app, Method::GET, "/api/users?limit=2&offset=1&sort_by=age&order=desc", None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body, { ".data.users[].id" => "[id]", ".data.next_cursor" => "[cursor]" });
+}
+
+#[tokio::test]
+async fn list_users_invalid_limit() {
+    let app = in_memory_app();
+
+    let (status, body) = send(&app, Method::GET, "/api/users?limit=0", None).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn search_users_success() {
+    let app = in_memory_app();
+    for (name, age) in [("Carol", 41), ("José", 25), ("Bob", 33)] {
+        let email = format!("{}@example.com", name.to_lowercase());
+        send(&app, Method::POST, "/api/users", Some(json!({"name": name, "email": email, "age": age}))).await;
+    }
+
+    let (status, body) = send(&app, Method::GET, "/api/users/search?name=O&min_age=30", None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body, { ".data.users[].id" => "[id]" });
+}
+
+#[tokio::test]
+async fn list_users_failed() {
+    let app = failing_app(|| UserDomainError::UserListFailed(StorageError::msg("storage failed")));
+
+    let (status, body) = send(&app, Method::GET, "/api/users", None).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn update_user_success() {
+    let app = in_memory_app();
+    let id = create(&app).await;
+
+    let (status, body) = update_as(&app, &token(), &format!("/api/users/{}", id), json!({"name": "Janet", "age": 31})).await;
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body, { ".data.id" => "[id]" });
+}
+
+#[tokio::test]
+async fn update_user_not_found() {
+    let app = in_memory_app();
+
+    let (status, body) = update_as(&app, &token(), "/api/users/missing", json!({"name": "Janet"})).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn update_user_invalid() {
+    let app = in_memory_app();
+    let id = create(&app).await;
+
+    let (status, body) = update_as(&app, &token(), &format!("/api/users/{}", id), json!({"email": "jane@"})).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn update_user_failed() {
+    let app = failing_app(|| UserDomainError::UserUpdateFailed(StorageError::msg("storage failed")));
+
+    let (status, body) = update_as(&app, &token(), &format!("/api/users/{}", ANY_ID), json!({"name": "Janet"})).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn delete_user_success() {
+    let app = in_memory_app();
+    let id = create(&app).await;
+
+    let (status, body) = send_as(&app, Some(&token()), Method::DELETE, &format!("/api/users/{}", id), None).await;
+
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(body, Value::Null);
+}
+
+#[tokio::test]
+async fn delete_user_not_found() {
+    let app = in_memory_app();
+
+    let (status, body) = send_as(&app, Some(&token()), Method::DELETE, "/api/users/missing", None).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn delete_user_failed() {
+    let app = failing_app(|| UserDomainError::UserDeletionFailed(StorageError::msg("storage failed")));
+
+    let (status, body) = send_as(&app, Some(&token()), Method::DELETE, &format!("/api/users/{}", ANY_ID), None).await;
+
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn update_user_unauthenticated() {
+    let app = in_memory_app();
+    let id = create(&app).await;
+
+    let (status, body) = send(&app, Method::PUT, &format!("/api/users/{}", id), Some(json!({"name": "Janet"}))).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn delete_user_invalid_token() {
+    let app = in_memory_app();
+    let id = create(&app).await;
+    let forged = JwtTokens::new(&JwtConfig { secret: "other-secret".to_string().into(), expiry_secs: 3600 }).issue("user-1", &[], &all_scopes()).unwrap().token;
+
+    let (status, body) = send_as(&app, Some(&forged), Method::DELETE, &format!("/api/users/{}", id), None).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn login_success() {
+    let app = in_memory_app();
+
+    let (status, body) = send(&app, Method::POST, "/api/auth/login", Some(json!({"email": "jane@example.com", "password": "correct horse"}))).await;
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body, { ".data.access_token" => "[token]" });
+}
+
+#[tokio::test]
+async fn login_invalid_credentials() {
+    let app = in_memory_app();
+
+    let (status, body) = send(&app, Method::POST, "/api/auth/login", Some(json!({"email": "jane@example.com", "password": "wrong"}))).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    insta::assert_json_snapshot!(body);
+}
+
+fn consent(action: &str) -> Value {
+    json!({"consent_type": "marketing_email", "action": action, "version": "2024-03", "source": "settings"})
+}
+
+#[tokio::test]
+async fn record_consent_success() {
+    let app = in_memory_app();
+    let id = create(&app).await;
+
+    let (status, body) = send_as(&app, Some(&token()), Method::POST, &format!("/api/users/{}/consents", id), Some(consent("granted"))).await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    insta::assert_json_snapshot!(body, { ".data.recorded_at" => "[timestamp]" });
+}
+
+#[tokio::test]
+async fn record_consent_invalid_version() {
+    let app = in_memory_app();
+    let id = create(&app).await;
+
+    let (status, body) = send_as(&app, Some(&token()), Method::POST, &format!("/api/users/{}/consents", id), Some(json!({"consent_type": "analytics", "action": "granted", "version": "", "source": "settings"}))).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn record_consent_user_not_found() {
+    let app = in_memory_app();
+
+    let (status, body) = send_as(&app, Some(&token()), Method::POST, "/api/users/missing/consents", Some(consent("granted"))).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn list_consents_success() {
+    let app = in_memory_app();
+    let id = create(&app).await;
+    send_as(&app, Some(&token()), Method::POST, &format!("/api/users/{}/consents", id), Some(consent("granted"))).await;
+    send_as(&app, Some(&token()), Method::POST, &format!("/api/users/{}/consents", id), Some(consent("withdrawn"))).await;
+
+    let (status, body) = send(&app, Method::GET, &format!("/api/users/{}/consents", id), None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body, { ".data[].recorded_at" => "[timestamp]" });
+}
+
+/// The app with admin routes enabled, returning it with a created user's id.
+async fn admin_app() -> (axum::Router, String) {
+    let app = router(AppState {
+        admin_token: Some("secret".into()),
+        auth: auth_state(),
+        ..AppState::new(Arc::new(UserService::new(Arc::new(InMemoryUserRepository::new()))))
+    });
+    let id = create(&app).await;
+    (app, id)
+}
+
+#[tokio::test]
+async fn set_legal_hold_success() {
+    let (app, id) = admin_app().await;
+
+    let (status, body) = send_as(&app, Some("secret"), Method::PUT, &format!("/api/admin/users/{}/legal-hold", id), Some(json!({"legal_hold": true}))).await;
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body, { ".data.id" => "[id]" });
+}
+
+#[tokio::test]
+async fn delete_user_under_legal_hold() {
+    let (app, id) = admin_app().await;
+    send_as(&app, Some("secret"), Method::PUT, &format!("/api/admin/users/{}/legal-hold", id), Some(json!({"legal_hold": true}))).await;
+
+    let (status, body) = send_as(&app, Some(&token()), Method::DELETE, &format!("/api/users/{}", id), None).await;
+
+    assert_eq!(status, StatusCode::LOCKED);
+    insta::assert_json_snapshot!(body);
+
+    send_as(&app, Some("secret"), Method::PUT, &format!("/api/admin/users/{}/legal-hold", id), Some(json!({"legal_hold": false}))).await;
+    let (status, _) = send_as(&app, Some(&token()), Method::DELETE, &format!("/api/users/{}", id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+/// Mints an impersonation token of the user `id` through the admin API.
+async fn impersonate(app: &axum::Router, id: &str) -> (StatusCode, Value) {
+    send_as(app, Some("secret"), Method::POST, "/api/admin/impersonations", Some(json!({"actor_id": "admin-1", "subject_id": id, "ttl_secs": 600}))).await
+}
+
+#[tokio::test]
+async fn create_impersonation_success() {
+    let (app, id) = admin_app().await;
+
+    let (status, body) = impersonate(&app, &id).await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    insta::assert_json_snapshot!(body, { ".data.access_token" => "[token]", ".data.subject_id" => "[id]" });
+}
+
+#[tokio::test]
+async fn delete_user_impersonated() {
+    let (app, id) = admin_app().await;
+    let (_, session) = impersonate(&app, &id).await;
+    let token = session["data"]["access_token"].as_str().unwrap();
+
+    let (status, _) = update_as(&app, token, &format!("/api/users/{}", id), json!({"name": "Janet"})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send_as(&app, Some(token), Method::DELETE, &format!("/api/users/{}", id), None).await;
+
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn get_dependencies_disabled() {
+    let app = router(AppState {
+        admin_token: Some("secret".into()),
+        ..AppState::new(Arc::new(UserService::new(Arc::new(InMemoryUserRepository::new()))))
+    });
+    let request = Request::builder()
+        .uri("/api/admin/dependencies")
+        .header("authorization", "Bearer secret")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn healthz() {
+    let app = in_memory_app();
+
+    let (status, body) = send(&app, Method::GET, "/healthz", None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn readyz_up() {
+    let app = router(AppState {
+        health_checks: HealthChecks::new(vec![Arc::new(StaticHealthCheck(DependencyStatus::Up))]),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    });
+
+    let (status, body) = send(&app, Method::GET, "/readyz", None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body);
+}
+
+#[tokio::test]
+async fn readyz_down() {
+    let app = router(AppState {
+        health_checks: HealthChecks::new(vec![Arc::new(StaticHealthCheck(DependencyStatus::Down))]),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    });
+
+    let (status, body) = send(&app, Method::GET, "/readyz", None).await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    insta::assert_json_snapshot!(body);
+}
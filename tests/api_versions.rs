@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, Request, Response, StatusCode};
+use axum::routing::get;
+use chrono::{TimeZone, Utc};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::versioning::{mount_versions, unversioned_route, ApiVersion, Deprecation, DEPRECATION, SUNSET};
+
+fn app() -> axum::Router {
+    router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))))
+}
+
+async fn send(app: &axum::Router, uri: &str) -> Response<Body> {
+    app.clone().oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap()
+}
+
+async fn body(response: Response<Body>) -> String {
+    String::from_utf8(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn serves_the_first_version_without_deprecation() {
+    let response = send(&app(), "/api/v1/users").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(DEPRECATION).is_none());
+    assert!(response.headers().get(header::LINK).is_none());
+}
+
+#[tokio::test]
+async fn deprecates_the_unversioned_routes_in_favor_of_the_first_version() {
+    let app = app();
+    let response = send(&app, "/api/users?limit=5").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[DEPRECATION], "@1792022400");
+    assert_eq!(response.headers()[header::LINK], "</api/v1/users>; rel=\"successor-version\"");
+    assert!(response.headers().get(SUNSET).is_none());
+    // Both serve the same users
+    assert_eq!(body(response).await, body(send(&app, "/api/v1/users?limit=5").await).await);
+}
+
+#[tokio::test]
+async fn mounts_versions_side_by_side() {
+    let deprecation = Deprecation::new(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(), "/api/v2").with_sunset(Utc.with_ymd_and_hms(2027, 1, 1, 0, 0, 0).unwrap());
+    let app = mount_versions(
+        axum::Router::new(),
+        [
+            ApiVersion::new("/api/v1", axum::Router::new().route("/greeting", get(|| async { "hello" }))).deprecated(deprecation),
+            ApiVersion::new("/api/v2", axum::Router::new().route("/greeting", get(|| async { "{\"greeting\":\"hello\"}" }))),
+        ],
+    );
+
+    let v1 = send(&app, "/api/v1/greeting").await;
+    assert_eq!(v1.headers()[DEPRECATION], "@1767225600");
+    assert_eq!(v1.headers()[SUNSET], "Fri, 01 Jan 2027 00:00:00 GMT");
+    assert_eq!(v1.headers()[header::LINK], "</api/v2/greeting>; rel=\"successor-version\"");
+    assert_eq!(body(v1).await, "hello");
+
+    let v2 = send(&app, "/api/v2/greeting").await;
+    assert!(v2.headers().get(DEPRECATION).is_none());
+    assert_eq!(body(v2).await, "{\"greeting\":\"hello\"}");
+}
+
+#[test]
+fn identifies_routes_alike_in_every_version() {
+    assert_eq!(unversioned_route("/api/v1/users/{id}"), "/api/users/{id}");
+    assert_eq!(unversioned_route("/api/v12"), "/api");
+    assert_eq!(unversioned_route("/api/users"), "/api/users");
+    assert_eq!(unversioned_route("/api/verify"), "/api/verify");
+    assert_eq!(unversioned_route("/healthz"), "/healthz");
+}
@@ -0,0 +1,288 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::{header, HeaderValue, Method, StatusCode};
+use serde_json::{json, Value};
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::ports::audit::{diff, AuditAction, AuditLogPort, FieldChange};
+use rust_web_server_lib::application::ports::auth::TokenPort;
+use rust_web_server_lib::domain::user::model::{CreateUser, Email, User, UserId};
+use rust_web_server_lib::infra::storage::adapter::in_memory::audit_log::InMemoryAuditLog;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::audit_handlers::AuditState;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::admin::ADMIN_TOKEN_ACTOR;
+
+use common::{auth_state, jwt_tokens, request, scoped_token, send, send_request, token, ADMIN_TOKEN};
+
+fn john_doe() -> User {
+    User::new(UserId::generate(), "John Doe".to_string(), Email::parse("jdoe@example.com").unwrap(), 42)
+}
+
+fn jdoe() -> CreateUser {
+    CreateUser::new("John Doe".to_string(), "jdoe@example.com".to_string(), 42).unwrap()
+}
+
+/// Returns an app recording the changes of the users in `audit_log`, with the admin routes.
+fn app(audit_log: Arc<InMemoryAuditLog>) -> axum::Router {
+    let user_service = UserService::new(InMemoryUserRepository::new()).with_audit_log(audit_log.clone());
+    router(AppState {
+        admin_token: Some(ADMIN_TOKEN.into()),
+        auth: auth_state(),
+        audit: Some(AuditState { audit_log }),
+        ..AppState::new(Arc::new(user_service))
+    })
+}
+
+/// Updates the user at `uri` whatever its version, returning the status and the body of the
+/// response.
+async fn update(app: &axum::Router, token: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+    let mut request = request(Method::PUT, uri, Some(token), Some(body));
+    request.headers_mut().insert(header::IF_MATCH, HeaderValue::from_static("*"));
+    send_request(app, request).await
+}
+
+#[tokio::test]
+async fn records_the_changes_of_users_with_their_actor() {
+    let app = app(Arc::new(InMemoryAuditLog::new()));
+    let (_, body) = send(&app, Method::POST, "/api/v1/users", None, Some(json!({ "name": "John Doe", "email": "jdoe@example.com", "age": 42 }))).await;
+    let user = format!("/api/v1/users/{}", body["data"]["id"].as_str().unwrap());
+
+    let (status, _) = update(&app, &token("admin-1"), &user, json!({ "age": 43 })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(send(&app, Method::DELETE, &user, Some(&token("admin-2")), None).await.0, StatusCode::NO_CONTENT);
+
+    let (status, body) = send(&app, Method::GET, &format!("{}/audit", user), Some(&token("auditor")), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let entries = body["data"]["entries"].as_array().unwrap();
+    let summary: Vec<_> = entries.iter().map(|entry| (entry["action"].clone(), entry["actor"].clone())).collect();
+    // Signups are not made by authenticated users
+    assert_eq!(summary, [(json!("created"), Value::Null), (json!("updated"), json!("admin-1")), (json!("deleted"), json!("admin-2"))]);
+    assert_eq!(entries[1]["changes"], json!([{ "field": "age", "before": 42, "after": 43 }]));
+    assert_eq!(entries[0]["changes"][0], json!({ "field": "name", "before": null, "after": "John Doe" }));
+    assert_eq!(entries[2]["changes"].as_array().unwrap().len(), 5);
+    // Passwords are never recorded
+    assert!(!entries.iter().any(|entry| entry.to_string().contains("password")));
+}
+
+#[tokio::test]
+async fn records_the_user_impersonated_by_the_actor() {
+    let app = app(Arc::new(InMemoryAuditLog::new()));
+    let (_, body) = send(&app, Method::POST, "/api/v1/users", None, Some(json!({ "name": "John Doe", "email": "jdoe@example.com", "age": 42 }))).await;
+    let id = body["data"]["id"].as_str().unwrap();
+    let impersonation = jwt_tokens().issue_impersonation("admin-1", id, Duration::from_secs(600)).unwrap().token;
+
+    let user = format!("/api/v1/users/{}", id);
+    assert_eq!(update(&app, &impersonation, &user, json!({ "age": 43 })).await.0, StatusCode::OK);
+    assert_eq!(update(&app, &token(id), &user, json!({ "age": 44 })).await.0, StatusCode::OK);
+
+    let (_, body) = send(&app, Method::GET, &format!("{}/audit", user), Some(&token("auditor")), None).await;
+    let entries = body["data"]["entries"].as_array().unwrap();
+    let identities: Vec<_> = entries.iter().map(|entry| (entry["actor"].clone(), entry["impersonated_user_id"].clone())).collect();
+    assert_eq!(identities, [(Value::Null, Value::Null), (json!("admin-1"), json!(id)), (json!(id), Value::Null)]);
+}
+
+#[tokio::test]
+async fn records_the_changes_made_with_the_admin_token() {
+    let app = app(Arc::new(InMemoryAuditLog::new()));
+    let (_, body) = send(&app, Method::POST, "/api/v1/users", None, Some(json!({ "name": "John Doe", "email": "jdoe@example.com", "age": 42 }))).await;
+    let id = body["data"]["id"].as_str().unwrap();
+
+    let admin = format!("/api/v1/admin/users/{}", id);
+    let (status, _) = send(&app, Method::PUT, &format!("{}/role", admin), Some(ADMIN_TOKEN), Some(json!({ "role": "admin" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, Method::PUT, &format!("{}/legal-hold", admin), Some(ADMIN_TOKEN), Some(json!({ "legal_hold": true }))).await;
+    assert_eq!(status, StatusCode::OK);
+    send(&app, Method::PUT, &format!("{}/legal-hold", admin), Some(ADMIN_TOKEN), Some(json!({ "legal_hold": false }))).await;
+    assert_eq!(send(&app, Method::DELETE, &admin, Some(ADMIN_TOKEN), None).await.0, StatusCode::NO_CONTENT);
+
+    let (_, body) = send(&app, Method::GET, &format!("/api/v1/users/{}/audit", id), Some(&token("auditor")), None).await;
+    let entries = body["data"]["entries"].as_array().unwrap();
+    let summary: Vec<_> = entries.iter().skip(1).map(|entry| (entry["action"].clone(), entry["actor"].clone())).collect();
+    let updated = (json!("updated"), json!(ADMIN_TOKEN_ACTOR));
+    assert_eq!(summary, [updated.clone(), updated.clone(), updated, (json!("hard_deleted"), json!(ADMIN_TOKEN_ACTOR))]);
+}
+
+#[tokio::test]
+async fn requires_the_users_write_scope() {
+    let app = app(Arc::new(InMemoryAuditLog::new()));
+    let uri = format!("/api/v1/users/{}/audit", UserId::generate());
+
+    assert_eq!(send(&app, Method::GET, &uri, None, None).await.0, StatusCode::UNAUTHORIZED);
+    let unscoped = scoped_token("jdoe", &[]);
+    assert_eq!(send(&app, Method::GET, &uri, Some(&unscoped), None).await.0, StatusCode::FORBIDDEN);
+    let (status, body) = send(&app, Method::GET, &uri, Some(&token("auditor")), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["entries"], json!([]));
+}
+
+#[tokio::test]
+async fn keeps_the_history_of_users_deleted_for_good() {
+    let audit_log = Arc::new(InMemoryAuditLog::new());
+    let user_service = UserService::new(InMemoryUserRepository::new()).with_audit_log(audit_log.clone());
+    let user = user_service.create_user(jdoe()).await.unwrap();
+    user_service.delete_user(user.id()).await.unwrap();
+    user_service.hard_delete_user(user.id()).await.unwrap();
+
+    let history = audit_log.history(user.id()).await.unwrap();
+    let actions: Vec<_> = history.iter().map(|entry| entry.action).collect();
+    assert_eq!(actions, [AuditAction::Created, AuditAction::Deleted, AuditAction::HardDeleted]);
+    // Outside of requests, changes have no actor
+    assert!(history.iter().all(|entry| entry.actor.is_none()));
+}
+
+#[test]
+fn diffs_only_the_changed_fields() {
+    let user = john_doe();
+    let updated = User::new(user.id(), "John Doe".to_string(), user.email().clone(), 43).with_legal_hold(true);
+
+    assert_eq!(
+        diff(Some(&user), Some(&updated)),
+        [
+            FieldChange { field: "age".to_string(), before: Some(json!(42)), after: Some(json!(43)) },
+            FieldChange { field: "legal_hold".to_string(), before: Some(json!(false)), after: Some(json!(true)) },
+        ]
+    );
+    assert!(diff(Some(&user), Some(&user)).is_empty());
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::application::ports::audit::AuditEntry;
+    use rust_web_server_lib::application::ports::unit_of_work::{IsolationLevel, UnitOfWorkPort};
+    use rust_web_server_lib::infra::storage::adapter::postgres::audit_log::{ChainVerification, PostgresAuditLog};
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::unit_of_work::PostgresUnitOfWork;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stores_the_entries_of_each_user_in_order() {
+        let db = TestDb::new().await.unwrap();
+        let audit_log = PostgresAuditLog::new(db.db());
+        let user = john_doe();
+        let other = UserId::generate();
+
+        audit_log.record(AuditEntry::new(user.id(), AuditAction::Created, None, Some(&user))).await.unwrap();
+        audit_log.record(AuditEntry::new(other, AuditAction::Deleted, Some(&user), None)).await.unwrap();
+        let impersonated = AuditEntry { actor: Some("admin-1".to_string()), impersonated_user_id: Some(user.id().to_string()), ..AuditEntry::new(user.id(), AuditAction::Deleted, Some(&user), None) };
+        audit_log.record(impersonated.clone()).await.unwrap();
+
+        let history = audit_log.history(user.id()).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!((history[0].action, history[0].actor.as_deref()), (AuditAction::Created, None));
+        assert_eq!((history[1].action, history[1].actor.as_deref()), (AuditAction::Deleted, Some("admin-1")));
+        assert_eq!(history[1].impersonated_user_id, impersonated.impersonated_user_id);
+        assert_eq!(history[0].changes, diff(None, Some(&user)));
+        assert!(audit_log.history(UserId::generate()).await.unwrap().is_empty());
+    }
+
+    /// Returns the id of the `n`th entry of the log, from 0.
+    async fn entry_id(db: &TestDb, n: i64) -> i64 {
+        sqlx::query_scalar("SELECT id FROM audit_log ORDER BY id OFFSET $1 LIMIT 1").bind(n).fetch_one(&*db.db()).await.unwrap()
+    }
+
+    /// Returns a log of three entries of John Doe.
+    async fn chained_log(db: &TestDb) -> PostgresAuditLog {
+        let audit_log = PostgresAuditLog::new(db.db());
+        let user = john_doe();
+        let older = User::new(user.id(), "John Doe".to_string(), user.email().clone(), 41);
+        audit_log.record(AuditEntry::new(user.id(), AuditAction::Created, None, Some(&older))).await.unwrap();
+        audit_log.record(AuditEntry::new(user.id(), AuditAction::Updated, Some(&older), Some(&user))).await.unwrap();
+        audit_log.record(AuditEntry::new(user.id(), AuditAction::Deleted, Some(&user), None)).await.unwrap();
+        audit_log
+    }
+
+    #[tokio::test]
+    async fn chains_the_entries_of_every_user() {
+        let db = TestDb::new().await.unwrap();
+        let audit_log = chained_log(&db).await;
+
+        let ChainVerification::Intact { entries, unchained, last_hash } = audit_log.verify_chain().await.unwrap() else {
+            panic!("the chain of an untouched log is broken");
+        };
+        assert_eq!((entries, unchained), (3, 0));
+        let last: Option<String> = sqlx::query_scalar("SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1").fetch_one(&*db.db()).await.unwrap();
+        assert_eq!(last_hash, last);
+    }
+
+    #[tokio::test]
+    async fn detects_tampered_entries() {
+        let db = TestDb::new().await.unwrap();
+        let audit_log = chained_log(&db).await;
+        let updated = entry_id(&db, 1).await;
+
+        // The age before the update is rewritten, as if the user had always been 42
+        sqlx::query("UPDATE audit_log SET changes = jsonb_set(changes, '{0,before}', '42') WHERE id = $1").bind(updated).execute(&*db.db()).await.unwrap();
+
+        assert_eq!(audit_log.verify_chain().await.unwrap(), ChainVerification::Broken { id: updated });
+    }
+
+    #[tokio::test]
+    async fn detects_removed_entries() {
+        let db = TestDb::new().await.unwrap();
+        let audit_log = chained_log(&db).await;
+        let (updated, deleted) = (entry_id(&db, 1).await, entry_id(&db, 2).await);
+
+        sqlx::query("DELETE FROM audit_log WHERE id = $1").bind(updated).execute(&*db.db()).await.unwrap();
+
+        assert_eq!(audit_log.verify_chain().await.unwrap(), ChainVerification::Broken { id: deleted });
+    }
+
+    #[tokio::test]
+    async fn detects_entries_rehashed_out_of_the_chain() {
+        let db = TestDb::new().await.unwrap();
+        let audit_log = chained_log(&db).await;
+        let created = entry_id(&db, 0).await;
+
+        // Entries predating the chain come first only
+        sqlx::query("UPDATE audit_log SET prev_hash = NULL, hash = NULL WHERE id > $1").bind(created).execute(&*db.db()).await.unwrap();
+
+        assert_eq!(audit_log.verify_chain().await.unwrap(), ChainVerification::Broken { id: entry_id(&db, 1).await });
+    }
+
+    #[tokio::test]
+    async fn chains_concurrent_appends_one_after_the_other() {
+        let db = TestDb::new().await.unwrap();
+        let audit_log = Arc::new(PostgresAuditLog::new(db.db()));
+
+        let appends: Vec<_> = (0..16)
+            .map(|_| {
+                let audit_log = audit_log.clone();
+                tokio::spawn(async move { audit_log.record(AuditEntry::new(UserId::generate(), AuditAction::Created, None, Some(&john_doe()))).await })
+            })
+            .collect();
+        for append in appends {
+            append.await.unwrap().unwrap();
+        }
+
+        assert!(matches!(audit_log.verify_chain().await.unwrap(), ChainVerification::Intact { entries: 16, .. }));
+    }
+
+    #[tokio::test]
+    async fn refuses_appends_of_serializable_transactions_behind_the_head_of_the_chain() {
+        let db = TestDb::new().await.unwrap();
+        let audit_log = PostgresAuditLog::new(db.db());
+        let user = john_doe();
+        let entry = || AuditEntry::new(user.id(), AuditAction::Deleted, Some(&user), None);
+
+        let transaction = PostgresUnitOfWork::new(db.db()).begin(IsolationLevel::Serializable).await.unwrap();
+        // The snapshot of the transaction is taken by its first statement
+        transaction.audit_log().history(user.id()).await.unwrap();
+        audit_log.record(entry()).await.unwrap();
+
+        // The entry would follow a stale head, forking the chain
+        assert!(transaction.audit_log().record(entry()).await.is_err());
+        drop(transaction);
+        assert!(matches!(audit_log.verify_chain().await.unwrap(), ChainVerification::Intact { entries: 1, .. }));
+
+        let transaction = PostgresUnitOfWork::new(db.db()).begin(IsolationLevel::Serializable).await.unwrap();
+        transaction.audit_log().record(entry()).await.unwrap();
+        transaction.commit().await.unwrap();
+        assert!(matches!(audit_log.verify_chain().await.unwrap(), ChainVerification::Intact { entries: 2, .. }));
+    }
+}
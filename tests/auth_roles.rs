@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use rust_web_server_lib::application::ports::auth::{all_scopes, TokenPort};
+use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+use rust_web_server_lib::infra::auth::{JwtConfig, LdapConfig};
+
+fn ldap_config() -> LdapConfig {
+    LdapConfig {
+        url: "ldap://127.0.0.1:1".to_string(),
+        bind_dn: None,
+        bind_password: None,
+        user_base_dn: "ou=people,dc=example,dc=com".to_string(),
+        user_filter: "(mail={username})".to_string(),
+        user_id_attribute: "uid".to_string(),
+        group_attribute: "memberOf".to_string(),
+        group_roles: vec![
+            ("cn=admins,ou=groups,dc=example,dc=com".to_string(), "admin".to_string()),
+            ("cn=staff,ou=groups,dc=example,dc=com".to_string(), "user".to_string()),
+            ("cn=contractors,ou=groups,dc=example,dc=com".to_string(), "user".to_string()),
+        ],
+        pool_size: 1,
+        timeout_secs: 1,
+    }
+}
+
+fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "roles-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+#[test]
+fn maps_groups_to_roles() {
+    let groups = vec![
+        "CN=Staff, OU=Groups, DC=example, DC=com".to_string(),
+        "cn=contractors,ou=groups,dc=example,dc=com".to_string(),
+        "cn=admins,ou=groups,dc=other,dc=com".to_string(),
+    ];
+
+    assert_eq!(ldap_config().roles_for_groups(&groups), vec!["user".to_string()]);
+    assert!(ldap_config().roles_for_groups(&[]).is_empty());
+}
+
+#[test]
+fn tokens_carry_roles() {
+    let tokens = jwt_tokens();
+    let token = tokens.issue("jdoe", &["admin".to_string()], &all_scopes()).unwrap().token;
+
+    let principal = tokens.verify(&token).unwrap();
+
+    assert_eq!(principal.user_id, "jdoe");
+    assert_eq!(principal.roles, vec!["admin".to_string()]);
+}
+
+#[test]
+fn impersonation_tokens_grant_no_roles() {
+    let tokens = jwt_tokens();
+    let token = tokens.issue_impersonation("admin-1", "jdoe", Duration::from_secs(60)).unwrap().token;
+
+    assert!(tokens.verify(&token).unwrap().roles.is_empty());
+}
+
+#[cfg(feature = "ldap")]
+mod ldap {
+    use rust_web_server_lib::application::ports::auth::{AuthError, AuthenticatorPort};
+    use rust_web_server_lib::infra::auth::ldap::LdapAuthenticator;
+
+    use super::ldap_config;
+
+    #[tokio::test]
+    async fn rejects_empty_password_without_binding() {
+        let authenticator = LdapAuthenticator::new(ldap_config());
+
+        let result = authenticator.authenticate("jane@example.com".to_string(), String::new()).await;
+
+        assert_eq!(result, Err(AuthError::InvalidCredentials));
+    }
+
+    #[tokio::test]
+    async fn unreachable_server_is_unavailable() {
+        let authenticator = LdapAuthenticator::new(ldap_config());
+
+        let result = authenticator.authenticate("jane@example.com".to_string(), "secret".to_string()).await;
+
+        assert_eq!(result, Err(AuthError::Unavailable));
+    }
+}
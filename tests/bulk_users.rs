@@ -0,0 +1,127 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::CONSENTS_WRITE_SCOPE;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::user_handlers::MAX_BULK_USERS;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+use common::{auth_state, scoped_token, send, token};
+
+fn app() -> axum::Router {
+    router(AppState {
+        auth: auth_state(),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+/// Sends `users` to the bulk creation route, returning the status and the body of the response.
+async fn create_bulk(app: &axum::Router, token: Option<&str>, users: Value) -> (StatusCode, Value) {
+    send(app, Method::POST, "/api/users/bulk", token, Some(users)).await
+}
+
+#[tokio::test]
+async fn creates_the_valid_users_and_reports_the_invalid_ones() {
+    let app = app();
+    let users = json!([
+        { "name": "Alice", "email": "alice@example.com", "age": 30 },
+        { "name": "", "email": "not-an-email", "age": 30 },
+        { "name": "Bob", "email": "bob@example.com", "age": 40 },
+    ]);
+
+    let (status, body) = create_bulk(&app, Some(&token("user-1")), users).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["created"], 2);
+    assert_eq!(body["data"]["failed"], 1);
+    let results = body["data"]["results"].as_array().unwrap();
+    assert_eq!(results.iter().map(|result| result["index"].clone()).collect::<Vec<_>>(), vec![json!(0), json!(1), json!(2)]);
+
+    assert_eq!(results[0]["status_code"], 201);
+    assert_eq!(results[0]["user"]["email"], "alice@example.com");
+    assert!(results[0].get("error").is_none());
+    assert_eq!(results[1]["status_code"], 400);
+    assert!(results[1].get("user").is_none());
+    assert_eq!(results[1]["error"]["message"], "Invalid user");
+    let fields: Vec<&str> = results[1]["error"]["errors"].as_array().unwrap().iter().map(|error| error["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, vec!["name", "email"]);
+    assert_eq!(results[2]["status_code"], 201);
+
+    let id = results[2]["user"]["id"].as_str().unwrap();
+    assert_eq!(send(&app, Method::GET, &format!("/api/users/{}", id), None, None).await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn reports_no_results_for_no_users() {
+    let (status, body) = create_bulk(&app(), Some(&token("user-1")), json!([])).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!({ "created": 0, "failed": 0, "results": [] }));
+}
+
+#[tokio::test]
+async fn rejects_too_many_users() {
+    let users: Vec<Value> = (0..=MAX_BULK_USERS).map(|i| json!({ "name": "User", "email": format!("user{}@example.com", i), "age": 30 })).collect();
+
+    let (status, _) = create_bulk(&app(), Some(&token("user-1")), Value::Array(users)).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn requires_the_users_write_scope() {
+    let users = json!([{ "name": "Alice", "email": "alice@example.com", "age": 30 }]);
+
+    assert_eq!(create_bulk(&app(), None, users.clone()).await.0, StatusCode::UNAUTHORIZED);
+    let consents_only = scoped_token("user-1", &[CONSENTS_WRITE_SCOPE]);
+    assert_eq!(create_bulk(&app(), Some(&consents_only), users).await.0, StatusCode::FORBIDDEN);
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use std::sync::Arc;
+
+    use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+    use rust_web_server_lib::domain::user::model::CreateUser;
+    use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::unit_of_work::PostgresUnitOfWork;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+
+    fn users(count: usize) -> Vec<CreateUser> {
+        (0..count).map(|i| CreateUser::new(format!("User {}", i), format!("user{}@example.com", i), 30).unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn inserts_the_users_in_batches_in_order() {
+        let db = TestDb::new().await.unwrap();
+        let repository = UserRepository::new(db.db());
+
+        let created = repository.create_users(users(1200).into_iter().map(|user| (user, None)).collect()).await;
+
+        assert_eq!(created.len(), 1200);
+        for (i, user) in created.iter().enumerate() {
+            let user = user.as_ref().unwrap();
+            assert_eq!(user.email().as_str(), format!("user{}@example.com", i));
+            assert_eq!(repository.get_user(user.id()).await.unwrap(), *user);
+        }
+    }
+
+    #[tokio::test]
+    async fn records_the_event_of_every_user_in_the_transaction() {
+        let db = TestDb::new().await.unwrap();
+        let service = UserService::new(UserRepository::new(db.db())).with_unit_of_work(Arc::new(PostgresUnitOfWork::new(db.db())));
+
+        let created = service.create_users_bulk(users(3)).await;
+
+        assert!(created.iter().all(Result::is_ok));
+        let events: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM event_outbox").fetch_one(&*db.db()).await.unwrap();
+        assert_eq!(events, 3);
+    }
+}
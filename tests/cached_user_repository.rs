@@ -0,0 +1,251 @@
+//! Tests of the caching of users by `CachedUserRepository`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use rust_web_server_lib::application::ports::audit::{AuditLogPort, DisabledAuditLog};
+use rust_web_server_lib::application::ports::cache::{CachePort, DisabledCache};
+use rust_web_server_lib::application::ports::capability::Capability;
+use rust_web_server_lib::application::ports::events::{DisabledEventPublisher, EventPublisherPort};
+use rust_web_server_lib::application::ports::unit_of_work::{IsolationLevel, TransactionPort, UnitOfWorkPort};
+use rust_web_server_lib::domain::user::error::UserDomainError;
+use rust_web_server_lib::domain::user::model::{CreateUser, UpdateUser, User};
+use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::infra::storage::cached_user_repository::{CachedUnitOfWork, CachedUserRepository};
+
+const TTL: Duration = Duration::from_secs(300);
+
+/// Cache keeping values in memory with their TTL, failing every call while `unavailable` is set.
+#[derive(Default)]
+struct MemoryCache {
+    values: Mutex<HashMap<String, (Vec<u8>, Duration)>>,
+    unavailable: AtomicBool,
+}
+
+impl MemoryCache {
+    fn check(&self) -> eyre::Result<()> {
+        if self.unavailable.load(Ordering::SeqCst) {
+            eyre::bail!("cache unavailable");
+        }
+        Ok(())
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.values.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+impl Capability for MemoryCache {
+    fn name(&self) -> &'static str {
+        "cache"
+    }
+}
+
+#[async_trait]
+impl CachePort for MemoryCache {
+    async fn get(&self, key: &str) -> eyre::Result<Option<Vec<u8>>> {
+        self.check()?;
+        Ok(self.values.lock().unwrap().get(key).map(|(value, _)| value.clone()))
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> eyre::Result<()> {
+        self.check()?;
+        self.values.lock().unwrap().insert(key.to_string(), (value, ttl));
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> eyre::Result<()> {
+        self.check()?;
+        self.values.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
+
+struct Fixture {
+    database: Arc<InMemoryUserRepository>,
+    cache: Arc<MemoryCache>,
+    repository: CachedUserRepository<Arc<InMemoryUserRepository>>,
+    user: User,
+}
+
+async fn fixture() -> Fixture {
+    let database = Arc::new(InMemoryUserRepository::new());
+    let cache = Arc::new(MemoryCache::default());
+    let repository = CachedUserRepository::new(database.clone(), cache.clone(), TTL);
+    let user = repository
+        .create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap(), None)
+        .await
+        .unwrap();
+    Fixture { database, cache, repository, user }
+}
+
+/// Renames the user in the database, behind the back of the cache.
+async fn rename_in_database(fixture: &Fixture, name: &str) {
+    let update = UpdateUser::new(fixture.user.id(), Some(name.to_string()), None, None).unwrap();
+    fixture.database.update_user(update).await.unwrap();
+}
+
+#[tokio::test]
+async fn serves_users_from_the_cache() {
+    let fixture = fixture().await;
+
+    assert_eq!(fixture.repository.get_user(fixture.user.id()).await.unwrap(), fixture.user);
+    rename_in_database(&fixture, "Janet").await;
+
+    assert_eq!(fixture.repository.get_user(fixture.user.id()).await.unwrap(), fixture.user);
+    let values = fixture.cache.values.lock().unwrap();
+    assert_eq!(values[&format!("user:{}", fixture.user.id())].1, TTL);
+}
+
+#[tokio::test]
+async fn keeps_every_attribute_of_cached_users() {
+    let fixture = fixture().await;
+    let held = fixture.repository.set_legal_hold(fixture.user.id(), true).await.unwrap();
+
+    fixture.repository.get_user(fixture.user.id()).await.unwrap();
+    let cached = fixture.repository.get_user(fixture.user.id()).await.unwrap();
+
+    assert_eq!(cached, held);
+    assert!(cached.legal_hold());
+}
+
+#[tokio::test]
+async fn evicts_updated_users() {
+    let fixture = fixture().await;
+    fixture.repository.get_user(fixture.user.id()).await.unwrap();
+
+    let update = UpdateUser::new(fixture.user.id(), Some("Janet".to_string()), None, None).unwrap();
+    fixture.repository.update_user(update).await.unwrap();
+
+    assert_eq!(fixture.repository.get_user(fixture.user.id()).await.unwrap().name(), "Janet");
+}
+
+#[tokio::test]
+async fn evicts_deleted_users() {
+    let fixture = fixture().await;
+    fixture.repository.get_user(fixture.user.id()).await.unwrap();
+
+    fixture.repository.delete_user(fixture.user.id()).await.unwrap();
+
+    assert!(fixture.cache.keys().is_empty());
+    assert_eq!(fixture.repository.get_user(fixture.user.id()).await, Err(UserDomainError::UserNotFound));
+}
+
+#[tokio::test]
+async fn evicts_users_when_their_legal_hold_changes() {
+    let fixture = fixture().await;
+    fixture.repository.get_user(fixture.user.id()).await.unwrap();
+
+    fixture.repository.set_legal_hold(fixture.user.id(), true).await.unwrap();
+
+    assert!(fixture.repository.get_user(fixture.user.id()).await.unwrap().legal_hold());
+}
+
+#[tokio::test]
+async fn does_not_cache_missing_users() {
+    let fixture = fixture().await;
+    fixture.repository.delete_user(fixture.user.id()).await.unwrap();
+
+    assert_eq!(fixture.repository.get_user(fixture.user.id()).await, Err(UserDomainError::UserNotFound));
+    assert!(fixture.cache.keys().is_empty());
+}
+
+#[tokio::test]
+async fn falls_back_to_the_database_when_the_cache_is_unavailable() {
+    let fixture = fixture().await;
+    fixture.cache.unavailable.store(true, Ordering::SeqCst);
+
+    assert_eq!(fixture.repository.get_user(fixture.user.id()).await.unwrap(), fixture.user);
+    rename_in_database(&fixture, "Janet").await;
+    assert_eq!(fixture.repository.get_user(fixture.user.id()).await.unwrap().name(), "Janet");
+    let update = UpdateUser::new(fixture.user.id(), Some("Jo".to_string()), None, None).unwrap();
+    assert_eq!(fixture.repository.update_user(update).await.unwrap().name(), "Jo");
+}
+
+#[tokio::test]
+async fn discards_undecodable_entries() {
+    let fixture = fixture().await;
+    let key = format!("user:{}", fixture.user.id());
+    fixture.cache.set(&key, b"not a user".to_vec(), TTL).await.unwrap();
+
+    assert_eq!(fixture.repository.get_user(fixture.user.id()).await.unwrap(), fixture.user);
+    assert_ne!(fixture.cache.get(&key).await.unwrap().unwrap(), b"not a user");
+}
+
+#[tokio::test]
+async fn reads_the_database_with_a_disabled_cache() {
+    let database = Arc::new(InMemoryUserRepository::new());
+    let repository = CachedUserRepository::new(database.clone(), Arc::new(DisabledCache), Duration::ZERO);
+    let user = repository
+        .create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap(), None)
+        .await
+        .unwrap();
+
+    repository.get_user(user.id()).await.unwrap();
+    let update = UpdateUser::new(user.id(), Some("Janet".to_string()), None, None).unwrap();
+    database.update_user(update).await.unwrap();
+
+    assert_eq!(repository.get_user(user.id()).await.unwrap().name(), "Janet");
+}
+
+/// Unit of work changing users of the database directly, committing nothing.
+struct DirectUnitOfWork(Arc<InMemoryUserRepository>);
+
+#[async_trait]
+impl UnitOfWorkPort for DirectUnitOfWork {
+    async fn begin(&self, _isolation: IsolationLevel) -> eyre::Result<Box<dyn TransactionPort + Send + Sync>> {
+        Ok(Box::new(DirectUnitOfWork(self.0.clone())))
+    }
+}
+
+#[async_trait]
+impl TransactionPort for DirectUnitOfWork {
+    fn users(&self) -> &(dyn UserRepositoryPort + Send + Sync) {
+        self.0.as_ref()
+    }
+
+    fn events(&self) -> &(dyn EventPublisherPort + Send + Sync) {
+        &DisabledEventPublisher
+    }
+
+    fn audit_log(&self) -> &(dyn AuditLogPort + Send + Sync) {
+        &DisabledAuditLog
+    }
+
+    async fn commit(self: Box<Self>) -> eyre::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn evicts_users_changed_in_transactions_once_committed() {
+    let fixture = fixture().await;
+    let unit_of_work = CachedUnitOfWork::new(DirectUnitOfWork(fixture.database.clone()), fixture.cache.clone());
+    fixture.repository.get_user(fixture.user.id()).await.unwrap();
+
+    let transaction = unit_of_work.begin(IsolationLevel::ReadCommitted).await.unwrap();
+    let update = UpdateUser::new(fixture.user.id(), Some("Janet".to_string()), None, None).unwrap();
+    transaction.users().update_user(update).await.unwrap();
+    assert_eq!(fixture.cache.keys().len(), 1);
+    transaction.commit().await.unwrap();
+
+    assert!(fixture.cache.keys().is_empty());
+    assert_eq!(fixture.repository.get_user(fixture.user.id()).await.unwrap().name(), "Janet");
+}
+
+#[tokio::test]
+async fn does_not_cache_users_read_in_transactions() {
+    let fixture = fixture().await;
+    let unit_of_work = CachedUnitOfWork::new(DirectUnitOfWork(fixture.database.clone()), fixture.cache.clone());
+
+    let transaction = unit_of_work.begin(IsolationLevel::ReadCommitted).await.unwrap();
+    transaction.users().get_user(fixture.user.id()).await.unwrap();
+    drop(transaction);
+
+    assert!(fixture.cache.keys().is_empty());
+}
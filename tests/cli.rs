@@ -0,0 +1,105 @@
+use std::process::{Command, Output};
+
+fn cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rustweb-cli")).args(args).output().unwrap()
+}
+
+#[test]
+fn prints_the_usage_without_a_command() {
+    let output = cli(&[]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Usage: rustweb-cli"));
+}
+
+#[test]
+fn rejects_invalid_arguments_before_connecting() {
+    for args in [&["users", "get", "not-an-id"][..], &["users", "list", "--limit", "0"], &["users", "create", "--name", "Ada"]] {
+        assert_eq!(cli(args).status.code(), Some(2), "{:?}", args);
+    }
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use std::env;
+    use std::io::Write;
+    use std::process::Stdio;
+
+    use rust_web_server_lib::application::ports::audit::{AuditAction, AuditEntry, AuditLogPort};
+    use rust_web_server_lib::domain::user::model::UserId;
+    use rust_web_server_lib::infra::storage::adapter::postgres::audit_log::PostgresAuditLog;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+
+    use super::*;
+
+    /// Runs the CLI against the test database, returning its stdout, or its stderr on failure.
+    fn run(db: &TestDb, args: &[&str], stdin: Option<&str>) -> Result<String, String> {
+        let url = env::var("TEST_DATABASE_URL").or_else(|_| env::var("DATABASE_URL")).unwrap();
+        let (server, _) = url.split('?').next().unwrap().rsplit_once('/').unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_rustweb-cli"))
+            .args(args)
+            .env("DATABASE_URL", format!("{}/{}", server, db.name()))
+            .env_remove("CONFIG_FILE")
+            .env_remove("OUTBOX_ENABLED")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(stdin.unwrap_or_default().as_bytes()).unwrap();
+        let output = child.wait_with_output().unwrap();
+        match output.status.success() {
+            true => Ok(String::from_utf8(output.stdout).unwrap()),
+            false => Err(String::from_utf8(output.stderr).unwrap()),
+        }
+    }
+
+    #[tokio::test]
+    async fn manages_users() {
+        let db = TestDb::new().await.unwrap();
+        run(&db, &["migrate"], None).unwrap();
+
+        let created = run(&db, &["users", "create", "--name", "Ada", "--email", "ada@example.com", "--age", "36", "--password-stdin"], Some("s3cret-pass\n")).unwrap();
+        let fields: Vec<&str> = created.trim_end().split('\t').collect();
+        assert_eq!(fields[1..], ["Ada", "ada@example.com", "36", "user"]);
+        let id = fields[0];
+        run(&db, &["users", "create", "--name", "Bob", "--email", "bob@example.com", "--age", "40"], None).unwrap();
+
+        assert_eq!(run(&db, &["users", "get", id], None).unwrap(), created);
+        let listed = run(&db, &["users", "list", "--limit", "1"], None).unwrap();
+        assert_eq!(listed, created);
+        assert!(run(&db, &["users", "list", "--offset", "1"], None).unwrap().contains("Bob"));
+
+        run(&db, &["users", "delete", id], None).unwrap();
+        assert!(run(&db, &["users", "get", id], None).unwrap_err().contains("user not found"));
+        run(&db, &["users", "delete", "--hard", id], None).unwrap();
+        assert!(run(&db, &["users", "delete", "--hard", id], None).unwrap_err().contains("user not found"));
+    }
+
+    #[tokio::test]
+    async fn verifies_the_audit_log() {
+        let db = TestDb::new().await.unwrap();
+        let audit_log = PostgresAuditLog::new(db.db());
+        for action in [AuditAction::Created, AuditAction::Updated] {
+            audit_log.record(AuditEntry::new(UserId::generate(), action, None, None)).await.unwrap();
+        }
+
+        let verified = run(&db, &["audit", "verify"], None).unwrap();
+        let fields: Vec<&str> = verified.trim_end().split('\t').collect();
+        assert_eq!(fields[..2], ["2", "0"]);
+        assert_eq!(fields[2].len(), 64);
+
+        sqlx::query("UPDATE audit_log SET action = 'deleted' WHERE action = 'updated'").execute(&*db.db()).await.unwrap();
+        assert!(run(&db, &["audit", "verify"], None).unwrap_err().contains("tampered with"));
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_users() {
+        let db = TestDb::new().await.unwrap();
+
+        let error = run(&db, &["users", "create", "--name", "", "--email", "ada@example.com", "--age", "36"], None).unwrap_err();
+        assert!(error.contains("invalid user"), "{}", error);
+        assert_eq!(run(&db, &["users", "list"], None).unwrap(), "");
+    }
+}
@@ -0,0 +1,69 @@
+//! Helpers shared by the integration tests of the HTTP API: the access tokens of the tests,
+//! and requests sent to a router in-process.
+// Every test crate only uses some of the helpers
+#![allow(dead_code)]
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::auth_service::AuthService;
+use rust_web_server_lib::application::ports::auth::{all_scopes, DisabledAuthenticator, TokenPort};
+use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+use rust_web_server_lib::infra::auth::JwtConfig;
+use rust_web_server_lib::presentation::middleware::auth::AuthState;
+
+/// Admin token of the apps of the tests mounting the admin routes.
+pub const ADMIN_TOKEN: &str = "secret";
+
+/// Returns the issuer of the access tokens of the tests, valid for an hour.
+pub fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "integration-test-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+/// Returns an access token of `user_id` with every scope.
+pub fn token(user_id: &str) -> String {
+    jwt_tokens().issue(user_id, &[], &all_scopes()).unwrap().token
+}
+
+/// Returns an access token of `user_id` with the `scopes` only.
+pub fn scoped_token(user_id: &str, scopes: &[&str]) -> String {
+    let scopes: Vec<String> = scopes.iter().map(|scope| scope.to_string()).collect();
+    jwt_tokens().issue(user_id, &[], &scopes).unwrap().token
+}
+
+/// Returns the authentication of the tokens of [`jwt_tokens`], without password logins.
+pub fn auth_state() -> AuthState {
+    AuthState { auth_service: Arc::new(AuthService::new(Arc::new(DisabledAuthenticator), Arc::new(jwt_tokens()))) }
+}
+
+/// Returns a `method` request to `uri`, with the bearer `token` and the JSON `body`, if any.
+pub fn request(method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> Request<Body> {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    match body {
+        Some(body) => request.header(header::CONTENT_TYPE, "application/json").body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    }
+    .unwrap()
+}
+
+/// Sends a `method` request to `uri` with the bearer `token` and the JSON `body`, returning the
+/// status and the body of the response.
+pub async fn send(app: &axum::Router, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+    send_request(app, request(method, uri, token, body)).await
+}
+
+/// Sends `request`, returning the status and the body of the response, or `Value::Null` if the
+/// body is not JSON.
+pub async fn send_request(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use rust_web_server_lib::application::flows::consent_service::{ConsentService, ConsentServiceTrait};
+use rust_web_server_lib::application::ports::consent::ConsentPort;
+use rust_web_server_lib::domain::consent::model::{ConsentAction, ConsentType, RecordConsent};
+use rust_web_server_lib::domain::user::{model::CreateUser, repository::UserRepositoryPort};
+use rust_web_server_lib::infra::storage::adapter::in_memory::{consent_repository::InMemoryConsentRepository, user_repository::InMemoryUserRepository};
+
+async fn service_with_user() -> (ConsentService, String) {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let user = users
+        .create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap(), None)
+        .await
+        .unwrap();
+    (ConsentService::new(Arc::new(InMemoryConsentRepository::new()), users), user.id().to_string())
+}
+
+async fn record(service: &ConsentService, user_id: &str, consent_type: ConsentType, action: ConsentAction) {
+    let consent = RecordConsent {
+        user_id: user_id.to_string(),
+        consent_type,
+        action,
+        version: "2024-03".to_string(),
+        source: "settings".to_string(),
+    };
+    service.record_consent(consent).await.unwrap();
+}
+
+#[tokio::test]
+async fn consents_are_not_granted_until_recorded() {
+    let (service, user_id) = service_with_user().await;
+
+    assert_eq!(service.has_consent(&user_id, ConsentType::Analytics).await, Ok(false));
+    assert_eq!(service.has_consent(&user_id, ConsentType::MarketingEmail).await, Ok(false));
+}
+
+#[tokio::test]
+async fn latest_record_of_a_type_is_in_effect() {
+    let (service, user_id) = service_with_user().await;
+
+    record(&service, &user_id, ConsentType::MarketingEmail, ConsentAction::Granted).await;
+    record(&service, &user_id, ConsentType::Analytics, ConsentAction::Granted).await;
+    assert_eq!(service.has_consent(&user_id, ConsentType::MarketingEmail).await, Ok(true));
+
+    record(&service, &user_id, ConsentType::MarketingEmail, ConsentAction::Withdrawn).await;
+    assert_eq!(service.has_consent(&user_id, ConsentType::MarketingEmail).await, Ok(false));
+    assert_eq!(service.has_consent(&user_id, ConsentType::Analytics).await, Ok(true));
+}
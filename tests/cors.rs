@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::response::Response;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::cors::CorsPolicy;
+
+const FRONTEND: &str = "https://app.example.com";
+
+fn policy() -> CorsPolicy {
+    CorsPolicy {
+        allowed_origins: vec![FRONTEND.to_string()],
+        allowed_methods: vec!["GET".to_string(), "post".to_string(), "PUT".to_string()],
+        allowed_headers: vec!["authorization".to_string(), "content-type".to_string()],
+        allow_credentials: false,
+    }
+}
+
+/// Builds the router the way `HttpServer::new` does.
+fn app(policy: &CorsPolicy) -> axum::Router {
+    router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))).layer(policy.layer().unwrap())
+}
+
+async fn preflight(app: &axum::Router, origin: &str) -> Response {
+    let request = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/api/users")
+        .header(header::ORIGIN, origin)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization,content-type")
+        .body(Body::empty())
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+fn header_value(response: &Response, name: header::HeaderName) -> Option<&str> {
+    response.headers().get(name).map(|value| value.to_str().unwrap())
+}
+
+#[tokio::test]
+async fn answers_preflight_requests_of_allowed_origins() {
+    let response = preflight(&app(&policy()), FRONTEND).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN), Some(FRONTEND));
+    assert_eq!(header_value(&response, header::ACCESS_CONTROL_ALLOW_METHODS), Some("GET,POST,PUT"));
+    assert_eq!(header_value(&response, header::ACCESS_CONTROL_ALLOW_HEADERS), Some("authorization,content-type"));
+    assert_eq!(header_value(&response, header::ACCESS_CONTROL_ALLOW_CREDENTIALS), None);
+}
+
+#[tokio::test]
+async fn adds_headers_to_responses() {
+    let request = Request::builder().uri("/healthz").header(header::ORIGIN, FRONTEND).body(Body::empty()).unwrap();
+
+    let response = app(&policy()).oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN), Some(FRONTEND));
+    assert!(header_value(&response, header::VARY).unwrap().contains("origin"));
+    assert_eq!(header_value(&response, header::ACCESS_CONTROL_EXPOSE_HEADERS), Some("x-request-id,etag,deprecation,sunset,link"));
+}
+
+#[tokio::test]
+async fn ignores_other_origins() {
+    let response = preflight(&app(&policy()), "https://evil.example.com").await;
+
+    assert_eq!(header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN), None);
+}
+
+#[tokio::test]
+async fn allows_credentials_of_listed_origins() {
+    let policy = CorsPolicy {
+        allowed_headers: vec!["*".to_string()],
+        allow_credentials: true,
+        ..policy()
+    };
+
+    let response = preflight(&app(&policy), FRONTEND).await;
+
+    assert_eq!(header_value(&response, header::ACCESS_CONTROL_ALLOW_CREDENTIALS), Some("true"));
+    // Wildcards are mirrored, as browsers take them literally along with credentials
+    assert_eq!(header_value(&response, header::ACCESS_CONTROL_ALLOW_HEADERS), Some("authorization,content-type"));
+}
+
+#[tokio::test]
+async fn allows_any_origin() {
+    let policy = CorsPolicy { allowed_origins: vec!["*".to_string()], ..policy() };
+
+    let response = preflight(&app(&policy), "https://other.example.com").await;
+
+    assert_eq!(header_value(&response, header::ACCESS_CONTROL_ALLOW_ORIGIN), Some("*"));
+}
+
+#[test]
+fn rejects_credentials_for_any_origin() {
+    let policy = CorsPolicy {
+        allowed_origins: vec!["*".to_string()],
+        allow_credentials: true,
+        ..policy()
+    };
+
+    assert!(policy.layer().is_err());
+}
+
+#[test]
+fn rejects_invalid_methods_and_headers() {
+    let methods = CorsPolicy { allowed_methods: vec!["GET POST".to_string()], ..policy() };
+    let headers = CorsPolicy { allowed_headers: vec!["x-header:".to_string()], ..policy() };
+
+    assert!(methods.layer().unwrap_err().to_string().contains("GET POST"));
+    assert!(headers.layer().unwrap_err().to_string().contains("x-header:"));
+}
@@ -0,0 +1,176 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use port_decorators::RetryPolicy;
+use sqlx::error::{DatabaseError, ErrorKind};
+use sqlx::postgres::PgPoolOptions;
+
+use rust_web_server_lib::infra::storage::adapter::postgres::retry::{backoff_bound, is_transient, retry_transient, DEADLOCK_DETECTED, MAX_ATTEMPTS, SERIALIZATION_FAILURE};
+use rust_web_server_lib::infra::storage::{connect_with_retry, PoolConfig};
+
+const RETRY: RetryPolicy = RetryPolicy { max_attempts: 3, initial_backoff: Duration::from_millis(1) };
+
+fn unreachable() -> sqlx::Error {
+    sqlx::Error::Io(io::Error::new(io::ErrorKind::NotFound, "failed to lookup address information"))
+}
+
+/// An error reported by the database with a SQLSTATE code.
+#[derive(Debug)]
+struct SqlState(&'static str);
+
+impl fmt::Display for SqlState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SQLSTATE {}", self.0)
+    }
+}
+
+impl Error for SqlState {}
+
+impl DatabaseError for SqlState {
+    fn message(&self) -> &str {
+        self.0
+    }
+
+    fn code(&self) -> Option<Cow<'_, str>> {
+        Some(Cow::Borrowed(self.0))
+    }
+
+    fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+fn database_error(code: &'static str) -> sqlx::Error {
+    sqlx::Error::Database(Box::new(SqlState(code)))
+}
+
+#[tokio::test]
+async fn retries_connections_while_the_database_is_unreachable() {
+    let attempts = AtomicU32::new(0);
+
+    let result = connect_with_retry(RETRY, || async {
+        match attempts.fetch_add(1, Ordering::SeqCst) {
+            0 => Err(unreachable()),
+            1 => Err(sqlx::Error::PoolTimedOut),
+            _ => Ok("connected"),
+        }
+    })
+    .await;
+
+    assert_eq!(result.unwrap(), "connected");
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_the_last_attempt() {
+    let attempts = AtomicU32::new(0);
+
+    let result = connect_with_retry(RETRY, || async {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        Err::<(), _>(unreachable())
+    })
+    .await;
+
+    assert!(matches!(result, Err(sqlx::Error::Io(_))));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn fails_at_once_on_other_errors() {
+    let attempts = AtomicU32::new(0);
+
+    let result = connect_with_retry(RETRY, || async {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        Err::<(), _>(sqlx::Error::Configuration("invalid port".into()))
+    })
+    .await;
+
+    assert!(matches!(result, Err(sqlx::Error::Configuration(_))));
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn retries_serialization_failures_and_deadlocks() {
+    let attempts = AtomicU32::new(0);
+
+    let result = retry_transient(|| async {
+        match attempts.fetch_add(1, Ordering::SeqCst) {
+            0 => Err(database_error(SERIALIZATION_FAILURE)),
+            1 => Err(database_error(DEADLOCK_DETECTED)),
+            _ => Ok("committed"),
+        }
+    })
+    .await;
+
+    assert_eq!(result.unwrap(), "committed");
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn gives_up_on_transient_failures_after_the_last_attempt() {
+    let attempts = AtomicU32::new(0);
+
+    let result = retry_transient(|| async {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        Err::<(), _>(database_error(DEADLOCK_DETECTED))
+    })
+    .await;
+
+    assert!(result.is_err_and(|e| is_transient(&e)));
+    assert_eq!(attempts.load(Ordering::SeqCst), MAX_ATTEMPTS);
+}
+
+#[tokio::test]
+async fn fails_at_once_on_other_database_errors() {
+    let attempts = AtomicU32::new(0);
+
+    // A unique violation fails the same way when run again
+    let result = retry_transient(|| async {
+        attempts.fetch_add(1, Ordering::SeqCst);
+        Err::<(), _>(database_error("23505"))
+    })
+    .await;
+
+    assert!(result.is_err_and(|e| !is_transient(&e)));
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    assert!(!is_transient(&unreachable()));
+}
+
+#[test]
+fn caps_the_backoff_of_transient_failures() {
+    assert_eq!(backoff_bound(1), Duration::from_millis(20));
+    assert_eq!(backoff_bound(2), Duration::from_millis(40));
+    assert_eq!(backoff_bound(10), Duration::from_millis(500));
+    assert_eq!(backoff_bound(u32::MAX), Duration::from_millis(500));
+}
+
+#[test]
+fn applies_the_limits_and_timeouts_of_the_pool() {
+    let config = PoolConfig { max_connections: 20, min_connections: 2, acquire_timeout_secs: 3, idle_timeout_secs: None, max_lifetime_secs: Some(60), connect_attempts: 0 };
+
+    let options = config.apply(PgPoolOptions::new());
+
+    assert_eq!(options.get_max_connections(), 20);
+    assert_eq!(options.get_min_connections(), 2);
+    assert_eq!(options.get_acquire_timeout(), Duration::from_secs(3));
+    assert_eq!(options.get_idle_timeout(), None);
+    assert_eq!(options.get_max_lifetime(), Some(Duration::from_secs(60)));
+    // A single attempt is made at least
+    assert_eq!(config.connect_retry().max_attempts, 1);
+}
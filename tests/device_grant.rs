@@ -0,0 +1,280 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::auth_service::AuthService;
+use rust_web_server_lib::application::flows::device_service::DeviceService;
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::{all_scopes, AuthError, Authentication, AuthenticatorPort, TokenPort};
+use rust_web_server_lib::application::ports::clock::{ClockPort, ShiftedClock, SystemClock};
+use rust_web_server_lib::infra::auth::device::InMemoryDeviceGrants;
+use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+use rust_web_server_lib::infra::auth::{DeviceGrantConfig, JwtConfig};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::device_handlers::{DeviceState, DEVICE_CODE_GRANT_TYPE};
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::auth::AuthState;
+
+const VERIFICATION_URI: &str = "https://app.example.com/api/auth/device";
+
+/// Authenticator accepting `jdoe` with the password `secret`, granting it the `admin` role.
+struct StaticAuthenticator;
+
+#[async_trait]
+impl AuthenticatorPort for StaticAuthenticator {
+    async fn authenticate(&self, username: String, password: String) -> Result<Authentication, AuthError> {
+        match (username.as_str(), password.as_str()) {
+            ("jdoe", "secret") => Ok(Authentication {
+                user_id: "jdoe".to_string(),
+                roles: vec!["admin".to_string()],
+            }),
+            _ => Err(AuthError::InvalidCredentials),
+        }
+    }
+}
+
+fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "device-test-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+fn app(code_lifetime_secs: u64, poll_interval_secs: u64) -> axum::Router {
+    app_with_clock(code_lifetime_secs, poll_interval_secs, Arc::new(SystemClock))
+}
+
+/// Returns the app expiring device codes with the time of `clock`.
+fn app_with_clock(code_lifetime_secs: u64, poll_interval_secs: u64, clock: Arc<dyn ClockPort + Send + Sync>) -> axum::Router {
+    let config = DeviceGrantConfig {
+        verification_uri: VERIFICATION_URI.to_string(),
+        code_lifetime_secs,
+        poll_interval_secs,
+    };
+    let grants = InMemoryDeviceGrants::new(&config).with_clock(clock);
+    let device = DeviceState {
+        device_service: Arc::new(DeviceService::new(Arc::new(grants), Arc::new(jwt_tokens()))),
+        verification_uri: VERIFICATION_URI.to_string(),
+    };
+    router(AppState {
+        auth: AuthState {
+            auth_service: Arc::new(AuthService::new(Arc::new(StaticAuthenticator), Arc::new(jwt_tokens()))),
+        },
+        device: Some(device),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, String) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+async fn post_form(app: &axum::Router, uri: &str, form: &str) -> (StatusCode, String) {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from(form.to_string()))
+        .unwrap();
+    send(app, request).await
+}
+
+/// Requests the codes of the client `cli`.
+async fn device_code(app: &axum::Router) -> Value {
+    let (status, body) = post_form(app, "/api/auth/device/code", "client_id=cli").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    serde_json::from_str(&body).unwrap()
+}
+
+async fn poll(app: &axum::Router, device_code: &str, client_id: &str) -> (StatusCode, Value) {
+    let form = format!("grant_type={}&device_code={}&client_id={}", DEVICE_CODE_GRANT_TYPE, device_code, client_id);
+    let (status, body) = post_form(app, "/api/auth/device/token", &form).await;
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+async fn decide(app: &axum::Router, token: &str, user_code: &str, approved: bool) -> StatusCode {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/auth/device/approve")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json!({ "user_code": user_code, "approved": approved }).to_string()))
+        .unwrap();
+    send(app, request).await.0
+}
+
+fn jdoe_token() -> String {
+    jwt_tokens().issue("jdoe", &["admin".to_string()], &all_scopes()).unwrap().token
+}
+
+#[tokio::test]
+async fn issues_codes() {
+    let app = app(600, 5);
+
+    let codes = device_code(&app).await;
+
+    let user_code = codes["user_code"].as_str().unwrap();
+    assert_eq!(user_code.len(), 9);
+    assert_eq!(codes["verification_uri"], VERIFICATION_URI);
+    assert_eq!(codes["verification_uri_complete"], format!("{}?user_code={}", VERIFICATION_URI, user_code));
+    assert_eq!(codes["expires_in"], 600);
+    assert_eq!(codes["interval"], 5);
+}
+
+#[tokio::test]
+async fn requires_client_id() {
+    let app = app(600, 5);
+
+    let (status, body) = post_form(&app, "/api/auth/device/code", "scope=").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("invalid_request"), "{}", body);
+}
+
+#[tokio::test]
+async fn exchanges_approved_device_code_once() {
+    let app = app(600, 0);
+    let codes = device_code(&app).await;
+    let device_code = codes["device_code"].as_str().unwrap();
+
+    let (status, body) = poll(&app, device_code, "cli").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "authorization_pending");
+
+    // User codes are case-insensitive, and their separator optional
+    let user_code = codes["user_code"].as_str().unwrap().replace('-', "").to_lowercase();
+    assert_eq!(decide(&app, &jdoe_token(), &user_code, true).await, StatusCode::NO_CONTENT);
+
+    let (status, body) = poll(&app, device_code, "cli").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["token_type"], "Bearer");
+    let principal = jwt_tokens().verify(body["access_token"].as_str().unwrap()).unwrap();
+    assert_eq!(principal.user_id, "jdoe");
+    assert_eq!(principal.roles, vec!["admin".to_string()]);
+
+    let (_, body) = poll(&app, device_code, "cli").await;
+    assert_eq!(body["error"], "invalid_grant");
+}
+
+#[tokio::test]
+async fn slows_down_devices_polling_too_fast() {
+    let app = app(600, 1);
+    let codes = device_code(&app).await;
+    let device_code = codes["device_code"].as_str().unwrap();
+
+    assert_eq!(poll(&app, device_code, "cli").await.1["error"], "authorization_pending");
+    assert_eq!(poll(&app, device_code, "cli").await.1["error"], "slow_down");
+
+    // The interval is now 6 seconds: waiting the original one is still too fast
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(poll(&app, device_code, "cli").await.1["error"], "slow_down");
+}
+
+#[tokio::test]
+async fn reports_denied_devices() {
+    let app = app(600, 0);
+    let codes = device_code(&app).await;
+
+    assert_eq!(decide(&app, &jdoe_token(), codes["user_code"].as_str().unwrap(), false).await, StatusCode::NO_CONTENT);
+
+    let (_, body) = poll(&app, codes["device_code"].as_str().unwrap(), "cli").await;
+    assert_eq!(body["error"], "access_denied");
+    // The decision is final
+    assert_eq!(decide(&app, &jdoe_token(), codes["user_code"].as_str().unwrap(), true).await, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn reports_expired_device_codes() {
+    let app = app(0, 0);
+    let codes = device_code(&app).await;
+
+    let (_, body) = poll(&app, codes["device_code"].as_str().unwrap(), "cli").await;
+
+    assert_eq!(body["error"], "expired_token");
+    assert_eq!(decide(&app, &jdoe_token(), codes["user_code"].as_str().unwrap(), true).await, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn expires_device_codes_with_the_clock() {
+    let clock = Arc::new(ShiftedClock::new());
+    let app = app_with_clock(600, 0, clock.clone());
+    let codes = device_code(&app).await;
+    let device_code = codes["device_code"].as_str().unwrap();
+    assert_eq!(poll(&app, device_code, "cli").await.1["error"], "authorization_pending");
+
+    clock.shift(chrono::TimeDelta::seconds(601));
+
+    assert_eq!(decide(&app, &jdoe_token(), codes["user_code"].as_str().unwrap(), true).await, StatusCode::NOT_FOUND);
+    assert_eq!(poll(&app, device_code, "cli").await.1["error"], "expired_token");
+}
+
+#[tokio::test]
+async fn rejects_device_code_of_another_client() {
+    let app = app(600, 0);
+    let codes = device_code(&app).await;
+
+    let (_, body) = poll(&app, codes["device_code"].as_str().unwrap(), "other").await;
+
+    assert_eq!(body["error"], "invalid_grant");
+}
+
+#[tokio::test]
+async fn rejects_other_grant_types() {
+    let app = app(600, 0);
+
+    let (status, body) = post_form(&app, "/api/auth/device/token", "grant_type=password&username=jdoe&password=secret").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("unsupported_grant_type"), "{}", body);
+}
+
+#[tokio::test]
+async fn refuses_approval_while_impersonating() {
+    let app = app(600, 0);
+    let codes = device_code(&app).await;
+    let token = jwt_tokens().issue_impersonation("admin-1", "jdoe", Duration::from_secs(60)).unwrap().token;
+
+    assert_eq!(decide(&app, &token, codes["user_code"].as_str().unwrap(), true).await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn serves_verification_page_with_user_code() {
+    let app = app(600, 0);
+
+    let request = Request::builder().uri("/api/auth/device?user_code=BDWP-HQPK%22%3E%3Cscript%3E").body(Body::empty()).unwrap();
+    let (status, body) = send(&app, request).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains(r#"value="BDWP-HQPKscript""#), "{}", body);
+    assert!(!body.contains("<script>"), "{}", body);
+}
+
+#[tokio::test]
+async fn approves_device_from_verification_page() {
+    let app = app(600, 0);
+    let codes = device_code(&app).await;
+    let user_code = codes["user_code"].as_str().unwrap();
+
+    let form = format!("user_code={}&email=jdoe&password=wrong&action=approve", user_code);
+    assert_eq!(post_form(&app, "/api/auth/device", &form).await.0, StatusCode::UNAUTHORIZED);
+
+    let form = format!("user_code={}&email=jdoe&password=secret&action=approve", user_code);
+    let (status, body) = post_form(&app, "/api/auth/device", &form).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body.contains("Device approved"), "{}", body);
+
+    let (status, body) = poll(&app, codes["device_code"].as_str().unwrap(), "cli").await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+#[tokio::test]
+async fn device_routes_are_not_mounted_by_default() {
+    let app = router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))));
+
+    assert_eq!(post_form(&app, "/api/auth/device/code", "client_id=cli").await.0, StatusCode::NOT_FOUND);
+}
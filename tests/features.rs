@@ -0,0 +1,44 @@
+use rust_web_server_lib::features::{check_features, missing_features, MissingFeature};
+use rust_web_server_lib::infra::config::{Config, ConfigSource};
+
+fn load(vars: &[(&str, &str)]) -> Config {
+    let vars = [("SERVER_PORT", "8080"), ("DATABASE_URL", "postgres://localhost/users")].iter().chain(vars);
+    Config::load(&ConfigSource::new(vars.map(|(key, value)| (key.to_string(), value.to_string()))).unwrap()).unwrap()
+}
+
+#[test]
+fn accepts_configurations_without_optional_subsystems() {
+    let config = load(&[]);
+
+    assert!(missing_features(&config).is_empty());
+    assert!(check_features(&config).is_ok());
+}
+
+#[test]
+fn lists_the_configured_subsystems_missing_from_the_build() {
+    let config = load(&[("KAFKA_BROKERS", "localhost:9092"), ("THRIFT_PORT", "9090"), ("CACHE_URL", "redis://localhost")]);
+
+    let mut expected = Vec::new();
+    if !cfg!(feature = "kafka") {
+        expected.push(MissingFeature { setting: "KAFKA_BROKERS", feature: "kafka" });
+    }
+    if !cfg!(feature = "thrift") {
+        expected.push(MissingFeature { setting: "THRIFT_PORT", feature: "thrift" });
+    }
+    if !cfg!(feature = "redis") {
+        expected.push(MissingFeature { setting: "CACHE_URL", feature: "redis" });
+    }
+    assert_eq!(missing_features(&config), expected);
+}
+
+#[cfg(not(any(feature = "kafka", feature = "redis")))]
+#[test]
+fn fails_with_every_mismatch_and_the_features_to_build_with() {
+    let config = load(&[("KAFKA_BROKERS", "localhost:9092"), ("COMMANDS_KAFKA_BROKERS", "localhost:9092"), ("CACHE_URL", "redis://localhost")]);
+
+    let error = check_features(&config).unwrap_err().to_string();
+    assert!(error.contains("  - KAFKA_BROKERS is set, but the server was built without the `kafka` feature"), "{}", error);
+    assert!(error.contains("  - COMMANDS_KAFKA_BROKERS is set, but the server was built without the `kafka` feature"), "{}", error);
+    assert!(error.contains("  - CACHE_URL is set, but the server was built without the `redis` feature"), "{}", error);
+    assert!(error.contains("Build with `--features kafka,redis`"), "{}", error);
+}
@@ -0,0 +1,123 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::sync::oneshot;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::domain::user::{error::{StorageError, UserDomainError}, model::{CreateUser, Email, ListUsers, PasswordHash, Role, UpdateUser, User, UserCredentials, UserFacets, UserFilter, UserId, UserPage}, repository::UserRepositoryPort};
+use rust_web_server_lib::presentation::http::{AppState, HttpServer, HttpServerConfig};
+use rust_web_server_lib::presentation::middleware::load_shedding::LoadSheddingPolicy;
+
+/// Repository answering every lookup with "not found" after a delay.
+struct SlowUserRepository(Duration);
+
+#[async_trait]
+impl UserRepositoryPort for SlowUserRepository {
+    async fn create_user(&self, _user: CreateUser, _password_hash: Option<PasswordHash>) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserCreationFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn get_user(&self, _id: UserId) -> Result<User, UserDomainError> {
+        tokio::time::sleep(self.0).await;
+        Err(UserDomainError::UserNotFound)
+    }
+
+    async fn get_user_by_email(&self, _email: Email) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserNotFound)
+    }
+
+    async fn get_user_credentials(&self, _email: Email) -> Result<UserCredentials, UserDomainError> {
+        Err(UserDomainError::UserNotFound)
+    }
+
+    async fn list_users(&self, _query: ListUsers) -> Result<UserPage, UserDomainError> {
+        Err(UserDomainError::UserListFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn search_users(&self, _filter: UserFilter) -> Result<UserPage, UserDomainError> {
+        Err(UserDomainError::UserListFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError> {
+        Err(UserDomainError::UserListFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn update_user(&self, _user: UpdateUser) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserUpdateFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn delete_user(&self, _id: UserId) -> Result<(), UserDomainError> {
+        Err(UserDomainError::UserDeletionFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn restore_user(&self, _id: UserId) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserUpdateFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn hard_delete_user(&self, _id: UserId) -> Result<(), UserDomainError> {
+        Err(UserDomainError::UserDeletionFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn set_legal_hold(&self, _id: UserId, _legal_hold: bool) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserUpdateFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn set_role(&self, _id: UserId, _role: Role) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserUpdateFailed(StorageError::msg("storage failed")))
+    }
+}
+
+/// Starts a server whose user lookups take `delay`, returning its address, a shutdown
+/// trigger and the handle of the running server.
+async fn start(delay: Duration, shutdown_timeout: Duration) -> (SocketAddr, oneshot::Sender<()>, tokio::task::JoinHandle<eyre::Result<()>>) {
+    let state = AppState::new(Arc::new(UserService::new(SlowUserRepository(delay))));
+    let server = HttpServer::new(state, HttpServerConfig { port: 0, shutdown_timeout, cors: None, max_body_bytes: 2 * 1024 * 1024, compression: None, load_shedding: LoadSheddingPolicy::default() }).await.unwrap();
+    let addr = SocketAddr::from(([127, 0, 0, 1], server.local_addr().unwrap().port()));
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    let handle = tokio::spawn(server.run_until(async {
+        let _ = shutdown_rx.await;
+    }));
+    (addr, shutdown_tx, handle)
+}
+
+/// Sends a user lookup on a blocking thread, returning the raw response.
+fn get_user(addr: SocketAddr) -> tokio::task::JoinHandle<std::io::Result<String>> {
+    tokio::task::spawn_blocking(move || {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(b"GET /api/users/6f1c2b4e-8f7a-4c1d-9a3e-2b5d7c9e1f0a HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response)
+    })
+}
+
+#[tokio::test]
+async fn drains_in_flight_requests() {
+    let (addr, shutdown, server) = start(Duration::from_millis(300), Duration::from_secs(10)).await;
+
+    let request = get_user(addr);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    shutdown.send(()).unwrap();
+
+    let response = request.await.unwrap().unwrap();
+    assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+    server.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn stops_waiting_after_the_shutdown_timeout() {
+    let (addr, shutdown, server) = start(Duration::from_secs(3), Duration::from_millis(200)).await;
+
+    let request = get_user(addr);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let start = Instant::now();
+    shutdown.send(()).unwrap();
+
+    server.await.unwrap().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(2), "shutdown took {:?}", start.elapsed());
+    drop(request);
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "graphql")]
+
+mod common;
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::ports::auth::USERS_WRITE_SCOPE;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::graphql::GraphQlState;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::rpc::UserRpc;
+
+use common::{auth_state, scoped_token, send};
+
+fn app() -> axum::Router {
+    let user_service = Arc::new(UserService::new(InMemoryUserRepository::new()));
+    let auth = auth_state();
+    let rpc_service: Arc<dyn UserServiceTrait + Send + Sync> = user_service.clone();
+    let graphql = GraphQlState::new(UserRpc::new(rpc_service, auth.clone()));
+    router(AppState { auth, graphql: Some(graphql), ..AppState::new(user_service) })
+}
+
+/// Posts a GraphQL `query` with `variables`, with `token` if any, returning the response.
+async fn execute(app: &axum::Router, token: Option<&str>, query: &str, variables: Value) -> Value {
+    let (status, body) = send(app, Method::POST, "/api/graphql", token, Some(json!({ "query": query, "variables": variables }))).await;
+    assert_eq!(status, StatusCode::OK);
+    body
+}
+
+const CREATE_USER: &str = "mutation($input: CreateUserInput!) { createUser(input: $input) { id name email age } }";
+
+async fn create_user(app: &axum::Router) -> String {
+    let response = execute(app, None, CREATE_USER, json!({ "input": { "name": "John Doe", "email": "john@example.com", "age": 30 } })).await;
+    response["data"]["createUser"]["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn creates_users_readable_through_both_apis() {
+    let app = app();
+    let id = create_user(&app).await;
+
+    let response = execute(&app, None, "query($id: ID!) { user(id: $id) { id name email age } }", json!({ "id": id })).await;
+    assert_eq!(response["data"]["user"], json!({ "id": id, "name": "John Doe", "email": "john@example.com", "age": 30 }));
+
+    let response = execute(&app, None, "{ users(limit: 10, sortBy: NAME, order: ASC) { total users { id } } }", json!({})).await;
+    assert_eq!(response["data"]["users"], json!({ "total": 1, "users": [{ "id": id }] }));
+
+    let (status, rest) = send(&app, Method::GET, &format!("/api/v1/users/{}", id), None, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(rest["data"]["email"], "john@example.com");
+}
+
+#[tokio::test]
+async fn searches_users() {
+    let app = app();
+    let id = create_user(&app).await;
+
+    let response = execute(&app, None, "{ searchUsers(name: \"john\", minAge: 18) { total users { id } } }", json!({})).await;
+    assert_eq!(response["data"]["searchUsers"], json!({ "total": 1, "users": [{ "id": id }] }));
+    let response = execute(&app, None, "{ searchUsers(maxAge: 18) { total } }", json!({})).await;
+    assert_eq!(response["data"]["searchUsers"]["total"], 0);
+}
+
+#[tokio::test]
+async fn updates_users_with_a_token_allowed_to_write_users() {
+    let app = app();
+    let id = create_user(&app).await;
+    let update = "mutation($id: ID!) { updateUser(id: $id, input: { age: 31 }) { id age } }";
+
+    let response = execute(&app, None, update, json!({ "id": id })).await;
+    assert_eq!(response["errors"][0]["extensions"]["code"], "unauthorized");
+    let response = execute(&app, Some(&scoped_token("jdoe", &[])), update, json!({ "id": id })).await;
+    assert_eq!(response["errors"][0]["extensions"]["code"], "forbidden");
+
+    let response = execute(&app, Some(&scoped_token("jdoe", &[USERS_WRITE_SCOPE])), update, json!({ "id": id })).await;
+    assert_eq!(response["data"]["updateUser"], json!({ "id": id, "age": 31 }));
+}
+
+#[tokio::test]
+async fn deletes_and_restores_users() {
+    let app = app();
+    let id = create_user(&app).await;
+    let token = scoped_token("jdoe", &[USERS_WRITE_SCOPE]);
+
+    let response = execute(&app, Some(&token), "mutation($id: ID!) { deleteUser(id: $id) }", json!({ "id": id })).await;
+    assert_eq!(response["data"]["deleteUser"], true);
+    let response = execute(&app, None, "query($id: ID!) { user(id: $id) { id } }", json!({ "id": id })).await;
+    assert_eq!(response["errors"][0]["extensions"]["code"], "not_found");
+
+    let response = execute(&app, Some(&token), "mutation($id: ID!) { restoreUser(id: $id) { id } }", json!({ "id": id })).await;
+    assert_eq!(response["data"]["restoreUser"]["id"], id.as_str());
+}
+
+#[tokio::test]
+async fn reports_the_errors_of_invalid_fields_in_the_extensions() {
+    let app = app();
+
+    let response = execute(&app, None, CREATE_USER, json!({ "input": { "name": "", "email": "not-an-email", "age": 30 } })).await;
+
+    let extensions = &response["errors"][0]["extensions"];
+    assert_eq!(extensions["code"], "invalid_request");
+    let fields: Vec<&str> = extensions["errors"].as_array().unwrap().iter().map(|error| error["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, ["name", "email"]);
+}
+
+#[tokio::test]
+async fn serves_the_playground() {
+    let response = app().oneshot(Request::get("/api/graphql").body(Body::empty()).unwrap()).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let page = String::from_utf8(axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap();
+    assert!(page.contains("graphiql"), "{}", page);
+    assert!(page.contains("/api/graphql"), "{}", page);
+}
+
+#[tokio::test]
+async fn is_not_mounted_unless_enabled() {
+    let app = router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))));
+
+    let (status, _) = send(&app, Method::GET, "/api/graphql", None, None).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
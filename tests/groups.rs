@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::auth_service::{AuthService, AuthServiceTrait};
+use rust_web_server_lib::application::flows::group_service::GroupService;
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::{all_scopes, AuthError, Authentication, AuthenticatorPort, TokenPort};
+use rust_web_server_lib::domain::group::{model::{Group, GroupRoles, SaveGroup, UpdateMembers, MAX_MEMBERS_PER_UPDATE}, repository::GroupRepositoryPort};
+use rust_web_server_lib::infra::auth::{jwt::JwtTokens, JwtConfig};
+use rust_web_server_lib::infra::storage::adapter::in_memory::group_repository::InMemoryGroupRepository;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::group_handlers::GroupState;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+const USER_ID: &str = "6f1c2b4e-8f7a-4c1d-9a3e-2b5d7c9e1f0a";
+
+fn group(name: &str, granted: &[&str], denied: &[&str]) -> Group {
+    Group {
+        name: name.to_string(),
+        granted_roles: granted.iter().map(ToString::to_string).collect(),
+        denied_roles: denied.iter().map(ToString::to_string).collect(),
+    }
+}
+
+fn roles(roles: &[&str]) -> Vec<String> {
+    roles.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn merges_direct_and_group_roles() {
+    let groups = GroupRoles::of(&[group("support", &["tickets", "users:read"], &[]), group("billing", &["invoices", "users:read"], &[])]);
+
+    assert_eq!(groups.groups, roles(&["billing", "support"]));
+    assert_eq!(groups.apply(&roles(&["user", "tickets"])), roles(&["user", "tickets", "invoices", "users:read"]));
+}
+
+#[test]
+fn denials_take_precedence() {
+    let groups = GroupRoles::of(&[group("support", &["admin", "tickets"], &[]), group("contractors", &[], &["admin"])]);
+
+    assert_eq!(groups.granted, roles(&["tickets"]));
+    assert_eq!(groups.denied, roles(&["admin"]));
+    // Denied roles are removed even when granted directly
+    assert_eq!(groups.apply(&roles(&["admin", "user"])), roles(&["user", "tickets"]));
+}
+
+#[test]
+fn users_without_groups_keep_their_direct_roles() {
+    assert_eq!(GroupRoles::of(&[]).apply(&roles(&["user"])), roles(&["user"]));
+}
+
+#[test]
+fn validates_groups() {
+    let group = SaveGroup::new("support".to_string(), roles(&["tickets", "admin", "tickets"]), Vec::new()).unwrap();
+    assert_eq!(group.granted_roles, roles(&["admin", "tickets"]));
+
+    assert!(SaveGroup::new("Support Team".to_string(), Vec::new(), Vec::new()).is_err());
+    assert!(SaveGroup::new("support".to_string(), roles(&[""]), Vec::new()).is_err());
+    assert!(SaveGroup::new("support".to_string(), roles(&["admin"]), roles(&["admin"])).is_err());
+}
+
+#[test]
+fn validates_membership_updates() {
+    assert!(UpdateMembers::new(vec![USER_ID.to_string()], Vec::new()).is_ok());
+    assert!(UpdateMembers::new(vec!["jdoe".to_string()], Vec::new()).is_err());
+    assert!(UpdateMembers::new(vec![USER_ID.to_string()], vec![USER_ID.to_string()]).is_err());
+    assert!(UpdateMembers::new(vec![USER_ID.to_string(); MAX_MEMBERS_PER_UPDATE + 1], Vec::new()).is_err());
+}
+
+fn app() -> axum::Router {
+    let users = Arc::new(InMemoryUserRepository::new());
+    router(AppState {
+        admin_token: Some("secret".into()),
+        groups: GroupState {
+            group_service: Arc::new(GroupService::new(Arc::new(InMemoryGroupRepository::new()), users.clone())),
+        },
+        ..AppState::new(Arc::new(UserService::new(users)))
+    })
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("authorization", "Bearer secret")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes).unwrap() };
+
+    (status, body)
+}
+
+async fn create_user(app: &axum::Router, email: &str) -> String {
+    let (_, body) = send(app, Method::POST, "/api/users", Some(json!({"name": "Jane", "email": email, "age": 30}))).await;
+    body["data"]["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn manages_groups_and_members() {
+    let app = app();
+    let jane = create_user(&app, "jane@example.com").await;
+    let john = create_user(&app, "john@example.com").await;
+
+    let (status, body) = send(&app, Method::PUT, "/api/admin/groups/support", Some(json!({"granted_roles": ["tickets"], "denied_roles": ["admin"]}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!({"name": "support", "granted_roles": ["tickets"], "denied_roles": ["admin"]}));
+
+    let (status, body) = send(&app, Method::PATCH, "/api/admin/groups/support/members", Some(json!({"add": [jane, john]}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["user_ids"].as_array().unwrap().len(), 2);
+
+    let (status, body) = send(&app, Method::PATCH, "/api/admin/groups/support/members", Some(json!({"remove": [john]}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["user_ids"], json!([jane]));
+
+    let (status, body) = send(&app, Method::GET, &format!("/api/admin/users/{}/roles", jane), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!({"id": jane, "groups": ["support"], "granted_roles": ["tickets"], "denied_roles": ["admin"]}));
+
+    let (status, _) = send(&app, Method::DELETE, "/api/admin/groups/support", None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, body) = send(&app, Method::GET, &format!("/api/admin/users/{}/roles", jane), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["groups"], json!([]));
+}
+
+#[tokio::test]
+async fn rejects_invalid_membership_updates() {
+    let app = app();
+    send(&app, Method::PUT, "/api/admin/groups/support", Some(json!({"granted_roles": ["tickets"]}))).await;
+
+    let (status, _) = send(&app, Method::PATCH, "/api/admin/groups/support/members", Some(json!({"add": ["jdoe"]}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send(&app, Method::PATCH, "/api/admin/groups/unknown/members", Some(json!({"add": [USER_ID]}))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(&app, Method::GET, &format!("/api/admin/users/{}/roles", USER_ID), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn rejects_groups_granting_and_denying_a_role() {
+    let app = app();
+
+    let (status, _) = send(&app, Method::PUT, "/api/admin/groups/support", Some(json!({"granted_roles": ["admin"], "denied_roles": ["admin"]}))).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&app, Method::GET, "/api/admin/groups/support", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+/// Authenticator accepting any password of the user `USER_ID`, who has the `user` and `admin` roles.
+struct StaticAuthenticator;
+
+#[async_trait]
+impl AuthenticatorPort for StaticAuthenticator {
+    async fn authenticate(&self, _username: String, _password: String) -> Result<Authentication, AuthError> {
+        Ok(Authentication { user_id: USER_ID.to_string(), roles: roles(&["user", "admin"]) })
+    }
+}
+
+#[tokio::test]
+async fn tokens_carry_the_roles_of_groups() {
+    let groups = Arc::new(InMemoryGroupRepository::new());
+    let group_service = Arc::new(GroupService::new(groups.clone(), Arc::new(InMemoryUserRepository::new())));
+    let tokens = Arc::new(JwtTokens::new(&JwtConfig { secret: "groups-secret".to_string().into(), expiry_secs: 3600 }));
+    let auth_service = AuthService::new(Arc::new(StaticAuthenticator), tokens.clone()).with_group_roles(group_service);
+
+    groups.save_group(SaveGroup::new("support".to_string(), roles(&["tickets"]), Vec::new()).unwrap()).await.unwrap();
+    groups.save_group(SaveGroup::new("contractors".to_string(), Vec::new(), roles(&["admin"])).unwrap()).await.unwrap();
+    groups.update_members("support".to_string(), UpdateMembers::new(vec![USER_ID.to_string()], Vec::new()).unwrap()).await.unwrap();
+    groups.update_members("contractors".to_string(), UpdateMembers::new(vec![USER_ID.to_string()], Vec::new()).unwrap()).await.unwrap();
+
+    let token = auth_service.login("jane@example.com".to_string(), "secret".to_string(), all_scopes()).await.unwrap().token;
+
+    assert_eq!(tokens.verify(&token).unwrap().roles, roles(&["user", "tickets"]));
+}
@@ -0,0 +1,90 @@
+use std::net::{SocketAddr, TcpListener};
+use std::process::{Command, Output};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::capability::DependencyStatus;
+use rust_web_server_lib::application::ports::health::{HealthCheckPort, HealthChecks};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{AppState, HttpServer, HttpServerConfig};
+use rust_web_server_lib::presentation::middleware::load_shedding::LoadSheddingPolicy;
+
+/// Health check always reporting the same status.
+struct StaticHealthCheck(DependencyStatus);
+
+#[async_trait]
+impl HealthCheckPort for StaticHealthCheck {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    async fn check(&self) -> DependencyStatus {
+        self.0
+    }
+}
+
+/// Starts a server whose database reports `status`, returning its address.
+async fn start(status: DependencyStatus) -> SocketAddr {
+    let state = AppState {
+        health_checks: HealthChecks::new(vec![Arc::new(StaticHealthCheck(status))]),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    };
+    let config = HttpServerConfig { port: 0, shutdown_timeout: Duration::from_secs(1), cors: None, max_body_bytes: 2 * 1024 * 1024, compression: None, load_shedding: LoadSheddingPolicy::default() };
+    let server = HttpServer::new(state, config).await.unwrap();
+    let addr = SocketAddr::from(([127, 0, 0, 1], server.local_addr().unwrap().port()));
+    tokio::spawn(server.run_until(std::future::pending()));
+    addr
+}
+
+/// Runs the healthcheck command of the server binary on a blocking thread.
+async fn healthcheck(args: Vec<String>) -> Output {
+    tokio::task::spawn_blocking(move || Command::new(env!("CARGO_BIN_EXE_rustweb-server-bin")).arg("healthcheck").args(args).output().unwrap())
+        .await
+        .unwrap()
+}
+
+fn url_args(addr: SocketAddr, path: &str) -> Vec<String> {
+    vec!["--url".to_string(), format!("http://{}{}", addr, path)]
+}
+
+#[tokio::test]
+async fn succeeds_against_a_ready_server() {
+    let addr = start(DependencyStatus::Up).await;
+
+    for path in ["/readyz", "/healthz"] {
+        let output = healthcheck(url_args(addr, path)).await;
+        assert!(output.status.success(), "{}: {}", path, String::from_utf8_lossy(&output.stderr));
+    }
+}
+
+#[tokio::test]
+async fn fails_when_a_dependency_is_down() {
+    let addr = start(DependencyStatus::Down).await;
+
+    let output = healthcheck(url_args(addr, "/readyz")).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("returned 503"), "{}", String::from_utf8_lossy(&output.stderr));
+    // The server itself is still live
+    assert!(healthcheck(url_args(addr, "/healthz")).await.status.success());
+}
+
+#[tokio::test]
+async fn fails_when_nothing_listens() {
+    // The port is released once the listener is dropped
+    let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+    let output = healthcheck(url_args(addr, "/readyz")).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("failed"), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[tokio::test]
+async fn rejects_invalid_arguments() {
+    for args in [&[] as &[&str], &["--url"], &["--url", "https://127.0.0.1/readyz"], &["--url", "http://127.0.0.1/readyz", "--timeout", "soon"], &["--verbose"]] {
+        let output = healthcheck(args.iter().map(|arg| arg.to_string()).collect()).await;
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+    }
+}
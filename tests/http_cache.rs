@@ -0,0 +1,228 @@
+use std::sync::{Arc, Mutex};
+
+use axum::body::Body;
+use axum::http::{header, Request, Response, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::ports::purge::PurgePort;
+use rust_web_server_lib::domain::user::model::{CreateUser, UpdateUser, UserId};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::http_cache::{CACHE_TAG, SURROGATE_KEY};
+
+#[derive(Default)]
+struct RecordingPurge(Mutex<Vec<Vec<String>>>);
+
+impl PurgePort for RecordingPurge {
+    fn purge(&self, keys: Vec<String>) {
+        self.0.lock().unwrap().push(keys);
+    }
+}
+
+fn app() -> axum::Router {
+    router(AppState {
+        admin_token: Some("secret".into()),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> Response<Body> {
+    app.clone().oneshot(request).await.unwrap()
+}
+
+fn header_values<'a>(response: &'a Response<Body>, name: &header::HeaderName) -> Vec<&'a str> {
+    response.headers().get_all(name).iter().map(|value| value.to_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn tags_user_responses_with_surrogate_keys() {
+    let app = app();
+    let request = Request::post("/api/users")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json!({"name": "Jane", "email": "jane@example.com", "age": 30}).to_string()))
+        .unwrap();
+    let bytes = axum::body::to_bytes(send(&app, request).await.into_body(), usize::MAX).await.unwrap();
+    let id = serde_json::from_slice::<Value>(&bytes).unwrap()["data"]["id"].as_str().unwrap().to_string();
+
+    let response = send(&app, Request::get(format!("/api/users/{}", id)).body(Body::empty()).unwrap()).await;
+    assert_eq!(header_values(&response, &SURROGATE_KEY), [format!("user-{}", id)]);
+    assert_eq!(header_values(&response, &CACHE_TAG), [format!("user-{}", id)]);
+
+    for uri in ["/api/users", "/api/users/search?name=jane"] {
+        let response = send(&app, Request::get(uri).body(Body::empty()).unwrap()).await;
+        assert_eq!(header_values(&response, &SURROGATE_KEY), ["users"], "{}", uri);
+        assert_eq!(header_values(&response, &CACHE_TAG), ["users"], "{}", uri);
+    }
+
+    // Errors are not tagged
+    let response = send(&app, Request::get(format!("/api/users/{}", UserId::generate())).body(Body::empty()).unwrap()).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.headers().get(SURROGATE_KEY).is_none());
+}
+
+#[tokio::test]
+async fn varies_on_the_headers_responses_are_chosen_by() {
+    let app = app();
+
+    // Public routes do not depend on the credentials of the request
+    let response = send(&app, Request::get("/api/users").header(header::AUTHORIZATION, "Bearer token").body(Body::empty()).unwrap()).await;
+    assert!(header_values(&response, &header::VARY).is_empty());
+
+    let request = Request::delete(format!("/api/users/{}", UserId::generate())).body(Body::empty()).unwrap();
+    let response = send(&app, request).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(header_values(&response, &header::VARY), ["authorization"]);
+
+    let response = send(&app, Request::get("/api/admin/users").header(header::AUTHORIZATION, "Bearer secret").body(Body::empty()).unwrap()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_values(&response, &header::VARY), ["authorization"]);
+}
+
+#[tokio::test]
+async fn purges_the_responses_changes_alter() {
+    let (purge, cdn) = (Arc::new(RecordingPurge::default()), Arc::new(RecordingPurge::default()));
+    let service = UserService::new(InMemoryUserRepository::new()).with_purge(purge.clone()).with_purge(cdn.clone());
+
+    let user = service.create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap()).await.unwrap();
+    let update = UpdateUser::new(user.id(), Some("Janet".to_string()), None, None).unwrap();
+    service.update_user(update).await.unwrap();
+    service.delete_user(user.id()).await.unwrap();
+    // Failed changes purge nothing
+    assert!(service.delete_user(user.id()).await.is_err());
+
+    let key = format!("user-{}", user.id());
+    assert_eq!(*purge.0.lock().unwrap(), vec![vec!["users".to_string()], vec![key.clone(), "users".to_string()], vec![key, "users".to_string()]]);
+    // Every purge port purges the same responses
+    assert_eq!(*cdn.0.lock().unwrap(), *purge.0.lock().unwrap());
+}
+
+#[cfg(feature = "purge")]
+mod purgers {
+    use axum::extract::{Request, State};
+    use axum::http::Method;
+    use tokio::sync::mpsc;
+
+    use rust_web_server_lib::infra::purge::cloudflare::CloudflarePurger;
+    use rust_web_server_lib::infra::purge::fastly::FastlyPurger;
+    use rust_web_server_lib::infra::purge::http::HttpPurger;
+    use rust_web_server_lib::infra::purge::{CloudflarePurgeConfig, FastlyPurgeConfig, PurgeConfig};
+
+    use super::*;
+
+    /// A purge request received by the endpoint of [`serve`].
+    struct Purge {
+        method: Method,
+        path: String,
+        headers: axum::http::HeaderMap,
+        body: String,
+    }
+
+    impl Purge {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers.get(name).map(|value| value.to_str().unwrap())
+        }
+    }
+
+    /// State of the endpoint of [`serve`]: where received requests are sent, and the statuses
+    /// left to answer with.
+    type Endpoint = (mpsc::UnboundedSender<Purge>, Arc<Mutex<std::vec::IntoIter<StatusCode>>>);
+
+    /// Serves an endpoint answering purge requests with the statuses of `statuses` in turn, then
+    /// with `200 OK`, returning its URL and the requests it receives.
+    async fn serve(statuses: Vec<StatusCode>) -> (String, mpsc::UnboundedReceiver<Purge>) {
+        let (sender, received) = mpsc::unbounded_channel();
+        let statuses = Arc::new(Mutex::new(statuses.into_iter()));
+        let endpoint = axum::Router::new().fallback(|State((sender, statuses)): State<Endpoint>, request: Request| async move {
+            let (parts, body) = request.into_parts();
+            let body = String::from_utf8(axum::body::to_bytes(body, usize::MAX).await.unwrap().to_vec()).unwrap();
+            sender.send(Purge { method: parts.method, path: parts.uri.path().to_string(), headers: parts.headers, body }).unwrap();
+            statuses.lock().unwrap().next().unwrap_or(StatusCode::OK)
+        });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(axum::serve(listener, endpoint.with_state((sender, statuses))).into_future());
+        (url, received)
+    }
+
+    fn http_config(url: String) -> PurgeConfig {
+        PurgeConfig { url, method: "PURGE".to_string(), keys_header: "xkey-purge".to_string(), token: Some("token".to_string().into()) }
+    }
+
+    #[tokio::test]
+    async fn sends_the_keys_of_the_purges_in_a_header() {
+        let (url, mut received) = serve(vec![]).await;
+
+        let purger = HttpPurger::spawn(http_config(format!("{}/purge", url))).unwrap();
+        purger.purge(vec!["user-1".to_string(), "users".to_string()]);
+
+        let purge = received.recv().await.unwrap();
+        assert_eq!((purge.method.as_str(), purge.path.as_str()), ("PURGE", "/purge"));
+        assert_eq!(purge.header("xkey-purge"), Some("user-1 users"));
+        assert_eq!(purge.header("authorization"), Some("Bearer token"));
+    }
+
+    #[tokio::test]
+    async fn batches_purges_and_retries_transient_failures() {
+        let (url, mut received) = serve(vec![StatusCode::SERVICE_UNAVAILABLE]).await;
+
+        let purger = HttpPurger::spawn(http_config(url)).unwrap();
+        purger.purge(vec!["user-2".to_string(), "users".to_string()]);
+        purger.purge(vec!["user-1".to_string(), "users".to_string()]);
+
+        // The keys of both purges are sent once, and again after the first request failed
+        for _ in 0..2 {
+            assert_eq!(received.recv().await.unwrap().header("xkey-purge"), Some("user-1 user-2 users"));
+        }
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_rejected_purges() {
+        let (url, mut received) = serve(vec![StatusCode::FORBIDDEN]).await;
+
+        let purger = HttpPurger::spawn(http_config(url)).unwrap();
+        purger.purge(vec!["users".to_string()]);
+        received.recv().await.unwrap();
+        purger.purge(vec!["user-1".to_string()]);
+
+        assert_eq!(received.recv().await.unwrap().header("xkey-purge"), Some("user-1"));
+    }
+
+    #[tokio::test]
+    async fn purges_cloudflare_cache_tags_in_batches_of_30() {
+        let (url, mut received) = serve(vec![]).await;
+        let keys: Vec<String> = (10..41).map(|i| format!("user-{}", i)).collect();
+
+        let purger = CloudflarePurger::spawn(CloudflarePurgeConfig { api_url: format!("{}/client/v4/", url), zone_id: "zone".to_string(), api_token: "token".to_string().into() }).unwrap();
+        purger.purge(keys.clone());
+
+        let first = received.recv().await.unwrap();
+        assert_eq!((first.method.as_str(), first.path.as_str()), ("POST", "/client/v4/zones/zone/purge_cache"));
+        assert_eq!(first.header("authorization"), Some("Bearer token"));
+        assert_eq!(serde_json::from_str::<Value>(&first.body).unwrap(), json!({ "tags": keys[..30] }));
+        assert_eq!(serde_json::from_str::<Value>(&received.recv().await.unwrap().body).unwrap(), json!({ "tags": keys[30..] }));
+    }
+
+    #[tokio::test]
+    async fn purges_fastly_surrogate_keys() {
+        let (url, mut received) = serve(vec![]).await;
+
+        let purger = FastlyPurger::spawn(FastlyPurgeConfig { api_url: url, service_id: "service".to_string(), api_token: "token".to_string().into(), soft: true }).unwrap();
+        purger.purge(vec!["user-1".to_string(), "users".to_string()]);
+
+        let purge = received.recv().await.unwrap();
+        assert_eq!((purge.method.as_str(), purge.path.as_str()), ("POST", "/service/service/purge"));
+        assert_eq!(purge.header("fastly-key"), Some("token"));
+        assert_eq!(purge.header("surrogate-key"), Some("user-1 users"));
+        assert_eq!(purge.header("fastly-soft-purge"), Some("1"));
+    }
+
+    #[test]
+    fn rejects_invalid_methods_and_headers() {
+        let config = PurgeConfig { url: "http://localhost".to_string(), method: "POST".to_string(), keys_header: "Surrogate-Key".to_string(), token: None };
+
+        assert!(HttpPurger::spawn(PurgeConfig { method: "BAD METHOD".to_string(), ..config.clone() }).is_err());
+        assert!(HttpPurger::spawn(PurgeConfig { keys_header: "bad header".to_string(), ..config }).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, Request};
+use serde_json::json;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState, HttpServer, HttpServerConfig};
+use rust_web_server_lib::presentation::middleware::compression::CompressionPolicy;
+use rust_web_server_lib::presentation::middleware::load_shedding::LoadSheddingPolicy;
+
+const MAX_BODY_BYTES: usize = 1024;
+
+fn policy(encodings: &[&str]) -> CompressionPolicy {
+    CompressionPolicy { encodings: encodings.iter().map(|encoding| encoding.to_string()).collect() }
+}
+
+/// Starts a server storing users in memory, returning its address.
+async fn start(compression: Option<CompressionPolicy>) -> SocketAddr {
+    let state = AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())));
+    let config = HttpServerConfig { port: 0, shutdown_timeout: Duration::from_secs(1), cors: None, max_body_bytes: MAX_BODY_BYTES, compression, load_shedding: LoadSheddingPolicy::default() };
+    let server = HttpServer::new(state, config).await.unwrap();
+    let addr = SocketAddr::from(([127, 0, 0, 1], server.local_addr().unwrap().port()));
+    tokio::spawn(server.run_until(std::future::pending()));
+    addr
+}
+
+/// Sends a raw HTTP request on a blocking thread, returning the raw response.
+async fn send(addr: SocketAddr, request: String) -> String {
+    tokio::task::spawn_blocking(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        String::from_utf8_lossy(&response).into_owned()
+    })
+    .await
+    .unwrap()
+}
+
+fn create_user_request(name: &str, headers: &str) -> String {
+    let body = json!({"name": name, "email": "jane@example.com", "age": 30}).to_string();
+    format!(
+        "POST /api/users HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\ncontent-type: application/json\r\ncontent-length: {}\r\n{}\r\n{}",
+        body.len(),
+        headers,
+        body
+    )
+}
+
+#[tokio::test]
+async fn accepts_bodies_up_to_the_limit() {
+    let addr = start(None).await;
+
+    let response = send(addr, create_user_request("Jane", "")).await;
+    assert!(response.starts_with("HTTP/1.1 201"), "{}", response);
+}
+
+#[tokio::test]
+async fn rejects_larger_bodies() {
+    let addr = start(None).await;
+
+    let response = send(addr, create_user_request(&"a".repeat(MAX_BODY_BYTES), "")).await;
+    assert!(response.starts_with("HTTP/1.1 413"), "{}", response);
+}
+
+#[tokio::test]
+async fn rejects_larger_chunked_bodies() {
+    let addr = start(None).await;
+    let body = json!({"name": "a".repeat(MAX_BODY_BYTES), "email": "jane@example.com", "age": 30}).to_string();
+    let request = format!(
+        "POST /api/users HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\ncontent-type: application/json\r\ntransfer-encoding: chunked\r\n\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+        body.len(),
+        body
+    );
+
+    let response = send(addr, request).await;
+    assert!(response.starts_with("HTTP/1.1 413"), "{}", response);
+}
+
+#[tokio::test]
+async fn compresses_responses_with_an_accepted_encoding() {
+    let addr = start(Some(policy(&["gzip", "br"]))).await;
+
+    let response = send(addr, create_user_request("Jane", "accept-encoding: br\r\n")).await;
+    assert!(response.starts_with("HTTP/1.1 201"), "{}", response);
+    assert!(response.to_lowercase().contains("content-encoding: br\r\n"), "{}", response);
+
+    // Encodings not enabled are not used, even when accepted
+    let response = send(addr, create_user_request("Jane", "accept-encoding: zstd\r\n")).await;
+    assert!(!response.to_lowercase().contains("content-encoding"), "{}", response);
+}
+
+#[tokio::test]
+async fn sends_uncompressed_responses_without_compression() {
+    let addr = start(None).await;
+
+    let response = send(addr, create_user_request("Jane", "accept-encoding: gzip\r\n")).await;
+    assert!(response.starts_with("HTTP/1.1 201"), "{}", response);
+    assert!(!response.to_lowercase().contains("content-encoding"), "{}", response);
+}
+
+#[tokio::test]
+async fn compresses_with_each_enabled_encoding() {
+    let app = router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))))
+        .layer(policy(&["GZIP", "br", "zstd", "deflate"]).layer().unwrap());
+
+    for encoding in ["gzip", "br", "zstd", "deflate"] {
+        let request = Request::get("/api/docs/openapi.json").header(header::ACCEPT_ENCODING, encoding).body(Body::empty()).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers().get(header::CONTENT_ENCODING).unwrap(), encoding);
+        assert!(response.headers().get_all(header::VARY).iter().any(|vary| vary == "accept-encoding"));
+    }
+}
+
+#[test]
+fn rejects_unknown_encodings() {
+    let error = policy(&["gzip", "lzma"]).layer().err().unwrap();
+    assert!(error.to_string().contains("lzma"));
+}
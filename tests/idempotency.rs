@@ -0,0 +1,231 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use axum::middleware;
+use axum::routing::post;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::idempotency::InMemoryIdempotencyStore;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::idempotency::{replay_idempotent_requests, Idempotency, IDEMPOTENT_REPLAYED_HEADER};
+
+fn app(ttl: Duration) -> axum::Router {
+    router(AppState {
+        idempotency: Some(Idempotency::new(Arc::new(InMemoryIdempotencyStore::new()), ttl)),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+/// Sends `request`, returning the status, the headers and the body of the response.
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, HeaderMap, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, headers, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+/// Creates `user` with the idempotency `key`, and the `authorization` of the client if any.
+async fn create_user(app: &axum::Router, key: Option<&str>, authorization: Option<&str>, user: &Value) -> (StatusCode, HeaderMap, Value) {
+    let mut request = Request::builder().method(Method::POST).uri("/api/users").header(header::CONTENT_TYPE, "application/json");
+    if let Some(key) = key {
+        request = request.header("Idempotency-Key", key);
+    }
+    if let Some(authorization) = authorization {
+        request = request.header(header::AUTHORIZATION, authorization);
+    }
+    send(app, request.body(Body::from(user.to_string())).unwrap()).await
+}
+
+async fn count_users(app: &axum::Router) -> usize {
+    let (_, _, body) = send(app, Request::get("/api/users").body(Body::empty()).unwrap()).await;
+    body["data"]["users"].as_array().unwrap().len()
+}
+
+fn alice() -> Value {
+    json!({ "name": "Alice", "email": "alice@example.com", "age": 30 })
+}
+
+#[tokio::test]
+async fn replays_the_response_to_the_retries_of_a_request() {
+    let app = app(Duration::from_secs(60));
+
+    let (status, headers, created) = create_user(&app, Some("key-1"), None, &alice()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(headers.get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+
+    let (status, headers, replayed) = create_user(&app, Some("key-1"), None, &alice()).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(headers[IDEMPOTENT_REPLAYED_HEADER], "true");
+    assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+    assert_eq!(replayed, created);
+    assert_eq!(count_users(&app).await, 1);
+}
+
+#[tokio::test]
+async fn replays_the_etag_of_the_response() {
+    let app = app(Duration::from_secs(60));
+
+    let (_, created, _) = create_user(&app, Some("key-1"), None, &alice()).await;
+    let (_, replayed, _) = create_user(&app, Some("key-1"), None, &alice()).await;
+
+    assert_eq!(replayed[IDEMPOTENT_REPLAYED_HEADER], "true");
+    assert_eq!(replayed[header::ETAG], created[header::ETAG]);
+    assert_eq!(count_users(&app).await, 1);
+}
+
+#[tokio::test]
+async fn rejects_a_key_reused_for_another_request() {
+    let app = app(Duration::from_secs(60));
+    create_user(&app, Some("key-1"), None, &alice()).await;
+
+    let bob = json!({ "name": "Bob", "email": "bob@example.com", "age": 40 });
+    let (status, _, body) = create_user(&app, Some("key-1"), None, &bob).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["data"]["message"], "Idempotency-Key was used for another request");
+    assert_eq!(count_users(&app).await, 1);
+}
+
+#[tokio::test]
+async fn scopes_the_keys_to_the_credentials_of_the_client() {
+    let app = app(Duration::from_secs(60));
+    create_user(&app, Some("key-1"), Some("Bearer first"), &alice()).await;
+
+    let (_, headers, _) = create_user(&app, Some("key-1"), Some("Bearer second"), &alice()).await;
+
+    assert!(headers.get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+}
+
+#[tokio::test]
+async fn handles_every_request_without_a_key() {
+    let app = app(Duration::from_secs(60));
+
+    let (_, first, _) = create_user(&app, None, None, &alice()).await;
+    let (_, second, _) = create_user(&app, None, None, &json!({ "name": "Bob", "email": "bob@example.com", "age": 40 })).await;
+
+    assert!(first.get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+    assert!(second.get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+    assert_eq!(count_users(&app).await, 2);
+}
+
+#[tokio::test]
+async fn handles_the_request_again_once_the_key_expired() {
+    let app = app(Duration::ZERO);
+    create_user(&app, Some("key-1"), None, &alice()).await;
+
+    let (_, headers, _) = create_user(&app, Some("key-1"), None, &alice()).await;
+
+    assert!(headers.get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+}
+
+#[tokio::test]
+async fn rejects_invalid_keys() {
+    let app = app(Duration::from_secs(60));
+
+    assert_eq!(create_user(&app, Some(""), None, &alice()).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(create_user(&app, Some(&"k".repeat(256)), None, &alice()).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(count_users(&app).await, 0);
+}
+
+#[tokio::test]
+async fn releases_the_key_of_a_request_dropped_before_its_response() {
+    // The first attempt is handled until it is dropped, the retries are answered
+    let (entered, mut handling) = mpsc::channel(1);
+    let first = Arc::new(AtomicBool::new(true));
+    let handler = move || {
+        let (entered, first) = (entered.clone(), first.clone());
+        async move {
+            if first.swap(false, Ordering::SeqCst) {
+                entered.send(()).await.unwrap();
+                std::future::pending::<()>().await;
+            }
+            StatusCode::CREATED
+        }
+    };
+    let idempotency = Idempotency::new(Arc::new(InMemoryIdempotencyStore::new()), Duration::from_secs(60));
+    let app = axum::Router::new().route("/orders", post(handler)).route_layer(middleware::from_fn_with_state(idempotency, replay_idempotent_requests));
+    let order = || Request::post("/orders").header("Idempotency-Key", "key-1").body(Body::from("{}")).unwrap();
+
+    let attempt = tokio::spawn(app.clone().oneshot(order()));
+    handling.recv().await.unwrap();
+    assert_eq!(app.clone().oneshot(order()).await.unwrap().status(), StatusCode::CONFLICT);
+
+    // As on a timeout, or when the client disconnects
+    attempt.abort();
+    assert!(attempt.await.unwrap_err().is_cancelled());
+    // The key is released by a task of its own, run before this one is polled again
+    tokio::task::yield_now().await;
+
+    let response = app.clone().oneshot(order()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(response.headers().get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+    let response = app.oneshot(order()).await.unwrap();
+    assert_eq!(response.headers()[IDEMPOTENT_REPLAYED_HEADER], "true");
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use std::time::Duration;
+
+    use rust_web_server_lib::application::ports::idempotency::{IdempotencyPort, Reservation, StoredResponse};
+    use rust_web_server_lib::infra::storage::adapter::postgres::idempotency::PostgresIdempotencyStore;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn response() -> StoredResponse {
+        StoredResponse {
+            status: 201,
+            content_type: Some("application/json".to_string()),
+            headers: vec![("etag".to_string(), "\"1\"".to_string()), ("location".to_string(), "/api/v1/users/1".to_string())],
+            body: b"{}".to_vec(),
+        }
+    }
+
+    #[tokio::test]
+    async fn stores_the_response_of_the_request_holding_the_key() {
+        let db = TestDb::new().await.unwrap();
+        let store = PostgresIdempotencyStore::new(db.db());
+
+        assert_eq!(store.reserve("key", "request", TTL).await.unwrap(), Reservation::Reserved);
+        assert_eq!(store.reserve("key", "other", TTL).await.unwrap(), Reservation::InProgress { fingerprint: "request".to_string() });
+
+        store.complete("key", response(), TTL).await.unwrap();
+        assert_eq!(
+            store.reserve("key", "request", TTL).await.unwrap(),
+            Reservation::Completed { fingerprint: "request".to_string(), response: response() }
+        );
+        // Completed keys are not released
+        store.release("key").await.unwrap();
+        assert!(matches!(store.reserve("key", "request", TTL).await.unwrap(), Reservation::Completed { .. }));
+    }
+
+    #[tokio::test]
+    async fn reserves_released_and_expired_keys_again() {
+        let db = TestDb::new().await.unwrap();
+        let store = PostgresIdempotencyStore::new(db.db());
+
+        store.reserve("released", "request", TTL).await.unwrap();
+        store.release("released").await.unwrap();
+        assert_eq!(store.reserve("released", "request", TTL).await.unwrap(), Reservation::Reserved);
+
+        store.reserve("expired", "request", TTL).await.unwrap();
+        store.complete("expired", response(), Duration::ZERO).await.unwrap();
+        assert_eq!(store.reserve("expired", "other", TTL).await.unwrap(), Reservation::Reserved);
+
+        // The lease of a request still handled is shorter than the time its response is kept
+        store.reserve("abandoned", "request", Duration::ZERO).await.unwrap();
+        assert_eq!(store.reserve("abandoned", "request", TTL).await.unwrap(), Reservation::Reserved);
+        store.complete("abandoned", response(), TTL).await.unwrap();
+        assert!(matches!(store.reserve("abandoned", "request", TTL).await.unwrap(), Reservation::Completed { .. }));
+    }
+}
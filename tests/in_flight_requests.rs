@@ -0,0 +1,222 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::auth_service::AuthService;
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::{all_scopes, DisabledAuthenticator, TokenPort};
+use rust_web_server_lib::domain::user::{error::{StorageError, UserDomainError}, model::{CreateUser, Email, ListUsers, PasswordHash, Role, UpdateUser, User, UserCredentials, UserFacets, UserFilter, UserId, UserPage}, repository::UserRepositoryPort};
+use rust_web_server_lib::infra::auth::{jwt::JwtTokens, JwtConfig};
+use rust_web_server_lib::presentation::handlers::in_flight_handlers::InFlightState;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::auth::AuthState;
+use rust_web_server_lib::presentation::middleware::in_flight::InFlightRequests;
+
+const USER_ID: &str = "00000000-0000-0000-0000-000000000001";
+
+/// Repository whose lookups and updates never complete, recording when they are stopped.
+#[derive(Default)]
+struct StuckUserRepository {
+    stopped: Arc<AtomicBool>,
+}
+
+/// Sets its flag when dropped, i.e. when the future holding it is stopped.
+struct StopFlag(Arc<AtomicBool>);
+
+impl Drop for StopFlag {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+}
+
+impl StuckUserRepository {
+    async fn hang<T>(&self) -> T {
+        let _flag = StopFlag(self.stopped.clone());
+        std::future::pending().await
+    }
+}
+
+#[async_trait]
+impl UserRepositoryPort for StuckUserRepository {
+    async fn create_user(&self, _user: CreateUser, _password_hash: Option<PasswordHash>) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserCreationFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn get_user(&self, _id: UserId) -> Result<User, UserDomainError> {
+        self.hang().await
+    }
+
+    async fn get_user_by_email(&self, _email: Email) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserNotFound)
+    }
+
+    async fn get_user_credentials(&self, _email: Email) -> Result<UserCredentials, UserDomainError> {
+        Err(UserDomainError::UserNotFound)
+    }
+
+    async fn list_users(&self, _query: ListUsers) -> Result<UserPage, UserDomainError> {
+        Err(UserDomainError::UserListFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn search_users(&self, _filter: UserFilter) -> Result<UserPage, UserDomainError> {
+        Err(UserDomainError::UserListFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn count_user_facets(&self) -> Result<UserFacets, UserDomainError> {
+        Err(UserDomainError::UserListFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn update_user(&self, _user: UpdateUser) -> Result<User, UserDomainError> {
+        self.hang().await
+    }
+
+    async fn delete_user(&self, _id: UserId) -> Result<(), UserDomainError> {
+        Err(UserDomainError::UserDeletionFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn restore_user(&self, _id: UserId) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserUpdateFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn hard_delete_user(&self, _id: UserId) -> Result<(), UserDomainError> {
+        Err(UserDomainError::UserDeletionFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn set_legal_hold(&self, _id: UserId, _legal_hold: bool) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserUpdateFailed(StorageError::msg("storage failed")))
+    }
+
+    async fn set_role(&self, _id: UserId, _role: Role) -> Result<User, UserDomainError> {
+        Err(UserDomainError::UserUpdateFailed(StorageError::msg("storage failed")))
+    }
+}
+
+fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "in-flight-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+/// Returns an app tracking its requests with `requests`, and the flag of its stuck repository.
+fn app(requests: Option<InFlightRequests>) -> (axum::Router, Arc<AtomicBool>) {
+    let repository = StuckUserRepository::default();
+    let stopped = repository.stopped.clone();
+    let app = router(AppState {
+        admin_token: Some("secret".into()),
+        auth: AuthState { auth_service: Arc::new(AuthService::new(Arc::new(DisabledAuthenticator), Arc::new(jwt_tokens()))) },
+        in_flight: InFlightState { requests },
+        ..AppState::new(Arc::new(UserService::new(repository)))
+    });
+    (app, stopped)
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, token: Option<&str>) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let response = app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+/// Starts renaming the user as `user-1`, a request that never completes unless cancelled.
+fn start_update(app: &axum::Router) -> tokio::task::JoinHandle<(StatusCode, Value)> {
+    let token = jwt_tokens().issue("user-1", &[], &all_scopes()).unwrap().token;
+    let request = Request::builder()
+        .method(Method::PUT)
+        .uri(format!("/api/users/{}", USER_ID))
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::IF_MATCH, "*")
+        .body(Body::from(json!({ "name": "Janet" }).to_string()))
+        .unwrap();
+    let app = app.clone();
+    tokio::spawn(async move {
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    })
+}
+
+/// Waits until the update is in flight, returning it as listed.
+async fn in_flight_update(app: &axum::Router) -> Value {
+    for _ in 0..100 {
+        let (_, body) = send(app, Method::GET, "/api/admin/requests", Some("secret")).await;
+        let update = body["data"]["requests"].as_array().unwrap().iter().find(|request| request["method"] == "PUT").cloned();
+        if let Some(update) = update.filter(|update| update.get("principal").is_some()) {
+            return update;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("update not in flight");
+}
+
+#[tokio::test]
+async fn lists_the_requests_being_handled() {
+    let (app, _) = app(Some(InFlightRequests::new()));
+    let _update = start_update(&app);
+
+    let update = in_flight_update(&app).await;
+    assert_eq!(update["route"], "/api/users/{id}");
+    assert_eq!(update["path"], format!("/api/users/{}", USER_ID));
+    assert_eq!(update["principal"], "user-1");
+    assert!(update["request_id"].is_string());
+    assert!(update["elapsed_ms"].is_u64());
+
+    // The listing is in flight too, behind the update which started first
+    let (status, body) = send(&app, Method::GET, "/api/admin/requests", Some("secret")).await;
+    assert_eq!(status, StatusCode::OK);
+    let routes: Vec<_> = body["data"]["requests"].as_array().unwrap().iter().map(|request| request["route"].clone()).collect();
+    assert_eq!(routes, [json!("/api/users/{id}"), json!("/api/admin/requests")]);
+}
+
+#[tokio::test]
+async fn cancels_a_request_and_stops_its_handler() {
+    let (app, stopped) = app(Some(InFlightRequests::new()));
+    let update = start_update(&app);
+    let id = in_flight_update(&app).await["id"].as_u64().unwrap();
+
+    let (status, _) = send(&app, Method::DELETE, &format!("/api/admin/requests/{}", id), Some("secret")).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = tokio::time::timeout(Duration::from_secs(5), update).await.expect("request not cancelled").unwrap();
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["data"]["message"], "Request was cancelled");
+    assert!(stopped.load(Ordering::SeqCst));
+
+    // Handled requests leave the registry
+    let (_, body) = send(&app, Method::GET, "/api/admin/requests", Some("secret")).await;
+    assert_eq!(body["data"]["requests"].as_array().unwrap().len(), 1);
+    assert_eq!(send(&app, Method::DELETE, &format!("/api/admin/requests/{}", id), Some("secret")).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn cannot_cancel_unknown_requests() {
+    let (app, _) = app(Some(InFlightRequests::new()));
+
+    assert_eq!(send(&app, Method::DELETE, "/api/admin/requests/42", Some("secret")).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(send(&app, Method::DELETE, "/api/admin/requests/abc", Some("secret")).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn is_not_found_when_disabled() {
+    let (app, _) = app(None);
+
+    assert_eq!(send(&app, Method::GET, "/api/admin/requests", Some("secret")).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(send(&app, Method::DELETE, "/api/admin/requests/1", Some("secret")).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn requires_the_admin_token() {
+    let (app, _) = app(Some(InFlightRequests::new()));
+
+    assert_eq!(send(&app, Method::GET, "/api/admin/requests", None).await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(send(&app, Method::DELETE, "/api/admin/requests/1", Some("wrong")).await.0, StatusCode::UNAUTHORIZED);
+}
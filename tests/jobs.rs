@@ -0,0 +1,227 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::jobs::welcome_email::{WelcomeEmailJob, WELCOME_EMAIL_JOB};
+use rust_web_server_lib::application::jobs::{JobHandler, JobHandlers, JobQueuePort, NewJob};
+use rust_web_server_lib::application::ports::capability::Capability;
+use rust_web_server_lib::application::ports::email::{DisabledEmailSender, EmailMessage, EmailSenderPort};
+use rust_web_server_lib::domain::user::model::CreateUser;
+use rust_web_server_lib::infra::email::NoopEmailSender;
+use rust_web_server_lib::infra::jobs::{JobWorker, JobsConfig};
+use rust_web_server_lib::infra::storage::adapter::in_memory::job_queue::InMemoryJobQueue;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+
+/// E-mail sender recording the e-mails it sends, failing the first `failures` sends.
+#[derive(Default)]
+struct RecordingEmailSender {
+    sent: Mutex<Vec<EmailMessage>>,
+    failures: Mutex<usize>,
+}
+
+impl RecordingEmailSender {
+    fn failing(failures: usize) -> Self {
+        Self { failures: Mutex::new(failures), ..Self::default() }
+    }
+
+    fn sent(&self) -> Vec<EmailMessage> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+impl Capability for RecordingEmailSender {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+}
+
+#[async_trait]
+impl EmailSenderPort for RecordingEmailSender {
+    async fn send(&self, message: EmailMessage) -> eyre::Result<()> {
+        let mut failures = self.failures.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            eyre::bail!("mail server unavailable");
+        }
+        self.sent.lock().unwrap().push(message);
+        Ok(())
+    }
+}
+
+fn config(max_attempts: u32) -> JobsConfig {
+    JobsConfig { poll_interval_ms: 10, batch_size: 2, max_attempts, lease_secs: 60 }
+}
+
+/// Waits until the jobs of `queue` are run, all of them completed but `failed` failed for good.
+async fn wait_for_jobs(queue: &InMemoryJobQueue, failed: usize) {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while queue.pending().await != failed || queue.failed().await.len() != failed {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("jobs were not run");
+}
+
+#[tokio::test]
+async fn sends_the_welcome_emails_of_created_users() {
+    let queue = Arc::new(InMemoryJobQueue::new());
+    let service = UserService::new(InMemoryUserRepository::new()).with_job_queue(queue.clone());
+    let email = Arc::new(RecordingEmailSender::default());
+    let worker = JobWorker::spawn(queue.clone(), JobHandlers::new().with(Arc::new(WelcomeEmailJob::new(email.clone()))), config(5));
+
+    for i in 0..3 {
+        let user = CreateUser::new(format!("User {}", i), format!("user{}@example.com", i), 30).unwrap();
+        service.create_user(user).await.unwrap();
+    }
+    service.create_users_bulk(vec![CreateUser::new("Ada".to_string(), "ada@example.com".to_string(), 36).unwrap()]).await;
+
+    wait_for_jobs(&queue, 0).await;
+    worker.shutdown().await;
+    let recipients: Vec<String> = email.sent().into_iter().map(|message| message.to).collect();
+    assert_eq!(recipients, ["user0@example.com", "user1@example.com", "user2@example.com", "ada@example.com"]);
+    assert_eq!(email.sent()[3].subject, "Welcome");
+    assert!(email.sent()[3].body.contains("Hello Ada"));
+}
+
+#[tokio::test]
+async fn retries_failing_jobs() {
+    let queue = Arc::new(InMemoryJobQueue::new());
+    let email = Arc::new(RecordingEmailSender::failing(1));
+    let worker = JobWorker::spawn(queue.clone(), JobHandlers::new().with(Arc::new(WelcomeEmailJob::new(email.clone()))), config(5));
+
+    queue.enqueue(NewJob::new(WELCOME_EMAIL_JOB, json!({ "name": "Ada", "email": "ada@example.com" }))).await.unwrap();
+
+    wait_for_jobs(&queue, 0).await;
+    worker.shutdown().await;
+    assert_eq!(email.sent().len(), 1);
+}
+
+#[tokio::test]
+async fn gives_up_on_jobs_after_the_last_attempt() {
+    let queue = Arc::new(InMemoryJobQueue::new());
+    let email = Arc::new(RecordingEmailSender::failing(1));
+    let worker = JobWorker::spawn(queue.clone(), JobHandlers::new().with(Arc::new(WelcomeEmailJob::new(email.clone()))), config(1));
+
+    queue.enqueue(NewJob::new(WELCOME_EMAIL_JOB, json!({ "name": "Ada", "email": "ada@example.com" }))).await.unwrap();
+    // Jobs without a handler fail for good at once
+    queue.enqueue(NewJob::new("user.unknown", json!({}))).await.unwrap();
+
+    wait_for_jobs(&queue, 2).await;
+    worker.shutdown().await;
+    assert_eq!(queue.failed().await, ["mail server unavailable", "no handler for jobs of kind user.unknown"]);
+    assert!(email.sent().is_empty());
+}
+
+#[tokio::test]
+async fn logs_welcome_emails_without_a_mail_transport() {
+    let job = WelcomeEmailJob::new(Arc::new(DisabledEmailSender));
+
+    job.run(&json!({ "name": "Ada", "email": "ada@example.com" })).await.unwrap();
+    assert!(job.run(&Value::Null).await.is_err());
+}
+
+#[tokio::test]
+async fn sends_welcome_emails_right_away_without_a_job_queue() {
+    let email = Arc::new(RecordingEmailSender::default());
+    let service = UserService::new(InMemoryUserRepository::new()).with_email_sender(email.clone());
+
+    service.create_user(CreateUser::new("Ada".to_string(), "ada@example.com".to_string(), 36).unwrap()).await.unwrap();
+    service.create_users_bulk(vec![CreateUser::new("Alan".to_string(), "alan@example.com".to_string(), 41).unwrap()]).await;
+
+    let recipients: Vec<String> = email.sent().into_iter().map(|message| message.to).collect();
+    assert_eq!(recipients, ["ada@example.com", "alan@example.com"]);
+    assert!(email.sent()[0].body.contains("Hello Ada"));
+}
+
+#[tokio::test]
+async fn leaves_welcome_emails_to_the_jobs_with_a_job_queue() {
+    let queue = Arc::new(InMemoryJobQueue::new());
+    let email = Arc::new(RecordingEmailSender::default());
+    let service = UserService::new(InMemoryUserRepository::new()).with_job_queue(queue.clone()).with_email_sender(email.clone());
+
+    service.create_user(CreateUser::new("Ada".to_string(), "ada@example.com".to_string(), 36).unwrap()).await.unwrap();
+
+    assert!(email.sent().is_empty());
+    assert_eq!(queue.pending().await, 1);
+}
+
+#[tokio::test]
+async fn creates_users_whose_welcome_email_fails_to_be_sent() {
+    let service = UserService::new(InMemoryUserRepository::new()).with_email_sender(Arc::new(RecordingEmailSender::failing(1)));
+    assert!(service.create_user(CreateUser::new("Ada".to_string(), "ada@example.com".to_string(), 36).unwrap()).await.is_ok());
+
+    let service = UserService::new(InMemoryUserRepository::new()).with_email_sender(Arc::new(NoopEmailSender));
+    assert!(service.create_user(CreateUser::new("Ada".to_string(), "ada@example.com".to_string(), 36).unwrap()).await.is_ok());
+}
+
+#[tokio::test]
+async fn leases_claimed_jobs() {
+    let queue = InMemoryJobQueue::new();
+    queue.enqueue(NewJob::new("a", json!(1))).await.unwrap();
+    queue.enqueue(NewJob::new("b", json!(2))).await.unwrap();
+
+    let claimed = queue.claim(10, Duration::from_millis(50)).await.unwrap();
+    assert_eq!(claimed.iter().map(|job| job.kind.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+    assert!(queue.claim(10, Duration::from_millis(50)).await.unwrap().is_empty());
+
+    // Jobs neither completed nor failed are claimed again once their lease expires
+    queue.complete(claimed[0].id).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    let reclaimed = queue.claim(10, Duration::from_millis(50)).await.unwrap();
+    assert_eq!((reclaimed.len(), reclaimed[0].kind.as_str(), reclaimed[0].attempts), (1, "b", 2));
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::infra::storage::adapter::postgres::job_queue::PostgresJobQueue;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn claims_each_job_once() {
+        let db = TestDb::new().await.unwrap();
+        let queue = Arc::new(PostgresJobQueue::new(db.db()));
+        for i in 0..20 {
+            queue.enqueue(NewJob::new(WELCOME_EMAIL_JOB, json!({ "i": i }))).await.unwrap();
+        }
+
+        // Concurrent workers skip the jobs locked by each other
+        let claims = (0..4).map(|_| {
+            let queue = queue.clone();
+            tokio::spawn(async move { queue.claim(5, Duration::from_secs(60)).await.unwrap() })
+        });
+        let mut ids = Vec::new();
+        for claim in claims {
+            ids.extend(claim.await.unwrap().into_iter().map(|job| job.id));
+        }
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 20);
+        assert!(queue.claim(5, Duration::from_secs(60)).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn completes_retries_and_fails_jobs() {
+        let db = TestDb::new().await.unwrap();
+        let queue = PostgresJobQueue::new(db.db());
+        for kind in ["a", "b", "c"] {
+            queue.enqueue(NewJob::new(kind, json!({ "kind": kind }))).await.unwrap();
+        }
+
+        let jobs = queue.claim(10, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(jobs.iter().map(|job| (job.kind.as_str(), job.attempts)).collect::<Vec<_>>(), [("a", 1), ("b", 1), ("c", 1)]);
+        assert_eq!(jobs[0].payload, json!({ "kind": "a" }));
+        queue.complete(jobs[0].id).await.unwrap();
+        queue.fail(jobs[1].id, "failed", Some(Duration::ZERO)).await.unwrap();
+        queue.fail(jobs[2].id, "failed", None).await.unwrap();
+
+        let retried = queue.claim(10, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(retried.iter().map(|job| (job.kind.as_str(), job.attempts)).collect::<Vec<_>>(), [("b", 2)]);
+    }
+}
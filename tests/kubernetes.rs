@@ -0,0 +1,262 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+use tokio::time::{self, Instant};
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::kubernetes::termination::wait_for_termination;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{AppState, HttpServer, HttpServerConfig};
+use rust_web_server_lib::presentation::middleware::load_shedding::LoadSheddingPolicy;
+
+#[tokio::test(start_paused = true)]
+async fn waits_for_the_grace_delay_once_termination_is_requested() {
+    let (terminate, terminated) = oneshot::channel::<()>();
+    let termination = tokio::spawn(wait_for_termination(
+        async {
+            let _ = terminated.await;
+        },
+        Duration::from_secs(10),
+    ));
+
+    time::sleep(Duration::from_secs(60)).await;
+    assert!(!termination.is_finished());
+
+    let requested = Instant::now();
+    terminate.send(()).unwrap();
+    termination.await.unwrap();
+    assert!(requested.elapsed() >= Duration::from_secs(10), "terminated after {:?}", requested.elapsed());
+    assert!(requested.elapsed() < Duration::from_secs(11), "terminated after {:?}", requested.elapsed());
+}
+
+#[tokio::test]
+async fn keeps_serving_during_the_grace_delay() {
+    let state = AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())));
+    let config = HttpServerConfig { port: 0, shutdown_timeout: Duration::from_secs(10), cors: None, max_body_bytes: 2 * 1024 * 1024, compression: None, load_shedding: LoadSheddingPolicy::default() };
+    let server = HttpServer::new(state, config).await.unwrap();
+    let addr = SocketAddr::from(([127, 0, 0, 1], server.local_addr().unwrap().port()));
+    let (terminate, terminated) = oneshot::channel::<()>();
+    let grace = Duration::from_millis(500);
+    let server = tokio::spawn(server.run_until(wait_for_termination(
+        async {
+            let _ = terminated.await;
+        },
+        grace,
+    )));
+
+    let requested = std::time::Instant::now();
+    terminate.send(()).unwrap();
+    time::sleep(Duration::from_millis(100)).await;
+    // Still routed to the pod until its endpoint is removed
+    let response = reqwest::get(format!("http://{}/healthz", addr)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+    server.await.unwrap().unwrap();
+    assert!(requested.elapsed() >= grace, "stopped after {:?}", requested.elapsed());
+    assert!(reqwest::get(format!("http://{}/healthz", addr)).await.is_err());
+}
+
+/// Leader election against a stubbed API server.
+#[cfg(feature = "kubernetes")]
+mod leader_election {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use chrono::{TimeDelta, Utc};
+    use tokio::sync::watch;
+    use tokio::time::{self, Instant};
+
+    use rust_web_server_lib::infra::kubernetes::client::ApiResponse;
+    use rust_web_server_lib::infra::kubernetes::leader_election::{Lease, LeaseClient, LeaseSpec, LeaderElection, MicroTime};
+    use rust_web_server_lib::infra::kubernetes::{LeaderElectionConfig, PodMetadata};
+
+    const LEASE_DURATION_SECS: u32 = 15;
+
+    /// Attempts are made every third of the lease duration.
+    const RETRY_PERIOD: Duration = Duration::from_secs(5);
+
+    /// The lease stored by the stubbed API server, rejecting updates of stale versions like
+    /// the API server does.
+    #[derive(Default)]
+    struct StubLeases {
+        lease: Mutex<Option<Lease>>,
+        replacements: AtomicUsize,
+        /// Whether requests fail.
+        failing: AtomicBool,
+        /// Whether requests never complete.
+        hanging: AtomicBool,
+    }
+
+    impl StubLeases {
+        fn lease(&self) -> Lease {
+            self.lease.lock().unwrap().clone().unwrap()
+        }
+
+        /// Stores the lease held by `holder`, as renewed `renewed` ago.
+        fn hold(&self, holder: &str, renewed: TimeDelta) {
+            let mut stored = self.lease.lock().unwrap();
+            let version = stored.as_ref().map_or(0, version);
+            let mut lease = Lease::new("jobs", LeaseSpec {
+                holder_identity: Some(holder.to_string()),
+                lease_duration_seconds: Some(LEASE_DURATION_SECS),
+                acquire_time: Some(MicroTime(Utc::now() - renewed)),
+                renew_time: Some(MicroTime(Utc::now() - renewed)),
+                lease_transitions: Some(0),
+            });
+            lease.metadata.resource_version = Some((version + 1).to_string());
+            *stored = Some(lease);
+        }
+
+        fn replacements(&self) -> usize {
+            self.replacements.load(Ordering::SeqCst)
+        }
+
+        async fn fault(&self) -> eyre::Result<()> {
+            if self.hanging.load(Ordering::SeqCst) {
+                std::future::pending::<()>().await;
+            }
+            if self.failing.load(Ordering::SeqCst) {
+                eyre::bail!("the API server is unavailable");
+            }
+            Ok(())
+        }
+    }
+
+    fn version(lease: &Lease) -> u64 {
+        lease.metadata.resource_version.as_deref().map_or(0, |version| version.parse().unwrap())
+    }
+
+    #[derive(Clone, Default)]
+    struct StubLeaseClient(Arc<StubLeases>);
+
+    #[async_trait]
+    impl LeaseClient for StubLeaseClient {
+        async fn get_lease(&self, _path: &str) -> eyre::Result<ApiResponse<Lease>> {
+            self.0.fault().await?;
+            Ok(self.0.lease.lock().unwrap().clone().map_or(ApiResponse::NotFound, ApiResponse::Ok))
+        }
+
+        async fn create_lease(&self, _collection_path: &str, lease: &Lease) -> eyre::Result<ApiResponse<Lease>> {
+            self.0.fault().await?;
+            let mut stored = self.0.lease.lock().unwrap();
+            if stored.is_some() {
+                return Ok(ApiResponse::Conflict);
+            }
+            let mut lease = lease.clone();
+            lease.metadata.resource_version = Some("1".to_string());
+            *stored = Some(lease.clone());
+            Ok(ApiResponse::Ok(lease))
+        }
+
+        async fn replace_lease(&self, _path: &str, lease: &Lease) -> eyre::Result<ApiResponse<Lease>> {
+            self.0.fault().await?;
+            let mut stored = self.0.lease.lock().unwrap();
+            let Some(current) = stored.as_ref() else {
+                return Ok(ApiResponse::NotFound);
+            };
+            if current.metadata.resource_version != lease.metadata.resource_version {
+                return Ok(ApiResponse::Conflict);
+            }
+            let mut lease = lease.clone();
+            lease.metadata.resource_version = Some((version(current) + 1).to_string());
+            *stored = Some(lease.clone());
+            self.0.replacements.fetch_add(1, Ordering::SeqCst);
+            Ok(ApiResponse::Ok(lease))
+        }
+    }
+
+    fn spawn(client: &StubLeaseClient, name: &str) -> LeaderElection {
+        let pod = PodMetadata { name: name.to_string(), namespace: "default".to_string(), node_name: None, pod_ip: None };
+        LeaderElection::spawn(client.clone(), &pod, LeaderElectionConfig { lease_name: "jobs".to_string(), lease_duration_secs: LEASE_DURATION_SECS })
+    }
+
+    /// Waits for leadership to be gained or lost, returning whether it is held.
+    async fn changed(is_leader: &mut watch::Receiver<bool>) -> bool {
+        time::timeout(Duration::from_secs(60), is_leader.changed()).await.expect("leadership did not change").unwrap();
+        *is_leader.borrow_and_update()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn acquires_renews_and_releases_the_lease() {
+        let client = StubLeaseClient::default();
+        let election = spawn(&client, "pod-a");
+        let mut is_leader = election.subscribe();
+
+        assert!(changed(&mut is_leader).await);
+        let lease = client.0.lease();
+        assert_eq!(lease.spec.holder_identity.as_deref(), Some("pod-a"));
+        assert_eq!(lease.spec.lease_transitions, Some(0));
+
+        time::sleep(RETRY_PERIOD * 2 + Duration::from_secs(1)).await;
+        assert_eq!(client.0.replacements(), 2);
+        assert!(election.is_leader());
+
+        election.shutdown().await;
+        assert_eq!(client.0.lease().spec.holder_identity, None);
+        assert!(!*is_leader.borrow());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn takes_over_the_lease_of_another_replica_once_expired() {
+        let client = StubLeaseClient::default();
+        client.0.hold("pod-b", TimeDelta::zero());
+        let election = spawn(&client, "pod-a");
+        let mut is_leader = election.subscribe();
+
+        time::sleep(RETRY_PERIOD * 2).await;
+        assert!(!election.is_leader());
+        assert_eq!(client.0.lease().spec.holder_identity.as_deref(), Some("pod-b"));
+
+        client.0.hold("pod-b", TimeDelta::seconds(i64::from(LEASE_DURATION_SECS) + 1));
+        assert!(changed(&mut is_leader).await);
+        let lease = client.0.lease();
+        assert_eq!(lease.spec.holder_identity.as_deref(), Some("pod-a"));
+        assert_eq!(lease.spec.lease_transitions, Some(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn loses_leadership_to_another_replica_holding_the_lease() {
+        let client = StubLeaseClient::default();
+        let election = spawn(&client, "pod-a");
+        let mut is_leader = election.subscribe();
+        assert!(changed(&mut is_leader).await);
+
+        client.0.hold("pod-b", TimeDelta::zero());
+        assert!(!changed(&mut is_leader).await);
+        assert_eq!(client.0.lease().spec.holder_identity.as_deref(), Some("pod-b"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drops_leadership_before_the_lease_expires_when_renewals_fail() {
+        let client = StubLeaseClient::default();
+        let election = spawn(&client, "pod-a");
+        let mut is_leader = election.subscribe();
+        assert!(changed(&mut is_leader).await);
+
+        let failing = Instant::now();
+        client.0.failing.store(true, Ordering::SeqCst);
+        assert!(!changed(&mut is_leader).await);
+        assert!(failing.elapsed() < Duration::from_secs(u64::from(LEASE_DURATION_SECS)), "dropped after {:?}", failing.elapsed());
+
+        // Leadership is regained once the API server answers again
+        client.0.failing.store(false, Ordering::SeqCst);
+        assert!(changed(&mut is_leader).await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn drops_leadership_before_the_lease_expires_when_renewals_hang() {
+        let client = StubLeaseClient::default();
+        let election = spawn(&client, "pod-a");
+        let mut is_leader = election.subscribe();
+        assert!(changed(&mut is_leader).await);
+
+        let hanging = Instant::now();
+        client.0.hanging.store(true, Ordering::SeqCst);
+        assert!(!changed(&mut is_leader).await);
+        assert!(hanging.elapsed() < Duration::from_secs(u64::from(LEASE_DURATION_SECS)), "dropped after {:?}", hanging.elapsed());
+    }
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::{Body, Bytes};
+use axum::http::{header, Method, Request, StatusCode};
+use axum::routing::get;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, Notify};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router_with_load_shedding, AppState};
+use rust_web_server_lib::presentation::middleware::load_shedding::LoadSheddingPolicy;
+
+use common::send;
+
+/// Returns the status and the body of the response of `app` to `GET uri`.
+async fn get_json(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    send(app, Method::GET, uri, None, None).await
+}
+
+#[tokio::test]
+async fn answers_the_requests_taking_too_long_with_408() {
+    let router = axum::Router::new()
+        .route("/slow", get(|| async { tokio::time::sleep(Duration::from_secs(60)).await }))
+        .route("/fast", get(|| async { StatusCode::NO_CONTENT }));
+    let app = LoadSheddingPolicy { request_timeout: Some(Duration::from_millis(50)), max_concurrent_requests: None }.apply(router);
+
+    let (status, body) = get_json(&app, "/slow").await;
+    assert_eq!(status, StatusCode::REQUEST_TIMEOUT);
+    assert_eq!(body, json!({ "status_code": 408, "data": { "code": "request_timeout", "message": "Request timed out" } }));
+    assert_eq!(get_json(&app, "/fast").await.0, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn sheds_the_requests_beyond_the_concurrency_limit_with_503() {
+    let (started, mut handling) = mpsc::channel(1);
+    let release = Arc::new(Notify::new());
+    let held = release.clone();
+    let router = axum::Router::new()
+        .route(
+            "/held",
+            get(move || {
+                let (started, held) = (started.clone(), held.clone());
+                async move {
+                    started.send(()).await.unwrap();
+                    held.notified().await;
+                }
+            }),
+        )
+        .route("/other", get(|| async { StatusCode::NO_CONTENT }));
+    let app = LoadSheddingPolicy { request_timeout: None, max_concurrent_requests: Some(1) }.apply(router);
+
+    let first = tokio::spawn({
+        let app = app.clone();
+        async move { get_json(&app, "/held").await.0 }
+    });
+    handling.recv().await.unwrap();
+
+    // The limit is shared by every route
+    let response = app.clone().oneshot(Request::get("/other").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["data"], json!({ "code": "unavailable", "message": "Too many concurrent requests" }));
+
+    release.notify_one();
+    assert_eq!(first.await.unwrap(), StatusCode::OK);
+    assert_eq!(get_json(&app, "/other").await.0, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn keeps_answering_the_probes_while_the_api_is_saturated() {
+    let policy = LoadSheddingPolicy { request_timeout: None, max_concurrent_requests: Some(1) };
+    let app = router_with_load_shedding(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))), &policy);
+
+    // The body of the request never ends once read, so it holds the only slot of the API
+    let (started, mut reading) = mpsc::channel(1);
+    let body = Body::from_stream(futures_util::stream::unfold(started, |started| async move {
+        started.send(()).await.unwrap();
+        std::future::pending::<Option<(Result<Bytes, std::io::Error>, mpsc::Sender<()>)>>().await
+    }));
+    let request = Request::post("/api/v1/users").header(header::CONTENT_TYPE, "application/json").body(body).unwrap();
+    let held = tokio::spawn(app.clone().oneshot(request));
+    reading.recv().await.unwrap();
+
+    // Both versions of the API share the limit, unlike the probes
+    assert_eq!(get_json(&app, "/api/v1/users").await.0, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(get_json(&app, "/api/users").await.0, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(get_json(&app, "/healthz").await.0, StatusCode::OK);
+    assert_eq!(get_json(&app, "/readyz").await.0, StatusCode::OK);
+
+    held.abort();
+}
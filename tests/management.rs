@@ -0,0 +1,146 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, Request, Response, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::in_flight_handlers::InFlightState;
+use rust_web_server_lib::presentation::handlers::management_handlers::ManagementState;
+use rust_web_server_lib::presentation::http::{router, AppState, HttpServer, HttpServerConfig};
+use rust_web_server_lib::presentation::middleware::admin::AdminToken;
+use rust_web_server_lib::presentation::middleware::in_flight::InFlightRequests;
+use rust_web_server_lib::presentation::middleware::load_shedding::LoadSheddingPolicy;
+
+const TOKEN: &str = "management-token";
+
+fn state(management: ManagementState) -> AppState<UserService<InMemoryUserRepository>> {
+    AppState {
+        management: Some(management),
+        admin_token: Some("admin-token".into()),
+        in_flight: InFlightState { requests: Some(InFlightRequests::new()) },
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    }
+}
+
+fn management(base_path: &str) -> ManagementState {
+    ManagementState::new(base_path, AdminToken(TOKEN.into()))
+}
+
+async fn get(app: &axum::Router, uri: &str, token: Option<&str>) -> Response<Body> {
+    let mut request = Request::get(uri);
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    app.clone().oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+}
+
+/// Sends a raw GET request on a blocking thread, returning the raw response.
+async fn send(addr: SocketAddr, path: &str, token: Option<&str>) -> String {
+    let authorization = token.map(|token| format!("authorization: Bearer {}\r\n", token)).unwrap_or_default();
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{}\r\n", path, authorization);
+    tokio::task::spawn_blocking(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        String::from_utf8_lossy(&response).into_owned()
+    })
+    .await
+    .unwrap()
+}
+
+async fn json(response: Response<Body>) -> Value {
+    serde_json::from_slice(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn serves_the_health_endpoints_without_the_token() {
+    let app = router(state(management("/actuator")));
+
+    for path in ["/actuator/health", "/actuator/health/liveness", "/actuator/health/readiness"] {
+        let response = get(&app, path, None).await;
+        assert_eq!(response.status(), StatusCode::OK, "{}", path);
+        assert_eq!(json(response).await["data"]["status"], "up", "{}", path);
+    }
+}
+
+#[tokio::test]
+async fn guards_the_other_endpoints_with_the_management_token() {
+    let app = router(state(management("/actuator")));
+
+    for path in ["/actuator", "/actuator/info", "/actuator/metrics", "/actuator/loggers", "/actuator/routes", "/actuator/dependencies"] {
+        assert_eq!(get(&app, path, None).await.status(), StatusCode::UNAUTHORIZED, "{}", path);
+        assert_eq!(get(&app, path, Some("admin-token")).await.status(), StatusCode::UNAUTHORIZED, "{}", path);
+        assert_eq!(get(&app, path, Some(TOKEN)).await.status(), StatusCode::OK, "{}", path);
+    }
+}
+
+#[tokio::test]
+async fn nests_the_endpoints_under_the_base_path() {
+    let app = router(state(management("/ops/manage")));
+
+    let response = get(&app, "/ops/manage", Some(TOKEN)).await;
+    let endpoints = json(response).await["data"]["endpoints"].clone();
+    assert_eq!(endpoints[0], json!({ "name": "health", "href": "/ops/manage/health" }));
+    assert_eq!(endpoints.as_array().unwrap().len(), 8);
+    assert_eq!(get(&app, "/actuator/info", Some(TOKEN)).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn reports_the_info_and_metrics_of_the_server() {
+    let app = router(state(management("/actuator")));
+
+    let info = json(get(&app, "/actuator/info", Some(TOKEN)).await).await;
+    assert_eq!(info["data"]["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(info["data"]["api_versions"], json!(["/api/v1"]));
+
+    let metrics = json(get(&app, "/actuator/metrics", Some(TOKEN)).await).await;
+    assert!(metrics["data"]["uptime_secs"].is_u64());
+    // The management routes are tracked in flight like the other internal routes
+    assert_eq!(metrics["data"]["requests_in_flight"], 1);
+    assert_eq!(metrics["data"]["slos"]["objectives"], json!([]));
+}
+
+#[tokio::test]
+async fn lists_the_mounted_routes() {
+    let app = router(state(management("/actuator")));
+
+    let routes = json(get(&app, "/actuator/routes", Some(TOKEN)).await).await["data"]["routes"].clone();
+    let routes = routes.as_array().unwrap();
+    assert!(routes.contains(&json!({ "module": "users", "path": "/api/v1", "preset": "PublicApi" })));
+    assert!(routes.contains(&json!({ "module": "admin", "path": "/api/v1/admin", "preset": "Admin" })));
+    assert!(routes.contains(&json!({ "module": "management", "path": "/actuator", "preset": "InternalApi" })));
+    assert!(!routes.iter().any(|route| route["module"] == "scim"));
+}
+
+#[tokio::test]
+async fn serves_the_endpoints_on_their_own_port() {
+    let management = ManagementState { port: Some(0), ..management("/actuator") };
+    let config = HttpServerConfig { port: 0, shutdown_timeout: Duration::from_secs(1), cors: None, max_body_bytes: 1024, compression: None, load_shedding: LoadSheddingPolicy::default() };
+    let server = HttpServer::new(state(management), config).await.unwrap();
+    let api = SocketAddr::from(([127, 0, 0, 1], server.local_addr().unwrap().port()));
+    let ops = SocketAddr::from(([127, 0, 0, 1], server.management_addr().unwrap().unwrap().port()));
+    tokio::spawn(server.run_until(std::future::pending()));
+
+    let response = send(ops, "/actuator/info", Some(TOKEN)).await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    let response = send(api, "/actuator/info", Some(TOKEN)).await;
+    assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+    // The API is not served on the management port
+    let response = send(ops, "/api/v1/users", None).await;
+    assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+}
+
+#[tokio::test]
+async fn is_not_mounted_unless_enabled() {
+    let app = router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))));
+
+    assert_eq!(get(&app, "/actuator/health", None).await.status(), StatusCode::NOT_FOUND);
+    assert_eq!(get(&app, "/actuator/info", Some(TOKEN)).await.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::ConnectInfo;
+use axum::http::{Method, StatusCode};
+use axum::routing::post;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::AppState;
+use rust_web_server_lib::presentation::middleware::admin::AdminToken;
+use rust_web_server_lib::presentation::middleware::rate_limit::{RateLimitPolicy, RateLimiter};
+use rust_web_server_lib::presentation::presets::MiddlewarePreset;
+
+use common::{request, send_request};
+
+/// Returns a module served with `preset`, in a state limiting clients to 2 requests a minute.
+fn module(preset: MiddlewarePreset) -> axum::Router {
+    let policy = RateLimitPolicy { requests: 2, period: Duration::from_secs(60), route_overrides: Vec::new(), class_overrides: Vec::new(), trust_forwarded_for: false };
+    let state = AppState { rate_limiter: Some(RateLimiter::new(policy).unwrap()), ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))) };
+    preset.apply(axum::Router::new().route("/events", post(|| async { StatusCode::ACCEPTED })), &state)
+}
+
+fn token() -> AdminToken {
+    AdminToken("module-token".into())
+}
+
+/// Sends 3 requests from the same client, with `token` if any, returning their statuses.
+async fn send_three(app: &axum::Router, token: Option<&str>) -> Vec<StatusCode> {
+    let mut statuses = Vec::new();
+    for _ in 0..3 {
+        let mut request = request(Method::POST, "/events", token, None);
+        request.extensions_mut().insert(ConnectInfo("10.0.0.1:1234".parse::<SocketAddr>().unwrap()));
+        statuses.push(send_request(app, request).await.0);
+    }
+    statuses
+}
+
+#[tokio::test]
+async fn limits_the_rate_of_the_public_api() {
+    let app = module(MiddlewarePreset::PublicApi);
+
+    assert_eq!(send_three(&app, None).await, [StatusCode::ACCEPTED, StatusCode::ACCEPTED, StatusCode::TOO_MANY_REQUESTS]);
+}
+
+#[tokio::test]
+async fn guards_the_admin_routes_with_the_token_within_the_rate_limit() {
+    let app = module(MiddlewarePreset::Admin(token()));
+
+    assert_eq!(send_three(&app, Some("module-token")).await, [StatusCode::ACCEPTED, StatusCode::ACCEPTED, StatusCode::TOO_MANY_REQUESTS]);
+    // Rejected requests count against the limit of the client too
+    assert_eq!(send_three(&module(MiddlewarePreset::Admin(token())), None).await, [StatusCode::UNAUTHORIZED, StatusCode::UNAUTHORIZED, StatusCode::TOO_MANY_REQUESTS]);
+}
+
+#[tokio::test]
+async fn guards_the_internal_api_with_the_token_without_limiting_its_rate() {
+    let app = module(MiddlewarePreset::InternalApi(token()));
+
+    assert_eq!(send_three(&app, Some("module-token")).await, [StatusCode::ACCEPTED; 3]);
+    assert_eq!(send_three(&app, Some("other-token")).await, [StatusCode::UNAUTHORIZED; 3]);
+}
+
+#[tokio::test]
+async fn limits_the_rate_of_webhook_receivers_without_requiring_a_token() {
+    let app = module(MiddlewarePreset::WebhookReceiver);
+
+    assert_eq!(send_three(&app, None).await, [StatusCode::ACCEPTED, StatusCode::ACCEPTED, StatusCode::TOO_MANY_REQUESTS]);
+}
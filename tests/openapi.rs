@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+fn app() -> axum::Router {
+    router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))))
+}
+
+async fn get(app: axum::Router, uri: &str) -> (StatusCode, Vec<u8>) {
+    let response = app.oneshot(Request::get(uri).body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    (status, axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec())
+}
+
+async fn spec() -> Value {
+    let (status, body) = get(app(), "/api/v1/docs/openapi.json").await;
+    assert_eq!(status, StatusCode::OK);
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn serves_openapi_spec() {
+    insta::assert_json_snapshot!(spec().await);
+}
+
+#[tokio::test]
+async fn serves_swagger_ui() {
+    let (status, body) = get(app(), "/api/v1/docs").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert!(String::from_utf8(body).unwrap().contains("/api/v1/docs/openapi.json"));
+}
+
+/// Every documented operation is routed to a handler: the router answers unrouted requests with
+/// a 405, or a 404 with an empty body, whereas handlers describe their 404s.
+#[tokio::test]
+async fn documented_operations_are_routed() {
+    let spec = spec().await;
+
+    for (path, operations) in spec["paths"].as_object().unwrap() {
+        for method in operations.as_object().unwrap().keys() {
+            let uri = path.replace("{id}", "missing");
+            let request = Request::builder()
+                .method(Method::from_bytes(method.to_uppercase().as_bytes()).unwrap())
+                .uri(&uri)
+                .header("content-type", "application/json")
+                .body(Body::from("{}"))
+                .unwrap();
+            let response = app().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+            assert_ne!(status, StatusCode::METHOD_NOT_ALLOWED, "{} {} is not routed", method, path);
+            assert!(status != StatusCode::NOT_FOUND || !body.is_empty(), "{} {} is not routed", method, path);
+        }
+    }
+}
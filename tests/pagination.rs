@@ -0,0 +1,212 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::user_handlers::ApiError;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::pagination::{Cursor, CursorError, CursorKey, Page};
+
+use common::send;
+
+/// Position of a user in a list sorted by name: its name and id.
+type NamePosition = (String, String);
+
+fn position(name: &str, id: &str) -> NamePosition {
+    (name.to_string(), id.to_string())
+}
+
+#[test]
+fn decodes_the_position_of_encoded_cursors() {
+    let key = CursorKey::new("cursor-secret");
+    let cursor = Cursor::new(position("Jane Doe", "4c8a")).encode(&key);
+
+    // Cursors are opaque in query strings
+    assert!(cursor.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')), "{}", cursor);
+    assert_eq!(Cursor::<NamePosition>::decode(&cursor, &key).unwrap().position, position("Jane Doe", "4c8a"));
+}
+
+#[test]
+fn refuses_cursors_altered_or_signed_with_another_key() {
+    let key = CursorKey::new("cursor-secret");
+    let cursor = Cursor::new(position("Jane Doe", "4c8a")).encode(&key);
+
+    assert_eq!(Cursor::<NamePosition>::decode(&cursor, &CursorKey::new("other-secret")), Err(CursorError::InvalidSignature));
+    assert_eq!(Cursor::<NamePosition>::decode(&cursor, &CursorKey::random()), Err(CursorError::InvalidSignature));
+
+    let (_, signature) = cursor.split_once('.').unwrap();
+    let forged = format!("{}.{}", base64_url(br#"["Aaron","0000"]"#), signature);
+    assert_eq!(Cursor::<NamePosition>::decode(&forged, &key), Err(CursorError::InvalidSignature));
+}
+
+#[test]
+fn refuses_malformed_cursors() {
+    let key = CursorKey::new("cursor-secret");
+
+    assert_eq!(Cursor::<NamePosition>::decode("", &key), Err(CursorError::Malformed));
+    assert_eq!(Cursor::<NamePosition>::decode("not a cursor", &key), Err(CursorError::Malformed));
+    assert_eq!(Cursor::<NamePosition>::decode("!!.!!", &key), Err(CursorError::Malformed));
+    // Signed positions of another shape are not positions of the list
+    let other = Cursor::new(42u64).encode(&key);
+    assert_eq!(Cursor::<NamePosition>::decode(&other, &key), Err(CursorError::Malformed));
+}
+
+#[test]
+fn reports_refused_cursors_as_invalid_requests() {
+    let ApiError::InvalidRequest(errors) = ApiError::from(CursorError::InvalidSignature) else { panic!("expected an invalid request") };
+
+    assert_eq!(errors.errors().len(), 1);
+    assert_eq!(errors.errors()[0].field, "cursor");
+}
+
+#[test]
+fn links_full_pages_to_the_next_page() {
+    let key = CursorKey::new("cursor-secret");
+    let users = vec![position("Jane Doe", "4c8a"), position("John Doe", "1f2e")];
+
+    let page = Page::new(users.clone(), 2, &key, |last| last.clone());
+    let next = Cursor::<NamePosition>::decode(page.next_cursor.as_deref().unwrap(), &key).unwrap();
+    assert_eq!(next.position, position("John Doe", "1f2e"));
+    assert_eq!(page.items, users);
+
+    // Shorter pages are the last ones
+    assert_eq!(Page::new(users.clone(), 3, &key, |last| last.clone()).next_cursor, None);
+    assert_eq!(Page::new(Vec::<NamePosition>::new(), 2, &key, |last| last.clone()).next_cursor, None);
+}
+
+#[test]
+fn serializes_pages_with_the_cursor_of_the_next_page() {
+    let key = CursorKey::new("cursor-secret");
+    let page = Page::new(vec![position("Jane Doe", "4c8a")], 1, &key, |last| last.1.clone()).map(|(name, _)| name);
+
+    let cursor = Cursor::new("4c8a".to_string()).encode(&key);
+    assert_eq!(serde_json::to_value(&page).unwrap(), json!({ "items": ["Jane Doe"], "next_cursor": cursor }));
+    assert_eq!(serde_json::to_value(Page::new(Vec::<String>::new(), 1, &key, |last| last.clone())).unwrap(), json!({ "items": [], "next_cursor": null }));
+}
+
+/// Lists of users paged through by [`assert_pages_neither_overlap_nor_skip`], all ordering the
+/// users it creates by name: by name, by decreasing age and searched among adults.
+const LISTS: [&str; 3] = ["/api/v1/users?limit=2", "/api/v1/users?limit=2&sort_by=age&order=desc", "/api/v1/users/search?limit=2&min_age=18"];
+
+/// Users created before each page of a list is requested, with their age. Each batch after the
+/// first holds a user ordered before the last user of the previous page, and one after it.
+const BATCHES: [&[(&str, u8)]; 3] = [
+    &[("Anna", 50), ("Cara", 40), ("Emma", 30), ("Gina", 20), ("Iris", 18)],
+    &[("Bea", 45), ("Dana", 35)],
+    &[("Ava", 60), ("Hana", 19)],
+];
+
+/// Pages through the list `uri` of `app`, which has no users, creating the users of the next
+/// batch before requesting each page, and checks that every page starts right after the
+/// previous one: no user is listed twice, and only the users created before the last user of
+/// a page are missed.
+async fn assert_pages_neither_overlap_nor_skip(app: &axum::Router, uri: &str) {
+    let (mut pages, mut cursor) = (Vec::new(), None::<String>);
+    for batch in BATCHES.into_iter().chain(std::iter::repeat(&[] as &[(&str, u8)])) {
+        for (name, age) in batch {
+            let body = json!({ "name": name, "email": format!("{}@example.com", name.to_lowercase()), "age": age });
+            assert_eq!(send(app, Method::POST, "/api/v1/users", None, Some(body)).await.0, StatusCode::CREATED);
+        }
+
+        let page_uri = match &cursor {
+            Some(cursor) => format!("{}&cursor={}", uri, cursor),
+            None => uri.to_string(),
+        };
+        let (status, body) = send(app, Method::GET, &page_uri, None, None).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        pages.push(body["data"]["users"].as_array().unwrap().iter().map(|user| user["name"].as_str().unwrap().to_string()).collect::<Vec<_>>());
+        match body["data"]["next_cursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+
+    assert_eq!(pages, [vec!["Anna", "Cara"], vec!["Dana", "Emma"], vec!["Gina", "Hana"], vec!["Iris"]], "{}", uri);
+}
+
+fn in_memory_app() -> axum::Router {
+    router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))))
+}
+
+#[tokio::test]
+async fn pages_through_the_users_created_between_pages() {
+    for uri in LISTS {
+        assert_pages_neither_overlap_nor_skip(&in_memory_app(), uri).await;
+    }
+}
+
+#[tokio::test]
+async fn refuses_the_cursors_of_lists_in_another_order() {
+    let app = in_memory_app();
+    for (name, email) in [("Anna", "anna@example.com"), ("Cara", "cara@example.com")] {
+        let body = json!({ "name": name, "email": email, "age": 30 });
+        send(&app, Method::POST, "/api/v1/users", None, Some(body)).await;
+    }
+    let (_, body) = send(&app, Method::GET, "/api/v1/users?limit=1", None, None).await;
+    let cursor = body["data"]["next_cursor"].as_str().unwrap();
+
+    for uri in [format!("/api/v1/users?limit=1&sort_by=age&cursor={}", cursor), format!("/api/v1/users?limit=1&order=desc&cursor={}", cursor), "/api/v1/users?cursor=forged".to_string()] {
+        let (status, body) = send(&app, Method::GET, &uri, None, None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["data"]["errors"][0]["field"], "cursor");
+    }
+    // Searches are sorted by name
+    let (status, _) = send(&app, Method::GET, &format!("/api/v1/users/search?limit=1&cursor={}", cursor), None, None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+fn base64_url(bytes: &[u8]) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[cfg(feature = "testing")]
+mod postgres {
+    use std::sync::Arc;
+
+    use rust_web_server_lib::application::flows::user_service::UserService;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+    use rust_web_server_lib::presentation::http::{router, AppState};
+
+    use super::{assert_pages_neither_overlap_nor_skip, LISTS};
+
+    #[tokio::test]
+    async fn pages_through_the_users_created_between_pages() {
+        for uri in LISTS {
+            let db = TestDb::new().await.unwrap();
+            let app = router(AppState::new(Arc::new(UserService::new(UserRepository::new(db.db())))));
+
+            assert_pages_neither_overlap_nor_skip(&app, uri).await;
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::sync::Arc;
+
+    use rust_web_server_lib::application::flows::user_service::UserService;
+    use rust_web_server_lib::infra::storage::adapter::sqlite::user_repository::SqliteUserRepository;
+    use rust_web_server_lib::infra::storage::adapter::sqlite::{db_connect, run_migrations};
+    use rust_web_server_lib::infra::storage::PoolConfig;
+    use rust_web_server_lib::presentation::http::{router, AppState};
+
+    use super::{assert_pages_neither_overlap_nor_skip, LISTS};
+
+    #[tokio::test]
+    async fn pages_through_the_users_created_between_pages() {
+        let pool = PoolConfig { max_connections: 5, min_connections: 0, acquire_timeout_secs: 30, idle_timeout_secs: None, max_lifetime_secs: None, connect_attempts: 1 };
+        for uri in LISTS {
+            let db = db_connect("sqlite::memory:", &pool).await.unwrap();
+            run_migrations(&db).await.unwrap();
+            let app = router(AppState::new(Arc::new(UserService::new(SqliteUserRepository::new(db)))));
+
+            assert_pages_neither_overlap_nor_skip(&app, uri).await;
+        }
+    }
+}
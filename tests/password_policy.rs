@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use argon2::Params;
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::password_policy::{BreachedPasswordRule, MinLengthRule, PasswordPolicy};
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::ports::breached_passwords::BreachedPasswordsPort;
+use rust_web_server_lib::domain::user::error::UserDomainError;
+use rust_web_server_lib::domain::user::model::CreateUser;
+use rust_web_server_lib::domain::user::validation::PasswordViolation;
+use rust_web_server_lib::infra::auth::password::Argon2PasswordHasher;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+/// Breaches listing `password123` 5 times, or failing every lookup.
+struct StubBreaches {
+    available: bool,
+}
+
+#[async_trait]
+impl BreachedPasswordsPort for StubBreaches {
+    async fn breach_count(&self, password: &str) -> eyre::Result<u64> {
+        if !self.available {
+            eyre::bail!("breaches unavailable");
+        }
+        Ok(if password == "password123" { 5 } else { 0 })
+    }
+}
+
+fn policy(breaches_available: bool) -> PasswordPolicy {
+    PasswordPolicy::new()
+        .with_rule(Arc::new(MinLengthRule::new(12)))
+        .with_rule(Arc::new(BreachedPasswordRule::new(Arc::new(StubBreaches { available: breaches_available }))))
+}
+
+fn service(breaches_available: bool) -> UserService<InMemoryUserRepository> {
+    let hasher = Argon2PasswordHasher::new(Params::new(Params::MIN_M_COST, 1, 1, None).unwrap());
+    UserService::new(InMemoryUserRepository::new())
+        .with_password_hasher(Arc::new(hasher))
+        .with_password_policy(policy(breaches_available))
+}
+
+fn user(email: &str, password: &str) -> CreateUser {
+    CreateUser::new("Jane".to_string(), email.to_string(), 30).unwrap().with_password(password.to_string()).unwrap()
+}
+
+#[tokio::test]
+async fn refuses_passwords_with_every_rule_they_violate() {
+    let service = service(true);
+
+    let error = service.create_user(user("jane@example.com", "password123")).await.unwrap_err();
+    assert_eq!(
+        error,
+        UserDomainError::WeakPassword(vec![
+            PasswordViolation { rule: "min_length", message: "must be at least 12 characters".to_string() },
+            PasswordViolation { rule: "breached", message: "appeared 5 times in data breaches".to_string() },
+        ])
+    );
+    assert!(service.create_user(user("jane@example.com", "correct horse battery")).await.is_ok());
+    // Users without a password are not affected
+    assert!(service.create_user(CreateUser::new("John".to_string(), "john@example.com".to_string(), 40).unwrap()).await.is_ok());
+}
+
+#[tokio::test]
+async fn accepts_passwords_when_breaches_cannot_be_looked_up() {
+    let service = service(false);
+
+    assert!(service.create_user(user("jane@example.com", "password123456")).await.is_ok());
+}
+
+#[tokio::test]
+async fn refuses_weak_passwords_of_bulk_creations_alone() {
+    let service = service(true);
+
+    let results = service.create_users_bulk(vec![user("jane@example.com", "password123"), user("john@example.com", "correct horse battery")]).await;
+    assert!(matches!(results[0], Err(UserDomainError::WeakPassword(_))), "{:?}", results[0]);
+    assert!(results[1].is_ok(), "{:?}", results[1]);
+}
+
+#[tokio::test]
+async fn lists_the_violated_rules_in_responses() {
+    let app = router(AppState::new(Arc::new(service(true))));
+
+    let body = json!({"name": "Jane", "email": "jane@example.com", "age": 30, "password": "password123"});
+    let request = Request::post("/api/v1/users").header("content-type", "application/json").body(Body::from(body.to_string())).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        body,
+        json!({
+            "status_code": 422,
+            "data": {
+                "code": "weak_password",
+                "message": "Password violates the password policy",
+                "errors": [
+                    {"field": "password", "rule": "min_length", "message": "must be at least 12 characters"},
+                    {"field": "password", "rule": "breached", "message": "appeared 5 times in data breaches"}
+                ]
+            }
+        })
+    );
+}
+
+#[cfg(feature = "hibp")]
+#[test]
+fn counts_the_breaches_of_a_suffix_in_range_responses() {
+    use rust_web_server_lib::infra::auth::hibp::count_in_range;
+
+    let range = "0018A45C4D1DEF81644B54AB7F969B88D65:3\r\n1E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r\n011053FD0102E94D6AE2F8B83D76FAF94F6:0\r\n";
+    assert_eq!(count_in_range(range, "1e4c9b93f3f0682250b6cf8331b7ee68fd8"), 9_659_365);
+    // Padding suffixes have a count of 0, like missing ones
+    assert_eq!(count_in_range(range, "011053FD0102E94D6AE2F8B83D76FAF94F6"), 0);
+    assert_eq!(count_in_range(range, "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"), 0);
+}
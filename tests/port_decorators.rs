@@ -0,0 +1,97 @@
+//! Tests of the decorators generated by `#[instrumented_port]`.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use port_decorators::{instrumented_port, RetryPolicy, Retryable};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LookupError {
+    Unavailable,
+    Missing,
+}
+
+impl Retryable for LookupError {
+    fn is_retryable(&self) -> bool {
+        *self == LookupError::Unavailable
+    }
+}
+
+#[instrumented_port]
+#[async_trait]
+trait LookupPort {
+    #[port(retry)]
+    async fn lookup(&self, key: String) -> Result<String, LookupError>;
+
+    async fn store(&self, key: String) -> Result<(), LookupError>;
+}
+
+/// Fails with `error` on the first `failures` calls of each method, then succeeds.
+struct FlakyLookup {
+    failures: u32,
+    error: LookupError,
+    calls: AtomicU32,
+}
+
+impl FlakyLookup {
+    fn new(failures: u32, error: LookupError) -> Self {
+        Self { failures, error, calls: AtomicU32::new(0) }
+    }
+
+    fn call(&self) -> Result<(), LookupError> {
+        if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+            Err(self.error.clone())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[async_trait]
+impl LookupPort for FlakyLookup {
+    async fn lookup(&self, key: String) -> Result<String, LookupError> {
+        self.call().map(|_| key)
+    }
+
+    async fn store(&self, _key: String) -> Result<(), LookupError> {
+        self.call()
+    }
+}
+
+const RETRY: RetryPolicy = RetryPolicy {
+    max_attempts: 3,
+    initial_backoff: Duration::from_millis(1),
+};
+
+#[tokio::test]
+async fn retries_retryable_errors() {
+    let lookup = InstrumentedLookup::new(FlakyLookup::new(2, LookupError::Unavailable)).with_retry(RETRY);
+
+    assert_eq!(lookup.lookup("key".to_string()).await, Ok("key".to_string()));
+    assert_eq!(lookup.into_inner().calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let lookup = InstrumentedLookup::new(FlakyLookup::new(5, LookupError::Unavailable)).with_retry(RETRY);
+
+    assert_eq!(lookup.lookup("key".to_string()).await, Err(LookupError::Unavailable));
+    assert_eq!(lookup.into_inner().calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn does_not_retry_permanent_errors() {
+    let lookup = InstrumentedLookup::new(FlakyLookup::new(1, LookupError::Missing)).with_retry(RETRY);
+
+    assert_eq!(lookup.lookup("key".to_string()).await, Err(LookupError::Missing));
+    assert_eq!(lookup.into_inner().calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn does_not_retry_unmarked_methods() {
+    let lookup = InstrumentedLookup::new(FlakyLookup::new(1, LookupError::Unavailable)).with_retry(RETRY);
+
+    assert_eq!(lookup.store("key".to_string()).await, Err(LookupError::Unavailable));
+    assert_eq!(lookup.into_inner().calls.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,102 @@
+//! Tests of the server over HTTP, storing the users in PostgreSQL.
+//!
+//! Each test runs a server on an ephemeral port, on a throwaway database of [`TestDb`]: in a
+//! PostgreSQL container started with testcontainers, unless `TEST_DATABASE_URL` is set. Tests
+//! of new adapters follow the same pattern, starting their dependencies in containers.
+#![cfg(feature = "testing")]
+
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::{header, Client, StatusCode};
+use serde_json::{json, Value};
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+use rust_web_server_lib::presentation::http::{AppState, HttpServer, HttpServerConfig};
+use rust_web_server_lib::presentation::middleware::load_shedding::LoadSheddingPolicy;
+
+use common::{auth_state, token};
+
+/// A server storing its users in a database of its own.
+struct TestServer {
+    // Kept until the end of the test, dropping the database
+    _db: TestDb,
+    url: String,
+    client: Client,
+    token: String,
+}
+
+impl TestServer {
+    /// Provisions a migrated database and starts a server on it, on an ephemeral port.
+    async fn start() -> Self {
+        let db = TestDb::new().await.unwrap();
+        let state = AppState {
+            auth: auth_state(),
+            ..AppState::new(Arc::new(UserService::new(UserRepository::new(db.db()))))
+        };
+        let config = HttpServerConfig { port: 0, shutdown_timeout: Duration::from_secs(1), cors: None, max_body_bytes: 1024 * 1024, compression: None, load_shedding: LoadSheddingPolicy::default() };
+        let server = HttpServer::new(state, config).await.unwrap();
+        let addr = SocketAddr::from(([127, 0, 0, 1], server.local_addr().unwrap().port()));
+        tokio::spawn(server.run_until(std::future::pending()));
+
+        Self {
+            _db: db,
+            url: format!("http://{}/api/v1", addr),
+            client: Client::new(),
+            token: token("user-1"),
+        }
+    }
+
+    /// Sends `method` to `path` with `body` if any, returning the status, the `ETag` and the body
+    /// of the response.
+    async fn send(&self, method: reqwest::Method, path: &str, body: Option<Value>) -> (StatusCode, Option<String>, Value) {
+        let mut request = self.client.request(method, format!("{}{}", self.url, path)).bearer_auth(&self.token);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request.send().await.unwrap();
+        let status = response.status();
+        let etag = response.headers().get(header::ETAG).map(|etag| etag.to_str().unwrap().to_string());
+        (status, etag, response.json().await.unwrap_or(Value::Null))
+    }
+}
+
+#[tokio::test]
+async fn creates_reads_updates_and_deletes_users() {
+    let server = TestServer::start().await;
+
+    let (status, etag, body) = server.send(reqwest::Method::POST, "/users", Some(json!({"name": "Jane", "email": "jane@example.com", "age": 30}))).await;
+    assert_eq!(status, StatusCode::CREATED, "{}", body);
+    assert_eq!(etag.as_deref(), Some("\"1\""));
+    let user = format!("/users/{}", body["data"]["id"].as_str().unwrap());
+
+    let (status, _, body) = server.send(reqwest::Method::GET, &user, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((body["data"]["name"].as_str(), body["data"]["email"].as_str()), (Some("Jane"), Some("jane@example.com")));
+    let (_, _, body) = server.send(reqwest::Method::GET, "/users", None).await;
+    assert_eq!(body["data"]["total"], 1);
+
+    let (status, etag, body) = server.send(reqwest::Method::PUT, &user, Some(json!({"name": "Janet"}))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(etag.as_deref(), Some("\"2\""));
+    assert_eq!(server.send(reqwest::Method::GET, &user, None).await.2["data"]["name"], "Janet");
+
+    assert_eq!(server.send(reqwest::Method::DELETE, &user, None).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(server.send(reqwest::Method::GET, &user, None).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(server.send(reqwest::Method::GET, "/users", None).await.2["data"]["total"], 0);
+}
+
+#[tokio::test]
+async fn rejects_users_whose_email_is_taken() {
+    let server = TestServer::start().await;
+    let jane = json!({"name": "Jane", "email": "jane@example.com", "age": 30});
+
+    assert_eq!(server.send(reqwest::Method::POST, "/users", Some(jane.clone())).await.0, StatusCode::CREATED);
+    // The unique index of the database, not a check of the service, refuses the second one
+    assert_eq!(server.send(reqwest::Method::POST, "/users", Some(jane)).await.0, StatusCode::CONFLICT);
+}
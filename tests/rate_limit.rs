@@ -0,0 +1,179 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::throttle::ThrottlePort;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::rate_limit::{RateLimitPolicy, RateLimiter, RouteRateLimit};
+
+fn policy(requests: u32) -> RateLimitPolicy {
+    RateLimitPolicy {
+        requests,
+        period: Duration::from_secs(60),
+        route_overrides: Vec::new(),
+        class_overrides: Vec::new(),
+        trust_forwarded_for: false,
+    }
+}
+
+fn app(policy: RateLimitPolicy) -> axum::Router {
+    router(AppState {
+        rate_limiter: Some(RateLimiter::new(policy).unwrap()),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+fn request(method: Method, uri: &str, peer: &str) -> Request<Body> {
+    let mut request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+    request.extensions_mut().insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+    request
+}
+
+async fn status(app: &axum::Router, request: Request<Body>) -> StatusCode {
+    app.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn rejects_requests_beyond_the_limit() {
+    let app = app(policy(2));
+
+    for _ in 0..2 {
+        assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:1234")).await, StatusCode::OK);
+    }
+    let response = app.clone().oneshot(request(Method::GET, "/api/users", "10.0.0.1:1234")).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body, json!({ "status_code": 429, "data": { "code": "too_many_requests", "message": "Too many requests" } }));
+}
+
+#[tokio::test]
+async fn limits_each_client_separately() {
+    let app = app(policy(1));
+
+    assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:1234")).await, StatusCode::OK);
+    assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:5678")).await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.2:1234")).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn limits_each_route_separately() {
+    let app = app(policy(1));
+
+    assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:1234")).await, StatusCode::OK);
+    assert_eq!(status(&app, request(Method::GET, "/api/docs/openapi.json", "10.0.0.1:1234")).await, StatusCode::OK);
+    // Paths of the same route share its limit
+    let first = status(&app, request(Method::GET, "/api/users/00000000-0000-0000-0000-000000000001", "10.0.0.1:1234")).await;
+    let second = status(&app, request(Method::GET, "/api/users/00000000-0000-0000-0000-000000000002", "10.0.0.1:1234")).await;
+
+    assert_ne!(first, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(second, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn applies_route_overrides() {
+    let app = app(RateLimitPolicy {
+        route_overrides: vec![RouteRateLimit { path_prefix: "/api/auth".to_string(), requests: 1 }],
+        ..policy(10)
+    });
+    let login = || {
+        let mut request = request(Method::POST, "/api/auth/login", "10.0.0.1:1234");
+        request.headers_mut().insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+        *request.body_mut() = Body::from(json!({ "email": "jdoe@example.com", "password": "secret" }).to_string());
+        request
+    };
+
+    assert_ne!(status(&app, login()).await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(status(&app, login()).await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:1234")).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn shares_limits_and_overrides_across_versions() {
+    let app = app(RateLimitPolicy {
+        route_overrides: vec![RouteRateLimit { path_prefix: "/api/users".to_string(), requests: 1 }],
+        ..policy(10)
+    });
+
+    assert_eq!(status(&app, request(Method::GET, "/api/v1/users", "10.0.0.1:1234")).await, StatusCode::OK);
+    assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:1234")).await, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn refills_over_the_period() {
+    let app = app(RateLimitPolicy { period: Duration::from_millis(200), ..policy(1) });
+
+    assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:1234")).await, StatusCode::OK);
+    assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:1234")).await, StatusCode::TOO_MANY_REQUESTS);
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:1234")).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn tightens_limits_for_a_while() {
+    let limiter = RateLimiter::new(RateLimitPolicy { period: Duration::from_secs(3600), ..policy(4) }).unwrap();
+    let app = router(AppState {
+        rate_limiter: Some(limiter.clone()),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    });
+
+    limiter.tighten(2, Duration::from_millis(200));
+    for _ in 0..2 {
+        assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:1234")).await, StatusCode::OK);
+    }
+    assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.1:1234")).await, StatusCode::TOO_MANY_REQUESTS);
+    tokio::time::sleep(Duration::from_millis(250)).await;
+
+    for _ in 0..4 {
+        assert_eq!(status(&app, request(Method::GET, "/api/users", "10.0.0.2:1234")).await, StatusCode::OK);
+    }
+}
+
+#[tokio::test]
+async fn identifies_clients_by_forwarded_for_when_trusted() {
+    let forwarded = |client: &str| {
+        let mut request = request(Method::GET, "/api/users", "10.0.0.254:1234");
+        request.headers_mut().insert("x-forwarded-for", format!("203.0.113.9, {}", client).parse().unwrap());
+        request
+    };
+    let trusting = app(RateLimitPolicy { trust_forwarded_for: true, ..policy(1) });
+    let untrusting = app(policy(1));
+
+    assert_eq!(status(&trusting, forwarded("198.51.100.1")).await, StatusCode::OK);
+    assert_eq!(status(&trusting, forwarded("198.51.100.2")).await, StatusCode::OK);
+    assert_eq!(status(&trusting, forwarded("198.51.100.1")).await, StatusCode::TOO_MANY_REQUESTS);
+
+    assert_eq!(status(&untrusting, forwarded("198.51.100.1")).await, StatusCode::OK);
+    assert_eq!(status(&untrusting, forwarded("198.51.100.2")).await, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn does_not_limit_health_probes() {
+    let app = app(policy(1));
+
+    for _ in 0..3 {
+        assert_eq!(status(&app, request(Method::GET, "/healthz", "10.0.0.1:1234")).await, StatusCode::OK);
+    }
+}
+
+#[test]
+fn rejects_zero_limits() {
+    assert!(RateLimiter::new(policy(0)).is_err());
+    assert!(RateLimiter::new(RateLimitPolicy { period: Duration::ZERO, ..policy(1) }).is_err());
+    assert!(RateLimiter::new(RateLimitPolicy {
+        route_overrides: vec![RouteRateLimit { path_prefix: "/api/auth".to_string(), requests: 0 }],
+        ..policy(1)
+    })
+    .is_err());
+}
@@ -0,0 +1,93 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use serde_json::Value;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+
+use rust_web_server_lib::domain::user::model::{CreateUser, UserId};
+use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+
+/// Log lines written by the subscriber, one JSON object each.
+#[derive(Clone, Default)]
+struct Logs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Logs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Logs {
+    /// Returns the fields of the spans named `name`, as recorded when they closed.
+    fn closed_spans(&self, name: &str) -> Vec<Value> {
+        let logs = self.0.lock().unwrap();
+        String::from_utf8_lossy(&logs)
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .filter(|line| line["span"]["name"] == name)
+            .map(|line| line["span"].clone())
+            .collect()
+    }
+}
+
+/// Creates a user, then looks up an unknown one, returning the spans of both calls.
+async fn create_and_miss(users: &(dyn UserRepositoryPort + Send + Sync)) -> (Vec<Value>, Vec<Value>) {
+    let logs = Logs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::registry().with(
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(move || writer.clone()),
+    );
+    // The test runtime is single-threaded, so the calls are made on this thread
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let jane = CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap();
+    users.create_user(jane, None).await.unwrap();
+    users.get_user(UserId::generate()).await.unwrap_err();
+
+    (logs.closed_spans("user_repository.create_user"), logs.closed_spans("user_repository.get_user"))
+}
+
+/// Asserts the outcome of the calls of `create_and_miss` is recorded in their spans.
+fn assert_outcomes_recorded((created, missed): (Vec<Value>, Vec<Value>), db_system: &str) {
+    assert_eq!(created.len(), 1);
+    assert_eq!(created[0]["db.system"], db_system);
+    assert_eq!(created[0]["outcome"], "success");
+    assert!(created[0].get("error.class").is_none(), "{}", created[0]);
+
+    assert_eq!(missed.len(), 1);
+    assert_eq!(missed[0]["outcome"], "error");
+    assert_eq!(missed[0]["error.class"], "not_found");
+}
+
+#[tokio::test]
+async fn records_the_outcome_of_in_memory_calls_in_their_spans() {
+    let users = InMemoryUserRepository::new();
+
+    assert_outcomes_recorded(create_and_miss(&users).await, "in_memory");
+}
+
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn records_the_outcome_of_postgres_calls_in_their_spans() {
+        let db = TestDb::new().await.unwrap();
+        let users = UserRepository::new(db.db());
+
+        assert_outcomes_recorded(create_and_miss(&users).await, "postgresql");
+    }
+}
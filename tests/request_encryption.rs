@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, Payload};
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::encryption::{JweKeys, JOSE_CONTENT_TYPE};
+
+const KEY: [u8; 32] = [7; 32];
+
+fn app() -> axum::Router {
+    let keys = JweKeys::from_base64([("2024-01".to_string(), URL_SAFE_NO_PAD.encode(KEY))]).unwrap();
+    router(AppState {
+        jwe_keys: Some(keys),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+/// Encrypts `body` into a compact JWE with `alg: dir` and `enc: A256GCM`.
+fn encrypt(key: &[u8; 32], kid: &str, body: &Value) -> String {
+    let protected = URL_SAFE_NO_PAD.encode(json!({"alg": "dir", "enc": "A256GCM", "kid": kid}).to_string());
+    let iv = [1u8; 12];
+    let sealed = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+        .encrypt(Nonce::from_slice(&iv), Payload { msg: body.to_string().as_bytes(), aad: protected.as_bytes() })
+        .unwrap();
+    let (ciphertext, tag) = sealed.split_at(sealed.len() - 16);
+
+    format!("{}..{}.{}.{}", protected, URL_SAFE_NO_PAD.encode(iv), URL_SAFE_NO_PAD.encode(ciphertext), URL_SAFE_NO_PAD.encode(tag))
+}
+
+async fn create_user(app: axum::Router, content_type: &str, body: String) -> (StatusCode, Value) {
+    let request = Request::post("/api/users").header("content-type", content_type).body(Body::from(body)).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+fn new_user() -> Value {
+    json!({"name": "Jane", "email": "jane@example.com", "age": 30})
+}
+
+#[tokio::test]
+async fn accepts_encrypted_body() {
+    let (status, body) = create_user(app(), JOSE_CONTENT_TYPE, encrypt(&KEY, "2024-01", &new_user())).await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["email"], "jane@example.com");
+}
+
+#[tokio::test]
+async fn accepts_plaintext_body() {
+    let (status, _) = create_user(app(), "application/json", new_user().to_string()).await;
+
+    assert_eq!(status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn rejects_undecryptable_bodies() {
+    let tampered = encrypt(&KEY, "2024-01", &new_user()).replacen('.', ".x", 3);
+    let cases = [
+        encrypt(&[8; 32], "2024-01", &new_user()),
+        encrypt(&KEY, "unknown", &new_user()),
+        tampered,
+        "not a jwe".to_string(),
+    ];
+
+    for jwe in cases {
+        let (status, body) = create_user(app(), JOSE_CONTENT_TYPE, jwe).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["data"]["message"], "Invalid encrypted payload");
+    }
+}
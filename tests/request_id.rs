@@ -0,0 +1,146 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+fn app() -> axum::Router {
+    router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))))
+}
+
+async fn request_id_of(request: Request<Body>) -> String {
+    let response = app().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    response.headers()["x-request-id"].to_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn generates_request_ids() {
+    let first = request_id_of(Request::get("/api/users").body(Body::empty()).unwrap()).await;
+    let second = request_id_of(Request::get("/api/users").body(Body::empty()).unwrap()).await;
+
+    assert!(uuid::Uuid::parse_str(&first).is_ok());
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn keeps_request_ids_of_clients() {
+    let request = Request::get("/healthz").header("x-request-id", "req-42").body(Body::empty()).unwrap();
+
+    assert_eq!(request_id_of(request).await, "req-42");
+}
+
+/// Log lines written by the subscriber, one JSON object each.
+#[derive(Clone, Default)]
+struct Logs(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Logs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Logs {
+    fn lines(&self) -> Vec<Value> {
+        let logs = self.0.lock().unwrap();
+        String::from_utf8_lossy(&logs).lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+}
+
+#[tokio::test]
+async fn records_request_ids_in_the_spans_of_requests() {
+    let logs = Logs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::registry().with(
+        tracing_subscriber::fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_span_list(true)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(move || writer.clone()),
+    );
+    // The test runtime is single-threaded, so the request is handled on this thread
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let request = Request::get("/api/users").header("x-request-id", "req-42").body(Body::empty()).unwrap();
+    request_id_of(request).await;
+
+    let lines = logs.lines();
+    let service_span = lines
+        .iter()
+        .find(|line| line["span"]["name"] == "user_service.list_users")
+        .expect("the service span is logged when closed");
+    assert_eq!(service_span["spans"][0]["name"], "http_request");
+    assert_eq!(service_span["spans"][0]["request_id"], "req-42");
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::trace::{SpanId, TraceId};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider, SpanData};
+
+    use rust_web_server_lib::infra::telemetry::otlp;
+
+    use super::*;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    const PARENT_ID: &str = "00f067aa0ba902b7";
+
+    /// Spans exported while handling `request`.
+    async fn exported_spans(request: Request<Body>) -> Vec<SpanData> {
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let subscriber = tracing_subscriber::registry().with(otlp::layer(&provider));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        request_id_of(request).await;
+
+        provider.force_flush().unwrap();
+        exporter.get_finished_spans().unwrap()
+    }
+
+    fn span<'a>(spans: &'a [SpanData], name: &str) -> &'a SpanData {
+        spans.iter().find(|span| span.name == name).unwrap_or_else(|| panic!("span {} is exported", name))
+    }
+
+    #[tokio::test]
+    async fn continues_the_trace_of_the_caller() {
+        let traceparent = format!("00-{}-{}-01", TRACE_ID, PARENT_ID);
+        let request = Request::get("/api/users").header("traceparent", traceparent).body(Body::empty()).unwrap();
+
+        let spans = exported_spans(request).await;
+
+        let request_span = span(&spans, "http_request");
+        assert_eq!(request_span.span_context.trace_id(), TraceId::from_hex(TRACE_ID).unwrap());
+        assert_eq!(request_span.parent_span_id, SpanId::from_hex(PARENT_ID).unwrap());
+        let service_span = span(&spans, "user_service.list_users");
+        assert_eq!(service_span.span_context.trace_id(), request_span.span_context.trace_id());
+        assert_eq!(service_span.parent_span_id, request_span.span_context.span_id());
+    }
+
+    #[tokio::test]
+    async fn starts_traces_without_traceparent() {
+        let spans = exported_spans(Request::get("/api/users").body(Body::empty()).unwrap()).await;
+
+        let request_span = span(&spans, "http_request");
+        assert_ne!(request_span.span_context.trace_id(), TraceId::from_hex(TRACE_ID).unwrap());
+        assert_eq!(request_span.parent_span_id, SpanId::INVALID);
+    }
+}
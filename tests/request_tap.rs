@@ -0,0 +1,128 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::{header, Method, StatusCode};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::tap_handlers::TapState;
+use rust_web_server_lib::presentation::http::{router, AppState, HttpServer, HttpServerConfig};
+use rust_web_server_lib::presentation::middleware::load_shedding::LoadSheddingPolicy;
+use rust_web_server_lib::presentation::middleware::request_tap::RequestTap;
+
+use common::{auth_state, token, ADMIN_TOKEN};
+
+type Tap = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+fn state(tap: Option<RequestTap>) -> AppState {
+    AppState {
+        admin_token: Some(ADMIN_TOKEN.into()),
+        auth: auth_state(),
+        request_tap: TapState { tap },
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    }
+}
+
+/// Serves `state` on a free port, returning its address.
+async fn serve(state: AppState) -> SocketAddr {
+    let config = HttpServerConfig { port: 0, shutdown_timeout: Duration::from_secs(1), cors: None, max_body_bytes: 1024 * 1024, compression: None, load_shedding: LoadSheddingPolicy::default() };
+    let server = HttpServer::new(state, config).await.unwrap();
+    let addr = SocketAddr::from(([127, 0, 0, 1], server.local_addr().unwrap().port()));
+    tokio::spawn(server.run_until(std::future::pending()));
+    addr
+}
+
+/// Opens the request tap of the server at `addr` with the `query` filters.
+async fn watch(addr: SocketAddr, query: &str) -> Result<Tap, Error> {
+    let mut request = format!("ws://{}/api/admin/tap{}", addr, query).into_client_request().unwrap();
+    request.headers_mut().insert(header::AUTHORIZATION, format!("Bearer {}", ADMIN_TOKEN).parse().unwrap());
+    tokio_tungstenite::connect_async(request).await.map(|(tap, _)| tap)
+}
+
+async fn next_summary(tap: &mut Tap) -> Value {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), tap.next()).await.expect("no summary streamed").unwrap().unwrap();
+        if let Message::Text(text) = message {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+fn rejection(result: Result<Tap, Error>) -> StatusCode {
+    match result {
+        Err(Error::Http(response)) => response.status(),
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("tap opened"),
+    }
+}
+
+/// Sends a request to a router sharing the tap of the server.
+async fn send(state: &AppState, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) {
+    common::send(&router(state.clone()), method, uri, token, body).await;
+}
+
+#[tokio::test]
+async fn streams_sanitized_summaries_of_the_requests() {
+    let state = state(Some(RequestTap::new(16)));
+    let mut tap = watch(serve(state.clone()).await, "").await.unwrap();
+
+    send(&state, Method::GET, "/api/users/alice@example.com?token=secret", None, None).await;
+    let summary = next_summary(&mut tap).await;
+    assert_eq!(summary["method"], "GET");
+    assert_eq!(summary["route"], "/api/users/{id}");
+    assert_eq!(summary["path"], "/api/users/redacted");
+    assert!(summary["status"].as_u64().unwrap() >= 400);
+    assert!(summary["latency_ms"].is_u64());
+    assert!(summary.get("principal").is_none());
+
+    send(&state, Method::POST, "/api/users/bulk", Some(&token("user-1")), Some(json!([]))).await;
+    let summary = next_summary(&mut tap).await;
+    assert_eq!((summary["method"].clone(), summary["status"].clone()), (json!("POST"), json!(200)));
+    assert_eq!(summary["principal"], "user-1");
+}
+
+#[tokio::test]
+async fn streams_only_the_requests_matching_the_filters() {
+    let state = state(Some(RequestTap::new(16)));
+    let mut tap = watch(serve(state.clone()).await, "?method=post&status=2xx&path_prefix=/api/users").await.unwrap();
+
+    send(&state, Method::GET, "/api/users", None, None).await;
+    send(&state, Method::POST, "/api/auth/login", None, Some(json!({}))).await;
+    let user = json!({ "name": "Alice", "email": "alice@example.com", "age": 30 });
+    send(&state, Method::POST, "/api/users", None, Some(user)).await;
+
+    let summary = next_summary(&mut tap).await;
+    assert_eq!((summary["method"].clone(), summary["path"].clone(), summary["status"].clone()), (json!("POST"), json!("/api/users"), json!(201)));
+}
+
+#[tokio::test]
+async fn rejects_invalid_filters() {
+    let addr = serve(state(Some(RequestTap::new(16)))).await;
+
+    assert_eq!(rejection(watch(addr, "?status=9xx").await), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(rejection(watch(addr, "?status=abc").await), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn is_not_found_when_disabled() {
+    let addr = serve(state(None)).await;
+
+    assert_eq!(rejection(watch(addr, "").await), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn requires_the_admin_token() {
+    let addr = serve(state(Some(RequestTap::new(16)))).await;
+    let request = format!("ws://{}/api/admin/tap", addr).into_client_request().unwrap();
+
+    assert_eq!(rejection(tokio_tungstenite::connect_async(request).await.map(|(tap, _)| tap)), StatusCode::UNAUTHORIZED);
+}
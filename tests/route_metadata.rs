@@ -0,0 +1,144 @@
+mod common;
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, Method, Request, StatusCode};
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::USERS_WRITE_SCOPE;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::rate_limit::{ClassRateLimit, RateLimitClass, RateLimitPolicy, RateLimiter};
+use rust_web_server_lib::presentation::presets::MiddlewarePreset;
+use rust_web_server_lib::presentation::routes::{RouteMeta, Routes};
+use rust_web_server_lib::presentation::versioning::{Deprecation, DEPRECATION};
+
+use common::{auth_state, scoped_token, send_request};
+
+/// Returns a state authenticating the tokens of the tests, limiting clients to 100
+/// requests a minute on each route and to 1 on the routes of the credentials class.
+fn state() -> AppState<UserService<InMemoryUserRepository>> {
+    let policy = RateLimitPolicy {
+        requests: 100,
+        period: Duration::from_secs(60),
+        route_overrides: Vec::new(),
+        class_overrides: vec![ClassRateLimit { class: RateLimitClass::Credentials, requests: 1 }],
+        trust_forwarded_for: false,
+    };
+    AppState {
+        auth: auth_state(),
+        rate_limiter: Some(RateLimiter::new(policy).unwrap()),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    }
+}
+
+/// Returns a module of routes declared with their metadata, served with the public API preset.
+fn module() -> axum::Router {
+    let deprecation = Deprecation::new(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap(), "/v2/events");
+    let routes = Routes::new()
+        .route(Method::POST, "/events", || async { StatusCode::ACCEPTED }, RouteMeta::new().scope(USERS_WRITE_SCOPE))
+        .route(Method::GET, "/events", || async { StatusCode::OK }, RouteMeta::new().deprecated(deprecation))
+        .route(Method::POST, "/sessions", || async { StatusCode::CREATED }, RouteMeta::new().rate_limit(RateLimitClass::Credentials));
+    MiddlewarePreset::PublicApi.apply_routes(routes, &state())
+}
+
+/// Returns a request of a client connected from `10.0.0.1`, which the rate limiter keys on.
+fn request(method: Method, uri: &str, token: Option<&str>) -> Request<Body> {
+    let mut request = common::request(method, uri, token, None);
+    request.extensions_mut().insert(ConnectInfo("10.0.0.1:1234".parse::<SocketAddr>().unwrap()));
+    request
+}
+
+async fn status(app: &axum::Router, request: Request<Body>) -> StatusCode {
+    send_request(app, request).await.0
+}
+
+#[tokio::test]
+async fn rejects_the_requests_lacking_the_scopes_of_their_route() {
+    let app = module();
+    let scoped = scoped_token("jdoe", &[USERS_WRITE_SCOPE]);
+    let unscoped = scoped_token("jdoe", &[]);
+
+    assert_eq!(status(&app, request(Method::POST, "/events", None)).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(status(&app, request(Method::POST, "/events", Some(&unscoped))).await, StatusCode::FORBIDDEN);
+    assert_eq!(status(&app, request(Method::POST, "/events", Some(&scoped))).await, StatusCode::ACCEPTED);
+    // Routes without scopes are public
+    assert_eq!(status(&app, request(Method::GET, "/events", None)).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn announces_the_deprecation_of_deprecated_routes() {
+    let app = module();
+
+    let response = app.clone().oneshot(request(Method::GET, "/events", None)).await.unwrap();
+    assert_eq!(response.headers()[DEPRECATION], "@1767225600");
+    assert_eq!(response.headers()[header::LINK], "</v2/events>; rel=\"successor-version\"");
+
+    let scoped = scoped_token("jdoe", &[USERS_WRITE_SCOPE]);
+    let response = app.oneshot(request(Method::POST, "/events", Some(&scoped))).await.unwrap();
+    assert!(!response.headers().contains_key(DEPRECATION));
+}
+
+#[tokio::test]
+async fn limits_the_routes_by_the_limit_of_their_class() {
+    let app = module();
+
+    assert_eq!(status(&app, request(Method::POST, "/sessions", None)).await, StatusCode::CREATED);
+    assert_eq!(status(&app, request(Method::POST, "/sessions", None)).await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(status(&app, request(Method::GET, "/events", None)).await, StatusCode::OK);
+    assert_eq!(status(&app, request(Method::GET, "/events", None)).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn limits_the_logins_by_the_limit_of_the_credentials_class() {
+    let app = router(state());
+
+    assert_ne!(status(&app, request(Method::POST, "/api/v1/auth/login", None)).await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(status(&app, request(Method::POST, "/api/v1/auth/login", None)).await, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(status(&app, request(Method::GET, "/api/v1/users", None)).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn documents_the_scopes_and_tags_of_the_routes() {
+    let (_, spec) = send_request(&router(state()), request(Method::GET, "/api/v1/docs/openapi.json", None)).await;
+
+    let bulk = &spec["paths"]["/api/v1/users/bulk"]["post"];
+    assert_eq!(bulk["security"], json!([{ "bearer_auth": ["users:write"] }]));
+    assert_eq!(bulk["tags"], json!(["users"]));
+    assert_eq!(spec["paths"]["/api/v1/auth/login"]["post"]["tags"], json!(["auth"]));
+    assert!(spec["paths"]["/api/v1/users"]["get"].get("security").is_none());
+}
+
+#[tokio::test]
+async fn answers_options_requests_with_the_methods_of_the_path() {
+    let app = module();
+
+    let response = app.clone().oneshot(request(Method::OPTIONS, "/events", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers()[header::ALLOW], "POST, GET, HEAD, OPTIONS");
+    let response = app.oneshot(request(Method::OPTIONS, "/sessions", None)).await.unwrap();
+    assert_eq!(response.headers()[header::ALLOW], "POST, OPTIONS");
+
+    let response = router(state()).oneshot(request(Method::OPTIONS, &format!("/api/v1/users/{}", uuid::Uuid::nil()), None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers()[header::ALLOW], "GET, HEAD, PUT, DELETE, OPTIONS");
+}
+
+#[tokio::test]
+async fn serves_head_requests_with_the_get_routes() {
+    let app = router(state());
+
+    let response = app.clone().oneshot(request(Method::HEAD, "/api/v1/users", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(bytes.is_empty());
+    assert_eq!(status(&module(), request(Method::HEAD, "/events", None)).await, StatusCode::OK);
+}
@@ -0,0 +1,347 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::saml_service::SamlService;
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::{AuthError, SamlIdentity, SamlServiceProviderPort, TokenPort};
+use rust_web_server_lib::domain::user::model::CreateUser;
+use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+use rust_web_server_lib::infra::auth::JwtConfig;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::saml_handlers::{SamlState, SAML_METADATA_CONTENT_TYPE};
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+/// Service provider accepting the response `"valid"` as asserting `jane@example.com`.
+struct StaticServiceProvider;
+
+impl SamlServiceProviderPort for StaticServiceProvider {
+    fn metadata(&self) -> String {
+        r#"<md:EntityDescriptor entityID="https://app.example.com/saml"/>"#.to_string()
+    }
+
+    fn login_url(&self) -> Result<String, AuthError> {
+        Ok("https://idp.example.com/sso?SAMLRequest=request".to_string())
+    }
+
+    fn consume(&self, response: &str) -> Result<SamlIdentity, AuthError> {
+        match response {
+            "valid" => Ok(SamlIdentity {
+                email: "jane@example.com".to_string(),
+                roles: vec!["admin".to_string()],
+            }),
+            "unknown" => Ok(SamlIdentity {
+                email: "john@example.com".to_string(),
+                roles: Vec::new(),
+            }),
+            _ => Err(AuthError::InvalidCredentials),
+        }
+    }
+}
+
+fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "saml-test-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+/// Returns the router and the id of the local user `jane@example.com`.
+async fn app(login_redirect_url: Option<&str>) -> (axum::Router, String) {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let jane = users
+        .create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap(), None)
+        .await
+        .unwrap();
+
+    let saml = SamlState {
+        saml_service: Arc::new(SamlService::new(Arc::new(StaticServiceProvider), users.clone(), Arc::new(jwt_tokens()))),
+        login_redirect_url: login_redirect_url.map(str::to_string),
+    };
+    let app = router(AppState {
+        saml: Some(saml),
+        ..AppState::new(Arc::new(UserService::new(users)))
+    });
+    (app, jane.id().to_string())
+}
+
+async fn post_response(app: &axum::Router, response: &str) -> axum::response::Response {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/auth/saml/acs")
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(Body::from(format!("SAMLResponse={}&RelayState=", response)))
+        .unwrap();
+    app.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn serves_metadata() {
+    let (app, _) = app(None).await;
+
+    let response = app
+        .oneshot(Request::builder().uri("/api/auth/saml/metadata").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], SAML_METADATA_CONTENT_TYPE);
+}
+
+#[tokio::test]
+async fn redirects_login_to_identity_provider() {
+    let (app, _) = app(None).await;
+
+    let response = app
+        .oneshot(Request::builder().uri("/api/auth/saml/login").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(response.headers()[header::LOCATION], "https://idp.example.com/sso?SAMLRequest=request");
+}
+
+#[tokio::test]
+async fn exchanges_response_for_token_of_local_user() {
+    let (app, jane_id) = app(None).await;
+
+    let response = post_response(&app, "valid").await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    let principal = jwt_tokens().verify(body["data"]["access_token"].as_str().unwrap()).unwrap();
+    assert_eq!(principal.user_id, jane_id);
+    assert_eq!(principal.roles, vec!["admin".to_string()]);
+}
+
+#[tokio::test]
+async fn redirects_with_token_in_fragment() {
+    let (app, _) = app(Some("https://app.example.com/logged-in")).await;
+
+    let response = post_response(&app, "valid").await;
+
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    let location = response.headers()[header::LOCATION].to_str().unwrap();
+    assert!(location.starts_with("https://app.example.com/logged-in#access_token="), "{}", location);
+    assert!(location.ends_with("&token_type=Bearer&expires_in=3600"), "{}", location);
+}
+
+#[tokio::test]
+async fn rejects_invalid_response() {
+    let (app, _) = app(None).await;
+
+    assert_eq!(post_response(&app, "forged").await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn rejects_user_unknown_locally() {
+    let (app, _) = app(None).await;
+
+    assert_eq!(post_response(&app, "unknown").await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn saml_routes_are_not_mounted_by_default() {
+    let app = router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))));
+
+    let response = app
+        .oneshot(Request::builder().uri("/api/auth/saml/metadata").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+/// Responses signed with xmlsec by the key of `fixtures/saml/idp-cert.pem`, answering a request
+/// issued at 2026-01-15T10:00:00Z with the request key `saml-test-secret`.
+#[cfg(feature = "saml")]
+mod service_provider {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use chrono::{DateTime, Utc};
+
+    use rust_web_server_lib::application::ports::auth::SamlServiceProviderPort;
+    use rust_web_server_lib::infra::auth::saml::{SamlError, SamlServiceProvider};
+    use rust_web_server_lib::infra::auth::SamlConfig;
+
+    const SIGNED_ASSERTION: &str = include_str!("fixtures/saml/response-signed-assertion.xml");
+    const SIGNED_RESPONSE: &str = include_str!("fixtures/saml/response-signed-response.xml");
+
+    fn config() -> SamlConfig {
+        SamlConfig {
+            sp_entity_id: "https://app.example.com/saml".to_string(),
+            acs_url: "https://app.example.com/api/auth/saml/acs".to_string(),
+            idp_entity_id: "https://idp.example.com/metadata".to_string(),
+            idp_sso_url: "https://idp.example.com/sso".to_string(),
+            idp_certificates: vec![certificate(include_str!("fixtures/saml/idp-cert.pem"))],
+            email_attribute: None,
+            roles_attribute: None,
+            clock_skew_secs: 60,
+            login_redirect_url: None,
+        }
+    }
+
+    fn certificate(pem: &str) -> String {
+        pem.lines().filter(|line| !line.starts_with("-----")).collect()
+    }
+
+    fn provider(config: SamlConfig) -> SamlServiceProvider {
+        SamlServiceProvider::new(config, b"saml-test-secret").unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2026-01-15T10:00:30Z".parse().unwrap()
+    }
+
+    fn encode(response: &str) -> String {
+        STANDARD.encode(response)
+    }
+
+    #[test]
+    fn accepts_signed_assertion() {
+        let identity = provider(config()).consume_at(&encode(SIGNED_ASSERTION), now()).unwrap();
+
+        assert_eq!(identity.email, "jane@example.com");
+        assert!(identity.roles.is_empty());
+    }
+
+    #[test]
+    fn accepts_signed_response() {
+        let identity = provider(config()).consume_at(&encode(SIGNED_RESPONSE), now()).unwrap();
+
+        assert_eq!(identity.email, "jane@example.com");
+    }
+
+    #[test]
+    fn maps_attributes() {
+        let config = SamlConfig {
+            email_attribute: Some("email".to_string()),
+            roles_attribute: Some("roles".to_string()),
+            ..config()
+        };
+
+        let identity = provider(config).consume_at(&encode(SIGNED_ASSERTION), now()).unwrap();
+
+        assert_eq!(identity.email, "jane.doe@example.com");
+        assert_eq!(identity.roles, vec!["admin".to_string(), "R&D".to_string()]);
+    }
+
+    #[test]
+    fn accepts_clock_skew() {
+        let before_validity = "2026-01-15T09:54:30Z".parse().unwrap();
+
+        assert!(provider(config()).consume_at(&encode(SIGNED_ASSERTION), before_validity).is_ok());
+    }
+
+    #[test]
+    fn rejects_tampered_assertion() {
+        let tampered = SIGNED_ASSERTION.replace("jane@example.com</saml2:NameID>", "admin@example.com</saml2:NameID>");
+
+        let result = provider(config()).consume_at(&encode(&tampered), now());
+
+        assert!(matches!(result, Err(SamlError::Signature(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn rejects_unsigned_assertion_wrapped_in_signed_response() {
+        // The signed assertion is moved out of the way and an unsigned one takes its place
+        let start = SIGNED_ASSERTION.find("<saml2:Assertion").unwrap();
+        let end = SIGNED_ASSERTION.find("</saml2:Assertion>").unwrap() + "</saml2:Assertion>".len();
+        let signed = &SIGNED_ASSERTION[start..end];
+        let forged = signed.replace("ID=\"assertion-1\"", "ID=\"assertion-forged\"").replace("jane@example.com", "admin@example.com");
+        let wrapped = SIGNED_ASSERTION.replace(signed, &format!("<saml2p:Extensions>{}</saml2p:Extensions>{}", signed, forged));
+
+        let result = provider(config()).consume_at(&encode(&wrapped), now());
+
+        assert!(result.is_err(), "{:?}", result);
+    }
+
+    #[test]
+    fn rejects_unsigned_response() {
+        let start = SIGNED_RESPONSE.find("<ds:Signature").unwrap();
+        let end = SIGNED_RESPONSE.find("</ds:Signature>").unwrap() + "</ds:Signature>".len();
+        let unsigned = format!("{}{}", &SIGNED_RESPONSE[..start], &SIGNED_RESPONSE[end..]);
+
+        let result = provider(config()).consume_at(&encode(&unsigned), now());
+
+        assert!(matches!(result, Err(SamlError::Signature(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn rejects_untrusted_certificate() {
+        let config = SamlConfig {
+            idp_certificates: vec![certificate(include_str!("fixtures/saml/other-cert.pem"))],
+            ..config()
+        };
+
+        let result = provider(config).consume_at(&encode(SIGNED_ASSERTION), now());
+
+        assert!(matches!(result, Err(SamlError::Signature(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn rejects_expired_assertion() {
+        let after_validity = "2026-01-15T10:06:30Z".parse().unwrap();
+
+        let result = provider(config()).consume_at(&encode(SIGNED_ASSERTION), after_validity);
+
+        assert!(matches!(result, Err(SamlError::Response(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn rejects_response_to_request_of_another_service_provider() {
+        let provider = SamlServiceProvider::new(config(), b"other-secret").unwrap();
+
+        let result = provider.consume_at(&encode(SIGNED_ASSERTION), now());
+
+        assert!(matches!(result, Err(SamlError::Response(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn rejects_other_audience() {
+        let config = SamlConfig {
+            sp_entity_id: "https://other.example.com/saml".to_string(),
+            ..config()
+        };
+
+        let result = provider(config).consume_at(&encode(SIGNED_ASSERTION), now());
+
+        assert!(matches!(result, Err(SamlError::Response(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn rejects_replayed_assertion() {
+        let provider = provider(config());
+        provider.consume_at(&encode(SIGNED_ASSERTION), now()).unwrap();
+
+        let result = provider.consume_at(&encode(SIGNED_ASSERTION), now());
+
+        assert!(matches!(result, Err(SamlError::Response(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn rejects_document_type_definitions() {
+        let document = SIGNED_ASSERTION.replacen("<saml2p:Response", "<!DOCTYPE r [<!ENTITY x \"x\">]><saml2p:Response", 1);
+
+        let result = provider(config()).consume_at(&encode(&document), now());
+
+        assert!(matches!(result, Err(SamlError::Xml(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn login_url_carries_request() {
+        let url = provider(config()).login_url().unwrap();
+
+        assert!(url.starts_with("https://idp.example.com/sso?SAMLRequest="), "{}", url);
+    }
+
+    #[test]
+    fn metadata_describes_service_provider() {
+        let metadata = provider(config()).metadata();
+
+        assert!(metadata.contains(r#"entityID="https://app.example.com/saml""#), "{}", metadata);
+        assert!(metadata.contains(r#"Location="https://app.example.com/api/auth/saml/acs""#), "{}", metadata);
+    }
+}
@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::scim_handlers::{SCIM_CONTENT_TYPE, USER_EXTENSION_SCHEMA, USER_SCHEMA};
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+fn app() -> axum::Router {
+    router(AppState {
+        scim_token: Some("scim-secret".into()),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, "Bearer scim-secret")
+        .header(header::CONTENT_TYPE, SCIM_CONTENT_TYPE)
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    if status != StatusCode::NO_CONTENT && status != StatusCode::UNAUTHORIZED {
+        assert_eq!(response.headers()[header::CONTENT_TYPE], SCIM_CONTENT_TYPE);
+    }
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+fn new_user() -> Value {
+    json!({
+        "schemas": [USER_SCHEMA, USER_EXTENSION_SCHEMA],
+        "userName": "jane@example.com",
+        "name": {"givenName": "Jane", "familyName": "Doe"},
+        "externalId": "00u1",
+        "active": true,
+        USER_EXTENSION_SCHEMA: {"age": 30}
+    })
+}
+
+async fn provision(app: &axum::Router) -> String {
+    let (status, body) = send(app, Method::POST, "/scim/v2/Users", Some(new_user())).await;
+    assert_eq!(status, StatusCode::CREATED);
+    body["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn provisions_user() {
+    let app = app();
+    let id = provision(&app).await;
+
+    let (status, body) = send(&app, Method::GET, &format!("/scim/v2/Users/{}", id), None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    insta::assert_json_snapshot!(body, { ".id" => "[id]", ".meta.location" => "[location]" });
+}
+
+#[tokio::test]
+async fn rejects_user_without_age() {
+    let mut user = new_user();
+    user.as_object_mut().unwrap().remove(USER_EXTENSION_SCHEMA);
+
+    let (status, body) = send(&app(), Method::POST, "/scim/v2/Users", Some(user)).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["scimType"], "invalidValue");
+    assert_eq!(body["status"], "400");
+}
+
+#[tokio::test]
+async fn filters_by_user_name() {
+    let app = app();
+    let id = provision(&app).await;
+
+    let (status, body) = send(&app, Method::GET, "/scim/v2/Users?filter=userName%20eq%20%22jane@example.com%22", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["totalResults"], 1);
+    assert_eq!(body["Resources"][0]["id"], id.as_str());
+
+    let (status, body) = send(&app, Method::GET, "/scim/v2/Users?filter=userName%20eq%20%22john@example.com%22", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["totalResults"], 0);
+
+    let (status, body) = send(&app, Method::GET, "/scim/v2/Users?filter=displayName%20co%20%22Jane%22", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["scimType"], "invalidFilter");
+}
+
+#[tokio::test]
+async fn lists_users_by_page() {
+    let app = app();
+    provision(&app).await;
+
+    let (status, body) = send(&app, Method::GET, "/scim/v2/Users?startIndex=1&count=0", None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["totalResults"], 1);
+    assert_eq!(body["itemsPerPage"], 0);
+    assert_eq!(body["Resources"], json!([]));
+}
+
+#[tokio::test]
+async fn patches_user() {
+    let app = app();
+    let id = provision(&app).await;
+    let patch = json!({
+        "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+        "Operations": [
+            {"op": "Replace", "path": "displayName", "value": "Jane Smith"},
+            {"op": "replace", "value": {"userName": "jane.smith@example.com", "active": "True"}},
+            {"op": "add", "path": format!("{}:age", USER_EXTENSION_SCHEMA), "value": 31},
+            {"op": "replace", "path": "externalId", "value": "00u2"}
+        ]
+    });
+
+    let (status, body) = send(&app, Method::PATCH, &format!("/scim/v2/Users/{}", id), Some(patch)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["displayName"], "Jane Smith");
+    assert_eq!(body["userName"], "jane.smith@example.com");
+    assert_eq!(body[USER_EXTENSION_SCHEMA]["age"], 31);
+}
+
+#[tokio::test]
+async fn rejects_deactivation() {
+    let app = app();
+    let id = provision(&app).await;
+    let patch = json!({"Operations": [{"op": "replace", "path": "active", "value": false}]});
+
+    let (status, body) = send(&app, Method::PATCH, &format!("/scim/v2/Users/{}", id), Some(patch)).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["scimType"], "mutability");
+}
+
+#[tokio::test]
+async fn deprovisions_user() {
+    let app = app();
+    let id = provision(&app).await;
+
+    let (status, _) = send(&app, Method::DELETE, &format!("/scim/v2/Users/{}", id), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, body) = send(&app, Method::GET, &format!("/scim/v2/Users/{}", id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["status"], "404");
+}
+
+#[tokio::test]
+async fn requires_scim_token() {
+    let request = Request::get("/scim/v2/Users").header(header::AUTHORIZATION, "Bearer wrong").body(Body::empty()).unwrap();
+
+    let response = app().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn not_mounted_without_token() {
+    let app = router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))));
+
+    let response = app.oneshot(Request::get("/scim/v2/Users").body(Body::empty()).unwrap()).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
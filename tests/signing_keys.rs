@@ -0,0 +1,212 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use chrono::{DateTime, TimeDelta, Utc};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::{all_scopes, AuthError, KeySetPort, TokenPort};
+use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+use rust_web_server_lib::infra::auth::signing_keys::{SigningKeyStore, SigningKeys};
+use rust_web_server_lib::infra::auth::{JwtConfig, SigningKeysConfig};
+use rust_web_server_lib::infra::storage::adapter::in_memory::signing_keys::InMemorySigningKeyStore;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+const SECRET: &str = "signing-keys-test-secret";
+const ROTATION_INTERVAL: TimeDelta = TimeDelta::hours(24);
+const PUBLICATION_DELAY: TimeDelta = TimeDelta::hours(1);
+const TOKEN_LIFETIME: TimeDelta = TimeDelta::hours(2);
+
+fn jwt_config() -> JwtConfig {
+    JwtConfig { secret: SECRET.to_string().into(), expiry_secs: TOKEN_LIFETIME.num_seconds() as u64 }
+}
+
+fn signing_keys(store: &Arc<InMemorySigningKeyStore>, secret: &str) -> Arc<SigningKeys> {
+    let config = SigningKeysConfig {
+        rotation_interval_secs: ROTATION_INTERVAL.num_seconds() as u64,
+        publication_delay_secs: PUBLICATION_DELAY.num_seconds() as u64,
+        refresh_interval_secs: 60,
+    };
+    Arc::new(SigningKeys::new(store.clone(), secret, &config, Duration::from_secs(TOKEN_LIFETIME.num_seconds() as u64)).unwrap())
+}
+
+fn kid(token: &str) -> String {
+    decode_header(token).unwrap().kid.unwrap()
+}
+
+fn kids(keys: &SigningKeys) -> Vec<String> {
+    keys.public_keys().into_iter().map(|key| key.kid).collect()
+}
+
+fn issue(tokens: &JwtTokens) -> String {
+    tokens.issue("jdoe", &[], &all_scopes()).unwrap().token
+}
+
+async fn get_jwks(app: &axum::Router) -> (StatusCode, Option<String>, Value) {
+    let request = Request::builder().uri("/.well-known/jwks.json").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let cache_control = response.headers().get(header::CACHE_CONTROL).map(|value| value.to_str().unwrap().to_string());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, cache_control, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn signs_tokens_with_kid() {
+    let keys = signing_keys(&Arc::new(InMemorySigningKeyStore::new()), SECRET);
+    keys.refresh().await.unwrap();
+    let tokens = JwtTokens::with_signing_keys(&jwt_config(), keys.clone());
+
+    let token = issue(&tokens);
+
+    let header = decode_header(&token).unwrap();
+    assert_eq!(header.alg, Algorithm::ES256);
+    assert_eq!(Some(header.kid.unwrap()), kids(&keys).pop());
+    assert_eq!(tokens.verify(&token).unwrap().user_id, "jdoe");
+}
+
+#[tokio::test]
+async fn requires_a_loaded_key_to_sign() {
+    let keys = signing_keys(&Arc::new(InMemorySigningKeyStore::new()), SECRET);
+    let tokens = JwtTokens::with_signing_keys(&jwt_config(), keys);
+
+    assert!(tokens.issue("jdoe", &[], &all_scopes()).is_err());
+}
+
+#[tokio::test]
+async fn rejects_tokens_of_unknown_keys() {
+    let keys = signing_keys(&Arc::new(InMemorySigningKeyStore::new()), SECRET);
+    keys.refresh().await.unwrap();
+    let tokens = JwtTokens::with_signing_keys(&jwt_config(), keys);
+    let other = signing_keys(&Arc::new(InMemorySigningKeyStore::new()), SECRET);
+    other.refresh().await.unwrap();
+
+    let forged = issue(&JwtTokens::with_signing_keys(&jwt_config(), other));
+    let hs256 = issue(&JwtTokens::new(&jwt_config()));
+
+    assert!(matches!(tokens.verify(&forged), Err(AuthError::InvalidToken)));
+    assert!(matches!(tokens.verify(&hs256), Err(AuthError::InvalidToken)));
+}
+
+#[tokio::test]
+async fn publishes_new_keys_before_signing_with_them() {
+    let keys = signing_keys(&Arc::new(InMemorySigningKeyStore::new()), SECRET);
+    let tokens = JwtTokens::with_signing_keys(&jwt_config(), keys.clone());
+    let created_at: DateTime<Utc> = Utc::now();
+    keys.refresh_at(created_at).await.unwrap();
+    let old_token = issue(&tokens);
+    let old_kid = kid(&old_token);
+
+    keys.refresh_at(created_at + ROTATION_INTERVAL).await.unwrap();
+
+    let published = kids(&keys);
+    assert_eq!(published.len(), 2);
+    assert_eq!(published[0], old_kid);
+    assert_eq!(kid(&issue(&tokens)), old_kid);
+
+    keys.refresh_at(created_at + ROTATION_INTERVAL + PUBLICATION_DELAY).await.unwrap();
+
+    assert_eq!(kid(&issue(&tokens)), published[1]);
+    // Tokens of the retiring key remain valid
+    assert_eq!(tokens.verify(&old_token).unwrap().user_id, "jdoe");
+}
+
+#[tokio::test]
+async fn removes_retired_keys_once_their_tokens_expired() {
+    let keys = signing_keys(&Arc::new(InMemorySigningKeyStore::new()), SECRET);
+    let tokens = JwtTokens::with_signing_keys(&jwt_config(), keys.clone());
+    let created_at = Utc::now();
+    keys.refresh_at(created_at).await.unwrap();
+    let old_token = issue(&tokens);
+    let replaced_at = created_at + ROTATION_INTERVAL + PUBLICATION_DELAY;
+
+    keys.refresh_at(created_at + ROTATION_INTERVAL).await.unwrap();
+    keys.refresh_at(replaced_at + TOKEN_LIFETIME - TimeDelta::seconds(1)).await.unwrap();
+
+    assert_eq!(kids(&keys).len(), 2);
+    assert!(tokens.verify(&old_token).is_ok());
+
+    keys.refresh_at(replaced_at + TOKEN_LIFETIME).await.unwrap();
+
+    assert_eq!(kids(&keys).len(), 1);
+    assert!(matches!(tokens.verify(&old_token), Err(AuthError::InvalidToken)));
+}
+
+#[tokio::test]
+async fn shares_keys_between_replicas() {
+    let store = Arc::new(InMemorySigningKeyStore::new());
+    let first = signing_keys(&store, SECRET);
+    let second = signing_keys(&store, SECRET);
+    let now = Utc::now();
+
+    // Both replicas find no key and generate one, only one of them is stored
+    tokio::try_join!(first.refresh_at(now), second.refresh_at(now)).unwrap();
+
+    assert_eq!(store.list().await.unwrap().len(), 1);
+    assert_eq!(kids(&first), kids(&second));
+    let token = issue(&JwtTokens::with_signing_keys(&jwt_config(), first));
+    assert!(JwtTokens::with_signing_keys(&jwt_config(), second).verify(&token).is_ok());
+}
+
+#[tokio::test]
+async fn encrypts_stored_keys_with_the_secret() {
+    let store = Arc::new(InMemorySigningKeyStore::new());
+    signing_keys(&store, SECRET).refresh().await.unwrap();
+
+    let error = signing_keys(&store, "other-secret").refresh().await.unwrap_err();
+
+    assert!(error.to_string().contains("failed to decrypt signing key"), "{}", error);
+}
+
+#[test]
+fn rejects_refresh_intervals_missing_new_keys() {
+    let config = SigningKeysConfig { rotation_interval_secs: 86400, publication_delay_secs: 60, refresh_interval_secs: 60 };
+
+    let result = SigningKeys::new(Arc::new(InMemorySigningKeyStore::new()), SECRET, &config, Duration::from_secs(3600));
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn serves_public_keys_as_jwks() {
+    let keys = signing_keys(&Arc::new(InMemorySigningKeyStore::new()), SECRET);
+    let created_at = Utc::now();
+    keys.refresh_at(created_at).await.unwrap();
+    keys.refresh_at(created_at + ROTATION_INTERVAL).await.unwrap();
+    let token = issue(&JwtTokens::with_signing_keys(&jwt_config(), keys.clone()));
+    let app = router(AppState {
+        key_set: Some(keys),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    });
+
+    let (status, cache_control, body) = get_jwks(&app).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(cache_control.as_deref(), Some("public, max-age=300"));
+    let jwks = body["keys"].as_array().unwrap();
+    assert_eq!(jwks.len(), 2);
+    for jwk in jwks {
+        assert_eq!(jwk["kty"], "EC");
+        assert_eq!(jwk["crv"], "P-256");
+        assert_eq!(jwk["alg"], "ES256");
+        assert_eq!(jwk["use"], "sig");
+    }
+
+    // Services validating the tokens find their key in the key set
+    let jwk = jwks.iter().find(|jwk| jwk["kid"] == kid(&token)).unwrap();
+    let key = DecodingKey::from_ec_components(jwk["x"].as_str().unwrap(), jwk["y"].as_str().unwrap()).unwrap();
+    let claims = decode::<Value>(&token, &key, &Validation::new(Algorithm::ES256)).unwrap().claims;
+    assert_eq!(claims["sub"], "jdoe");
+}
+
+#[tokio::test]
+async fn jwks_is_not_mounted_by_default() {
+    let app = router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))));
+
+    assert_eq!(get_jwks(&app).await.0, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,198 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::slo_tracker::{SloObjective, SloPolicy, SloTracker, SLO_BURN_RATE_ALERT};
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::alert::{Alert, AlertPort};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::slo_handlers::SloState;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+const BUCKET: Duration = Duration::from_secs(60);
+
+const FAST: Duration = Duration::from_millis(10);
+
+const SLOW: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+struct RecordingAlerts(Mutex<Vec<Alert>>);
+
+impl AlertPort for RecordingAlerts {
+    fn raise(&self, alert: Alert) {
+        self.0.lock().unwrap().push(alert);
+    }
+}
+
+impl RecordingAlerts {
+    fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+}
+
+fn reads() -> SloObjective {
+    SloObjective { name: "reads".to_string(), method: Some("GET".to_string()), latency: Duration::from_millis(100), target: 0.9 }
+}
+
+fn policy(objectives: Vec<SloObjective>) -> SloPolicy {
+    SloPolicy {
+        objectives,
+        window: BUCKET * 60,
+        bucket: BUCKET,
+        burn_window: BUCKET * 5,
+        burn_rate_threshold: 5.0,
+        min_requests: 10,
+    }
+}
+
+fn tracker(policy: SloPolicy) -> (SloTracker, Arc<RecordingAlerts>) {
+    let alerts = Arc::new(RecordingAlerts::default());
+    (SloTracker::new(policy, alerts.clone()).unwrap(), alerts)
+}
+
+/// Records `count` requests of `method` served with `status` within `latency` at `at`.
+fn record(tracker: &SloTracker, method: &str, status: u16, latency: Duration, count: u32, at: Instant) {
+    for _ in 0..count {
+        tracker.record_at(method, status, latency, at);
+    }
+}
+
+#[test]
+fn reports_the_compliance_and_the_error_budget_of_the_window() {
+    let (tracker, _) = tracker(policy(vec![reads()]));
+    let start = Instant::now();
+
+    record(&tracker, "GET", 200, FAST, 95, start);
+    record(&tracker, "GET", 200, SLOW, 3, start);
+    record(&tracker, "GET", 503, FAST, 2, start);
+    // Not covered by the objective
+    record(&tracker, "POST", 200, SLOW, 50, start);
+
+    let [report] = &tracker.report_at(start)[..] else { panic!("expected one report") };
+    assert_eq!((report.total, report.good), (100, 95));
+    assert_eq!(report.compliance, Some(0.95));
+    // 5% of the requests were bad, half of the 10% allowed
+    assert!((report.error_budget_remaining - 0.5).abs() < 1e-9, "{}", report.error_budget_remaining);
+    assert!((report.burn_rate - 0.5).abs() < 1e-9, "{}", report.burn_rate);
+}
+
+#[test]
+fn forgets_the_requests_of_past_windows() {
+    let (tracker, _) = tracker(policy(vec![reads()]));
+    let start = Instant::now();
+    record(&tracker, "GET", 200, SLOW, 5, start);
+    record(&tracker, "GET", 200, FAST, 5, start + BUCKET * 10);
+
+    let report = &tracker.report_at(start + BUCKET * 10)[0];
+    assert_eq!((report.total, report.good), (10, 5));
+    // The slow requests are out of the burn window, not of the window
+    assert_eq!(report.burn_rate, 0.0);
+
+    let report = &tracker.report_at(start + BUCKET * 65)[0];
+    assert_eq!((report.total, report.good), (5, 5));
+
+    let report = &tracker.report_at(start + BUCKET * 75)[0];
+    assert_eq!((report.total, report.compliance, report.error_budget_remaining), (0, None, 1.0));
+}
+
+#[test]
+fn alerts_once_per_burn_window_when_the_budget_burns_too_fast() {
+    let (tracker, alerts) = tracker(policy(vec![reads()]));
+    let start = Instant::now();
+
+    // 40% of the requests are slow, 4 times the budget, under the threshold
+    record(&tracker, "GET", 200, FAST, 6, start);
+    record(&tracker, "GET", 200, SLOW, 4, start);
+    assert_eq!(alerts.len(), 0);
+
+    // 70% are, 7 times the budget
+    record(&tracker, "GET", 200, SLOW, 10, start + BUCKET);
+    assert_eq!(alerts.len(), 1);
+    let alert = alerts.0.lock().unwrap()[0].clone();
+    assert_eq!(alert.name, SLO_BURN_RATE_ALERT);
+    assert!(alert.message.contains("SLO reads"), "{}", alert.message);
+
+    record(&tracker, "GET", 200, SLOW, 10, start + BUCKET * 3);
+    assert_eq!(alerts.len(), 1);
+    record(&tracker, "GET", 200, SLOW, 10, start + BUCKET * 6);
+    assert_eq!(alerts.len(), 2);
+}
+
+#[test]
+fn needs_enough_requests_to_alert() {
+    let (tracker, alerts) = tracker(policy(vec![reads()]));
+
+    record(&tracker, "GET", 500, FAST, 9, Instant::now());
+
+    assert_eq!(alerts.len(), 0);
+}
+
+#[test]
+fn rejects_invalid_policies() {
+    let alerts = Arc::new(RecordingAlerts::default());
+    let objective = |target| SloObjective { target, ..reads() };
+
+    assert!(SloTracker::new(policy(vec![objective(1.0)]), alerts.clone()).is_err());
+    assert!(SloTracker::new(policy(vec![objective(0.0)]), alerts.clone()).is_err());
+    assert!(SloTracker::new(policy(vec![reads(), reads()]), alerts.clone()).is_err());
+    assert!(SloTracker::new(SloPolicy { burn_window: BUCKET * 61, ..policy(vec![reads()]) }, alerts.clone()).is_err());
+    assert!(SloTracker::new(SloPolicy { burn_rate_threshold: 0.0, ..policy(vec![reads()]) }, alerts).is_err());
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, Value) {
+    let request = Request::builder().uri(uri).header(header::AUTHORIZATION, "Bearer secret").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn reports_the_objectives_of_the_api_requests() {
+    let all = SloObjective { name: "all".to_string(), method: None, latency: Duration::from_secs(10), target: 0.99 };
+    let (tracker, _) = tracker(policy(vec![reads(), all]));
+    let app = router(AppState {
+        admin_token: Some("secret".into()),
+        slos: SloState { tracker: Some(Arc::new(tracker)) },
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    });
+
+    assert_eq!(get(&app, "/api/users").await.0, StatusCode::OK);
+    assert_eq!(get(&app, "/api/users/search").await.0, StatusCode::OK);
+    // Probes are not tracked
+    assert_eq!(get(&app, "/healthz").await.0, StatusCode::OK);
+
+    let (status, body) = get(&app, "/api/admin/slos").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["window_secs"], 3600);
+    assert_eq!(body["data"]["burn_window_secs"], 300);
+    let objectives = body["data"]["objectives"].as_array().unwrap();
+    assert_eq!(objectives[0]["name"], "reads");
+    assert_eq!(objectives[0]["method"], "GET");
+    assert_eq!(objectives[0]["latency_ms"], 100);
+    assert_eq!(objectives[0]["target"], 0.9);
+    assert_eq!(objectives[1]["name"], "all");
+    assert_eq!(objectives[1]["method"], Value::Null);
+    assert_eq!(objectives[1]["total"], 2);
+    assert_eq!(objectives[1]["good"], 2);
+    assert_eq!(objectives[1]["compliance"], 1.0);
+    assert_eq!(objectives[1]["error_budget_remaining"], 1.0);
+}
+
+#[tokio::test]
+async fn reports_no_objectives_when_disabled() {
+    let app = router(AppState {
+        admin_token: Some("secret".into()),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    });
+
+    let (status, body) = get(&app, "/api/admin/slos").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!({ "objectives": [] }));
+}
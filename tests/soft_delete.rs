@@ -0,0 +1,135 @@
+mod common;
+
+use std::sync::Arc;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::domain::user::error::UserDomainError;
+use rust_web_server_lib::domain::user::model::{CreateUser, UserId};
+use rust_web_server_lib::infra::storage::adapter::in_memory::outbox::InMemoryOutbox;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+use common::{auth_state, send, token, ADMIN_TOKEN};
+
+fn jdoe() -> CreateUser {
+    CreateUser::new("John Doe".to_string(), "jdoe@example.com".to_string(), 42).unwrap()
+}
+
+/// Returns an app with a single user, and the id of the user.
+async fn app() -> (axum::Router, String) {
+    let user_service = Arc::new(UserService::new(InMemoryUserRepository::new()));
+    let id = user_service.create_user(jdoe()).await.unwrap().id().to_string();
+
+    let router = router(AppState {
+        auth: auth_state(),
+        admin_token: Some(ADMIN_TOKEN.into()),
+        ..AppState::new(user_service)
+    });
+    (router, id)
+}
+
+#[tokio::test]
+async fn deleted_users_are_hidden_until_restored() {
+    let (app, id) = app().await;
+    let token = token("user-1");
+    let user = format!("/api/users/{}", id);
+
+    assert_eq!(send(&app, Method::DELETE, &user, Some(&token), None).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(send(&app, Method::GET, &user, Some(&token), None).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(send(&app, Method::GET, "/api/users", Some(&token), None).await.1["data"]["total"], 0);
+    assert_eq!(send(&app, Method::GET, "/api/users/search?name=john", Some(&token), None).await.1["data"]["total"], 0);
+    // Deleted users cannot be deleted again
+    assert_eq!(send(&app, Method::DELETE, &user, Some(&token), None).await.0, StatusCode::NOT_FOUND);
+
+    let (status, body) = send(&app, Method::POST, &format!("{}/restore", user), Some(&token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!({ "id": id, "name": "John Doe", "email": "jdoe@example.com", "age": 42 }));
+    assert_eq!(send(&app, Method::GET, &user, Some(&token), None).await.0, StatusCode::OK);
+
+    // Only deleted users can be restored
+    assert_eq!(send(&app, Method::POST, &format!("{}/restore", user), Some(&token), None).await.0, StatusCode::NOT_FOUND);
+}
+
+async fn set_legal_hold(app: &axum::Router, id: &str, legal_hold: bool) {
+    let (status, _) = send(app, Method::PUT, &format!("/api/admin/users/{}/legal-hold", id), Some(ADMIN_TOKEN), Some(json!({ "legal_hold": legal_hold }))).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn admins_delete_users_for_good() {
+    let (app, id) = app().await;
+    let token = token("user-1");
+    let admin_user = format!("/api/admin/users/{}", id);
+
+    // Users under legal hold are kept, and user tokens are not admin tokens
+    set_legal_hold(&app, &id, true).await;
+    assert_eq!(send(&app, Method::DELETE, &admin_user, Some(ADMIN_TOKEN), None).await.0, StatusCode::LOCKED);
+    set_legal_hold(&app, &id, false).await;
+    assert_eq!(send(&app, Method::DELETE, &admin_user, Some(&token), None).await.0, StatusCode::UNAUTHORIZED);
+
+    // Deleted users can be deleted for good, and are then gone
+    assert_eq!(send(&app, Method::DELETE, &format!("/api/users/{}", id), Some(&token), None).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(send(&app, Method::DELETE, &admin_user, Some(ADMIN_TOKEN), None).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(send(&app, Method::POST, &format!("/api/users/{}/restore", id), Some(&token), None).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(send(&app, Method::DELETE, &admin_user, Some(ADMIN_TOKEN), None).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn publishes_restorations_and_hard_deletions() {
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let service = UserService::new(InMemoryUserRepository::new()).with_event_publisher(outbox.clone());
+
+    let user = service.create_user(jdoe()).await.unwrap();
+    service.delete_user(user.id()).await.unwrap();
+    // Deleting and restoring the user each changed its version
+    assert_eq!(service.restore_user(user.id()).await.unwrap(), user.clone().with_version(user.version() + 2));
+    service.hard_delete_user(user.id()).await.unwrap();
+    assert_eq!(service.restore_user(user.id()).await.unwrap_err(), UserDomainError::UserNotFound);
+    assert_eq!(service.hard_delete_user(UserId::generate()).await.unwrap_err(), UserDomainError::UserNotFound);
+
+    assert_eq!(outbox.pending().await, 4);
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::domain::user::model::{ListUsers, SortDirection, UpdateUser, UserFilter, UserSortField};
+    use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn leaves_soft_deleted_users_out_of_reads_and_changes() {
+        let db = TestDb::new().await.unwrap();
+        let users = UserRepository::new(db.db());
+        let user = users.create_user(jdoe(), None).await.unwrap();
+        users.delete_user(user.id()).await.unwrap();
+
+        assert_eq!(users.get_user(user.id()).await.unwrap_err(), UserDomainError::UserNotFound);
+        assert_eq!(users.get_user_by_email(user.email().clone()).await.unwrap_err(), UserDomainError::UserNotFound);
+        let query = ListUsers { limit: 20, offset: 0, sort_by: UserSortField::Name, direction: SortDirection::Asc, after: None };
+        assert_eq!(users.list_users(query).await.unwrap().total, 0);
+        let filter = UserFilter { name: None, email: None, min_age: None, max_age: None, limit: 20, offset: 0, after: None };
+        assert_eq!(users.search_users(filter).await.unwrap().total, 0);
+        assert!(users.count_user_facets().await.unwrap().by_email_domain.is_empty());
+        let update = UpdateUser::new(user.id(), None, None, Some(43)).unwrap();
+        assert_eq!(users.update_user(update).await.unwrap_err(), UserDomainError::UserNotFound);
+        assert_eq!(users.set_legal_hold(user.id(), true).await.unwrap_err(), UserDomainError::UserNotFound);
+        assert_eq!(users.delete_user(user.id()).await.unwrap_err(), UserDomainError::UserNotFound);
+
+        let restored = user.clone().with_version(user.version() + 2);
+        assert_eq!(users.restore_user(user.id()).await.unwrap(), restored);
+        assert_eq!(users.get_user(user.id()).await.unwrap(), restored);
+        assert_eq!(users.restore_user(user.id()).await.unwrap_err(), UserDomainError::UserNotFound);
+
+        users.delete_user(user.id()).await.unwrap();
+        users.hard_delete_user(user.id()).await.unwrap();
+        assert_eq!(users.restore_user(user.id()).await.unwrap_err(), UserDomainError::UserNotFound);
+        assert_eq!(users.hard_delete_user(user.id()).await.unwrap_err(), UserDomainError::UserNotFound);
+    }
+}
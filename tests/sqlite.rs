@@ -0,0 +1,191 @@
+/// The SQLite adapter, run against in-memory and temporary file databases.
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use std::sync::Arc;
+
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    use rust_web_server_lib::application::flows::user_service::UserService;
+    use rust_web_server_lib::domain::user::error::UserDomainError;
+    use rust_web_server_lib::domain::user::model::{CreateUser, ListUsers, PasswordHash, Role, SortDirection, UpdateUser, User, UserCredentials, UserFilter, UserId, UserSortField};
+    use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+    use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+    use rust_web_server_lib::infra::storage::adapter::is_sqlite_url;
+    use rust_web_server_lib::infra::storage::adapter::sqlite::user_repository::SqliteUserRepository;
+    use rust_web_server_lib::infra::storage::adapter::sqlite::{create_sqlite_repositories, db_connect, run_migrations, Db};
+    use rust_web_server_lib::infra::storage::PoolConfig;
+    use rust_web_server_lib::presentation::http::{router, AppState};
+
+    /// Users with their name, email and age.
+    const USERS: [(&str, &str, u8); 5] = [
+        ("José Núñez", "jose@example.com", 17),
+        ("Jane Doe", "jane@Example.org", 30),
+        ("Joe Bloggs", "joe@example.com", 34),
+        ("Ann Nunez", "ann@mail.example.com", 65),
+        ("Bob", "bob@exámple.org", 120),
+    ];
+
+    fn pool() -> PoolConfig {
+        PoolConfig { max_connections: 5, min_connections: 0, acquire_timeout_secs: 30, idle_timeout_secs: None, max_lifetime_secs: None, connect_attempts: 1 }
+    }
+
+    async fn db() -> Db {
+        let db = db_connect("sqlite::memory:", &pool()).await.unwrap();
+        run_migrations(&db).await.unwrap();
+        db
+    }
+
+    fn jane() -> CreateUser {
+        CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap()
+    }
+
+    async fn create_users(users: &(dyn UserRepositoryPort + Send + Sync)) {
+        for (i, (name, email, age)) in USERS.into_iter().enumerate() {
+            let user = users.create_user(CreateUser::new(name.to_string(), email.to_string(), age).unwrap(), None).await.unwrap();
+            if i == 0 {
+                users.set_legal_hold(user.id(), true).await.unwrap();
+            }
+        }
+    }
+
+    fn names(users: &[User]) -> Vec<&str> {
+        users.iter().map(User::name).collect()
+    }
+
+    #[test]
+    fn recognizes_sqlite_urls() {
+        assert!(is_sqlite_url("sqlite://users.db"));
+        assert!(is_sqlite_url("sqlite::memory:"));
+        assert!(!is_sqlite_url("postgres://postgres@localhost/users"));
+    }
+
+    #[tokio::test]
+    async fn stores_users() {
+        let users = SqliteUserRepository::new(db().await);
+        let hash = PasswordHash::new("$argon2id$v=19$m=8,t=1,p=1$c2FsdA$aGFzaA");
+
+        let user = users.create_user(jane(), Some(hash.clone())).await.unwrap();
+        assert_eq!(users.get_user(user.id()).await.unwrap(), user);
+        // Emails are looked up ignoring case and accents
+        assert_eq!(users.get_user_by_email("JANE@exámple.com".parse().unwrap()).await.unwrap(), user);
+        assert_eq!(users.get_user_credentials(user.email().clone()).await.unwrap(), UserCredentials { user: user.clone(), password_hash: Some(hash) });
+
+        assert_eq!(users.set_role(user.id(), Role::Admin).await.unwrap().role(), Role::Admin);
+        assert!(users.set_legal_hold(user.id(), true).await.unwrap().legal_hold());
+        let update = UpdateUser::new(user.id(), Some("Janet".to_string()), None, Some(31)).unwrap();
+        let updated = users.update_user(update).await.unwrap();
+        assert_eq!((updated.name(), updated.age(), updated.role(), updated.legal_hold()), ("Janet", 31, Role::Admin, true));
+        assert_eq!(users.get_user(user.id()).await.unwrap(), updated);
+
+        users.delete_user(user.id()).await.unwrap();
+        assert_eq!(users.get_user(user.id()).await.unwrap_err(), UserDomainError::UserNotFound);
+        assert_eq!(users.get_user_by_email(user.email().clone()).await.unwrap_err(), UserDomainError::UserNotFound);
+        assert_eq!(users.restore_user(user.id()).await.unwrap(), updated.clone().with_version(updated.version() + 2));
+
+        users.hard_delete_user(user.id()).await.unwrap();
+        assert_eq!(users.restore_user(user.id()).await.unwrap_err(), UserDomainError::UserNotFound);
+        assert_eq!(users.hard_delete_user(user.id()).await.unwrap_err(), UserDomainError::UserNotFound);
+        assert_eq!(users.set_role(UserId::generate(), Role::User).await.unwrap_err(), UserDomainError::UserNotFound);
+    }
+
+    #[tokio::test]
+    async fn rejects_users_with_the_email_of_another_by_its_error_kind() {
+        let users = SqliteUserRepository::new(db().await);
+        users.create_user(jane(), None).await.unwrap();
+
+        assert_eq!(users.create_user(jane(), None).await.unwrap_err(), UserDomainError::UserAlreadyExists);
+        // Emails are compared ignoring case and accents
+        let shouted = CreateUser::new("Jane".to_string(), "JANE@exámple.com".to_string(), 30).unwrap();
+        assert_eq!(users.create_user(shouted, None).await.unwrap_err(), UserDomainError::UserAlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn releases_the_email_of_deleted_users_until_restored() {
+        let users = SqliteUserRepository::new(db().await);
+        let deleted = users.create_user(jane(), None).await.unwrap();
+        users.delete_user(deleted.id()).await.unwrap();
+
+        let registered = users.create_user(jane(), None).await.unwrap();
+        assert_eq!(users.get_user_by_email(registered.email().clone()).await.unwrap(), registered);
+        assert_eq!(users.restore_user(deleted.id()).await.unwrap_err(), UserDomainError::UserAlreadyExists);
+    }
+
+    #[tokio::test]
+    async fn updates_users_at_the_expected_version_only() {
+        let users = SqliteUserRepository::new(db().await);
+        let user = users.create_user(jane(), None).await.unwrap();
+        let rename = |version| UpdateUser::new(user.id(), Some("Janet".to_string()), None, None).unwrap().with_expected_version(version);
+
+        let updated = users.update_user(rename(User::INITIAL_VERSION)).await.unwrap();
+        assert_eq!(updated.version(), User::INITIAL_VERSION + 1);
+        assert_eq!(users.update_user(rename(User::INITIAL_VERSION)).await.unwrap_err(), UserDomainError::UserVersionMismatch);
+        assert_eq!(users.set_legal_hold(user.id(), true).await.unwrap().version(), updated.version() + 1);
+        assert_eq!(users.update_user(rename(7)).await.unwrap_err(), UserDomainError::UserVersionMismatch);
+        assert_eq!(users.get_user(user.id()).await.unwrap().name(), "Janet");
+    }
+
+    #[tokio::test]
+    async fn lists_searches_and_counts_users_like_the_in_memory_repository() {
+        let (sqlite, in_memory) = (SqliteUserRepository::new(db().await), InMemoryUserRepository::new());
+        create_users(&sqlite).await;
+        create_users(&in_memory).await;
+
+        for sort_by in [UserSortField::Name, UserSortField::Email, UserSortField::Age] {
+            for direction in [SortDirection::Asc, SortDirection::Desc] {
+                let query = ListUsers { limit: 3, offset: 1, sort_by, direction, after: None };
+                let (page, expected) = (sqlite.list_users(query.clone()).await.unwrap(), in_memory.list_users(query).await.unwrap());
+                assert_eq!((names(&page.users), page.total), (names(&expected.users), expected.total), "{:?} {:?}", sort_by, direction);
+            }
+        }
+
+        let filter = UserFilter { name: None, email: None, min_age: None, max_age: None, limit: 20, offset: 0, after: None };
+        for filter in [
+            UserFilter { name: Some("NUÑEZ".to_string()), ..filter.clone() },
+            UserFilter { email: Some("example.org".to_string()), ..filter.clone() },
+            UserFilter { email: Some("example.com".to_string()), min_age: Some(18), max_age: Some(65), ..filter.clone() },
+            UserFilter { min_age: Some(18), limit: 2, offset: 1, ..filter },
+        ] {
+            let (page, expected) = (sqlite.search_users(filter.clone()).await.unwrap(), in_memory.search_users(filter.clone()).await.unwrap());
+            assert_eq!((names(&page.users), page.total), (names(&expected.users), expected.total), "{:?}", filter);
+        }
+
+        assert_eq!(sqlite.count_user_facets().await.unwrap(), in_memory.count_user_facets().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn keeps_users_in_the_database_file() {
+        let path = std::env::temp_dir().join(format!("users-{}.db", UserId::generate()));
+        let url = format!("sqlite://{}", path.display());
+
+        let db = db_connect(&url, &pool()).await.unwrap();
+        run_migrations(&db).await.unwrap();
+        let user = SqliteUserRepository::new(db.clone()).create_user(jane(), None).await.unwrap();
+        db.close().await;
+
+        let db = db_connect(&url, &pool()).await.unwrap();
+        // Migrations already applied are skipped
+        run_migrations(&db).await.unwrap();
+        assert_eq!(SqliteUserRepository::new(db.clone()).get_user(user.id()).await.unwrap(), user);
+        db.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_the_users_stored_in_sqlite() {
+        let repositories = create_sqlite_repositories(db().await).unwrap();
+        let app = router(AppState::new(Arc::new(UserService::new(repositories.user_repository))));
+
+        let request = Request::post("/api/users").header("content-type", "application/json").body(Body::from(json!({"name": "Jane", "email": "jane@example.com", "age": 30}).to_string())).unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body: Value = serde_json::from_slice(&axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap()).unwrap();
+
+        let response = app.oneshot(Request::get(format!("/api/users/{}", body["data"]["id"].as_str().unwrap())).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::email::EmailMessage;
+use rust_web_server_lib::application::ports::tenant::{TenantBranding, TenantConfigPort, TenantSettings};
+use rust_web_server_lib::infra::storage::adapter::in_memory::tenant_config::InMemoryTenantConfig;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::infra::storage::cached_tenant_config::CachedTenantConfig;
+use rust_web_server_lib::presentation::handlers::tenant_handlers::TenantState;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::rate_limit::{RateLimitPolicy, RateLimiter};
+
+fn app(config: Option<Arc<dyn TenantConfigPort + Send + Sync>>, requests: u32) -> axum::Router {
+    router(AppState {
+        admin_token: Some("secret".into()),
+        tenants: TenantState { config },
+        rate_limiter: Some(
+            RateLimiter::new(RateLimitPolicy { requests, period: Duration::from_secs(60), route_overrides: Vec::new(), class_overrides: Vec::new(), trust_forwarded_for: false }).unwrap(),
+        ),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+fn admin(method: Method, tenant: &str, body: Option<Value>) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(format!("/api/admin/tenants/{}/settings", tenant))
+        .header(header::AUTHORIZATION, "Bearer secret")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap()
+}
+
+fn list_users(tenant: Option<&str>) -> Request<Body> {
+    let mut request = Request::get("/api/users");
+    if let Some(tenant) = tenant {
+        request = request.header("x-tenant-id", tenant);
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn manages_the_settings_of_tenants() {
+    let app = app(Some(Arc::new(InMemoryTenantConfig::new())), 100);
+    assert_eq!(send(&app, admin(Method::GET, "acme", None)).await.0, StatusCode::NOT_FOUND);
+
+    let settings = json!({ "rate_limit_requests": 5, "feature_flags": { "bulk_import": true }, "webhook_secret": "whsec" });
+    let (status, body) = send(&app, admin(Method::PUT, "acme", Some(settings))).await;
+    assert_eq!(status, StatusCode::OK);
+    // The webhook secret is never returned
+    let saved = json!({ "tenant": "acme", "rate_limit_requests": 5, "feature_flags": { "bulk_import": true }, "webhook_secret_set": true, "branding": {} });
+    assert_eq!(body["data"], saved);
+    assert_eq!(send(&app, admin(Method::GET, "acme", None)).await.1["data"], saved);
+
+    // Settings left out are reset
+    let (_, body) = send(&app, admin(Method::PUT, "acme", Some(json!({ "rate_limit_requests": 10 })))).await;
+    assert_eq!(body["data"], json!({ "tenant": "acme", "rate_limit_requests": 10, "feature_flags": {}, "webhook_secret_set": false, "branding": {} }));
+
+    assert_eq!(send(&app, admin(Method::DELETE, "acme", None)).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(send(&app, admin(Method::GET, "acme", None)).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(send(&app, admin(Method::DELETE, "acme", None)).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn rejects_invalid_settings() {
+    let app = app(Some(Arc::new(InMemoryTenantConfig::new())), 100);
+
+    let (status, body) = send(&app, admin(Method::PUT, "acme", Some(json!({ "rate_limit_requests": 0 })))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["data"]["message"], "Rate limit must be between 1 and 2147483647");
+    assert_eq!(send(&app, admin(Method::PUT, "acme", Some(json!({ "webhook_secret": "" })))).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(send(&app, admin(Method::PUT, "ac%20me", Some(json!({})))).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+    for branding in [json!({ "error_templates": { "200": "Fine" } }), json!({ "error_templates": { "404": " " } }), json!({ "support_url": "javascript:alert(1)" }), json!({ "email_footer": "" })] {
+        let (status, _) = send(&app, admin(Method::PUT, "acme", Some(json!({ "branding": branding.clone() })))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{}", branding);
+    }
+    assert_eq!(send(&app, admin(Method::GET, "acme", None)).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn applies_the_rate_limit_of_the_tenant() {
+    let config = Arc::new(InMemoryTenantConfig::new());
+    config.put("acme", TenantSettings { rate_limit_requests: Some(1), ..TenantSettings::default() }).await.unwrap();
+    let app = app(Some(config), 2);
+
+    assert_eq!(send(&app, list_users(Some("acme"))).await.0, StatusCode::OK);
+    assert_eq!(send(&app, list_users(Some("acme"))).await.0, StatusCode::TOO_MANY_REQUESTS);
+
+    // Other tenants, and requests without a tenant, get the limit of the deployment in buckets of their own
+    for tenant in [Some("globex"), None] {
+        assert_eq!(send(&app, list_users(tenant)).await.0, StatusCode::OK);
+        assert_eq!(send(&app, list_users(tenant)).await.0, StatusCode::OK);
+        assert_eq!(send(&app, list_users(tenant)).await.0, StatusCode::TOO_MANY_REQUESTS);
+    }
+}
+
+#[tokio::test]
+async fn brands_the_error_responses_of_the_tenant() {
+    let app = app(Some(Arc::new(InMemoryTenantConfig::new())), 100);
+    let branding = json!({ "error_templates": { "404": "Acme: {message} ({status})" }, "support_url": "mailto:support@acme.test" });
+    let (_, body) = send(&app, admin(Method::PUT, "acme", Some(json!({ "branding": branding.clone() })))).await;
+    assert_eq!(body["data"]["branding"], branding);
+
+    let get_user = |tenant: &str| Request::get("/api/users/00000000-0000-0000-0000-000000000000").header("x-tenant-id", tenant).body(Body::empty()).unwrap();
+    let (status, body) = send(&app, get_user("acme")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["data"], json!({ "code": "not_found", "message": "Acme: User not found (404)", "support_url": "mailto:support@acme.test" }));
+
+    // Statuses without a template keep their message, successes are left alone
+    let request = Request::post("/api/users").header("x-tenant-id", "acme").header(header::CONTENT_TYPE, "application/json").body(Body::from(r#"{"name":"","email":"ada","age":0}"#)).unwrap();
+    let (status, body) = send(&app, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["data"]["support_url"], "mailto:support@acme.test");
+    assert_eq!(body["data"]["message"], "Invalid request body");
+    assert_eq!(send(&app, list_users(Some("acme"))).await.1["data"].get("support_url"), None);
+
+    // Other tenants get the responses of the deployment
+    assert_eq!(send(&app, get_user("globex")).await.1["data"], json!({ "code": "not_found", "message": "User not found" }));
+}
+
+#[tokio::test]
+async fn brands_the_emails_of_the_tenant() {
+    let config = InMemoryTenantConfig::new();
+    let branding = TenantBranding { email_sender_name: Some("Acme".to_string()), email_footer: Some("Acme Corp, 1 Main St".to_string()), ..TenantBranding::default() };
+    config.put("acme", TenantSettings { branding, ..TenantSettings::default() }).await.unwrap();
+    let message = EmailMessage { to: "ada@example.com".to_string(), subject: "Welcome".to_string(), body: "Hello Ada\n".to_string() };
+
+    let branded = config.brand_email("acme", message.clone()).await;
+    assert_eq!(branded.subject, "[Acme] Welcome");
+    assert_eq!(branded.body, "Hello Ada\n\n--\nAcme Corp, 1 Main St");
+    assert_eq!(config.brand_email("globex", message.clone()).await, message);
+}
+
+#[tokio::test]
+async fn rejects_invalid_tenant_ids() {
+    let app = app(Some(Arc::new(InMemoryTenantConfig::new())), 100);
+
+    let (status, body) = send(&app, list_users(Some("acme corp"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["data"]["message"], "X-Tenant-Id must be 1 to 64 letters, digits, '.', '_' or '-'");
+    assert_eq!(send(&app, list_users(Some(&"a".repeat(65)))).await.0, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn ignores_tenants_when_disabled() {
+    let app = app(None, 100);
+
+    assert_eq!(send(&app, list_users(Some("acme corp"))).await.0, StatusCode::OK);
+    let (status, body) = send(&app, admin(Method::GET, "acme", None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["data"]["message"], "Tenant settings are not supported");
+}
+
+#[tokio::test]
+async fn requires_the_admin_token() {
+    let app = app(Some(Arc::new(InMemoryTenantConfig::new())), 100);
+    let request = Request::put("/api/admin/tenants/acme/settings").header(header::CONTENT_TYPE, "application/json").body(Body::from("{}")).unwrap();
+
+    assert_eq!(send(&app, request).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn resolves_feature_flags_and_webhook_secrets() {
+    let config = InMemoryTenantConfig::new();
+    let settings = TenantSettings {
+        feature_flags: BTreeMap::from([("bulk_import".to_string(), false)]),
+        webhook_secret: Some("whsec".to_string()),
+        ..TenantSettings::default()
+    };
+    config.put("acme", settings.clone()).await.unwrap();
+
+    assert!(!config.feature_enabled("acme", "bulk_import", true).await);
+    assert!(config.feature_enabled("acme", "exports", true).await);
+    assert!(!config.feature_enabled("globex", "bulk_import", false).await);
+    assert_eq!(config.webhook_secret("acme").await.unwrap(), Some("whsec".to_string()));
+    assert_eq!(config.webhook_secret("globex").await.unwrap(), None);
+    assert!(!format!("{:?}", settings).contains("whsec"));
+}
+
+/// Tenant config counting the reads reaching it.
+#[derive(Default)]
+struct CountingTenantConfig {
+    inner: InMemoryTenantConfig,
+    reads: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl TenantConfigPort for CountingTenantConfig {
+    async fn get(&self, tenant: &str) -> eyre::Result<Option<TenantSettings>> {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+        self.inner.get(tenant).await
+    }
+
+    async fn put(&self, tenant: &str, settings: TenantSettings) -> eyre::Result<()> {
+        self.inner.put(tenant, settings).await
+    }
+
+    async fn delete(&self, tenant: &str) -> eyre::Result<bool> {
+        self.inner.delete(tenant).await
+    }
+}
+
+#[tokio::test]
+async fn caches_the_settings_of_tenants_until_changed() {
+    let inner = CountingTenantConfig::default();
+    let reads = inner.reads.clone();
+    let config = CachedTenantConfig::new(inner, Duration::from_secs(60));
+    let limited = TenantSettings { rate_limit_requests: Some(5), ..TenantSettings::default() };
+
+    // The absence of settings is cached too
+    assert_eq!(config.get("acme").await.unwrap(), None);
+    assert_eq!(config.get("acme").await.unwrap(), None);
+    assert_eq!(reads.load(Ordering::SeqCst), 1);
+
+    config.put("acme", limited.clone()).await.unwrap();
+    assert_eq!(config.get("acme").await.unwrap(), Some(limited.clone()));
+    assert_eq!(config.get("acme").await.unwrap(), Some(limited));
+    assert_eq!(reads.load(Ordering::SeqCst), 2);
+
+    assert!(config.delete("acme").await.unwrap());
+    assert_eq!(config.get("acme").await.unwrap(), None);
+    assert_eq!(reads.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn reads_the_settings_again_once_expired() {
+    let inner = CountingTenantConfig::default();
+    let reads = inner.reads.clone();
+    let config = CachedTenantConfig::new(inner, Duration::from_millis(20));
+
+    config.get("acme").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    config.get("acme").await.unwrap();
+
+    assert_eq!(reads.load(Ordering::SeqCst), 2);
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::infra::storage::adapter::postgres::tenant_config::PostgresTenantConfig;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stores_the_settings_of_tenants() {
+        let db = TestDb::new().await.unwrap();
+        let config = PostgresTenantConfig::new(db.db());
+        let settings = TenantSettings {
+            rate_limit_requests: Some(5),
+            feature_flags: BTreeMap::from([("bulk_import".to_string(), true), ("exports".to_string(), false)]),
+            webhook_secret: Some("whsec".to_string()),
+            branding: TenantBranding {
+                error_templates: BTreeMap::from([(404, "Acme: {message}".to_string())]),
+                support_url: Some("https://help.acme.test".to_string()),
+                ..TenantBranding::default()
+            },
+        };
+
+        assert_eq!(config.get("acme").await.unwrap(), None);
+        config.put("acme", settings.clone()).await.unwrap();
+        assert_eq!(config.get("acme").await.unwrap(), Some(settings));
+
+        config.put("acme", TenantSettings::default()).await.unwrap();
+        assert_eq!(config.get("acme").await.unwrap(), Some(TenantSettings::default()));
+        assert_eq!(config.get("globex").await.unwrap(), None);
+
+        assert!(config.delete("acme").await.unwrap());
+        assert!(!config.delete("acme").await.unwrap());
+        assert_eq!(config.get("acme").await.unwrap(), None);
+    }
+}
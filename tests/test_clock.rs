@@ -0,0 +1,142 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use chrono::{TimeDelta, Utc};
+use jsonwebtoken::decode_header;
+use serde_json::json;
+
+use rust_web_server_lib::application::flows::auth_service::AuthService;
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::{all_scopes, AuthError, DisabledAuthenticator, KeySetPort, TokenPort};
+use rust_web_server_lib::application::ports::clock::{ClockPort, ShiftedClock};
+use rust_web_server_lib::domain::user::model::UserId;
+use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+use rust_web_server_lib::infra::auth::signing_keys::SigningKeys;
+use rust_web_server_lib::infra::auth::{JwtConfig, SigningKeysConfig};
+use rust_web_server_lib::infra::storage::adapter::in_memory::audit_log::InMemoryAuditLog;
+use rust_web_server_lib::infra::storage::adapter::in_memory::signing_keys::InMemorySigningKeyStore;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::audit_handlers::AuditState;
+use rust_web_server_lib::presentation::handlers::clock_handlers::ClockState;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::auth::AuthState;
+
+use common::{send, ADMIN_TOKEN};
+
+/// Lifetime of the tokens issued by [`jwt_tokens`].
+const TOKEN_LIFETIME_SECS: u64 = 3600;
+
+fn jwt_tokens(clock: &Arc<ShiftedClock>) -> JwtTokens {
+    common::jwt_tokens().with_clock(clock.clone())
+}
+
+/// Returns an app with the admin routes, authenticating tokens with the time of `clock`, and
+/// shifting it through the admin routes unless `None`.
+fn app(tokens: JwtTokens, clock: Option<Arc<ShiftedClock>>) -> axum::Router {
+    router(AppState {
+        admin_token: Some(ADMIN_TOKEN.into()),
+        auth: AuthState { auth_service: Arc::new(AuthService::new(Arc::new(DisabledAuthenticator), Arc::new(tokens))) },
+        audit: Some(AuditState { audit_log: Arc::new(InMemoryAuditLog::new()) }),
+        clock: clock.map(|clock| ClockState { clock }),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+#[test]
+fn shifts_the_time_of_the_system_until_reset() {
+    let clock = ShiftedClock::new();
+    assert!((clock.now() - Utc::now()).abs() < TimeDelta::seconds(1));
+
+    assert_eq!(clock.shift(TimeDelta::hours(2)), TimeDelta::hours(2));
+    assert_eq!(clock.shift(TimeDelta::minutes(-30)), TimeDelta::minutes(90));
+    assert!((clock.now() - Utc::now() - TimeDelta::minutes(90)).abs() < TimeDelta::seconds(1));
+
+    clock.reset();
+    assert_eq!(clock.offset(), TimeDelta::zero());
+}
+
+#[test]
+fn expires_tokens_with_the_time_of_the_clock() {
+    let clock = Arc::new(ShiftedClock::new());
+    let tokens = jwt_tokens(&clock);
+    let token = tokens.issue("jdoe", &[], &[]).unwrap().token;
+
+    clock.shift(TimeDelta::seconds(TOKEN_LIFETIME_SECS as i64 + 1));
+    assert!(matches!(tokens.verify(&token), Err(AuthError::InvalidToken)));
+
+    clock.reset();
+    assert_eq!(tokens.verify(&token).unwrap().user_id, "jdoe");
+}
+
+#[tokio::test]
+async fn rotates_signing_keys_with_the_time_of_the_clock() {
+    let clock = Arc::new(ShiftedClock::new());
+    let config = SigningKeysConfig { rotation_interval_secs: 86_400, publication_delay_secs: 3600, refresh_interval_secs: 60 };
+    let keys = SigningKeys::new(Arc::new(InMemorySigningKeyStore::new()), "test-clock-secret", &config, Duration::from_secs(TOKEN_LIFETIME_SECS))
+        .unwrap()
+        .with_clock(clock.clone());
+    let keys = Arc::new(keys);
+    let jwt = JwtConfig { secret: "test-clock-secret".to_string().into(), expiry_secs: TOKEN_LIFETIME_SECS };
+    let tokens = JwtTokens::with_signing_keys(&jwt, keys.clone()).with_clock(clock.clone());
+    let kid = || decode_header(&tokens.issue("jdoe", &[], &[]).unwrap().token).unwrap().kid.unwrap();
+    keys.refresh().await.unwrap();
+    let first = kid();
+
+    keys.refresh().await.unwrap();
+    assert_eq!(kid(), first);
+    // Due for rotation, then past the publication delay of the new key
+    clock.shift(TimeDelta::days(1));
+    keys.refresh().await.unwrap();
+    assert_eq!(keys.public_keys().len(), 2);
+    clock.shift(TimeDelta::hours(2));
+    keys.refresh().await.unwrap();
+    assert_ne!(kid(), first);
+}
+
+#[tokio::test]
+async fn shifts_the_clock_through_the_admin_routes() {
+    let clock = Arc::new(ShiftedClock::new());
+    let app = app(jwt_tokens(&clock), Some(clock.clone()));
+
+    let (status, body) = send(&app, Method::POST, "/api/v1/admin/clock/shift", Some(ADMIN_TOKEN), Some(json!({ "secs": 7200 }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["offset_secs"], 7200);
+    let (status, body) = send(&app, Method::POST, "/api/v1/admin/clock/shift", Some(ADMIN_TOKEN), Some(json!({ "secs": -3600 }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["offset_secs"], 3600);
+    assert_eq!(clock.offset(), TimeDelta::hours(1));
+    let (_, body) = send(&app, Method::GET, "/api/v1/admin/clock", Some(ADMIN_TOKEN), None).await;
+    assert_eq!(body["data"]["offset_secs"], 3600);
+
+    assert_eq!(send(&app, Method::DELETE, "/api/v1/admin/clock", Some(ADMIN_TOKEN), None).await.0, StatusCode::NO_CONTENT);
+    assert_eq!(clock.offset(), TimeDelta::zero());
+
+    let (status, _) = send(&app, Method::POST, "/api/v1/admin/clock/shift", Some(ADMIN_TOKEN), Some(json!({ "secs": i64::MAX }))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(send(&app, Method::GET, "/api/v1/admin/clock", Some("wrong"), None).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn expires_the_tokens_of_requests_once_the_clock_is_shifted() {
+    let clock = Arc::new(ShiftedClock::new());
+    let app = app(jwt_tokens(&clock), Some(clock.clone()));
+    let token = jwt_tokens(&clock).issue("jdoe", &[], &all_scopes()).unwrap().token;
+    let audit = format!("/api/v1/users/{}/audit", UserId::generate());
+
+    assert_eq!(send(&app, Method::GET, &audit, Some(&token), None).await.0, StatusCode::OK);
+    let shift = json!({ "secs": TOKEN_LIFETIME_SECS + 1 });
+    assert_eq!(send(&app, Method::POST, "/api/v1/admin/clock/shift", Some(ADMIN_TOKEN), Some(shift)).await.0, StatusCode::OK);
+    assert_eq!(send(&app, Method::GET, &audit, Some(&token), None).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn does_not_mount_the_clock_routes_unless_enabled() {
+    let app = app(jwt_tokens(&Arc::new(ShiftedClock::new())), None);
+
+    assert_eq!(send(&app, Method::GET, "/api/v1/admin/clock", Some(ADMIN_TOKEN), None).await.0, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, Method::POST, "/api/v1/admin/clock/shift", Some(ADMIN_TOKEN), Some(json!({ "secs": 60 }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,63 @@
+//! Tests of the test harness of the `testing` feature.
+#![cfg(feature = "testing")]
+
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::domain::user::error::UserDomainError;
+use rust_web_server_lib::domain::user::model::{CreateUser, User, UserId};
+use rust_web_server_lib::testing::{MockUserRepository, MockUserService, TestClient};
+
+fn jane(id: UserId) -> User {
+    User::new(id, "Jane".to_string(), "jane@example.com".parse().unwrap(), 30)
+}
+
+#[tokio::test]
+async fn answers_the_calls_of_services_with_the_mock_repository() {
+    let id = UserId::generate();
+    let repository = MockUserRepository::new()
+        .on_get_user(|id| Ok(jane(id)))
+        .on_create_user(|_user, _password_hash| Err(UserDomainError::UserAlreadyExists));
+    let service = UserService::new(repository);
+
+    assert_eq!(service.get_user(id).await.unwrap(), jane(id));
+    let user = CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap();
+    assert_eq!(service.create_user(user).await.unwrap_err(), UserDomainError::UserAlreadyExists);
+}
+
+#[tokio::test]
+async fn serves_the_mock_service_through_the_router() {
+    let id = UserId::generate();
+    let service = Arc::new(MockUserService::new().on_get_user(move |requested| if requested == id { Ok(jane(id)) } else { Err(UserDomainError::UserNotFound) }));
+    let client = TestClient::with_user_service(service.clone());
+
+    let response = client.get(&format!("/api/v1/users/{}", id)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["data"]["email"], "jane@example.com");
+    assert_eq!(client.get(&format!("/api/v1/users/{}", UserId::generate())).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(service.calls(), ["get_user", "get_user"]);
+}
+
+#[tokio::test]
+#[should_panic(expected = "unexpected call to MockUserService::delete_user")]
+async fn fails_on_unexpected_calls() {
+    let service = MockUserService::new();
+
+    let _ = service.delete_user(UserId::generate()).await;
+}
+
+#[tokio::test]
+async fn serves_in_memory_users_by_default() {
+    let client = TestClient::new();
+
+    let created = client.post("/api/v1/users", json!({"name": "Jane", "email": "jane@example.com", "age": 30})).await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    let id = created.json()["data"]["id"].as_str().unwrap().to_string();
+
+    let response = client.get(&format!("/api/v1/users/{}", id)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["data"]["name"], "Jane");
+}
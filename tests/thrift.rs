@@ -0,0 +1,307 @@
+/// The Thrift server of the user service, called by a client encoding calls with the binary
+/// protocol like clients generated from the IDL.
+#[cfg(feature = "thrift")]
+mod thrift {
+    use std::collections::BTreeMap;
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use serde_json::{json, Value};
+    use thrift::protocol::{TBinaryInputProtocol, TBinaryOutputProtocol, TFieldIdentifier, TInputProtocol, TMessageIdentifier, TMessageType, TOutputProtocol, TStructIdentifier, TType};
+    use thrift::{ApplicationError, ApplicationErrorKind};
+
+    use rust_web_server_lib::application::flows::auth_service::AuthService;
+    use rust_web_server_lib::application::flows::user_service::UserService;
+    use rust_web_server_lib::application::ports::auth::{DisabledAuthenticator, TokenPort, USERS_WRITE_SCOPE};
+    use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+    use rust_web_server_lib::infra::auth::JwtConfig;
+    use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+    use rust_web_server_lib::presentation::middleware::auth::AuthState;
+    use rust_web_server_lib::presentation::rpc::thrift::{ThriftServer, ThriftServerConfig, ThriftServerTask};
+    use rust_web_server_lib::presentation::rpc::UserRpc;
+
+    const MAX_FRAME_BYTES: usize = 4096;
+
+    fn jwt_tokens() -> JwtTokens {
+        JwtTokens::new(&JwtConfig { secret: "thrift-test-secret".to_string().into(), expiry_secs: 3600 })
+    }
+
+    fn token(scopes: &[&str]) -> String {
+        let scopes: Vec<String> = scopes.iter().map(|scope| scope.to_string()).collect();
+        jwt_tokens().issue("jdoe", &[], &scopes).unwrap().token
+    }
+
+    async fn start() -> (SocketAddr, ThriftServerTask) {
+        let user_service = Arc::new(UserService::new(InMemoryUserRepository::new()));
+        let auth = AuthState { auth_service: Arc::new(AuthService::new(Arc::new(DisabledAuthenticator), Arc::new(jwt_tokens()))) };
+        let config = ThriftServerConfig { port: 0, max_frame_bytes: MAX_FRAME_BYTES, shutdown_timeout: Duration::from_secs(1) };
+        let server = ThriftServer::new(UserRpc::new(user_service, auth), config).await.unwrap();
+        let addr = SocketAddr::from(([127, 0, 0, 1], server.local_addr().unwrap().port()));
+        (addr, server.spawn())
+    }
+
+    /// A field of the arguments of a call.
+    enum Arg {
+        String(i16, String),
+        /// A struct, with its fields.
+        Struct(i16, Vec<Arg>),
+        I16(i16, i16),
+        I32(i16, i32),
+    }
+
+    fn write_fields(output: &mut TBinaryOutputProtocol<&mut Vec<u8>>, fields: &[Arg]) {
+        output.write_struct_begin(&TStructIdentifier::new("args")).unwrap();
+        for field in fields {
+            match field {
+                Arg::String(id, value) => {
+                    output.write_field_begin(&TFieldIdentifier::new("field", TType::String, *id)).unwrap();
+                    output.write_string(value).unwrap();
+                }
+                Arg::Struct(id, fields) => {
+                    output.write_field_begin(&TFieldIdentifier::new("field", TType::Struct, *id)).unwrap();
+                    write_fields(output, fields);
+                }
+                Arg::I16(id, value) => {
+                    output.write_field_begin(&TFieldIdentifier::new("field", TType::I16, *id)).unwrap();
+                    output.write_i16(*value).unwrap();
+                }
+                Arg::I32(id, value) => {
+                    output.write_field_begin(&TFieldIdentifier::new("field", TType::I32, *id)).unwrap();
+                    output.write_i32(*value).unwrap();
+                }
+            }
+            output.write_field_end().unwrap();
+        }
+        output.write_field_stop().unwrap();
+        output.write_struct_end().unwrap();
+    }
+
+    /// Returns the frame of a call of `method`, with the context of `token` and the arguments.
+    fn call(method: &str, token: Option<&str>, args: Vec<Arg>) -> Vec<u8> {
+        let mut context = vec![Arg::String(2, "req-1".to_string())];
+        context.extend(token.map(|token| Arg::String(1, token.to_string())));
+        let mut fields = vec![Arg::Struct(1, context)];
+        fields.extend(args);
+
+        let mut frame = Vec::new();
+        let mut output = TBinaryOutputProtocol::new(&mut frame, true);
+        output.write_message_begin(&TMessageIdentifier::new(method, TMessageType::Call, 7)).unwrap();
+        write_fields(&mut output, &fields);
+        output.write_message_end().unwrap();
+        frame
+    }
+
+    fn id(id: &str) -> Arg {
+        Arg::String(2, id.to_string())
+    }
+
+    fn create_user_request(name: &str, email: &str, age: i16) -> Arg {
+        Arg::Struct(2, vec![Arg::String(1, name.to_string()), Arg::String(2, email.to_string()), Arg::I16(3, age)])
+    }
+
+    /// Sends the frames on a single connection, returning the reply frames, `None` once the
+    /// server closed the connection.
+    async fn exchange(addr: SocketAddr, frames: Vec<Vec<u8>>) -> Vec<Option<Vec<u8>>> {
+        tokio::task::spawn_blocking(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            frames
+                .into_iter()
+                .map(|frame| {
+                    stream.write_all(&(frame.len() as u32).to_be_bytes()).unwrap();
+                    stream.write_all(&frame).unwrap();
+                    let mut length = [0; 4];
+                    stream.read_exact(&mut length).ok()?;
+                    let mut reply = vec![0; u32::from_be_bytes(length) as usize];
+                    stream.read_exact(&mut reply).unwrap();
+                    Some(reply)
+                })
+                .collect()
+        })
+        .await
+        .unwrap()
+    }
+
+    /// A decoded reply.
+    #[derive(Debug, PartialEq)]
+    enum Reply {
+        /// The value returned, `null` for void methods, with struct fields keyed by id.
+        Success(Value),
+        /// The `UserError` thrown, with struct fields keyed by id.
+        Error(Value),
+        Exception(ApplicationErrorKind),
+    }
+
+    fn read_value(input: &mut TBinaryInputProtocol<&[u8]>, field_type: TType) -> Value {
+        match field_type {
+            TType::String => json!(input.read_string().unwrap()),
+            TType::I16 => json!(input.read_i16().unwrap()),
+            TType::I32 => json!(input.read_i32().unwrap()),
+            TType::I64 => json!(input.read_i64().unwrap()),
+            TType::Struct => {
+                let mut fields = BTreeMap::new();
+                input.read_struct_begin().unwrap();
+                loop {
+                    let field = input.read_field_begin().unwrap();
+                    if field.field_type == TType::Stop {
+                        break;
+                    }
+                    fields.insert(field.id.unwrap().to_string(), read_value(input, field.field_type));
+                    input.read_field_end().unwrap();
+                }
+                input.read_struct_end().unwrap();
+                json!(fields)
+            }
+            TType::List => {
+                let list = input.read_list_begin().unwrap();
+                let values = (0..list.size).map(|_| read_value(input, list.element_type)).collect();
+                input.read_list_end().unwrap();
+                Value::Array(values)
+            }
+            other => panic!("unexpected type {:?}", other),
+        }
+    }
+
+    fn decode(method: &str, reply: &[u8]) -> Reply {
+        let mut input = TBinaryInputProtocol::new(reply, true);
+        let message = input.read_message_begin().unwrap();
+        assert_eq!((message.name.as_str(), message.sequence_number), (method, 7));
+
+        if message.message_type == TMessageType::Exception {
+            let error: ApplicationError = thrift::Error::read_application_error_from_in_protocol(&mut input).unwrap();
+            return Reply::Exception(error.kind);
+        }
+        assert_eq!(message.message_type, TMessageType::Reply);
+        let result = read_value(&mut input, TType::Struct);
+        match (result.get("0"), result.get("1")) {
+            (Some(success), None) => Reply::Success(success.clone()),
+            (None, Some(error)) => Reply::Error(error.clone()),
+            (None, None) => Reply::Success(Value::Null),
+            _ => panic!("invalid result {}", result),
+        }
+    }
+
+    async fn invoke(addr: SocketAddr, method: &str, token: Option<&str>, args: Vec<Arg>) -> Reply {
+        let reply = exchange(addr, vec![call(method, token, args)]).await.remove(0).unwrap();
+        decode(method, &reply)
+    }
+
+    fn error_code(reply: &Reply) -> &str {
+        match reply {
+            Reply::Error(error) => error["1"].as_str().unwrap(),
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+
+    fn success(reply: Reply) -> Value {
+        match reply {
+            Reply::Success(value) => value,
+            other => panic!("expected a success, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn creates_and_gets_users() {
+        let (addr, _server) = start().await;
+
+        let user = success(invoke(addr, "createUser", None, vec![create_user_request("Jane", "jane@example.com", 30)]).await);
+        assert_eq!((&user["2"], &user["3"], &user["4"]), (&json!("Jane"), &json!("jane@example.com"), &json!(30)));
+
+        let id = user["1"].as_str().unwrap();
+        assert_eq!(success(invoke(addr, "getUser", None, vec![self::id(id)]).await), user);
+
+        let reply = invoke(addr, "getUser", None, vec![self::id("6f1c2b4e-8f7a-4c1d-9a3e-2b5d7c9e1f0a")]).await;
+        assert_eq!(error_code(&reply), "not_found");
+    }
+
+    #[tokio::test]
+    async fn validates_requests_like_the_http_api() {
+        let (addr, _server) = start().await;
+
+        let reply = invoke(addr, "createUser", None, vec![create_user_request("Jane", "not an email", 300)]).await;
+        assert_eq!(error_code(&reply), "invalid_request");
+        let Reply::Error(error) = reply else { unreachable!() };
+        let fields: Vec<&str> = error["3"].as_array().unwrap().iter().map(|error| error["1"].as_str().unwrap()).collect();
+        assert_eq!(fields, ["email", "age"]);
+    }
+
+    #[tokio::test]
+    async fn lists_and_searches_users() {
+        let (addr, _server) = start().await;
+        for (name, email, age) in [("Ann", "ann@example.com", 25), ("Bob", "bob@example.org", 40), ("Carl", "carl@example.com", 33)] {
+            success(invoke(addr, "createUser", None, vec![create_user_request(name, email, age)]).await);
+        }
+        let names = |page: &Value| page["1"].as_array().unwrap().iter().map(|user| user["2"].as_str().unwrap().to_string()).collect::<Vec<_>>();
+
+        // Sorted by age, in descending order
+        let request = Arg::Struct(2, vec![Arg::I32(1, 2), Arg::I32(3, 3), Arg::I32(4, 2)]);
+        let page = success(invoke(addr, "listUsers", None, vec![request]).await);
+        assert_eq!((names(&page), &page["2"]), (vec!["Bob".to_string(), "Carl".to_string()], &json!(3)));
+
+        let reply = invoke(addr, "listUsers", None, vec![Arg::Struct(2, vec![Arg::I32(1, 0)])]).await;
+        assert_eq!(error_code(&reply), "unprocessable_entity");
+
+        let request = Arg::Struct(2, vec![Arg::String(2, "example.com".to_string()), Arg::I16(3, 30)]);
+        let page = success(invoke(addr, "searchUsers", None, vec![request]).await);
+        assert_eq!((names(&page), &page["2"]), (vec!["Carl".to_string()], &json!(1)));
+    }
+
+    #[tokio::test]
+    async fn requires_the_users_write_scope_for_changes() {
+        let (addr, _server) = start().await;
+        let user = success(invoke(addr, "createUser", None, vec![create_user_request("Jane", "jane@example.com", 30)]).await);
+        let user_id = user["1"].as_str().unwrap();
+        let update = || vec![id(user_id), Arg::Struct(3, vec![Arg::String(1, "Janet".to_string())])];
+
+        assert_eq!(error_code(&invoke(addr, "updateUser", None, update()).await), "unauthorized");
+        assert_eq!(error_code(&invoke(addr, "updateUser", Some("invalid"), update()).await), "unauthorized");
+        assert_eq!(error_code(&invoke(addr, "updateUser", Some(&token(&[])), update()).await), "forbidden");
+
+        let token = token(&[USERS_WRITE_SCOPE]);
+        let updated = success(invoke(addr, "updateUser", Some(&token), update()).await);
+        assert_eq!(updated["2"], "Janet");
+
+        assert_eq!(success(invoke(addr, "deleteUser", Some(&token), vec![id(user_id)]).await), Value::Null);
+        assert_eq!(error_code(&invoke(addr, "getUser", None, vec![id(user_id)]).await), "not_found");
+        assert_eq!(success(invoke(addr, "restoreUser", Some(&token), vec![id(user_id)]).await), updated);
+    }
+
+    #[tokio::test]
+    async fn refuses_deletions_while_impersonating() {
+        let (addr, _server) = start().await;
+        let user = success(invoke(addr, "createUser", None, vec![create_user_request("Jane", "jane@example.com", 30)]).await);
+
+        let token = jwt_tokens().issue_impersonation("admin-1", "jdoe", Duration::from_secs(60)).unwrap().token;
+        let reply = invoke(addr, "deleteUser", Some(&token), vec![id(user["1"].as_str().unwrap())]).await;
+        assert_eq!(error_code(&reply), "forbidden");
+    }
+
+    #[tokio::test]
+    async fn answers_invalid_calls_with_exceptions() {
+        let (addr, _server) = start().await;
+
+        let replies = exchange(addr, vec![call("renameUser", None, vec![]), call("getUser", None, vec![]), call("createUser", None, vec![create_user_request("Jane", "jane@example.com", 30)])]).await;
+        assert_eq!(decode("renameUser", replies[0].as_ref().unwrap()), Reply::Exception(ApplicationErrorKind::UnknownMethod));
+        assert_eq!(decode("getUser", replies[1].as_ref().unwrap()), Reply::Exception(ApplicationErrorKind::ProtocolError));
+        // The connection is still usable
+        assert!(matches!(decode("createUser", replies[2].as_ref().unwrap()), Reply::Success(_)));
+    }
+
+    #[tokio::test]
+    async fn closes_connections_sending_larger_frames() {
+        let (addr, _server) = start().await;
+
+        let replies = exchange(addr, vec![call("createUser", None, vec![create_user_request(&"a".repeat(MAX_FRAME_BYTES), "jane@example.com", 30)])]).await;
+        assert_eq!(replies, [None]);
+    }
+
+    #[tokio::test]
+    async fn stops_accepting_connections_on_shutdown() {
+        let (addr, server) = start().await;
+        success(invoke(addr, "createUser", None, vec![create_user_request("Jane", "jane@example.com", 30)]).await);
+
+        server.shutdown().await;
+        assert!(TcpStream::connect(addr).is_err());
+    }
+}
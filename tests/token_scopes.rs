@@ -0,0 +1,221 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::auth_service::AuthService;
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::ports::auth::{all_scopes, AuthError, Authentication, AuthenticatorPort, TokenPort, USERS_WRITE_SCOPE};
+use rust_web_server_lib::domain::user::model::CreateUser;
+use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+use rust_web_server_lib::infra::auth::JwtConfig;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::auth::AuthState;
+
+const INTROSPECTION_TOKEN: &str = "introspection-secret";
+
+/// Authenticator accepting `jdoe` with the password `secret`, granting it the `admin` role.
+struct StaticAuthenticator;
+
+#[async_trait]
+impl AuthenticatorPort for StaticAuthenticator {
+    async fn authenticate(&self, username: String, password: String) -> Result<Authentication, AuthError> {
+        match (username.as_str(), password.as_str()) {
+            ("jdoe", "secret") => Ok(Authentication {
+                user_id: "jdoe".to_string(),
+                roles: vec!["admin".to_string()],
+            }),
+            _ => Err(AuthError::InvalidCredentials),
+        }
+    }
+}
+
+fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "scopes-test-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+struct TestApp {
+    router: axum::Router,
+    user_id: String,
+}
+
+async fn app() -> TestApp {
+    let user_service = Arc::new(UserService::new(InMemoryUserRepository::new()));
+    let user = CreateUser::new("John Doe".to_string(), "jdoe@example.com".to_string(), 42).unwrap();
+    let user_id = user_service.create_user(user).await.unwrap().id().to_string();
+
+    let router = router(AppState {
+        auth: AuthState {
+            auth_service: Arc::new(AuthService::new(Arc::new(StaticAuthenticator), Arc::new(jwt_tokens()))),
+        },
+        introspection_token: Some(INTROSPECTION_TOKEN.into()),
+        ..AppState::new(user_service)
+    });
+    TestApp { router, user_id }
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+async fn login(app: &axum::Router, body: Value) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/auth/login")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    send(app, request).await
+}
+
+async fn update_age(app: &TestApp, token: &str) -> StatusCode {
+    let request = Request::builder()
+        .method(Method::PUT)
+        .uri(format!("/api/users/{}", app.user_id))
+        .header(header::AUTHORIZATION, format!("Bearer {}", token))
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::IF_MATCH, "*")
+        .body(Body::from(json!({ "age": 43 }).to_string()))
+        .unwrap();
+    send(&app.router, request).await.0
+}
+
+async fn introspect(app: &axum::Router, bearer: Option<&str>, form: &str) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/auth/introspect")
+        .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+    if let Some(bearer) = bearer {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", bearer));
+    }
+    send(app, request.body(Body::from(form.to_string())).unwrap()).await
+}
+
+#[test]
+fn tokens_carry_scopes_and_lifetime() {
+    let tokens = jwt_tokens();
+    let before = SystemTime::now() - Duration::from_secs(1);
+    let token = tokens.issue("jdoe", &[], &[USERS_WRITE_SCOPE.to_string()]).unwrap().token;
+
+    let principal = tokens.verify(&token).unwrap();
+
+    assert_eq!(principal.scopes, vec![USERS_WRITE_SCOPE.to_string()]);
+    assert!(principal.issued_at >= before);
+    assert_eq!(principal.expires_at.duration_since(principal.issued_at).unwrap(), Duration::from_secs(3600));
+}
+
+#[tokio::test]
+async fn grants_every_scope_by_default() {
+    let app = app().await;
+
+    let (status, body) = login(&app.router, json!({ "email": "jdoe", "password": "secret" })).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["scope"], all_scopes().join(" "));
+    assert_eq!(update_age(&app, body["data"]["access_token"].as_str().unwrap()).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn limits_tokens_to_requested_scopes() {
+    let app = app().await;
+
+    let (_, body) = login(&app.router, json!({ "email": "jdoe", "password": "secret", "scope": "passkeys devices" })).await;
+
+    assert_eq!(body["data"]["scope"], "passkeys devices");
+    assert_eq!(update_age(&app, body["data"]["access_token"].as_str().unwrap()).await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn rejects_unknown_scopes() {
+    let app = app().await;
+
+    let (status, body) = login(&app.router, json!({ "email": "jdoe", "password": "secret", "scope": "users:write admin" })).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.to_string().contains("unknown scope admin"), "{}", body);
+}
+
+#[tokio::test]
+async fn rejects_tokens_without_scopes() {
+    let app = app().await;
+    let token = jwt_tokens().issue("jdoe", &[], &[]).unwrap().token;
+
+    assert_eq!(update_age(&app, &token).await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn introspects_active_tokens() {
+    let app = app().await;
+    let token = jwt_tokens().issue("jdoe", &["admin".to_string()], &[USERS_WRITE_SCOPE.to_string()]).unwrap().token;
+
+    let (status, body) = introspect(&app.router, Some(INTROSPECTION_TOKEN), &format!("token={}&token_type_hint=access_token", token)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["active"], true);
+    assert_eq!(body["sub"], "jdoe");
+    assert_eq!(body["scope"], USERS_WRITE_SCOPE);
+    assert_eq!(body["token_type"], "Bearer");
+    assert_eq!(body["roles"], json!(["admin"]));
+    assert_eq!(body["exp"].as_u64().unwrap() - body["iat"].as_u64().unwrap(), 3600);
+    assert!(body.get("act").is_none());
+}
+
+#[tokio::test]
+async fn introspects_impersonation_tokens() {
+    let app = app().await;
+    let token = jwt_tokens().issue_impersonation("admin-1", "jdoe", Duration::from_secs(60)).unwrap().token;
+
+    let (_, body) = introspect(&app.router, Some(INTROSPECTION_TOKEN), &format!("token={}", token)).await;
+
+    assert_eq!(body["active"], true);
+    assert_eq!(body["act"], json!({ "sub": "admin-1" }));
+}
+
+#[tokio::test]
+async fn reports_invalid_tokens_as_inactive() {
+    let app = app().await;
+    let forged = JwtTokens::new(&JwtConfig { secret: "other-secret".to_string().into(), expiry_secs: 3600 })
+        .issue("jdoe", &[], &all_scopes())
+        .unwrap()
+        .token;
+
+    let (status, body) = introspect(&app.router, Some(INTROSPECTION_TOKEN), &format!("token={}", forged)).await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "active": false }));
+}
+
+#[tokio::test]
+async fn requires_token_to_introspect() {
+    let app = app().await;
+
+    let (status, body) = introspect(&app.router, Some(INTROSPECTION_TOKEN), "token_type_hint=access_token").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"], "invalid_request");
+}
+
+#[tokio::test]
+async fn requires_introspection_token() {
+    let app = app().await;
+    let token = jwt_tokens().issue("jdoe", &[], &all_scopes()).unwrap().token;
+
+    assert_eq!(introspect(&app.router, None, &format!("token={}", token)).await.0, StatusCode::UNAUTHORIZED);
+    // Access tokens do not grant access to the introspection of other tokens
+    assert_eq!(introspect(&app.router, Some(&token), &format!("token={}", token)).await.0, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn introspection_is_not_mounted_by_default() {
+    let app = router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))));
+
+    assert_eq!(introspect(&app, Some(INTROSPECTION_TOKEN), "token=abc").await.0, StatusCode::NOT_FOUND);
+}
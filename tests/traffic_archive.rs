@@ -0,0 +1,209 @@
+use std::sync::{Arc, Mutex};
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::traffic_archive::{ArchivedExchange, TrafficArchivePort};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::traffic_archive::{TrafficArchivePolicy, TrafficArchiver};
+
+#[derive(Default)]
+struct RecordingArchive(Mutex<Vec<ArchivedExchange>>);
+
+impl TrafficArchivePort for RecordingArchive {
+    fn archive(&self, exchange: ArchivedExchange) {
+        self.0.lock().unwrap().push(exchange);
+    }
+}
+
+impl RecordingArchive {
+    fn exchanges(&self) -> Vec<ArchivedExchange> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+fn policy(sample_rate: f64) -> TrafficArchivePolicy {
+    TrafficArchivePolicy { sample_rate, max_body_bytes: 1024 }
+}
+
+fn archived_app(policy: TrafficArchivePolicy) -> (axum::Router, Arc<RecordingArchive>) {
+    let archive = Arc::new(RecordingArchive::default());
+    let app = router(AppState {
+        traffic_archive: Some(TrafficArchiver::new(policy, archive.clone()).unwrap()),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    });
+    (app, archive)
+}
+
+fn json_request(uri: &str, body: Value) -> Request<Body> {
+    Request::post(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::AUTHORIZATION, "Bearer secret-token")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn body_of(body: &Option<Vec<u8>>) -> Value {
+    serde_json::from_slice(body.as_ref().expect("the body is archived")).unwrap()
+}
+
+fn header_of<'a>(headers: &'a [(String, String)], name: &str) -> &'a str {
+    &headers.iter().find(|(header, _)| header == name).unwrap().1
+}
+
+#[tokio::test]
+async fn archives_sanitized_exchanges() {
+    let (app, archive) = archived_app(policy(1.0));
+
+    let request = json_request("/api/users", json!({ "name": "Jane Doe", "email": "jane@example.com", "age": 30 }));
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    // The client gets the response untouched
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let created: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(created["data"]["email"], "jane@example.com");
+
+    let exchanges = archive.exchanges();
+    assert_eq!(exchanges.len(), 1);
+    let exchange = &exchanges[0];
+    assert_eq!(exchange.method, "POST");
+    assert_eq!(exchange.route.as_deref(), Some("/api/users"));
+    assert_eq!(exchange.status, 201);
+    assert!(exchange.request_id.is_some());
+    assert_eq!(header_of(&exchange.request_headers, "authorization"), "[redacted]");
+
+    let request_body = body_of(&exchange.request_body);
+    let response_body = body_of(&exchange.response_body);
+    let pseudonym = request_body["email"].as_str().unwrap();
+    assert!(pseudonym.starts_with("user-") && pseudonym.ends_with("@example.invalid"), "{}", pseudonym);
+    assert_eq!(response_body["data"]["email"], pseudonym);
+    assert_eq!(request_body["name"], "Jane Doe");
+}
+
+#[tokio::test]
+async fn redacts_secret_fields_and_query_parameters() {
+    let (app, archive) = archived_app(policy(1.0));
+
+    app.clone().oneshot(json_request("/api/auth/login", json!({ "email": "jane@example.com", "password": "hunter2hunter2" }))).await.unwrap();
+    let request = Request::get("/api/users?access_token=abc&owner=jane%40example.com&limit=5").body(Body::empty()).unwrap();
+    app.oneshot(request).await.unwrap();
+
+    let exchanges = archive.exchanges();
+    assert_eq!(body_of(&exchanges[0].request_body)["password"], "[redacted]");
+    let query = exchanges[1].query.as_deref().unwrap();
+    assert!(query.starts_with("access_token=[redacted]&owner=user-"), "{}", query);
+    assert!(query.ends_with("@example.invalid&limit=5"), "{}", query);
+}
+
+#[tokio::test]
+async fn leaves_out_large_and_non_json_bodies() {
+    let (app, archive) = archived_app(policy(1.0));
+
+    let name = "a".repeat(2000);
+    let response = app.clone().oneshot(json_request("/api/users", json!({ "name": name, "email": "jane@example.com", "age": 30 }))).await.unwrap();
+    // The request still reaches the handler, which rejects the name
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let request = Request::post("/api/users").header(header::CONTENT_TYPE, "text/plain").body(Body::from("jane@example.com")).unwrap();
+    app.oneshot(request).await.unwrap();
+
+    let exchanges = archive.exchanges();
+    assert_eq!(exchanges.len(), 2);
+    assert_eq!(exchanges[0].request_body, None);
+    assert!(exchanges[0].response_body.is_some());
+    assert_eq!(exchanges[1].request_body, None);
+}
+
+#[tokio::test]
+async fn archives_only_sampled_api_requests() {
+    let (app, archive) = archived_app(policy(0.0));
+    app.oneshot(Request::get("/api/users").body(Body::empty()).unwrap()).await.unwrap();
+    assert!(archive.exchanges().is_empty());
+
+    let (app, archive) = archived_app(policy(1.0));
+    app.oneshot(Request::get("/healthz").body(Body::empty()).unwrap()).await.unwrap();
+    assert!(archive.exchanges().is_empty());
+}
+
+#[test]
+fn rejects_invalid_sample_rates() {
+    let archive = Arc::new(RecordingArchive::default());
+
+    assert!(TrafficArchiver::new(policy(1.5), archive.clone()).is_err());
+    assert!(TrafficArchiver::new(policy(-0.1), archive).is_err());
+}
+
+#[cfg(feature = "archive")]
+mod parquet_files {
+    use std::time::SystemTime;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::UInt16Type;
+    use object_store::memory::InMemory;
+    use object_store::path::Path;
+    use object_store::ObjectStore;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use rust_web_server_lib::infra::traffic_archive::parquet::TrafficArchiveWriter;
+    use rust_web_server_lib::infra::traffic_archive::TrafficArchiveConfig;
+
+    use super::*;
+
+    fn exchange(status: u16) -> ArchivedExchange {
+        ArchivedExchange {
+            received_at: SystemTime::now(),
+            request_id: Some("req-42".to_string()),
+            method: "GET".to_string(),
+            route: Some("/api/users/{id}".to_string()),
+            path: "/api/users/1".to_string(),
+            query: None,
+            request_headers: vec![("accept".to_string(), "application/json".to_string())],
+            request_body: None,
+            status,
+            response_headers: Vec::new(),
+            response_body: Some(b"{}".to_vec()),
+            latency_ms: 3,
+        }
+    }
+
+    #[tokio::test]
+    async fn writes_batches_as_parquet_files() {
+        let store = Arc::new(InMemory::new());
+        let config = TrafficArchiveConfig {
+            url: "memory://".to_string(),
+            sample_rate: 1.0,
+            max_body_bytes: 1024,
+            batch_size: 2,
+            flush_interval_secs: 3600,
+        };
+        let writer = TrafficArchiveWriter::with_store(store.clone(), Path::from("traffic"), &config);
+
+        let archive = writer.archive();
+        for status in [200, 404, 500] {
+            archive.archive(exchange(status));
+        }
+        // The partial batch is written on shutdown
+        writer.shutdown().await;
+
+        let days = store.list_with_delimiter(Some(&Path::from("traffic"))).await.unwrap().common_prefixes;
+        assert_eq!(days.len(), 1);
+        assert!(days[0].as_ref().starts_with("traffic/date="), "{}", days[0]);
+        let files = store.list_with_delimiter(Some(&days[0])).await.unwrap().objects;
+        assert_eq!(files.len(), 2);
+
+        let mut statuses = Vec::new();
+        for file in files {
+            let bytes = store.get(&file.location).await.unwrap().bytes().await.unwrap();
+            for batch in ParquetRecordBatchReaderBuilder::try_new(bytes).unwrap().build().unwrap() {
+                let batch = batch.unwrap();
+                statuses.extend(batch.column_by_name("status").unwrap().as_primitive::<UInt16Type>().values().iter().copied());
+                assert_eq!(batch.column_by_name("route").unwrap().as_string::<i32>().value(0), "/api/users/{id}");
+            }
+        }
+        statuses.sort();
+        assert_eq!(statuses, [200, 404, 500]);
+    }
+}
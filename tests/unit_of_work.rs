@@ -0,0 +1,332 @@
+//! Tests of the changes made by `UserService` in transactions of a unit of work.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::ports::audit::{AuditAction, AuditEntry, AuditLogPort};
+use rust_web_server_lib::application::ports::events::EventPublisherPort;
+use rust_web_server_lib::application::ports::unit_of_work::{IsolationLevel, TransactionPort, UnitOfWorkPort};
+use rust_web_server_lib::domain::user::error::{StorageError, UserDomainError};
+use rust_web_server_lib::domain::user::event::UserEvent;
+use rust_web_server_lib::domain::user::model::{CreateUser, Role, UpdateUser, UserId};
+use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+use rust_web_server_lib::infra::storage::adapter::in_memory::audit_log::InMemoryAuditLog;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+
+/// Unit of work changing users directly, and recording events and audit entries only once
+/// committed and the isolation level of each transaction begun.
+#[derive(Default)]
+struct RecordingUnitOfWork {
+    users: Arc<InMemoryUserRepository>,
+    isolation_levels: Mutex<Vec<IsolationLevel>>,
+    committed: Arc<Mutex<Vec<UserEvent>>>,
+    audited: Arc<Mutex<Vec<AuditEntry>>>,
+    commits: Arc<AtomicUsize>,
+    failing_begin: AtomicBool,
+    failing_events: Arc<AtomicBool>,
+    failing_audit: Arc<AtomicBool>,
+}
+
+impl RecordingUnitOfWork {
+    fn committed(&self) -> Vec<&'static str> {
+        self.committed.lock().unwrap().iter().map(UserEvent::event_type).collect()
+    }
+
+    fn audited(&self) -> Vec<AuditAction> {
+        self.audited.lock().unwrap().iter().map(|entry| entry.action).collect()
+    }
+}
+
+#[async_trait]
+impl UnitOfWorkPort for RecordingUnitOfWork {
+    async fn begin(&self, isolation: IsolationLevel) -> eyre::Result<Box<dyn TransactionPort + Send + Sync>> {
+        if self.failing_begin.load(Ordering::SeqCst) {
+            eyre::bail!("database unavailable");
+        }
+        self.isolation_levels.lock().unwrap().push(isolation);
+        Ok(Box::new(RecordingTransaction {
+            users: self.users.clone(),
+            events: PendingEvents { events: Mutex::default(), failing: self.failing_events.clone() },
+            audit_log: PendingEntries { entries: Mutex::default(), failing: self.failing_audit.clone() },
+            committed: self.committed.clone(),
+            audited: self.audited.clone(),
+            commits: self.commits.clone(),
+        }))
+    }
+}
+
+struct RecordingTransaction {
+    users: Arc<InMemoryUserRepository>,
+    events: PendingEvents,
+    audit_log: PendingEntries,
+    committed: Arc<Mutex<Vec<UserEvent>>>,
+    audited: Arc<Mutex<Vec<AuditEntry>>>,
+    commits: Arc<AtomicUsize>,
+}
+
+struct PendingEvents {
+    events: Mutex<Vec<UserEvent>>,
+    failing: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl EventPublisherPort for PendingEvents {
+    async fn publish(&self, event: UserEvent) -> eyre::Result<()> {
+        if self.failing.load(Ordering::SeqCst) {
+            eyre::bail!("outbox unavailable");
+        }
+        self.events.lock().unwrap().push(event);
+        Ok(())
+    }
+}
+
+struct PendingEntries {
+    entries: Mutex<Vec<AuditEntry>>,
+    failing: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl AuditLogPort for PendingEntries {
+    async fn record(&self, entry: AuditEntry) -> eyre::Result<()> {
+        if self.failing.load(Ordering::SeqCst) {
+            eyre::bail!("audit log unavailable");
+        }
+        self.entries.lock().unwrap().push(entry);
+        Ok(())
+    }
+
+    async fn history(&self, user_id: UserId) -> eyre::Result<Vec<AuditEntry>> {
+        Ok(self.entries.lock().unwrap().iter().filter(|entry| entry.user_id == user_id).cloned().collect())
+    }
+}
+
+#[async_trait]
+impl TransactionPort for RecordingTransaction {
+    fn users(&self) -> &(dyn UserRepositoryPort + Send + Sync) {
+        self.users.as_ref()
+    }
+
+    fn events(&self) -> &(dyn EventPublisherPort + Send + Sync) {
+        &self.events
+    }
+
+    fn audit_log(&self) -> &(dyn AuditLogPort + Send + Sync) {
+        &self.audit_log
+    }
+
+    async fn commit(self: Box<Self>) -> eyre::Result<()> {
+        self.committed.lock().unwrap().append(&mut self.events.events.lock().unwrap());
+        self.audited.lock().unwrap().append(&mut self.audit_log.entries.lock().unwrap());
+        self.commits.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+fn user_service(unit_of_work: &Arc<RecordingUnitOfWork>) -> UserService<Arc<InMemoryUserRepository>> {
+    UserService::new(unit_of_work.users.clone()).with_unit_of_work(unit_of_work.clone())
+}
+
+fn jdoe() -> CreateUser {
+    CreateUser::new("John Doe".to_string(), "jdoe@example.com".to_string(), 42).unwrap()
+}
+
+#[tokio::test]
+async fn records_events_in_the_transaction_of_each_change() {
+    let unit_of_work = Arc::new(RecordingUnitOfWork::default());
+    let service = user_service(&unit_of_work);
+
+    let user = service.create_user(jdoe()).await.unwrap();
+    service.update_user(UpdateUser::new(user.id(), None, None, Some(43)).unwrap()).await.unwrap();
+    service.delete_user(user.id()).await.unwrap();
+
+    assert_eq!(unit_of_work.committed(), vec!["user.created", "user.updated", "user.deleted"]);
+    assert_eq!(unit_of_work.commits.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn does_not_commit_changes_whose_event_cannot_be_recorded() {
+    let unit_of_work = Arc::new(RecordingUnitOfWork::default());
+    let service = user_service(&unit_of_work);
+    unit_of_work.failing_events.store(true, Ordering::SeqCst);
+
+    assert_eq!(service.create_user(jdoe()).await, Err(UserDomainError::UserCreationFailed(StorageError::msg("storage failed"))));
+    assert_eq!(unit_of_work.commits.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn does_not_commit_failed_changes() {
+    let unit_of_work = Arc::new(RecordingUnitOfWork::default());
+    let service = user_service(&unit_of_work);
+    let user = service.create_user(jdoe()).await.unwrap();
+    service.set_legal_hold(user.id(), true).await.unwrap();
+
+    assert_eq!(service.delete_user(user.id()).await, Err(UserDomainError::UserUnderLegalHold));
+    assert_eq!(unit_of_work.committed(), vec!["user.created"]);
+}
+
+#[tokio::test]
+async fn records_audit_entries_in_the_transaction_of_each_change() {
+    let unit_of_work = Arc::new(RecordingUnitOfWork::default());
+    let audit_log = Arc::new(InMemoryAuditLog::new());
+    let service = user_service(&unit_of_work).with_audit_log(audit_log.clone());
+
+    let user = service.create_user(jdoe()).await.unwrap();
+    service.update_user(UpdateUser::new(user.id(), None, None, Some(43)).unwrap()).await.unwrap();
+    service.set_role(user.id(), Role::Admin).await.unwrap();
+    service.set_legal_hold(user.id(), true).await.unwrap();
+
+    use AuditAction::{Created, Updated};
+    assert_eq!(unit_of_work.audited(), [Created, Updated, Updated, Updated]);
+    let changes: Vec<_> = unit_of_work.audited.lock().unwrap().iter().skip(1).map(|entry| entry.changes[0].field.clone()).collect();
+    assert_eq!(changes, ["age", "role", "legal_hold"]);
+    // Recorded through the transactions rather than the audit log of the service
+    assert!(audit_log.history(user.id()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn does_not_commit_changes_whose_audit_entry_cannot_be_recorded() {
+    let unit_of_work = Arc::new(RecordingUnitOfWork::default());
+    let service = user_service(&unit_of_work).with_audit_log(Arc::new(InMemoryAuditLog::new()));
+    let user = service.create_user(jdoe()).await.unwrap();
+    unit_of_work.failing_audit.store(true, Ordering::SeqCst);
+
+    let update = UpdateUser::new(user.id(), None, None, Some(43)).unwrap();
+    assert_eq!(service.update_user(update).await, Err(UserDomainError::UserUpdateFailed(StorageError::msg("storage failed"))));
+    assert_eq!(service.set_role(user.id(), Role::Admin).await, Err(UserDomainError::UserUpdateFailed(StorageError::msg("storage failed"))));
+    assert_eq!(unit_of_work.commits.load(Ordering::SeqCst), 1);
+    assert_eq!((unit_of_work.committed(), unit_of_work.audited()), (vec!["user.created"], vec![AuditAction::Created]));
+}
+
+#[tokio::test]
+async fn serializes_bulk_creations_and_checked_deletions() {
+    let unit_of_work = Arc::new(RecordingUnitOfWork::default());
+    let service = user_service(&unit_of_work);
+
+    let user = service.create_user(jdoe()).await.unwrap();
+    service.update_user(UpdateUser::new(user.id(), None, None, Some(43)).unwrap()).await.unwrap();
+    service.create_users_bulk(vec![CreateUser::new("Ada".to_string(), "ada@example.com".to_string(), 36).unwrap()]).await;
+    service.delete_user(user.id()).await.unwrap();
+    service.restore_user(user.id()).await.unwrap();
+    service.hard_delete_user(user.id()).await.unwrap();
+
+    use IsolationLevel::{ReadCommitted, Serializable};
+    assert_eq!(*unit_of_work.isolation_levels.lock().unwrap(), [ReadCommitted, ReadCommitted, Serializable, Serializable, ReadCommitted, Serializable]);
+}
+
+#[tokio::test]
+async fn fails_changes_when_no_transaction_can_begin() {
+    let unit_of_work = Arc::new(RecordingUnitOfWork::default());
+    let service = user_service(&unit_of_work);
+    unit_of_work.failing_begin.store(true, Ordering::SeqCst);
+
+    assert_eq!(service.create_user(jdoe()).await, Err(UserDomainError::UserCreationFailed(StorageError::msg("storage failed"))));
+    assert!(unit_of_work.users.get_user_by_email("jdoe@example.com".parse().unwrap()).await.is_err());
+}
+
+/// Changes made in transactions of PostgreSQL, on a throwaway database of `TEST_DATABASE_URL`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use std::sync::Arc;
+
+    use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+    use rust_web_server_lib::application::ports::audit::{AuditAction, AuditEntry, AuditLogPort};
+    use rust_web_server_lib::application::ports::unit_of_work::{IsolationLevel, UnitOfWorkPort};
+    use rust_web_server_lib::domain::user::error::{StorageError, UserDomainError};
+    use rust_web_server_lib::domain::user::event::UserEvent;
+    use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+    use rust_web_server_lib::infra::storage::adapter::postgres::audit_log::PostgresAuditLog;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::unit_of_work::PostgresUnitOfWork;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+
+    use super::jdoe;
+
+    async fn outbox_len(db: &TestDb) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM event_outbox").fetch_one(&*db.db()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn records_events_with_the_change() {
+        let db = TestDb::new().await.unwrap();
+        let service = UserService::new(UserRepository::new(db.db())).with_unit_of_work(Arc::new(PostgresUnitOfWork::new(db.db())));
+
+        let user = service.create_user(jdoe()).await.unwrap();
+        service.delete_user(user.id()).await.unwrap();
+
+        assert_eq!(outbox_len(&db).await, 2);
+    }
+
+    #[tokio::test]
+    async fn records_audit_entries_with_the_change() {
+        let db = TestDb::new().await.unwrap();
+        let service = UserService::new(UserRepository::new(db.db()))
+            .with_unit_of_work(Arc::new(PostgresUnitOfWork::new(db.db())))
+            .with_audit_log(Arc::new(PostgresAuditLog::new(db.db())));
+
+        let user = service.create_user(jdoe()).await.unwrap();
+        service.set_legal_hold(user.id(), true).await.unwrap();
+
+        let history = PostgresAuditLog::new(db.db()).history(user.id()).await.unwrap();
+        let actions: Vec<_> = history.iter().map(|entry| entry.action).collect();
+        assert_eq!(actions, [AuditAction::Created, AuditAction::Updated]);
+    }
+
+    #[tokio::test]
+    async fn discards_changes_of_dropped_transactions() {
+        let db = TestDb::new().await.unwrap();
+        let unit_of_work = PostgresUnitOfWork::new(db.db());
+
+        let transaction = unit_of_work.begin(IsolationLevel::ReadCommitted).await.unwrap();
+        let user = transaction.users().create_user(jdoe(), None).await.unwrap();
+        transaction.events().publish(UserEvent::UserCreated(user.clone())).await.unwrap();
+        transaction.audit_log().record(AuditEntry::new(user.id(), AuditAction::Created, None, Some(&user))).await.unwrap();
+        // Changes are visible within the transaction only
+        assert_eq!(transaction.users().get_user(user.id()).await.unwrap(), user);
+        assert!(UserRepository::new(db.db()).get_user(user.id()).await.is_err());
+        drop(transaction);
+
+        assert!(UserRepository::new(db.db()).get_user(user.id()).await.is_err());
+        assert_eq!(outbox_len(&db).await, 0);
+        assert!(PostgresAuditLog::new(db.db()).history(user.id()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn applies_changes_of_committed_transactions() {
+        let db = TestDb::new().await.unwrap();
+        let unit_of_work = PostgresUnitOfWork::new(db.db());
+
+        let transaction = unit_of_work.begin(IsolationLevel::ReadCommitted).await.unwrap();
+        let user = transaction.users().create_user(jdoe(), None).await.unwrap();
+        transaction.events().publish(UserEvent::UserCreated(user.clone())).await.unwrap();
+        transaction.commit().await.unwrap();
+
+        assert_eq!(UserRepository::new(db.db()).get_user(user.id()).await.unwrap(), user);
+        assert_eq!(outbox_len(&db).await, 1);
+    }
+
+    /// Deletes the user through a transaction checking it is not under legal hold, while the
+    /// user is placed under hold once checked, by a concurrent transaction.
+    async fn delete_while_placed_under_legal_hold(db: &TestDb, isolation: IsolationLevel) -> Result<(), UserDomainError> {
+        let user = UserRepository::new(db.db()).create_user(jdoe(), None).await.unwrap();
+        let unit_of_work = PostgresUnitOfWork::new(db.db());
+
+        let transaction = unit_of_work.begin(isolation).await.unwrap();
+        assert!(!transaction.users().get_user(user.id()).await.unwrap().legal_hold());
+        UserRepository::new(db.db()).set_legal_hold(user.id(), true).await.unwrap();
+        transaction.users().delete_user(user.id()).await?;
+        transaction.commit().await.map_err(|e| UserDomainError::UserDeletionFailed(StorageError::boxed(e.into())))
+    }
+
+    #[tokio::test]
+    async fn serializable_transactions_prevent_changes_based_on_outdated_reads() {
+        let db = TestDb::new().await.unwrap();
+
+        // Read committed, the deletion overrides the hold it did not see
+        delete_while_placed_under_legal_hold(&db, IsolationLevel::ReadCommitted).await.unwrap();
+        // Serializable, the deletion fails rather than deleting a user under hold
+        assert!(delete_while_placed_under_legal_hold(&db, IsolationLevel::Serializable).await.is_err());
+    }
+}
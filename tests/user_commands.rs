@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use rust_web_server_lib::application::flows::auth_service::AuthService;
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::{DisabledAuthenticator, TokenPort, USERS_WRITE_SCOPE};
+use rust_web_server_lib::application::ports::commands::CommandHandlerPort;
+use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+use rust_web_server_lib::infra::auth::JwtConfig;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::middleware::auth::AuthState;
+use rust_web_server_lib::presentation::rpc::commands::UserCommandHandler;
+use rust_web_server_lib::presentation::rpc::UserRpc;
+
+fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "commands-test-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+fn token(scopes: &[&str]) -> String {
+    let scopes: Vec<String> = scopes.iter().map(|scope| scope.to_string()).collect();
+    jwt_tokens().issue("jdoe", &[], &scopes).unwrap().token
+}
+
+fn handler() -> UserCommandHandler {
+    let user_service = Arc::new(UserService::new(InMemoryUserRepository::new()));
+    let auth = AuthState { auth_service: Arc::new(AuthService::new(Arc::new(DisabledAuthenticator), Arc::new(jwt_tokens()))) };
+    UserCommandHandler::new(UserRpc::new(user_service, auth))
+}
+
+/// Executes `command`, returning the correlation id of the reply and its body.
+async fn handle(handler: &UserCommandHandler, command: &[u8]) -> (Option<String>, Value) {
+    let reply = handler.handle(command).await;
+    (reply.correlation_id, serde_json::from_slice(&reply.payload).unwrap())
+}
+
+async fn create_user(handler: &UserCommandHandler) -> String {
+    let command = json!({ "correlation_id": "c-1", "command": "create_user", "user": { "name": "John Doe", "email": "john@example.com", "age": 30 } });
+    let (_, reply) = handle(handler, command.to_string().as_bytes()).await;
+    reply["user"]["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn creates_users_and_replies_with_the_correlation_id() {
+    let handler = handler();
+    let command = json!({ "correlation_id": "c-1", "command": "create_user", "request_id": "req-1", "user": { "name": "John Doe", "email": "john@example.com", "age": 30 } });
+
+    let (correlation_id, reply) = handle(&handler, command.to_string().as_bytes()).await;
+
+    assert_eq!(correlation_id.as_deref(), Some("c-1"));
+    assert_eq!(reply["correlation_id"], "c-1");
+    assert_eq!(reply["user"]["name"], "John Doe");
+    assert_eq!(reply["user"]["email"], "john@example.com");
+    assert_eq!(reply["user"]["age"], 30);
+    assert!(reply.get("error").is_none());
+}
+
+#[tokio::test]
+async fn updates_users_with_a_token_allowed_to_write_users() {
+    let handler = handler();
+    let id = create_user(&handler).await;
+
+    let command = json!({ "correlation_id": "c-2", "command": "update_user", "id": id, "user": { "age": 31 } });
+    let (_, reply) = handle(&handler, command.to_string().as_bytes()).await;
+    assert_eq!(reply["error"]["code"], "unauthorized");
+
+    let command = json!({ "correlation_id": "c-3", "command": "update_user", "token": token(&[]), "id": id, "user": { "age": 31 } });
+    let (_, reply) = handle(&handler, command.to_string().as_bytes()).await;
+    assert_eq!(reply["error"]["code"], "forbidden");
+
+    let command = json!({ "correlation_id": "c-4", "command": "update_user", "token": token(&[USERS_WRITE_SCOPE]), "id": id, "user": { "age": 31 } });
+    let (_, reply) = handle(&handler, command.to_string().as_bytes()).await;
+    assert_eq!(reply["correlation_id"], "c-4");
+    assert_eq!(reply["user"]["id"], id.as_str());
+    assert_eq!(reply["user"]["age"], 31);
+}
+
+#[tokio::test]
+async fn replies_with_the_errors_of_invalid_fields() {
+    let handler = handler();
+    let command = json!({ "correlation_id": "c-1", "command": "create_user", "user": { "name": "", "email": "not-an-email", "age": 30 } });
+
+    let (_, reply) = handle(&handler, command.to_string().as_bytes()).await;
+
+    assert_eq!(reply["correlation_id"], "c-1");
+    assert_eq!(reply["error"]["code"], "invalid_request");
+    let fields: Vec<&str> = reply["error"]["errors"].as_array().unwrap().iter().map(|error| error["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, ["name", "email"]);
+}
+
+#[tokio::test]
+async fn replies_to_undecodable_commands() {
+    let handler = handler();
+
+    let (correlation_id, reply) = handle(&handler, json!({ "correlation_id": "c-1", "command": "drop_users" }).to_string().as_bytes()).await;
+    assert_eq!(correlation_id.as_deref(), Some("c-1"));
+    assert_eq!(reply["error"]["code"], "invalid_request");
+    assert!(reply["error"]["message"].as_str().unwrap().starts_with("Invalid command"));
+
+    let (correlation_id, reply) = handle(&handler, b"not json").await;
+    assert_eq!(correlation_id, None);
+    assert!(reply.get("correlation_id").is_none());
+    assert_eq!(reply["error"]["code"], "invalid_request");
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use argon2::Params;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::ports::password::PasswordHasherPort;
+use rust_web_server_lib::domain::user::error::{StorageError, UserDomainError};
+use rust_web_server_lib::domain::user::model::{CreateUser, Password, PasswordHash};
+use rust_web_server_lib::infra::auth::password::Argon2PasswordHasher;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+/// A hasher with the lowest parameters Argon2 accepts, keeping the tests fast.
+fn hasher() -> Argon2PasswordHasher {
+    Argon2PasswordHasher::new(Params::new(Params::MIN_M_COST, 1, 1, None).unwrap())
+}
+
+fn service() -> UserService<InMemoryUserRepository> {
+    UserService::new(InMemoryUserRepository::new()).with_password_hasher(Arc::new(hasher()))
+}
+
+fn jane() -> CreateUser {
+    CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap()
+}
+
+#[tokio::test]
+async fn hashes_passwords_with_argon2id() {
+    let hasher = hasher();
+    let password = Password::parse("correct horse").unwrap();
+
+    let hash = hasher.hash(&password).await.unwrap();
+    assert!(hash.as_str().starts_with("$argon2id$v=19$"), "{:?}", hash.as_str());
+    // Every hash has its own salt
+    assert_ne!(hasher.hash(&password).await.unwrap(), hash);
+
+    assert!(hasher.verify("correct horse", Some(&hash)).await.unwrap());
+    assert!(!hasher.verify("wrong horse", Some(&hash)).await.unwrap());
+    assert!(!hasher.verify("correct horse", None).await.unwrap());
+    assert!(hasher.verify("correct horse", Some(&PasswordHash::new("not a hash"))).await.is_err());
+    // Hashes made with other parameters are verified with their own
+    assert!(Argon2PasswordHasher::default().verify("correct horse", Some(&hash)).await.unwrap());
+}
+
+#[tokio::test]
+async fn verifies_the_credentials_of_users() {
+    let service = service();
+    let user = service.create_user(jane().with_password("correct horse".to_string()).unwrap()).await.unwrap();
+
+    assert_eq!(service.verify_credentials("JANE@example.com".parse().unwrap(), "correct horse".to_string()).await.unwrap(), user);
+    for (email, password) in [("jane@example.com", "wrong horse"), ("john@example.com", "correct horse")] {
+        let error = service.verify_credentials(email.parse().unwrap(), password.to_string()).await.unwrap_err();
+        assert_eq!(error, UserDomainError::InvalidCredentials, "{} {}", email, password);
+    }
+
+    // Neither deleted users nor users without a password have valid credentials
+    service.delete_user(user.id()).await.unwrap();
+    assert_eq!(service.verify_credentials(user.email().clone(), "correct horse".to_string()).await.unwrap_err(), UserDomainError::InvalidCredentials);
+    let john = service.create_user(CreateUser::new("John".to_string(), "john@example.com".to_string(), 40).unwrap()).await.unwrap();
+    assert_eq!(service.verify_credentials(john.email().clone(), String::new()).await.unwrap_err(), UserDomainError::InvalidCredentials);
+}
+
+#[tokio::test]
+async fn refuses_passwords_without_a_hasher() {
+    let service = UserService::new(InMemoryUserRepository::new());
+
+    let user = jane().with_password("correct horse".to_string()).unwrap();
+    assert_eq!(service.create_user(user).await.unwrap_err(), UserDomainError::UserCreationFailed(StorageError::msg("storage failed")));
+}
+
+async fn create(app: &axum::Router, body: Value) -> (StatusCode, Value) {
+    let request = Request::post("/api/users").header("content-type", "application/json").body(Body::from(body.to_string())).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn creates_users_with_a_password() {
+    let service = Arc::new(service());
+    let app = router(AppState::new(service.clone()));
+
+    let (status, body) = create(&app, json!({"name": "Jane", "email": "jane@example.com", "age": 30, "password": "correct horse"})).await;
+    assert_eq!(status, StatusCode::CREATED);
+    // The password is never returned
+    assert_eq!(body["data"].as_object().unwrap().keys().collect::<Vec<_>>(), ["age", "email", "id", "name"]);
+    assert!(service.verify_credentials("jane@example.com".parse().unwrap(), "correct horse".to_string()).await.is_ok());
+
+    let (status, body) = create(&app, json!({"name": "John", "email": "john@example.com", "age": 40, "password": "short"})).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["data"]["errors"][0]["field"], "password");
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::domain::user::model::UserCredentials;
+    use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stores_the_hashes_of_passwords() {
+        let db = TestDb::new().await.unwrap();
+        let users = UserRepository::new(db.db());
+        let hash = PasswordHash::new("$argon2id$v=19$m=8,t=1,p=1$c2FsdA$aGFzaA");
+
+        let jane = users.create_user(jane(), Some(hash.clone())).await.unwrap();
+        let john = users.create_user(CreateUser::new("John".to_string(), "john@example.com".to_string(), 40).unwrap(), None).await.unwrap();
+
+        let credentials = users.get_user_credentials("JANE@example.com".parse().unwrap()).await.unwrap();
+        assert_eq!(credentials, UserCredentials { user: jane.clone(), password_hash: Some(hash) });
+        assert_eq!(users.get_user_credentials(john.email().clone()).await.unwrap().password_hash, None);
+
+        users.delete_user(jane.id()).await.unwrap();
+        assert_eq!(users.get_user_credentials(jane.email().clone()).await.unwrap_err(), UserDomainError::UserNotFound);
+    }
+
+    #[tokio::test]
+    async fn verifies_the_credentials_of_stored_users() {
+        let db = TestDb::new().await.unwrap();
+        let service = UserService::new(UserRepository::new(db.db())).with_password_hasher(Arc::new(hasher()));
+
+        let user = service.create_user(jane().with_password("correct horse".to_string()).unwrap()).await.unwrap();
+
+        assert_eq!(service.verify_credentials(user.email().clone(), "correct horse".to_string()).await.unwrap(), user);
+        assert_eq!(service.verify_credentials(user.email().clone(), "wrong horse".to_string()).await.unwrap_err(), UserDomainError::InvalidCredentials);
+    }
+}
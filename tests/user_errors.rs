@@ -0,0 +1,107 @@
+use std::error::Error;
+use std::io;
+
+use axum::response::IntoResponse;
+use port_decorators::Retryable;
+use serde_json::{json, Value};
+
+use rust_web_server_lib::domain::outcome::ErrorClass;
+use rust_web_server_lib::domain::user::error::{StorageError, UserDomainError, UserErrorCategory};
+use rust_web_server_lib::presentation::handlers::user_handlers::ApiError;
+
+fn connection_reset() -> StorageError {
+    StorageError::new(io::Error::new(io::ErrorKind::ConnectionReset, "connection reset by peer"))
+}
+
+/// Returns the status and body of the response of `e`.
+async fn respond(e: UserDomainError) -> (u16, Value) {
+    let response = ApiError::from(e).into_response();
+    let status = response.status().as_u16();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[test]
+fn keeps_the_storage_failure_as_the_source() {
+    let error = UserDomainError::UserReadFailed(connection_reset());
+
+    assert_eq!(error.to_string(), "failed to read user");
+    assert_eq!(error.source().unwrap().to_string(), "connection reset by peer");
+}
+
+#[test]
+fn classifies_constraint_violations_conflicts_and_timeouts() {
+    let violation = UserDomainError::UserConstraintViolation(connection_reset());
+    let conflict = UserDomainError::UserChangeConflict(connection_reset());
+    let timeout = UserDomainError::StorageTimeout(connection_reset());
+
+    assert_eq!((violation.category(), violation.class(), violation.is_retryable()), (UserErrorCategory::Validation, "constraint_violation", false));
+    assert_eq!((conflict.category(), conflict.class(), conflict.is_retryable()), (UserErrorCategory::Conflict, "concurrent_change", true));
+    assert_eq!((timeout.category(), timeout.class(), timeout.is_retryable()), (UserErrorCategory::Infrastructure, "timeout", true));
+}
+
+#[tokio::test]
+async fn responds_with_the_code_of_the_error() {
+    let (status, body) = respond(UserDomainError::UserNotFound).await;
+    assert_eq!((status, body), (404, json!({ "status_code": 404, "data": { "code": "not_found", "message": "User not found" } })));
+
+    let (status, body) = respond(UserDomainError::UserChangeConflict(connection_reset())).await;
+    assert_eq!((status, &body["data"]["code"]), (409, &json!("conflict")));
+    let (status, body) = respond(UserDomainError::UserConstraintViolation(connection_reset())).await;
+    assert_eq!((status, &body["data"]["code"]), (422, &json!("unprocessable_entity")));
+}
+
+#[tokio::test]
+async fn logs_the_cause_of_storage_failures_without_returning_it() {
+    let timeout = UserDomainError::StorageTimeout(connection_reset());
+    assert_eq!(ApiError::from(timeout.clone()), ApiError::ServiceUnavailable("Storage timed out: connection reset by peer".to_string()));
+    let (status, body) = respond(timeout).await;
+    assert_eq!((status, &body["data"]), (503, &json!({ "code": "unavailable", "message": "Service unavailable" })));
+
+    let failure = UserDomainError::UserCreationFailed(connection_reset());
+    assert_eq!(ApiError::from(failure.clone()), ApiError::InternalServerError("Failed to create user: connection reset by peer".to_string()));
+    let (status, body) = respond(failure).await;
+    assert_eq!((status, &body["data"]), (500, &json!({ "code": "internal", "message": "Internal server error" })));
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::domain::consent::error::ConsentDomainError;
+    use rust_web_server_lib::domain::consent::model::{ConsentAction, ConsentType, RecordConsent};
+    use rust_web_server_lib::domain::consent::repository::ConsentRepositoryPort;
+    use rust_web_server_lib::domain::passkey::error::PasskeyDomainError;
+    use rust_web_server_lib::domain::passkey::model::RegisterPasskey;
+    use rust_web_server_lib::domain::passkey::repository::PasskeyRepositoryPort;
+    use rust_web_server_lib::domain::user::model::{CreateUser, UserId};
+    use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+    use rust_web_server_lib::infra::storage::adapter::postgres::consent_repository::ConsentRepository;
+    use rust_web_server_lib::infra::storage::adapter::postgres::passkey_repository::PasskeyRepository;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+
+    fn passkey(user_id: UserId) -> RegisterPasskey {
+        RegisterPasskey { id: "credential-1".to_string(), user_id, name: "Laptop".to_string(), credential: "{}".to_string() }
+    }
+
+    #[tokio::test]
+    async fn classifies_the_violations_of_passkeys_and_consents_by_their_sqlstate() {
+        let db = TestDb::new().await.unwrap();
+        let user = UserRepository::new(db.db()).create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap(), None).await.unwrap();
+        let passkeys = PasskeyRepository::new(db.db());
+
+        passkeys.add_passkey(passkey(user.id())).await.unwrap();
+        assert_eq!(passkeys.add_passkey(passkey(user.id())).await.unwrap_err(), PasskeyDomainError::PasskeyAlreadyExists);
+        let unknown = RegisterPasskey { id: "credential-2".to_string(), ..passkey(UserId::generate()) };
+        assert_eq!(passkeys.add_passkey(unknown).await.unwrap_err(), PasskeyDomainError::UserNotFound);
+
+        let consent = RecordConsent {
+            user_id: UserId::generate().to_string(),
+            consent_type: ConsentType::Analytics,
+            action: ConsentAction::Granted,
+            version: "1".to_string(),
+            source: "signup".to_string(),
+        };
+        assert_eq!(ConsentRepository::new(db.db()).record_consent(consent).await.unwrap_err(), ConsentDomainError::UserNotFound);
+    }
+}
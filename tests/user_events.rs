@@ -0,0 +1,515 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::ports::capability::Capability;
+use rust_web_server_lib::application::ports::events::OutboxPort;
+use rust_web_server_lib::application::ports::messaging::MessagePublisherPort;
+use rust_web_server_lib::domain::user::error::UserDomainError;
+use rust_web_server_lib::domain::user::event::UserEvent;
+use rust_web_server_lib::domain::user::model::{CreateUser, UpdateUser, User, UserId};
+use rust_web_server_lib::infra::messaging::protobuf::{self, UserEventMessage, UserMessage};
+use rust_web_server_lib::infra::messaging::EventFormat;
+use rust_web_server_lib::infra::outbox::{event_data, event_from_data, OutboxConfig, OutboxDispatcher};
+use rust_web_server_lib::infra::storage::adapter::in_memory::outbox::InMemoryOutbox;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+
+/// Message publisher recording the messages it publishes, failing while `failing` is set.
+#[derive(Default)]
+struct RecordingPublisher {
+    messages: Mutex<Vec<(String, Value)>>,
+    failing: Mutex<bool>,
+}
+
+impl RecordingPublisher {
+    fn messages(&self) -> Vec<(String, Value)> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    fn set_failing(&self, failing: bool) {
+        *self.failing.lock().unwrap() = failing;
+    }
+}
+
+impl Capability for RecordingPublisher {
+    fn name(&self) -> &'static str {
+        "messaging"
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl MessagePublisherPort for RecordingPublisher {
+    async fn publish(&self, topic: &str, payload: Vec<u8>) -> eyre::Result<()> {
+        if *self.failing.lock().unwrap() {
+            eyre::bail!("broker unavailable");
+        }
+        self.messages.lock().unwrap().push((topic.to_string(), serde_json::from_slice(&payload)?));
+        Ok(())
+    }
+}
+
+fn user_service(outbox: &Arc<InMemoryOutbox>) -> UserService<InMemoryUserRepository> {
+    UserService::new(InMemoryUserRepository::new()).with_event_publisher(outbox.clone())
+}
+
+fn jdoe() -> CreateUser {
+    CreateUser::new("John Doe".to_string(), "jdoe@example.com".to_string(), 42).unwrap()
+}
+
+#[tokio::test]
+async fn publishes_user_changes_in_order() {
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let service = user_service(&outbox);
+    let publisher = RecordingPublisher::default();
+
+    let user = service.create_user(jdoe()).await.unwrap();
+    service.update_user(UpdateUser::new(user.id(), None, None, Some(43)).unwrap()).await.unwrap();
+    service.delete_user(user.id()).await.unwrap();
+
+    assert_eq!(outbox.dispatch(&publisher, 100).await.unwrap(), 3);
+    let messages = publisher.messages();
+    let topics: Vec<&str> = messages.iter().map(|(topic, _)| topic.as_str()).collect();
+    assert_eq!(topics, ["user.created", "user.updated", "user.deleted"]);
+
+    let (_, created) = &messages[0];
+    assert_eq!(created["type"], "user.created");
+    assert_eq!(created["data"]["id"], user.id().to_string());
+    assert_eq!(created["data"]["email"], "jdoe@example.com");
+    assert_eq!(created["data"]["age"], 42);
+    assert!(created["occurred_at"].is_string());
+    assert_eq!(messages[1].1["data"]["age"], 43);
+    assert_eq!(messages[2].1["data"], serde_json::json!({ "id": user.id().to_string() }));
+
+    // Event ids are unique, for consumers to deduplicate events with
+    assert_ne!(messages[0].1["id"], messages[1].1["id"]);
+    assert_eq!(outbox.pending().await, 0);
+}
+
+#[tokio::test]
+async fn publishes_nothing_for_failed_changes() {
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let service = user_service(&outbox);
+
+    let user = service.create_user(jdoe()).await.unwrap();
+    service.set_legal_hold(user.id(), true).await.unwrap();
+
+    assert_eq!(service.delete_user(user.id()).await.unwrap_err(), UserDomainError::UserUnderLegalHold);
+    let unknown = UpdateUser::new(UserId::generate(), Some("Jane Doe".to_string()), None, None).unwrap();
+    assert_eq!(service.update_user(unknown).await.unwrap_err(), UserDomainError::UserNotFound);
+
+    // Only the creation was published
+    assert_eq!(outbox.pending().await, 1);
+}
+
+#[tokio::test]
+async fn keeps_events_until_they_are_published() {
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let service = user_service(&outbox);
+    let publisher = RecordingPublisher::default();
+    let user = service.create_user(jdoe()).await.unwrap();
+    service.delete_user(user.id()).await.unwrap();
+
+    publisher.set_failing(true);
+    assert!(outbox.dispatch(&publisher, 100).await.is_err());
+    assert_eq!(outbox.pending().await, 2);
+
+    publisher.set_failing(false);
+    assert_eq!(outbox.dispatch(&publisher, 1).await.unwrap(), 1);
+    assert_eq!(outbox.dispatch(&publisher, 1).await.unwrap(), 1);
+    assert_eq!(outbox.dispatch(&publisher, 1).await.unwrap(), 0);
+
+    let topics: Vec<String> = publisher.messages().into_iter().map(|(topic, _)| topic).collect();
+    assert_eq!(topics, ["user.created", "user.deleted"]);
+}
+
+#[tokio::test]
+async fn dispatcher_publishes_recorded_events() {
+    let outbox = Arc::new(InMemoryOutbox::new());
+    let service = user_service(&outbox);
+    let publisher = Arc::new(RecordingPublisher::default());
+    let dispatcher = OutboxDispatcher::spawn(outbox.clone(), publisher.clone(), OutboxConfig { poll_interval_ms: 10, batch_size: 2, format: EventFormat::Json });
+
+    for i in 0..5 {
+        let user = CreateUser::new(format!("User {}", i), format!("user{}@example.com", i), 30).unwrap();
+        service.create_user(user).await.unwrap();
+    }
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while publisher.messages().len() < 5 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("events were not dispatched");
+    dispatcher.shutdown().await;
+
+    let emails: Vec<Value> = publisher.messages().into_iter().map(|(_, message)| message["data"]["email"].clone()).collect();
+    let expected: Vec<Value> = (0..5).map(|i| Value::from(format!("user{}@example.com", i))).collect();
+    assert_eq!(emails, expected);
+}
+
+/// Message publisher keeping the raw payloads of the messages it publishes.
+#[derive(Default)]
+struct RawPublisher(Mutex<Vec<(String, Vec<u8>)>>);
+
+impl Capability for RawPublisher {
+    fn name(&self) -> &'static str {
+        "messaging"
+    }
+
+    fn is_enabled(&self) -> bool {
+        true
+    }
+}
+
+#[async_trait]
+impl MessagePublisherPort for RawPublisher {
+    async fn publish(&self, topic: &str, payload: Vec<u8>) -> eyre::Result<()> {
+        self.0.lock().unwrap().push((topic.to_string(), payload));
+        Ok(())
+    }
+}
+
+fn every_event() -> Vec<UserEvent> {
+    let user = User::new(UserId::generate(), "John Doe".to_string(), "jdoe@example.com".parse().unwrap(), 42);
+    vec![
+        UserEvent::UserCreated(user.clone()),
+        UserEvent::UserUpdated(user.clone()),
+        UserEvent::UserDeleted(user.id()),
+        UserEvent::UserRestored(user.clone()),
+        UserEvent::UserHardDeleted(user.id()),
+    ]
+}
+
+#[test]
+fn converts_events_to_and_from_protobuf() {
+    let occurred_at: DateTime<Utc> = "2026-01-15T10:00:00.250Z".parse().unwrap();
+    for event in every_event() {
+        let message = protobuf::decode_event(&protobuf::encode_event(&event, "event-1", occurred_at)).unwrap();
+        assert_eq!((message.id.as_str(), message.r#type.as_str()), ("event-1", event.event_type()));
+        assert_eq!(message.occurred_at, occurred_at.timestamp_millis());
+        assert_eq!(message.user_id, event.user_id().to_string());
+        assert_eq!(message.user.is_some(), !matches!(event, UserEvent::UserDeleted(_) | UserEvent::UserHardDeleted(_)));
+
+        assert_eq!(UserEvent::try_from(message).unwrap(), event);
+    }
+}
+
+#[test]
+fn converts_events_to_and_from_outbox_data() {
+    for event in every_event() {
+        assert_eq!(event_from_data(event.event_type(), &event_data(&event)).unwrap(), event);
+    }
+}
+
+#[test]
+fn rejects_invalid_protobuf_events() {
+    let user_id = UserId::generate().to_string();
+    let deleted = UserEventMessage { id: "event-1".to_string(), r#type: "user.deleted".to_string(), occurred_at: 0, user_id, user: None };
+
+    for message in [
+        UserEventMessage { r#type: "user.renamed".to_string(), ..deleted.clone() },
+        UserEventMessage { r#type: "user.created".to_string(), ..deleted.clone() },
+        UserEventMessage { user_id: "42".to_string(), ..deleted.clone() },
+        UserEventMessage { r#type: "user.created".to_string(), user: Some(UserMessage { name: "John Doe".to_string(), email: "jdoe@example.com".to_string(), age: 300 }), ..deleted },
+    ] {
+        assert!(UserEvent::try_from(message.clone()).is_err(), "{:?}", message);
+    }
+    assert!(protobuf::decode_event(b"\xff\xff").is_err());
+}
+
+#[tokio::test]
+async fn dispatches_events_as_protobuf() {
+    let outbox = Arc::new(InMemoryOutbox::new().with_format(EventFormat::Protobuf));
+    let service = user_service(&outbox);
+    let publisher = RawPublisher::default();
+
+    let user = service.create_user(jdoe()).await.unwrap();
+    service.delete_user(user.id()).await.unwrap();
+    assert_eq!(outbox.dispatch(&publisher, 100).await.unwrap(), 2);
+
+    let events: Vec<(String, UserEvent)> = std::mem::take(&mut *publisher.0.lock().unwrap())
+        .into_iter()
+        .map(|(topic, payload)| (topic, UserEvent::try_from(protobuf::decode_event(&payload).unwrap()).unwrap()))
+        .collect();
+    assert_eq!(events, [("user.created".to_string(), UserEvent::UserCreated(user.clone())), ("user.deleted".to_string(), UserEvent::UserDeleted(user.id()))]);
+}
+
+/// Encoding of the events published to Kafka. No broker runs in the tests: publishing is only
+/// exercised against an unreachable one.
+#[cfg(feature = "kafka")]
+mod kafka {
+    use apache_avro::types::Value as AvroValue;
+    use apache_avro::GenericSingleObjectReader;
+
+    use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+    use rust_web_server_lib::application::ports::events::EventPublisherPort;
+    use rust_web_server_lib::infra::messaging::kafka::{encode_event, user_event_schema, KafkaEventPublisher};
+    use rust_web_server_lib::infra::messaging::KafkaConfig;
+    use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+
+    use super::*;
+
+    fn occurred_at() -> DateTime<Utc> {
+        "2026-01-15T10:00:00.250Z".parse().unwrap()
+    }
+
+    async fn created_event() -> UserEvent {
+        let user = UserService::new(InMemoryUserRepository::new()).create_user(jdoe()).await.unwrap();
+        UserEvent::UserCreated(user)
+    }
+
+    fn decode_avro(payload: &[u8]) -> Vec<(String, AvroValue)> {
+        // Single-object encoding: a 2-byte marker, then the fingerprint of the schema
+        assert_eq!(&payload[..2], [0xC3, 0x01]);
+        match GenericSingleObjectReader::new(user_event_schema().unwrap()).unwrap().read_value(&mut &payload[..]).unwrap() {
+            AvroValue::Record(fields) => fields,
+            value => panic!("expected a record, got {:?}", value),
+        }
+    }
+
+    #[tokio::test]
+    async fn encodes_events_as_json() {
+        let event = created_event().await;
+        let schema = user_event_schema().unwrap();
+
+        let payload = encode_event(&event, EventFormat::Json, &schema, "event-1", occurred_at()).unwrap();
+
+        let message: Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(message["id"], "event-1");
+        assert_eq!(message["type"], "user.created");
+        assert_eq!(message["occurred_at"], "2026-01-15T10:00:00.250Z");
+        assert_eq!(message["data"]["id"], event.user_id().to_string());
+        assert_eq!(message["data"]["email"], "jdoe@example.com");
+    }
+
+    #[tokio::test]
+    async fn encodes_events_as_avro() {
+        let event = created_event().await;
+
+        let payload = encode_event(&event, EventFormat::Avro, &user_event_schema().unwrap(), "event-1", occurred_at()).unwrap();
+
+        assert_eq!(
+            decode_avro(&payload),
+            vec![
+                ("id".to_string(), AvroValue::String("event-1".to_string())),
+                ("type".to_string(), AvroValue::String("user.created".to_string())),
+                ("occurred_at".to_string(), AvroValue::TimestampMillis(occurred_at().timestamp_millis())),
+                ("user_id".to_string(), AvroValue::String(event.user_id().to_string())),
+                ("name".to_string(), AvroValue::Union(1, Box::new(AvroValue::String("John Doe".to_string())))),
+                ("email".to_string(), AvroValue::Union(1, Box::new(AvroValue::String("jdoe@example.com".to_string())))),
+                ("age".to_string(), AvroValue::Union(1, Box::new(AvroValue::Int(42)))),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn encodes_events_as_protobuf() {
+        let event = created_event().await;
+
+        let payload = encode_event(&event, EventFormat::Protobuf, &user_event_schema().unwrap(), "event-1", occurred_at()).unwrap();
+
+        let message = protobuf::decode_event(&payload).unwrap();
+        assert_eq!(message.id, "event-1");
+        assert_eq!(message.occurred_at, occurred_at().timestamp_millis());
+        assert_eq!(UserEvent::try_from(message).unwrap(), event);
+    }
+
+    #[test]
+    fn encodes_deleted_users_without_attributes() {
+        let id = UserId::generate();
+        let event = UserEvent::UserDeleted(id);
+
+        let fields = decode_avro(&encode_event(&event, EventFormat::Avro, &user_event_schema().unwrap(), "event-2", occurred_at()).unwrap());
+
+        assert_eq!(fields[1].1, AvroValue::String("user.deleted".to_string()));
+        assert_eq!(fields[3].1, AvroValue::String(id.to_string()));
+        for (_, value) in &fields[4..] {
+            assert_eq!(*value, AvroValue::Union(0, Box::new(AvroValue::Null)));
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_undelivered_events() {
+        let publisher = KafkaEventPublisher::new(&KafkaConfig {
+            // Nothing listens on the port 1
+            brokers: "127.0.0.1:1".to_string(),
+            topic: "user-events".to_string(),
+            format: EventFormat::Json,
+            delivery_timeout_ms: 500,
+        })
+        .unwrap();
+
+        let error = tokio::time::timeout(Duration::from_secs(10), publisher.publish(created_event().await))
+            .await
+            .expect("delivery did not time out")
+            .unwrap_err();
+
+        assert!(format!("{:#}", error).contains("to Kafka topic user-events"), "{:#}", error);
+    }
+}
+
+/// Events published to MQTT, through a minimal broker speaking just enough MQTT 3.1.1 to
+/// accept a publisher.
+#[cfg(feature = "mqtt")]
+mod mqtt {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::mpsc;
+
+    use rust_web_server_lib::application::ports::events::EventPublisherPort;
+    use rust_web_server_lib::infra::messaging::mqtt::MqttEventPublisher;
+    use rust_web_server_lib::infra::messaging::MqttConfig;
+
+    use super::*;
+
+    /// A PUBLISH packet received by the broker.
+    #[derive(Debug)]
+    struct Published {
+        topic: String,
+        qos: u8,
+        retain: bool,
+        payload: Vec<u8>,
+    }
+
+    /// Starts a broker accepting a single connection, acknowledging publishes when `acknowledging`.
+    /// Returns its address and the publishes it receives.
+    fn start_broker(acknowledging: bool) -> (String, mpsc::Receiver<Published>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            while let Some((header, body)) = read_packet(&mut stream) {
+                match header >> 4 {
+                    // CONNECT, answered with CONNACK
+                    1 => stream.write_all(&[0x20, 0x02, 0x00, 0x00]).unwrap(),
+                    // PUBLISH, answered with PUBACK (QoS 1) or PUBREC (QoS 2)
+                    3 => {
+                        let qos = (header >> 1) & 0x03;
+                        let topic_len = u16::from_be_bytes([body[0], body[1]]) as usize;
+                        let topic = String::from_utf8(body[2..2 + topic_len].to_vec()).unwrap();
+                        let payload_start = if qos > 0 { 4 + topic_len } else { 2 + topic_len };
+                        if acknowledging && qos > 0 {
+                            let ack = if qos == 1 { 0x40 } else { 0x50 };
+                            stream.write_all(&[ack, 0x02, body[2 + topic_len], body[3 + topic_len]]).unwrap();
+                        }
+                        let published = Published { topic, qos, retain: header & 0x01 == 1, payload: body[payload_start..].to_vec() };
+                        if sender.send(published).is_err() {
+                            return;
+                        }
+                    }
+                    // PUBREL, answered with PUBCOMP
+                    6 => stream.write_all(&[0x70, 0x02, body[0], body[1]]).unwrap(),
+                    // PINGREQ, answered with PINGRESP
+                    12 => stream.write_all(&[0xD0, 0x00]).unwrap(),
+                    _ => {}
+                }
+            }
+        });
+        (addr, receiver)
+    }
+
+    /// Reads the fixed header byte and the body of a packet, or `None` once disconnected.
+    fn read_packet(stream: &mut TcpStream) -> Option<(u8, Vec<u8>)> {
+        let mut byte = [0u8];
+        stream.read_exact(&mut byte).ok()?;
+        let header = byte[0];
+        // The remaining length is a variable-length integer, 7 bits per byte
+        let (mut len, mut shift) = (0usize, 0);
+        loop {
+            stream.read_exact(&mut byte).ok()?;
+            len |= ((byte[0] & 0x7F) as usize) << shift;
+            shift += 7;
+            if byte[0] & 0x80 == 0 {
+                break;
+            }
+        }
+        let mut body = vec![0u8; len];
+        stream.read_exact(&mut body).ok()?;
+        Some((header, body))
+    }
+
+    fn config(broker: String) -> MqttConfig {
+        MqttConfig {
+            broker,
+            client_id: Some("rustweb-test".to_string()),
+            username: None,
+            password: None,
+            topic: "users/{user_id}/events".to_string(),
+            qos: 1,
+            retain: false,
+            format: EventFormat::Json,
+            delivery_timeout_ms: 5000,
+        }
+    }
+
+    async fn created_event() -> UserEvent {
+        let user = UserService::new(InMemoryUserRepository::new()).create_user(jdoe()).await.unwrap();
+        UserEvent::UserCreated(user)
+    }
+
+    #[tokio::test]
+    async fn publishes_events_to_the_topic_of_their_user() {
+        let (broker, published) = start_broker(true);
+        let publisher = MqttEventPublisher::new(&MqttConfig { retain: true, ..config(broker) }).unwrap();
+        let event = created_event().await;
+
+        publisher.publish(event.clone()).await.unwrap();
+
+        let message = published.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(message.topic, format!("users/{}/events", event.user_id()));
+        assert_eq!(message.qos, 1);
+        assert!(message.retain);
+        let payload: Value = serde_json::from_slice(&message.payload).unwrap();
+        assert_eq!(payload["type"], "user.created");
+        assert_eq!(payload["data"]["email"], "jdoe@example.com");
+    }
+
+    #[tokio::test]
+    async fn publishes_events_exactly_once_as_protobuf() {
+        let (broker, published) = start_broker(true);
+        let config = MqttConfig { topic: "presence/{event_type}/{user_id}".to_string(), qos: 2, format: EventFormat::Protobuf, ..config(broker) };
+        let publisher = MqttEventPublisher::new(&config).unwrap();
+        let created = created_event().await;
+        let deleted = UserEvent::UserDeleted(created.user_id());
+
+        // Each publish returns once completed by the broker, so the events are received in order
+        publisher.publish(created.clone()).await.unwrap();
+        publisher.publish(deleted.clone()).await.unwrap();
+
+        for event in [created, deleted] {
+            let message = published.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(message.topic, format!("presence/{}/{}", event.event_type(), event.user_id()));
+            assert_eq!(message.qos, 2);
+            assert_eq!(UserEvent::try_from(protobuf::decode_event(&message.payload).unwrap()).unwrap(), event);
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_unacknowledged_events() {
+        let (broker, published) = start_broker(false);
+        let publisher = MqttEventPublisher::new(&MqttConfig { delivery_timeout_ms: 500, ..config(broker) }).unwrap();
+
+        let error = publisher.publish(created_event().await).await.unwrap_err();
+
+        assert!(format!("{:#}", error).contains("to MQTT topic users/"), "{:#}", error);
+        // The event was sent, but never acknowledged
+        assert!(published.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+
+    #[test]
+    fn rejects_topics_with_wildcards() {
+        let config = MqttConfig { topic: "users/+/events".to_string(), ..config("127.0.0.1:1883".to_string()) };
+
+        assert!(MqttEventPublisher::new(&config).is_err());
+    }
+}
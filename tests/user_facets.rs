@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::domain::user::model::{AgeBucket, FacetCount, UserFacets, UserStatus, MAX_EMAIL_DOMAIN_FACETS};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+/// Users with their email and age, the first one being placed under legal hold.
+const USERS: [(&str, u8); 5] = [
+    ("jane@Example.com", 17),
+    ("john@example.com", 30),
+    ("joe@example.org", 34),
+    ("ann@mail.example.com", 65),
+    ("bob@example.org", 120),
+];
+
+#[test]
+fn buckets_ages() {
+    assert_eq!(AgeBucket::of(1), AgeBucket::Under18);
+    assert_eq!(AgeBucket::of(18), AgeBucket::From18To24);
+    assert_eq!(AgeBucket::of(64), AgeBucket::From55To64);
+    assert_eq!(AgeBucket::of(150), AgeBucket::From65);
+}
+
+#[test]
+fn lists_every_status_and_age_bucket_and_the_most_common_domains() {
+    let domains = (0..MAX_EMAIL_DOMAIN_FACETS as u64 + 2).map(|i| (format!("d{:02}.com", i), i % 3));
+    let facets = UserFacets::from_counts([(UserStatus::LegalHold, 2)], domains, [(AgeBucket::From65, 1), (AgeBucket::Under18, 4)]);
+
+    assert_eq!(facets.by_status, vec![FacetCount { value: UserStatus::Active, count: 0 }, FacetCount { value: UserStatus::LegalHold, count: 2 }]);
+    assert_eq!(facets.by_age_bucket.len(), AgeBucket::ALL.len());
+    assert_eq!(facets.by_age_bucket[0], FacetCount { value: AgeBucket::Under18, count: 4 });
+    assert_eq!(facets.by_age_bucket[6], FacetCount { value: AgeBucket::From65, count: 1 });
+    assert_eq!(facets.by_email_domain.len(), MAX_EMAIL_DOMAIN_FACETS);
+    // Ordered by decreasing count, then by name
+    assert_eq!(facets.by_email_domain[0], FacetCount { value: "d02.com".to_string(), count: 2 });
+    assert_eq!(facets.by_email_domain[1], FacetCount { value: "d05.com".to_string(), count: 2 });
+}
+
+fn app() -> axum::Router {
+    router(AppState {
+        admin_token: Some("secret".into()),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .header("authorization", "Bearer secret")
+        .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+        .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = if bytes.is_empty() { Value::Null } else { serde_json::from_slice(&bytes).unwrap() };
+
+    (status, body)
+}
+
+#[tokio::test]
+async fn lists_users_with_facets() {
+    let app = app();
+    let mut ids = Vec::new();
+    for (email, age) in USERS {
+        let (_, body) = send(&app, Method::POST, "/api/users", Some(json!({"name": "Jane Doe", "email": email, "age": age}))).await;
+        ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+    send(&app, Method::PUT, &format!("/api/admin/users/{}/legal-hold", ids[0]), Some(json!({"legal_hold": true}))).await;
+
+    let (status, body) = send(&app, Method::GET, "/api/admin/users?limit=2&sort_by=age", None).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let data = &body["data"];
+    assert_eq!((data["total"].clone(), data["limit"].clone(), data["offset"].clone()), (json!(5), json!(2), json!(0)));
+    assert_eq!(data["users"][0]["status"], "legal_hold");
+    assert_eq!(data["users"][1]["status"], "active");
+    assert_eq!(
+        data["facets"],
+        json!({
+            "status": [{"value": "active", "count": 4}, {"value": "legal_hold", "count": 1}],
+            "email_domain": [
+                {"value": "example.com", "count": 2},
+                {"value": "example.org", "count": 2},
+                {"value": "mail.example.com", "count": 1}
+            ],
+            "age_bucket": [
+                {"value": "under_18", "count": 1},
+                {"value": "18_24", "count": 0},
+                {"value": "25_34", "count": 2},
+                {"value": "35_44", "count": 0},
+                {"value": "45_54", "count": 0},
+                {"value": "55_64", "count": 0},
+                {"value": "65_plus", "count": 2}
+            ]
+        })
+    );
+}
+
+#[tokio::test]
+async fn rejects_invalid_list_queries() {
+    let (status, _) = send(&app(), Method::GET, "/api/admin/users?limit=0", None).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn requires_the_admin_token() {
+    let request = Request::builder().uri("/api/admin/users").body(Body::empty()).unwrap();
+
+    let response = app().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::domain::user::model::{CreateUser, UserFacets};
+    use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+    use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+
+    use super::USERS;
+
+    fn create_users() -> Vec<CreateUser> {
+        USERS.iter().map(|(email, age)| CreateUser::new("Jane Doe".to_string(), email.to_string(), *age).unwrap()).collect()
+    }
+
+    async fn count_facets(users: &(dyn UserRepositoryPort + Send + Sync)) -> UserFacets {
+        for (i, user) in create_users().into_iter().enumerate() {
+            let user = users.create_user(user, None).await.unwrap();
+            if i == 0 {
+                users.set_legal_hold(user.id(), true).await.unwrap();
+            }
+        }
+        users.count_user_facets().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn counts_facets_like_the_in_memory_repository() {
+        let db = TestDb::new().await.unwrap();
+
+        let facets = count_facets(&UserRepository::new(db.db())).await;
+
+        assert_eq!(facets, count_facets(&InMemoryUserRepository::new()).await);
+    }
+
+    #[tokio::test]
+    async fn counts_facets_of_no_users() {
+        let db = TestDb::new().await.unwrap();
+
+        let facets = UserRepository::new(db.db()).count_user_facets().await.unwrap();
+
+        assert!(facets.by_email_domain.is_empty());
+        assert!(facets.by_status.iter().all(|c| c.count == 0));
+        assert!(facets.by_age_bucket.iter().all(|c| c.count == 0));
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::routing::get;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::auth_service::{AuthService, AuthServiceTrait};
+use rust_web_server_lib::application::flows::group_service::GroupService;
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::{all_scopes, AuthError, Authentication, AuthenticatorPort, DisabledAuthenticator, TokenPort};
+use rust_web_server_lib::domain::group::{model::{SaveGroup, UpdateMembers}, repository::GroupRepositoryPort};
+use rust_web_server_lib::domain::user::model::{CreateUser, Role, UserId};
+use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+use rust_web_server_lib::infra::auth::{jwt::JwtTokens, JwtConfig};
+use rust_web_server_lib::infra::storage::adapter::in_memory::group_repository::InMemoryGroupRepository;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::auth::{Admin, AuthState, RequireRole};
+
+fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "roles-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+fn roles(roles: &[&str]) -> Vec<String> {
+    roles.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn names_roles() {
+    for role in Role::ALL {
+        assert_eq!(Role::parse(role.as_str()), Some(role));
+    }
+    assert_eq!(Role::parse("root"), None);
+    assert_eq!(Role::default(), Role::User);
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri).header(header::CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let response = app.clone().oneshot(request.body(body.map_or_else(Body::empty, |body| Body::from(body.to_string()))).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn admins_set_the_roles_of_users() {
+    let app = router(AppState { admin_token: Some("secret".into()), ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))) });
+    let (_, body) = send(&app, Method::POST, "/api/users", None, Some(json!({"name": "Jane", "email": "jane@example.com", "age": 30}))).await;
+    let id = body["data"]["id"].as_str().unwrap().to_string();
+    let role = format!("/api/admin/users/{}/role", id);
+
+    let (status, body) = send(&app, Method::PUT, &role, Some("secret"), Some(json!({"role": "admin"}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"], json!({"id": id, "role": "admin"}));
+    let (_, body) = send(&app, Method::GET, "/api/admin/users", Some("secret"), None).await;
+    assert_eq!(body["data"]["users"][0]["role"], "admin");
+
+    assert_eq!(send(&app, Method::PUT, &role, Some("secret"), Some(json!({"role": "root"}))).await.0, StatusCode::UNPROCESSABLE_ENTITY);
+    let unknown = format!("/api/admin/users/{}/role", UserId::generate());
+    assert_eq!(send(&app, Method::PUT, &unknown, Some("secret"), Some(json!({"role": "user"}))).await.0, StatusCode::NOT_FOUND);
+}
+
+/// A route declaring that it requires the `admin` role.
+async fn admin_only(user: RequireRole<Admin>) -> String {
+    user.user_id.clone()
+}
+
+#[tokio::test]
+async fn routes_require_the_roles_they_declare() {
+    let tokens = Arc::new(jwt_tokens());
+    let auth = AuthState { auth_service: Arc::new(AuthService::new(Arc::new(DisabledAuthenticator), tokens.clone())) };
+    let app = axum::Router::new().route("/admin-only", get(admin_only)).with_state(auth);
+
+    let admin = tokens.issue("admin-1", &roles(&["admin"]), &all_scopes()).unwrap().token;
+    assert_eq!(send(&app, Method::GET, "/admin-only", Some(&admin), None).await.0, StatusCode::OK);
+
+    let user = tokens.issue("user-1", &roles(&["user"]), &all_scopes()).unwrap().token;
+    let (status, body) = send(&app, Method::GET, "/admin-only", Some(&user), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(body, json!({"status_code": 403, "data": {"code": "forbidden", "message": "The user lacks the admin role"}}));
+
+    assert_eq!(send(&app, Method::GET, "/admin-only", None, None).await.0, StatusCode::UNAUTHORIZED);
+}
+
+/// Authenticates every login as the user of the given id, granting no role.
+struct StaticAuthenticator(String);
+
+#[async_trait]
+impl AuthenticatorPort for StaticAuthenticator {
+    async fn authenticate(&self, _username: String, _password: String) -> Result<Authentication, AuthError> {
+        Ok(Authentication { user_id: self.0.clone(), roles: Vec::new() })
+    }
+}
+
+#[tokio::test]
+async fn tokens_carry_the_stored_roles_of_users() {
+    let (users, groups) = (Arc::new(InMemoryUserRepository::new()), Arc::new(InMemoryGroupRepository::new()));
+    let user = users.create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap(), None).await.unwrap();
+    let tokens = Arc::new(jwt_tokens());
+    let auth_service = AuthService::new(Arc::new(StaticAuthenticator(user.id().to_string())), tokens.clone())
+        .with_group_roles(Arc::new(GroupService::new(groups.clone(), users.clone())));
+    let login = || async { tokens.verify(&auth_service.login("jane@example.com".to_string(), "secret".to_string(), all_scopes()).await.unwrap().token).unwrap().roles };
+
+    assert_eq!(login().await, roles(&["user"]));
+    users.set_role(user.id(), Role::Admin).await.unwrap();
+    assert_eq!(login().await, roles(&["admin"]));
+
+    // Groups still deny the stored role
+    groups.save_group(SaveGroup::new("contractors".to_string(), Vec::new(), roles(&["admin"])).unwrap()).await.unwrap();
+    groups.update_members("contractors".to_string(), UpdateMembers::new(vec![user.id().to_string()], Vec::new()).unwrap()).await.unwrap();
+    assert!(login().await.is_empty());
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::domain::user::error::UserDomainError;
+    use rust_web_server_lib::domain::user::model::UpdateUser;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn stores_the_roles_of_users() {
+        let db = TestDb::new().await.unwrap();
+        let users = UserRepository::new(db.db());
+        let user = users.create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap(), None).await.unwrap();
+        assert_eq!(user.role(), Role::User);
+
+        assert_eq!(users.set_role(user.id(), Role::Admin).await.unwrap().role(), Role::Admin);
+        assert_eq!(users.get_user(user.id()).await.unwrap().role(), Role::Admin);
+        // Updates keep the role
+        let update = UpdateUser::new(user.id(), Some("Janet".to_string()), None, None).unwrap();
+        assert_eq!(users.update_user(update).await.unwrap().role(), Role::Admin);
+
+        assert_eq!(users.set_role(UserId::generate(), Role::Admin).await.unwrap_err(), UserDomainError::UserNotFound);
+    }
+}
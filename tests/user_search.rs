@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::domain::user::model::{User, UserFilter, UserId};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+/// Users with their name, email and age.
+const USERS: [(&str, &str, u8); 5] = [
+    ("José Núñez", "jose@example.com", 17),
+    ("Jane Doe", "jane@Example.org", 30),
+    ("Joe 50%_off", "joe@example.com", 34),
+    ("Ann Nunez", "ann@mail.example.com", 65),
+    ("Bob", "bob@exámple.org", 120),
+];
+
+fn filter() -> UserFilter {
+    UserFilter { name: None, email: None, min_age: None, max_age: None, limit: 20, offset: 0, after: None }
+}
+
+#[test]
+fn matches_every_criterion() {
+    let user = User::new(UserId::generate(), "José Núñez".to_string(), "jose@Example.com".parse().unwrap(), 30);
+
+    assert!(filter().matches(&user));
+    assert!(UserFilter { name: Some("NUNEZ".to_string()), email: Some("example.com".to_string()), ..filter() }.matches(&user));
+    assert!(UserFilter { min_age: Some(30), max_age: Some(30), ..filter() }.matches(&user));
+    assert!(!UserFilter { name: Some("nunez".to_string()), min_age: Some(31), ..filter() }.matches(&user));
+    assert!(!UserFilter { email: Some("example.org".to_string()), ..filter() }.matches(&user));
+}
+
+async fn app() -> axum::Router {
+    let app = router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))));
+    for (name, email, age) in USERS {
+        let request = Request::post("/api/users")
+            .header("content-type", "application/json")
+            .body(Body::from(json!({"name": name, "email": email, "age": age}).to_string()))
+            .unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::CREATED);
+    }
+    app
+}
+
+async fn search(app: &axum::Router, query: &str) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(Request::get(format!("/api/users/search?{}", query)).body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+fn names(body: &Value) -> Vec<&str> {
+    body["data"]["users"].as_array().unwrap().iter().map(|user| user["name"].as_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn searches_users_ignoring_case_and_accents() {
+    let app = app().await;
+
+    let (status, body) = search(&app, "name=NU%C3%91EZ").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(names(&body), ["Ann Nunez", "José Núñez"]);
+
+    let (_, body) = search(&app, "email=example.org").await;
+    assert_eq!(names(&body), ["Bob", "Jane Doe"]);
+}
+
+#[tokio::test]
+async fn combines_filters_and_pages_the_matches() {
+    let app = app().await;
+
+    let (_, body) = search(&app, "email=example.com&min_age=18&max_age=65").await;
+    assert_eq!(names(&body), ["Ann Nunez", "Joe 50%_off"]);
+
+    let (_, body) = search(&app, "min_age=18&limit=2&offset=1").await;
+    assert_eq!(names(&body), ["Bob", "Jane Doe"]);
+    assert_eq!((body["data"]["total"].clone(), body["data"]["limit"].clone(), body["data"]["offset"].clone()), (json!(4), json!(2), json!(1)));
+}
+
+#[tokio::test]
+async fn matches_wildcards_literally_and_ignores_blank_texts() {
+    let app = app().await;
+
+    let (_, body) = search(&app, "name=%25_").await;
+    assert_eq!(names(&body), ["Joe 50%_off"]);
+
+    let (_, body) = search(&app, "name=%20&email=").await;
+    assert_eq!(body["data"]["total"], 5);
+}
+
+#[tokio::test]
+async fn rejects_invalid_searches() {
+    let app = app().await;
+
+    assert_eq!(search(&app, "min_age=40&max_age=30").await.0, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(search(&app, "limit=101").await.0, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(search(&app, "min_age=-1").await.0, StatusCode::BAD_REQUEST);
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::domain::user::model::{CreateUser, UserFilter};
+    use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+    use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+
+    use super::{filter, USERS};
+
+    /// Searches the users with every filter, returning the names and totals of the pages.
+    async fn search_all(users: &(dyn UserRepositoryPort + Send + Sync)) -> Vec<(Vec<String>, u64)> {
+        for (name, email, age) in USERS {
+            users.create_user(CreateUser::new(name.to_string(), email.to_string(), age).unwrap(), None).await.unwrap();
+        }
+
+        let filters = [
+            filter(),
+            UserFilter { name: Some("NUÑEZ".to_string()), ..filter() },
+            UserFilter { name: Some("%_".to_string()), ..filter() },
+            UserFilter { email: Some("EXAMPLE.ORG".to_string()), ..filter() },
+            UserFilter { email: Some("example.com".to_string()), min_age: Some(18), max_age: Some(65), ..filter() },
+            UserFilter { min_age: Some(18), limit: 2, offset: 1, ..filter() },
+        ];
+        let mut pages = Vec::new();
+        for filter in filters {
+            let page = users.search_users(filter).await.unwrap();
+            pages.push((page.users.iter().map(|user| user.name().to_string()).collect(), page.total));
+        }
+        pages
+    }
+
+    #[tokio::test]
+    async fn searches_users_like_the_in_memory_repository() {
+        let db = TestDb::new().await.unwrap();
+
+        let pages = search_all(&UserRepository::new(db.db())).await;
+
+        assert_eq!(pages, search_all(&InMemoryUserRepository::new()).await);
+        assert_eq!(pages[1].0, ["Ann Nunez", "José Núñez"]);
+    }
+}
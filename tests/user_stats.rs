@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::stats::{group_stats, DailyUserStats, StatsPeriod, UserStats, UserStatsPort};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::stats_handlers::StatsState;
+use rust_web_server_lib::presentation::http::{router, AppState};
+
+fn date(date: &str) -> NaiveDate {
+    date.parse().unwrap()
+}
+
+fn day(day: &str, signups: u64, deletions: u64, active_users: u64) -> DailyUserStats {
+    DailyUserStats { day: date(day), signups, deletions, active_users }
+}
+
+/// Statistics of Sunday 2024-03-31 to Tuesday 2024-04-02.
+fn days() -> Vec<DailyUserStats> {
+    vec![day("2024-03-31", 3, 0, 10), day("2024-04-01", 2, 1, 11), day("2024-04-02", 4, 2, 13)]
+}
+
+#[test]
+fn starts_periods_on_mondays_and_first_days_of_months() {
+    assert_eq!(StatsPeriod::Day.start(date("2024-04-03")), date("2024-04-03"));
+    assert_eq!(StatsPeriod::Week.start(date("2024-04-03")), date("2024-04-01"));
+    assert_eq!(StatsPeriod::Week.start(date("2024-03-31")), date("2024-03-25"));
+    assert_eq!(StatsPeriod::Month.start(date("2024-04-03")), date("2024-04-01"));
+}
+
+#[test]
+fn groups_days_summing_changes_and_keeping_the_last_active_users() {
+    assert_eq!(group_stats(&days(), StatsPeriod::Day).len(), 3);
+    assert_eq!(
+        group_stats(&days(), StatsPeriod::Week),
+        vec![
+            UserStats { period: date("2024-03-25"), signups: 3, deletions: 0, active_users: 10 },
+            UserStats { period: date("2024-04-01"), signups: 6, deletions: 3, active_users: 13 },
+        ]
+    );
+    assert_eq!(group_stats(&[], StatsPeriod::Month), Vec::new());
+}
+
+/// Statistics stored beforehand, as projected.
+struct StoredStats(Vec<DailyUserStats>);
+
+#[async_trait]
+impl UserStatsPort for StoredStats {
+    async fn project(&self, _today: NaiveDate) -> eyre::Result<u64> {
+        Ok(0)
+    }
+
+    async fn daily_stats(&self, from: NaiveDate, to: NaiveDate) -> eyre::Result<Vec<DailyUserStats>> {
+        Ok(self.0.iter().filter(|stats| (from..=to).contains(&stats.day)).copied().collect())
+    }
+}
+
+fn app() -> axum::Router {
+    router(AppState {
+        admin_token: Some("secret".into()),
+        stats: StatsState { stats: Arc::new(StoredStats(days())) },
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+async fn get(app: &axum::Router, uri: &str) -> (StatusCode, Option<String>, String) {
+    let request = Request::builder().uri(uri).header("authorization", "Bearer secret").body(Body::empty()).unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let content_type = response.headers().get(header::CONTENT_TYPE).map(|value| value.to_str().unwrap().to_string());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    (status, content_type, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn reports_the_stats_of_the_days_of_the_range() {
+    let (status, _, body) = get(&app(), "/api/admin/stats?from=2024-04-01&to=2024-04-30").await;
+
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(
+        body["data"],
+        json!({
+            "from": "2024-04-01",
+            "to": "2024-04-30",
+            "group_by": "day",
+            "periods": [
+                { "period": "2024-04-01", "signups": 2, "deletions": 1, "active_users": 11 },
+                { "period": "2024-04-02", "signups": 4, "deletions": 2, "active_users": 13 },
+            ],
+        })
+    );
+}
+
+#[tokio::test]
+async fn groups_the_stats_by_period() {
+    let (status, _, body) = get(&app(), "/api/admin/stats?from=2024-03-01&to=2024-04-30&group_by=month").await;
+
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["data"]["group_by"], "month");
+    assert_eq!(
+        body["data"]["periods"],
+        json!([
+            { "period": "2024-03-01", "signups": 3, "deletions": 0, "active_users": 10 },
+            { "period": "2024-04-01", "signups": 6, "deletions": 3, "active_users": 13 },
+        ])
+    );
+}
+
+#[tokio::test]
+async fn exports_the_stats_as_csv() {
+    let (status, content_type, body) = get(&app(), "/api/admin/stats?from=2024-03-01&to=2024-04-30&group_by=week&format=csv").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("text/csv; charset=utf-8"));
+    assert_eq!(body, "period,signups,deletions,active_users\r\n2024-03-25,3,0,10\r\n2024-04-01,6,3,13\r\n");
+}
+
+#[tokio::test]
+async fn rejects_invalid_ranges_and_options() {
+    let app = app();
+
+    let (status, _, _) = get(&app, "/api/admin/stats?from=2024-04-02&to=2024-04-01").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    for query in ["from=yesterday", "group_by=year", "format=xml"] {
+        let (status, _, _) = get(&app, &format!("/api/admin/stats?{}", query)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
+
+#[tokio::test]
+async fn reports_no_stats_when_disabled() {
+    let app = router(AppState {
+        admin_token: Some("secret".into()),
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    });
+
+    let (status, _, body) = get(&app, "/api/admin/stats").await;
+
+    assert_eq!(status, StatusCode::OK);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["data"]["periods"], json!([]));
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::application::ports::stats::UserStatsPort;
+    use rust_web_server_lib::infra::storage::adapter::postgres::stats::PostgresUserStats;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::Db;
+    use uuid::Uuid;
+
+    use super::{date, day};
+
+    /// Inserts a user created and, if `deleted_at` is set, deleted at the given times (UTC).
+    async fn insert_user(db: &Db, created_at: &str, deleted_at: Option<&str>) {
+        sqlx::query("INSERT INTO users (id, name, email, age, created_at, deleted_at) VALUES ($1, 'Jane Doe', $2, 30, $3::timestamptz, $4::timestamptz)")
+            .bind(Uuid::new_v4().to_string())
+            .bind(format!("{}@example.com", Uuid::new_v4().simple()))
+            .bind(format!("{}Z", created_at))
+            .bind(deleted_at.map(|deleted_at| format!("{}Z", deleted_at)))
+            .execute(&**db)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn projects_the_days_from_the_first_signup() {
+        let db = TestDb::new().await.unwrap();
+        insert_user(&db.db(), "2024-04-01 23:59:59", None).await;
+        insert_user(&db.db(), "2024-04-01 08:00:00", Some("2024-04-03 00:00:00")).await;
+        insert_user(&db.db(), "2024-04-02 00:00:00", Some("2024-04-02 12:00:00")).await;
+        let stats = PostgresUserStats::new(db.db());
+
+        assert_eq!(stats.project(date("2024-04-03")).await.unwrap(), 3);
+
+        assert_eq!(
+            stats.daily_stats(date("2024-03-01"), date("2024-04-30")).await.unwrap(),
+            vec![day("2024-04-01", 2, 0, 2), day("2024-04-02", 1, 1, 2), day("2024-04-03", 0, 1, 1)]
+        );
+        assert_eq!(stats.daily_stats(date("2024-04-02"), date("2024-04-02")).await.unwrap(), vec![day("2024-04-02", 1, 1, 2)]);
+    }
+
+    #[tokio::test]
+    async fn projects_again_from_the_last_day_stored_only() {
+        let db = TestDb::new().await.unwrap();
+        insert_user(&db.db(), "2024-04-01 10:00:00", None).await;
+        let stats = PostgresUserStats::new(db.db());
+        stats.project(date("2024-04-02")).await.unwrap();
+
+        // Users deleted for good leave the days stored already as they were
+        sqlx::query("DELETE FROM users").execute(&*db.db()).await.unwrap();
+        insert_user(&db.db(), "2024-04-02 10:00:00", None).await;
+        insert_user(&db.db(), "2024-04-03 10:00:00", None).await;
+
+        assert_eq!(stats.project(date("2024-04-03")).await.unwrap(), 2);
+        assert_eq!(
+            stats.daily_stats(date("2024-04-01"), date("2024-04-03")).await.unwrap(),
+            vec![day("2024-04-01", 1, 0, 1), day("2024-04-02", 1, 0, 1), day("2024-04-03", 1, 0, 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn projects_the_current_day_without_users() {
+        let db = TestDb::new().await.unwrap();
+        let stats = PostgresUserStats::new(db.db());
+
+        assert_eq!(stats.project(date("2024-04-03")).await.unwrap(), 1);
+
+        assert_eq!(stats.daily_stats(date("2024-04-01"), date("2024-04-03")).await.unwrap(), vec![day("2024-04-03", 0, 0, 0)]);
+    }
+}
@@ -0,0 +1,75 @@
+use rust_web_server_lib::domain::user::model::{CreateUser, Email, UpdateUser, UserId};
+use rust_web_server_lib::domain::user::validation::{validate_age, validate_email, validate_name, MAX_NAME_LENGTH};
+
+#[test]
+fn accepts_well_formed_emails() {
+    for email in ["jane@example.com", "jane.doe+tag@mail.example.co.uk", "josé@exämple.de", "a@b-c.io"] {
+        assert_eq!(validate_email(email), Ok(()), "{}", email);
+    }
+}
+
+#[test]
+fn rejects_malformed_emails() {
+    for email in ["", "jane", "jane@", "@example.com", "jane@example", "jane@@example.com", "ja ne@example.com", "jane@example..com", "jane@-example.com", "jane@example.com "] {
+        assert!(validate_email(email).is_err(), "{}", email);
+    }
+}
+
+#[test]
+fn rejects_blank_and_overlong_names_and_out_of_range_ages() {
+    assert!(validate_name("").is_err());
+    assert!(validate_name("   ").is_err());
+    assert!(validate_name(&"a".repeat(MAX_NAME_LENGTH + 1)).is_err());
+    assert_eq!(validate_name(&"é".repeat(MAX_NAME_LENGTH)), Ok(()));
+
+    assert!(validate_age(0).is_err());
+    assert!(validate_age(151).is_err());
+    assert_eq!(validate_age(1), Ok(()));
+}
+
+#[test]
+fn constructors_report_every_invalid_field() {
+    let errors = CreateUser::new(String::new(), "jane".to_string(), 0).err().unwrap();
+    let fields: Vec<&str> = errors.errors().iter().map(|error| error.field).collect();
+    assert_eq!(fields, ["name", "email", "age"]);
+
+    assert!(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).is_ok());
+    assert!(UpdateUser::new(UserId::generate(), None, None, None).is_ok());
+
+    let errors = UpdateUser::new(UserId::generate(), None, Some("jane".to_string()), None).err().unwrap();
+    assert_eq!(errors.errors()[0].field, "email");
+}
+
+#[test]
+fn user_ids_are_uuids() {
+    let id = UserId::parse("6F1C2B4E-8F7A-4C1D-9A3E-2B5D7C9E1F0A").unwrap();
+    assert_eq!(id.to_string(), "6f1c2b4e-8f7a-4c1d-9a3e-2b5d7c9e1f0a");
+    assert_eq!("6f1c2b4e-8f7a-4c1d-9a3e-2b5d7c9e1f0a".parse::<UserId>(), Ok(id));
+
+    let errors = UserId::parse("user-1").err().unwrap();
+    assert_eq!(errors.errors()[0].field, "id");
+    assert_ne!(UserId::generate(), UserId::generate());
+}
+
+#[test]
+fn emails_are_checked_on_construction() {
+    let email = Email::parse("Jane@Example.com").unwrap();
+    assert_eq!(email.as_str(), "Jane@Example.com");
+
+    let errors = Email::parse("jane@").err().unwrap();
+    assert_eq!(errors.errors()[0].field, "email");
+    assert!("jane".parse::<Email>().is_err());
+}
+
+#[test]
+fn value_objects_serialize_as_strings() {
+    let id = UserId::parse("6f1c2b4e-8f7a-4c1d-9a3e-2b5d7c9e1f0a").unwrap();
+    assert_eq!(serde_json::to_value(id).unwrap(), "6f1c2b4e-8f7a-4c1d-9a3e-2b5d7c9e1f0a");
+    assert_eq!(serde_json::from_value::<UserId>("6f1c2b4e-8f7a-4c1d-9a3e-2b5d7c9e1f0a".into()).unwrap(), id);
+    assert!(serde_json::from_value::<UserId>("user-1".into()).is_err());
+
+    let email = Email::parse("jane@example.com").unwrap();
+    assert_eq!(serde_json::to_value(&email).unwrap(), "jane@example.com");
+    assert_eq!(serde_json::from_value::<Email>("jane@example.com".into()).unwrap(), email);
+    assert!(serde_json::from_value::<Email>("jane".into()).is_err());
+}
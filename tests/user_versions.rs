@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::auth_service::AuthService;
+use rust_web_server_lib::application::flows::user_service::{UserService, UserServiceTrait};
+use rust_web_server_lib::application::ports::auth::{all_scopes, DisabledAuthenticator, TokenPort};
+use rust_web_server_lib::domain::user::error::UserDomainError;
+use rust_web_server_lib::domain::user::model::{CreateUser, UpdateUser, User};
+use rust_web_server_lib::infra::auth::{jwt::JwtTokens, JwtConfig};
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::auth::AuthState;
+
+fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "versions-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+fn app() -> axum::Router {
+    router(AppState {
+        auth: AuthState { auth_service: Arc::new(AuthService::new(Arc::new(DisabledAuthenticator), Arc::new(jwt_tokens()))) },
+        ..AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new())))
+    })
+}
+
+/// Sends `request`, returning the status, the headers and the body of the response.
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, HeaderMap, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, headers, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+/// Creates a user, returning its id and the `ETag` it was created with.
+async fn create_user(app: &axum::Router) -> (String, String) {
+    let user = json!({ "name": "Jane", "email": "jane@example.com", "age": 30 });
+    let request = Request::post("/api/users").header(header::CONTENT_TYPE, "application/json").body(Body::from(user.to_string())).unwrap();
+    let (status, headers, body) = send(app, request).await;
+    assert_eq!(status, StatusCode::CREATED);
+    (body["data"]["id"].as_str().unwrap().to_string(), headers[header::ETAG].to_str().unwrap().to_string())
+}
+
+async fn get_user(app: &axum::Router, id: &str) -> (StatusCode, HeaderMap, Value) {
+    send(app, Request::get(format!("/api/users/{}", id)).body(Body::empty()).unwrap()).await
+}
+
+/// Renames the user `id` to `name`, with the `if_match` header if any.
+async fn rename_user(app: &axum::Router, id: &str, if_match: Option<&str>, name: &str) -> (StatusCode, HeaderMap, Value) {
+    let token = jwt_tokens().issue("user-1", &[], &all_scopes()).unwrap().token;
+    let mut request = Request::builder()
+        .method(Method::PUT)
+        .uri(format!("/api/users/{}", id))
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::AUTHORIZATION, format!("Bearer {}", token));
+    if let Some(if_match) = if_match {
+        request = request.header(header::IF_MATCH, if_match);
+    }
+    send(app, request.body(Body::from(json!({ "name": name }).to_string())).unwrap()).await
+}
+
+#[tokio::test]
+async fn returns_the_version_of_users_as_their_etag() {
+    let app = app();
+    let (id, etag) = create_user(&app).await;
+    assert_eq!(etag, "\"1\"");
+    assert_eq!(get_user(&app, &id).await.1[header::ETAG], "\"1\"");
+
+    let (status, headers, _) = rename_user(&app, &id, Some(&etag), "Janet").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::ETAG], "\"2\"");
+    assert_eq!(get_user(&app, &id).await.1[header::ETAG], "\"2\"");
+}
+
+#[tokio::test]
+async fn rejects_updates_of_users_changed_since_they_were_read() {
+    let app = app();
+    let (id, etag) = create_user(&app).await;
+    rename_user(&app, &id, Some(&etag), "Janet").await;
+
+    let (status, _, body) = rename_user(&app, &id, Some(&etag), "Joan").await;
+
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(body["data"]["message"], "User was changed since it was read");
+    assert_eq!(get_user(&app, &id).await.2["data"]["name"], "Janet");
+}
+
+#[tokio::test]
+async fn requires_if_match_on_updates() {
+    let app = app();
+    let (id, _) = create_user(&app).await;
+
+    let (status, _, body) = rename_user(&app, &id, None, "Janet").await;
+    assert_eq!(status, StatusCode::PRECONDITION_REQUIRED);
+    assert_eq!(body["data"]["message"], "If-Match header with the ETag of the User is required");
+
+    // Weak and malformed entity tags match no version
+    assert_eq!(rename_user(&app, &id, Some("W/\"1\""), "Janet").await.0, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(rename_user(&app, &id, Some("1"), "Janet").await.0, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(get_user(&app, &id).await.2["data"]["name"], "Jane");
+
+    // `*` matches any version
+    let (status, headers, _) = rename_user(&app, &id, Some("*"), "Janet").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers[header::ETAG], "\"2\"");
+}
+
+#[tokio::test]
+async fn lets_a_single_one_of_concurrent_updates_apply() {
+    let service = UserService::new(InMemoryUserRepository::new());
+    let user = service.create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap()).await.unwrap();
+    let rename = |name: &str| UpdateUser::new(user.id(), Some(name.to_string()), None, None).unwrap().with_expected_version(user.version());
+
+    let (first, second) = tokio::join!(service.update_user(rename("Janet")), service.update_user(rename("Joan")));
+
+    let (updated, conflicting): (Vec<_>, Vec<_>) = [first, second].into_iter().partition(Result::is_ok);
+    assert_eq!(updated.into_iter().map(|user| user.unwrap().version()).collect::<Vec<_>>(), [User::INITIAL_VERSION + 1]);
+    assert_eq!(conflicting, [Err(UserDomainError::UserVersionMismatch)]);
+}
+
+/// Runs against the database of `TEST_DATABASE_URL`, see `TestDb`.
+#[cfg(feature = "testing")]
+mod postgres {
+    use rust_web_server_lib::domain::user::model::UserId;
+    use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+    use rust_web_server_lib::infra::storage::adapter::postgres::test_db::TestDb;
+    use rust_web_server_lib::infra::storage::adapter::postgres::user_repository::UserRepository;
+
+    use super::*;
+
+    fn jane() -> CreateUser {
+        CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap()
+    }
+
+    #[tokio::test]
+    async fn updates_users_at_the_expected_version_only() {
+        let db = TestDb::new().await.unwrap();
+        let users = UserRepository::new(db.db());
+        let user = users.create_user(jane(), None).await.unwrap();
+        assert_eq!(users.get_user(user.id()).await.unwrap().version(), User::INITIAL_VERSION);
+        let rename = |version| UpdateUser::new(user.id(), Some("Janet".to_string()), None, None).unwrap().with_expected_version(version);
+
+        let updated = users.update_user(rename(User::INITIAL_VERSION)).await.unwrap();
+        assert_eq!(updated.version(), User::INITIAL_VERSION + 1);
+        assert_eq!(users.get_user(user.id()).await.unwrap(), updated);
+        assert_eq!(users.update_user(rename(User::INITIAL_VERSION)).await.unwrap_err(), UserDomainError::UserVersionMismatch);
+
+        // Every change moves the version on
+        assert_eq!(users.set_legal_hold(user.id(), true).await.unwrap().version(), updated.version() + 1);
+        assert_eq!(users.set_role(user.id(), updated.role()).await.unwrap().version(), updated.version() + 2);
+        assert_eq!(users.update_user(rename(updated.version())).await.unwrap_err(), UserDomainError::UserVersionMismatch);
+        // Updates without an expected version apply whatever the version
+        let update = UpdateUser::new(user.id(), None, None, Some(31)).unwrap();
+        assert_eq!(users.update_user(update).await.unwrap().version(), updated.version() + 3);
+
+        let unknown = UpdateUser::new(UserId::generate(), None, None, Some(31)).unwrap().with_expected_version(User::INITIAL_VERSION);
+        assert_eq!(users.update_user(unknown).await.unwrap_err(), UserDomainError::UserNotFound);
+    }
+
+    #[tokio::test]
+    async fn lets_a_single_one_of_concurrent_updates_apply() {
+        let db = TestDb::new().await.unwrap();
+        let users = UserRepository::new(db.db());
+        let user = users.create_user(jane(), None).await.unwrap();
+        let rename = |name: &str| UpdateUser::new(user.id(), Some(name.to_string()), None, None).unwrap().with_expected_version(user.version());
+
+        let (first, second) = tokio::join!(users.update_user(rename("Janet")), users.update_user(rename("Joan")));
+
+        let (updated, conflicting): (Vec<_>, Vec<_>) = [first, second].into_iter().partition(Result::is_ok);
+        assert_eq!(updated.into_iter().map(|user| user.unwrap().version()).collect::<Vec<_>>(), [User::INITIAL_VERSION + 1]);
+        assert_eq!(conflicting, [Err(UserDomainError::UserVersionMismatch)]);
+    }
+}
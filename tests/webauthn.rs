@@ -0,0 +1,407 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use rust_web_server_lib::application::flows::auth_service::AuthService;
+use rust_web_server_lib::application::flows::passkey_service::PasskeyService;
+use rust_web_server_lib::application::flows::user_service::UserService;
+use rust_web_server_lib::application::ports::auth::{all_scopes, AuthError, Authentication, AuthenticatorPort, TokenPort};
+use rust_web_server_lib::application::ports::webauthn::{NewCredential, PasskeyAssertion, PasskeyChallenge, PasskeyError, WebAuthnPort};
+use rust_web_server_lib::domain::passkey::model::Passkey;
+use rust_web_server_lib::domain::user::model::{CreateUser, User, UserId};
+use rust_web_server_lib::domain::user::repository::UserRepositoryPort;
+use rust_web_server_lib::infra::auth::jwt::JwtTokens;
+use rust_web_server_lib::infra::auth::JwtConfig;
+use rust_web_server_lib::infra::storage::adapter::in_memory::passkey_repository::InMemoryPasskeyRepository;
+use rust_web_server_lib::infra::storage::adapter::in_memory::user_repository::InMemoryUserRepository;
+use rust_web_server_lib::presentation::handlers::webauthn_handlers::WebAuthnState;
+use rust_web_server_lib::presentation::http::{router, AppState};
+use rust_web_server_lib::presentation::middleware::auth::AuthState;
+
+/// Relying party running a single ceremony at a time, accepting the response `{"id": ...}` as
+/// creating or asserting the passkey with that id.
+#[derive(Default)]
+struct StaticRelyingParty {
+    /// The started ceremony: its id, and the user it was started for.
+    ceremony: Mutex<Option<(&'static str, UserId)>>,
+}
+
+impl StaticRelyingParty {
+    fn take(&self, ceremony_id: &str) -> Result<UserId, PasskeyError> {
+        match self.ceremony.lock().unwrap().take() {
+            Some((id, user_id)) if id == ceremony_id => Ok(user_id),
+            _ => Err(PasskeyError::UnknownCeremony),
+        }
+    }
+}
+
+impl WebAuthnPort for StaticRelyingParty {
+    fn start_registration(&self, user: &User, registered: &[Passkey]) -> Result<PasskeyChallenge, PasskeyError> {
+        *self.ceremony.lock().unwrap() = Some(("registration", user.id()));
+        let exclude: Vec<&str> = registered.iter().map(|passkey| passkey.id.as_str()).collect();
+        Ok(PasskeyChallenge {
+            ceremony_id: "registration".to_string(),
+            options: json!({ "publicKey": { "excludeCredentials": exclude } }),
+        })
+    }
+
+    fn finish_registration(&self, user_id: UserId, ceremony_id: &str, response: Value) -> Result<NewCredential, PasskeyError> {
+        if self.take(ceremony_id)? != user_id {
+            return Err(PasskeyError::UnknownCeremony);
+        }
+        let id = response["id"].as_str().ok_or(PasskeyError::InvalidResponse)?;
+        Ok(NewCredential {
+            id: id.to_string(),
+            credential: format!("credential-{}", id),
+        })
+    }
+
+    fn start_authentication(&self, user_id: UserId, _registered: &[Passkey]) -> Result<PasskeyChallenge, PasskeyError> {
+        *self.ceremony.lock().unwrap() = Some(("authentication", user_id));
+        Ok(PasskeyChallenge {
+            ceremony_id: "authentication".to_string(),
+            options: json!({ "publicKey": {} }),
+        })
+    }
+
+    fn finish_authentication(&self, ceremony_id: &str, response: Value) -> Result<PasskeyAssertion, PasskeyError> {
+        let user_id = self.take(ceremony_id)?;
+        let id = response["id"].as_str().ok_or(PasskeyError::InvalidResponse)?;
+        Ok(PasskeyAssertion {
+            user_id,
+            id: id.to_string(),
+            credential: format!("credential-{}", id),
+        })
+    }
+}
+
+/// Authenticator accepting the password `secret` of any user, the username being its id.
+struct StaticAuthenticator;
+
+#[async_trait]
+impl AuthenticatorPort for StaticAuthenticator {
+    async fn authenticate(&self, username: String, password: String) -> Result<Authentication, AuthError> {
+        match password.as_str() {
+            "secret" => Ok(Authentication { user_id: username, roles: Vec::new() }),
+            _ => Err(AuthError::InvalidCredentials),
+        }
+    }
+}
+
+fn jwt_tokens() -> JwtTokens {
+    JwtTokens::new(&JwtConfig { secret: "webauthn-test-secret".to_string().into(), expiry_secs: 3600 })
+}
+
+struct TestApp {
+    router: axum::Router,
+    jane_id: String,
+    /// Bearer access token of `jane@example.com`.
+    jane_token: String,
+}
+
+/// Returns the router with the WebAuthn routes served by `relying_party`, and the local user
+/// `jane@example.com`. Password logins of users with a passkey are refused unless `password_fallback`.
+async fn app(relying_party: Arc<dyn WebAuthnPort + Send + Sync>, password_fallback: bool) -> TestApp {
+    let users = Arc::new(InMemoryUserRepository::new());
+    let jane = users
+        .create_user(CreateUser::new("Jane".to_string(), "jane@example.com".to_string(), 30).unwrap(), None)
+        .await
+        .unwrap();
+    let passkeys = Arc::new(InMemoryPasskeyRepository::new());
+
+    let mut auth_service = AuthService::new(Arc::new(StaticAuthenticator), Arc::new(jwt_tokens()));
+    if !password_fallback {
+        auth_service = auth_service.without_password_fallback(passkeys.clone());
+    }
+    let webauthn = WebAuthnState {
+        passkey_service: Arc::new(PasskeyService::new(relying_party, passkeys, users.clone(), Arc::new(jwt_tokens()))),
+    };
+    let router = router(AppState {
+        auth: AuthState {
+            auth_service: Arc::new(auth_service),
+        },
+        webauthn: Some(webauthn),
+        ..AppState::new(Arc::new(UserService::new(users)))
+    });
+
+    TestApp {
+        router,
+        jane_id: jane.id().to_string(),
+        jane_token: jwt_tokens().issue(&jane.id().to_string(), &[], &all_scopes()).unwrap().token,
+    }
+}
+
+async fn send(app: &axum::Router, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+    let mut request = Request::builder().method(method).uri(uri);
+    if let Some(token) = token {
+        request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let request = match body {
+        Some(body) => request.header(header::CONTENT_TYPE, "application/json").body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    };
+
+    let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+/// Registers the passkey `id` of Jane through the HTTP API.
+async fn register(app: &TestApp, id: &str) -> (StatusCode, Value) {
+    let (status, started) = send(&app.router, Method::POST, "/api/auth/webauthn/register/start", Some(&app.jane_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let body = json!({
+        "ceremony_id": started["data"]["ceremony_id"],
+        "name": "Laptop",
+        "credential": { "id": id },
+    });
+    send(&app.router, Method::POST, "/api/auth/webauthn/register/finish", Some(&app.jane_token), Some(body)).await
+}
+
+/// Logs Jane in with the passkey `id` through the HTTP API.
+async fn login(app: &TestApp, id: &str) -> (StatusCode, Value) {
+    let (status, started) = send(&app.router, Method::POST, "/api/auth/webauthn/login/start", None, Some(json!({ "email": "jane@example.com" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let body = json!({
+        "ceremony_id": started["data"]["ceremony_id"],
+        "credential": { "id": id },
+    });
+    send(&app.router, Method::POST, "/api/auth/webauthn/login/finish", None, Some(body)).await
+}
+
+#[tokio::test]
+async fn registers_and_lists_passkeys() {
+    let app = app(Arc::new(StaticRelyingParty::default()), true).await;
+
+    let (status, body) = register(&app, "passkey-1").await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["id"], "passkey-1");
+    assert_eq!(body["data"]["name"], "Laptop");
+    assert_eq!(body["data"]["last_used_at"], Value::Null);
+
+    let (status, body) = send(&app.router, Method::GET, "/api/auth/webauthn/credentials", Some(&app.jane_token), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let passkeys = body["data"].as_array().unwrap();
+    assert_eq!(passkeys.len(), 1);
+    assert!(passkeys[0].get("credential").is_none(), "{}", body);
+}
+
+#[tokio::test]
+async fn excludes_registered_passkeys_from_registration() {
+    let app = app(Arc::new(StaticRelyingParty::default()), true).await;
+    register(&app, "passkey-1").await;
+
+    let (_, body) = send(&app.router, Method::POST, "/api/auth/webauthn/register/start", Some(&app.jane_token), None).await;
+
+    assert_eq!(body["data"]["options"]["publicKey"]["excludeCredentials"], json!(["passkey-1"]));
+}
+
+#[tokio::test]
+async fn rejects_passkey_registered_twice() {
+    let app = app(Arc::new(StaticRelyingParty::default()), true).await;
+    register(&app, "passkey-1").await;
+
+    let (status, _) = register(&app, "passkey-1").await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn rejects_invalid_passkey_names() {
+    let app = app(Arc::new(StaticRelyingParty::default()), true).await;
+
+    for name in ["", "  ", &"a".repeat(65)] {
+        let body = json!({ "ceremony_id": "registration", "name": name, "credential": { "id": "passkey-1" } });
+        let (status, _) = send(&app.router, Method::POST, "/api/auth/webauthn/register/finish", Some(&app.jane_token), Some(body)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "{:?}", name);
+    }
+}
+
+#[tokio::test]
+async fn registration_requires_authentication() {
+    let app = app(Arc::new(StaticRelyingParty::default()), true).await;
+
+    let (status, _) = send(&app.router, Method::POST, "/api/auth/webauthn/register/start", None, None).await;
+
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn logs_in_with_passkey() {
+    let app = app(Arc::new(StaticRelyingParty::default()), true).await;
+    register(&app, "passkey-1").await;
+
+    let (status, body) = login(&app, "passkey-1").await;
+
+    assert_eq!(status, StatusCode::OK);
+    let principal = jwt_tokens().verify(body["data"]["access_token"].as_str().unwrap()).unwrap();
+    assert_eq!(principal.user_id, app.jane_id);
+    let (_, body) = send(&app.router, Method::GET, "/api/auth/webauthn/credentials", Some(&app.jane_token), None).await;
+    assert_ne!(body["data"][0]["last_used_at"], Value::Null);
+}
+
+#[tokio::test]
+async fn rejects_login_of_user_without_passkey() {
+    let app = app(Arc::new(StaticRelyingParty::default()), true).await;
+
+    for email in ["jane@example.com", "john@example.com"] {
+        let (status, _) = send(&app.router, Method::POST, "/api/auth/webauthn/login/start", None, Some(json!({ "email": email }))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", email);
+    }
+}
+
+#[tokio::test]
+async fn rejects_unknown_ceremony() {
+    let app = app(Arc::new(StaticRelyingParty::default()), true).await;
+    register(&app, "passkey-1").await;
+
+    let body = json!({ "ceremony_id": "authentication", "credential": { "id": "passkey-1" } });
+    let (status, _) = send(&app.router, Method::POST, "/api/auth/webauthn/login/finish", None, Some(body)).await;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn deleted_passkeys_no_longer_log_in() {
+    let app = app(Arc::new(StaticRelyingParty::default()), true).await;
+    register(&app, "passkey-1").await;
+    register(&app, "passkey-2").await;
+
+    let (status, _) = send(&app.router, Method::DELETE, "/api/auth/webauthn/credentials/passkey-1", Some(&app.jane_token), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    assert_eq!(login(&app, "passkey-1").await.0, StatusCode::UNAUTHORIZED);
+    assert_eq!(login(&app, "passkey-2").await.0, StatusCode::OK);
+    let (status, _) = send(&app.router, Method::DELETE, "/api/auth/webauthn/credentials/passkey-1", Some(&app.jane_token), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn password_logins_are_refused_once_a_passkey_is_registered_without_fallback() {
+    let app = app(Arc::new(StaticRelyingParty::default()), false).await;
+    let password_login = json!({ "email": app.jane_id, "password": "secret" });
+
+    let (status, _) = send(&app.router, Method::POST, "/api/auth/login", None, Some(password_login.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+
+    register(&app, "passkey-1").await;
+    let (status, _) = send(&app.router, Method::POST, "/api/auth/login", None, Some(password_login)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn password_logins_remain_accepted_with_fallback() {
+    let app = app(Arc::new(StaticRelyingParty::default()), true).await;
+    register(&app, "passkey-1").await;
+
+    let password_login = json!({ "email": app.jane_id, "password": "secret" });
+    let (status, _) = send(&app.router, Method::POST, "/api/auth/login", None, Some(password_login)).await;
+
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn webauthn_routes_are_not_mounted_by_default() {
+    let app = router(AppState::new(Arc::new(UserService::new(InMemoryUserRepository::new()))));
+
+    let (status, _) = send(&app, Method::POST, "/api/auth/webauthn/login/start", None, Some(json!({ "email": "jane@example.com" }))).await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+/// Ceremonies run against the `webauthn-rs` relying party with a software passkey.
+#[cfg(feature = "webauthn")]
+mod relying_party {
+    use std::sync::Arc;
+
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+    use webauthn_authenticator_rs::prelude::{CreationChallengeResponse, RequestChallengeResponse, Url};
+    use webauthn_authenticator_rs::softpasskey::SoftPasskey;
+    use webauthn_authenticator_rs::WebauthnAuthenticator;
+
+    use rust_web_server_lib::application::ports::auth::TokenPort;
+    use rust_web_server_lib::infra::auth::webauthn::WebAuthnRelyingParty;
+    use rust_web_server_lib::infra::auth::{PasswordFallback, WebAuthnConfig};
+
+    use super::{app, jwt_tokens, send, TestApp};
+
+    const ORIGIN: &str = "https://app.example.com";
+
+    async fn relying_party_app() -> TestApp {
+        let config = WebAuthnConfig {
+            rp_id: "example.com".to_string(),
+            rp_origin: ORIGIN.to_string(),
+            rp_name: "Example".to_string(),
+            ceremony_timeout_secs: 60,
+            password_fallback: PasswordFallback::Allowed,
+        };
+        app(Arc::new(WebAuthnRelyingParty::new(&config).unwrap()), true).await
+    }
+
+    async fn register(app: &TestApp, authenticator: &mut WebauthnAuthenticator<SoftPasskey>) -> (StatusCode, Value) {
+        let (_, started) = send(&app.router, Method::POST, "/api/auth/webauthn/register/start", Some(&app.jane_token), None).await;
+        let options: CreationChallengeResponse = serde_json::from_value(started["data"]["options"].clone()).unwrap();
+        let credential = authenticator.do_registration(Url::parse(ORIGIN).unwrap(), options).unwrap();
+
+        let body = json!({ "ceremony_id": started["data"]["ceremony_id"], "name": "Soft passkey", "credential": credential });
+        send(&app.router, Method::POST, "/api/auth/webauthn/register/finish", Some(&app.jane_token), Some(body)).await
+    }
+
+    #[tokio::test]
+    async fn registers_and_logs_in_with_passkey() {
+        let app = relying_party_app().await;
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+
+        let (status, registered) = register(&app, &mut authenticator).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", registered);
+
+        let (_, started) = send(&app.router, Method::POST, "/api/auth/webauthn/login/start", None, Some(json!({ "email": "jane@example.com" }))).await;
+        let options: RequestChallengeResponse = serde_json::from_value(started["data"]["options"].clone()).unwrap();
+        let assertion = authenticator.do_authentication(Url::parse(ORIGIN).unwrap(), options).unwrap();
+        let body = json!({ "ceremony_id": started["data"]["ceremony_id"], "credential": assertion });
+        let (status, logged_in) = send(&app.router, Method::POST, "/api/auth/webauthn/login/finish", None, Some(body.clone())).await;
+
+        assert_eq!(status, StatusCode::OK, "{}", logged_in);
+        let principal = jwt_tokens().verify(logged_in["data"]["access_token"].as_str().unwrap()).unwrap();
+        assert_eq!(principal.user_id, app.jane_id);
+
+        // Each ceremony is finished once
+        let (status, _) = send(&app.router, Method::POST, "/api/auth/webauthn/login/finish", None, Some(body)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn excludes_registered_passkeys_from_registration() {
+        let app = relying_party_app().await;
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+        register(&app, &mut authenticator).await;
+
+        let (_, started) = send(&app.router, Method::POST, "/api/auth/webauthn/register/start", Some(&app.jane_token), None).await;
+
+        let excluded = started["data"]["options"]["publicKey"]["excludeCredentials"].as_array().unwrap();
+        assert_eq!(excluded.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_assertion_for_another_origin() {
+        let app = relying_party_app().await;
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+        register(&app, &mut authenticator).await;
+
+        let (_, started) = send(&app.router, Method::POST, "/api/auth/webauthn/login/start", None, Some(json!({ "email": "jane@example.com" }))).await;
+        let options: RequestChallengeResponse = serde_json::from_value(started["data"]["options"].clone()).unwrap();
+        let assertion = authenticator.do_authentication(Url::parse("https://evil.example.com").unwrap(), options).unwrap();
+        let body = json!({ "ceremony_id": started["data"]["ceremony_id"], "credential": assertion });
+        let (status, _) = send(&app.router, Method::POST, "/api/auth/webauthn/login/finish", None, Some(body)).await;
+
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
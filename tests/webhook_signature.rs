@@ -0,0 +1,44 @@
+use std::time::{Duration, SystemTime};
+
+use rust_web_server_lib::infra::webhooks::{sign, SignatureError, WebhookVerifier};
+
+const PAYLOAD: &[u8] = br#"{"event":"user.created"}"#;
+
+#[test]
+fn accepts_signature_with_any_active_secret() {
+    let now = SystemTime::now();
+    let header = sign(&["old-secret", "new-secret"], now, PAYLOAD);
+
+    assert_eq!(WebhookVerifier::new(["old-secret"]).verify_at(&header, PAYLOAD, now), Ok(()));
+    assert_eq!(WebhookVerifier::new(["new-secret"]).verify_at(&header, PAYLOAD, now), Ok(()));
+    assert_eq!(WebhookVerifier::new(["other", "new-secret"]).verify_at(&header, PAYLOAD, now), Ok(()));
+}
+
+#[test]
+fn rejects_tampered_payload_and_unknown_secret() {
+    let now = SystemTime::now();
+    let header = sign(&["secret"], now, PAYLOAD);
+
+    let verifier = WebhookVerifier::new(["secret"]);
+    assert_eq!(verifier.verify_at(&header, br#"{"event":"user.deleted"}"#, now), Err(SignatureError::Mismatch));
+    assert_eq!(WebhookVerifier::new(["other"]).verify_at(&header, PAYLOAD, now), Err(SignatureError::Mismatch));
+}
+
+#[test]
+fn rejects_deliveries_outside_of_tolerance() {
+    let signed_at = SystemTime::now();
+    let header = sign(&["secret"], signed_at, PAYLOAD);
+
+    let verifier = WebhookVerifier::new(["secret"]).with_tolerance(Duration::from_secs(60));
+    assert_eq!(verifier.verify_at(&header, PAYLOAD, signed_at + Duration::from_secs(30)), Ok(()));
+    assert_eq!(verifier.verify_at(&header, PAYLOAD, signed_at + Duration::from_secs(120)), Err(SignatureError::Expired));
+}
+
+#[test]
+fn rejects_malformed_headers() {
+    let verifier = WebhookVerifier::new(["secret"]);
+
+    for header in ["", "v1=00", "t=abc,v1=00", "t=1,v1=zz", "garbage"] {
+        assert_eq!(verifier.verify(header, PAYLOAD), Err(SignatureError::Malformed), "{}", header);
+    }
+}
@@ -0,0 +1,11 @@
+mod scaffold;
+
+const USAGE: &str = "usage: cargo xtask scaffold <entity> [--plural <entities>] [--root <workspace>]";
+
+fn main() -> eyre::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("scaffold") => scaffold::run(&args[1..]),
+        _ => eyre::bail!(USAGE),
+    }
+}
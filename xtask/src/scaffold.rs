@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use eyre::{Context, OptionExt};
+
+use crate::USAGE;
+
+/// A generated file: its path relative to the workspace root and its template.
+struct Generated {
+    path: String,
+    template: &'static str,
+}
+
+/// A `pub mod` declaration added to an existing module.
+struct Registration {
+    path: &'static str,
+    module: String,
+}
+
+/// Names derived from the entity name, substituted into the templates.
+struct Names {
+    /// `snake_case` singular, e.g. `order_item`.
+    entity: String,
+    /// `snake_case` plural, used for the table and routes, e.g. `order_items`.
+    entities: String,
+    /// `PascalCase` singular, e.g. `OrderItem`.
+    pascal: String,
+}
+
+impl Names {
+    fn render(&self, template: &str) -> String {
+        template
+            .replace("{{Entity}}", &self.pascal)
+            .replace("{{entities}}", &self.entities)
+            .replace("{{entity}}", &self.entity)
+    }
+}
+
+/// Generates the boilerplate of a new aggregate, following the layout of the user module:
+/// domain model, error and port, service, PostgreSQL and in-memory adapters, migration,
+/// handlers with their routes, and an API test.
+///
+/// Existing files are never overwritten. Wiring the service into the server is left to the
+/// user, as it depends on how the aggregate relates to the rest of the application.
+///
+/// Files are generated into this workspace, or the copy of it given with `--root`.
+pub fn run(args: &[String]) -> eyre::Result<()> {
+    let mut entity = None;
+    let mut plural = None;
+    let mut root = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--plural" => plural = Some(args.next().ok_or_eyre(USAGE)?.clone()),
+            "--root" => root = Some(PathBuf::from(args.next().ok_or_eyre(USAGE)?)),
+            other if entity.is_none() && !other.starts_with('-') => entity = Some(other.to_string()),
+            other => eyre::bail!("unexpected argument {}\n{}", other, USAGE),
+        }
+    }
+
+    let entity = entity.ok_or_eyre(USAGE)?;
+    let entities = plural.unwrap_or_else(|| format!("{}s", entity));
+    validate_name(&entity)?;
+    validate_name(&entities)?;
+
+    let names = Names {
+        pascal: to_pascal_case(&entity),
+        entity,
+        entities,
+    };
+    let root = root.unwrap_or_else(workspace_root);
+
+    let generated = generated_files(&names);
+    for file in &generated {
+        if root.join(&file.path).exists() {
+            eyre::bail!("{} already exists, refusing to overwrite it", file.path);
+        }
+    }
+
+    for file in &generated {
+        let path = root.join(&file.path);
+        fs::create_dir_all(path.parent().expect("generated paths have a parent"))?;
+        fs::write(&path, names.render(file.template)).with_context(|| format!("failed to write {}", file.path))?;
+        println!("created {}", file.path);
+    }
+
+    for registration in registrations(&names) {
+        register_module(&root.join(registration.path), &registration.module)?;
+        println!("updated {}", registration.path);
+    }
+
+    println!();
+    println!("Next steps:");
+    println!("  1. Add the fields of {} to the model, migration, adapters and DTOs.", names.pascal);
+    println!("  2. Create the service in src/bin/server/main.rs, before the pool is moved into the user repositories:");
+    println!("       let {}_service = Arc::new({}Service::new(Arc::new({}Repository::new(db.clone()))));", names.entity, names.pascal, names.pascal);
+    println!("  3. Add it to AppState and merge its routes into the API in `router_with_load_shedding` (crates/presentation/src/http.rs), served under /api/v1:");
+    println!("       api = api.merge(MiddlewarePreset::PublicApi.apply({}_handlers::router(state.{}_service.clone()), &state));", names.entity, names.entity);
+    println!("  4. Run the tests: cargo test --test {}_api", names.entity);
+
+    Ok(())
+}
+
+fn generated_files(names: &Names) -> Vec<Generated> {
+    let entity = &names.entity;
+    let migration = format!("migrations/{}_create_{}_table", chrono::Utc::now().format("%Y%m%d%H%M%S"), names.entities);
+
+    vec![
+        Generated { path: format!("crates/domain/src/{}/mod.rs", entity), template: include_str!("../templates/domain_mod.rs.tmpl") },
+        Generated { path: format!("crates/domain/src/{}/model.rs", entity), template: include_str!("../templates/domain_model.rs.tmpl") },
+        Generated { path: format!("crates/domain/src/{}/error.rs", entity), template: include_str!("../templates/domain_error.rs.tmpl") },
+        Generated { path: format!("crates/domain/src/{}/repository.rs", entity), template: include_str!("../templates/domain_repository.rs.tmpl") },
+        Generated { path: format!("crates/application/src/flows/{}_service.rs", entity), template: include_str!("../templates/service.rs.tmpl") },
+        Generated {
+            path: format!("crates/infra/src/storage/adapter/postgres/{}_repository.rs", entity),
+            template: include_str!("../templates/postgres_repository.rs.tmpl"),
+        },
+        Generated {
+            path: format!("crates/infra/src/storage/adapter/in_memory/{}_repository.rs", entity),
+            template: include_str!("../templates/in_memory_repository.rs.tmpl"),
+        },
+        Generated { path: format!("{}.up.sql", migration), template: include_str!("../templates/migration.up.sql.tmpl") },
+        Generated { path: format!("{}.down.sql", migration), template: include_str!("../templates/migration.down.sql.tmpl") },
+        Generated { path: format!("crates/presentation/src/handlers/{}_handlers.rs", entity), template: include_str!("../templates/handlers.rs.tmpl") },
+        Generated { path: format!("tests/{}_api.rs", entity), template: include_str!("../templates/api_test.rs.tmpl") },
+    ]
+}
+
+fn registrations(names: &Names) -> Vec<Registration> {
+    let entity = &names.entity;
+
+    vec![
+        Registration { path: "crates/domain/src/lib.rs", module: entity.clone() },
+        Registration { path: "crates/application/src/flows/mod.rs", module: format!("{}_service", entity) },
+        Registration { path: "crates/infra/src/storage/adapter/postgres/mod.rs", module: format!("{}_repository", entity) },
+        Registration { path: "crates/infra/src/storage/adapter/in_memory/mod.rs", module: format!("{}_repository", entity) },
+        Registration { path: "crates/presentation/src/handlers/mod.rs", module: format!("{}_handlers", entity) },
+    ]
+}
+
+/// Adds `pub mod <module>;` after the last module declaration at the top of the file.
+fn register_module(path: &Path, module: &str) -> eyre::Result<()> {
+    let source = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut lines: Vec<&str> = source.lines().collect();
+
+    let declaration = format!("pub mod {};", module);
+    if lines.contains(&declaration.as_str()) {
+        return Ok(());
+    }
+
+    let position = lines
+        .iter()
+        .take_while(|line| line.starts_with("pub mod ") || line.starts_with("#[cfg("))
+        .count();
+    lines.insert(position, &declaration);
+
+    fs::write(path, lines.join("\n") + "\n").with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn validate_name(name: &str) -> eyre::Result<()> {
+    let valid = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        && !name.ends_with('_');
+
+    if !valid {
+        eyre::bail!("{} is not a valid snake_case name", name);
+    }
+    Ok(())
+}
+
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().expect("xtask lives in the workspace root").to_path_buf()
+}
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Entries of the workspace root left out of its copies: build outputs, history, and the fuzz
+/// crate, which is not a member of the workspace.
+const SKIPPED: [&str; 3] = ["target", ".git", "fuzz"];
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_path_buf()
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).unwrap();
+    for entry in fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let path = entry.path();
+        if entry.file_type().unwrap().is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()));
+        } else {
+            fs::copy(&path, to.join(entry.file_name())).unwrap();
+        }
+    }
+}
+
+/// Copies the workspace into a fresh temporary directory, removed when dropped.
+struct WorkspaceCopy(PathBuf);
+
+impl WorkspaceCopy {
+    /// Copies the workspace for the test `test`, so tests running together get their own copy.
+    fn new(test: &str) -> Self {
+        let root = workspace_root();
+        let copy = std::env::temp_dir().join(format!("xtask-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&copy);
+        for entry in fs::read_dir(&root).unwrap() {
+            let entry = entry.unwrap();
+            let name = entry.file_name();
+            if SKIPPED.iter().any(|skipped| name == *skipped) {
+                continue;
+            }
+            if entry.file_type().unwrap().is_dir() {
+                copy_dir(&entry.path(), &copy.join(&name));
+            } else {
+                fs::copy(entry.path(), copy.join(&name)).unwrap();
+            }
+        }
+        Self(copy)
+    }
+}
+
+impl Drop for WorkspaceCopy {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn run(command: &mut Command) -> String {
+    let output = command.output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+    assert!(output.status.success(), "{:?} failed:\n{}{}", command, String::from_utf8_lossy(&output.stdout), stderr);
+    stderr
+}
+
+#[test]
+fn scaffolded_entities_compile_with_their_tests() {
+    let copy = WorkspaceCopy::new("compile");
+
+    run(Command::new(env!("CARGO_BIN_EXE_xtask")).args(["scaffold", "order_item", "--root"]).arg(&copy.0));
+    assert!(copy.0.join("crates/domain/src/order_item/model.rs").exists());
+    assert!(fs::read_to_string(copy.0.join("crates/domain/src/lib.rs")).unwrap().contains("pub mod order_item;"));
+
+    // The build of the copy is kept apart from the running one, which holds the lock of
+    // `target`, and reused by the next runs
+    let warnings = run(Command::new(env!("CARGO"))
+        .args(["check", "--workspace", "--all-targets", "--message-format", "short"])
+        .current_dir(&copy.0)
+        .env("CARGO_TARGET_DIR", workspace_root().join("target/scaffold")));
+    let generated: Vec<&str> = warnings.lines().filter(|line| line.contains("order_item") && line.contains("warning")).collect();
+    assert!(generated.is_empty(), "{}", generated.join("\n"));
+}
+
+#[test]
+fn refuses_to_overwrite_existing_files() {
+    let copy = WorkspaceCopy::new("overwrite");
+    let scaffold = || Command::new(env!("CARGO_BIN_EXE_xtask")).args(["scaffold", "widget", "--root"]).arg(&copy.0).output().unwrap();
+
+    assert!(scaffold().status.success());
+    let output = scaffold();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"), "{}", String::from_utf8_lossy(&output.stderr));
+}